@@ -333,7 +333,7 @@ fn parse_docs(
     let mut iter = stmts.iter().peekable();
     while let Some(stmt) = iter.next() {
         match &stmt.kind {
-            AstKind::ImportStatement(path) => {
+            AstKind::ImportStatement(path, _) => {
                 let parts = path.split(".");
                 let mut mod_path = root_path.clone();
 
@@ -1,10 +1,40 @@
 use super::{expression::Expression, operator::Operator};
-use std::ops::Range;
+use std::{fmt::Display, ops::Range, sync::Arc};
+
+/// A parameter of a function definition. The default value gets evaluated whenever a call doesn't
+/// provide the parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    pub name: String,
+    pub default: Option<Expression>,
+}
+
+impl Parameter {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            default: None,
+        }
+    }
+}
+
+impl Display for Parameter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.default {
+            Some(default) => write!(f, "{} = {}", self.name, default),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// The statements of a function. Every copy of a function shares them, which is why looking up
+/// or calling a function doesn't have to copy its whole body.
+pub type FunctionBody = Arc<Vec<AstNode>>;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ClassMember {
-    StaticFunction(String, Vec<String>, Vec<AstNode>),
-    Function(String, Vec<String>, Vec<AstNode>),
+    StaticFunction(String, Vec<Parameter>, Vec<AstNode>),
+    Function(String, Vec<Parameter>, Vec<AstNode>),
     Field(String, Expression),
     Operator(Operator, Vec<String>, Vec<AstNode>),
 }
@@ -24,16 +54,22 @@ pub enum AstKind {
     DivideAssignment(String, Expression),
     ClassDefinition(String, Vec<ClassMember>),
     FunctionCall(String, Vec<Expression>),
-    ImportStatement(String),
+    /// The path of the module and the names to import from it. Without names the module itself
+    /// gets imported.
+    ImportStatement(String, Option<Vec<String>>),
+    /// The pattern of the files whose statements get executed in place of this one
+    IncludeStatement(String),
     Comment(Vec<String>),
+    /// The text between `/*` and `*/`
+    BlockComment(String),
     Documentation(Vec<String>),
     BreakStatement,
     ContinueStatement,
     ExportStatement(Box<AstNode>),
     ExternStatement(Box<AstNode>),
     OperatorImplementation(Operator, Vec<String>, Vec<AstNode>),
-    StaticFunctionDefinition(String, Vec<String>, Vec<AstNode>),
-    FunctionDefinition(String, Vec<String>, Vec<AstNode>),
+    StaticFunctionDefinition(String, Vec<Parameter>, Vec<AstNode>),
+    FunctionDefinition(String, Vec<Parameter>, Vec<AstNode>),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -46,4 +82,26 @@ impl AstNode {
     pub fn new(kind: AstKind, location: Range<usize>) -> Self {
         AstNode { kind, location }
     }
+
+    /// Whether the statement can define a name in the frame it runs in
+    pub fn defines_names(&self) -> bool {
+        !matches!(
+            self.kind,
+            AstKind::ReturnStatement(_)
+                | AstKind::Expression(_)
+                | AstKind::IfStatement(_)
+                | AstKind::WhileStatement(_, _)
+                | AstKind::VariableAssignment(_, _)
+                | AstKind::PlusAssignment(_, _)
+                | AstKind::MinusAssignment(_, _)
+                | AstKind::TimesAssignment(_, _)
+                | AstKind::DivideAssignment(_, _)
+                | AstKind::FunctionCall(_, _)
+                | AstKind::Comment(_)
+                | AstKind::BlockComment(_)
+                | AstKind::Documentation(_)
+                | AstKind::BreakStatement
+                | AstKind::ContinueStatement
+        )
+    }
 }
@@ -50,23 +50,21 @@ impl Class {
 
             Ok(this.get_field(&field))
         })
-        .set_op_impl(Operator::And, |_, this, args| {
-            let lhs = this.is_true();
-            let rhs = args[0].is_true();
-            Ok(lhs && rhs)
-        })
-        .set_op_impl(Operator::Or, |_, this, args| {
-            let lhs = this.is_true();
-            let rhs = args[0].is_true();
-            dbg!(lhs || rhs);
-            Ok(lhs || rhs)
-        })
         .set_op_impl(Operator::Add, |_, this, args| Ok(this + args[0].clone()))
         .set_op_impl(Operator::Subtract, |_, this, args| {
             Ok(this - args[0].clone())
         })
         .set_op_impl(Operator::Times, |_, this, args| Ok(this * args[0].clone()))
         .set_op_impl(Operator::Divide, |_, this, args| Ok(this / args[0].clone()))
+        .set_op_impl(Operator::BitAnd, |_, this, args| Ok(this & args[0].clone()))
+        .set_op_impl(Operator::BitOr, |_, this, args| Ok(this | args[0].clone()))
+        .set_op_impl(Operator::BitXor, |_, this, args| Ok(this ^ args[0].clone()))
+        .set_op_impl(Operator::ShiftLeft, |_, this, args| {
+            Ok(this << args[0].clone())
+        })
+        .set_op_impl(Operator::ShiftRight, |_, this, args| {
+            Ok(this >> args[0].clone())
+        })
         .set_op_impl(Operator::Equal, |_, this, args| Ok(this == args[0]))
         .set_op_impl(Operator::GreaterThan, |_, this, args| Ok(this > args[0]))
         .set_op_impl(Operator::GreaterThanOrEqual, |_, this, args| {
@@ -129,6 +127,6 @@ impl Class {
 
 impl Into<Dynamic> for Class {
     fn into(self) -> Dynamic {
-        Dynamic::Class(self)
+        Dynamic::Class(Box::new(self))
     }
 }
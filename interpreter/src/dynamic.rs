@@ -8,10 +8,11 @@ use std::{
 };
 
 use super::{
-    ast::AstNode,
+    ast::{FunctionBody, Parameter},
     class::Class,
     expression::Expression,
     function::Function,
+    gc,
     interpreter::Interpreter,
     module::Module,
     runtime_error::{RuntimeError, RuntimeResult},
@@ -21,22 +22,24 @@ use super::{
 pub mod object_builder;
 
 pub type Number = i32;
+pub type Float = f64;
 
 #[derive(Clone)]
 pub enum Dynamic {
     String(String),
     Number(Number),
+    Float(Float),
     RustValue(Arc<Box<dyn Any + Send + Sync>>),
     Boolean(bool),
-    Lazy(Expression),
+    Lazy(Box<Expression>),
     Array(Arc<Mutex<Vec<Dynamic>>>),
     Object(Arc<Mutex<HashMap<String, Dynamic>>>),
-    Module(Module),
-    Class(Class),
+    Module(Box<Module>),
+    Class(Box<Class>),
     Function {
         name: String,
-        arg_names: Vec<String>,
-        body: Vec<AstNode>,
+        arg_names: Vec<Parameter>,
+        body: FunctionBody,
         scope: Scope,
     },
     RustFunction {
@@ -69,13 +72,7 @@ impl Dynamic {
                 let fields = fields_ref.lock().unwrap();
                 fields.get(key).cloned().unwrap_or_default()
             }
-            Dynamic::Module(module) => module
-                .variables
-                .get(key)
-                .cloned()
-                .or_else(|| module.functions.get(key).map(|x| x.clone().into()))
-                .or_else(|| module.classes.get(key).map(|x| x.clone().into()))
-                .unwrap_or_default(),
+            Dynamic::Module(module) => module.get_export(key).unwrap_or_default(),
             _ => Dynamic::Null,
         }
     }
@@ -105,15 +102,21 @@ impl Dynamic {
     }
 
     pub fn new_array(items: Vec<Dynamic>) -> Self {
-        Dynamic::Array(Arc::new(Mutex::new(items)))
+        let items = Arc::new(Mutex::new(items));
+        gc::track_array(&items);
+        Dynamic::Array(items)
     }
 
     pub fn new_object(fields: HashMap<String, Dynamic>) -> Self {
-        Dynamic::Object(Arc::new(Mutex::new(fields)))
+        let fields = Arc::new(Mutex::new(fields));
+        gc::track_fields(&fields);
+        Dynamic::Object(fields)
     }
 
     pub fn new_instance(name: &str, fields: HashMap<String, Dynamic>) -> Self {
-        Dynamic::ClassInstance(name.to_string(), Arc::new(Mutex::new(fields)))
+        let fields = Arc::new(Mutex::new(fields));
+        gc::track_fields(&fields);
+        Dynamic::ClassInstance(name.to_string(), fields)
     }
 
     pub fn as_array(self) -> RuntimeResult<Vec<Dynamic>> {
@@ -133,11 +136,8 @@ impl Dynamic {
                 scope,
                 body,
                 arg_names,
-            } => Ok(Function::new(&name, Some(scope.clone()), move |i, args| {
-                let body = body.clone();
-                let arg_names = arg_names.clone();
-                let scope = scope.clone();
-                i.call_fn(None, Some(scope), &arg_names, &args, &body)
+            } => Ok(Function::new(&name, Some(scope.child()), move |i, args| {
+                i.call_fn(None, Some(scope.clone()), &arg_names, &args, &body)
             })),
             Dynamic::RustFunction {
                 name,
@@ -168,10 +168,25 @@ impl Dynamic {
         }
     }
 
+    /// Returns the value of a number or a float as a float
+    pub fn to_float(&self) -> Option<Float> {
+        match self {
+            Dynamic::Number(x) => Some(*x as Float),
+            Dynamic::Float(x) => Some(*x),
+            _ => None,
+        }
+    }
+
     pub fn type_name(&self) -> String {
+        self.class_name().into()
+    }
+
+    /// The name of the class of the value, without allocating it like `type_name` does
+    pub fn class_name(&self) -> &str {
         match self {
             Dynamic::String(_) => "String",
             Dynamic::Number(_) => "Number",
+            Dynamic::Float(_) => "Float",
             Dynamic::RustValue(_) => "RustValue",
             Dynamic::Lazy(_) => "Lazy",
             Dynamic::Module(_) => "Module",
@@ -184,7 +199,6 @@ impl Dynamic {
             Dynamic::RustFunction { .. } => "RustFunction",
             Dynamic::Null => "Null",
         }
-        .into()
     }
 
     pub fn is_true(&self) -> bool {
@@ -196,19 +210,36 @@ impl Dynamic {
     }
 }
 
+/// Applies the operator to two numbers. Whole numbers stay whole, but as soon as one of them is a
+/// float the result is a float as well. Any other value results in `null`.
+fn arithmetic(
+    lhs: &Dynamic,
+    rhs: &Dynamic,
+    whole: fn(Number, Number) -> Number,
+    float: fn(Float, Float) -> Float,
+) -> Dynamic {
+    match (lhs, rhs) {
+        (Dynamic::Number(x), Dynamic::Number(y)) => whole(*x, *y).into(),
+        (x, y) => match (x.to_float(), y.to_float()) {
+            (Some(x), Some(y)) => float(x, y).into(),
+            _ => Dynamic::Null,
+        },
+    }
+}
+
 impl std::ops::Add for Dynamic {
     type Output = Dynamic;
 
     fn add(self, other: Dynamic) -> Self::Output {
         match self {
-            Dynamic::Number(x) => match other {
-                Dynamic::Number(y) => (x + y).into(),
-                _ => Dynamic::Null,
-            },
+            Dynamic::Number(_) | Dynamic::Float(_) => {
+                arithmetic(&self, &other, |x, y| x + y, |x, y| x + y)
+            }
             Dynamic::String(x) => match other {
                 Dynamic::String(y) => format!("{}{}", x, y).into(),
-                Dynamic::Boolean(y) => format!("{}{}", x, y).into(),
-                Dynamic::Number(y) => format!("{}{}", x, y).into(),
+                Dynamic::Boolean(_) | Dynamic::Number(_) | Dynamic::Float(_) => {
+                    format!("{}{}", x, other).into()
+                }
                 _ => Dynamic::Null,
             },
             Dynamic::Array(x) => match other {
@@ -224,7 +255,6 @@ impl std::ops::Add for Dynamic {
             },
             _ => Dynamic::Null,
         }
-        .into()
     }
 }
 
@@ -232,14 +262,7 @@ impl std::ops::Sub for Dynamic {
     type Output = Dynamic;
 
     fn sub(self, other: Dynamic) -> Self::Output {
-        match self {
-            Dynamic::Number(x) => match other {
-                Dynamic::Number(y) => (x - y).into(),
-                _ => Dynamic::Null,
-            },
-            _ => Dynamic::Null,
-        }
-        .into()
+        arithmetic(&self, &other, |x, y| x - y, |x, y| x - y)
     }
 }
 
@@ -247,39 +270,95 @@ impl std::ops::Mul for Dynamic {
     type Output = Dynamic;
 
     fn mul(self, other: Dynamic) -> Self::Output {
-        match self {
-            Dynamic::Number(x) => match other {
-                Dynamic::Number(y) => (x * y).into(),
-                _ => Dynamic::Null,
-            },
-            _ => Dynamic::Null,
-        }
-        .into()
+        arithmetic(&self, &other, |x, y| x * y, |x, y| x * y)
     }
 }
 
+/// Dividing two whole numbers drops the remainder, `7 / 2.0` has to be used to get `3.5`
 impl std::ops::Div for Dynamic {
     type Output = Dynamic;
 
     fn div(self, other: Dynamic) -> Self::Output {
-        match self {
-            Dynamic::Number(x) => match other {
-                Dynamic::Number(y) => (x / y).into(),
-                _ => Dynamic::Null,
-            },
+        arithmetic(&self, &other, |x, y| x / y, |x, y| x / y)
+    }
+}
+
+impl std::ops::BitAnd for Dynamic {
+    type Output = Dynamic;
+
+    fn bitand(self, other: Dynamic) -> Self::Output {
+        match (self, other) {
+            (Dynamic::Number(x), Dynamic::Number(y)) => (x & y).into(),
+            (Dynamic::Boolean(x), Dynamic::Boolean(y)) => (x & y).into(),
+            _ => Dynamic::Null,
+        }
+    }
+}
+
+impl std::ops::BitOr for Dynamic {
+    type Output = Dynamic;
+
+    fn bitor(self, other: Dynamic) -> Self::Output {
+        match (self, other) {
+            (Dynamic::Number(x), Dynamic::Number(y)) => (x | y).into(),
+            (Dynamic::Boolean(x), Dynamic::Boolean(y)) => (x | y).into(),
             _ => Dynamic::Null,
         }
-        .into()
     }
 }
 
+impl std::ops::BitXor for Dynamic {
+    type Output = Dynamic;
+
+    fn bitxor(self, other: Dynamic) -> Self::Output {
+        match (self, other) {
+            (Dynamic::Number(x), Dynamic::Number(y)) => (x ^ y).into(),
+            (Dynamic::Boolean(x), Dynamic::Boolean(y)) => (x ^ y).into(),
+            _ => Dynamic::Null,
+        }
+    }
+}
+
+/// Shifting by a negative amount or by more than 31 bits results in `null`
+impl std::ops::Shl for Dynamic {
+    type Output = Dynamic;
+
+    fn shl(self, other: Dynamic) -> Self::Output {
+        match (self, other) {
+            (Dynamic::Number(x), Dynamic::Number(y)) if y >= 0 => x
+                .checked_shl(y as u32)
+                .map(Dynamic::from)
+                .unwrap_or_default(),
+            _ => Dynamic::Null,
+        }
+    }
+}
+
+/// Shifts are arithmetic, which means that negative numbers stay negative
+impl std::ops::Shr for Dynamic {
+    type Output = Dynamic;
+
+    fn shr(self, other: Dynamic) -> Self::Output {
+        match (self, other) {
+            (Dynamic::Number(x), Dynamic::Number(y)) if y >= 0 => x
+                .checked_shr(y as u32)
+                .map(Dynamic::from)
+                .unwrap_or_default(),
+            _ => Dynamic::Null,
+        }
+    }
+}
+
+/// Numbers and floats are compared by their value, so `1 == 1.0`
 impl std::cmp::PartialEq for Dynamic {
     fn eq(&self, other: &Dynamic) -> bool {
         match self {
             Dynamic::Number(x) => match other {
                 Dynamic::Number(y) => x == y,
+                Dynamic::Float(y) => *x as Float == *y,
                 _ => false,
             },
+            Dynamic::Float(x) => other.to_float().is_some_and(|y| *x == y),
             Dynamic::String(x) => match other {
                 Dynamic::String(y) => x == y,
                 _ => false,
@@ -302,8 +381,10 @@ impl std::cmp::PartialOrd for Dynamic {
         match self {
             Dynamic::Number(x) => match other {
                 Dynamic::Number(y) => Some(x.cmp(y)),
+                Dynamic::Float(y) => (*x as Float).partial_cmp(y),
                 _ => None,
             },
+            Dynamic::Float(x) => other.to_float().and_then(|y| x.partial_cmp(&y)),
             Dynamic::String(x) => match other {
                 Dynamic::String(y) => Some(x.cmp(y)),
                 _ => None,
@@ -315,35 +396,31 @@ impl std::cmp::PartialOrd for Dynamic {
 
 impl From<Expression> for Dynamic {
     fn from(expr: Expression) -> Self {
-        Dynamic::Lazy(expr)
+        Dynamic::Lazy(Box::new(expr))
     }
 }
 
 impl From<&Expression> for Dynamic {
     fn from(expr: &Expression) -> Self {
-        Dynamic::Lazy(expr.clone())
+        Dynamic::Lazy(Box::new(expr.clone()))
     }
 }
 
 impl<T: Into<Dynamic>> Into<Dynamic> for HashMap<String, T> {
     fn into(self) -> Dynamic {
-        Dynamic::Object(Arc::new(Mutex::new(
-            self.into_iter().map(|(k, v)| (k, v.into())).collect(),
-        )))
+        Dynamic::new_object(self.into_iter().map(|(k, v)| (k, v.into())).collect())
     }
 }
 
 impl<T: Into<Dynamic>> Into<Dynamic> for Vec<T> {
     fn into(self) -> Dynamic {
-        Dynamic::Array(Arc::new(Mutex::new(
-            self.into_iter().map(|x| x.into()).collect(),
-        )))
+        Dynamic::new_array(self.into_iter().map(|x| x.into()).collect())
     }
 }
 
 impl From<Module> for Dynamic {
     fn from(val: Module) -> Self {
-        Dynamic::Module(val)
+        Dynamic::Module(Box::new(val))
     }
 }
 
@@ -383,6 +460,12 @@ impl From<i64> for Dynamic {
     }
 }
 
+impl From<Float> for Dynamic {
+    fn from(val: Float) -> Self {
+        Dynamic::Float(val)
+    }
+}
+
 impl From<()> for Dynamic {
     fn from(_: ()) -> Self {
         Dynamic::Null
@@ -406,7 +489,7 @@ impl Debug for Dynamic {
         f.write_str(&match self {
             Dynamic::Function {
                 name, arg_names, ..
-            } => format!("Function({}, [{}])", name, arg_names.join(", ")),
+            } => format!("Function({}, [{}])", name, arg_names.iter().join(", ")),
             x => format!("{}", x), // if i use debug formatting it overflows the stack for some reason
         })
     }
@@ -467,6 +550,8 @@ impl Display for Dynamic {
                 }
             }
             Dynamic::Number(number) => number.to_string(),
+            // always shows the fractional part, so that `2.0` doesn't look like a whole number
+            Dynamic::Float(float) => format!("{:?}", float),
             Dynamic::ClassInstance(name, fields_ref) => {
                 let fields = fields_ref.lock().unwrap();
                 if fields.is_empty() {
@@ -486,7 +571,7 @@ impl Display for Dynamic {
             Dynamic::RustFunction { name, .. } => format!("extern function {}(...)", name),
             Dynamic::Function {
                 name, arg_names, ..
-            } => format!("function {}({})", name, arg_names.join(", ")),
+            } => format!("function {}({})", name, arg_names.iter().join(", ")),
         })
     }
 }
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, sync::Arc, sync::Mutex};
+use std::{collections::HashMap, sync::Arc};
 
 use crate::interpreter::Interpreter;
 use crate::runtime_error::RuntimeResult;
@@ -33,8 +33,7 @@ impl ObjectBuilder {
     }
 
     pub fn object(mut self, name: &str, obj: HashMap<String, Dynamic>) -> Self {
-        self.inner
-            .insert(name.into(), Dynamic::Object(Arc::new(Mutex::new(obj))));
+        self.inner.insert(name.into(), Dynamic::new_object(obj));
         self
     }
 
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, iter::Peekable};
+use std::{collections::HashMap, iter::Peekable, sync::Arc};
 
 use itertools::Itertools;
 use pratt::{Affix, Associativity, PrattParser, Precedence};
@@ -186,7 +186,7 @@ fn parse_arg_list(
             TokenKind::Comma => {
                 parser.prev_token = token.clone();
                 if depth == 0 && !arg.is_empty() {
-                    list.push(parser.parse(&mut arg.clone().into_iter()).unwrap().into());
+                    list.push(parse_arg(parser, arg.clone()));
                     parser.prev_token = token.clone();
                     arg.clear();
                     continue;
@@ -197,11 +197,27 @@ fn parse_arg_list(
         arg.push(token.clone());
     }
     if !arg.is_empty() {
-        list.push(parser.parse(&mut arg.into_iter()).unwrap().into());
+        list.push(parse_arg(parser, arg));
     }
     list
 }
 
+/// Parses a single argument of a function call. `name: value` passes the value to the parameter
+/// with that name.
+fn parse_arg(parser: &mut ExprParser, tokens: Vec<Token>) -> Expression {
+    if let [name, colon, ..] = tokens.as_slice() {
+        if name.0 == TokenKind::Identifier && colon.0 == TokenKind::Colon {
+            let name = parser.text(name).to_string();
+            parser.prev_token = colon.clone();
+            let value = parser.parse(&mut tokens[2..].to_vec().into_iter()).unwrap();
+
+            return ExpressionKind::NamedArgument(name, Box::new(value.into())).into();
+        }
+    }
+
+    parser.parse(&mut tokens.into_iter()).unwrap().into()
+}
+
 fn parse_arrow_fn<T: Iterator<Item = Token>>(
     parser: &mut ExprParser,
     rest: &mut Peekable<T>,
@@ -243,7 +259,7 @@ fn parse_arrow_fn<T: Iterator<Item = Token>>(
             parser.set_source("".into(), source, source_range.0);
             return Ok(ExpressionKind::ArrowFunction(
                 arg_names,
-                parser.parse()?.stmts,
+                Arc::new(parser.parse()?.stmts),
             ));
         } else {
             let mut tokens = Vec::new();
@@ -266,13 +282,13 @@ fn parse_arrow_fn<T: Iterator<Item = Token>>(
             let location = calculate_range(&tokens);
             return Ok(ExpressionKind::ArrowFunction(
                 arg_names,
-                vec![AstNode::new(
+                Arc::new(vec![AstNode::new(
                     AstKind::ReturnStatement(Expression::new(
                         parser.parse(&mut tokens.into_iter())?,
                         location.clone(),
                     )),
                     location.clone(),
-                )],
+                )]),
             ));
         }
     }
@@ -365,20 +381,50 @@ where
                 | TokenKind::SlashEqual
                 | TokenKind::LParan
                 | TokenKind::LBracket
-                | TokenKind::Equal => Affix::Prefix(Precedence(12)),
-                _ => Affix::Infix(Precedence(4), Associativity::Left),
+                | TokenKind::Ampersand
+                | TokenKind::Bar
+                | TokenKind::Caret
+                | TokenKind::ShiftLeft
+                | TokenKind::ShiftRight
+                | TokenKind::GT
+                | TokenKind::GTE
+                | TokenKind::LT
+                | TokenKind::LTE
+                | TokenKind::EQ
+                | TokenKind::NEQ
+                | TokenKind::And
+                | TokenKind::Or
+                | TokenKind::ExclamationMark
+                | TokenKind::QuestionMark
+                | TokenKind::Colon
+                | TokenKind::Arrow
+                | TokenKind::Return
+                | TokenKind::If
+                | TokenKind::ElseIf
+                | TokenKind::While
+                | TokenKind::Equal => Affix::Prefix(Precedence(13)),
+                _ => Affix::Infix(Precedence(9), Associativity::Left),
             },
-            TokenKind::Star | TokenKind::Slash => Affix::Infix(Precedence(5), Associativity::Left),
+            TokenKind::Star | TokenKind::Slash => Affix::Infix(Precedence(10), Associativity::Left),
+            // the bitwise operators bind tighter than comparisons, so that `x & mask == 0` doesn't
+            // need any parentheses
+            TokenKind::ShiftLeft | TokenKind::ShiftRight => {
+                Affix::Infix(Precedence(8), Associativity::Left)
+            }
+            TokenKind::Ampersand => Affix::Infix(Precedence(7), Associativity::Left),
+            TokenKind::Caret => Affix::Infix(Precedence(6), Associativity::Left),
+            TokenKind::Bar => Affix::Infix(Precedence(5), Associativity::Left),
             TokenKind::GT
             | TokenKind::GTE
             | TokenKind::LT
             | TokenKind::LTE
             | TokenKind::EQ
-            | TokenKind::NEQ => Affix::Infix(Precedence(3), Associativity::Left),
-            TokenKind::Dot => Affix::Infix(Precedence(11), Associativity::Left),
-            TokenKind::And => Affix::Infix(Precedence(2), Associativity::Left),
+            | TokenKind::NEQ => Affix::Infix(Precedence(4), Associativity::Left),
+            TokenKind::Dot => Affix::Infix(Precedence(12), Associativity::Left),
+            // `a || b && c` means `a || (b && c)`
+            TokenKind::And => Affix::Infix(Precedence(3), Associativity::Left),
             TokenKind::Or => Affix::Infix(Precedence(2), Associativity::Left),
-            TokenKind::DoubleColon => Affix::Infix(Precedence(11), Associativity::Left),
+            TokenKind::DoubleColon => Affix::Infix(Precedence(12), Associativity::Left),
             TokenKind::Equal => Affix::Infix(Precedence(1), Associativity::Neither),
             TokenKind::PlusEqual => Affix::Infix(Precedence(1), Associativity::Neither),
             TokenKind::MinusEqual => Affix::Infix(Precedence(1), Associativity::Neither),
@@ -399,14 +445,16 @@ where
                 | TokenKind::StarEqual
                 | TokenKind::SlashEqual
                 | TokenKind::Comma => Affix::Nilfix,
-                _ => Affix::Postfix(Precedence(10)),
+                _ => Affix::Postfix(Precedence(11)),
             },
             TokenKind::LCurly => match &self.prev_token.0 {
-                TokenKind::ClassIdentifier => Affix::Postfix(Precedence(10)),
+                TokenKind::ClassIdentifier => Affix::Postfix(Precedence(11)),
                 _ => Affix::Nilfix,
             },
-            TokenKind::MinusMinus | TokenKind::PlusPlus => Affix::Postfix(Precedence(10)),
-            TokenKind::ExclamationMark => Affix::Prefix(Precedence(10)),
+            TokenKind::MinusMinus | TokenKind::PlusPlus => Affix::Postfix(Precedence(11)),
+            TokenKind::ExclamationMark => Affix::Prefix(Precedence(11)),
+            // binds weaker than `||`, so that `a || b ? x : y` tests `a || b`
+            TokenKind::QuestionMark => Affix::Postfix(Precedence(2)),
             TokenKind::Arrow => Affix::Nilfix,
             TokenKind::RParan => Affix::Nilfix,
             TokenKind::Hash => Affix::Nilfix,
@@ -415,8 +463,10 @@ where
             TokenKind::RCurly => Affix::Nilfix,
             TokenKind::RBracket => Affix::Nilfix,
             TokenKind::Import => Affix::Nilfix,
+            TokenKind::Include => Affix::Nilfix,
             TokenKind::NewLine => Affix::Nilfix,
             TokenKind::HexLiteral => Affix::Nilfix,
+            TokenKind::BinaryLiteral => Affix::Nilfix,
             TokenKind::NumberLiteral => Affix::Nilfix,
             TokenKind::StringLiteral => Affix::Nilfix,
             TokenKind::ClassIdentifier => Affix::Nilfix,
@@ -442,16 +492,9 @@ where
         let text = self.text(&token).to_string();
         Ok(match token.0 {
             TokenKind::HexLiteral => ExpressionKind::HexLiteral(text),
+            TokenKind::BinaryLiteral => ExpressionKind::BinaryLiteral(text),
             TokenKind::NumberLiteral => ExpressionKind::NumberLiteral(text),
-            TokenKind::StringLiteral => {
-                let raw = text
-                    .clone()
-                    .replace("\\\\", "\\")
-                    .replace("\\\"", "\"")
-                    .replace("\\r", "\r")
-                    .replace("\\n", "\n");
-                ExpressionKind::StringLiteral(raw)
-            }
+            TokenKind::StringLiteral => ExpressionKind::StringLiteral(unescape(&text)),
             TokenKind::BooleanLiteral => ExpressionKind::BooleanLiteral(text),
             TokenKind::Null => ExpressionKind::Null,
             TokenKind::LBracket => {
@@ -571,6 +614,26 @@ where
         rhs: Self::Output,
         _: &mut Peekable<&mut I>,
     ) -> Result<Self::Output, Self::Error> {
+        // `a.b += 1` is short for `a.b = a.b + 1`
+        let compound_op = match token.0 {
+            TokenKind::PlusEqual => Some(Operator::Add),
+            TokenKind::MinusEqual => Some(Operator::Subtract),
+            TokenKind::StarEqual => Some(Operator::Times),
+            TokenKind::SlashEqual => Some(Operator::Divide),
+            _ => None,
+        };
+
+        if let Some(op) = compound_op {
+            let value =
+                ExpressionKind::BinaryOp(Box::new(lhs.clone().into()), op, Box::new(rhs.into()));
+
+            return Ok(ExpressionKind::BinaryOp(
+                Box::new(lhs.into()),
+                Operator::Assign,
+                Box::new(value.into()),
+            ));
+        }
+
         Ok(ExpressionKind::BinaryOp(
             Box::new(lhs.into()),
             token.0.into(),
@@ -628,110 +691,242 @@ where
                 token.0.into(),
                 None,
             )),
+            TokenKind::QuestionMark => {
+                let mut then_tokens = Vec::new();
+                let mut depth = 0;
+                // the number of conditions inside of the then branch that still miss their `:`
+                let mut nested = 0;
+
+                while let Some(next) = rest.next() {
+                    match next.0 {
+                        TokenKind::LParan | TokenKind::LBracket | TokenKind::LCurly => depth += 1,
+                        TokenKind::RParan | TokenKind::RBracket | TokenKind::RCurly => depth -= 1,
+                        TokenKind::QuestionMark if depth == 0 => nested += 1,
+                        TokenKind::Colon if depth == 0 => {
+                            if nested == 0 {
+                                break;
+                            }
+                            nested -= 1;
+                        }
+                        _ => {}
+                    }
+                    then_tokens.push(next);
+                }
+
+                self.prev_token = token;
+                let then = self
+                    .parse(&mut then_tokens.into_iter())
+                    .map_err(ParseError::from)?;
+                self.prev_token = (TokenKind::Colon, 0..0).into();
+                // everything up to the next assignment belongs to the else branch, which makes
+                // `a ? b : c ? d : e` mean `a ? b : (c ? d : e)`
+                let otherwise = self
+                    .parse_input(rest, Precedence(19))
+                    .map_err(ParseError::from)?;
+
+                Ok(ExpressionKind::Conditional(
+                    Box::new(lhs.into()),
+                    Box::new(then.into()),
+                    Box::new(otherwise.into()),
+                ))
+            }
             _ => todo!(),
         }
     }
 }
 
+/// Replaces the escape sequences of a string literal. `\u{1F600}` escapes a unicode code point.
+/// Unknown escape sequences are kept as they are.
+pub fn unescape(raw: &str) -> String {
+    let mut result = String::with_capacity(raw.len());
+    let mut chars = raw.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('t') => result.push('\t'),
+            Some('"') => result.push('"'),
+            Some('\\') => result.push('\\'),
+            Some('u') if chars.peek() == Some(&'{') => {
+                let code = chars
+                    .by_ref()
+                    .skip(1)
+                    .take_while(|c| *c != '}')
+                    .collect::<String>();
+
+                match u32::from_str_radix(&code, 16)
+                    .ok()
+                    .and_then(std::char::from_u32)
+                {
+                    Some(c) => result.push(c),
+                    None => result.push_str(&format!("\\u{{{}}}", code)),
+                }
+            }
+            Some(other) => {
+                result.push('\\');
+                result.push(other);
+            }
+            None => result.push('\\'),
+        }
+    }
+
+    result
+}
+
 #[cfg(test)]
 mod test {
-    use super::ExprParser;
-    use crate::{ast::Ast, expression::Expression::*};
-    use crate::{expression::Expression, lexer::Lexer, operator::Operator, token::Token};
-    use logos::Logos;
+    use super::{unescape, ExprParser};
+    use crate::ast::{AstKind, AstNode};
+    use crate::{
+        expression::{Expression, ExpressionKind},
+        lexer::Lexer,
+        operator::Operator,
+    };
     use pratt::PrattParser;
-    use std::collections::HashMap;
+    use std::{collections::HashMap, sync::Arc};
 
-    fn parse(input: &str) -> Expression {
-        ExprParser::new(input, 0)
-            .parse(&mut Lexer::new(input, 0))
-            .unwrap()
+    fn parse(input: &str) -> ExpressionKind {
+        without_locations(
+            ExprParser::new(input, 0)
+                .parse(&mut Lexer::new(input, 0))
+                .unwrap(),
+        )
     }
 
-    fn binary(lhs: Expression, op: &str, rhs: Expression) -> Expression {
-        Expression::BinaryOp(
-            Box::new(lhs),
+    fn binary(lhs: ExpressionKind, op: &str, rhs: ExpressionKind) -> ExpressionKind {
+        ExpressionKind::BinaryOp(
+            Box::new(lhs.into()),
             Operator::from_str(op).unwrap(),
-            Box::new(rhs),
+            Box::new(rhs.into()),
         )
     }
 
-    fn post(lhs: Expression, op: &str, value: Option<Expression>) -> Expression {
-        Expression::PostOp(
-            Box::new(lhs),
+    fn post(lhs: ExpressionKind, op: &str, value: Option<ExpressionKind>) -> ExpressionKind {
+        ExpressionKind::PostOp(
+            Box::new(lhs.into()),
             Operator::from_str(op).unwrap(),
-            value.map(|x| Box::new(x)),
+            value.map(|x| Box::new(x.into())),
         )
     }
 
-    fn pre(op: &str, lhs: Expression) -> Expression {
-        Expression::PreOp(Operator::from_str(op).unwrap(), Box::new(lhs))
+    fn pre(op: &str, lhs: ExpressionKind) -> ExpressionKind {
+        ExpressionKind::PreOp(Operator::from_str(op).unwrap(), Box::new(lhs.into()))
     }
 
-    fn array(items: Vec<Expression>) -> Expression {
-        Expression::ArrayLiteral(items)
+    fn array(items: Vec<ExpressionKind>) -> ExpressionKind {
+        ExpressionKind::ArrayLiteral(items.into_iter().map(Expression::from).collect())
     }
 
-    fn object(fields: HashMap<&str, Expression>) -> Expression {
-        Expression::ObjectLiteral(
+    fn object(fields: HashMap<&str, ExpressionKind>) -> ExpressionKind {
+        ExpressionKind::ObjectLiteral(
             fields
                 .into_iter()
-                .map(|(k, v)| (k.to_string(), v))
+                .map(|(k, v)| (k.to_string(), v.into()))
                 .collect(),
         )
     }
 
-    fn ident(name: &str) -> Expression {
-        Expression::Identifier(name.into())
+    fn ident(name: &str) -> ExpressionKind {
+        ExpressionKind::Identifier(name.into())
     }
 
-    fn class(name: &str) -> Expression {
-        Expression::ClassIdentifier(name.into())
+    fn class(name: &str) -> ExpressionKind {
+        ExpressionKind::ClassIdentifier(name.into())
     }
 
-    fn string(name: &str) -> Expression {
-        Expression::StringLiteral(name.into())
+    fn string(name: &str) -> ExpressionKind {
+        ExpressionKind::StringLiteral(name.into())
     }
 
-    fn call_op(lhs: Expression, args: Vec<Expression>) -> Expression {
+    fn call_op(lhs: ExpressionKind, args: Vec<ExpressionKind>) -> ExpressionKind {
         post(lhs, "()", Some(array(args)))
     }
 
-    fn index_op(lhs: Expression, rhs: Expression) -> Expression {
+    fn index_op(lhs: ExpressionKind, rhs: ExpressionKind) -> ExpressionKind {
         post(lhs, "[]", Some(rhs))
     }
 
-    fn number(x: i32) -> Expression {
-        Expression::NumberLiteral(x.to_string())
+    fn number(x: i32) -> ExpressionKind {
+        ExpressionKind::NumberLiteral(x.to_string())
     }
 
-    fn hex(x: i32) -> Expression {
-        Expression::HexLiteral(format!("0x{:x}", x))
+    fn hex(x: i32) -> ExpressionKind {
+        ExpressionKind::HexLiteral(format!("0x{:x}", x))
     }
 
-    fn boolean(x: bool) -> Expression {
-        Expression::BooleanLiteral(x.to_string())
+    fn boolean(x: bool) -> ExpressionKind {
+        ExpressionKind::BooleanLiteral(x.to_string())
     }
 
-    fn add_op(lhs: Expression, rhs: Expression) -> Expression {
+    fn add_op(lhs: ExpressionKind, rhs: ExpressionKind) -> ExpressionKind {
         binary(lhs, "+", rhs)
     }
 
-    fn dot_op(lhs: Expression, rhs: Expression) -> Expression {
+    fn dot_op(lhs: ExpressionKind, rhs: ExpressionKind) -> ExpressionKind {
         binary(lhs, ".", rhs)
     }
 
-    fn instance(name: &str, fields: HashMap<&str, Expression>) -> Expression {
-        post(
-            class(name),
-            "{}",
-            Some(Expression::ObjectLiteral(
+    fn instance(name: &str, fields: HashMap<&str, ExpressionKind>) -> ExpressionKind {
+        post(class(name), "{}", Some(object(fields)))
+    }
+
+    fn arrow(params: Vec<&str>, body: Vec<AstKind>) -> ExpressionKind {
+        ExpressionKind::ArrowFunction(
+            params.into_iter().map(String::from).collect(),
+            Arc::new(body.into_iter().map(|kind| AstNode::new(kind, 0..0)).collect()),
+        )
+    }
+
+    /// Removes the source locations, because the tests only care about the structure
+    fn without_locations(expr: ExpressionKind) -> ExpressionKind {
+        let strip = |e: Box<Expression>| Box::new(without_locations(e.kind).into());
+        match expr {
+            ExpressionKind::BinaryOp(lhs, op, rhs) => {
+                ExpressionKind::BinaryOp(strip(lhs), op, strip(rhs))
+            }
+            ExpressionKind::PostOp(lhs, op, rhs) => {
+                ExpressionKind::PostOp(strip(lhs), op, rhs.map(strip))
+            }
+            ExpressionKind::PreOp(op, rhs) => ExpressionKind::PreOp(op, strip(rhs)),
+            ExpressionKind::ArrayLiteral(items) => ExpressionKind::ArrayLiteral(
+                items
+                    .into_iter()
+                    .map(|x| without_locations(x.kind).into())
+                    .collect(),
+            ),
+            ExpressionKind::ObjectLiteral(fields) => ExpressionKind::ObjectLiteral(
                 fields
                     .into_iter()
-                    .map(|(k, v)| (k.to_string(), v))
+                    .map(|(k, v)| (k, without_locations(v.kind).into()))
                     .collect(),
-            )),
-        )
+            ),
+            ExpressionKind::ArrowFunction(params, body) => ExpressionKind::ArrowFunction(
+                params,
+                Arc::new(
+                    body.iter()
+                        .map(|node| {
+                            let kind = match &node.kind {
+                                AstKind::Expression(e) => {
+                                    AstKind::Expression(without_locations(e.kind.clone()).into())
+                                }
+                                AstKind::ReturnStatement(e) => AstKind::ReturnStatement(
+                                    without_locations(e.kind.clone()).into(),
+                                ),
+                                kind => kind.clone(),
+                            };
+                            AstNode::new(kind, 0..0)
+                        })
+                        .collect(),
+                ),
+            ),
+            expr => expr,
+        }
     }
 
     #[test]
@@ -868,6 +1063,34 @@ mod test {
         assert_eq!(parse(r"0x283123"), hex(0x283123));
     }
 
+    #[test]
+    fn binary_number() {
+        assert_eq!(
+            parse(r"0b1010"),
+            ExpressionKind::BinaryLiteral("0b1010".into())
+        );
+    }
+
+    #[test]
+    fn bitwise_precedence() {
+        assert_eq!(
+            parse(r"1 | 2 & 3 << 4 + 5"),
+            binary(
+                number(1),
+                "|",
+                binary(
+                    number(2),
+                    "&",
+                    binary(number(3), "<<", add_op(number(4), number(5)))
+                )
+            )
+        );
+        assert_eq!(
+            parse(r"x & 4 == 0"),
+            binary(binary(ident("x"), "&", number(4)), "==", number(0))
+        );
+    }
+
     #[test]
     fn object_literal() {
         assert_eq!(parse(r"#{}"), object(HashMap::new()));
@@ -939,14 +1162,14 @@ mod test {
 
     #[test]
     fn arrow_fn() {
-        assert_eq!(parse(r#"() => {}"#), ArrowFunction(vec![], vec![]));
+        assert_eq!(parse(r#"() => {}"#), arrow(vec![], vec![]));
     }
 
     #[test]
     fn arrow_fn_with_1_arg() {
         assert_eq!(
             parse(r#"(test) => {}"#),
-            ArrowFunction(vec!["test".into()], vec![])
+            arrow(vec!["test"], vec![])
         );
     }
 
@@ -954,7 +1177,7 @@ mod test {
     fn arrow_fn_with_args() {
         assert_eq!(
             parse(r#"(test, test) => {}"#),
-            ArrowFunction(vec!["test".into(), "test".into()], vec![])
+            arrow(vec!["test", "test"], vec![])
         );
     }
 
@@ -966,9 +1189,11 @@ mod test {
                     print(1);
                 }"#
             ),
-            ArrowFunction(
+            arrow(
                 vec![],
-                vec![Ast::Expression(call_op(ident("print"), vec![number(1)]))]
+                vec![AstKind::Expression(
+                    call_op(ident("print"), vec![number(1)]).into()
+                )]
             )
         );
     }
@@ -986,11 +1211,10 @@ mod test {
                 }"#
             ),
             object(hashmap! {
-                "f" => ArrowFunction(
+                "f" => arrow(
                     vec![],
-                    vec![Ast::FunctionCall(
-                        "print".into(),
-                        vec![string("hello world")]
+                    vec![AstKind::Expression(
+                        call_op(ident("print"), vec![string("hello world")]).into()
                     )]
                 )
             })
@@ -1126,12 +1350,11 @@ mod test {
                     ".",
                     ident("for_each")
                 ),
-                vec![Expression::ArrowFunction(
-                    vec!["i".into()],
-                    vec![Ast::ReturnStatement(call_op(
-                        ident("print"),
-                        vec![ident("i")]
-                    ))]
+                vec![arrow(
+                    vec!["i"],
+                    vec![AstKind::ReturnStatement(
+                        call_op(ident("print"), vec![ident("i")]).into()
+                    )]
                 )]
             )
         );
@@ -1163,4 +1386,12 @@ mod test {
             );
         }
     }
+
+    #[test]
+    fn unescape_string_literals() {
+        assert_eq!(unescape(r#"a\tb\n\"c\""#), "a\tb\n\"c\"");
+        assert_eq!(unescape(r"C:\\nog"), r"C:\nog");
+        assert_eq!(unescape(r"\u{1F600} café"), "😀 café");
+        assert_eq!(unescape(r"\u{zz} \q"), r"\u{zz} \q");
+    }
 }
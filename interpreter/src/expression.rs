@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use std::ops::Range;
 
 use super::{
-    ast::AstNode,
+    ast::FunctionBody,
     formatter::Formatter,
     operator::Operator,
     token::{Token, TokenKind},
@@ -16,6 +16,7 @@ pub enum ExpressionKind {
     PreOp(Operator, Box<Expression>),
     NumberLiteral(String),
     HexLiteral(String),
+    BinaryLiteral(String),
     ArrayLiteral(Vec<Expression>),
     ObjectLiteral(HashMap<String, Expression>),
     BooleanLiteral(String),
@@ -23,8 +24,12 @@ pub enum ExpressionKind {
     Identifier(String),
     ClassIdentifier(String),
     Null,
-    ArrowFunction(Vec<String>, Vec<AstNode>),
+    ArrowFunction(Vec<String>, FunctionBody),
     ClassInstantiation(String, HashMap<String, Expression>),
+    /// An argument of a function call that binds to the parameter with the given name
+    NamedArgument(String, Box<Expression>),
+    /// `condition ? then : otherwise`
+    Conditional(Box<Expression>, Box<Expression>, Box<Expression>),
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -25,7 +25,8 @@ impl<'a> Formatter<'a> {
             ExpressionKind::Null => "null".into(),
             ExpressionKind::StringLiteral(text) => format!(
                 "\"{}\"",
-                text.replace("\r", "\\r")
+                text.replace("\\", "\\\\")
+                    .replace("\r", "\\r")
                     .replace("\n", "\\n")
                     .replace("\t", "\\t")
                     .replace("\"", "\\\"")
@@ -34,6 +35,7 @@ impl<'a> Formatter<'a> {
             | ExpressionKind::ClassIdentifier(text)
             | ExpressionKind::NumberLiteral(text)
             | ExpressionKind::HexLiteral(text)
+            | ExpressionKind::BinaryLiteral(text)
             | ExpressionKind::BooleanLiteral(text) => text.clone(),
             ExpressionKind::ArrayLiteral(items) => format!(
                 "[{}]",
@@ -111,6 +113,15 @@ impl<'a> Formatter<'a> {
                     .map(|(k, v)| format!("{}: {}", k, self.format_expr(v)))
                     .join("\n")
             ),
+            ExpressionKind::NamedArgument(name, value) => {
+                format!("{}: {}", name, self.format_expr(value))
+            }
+            ExpressionKind::Conditional(condition, then, otherwise) => format!(
+                "{} ? {} : {}",
+                self.format_expr(condition),
+                self.format_expr(then),
+                self.format_expr(otherwise)
+            ),
             ExpressionKind::PreOp(op, expr) => {
                 format!("{}{}", op.to_string(), self.format_expr(expr))
             }
@@ -181,10 +192,21 @@ impl<'a> Formatter<'a> {
                 .iter()
                 .map(|line| format!("//{}", line))
                 .join(&format!("\n{}", self.indentation())),
+            AstKind::BlockComment(text) => format!("/*{}*/", text),
             AstKind::BreakStatement => "break".into(),
             AstKind::ReturnStatement(expr) => format!("return {}", self.format_expr(&expr)),
             AstKind::ExportStatement(stmt) => format!("export {}", self.format_ast(&stmt)),
-            AstKind::ImportStatement(path) => format!("import {}", path),
+            AstKind::ImportStatement(path, None) => format!("import {}", path),
+            AstKind::ImportStatement(path, Some(names)) => {
+                format!("import {{ {} }} from {}", names.join(", "), path)
+            }
+            AstKind::IncludeStatement(pattern) => format!(
+                "include {}",
+                self.format_expr(&Expression::new(
+                    ExpressionKind::StringLiteral(pattern.clone()),
+                    ast.location.clone()
+                ))
+            ),
             AstKind::IfStatement(branches) => branches
                 .iter()
                 .enumerate()
@@ -254,7 +276,7 @@ impl<'a> Formatter<'a> {
                                     format!(
                                         "fn {}({}) {{\n{}\n{}}}",
                                         name,
-                                        args.join(", "),
+                                        self.format_args(args),
                                         body,
                                         self.indentation()
                                     )
@@ -296,8 +318,6 @@ impl<'a> Formatter<'a> {
 #[cfg(test)]
 mod test {
     use super::Formatter;
-    use crate::ast::Ast;
-    use crate::interpreter::Program;
     use crate::parser::Parser;
 
     fn format(mut expected: &str) {
@@ -309,6 +329,35 @@ mod test {
         assert_eq!(actual, expected)
     }
 
+    #[test]
+    fn format_default_params_and_named_args() {
+        format(
+            r#"
+fn pad(s, width = 8) {
+    print(s, width)
+}
+pad("nog", width: 4)"#,
+        )
+    }
+
+    #[test]
+    fn format_selective_import() {
+        format(
+            r#"
+import { bar_config, Theme } from shared.theme
+export import { colors } from shared.theme"#,
+        )
+    }
+
+    #[test]
+    fn format_include() {
+        format(
+            r#"
+include "conf.d/*.ns"
+include "C:\\nog\\keybindings.ns""#,
+        )
+    }
+
     #[test]
     fn format_while() {
         format(
@@ -0,0 +1,258 @@
+//! Frees arrays, objects and class instances that are only kept alive by reference cycles, like an
+//! object that contains itself.
+//!
+//! Values are reference counted, so a cycle never gets freed on its own. Every container gets
+//! tracked when it is created and a collection runs once the number of tracked containers doubled
+//! since the last one. A collection counts how often each container is referenced by other tracked
+//! containers. A container with more references than that is used from somewhere else, like a
+//! scope or the host, and everything reachable from it is kept alive. The remaining containers are
+//! garbage and get emptied, which breaks the cycles and lets them be freed.
+//!
+//! Cycles that go through the environment of a function aren't detected, because scopes are not
+//! tracked.
+use crate::dynamic::Dynamic;
+use lazy_static::lazy_static;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard, Weak},
+};
+
+/// A collection never runs before this many containers are tracked
+const MIN_THRESHOLD: usize = 1000;
+
+type Items = Mutex<Vec<Dynamic>>;
+type Fields = Mutex<HashMap<String, Dynamic>>;
+
+enum Tracked {
+    Array(Weak<Items>),
+    Fields(Weak<Fields>),
+}
+
+enum Node {
+    Array(Arc<Items>),
+    Fields(Arc<Fields>),
+}
+
+enum Guard<'a> {
+    Array(MutexGuard<'a, Vec<Dynamic>>),
+    Fields(MutexGuard<'a, HashMap<String, Dynamic>>),
+}
+
+impl Tracked {
+    fn upgrade(&self) -> Option<Node> {
+        match self {
+            Tracked::Array(items) => items.upgrade().map(Node::Array),
+            Tracked::Fields(fields) => fields.upgrade().map(Node::Fields),
+        }
+    }
+}
+
+impl Node {
+    fn id(&self) -> usize {
+        match self {
+            Node::Array(items) => Arc::as_ptr(items) as *const () as usize,
+            Node::Fields(fields) => Arc::as_ptr(fields) as *const () as usize,
+        }
+    }
+
+    fn strong_count(&self) -> usize {
+        match self {
+            Node::Array(items) => Arc::strong_count(items),
+            Node::Fields(fields) => Arc::strong_count(fields),
+        }
+    }
+
+    fn downgrade(&self) -> Tracked {
+        match self {
+            Node::Array(items) => Tracked::Array(Arc::downgrade(items)),
+            Node::Fields(fields) => Tracked::Fields(Arc::downgrade(fields)),
+        }
+    }
+
+    /// Returns `None` if the container is locked, for example because the current thread is
+    /// modifying it.
+    fn try_lock(&self) -> Option<Guard<'_>> {
+        match self {
+            Node::Array(items) => items.try_lock().ok().map(Guard::Array),
+            Node::Fields(fields) => fields.try_lock().ok().map(Guard::Fields),
+        }
+    }
+}
+
+impl<'a> Guard<'a> {
+    fn values(&self) -> Vec<&Dynamic> {
+        match self {
+            Guard::Array(items) => items.iter().collect(),
+            Guard::Fields(fields) => fields.values().collect(),
+        }
+    }
+
+    fn take(&mut self) -> Vec<Dynamic> {
+        match self {
+            Guard::Array(items) => std::mem::take(&mut **items),
+            Guard::Fields(fields) => fields.drain().map(|(_, value)| value).collect(),
+        }
+    }
+}
+
+/// Collects the ids of the containers the value references directly.
+fn children(value: &Dynamic, ids: &mut Vec<usize>) {
+    match value {
+        Dynamic::Array(items) => ids.push(Arc::as_ptr(items) as *const () as usize),
+        Dynamic::Object(fields) | Dynamic::ClassInstance(_, fields) => {
+            ids.push(Arc::as_ptr(fields) as *const () as usize)
+        }
+        Dynamic::Module(module) => {
+            for value in module.variables.values() {
+                children(value, ids);
+            }
+        }
+        _ => {}
+    }
+}
+
+struct Registry {
+    tracked: Vec<Tracked>,
+    threshold: usize,
+}
+
+impl Registry {
+    fn track(&mut self, container: Tracked) {
+        self.tracked.push(container);
+
+        if self.tracked.len() >= self.threshold {
+            self.collect();
+            self.threshold = usize::max(MIN_THRESHOLD, self.tracked.len() * 2);
+        }
+    }
+
+    /// Returns how many containers got emptied
+    fn collect(&mut self) -> usize {
+        let nodes: Vec<Node> = self.tracked.iter().filter_map(Tracked::upgrade).collect();
+        self.tracked = nodes.iter().map(Node::downgrade).collect();
+
+        let index: HashMap<usize, usize> = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (node.id(), i))
+            .collect();
+
+        // the containers stay locked until the end, so that their content can't change in between
+        let mut guards: Vec<Option<Guard>> = nodes.iter().map(Node::try_lock).collect();
+        let mut internal_refs = vec![0; nodes.len()];
+        let mut edges = vec![Vec::new(); nodes.len()];
+
+        for (i, guard) in guards.iter().enumerate() {
+            if let Some(guard) = guard {
+                let mut ids = Vec::new();
+                for value in guard.values() {
+                    children(value, &mut ids);
+                }
+
+                for id in ids {
+                    if let Some(&child) = index.get(&id) {
+                        internal_refs[child] += 1;
+                        edges[i].push(child);
+                    }
+                }
+            }
+        }
+
+        // `nodes` holds one of the references itself
+        let mut stack: Vec<usize> = (0..nodes.len())
+            .filter(|&i| guards[i].is_none() || nodes[i].strong_count() - 1 > internal_refs[i])
+            .collect();
+        let mut reachable = vec![false; nodes.len()];
+
+        while let Some(i) = stack.pop() {
+            if !reachable[i] {
+                reachable[i] = true;
+                stack.extend(edges[i].iter().copied());
+            }
+        }
+
+        let garbage: Vec<Vec<Dynamic>> = guards
+            .iter_mut()
+            .enumerate()
+            .filter(|(i, _)| !reachable[*i])
+            .filter_map(|(_, guard)| guard.as_mut().map(Guard::take))
+            .collect();
+        let count = garbage.len();
+
+        // the content has to be dropped after the containers got unlocked
+        drop(guards);
+        drop(garbage);
+
+        count
+    }
+}
+
+lazy_static! {
+    static ref REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+        tracked: Vec::new(),
+        threshold: MIN_THRESHOLD,
+    });
+}
+
+pub fn track_array(items: &Arc<Items>) {
+    REGISTRY
+        .lock()
+        .unwrap()
+        .track(Tracked::Array(Arc::downgrade(items)));
+}
+
+pub fn track_fields(fields: &Arc<Fields>) {
+    REGISTRY
+        .lock()
+        .unwrap()
+        .track(Tracked::Fields(Arc::downgrade(fields)));
+}
+
+/// Returns how many arrays, objects and class instances are currently alive
+pub fn live_containers() -> usize {
+    REGISTRY
+        .lock()
+        .unwrap()
+        .tracked
+        .iter()
+        .filter(|container| match container {
+            Tracked::Array(items) => items.strong_count() > 0,
+            Tracked::Fields(fields) => fields.strong_count() > 0,
+        })
+        .count()
+}
+
+/// Frees every container that is only kept alive by reference cycles and returns how many got
+/// freed. Collections also run automatically while new containers get created.
+pub fn collect_garbage() -> usize {
+    REGISTRY.lock().unwrap().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collect_cycles() {
+        let fields = Arc::new(Mutex::new(HashMap::new()));
+        track_fields(&fields);
+        let array = Dynamic::new_array(vec![Dynamic::Object(fields.clone())]);
+        fields.lock().unwrap().insert("items".into(), array);
+
+        let kept = Dynamic::new_object(HashMap::new());
+        if let Dynamic::Object(kept_fields) = &kept {
+            kept_fields
+                .lock()
+                .unwrap()
+                .insert("self".into(), kept.clone());
+        }
+
+        let weak = Arc::downgrade(&fields);
+        drop(fields);
+
+        assert!(weak.upgrade().is_some());
+        collect_garbage();
+        assert!(weak.upgrade().is_none());
+        assert!(!kept.get_field("self").is_null());
+    }
+}
@@ -1,21 +1,32 @@
 use super::{
     ast::ClassMember,
-    ast::{AstKind, AstNode},
+    ast::{AstKind, AstNode, Parameter},
     class::Class,
-    dynamic::{object_builder::ObjectBuilder, Dynamic, Number},
+    dynamic::{object_builder::ObjectBuilder, Dynamic, Float, Number},
     expression::{Expression, ExpressionKind},
     formatter::Formatter,
     function::Function,
+    json,
     lexer::Lexer,
+    limits::{Limits, Usage},
+    method::Method,
     module::Module,
     operator::Operator,
     parser::Parser,
     runtime_error::*,
     scope::Scope,
+    time,
     token::{Token, TokenKind},
 };
 use itertools::Itertools;
-use std::{collections::HashMap, iter, ops::Range, path::PathBuf, sync::Arc, time::Instant};
+use std::{
+    collections::HashMap,
+    iter,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Instant,
+};
 
 #[derive(Debug, Clone)]
 pub struct Program<'a> {
@@ -91,12 +102,19 @@ pub struct Interpreter {
     pub exported_variables: Vec<String>,
     pub exported_classes: Vec<String>,
     pub module_cache: HashMap<PathBuf, Module>,
+    /// Every file that got included using an include statement
+    pub included_files: Vec<PathBuf>,
+    /// The files that are being included right now, which is how an include cycle gets detected
+    including: Vec<PathBuf>,
     /// This may contain a dynamic if a return statement was parsed. This gets consumed when a
     /// function definition finishes parsing
     pub return_value: Option<Dynamic>,
     /// This represents the scope hierachy where the scope at index 0 is the global scope and every
     /// scope after the first one is a subscope of the previous one
     pub scopes: Vec<Scope>,
+    /// Callbacks that exceed one of these limits get stopped with an error
+    pub limits: Limits,
+    usage: Usage,
 }
 
 impl Interpreter {
@@ -114,10 +132,14 @@ impl Interpreter {
             modules: create_default_modules(),
             classes: HashMap::new(),
             module_cache: HashMap::new(),
+            included_files: Vec::new(),
+            including: Vec::new(),
             exported_classes: Vec::new(),
             exported_variables: Vec::new(),
             return_value: None,
             scopes: vec![Scope::default()],
+            limits: Limits::default(),
+            usage: Usage::default(),
         }
     }
 
@@ -247,7 +269,7 @@ impl Interpreter {
                     };
                 }
             } else {
-                scope.set(name, value);
+                scope.assign(&name, value);
             }
         } else {
             panic!("Variable {} doesn't exist!", name);
@@ -262,52 +284,62 @@ impl Interpreter {
         match &expr.kind {
             ExpressionKind::PreOp(op, rhs) => {
                 let value = self.eval(rhs.as_ref())?;
-                Ok(match op {
-                    Operator::Subtract => match value {
-                        Dynamic::Number(x) => (-x).into(),
-                        _ => Dynamic::Null,
-                    },
-                    Operator::Add => match value {
-                        Dynamic::Number(x) => (x).into(),
-                        _ => Dynamic::Null,
-                    },
-                    Operator::Not => (!value.is_true()).into(),
-                    _ => Dynamic::Null,
-                })
+                match (op, value) {
+                    (Operator::Subtract, Dynamic::Number(x)) => Ok(x.wrapping_neg().into()),
+                    (Operator::Subtract, Dynamic::Float(x)) => Ok((-x).into()),
+                    (Operator::Add, value @ Dynamic::Number(_))
+                    | (Operator::Add, value @ Dynamic::Float(_)) => Ok(value),
+                    (Operator::Not, value) => Ok((!value.is_true()).into()),
+                    (op, value) => Err(RuntimeError::OperatorNotImplemented {
+                        expr: expr.clone(),
+                        class: value.type_name(),
+                        operator: op.clone(),
+                    }),
+                }
+            }
+            ExpressionKind::PostOp(lhs, Operator::Call, Some(arg))
+                if has_named_args(arg.as_ref()) =>
+            {
+                self.call_with_named_args(lhs.as_ref(), arg.as_ref())
+            }
+            ExpressionKind::PostOp(callee, Operator::Call, Some(arg)) => {
+                match method_call(callee) {
+                    Some((this, name)) => {
+                        let this = self.eval(this)?;
+                        self.call_method(callee, this, name, arg)
+                    }
+                    None => {
+                        let f = self.eval(callee)?;
+                        let args = self.eval_args(arg)?;
+                        self.apply_op(expr, f, &Operator::Call, args)
+                    }
+                }
             }
             ExpressionKind::PostOp(lhs, op, arg) => {
                 let value = self.eval(lhs.as_ref())?;
+                let args = match arg {
+                    Some(arg) => vec![self.eval(arg)?],
+                    None => vec![Dynamic::Null],
+                };
+                let res = self.apply_op(expr, value, op, args)?;
 
-                let arg = arg.as_ref().map(|arg| self.eval(arg.as_ref()));
-
-                let class = self.find_class(&value.type_name()).unwrap().clone();
-
-                if let Some(cb) = class.get_op_impl(&op) {
-                    let res = cb.invoke(
-                        self,
-                        value,
-                        match op {
-                            Operator::Call => arg.unwrap()?.as_array().unwrap(),
-                            _ => vec![arg.unwrap_or(Ok(Default::default()))?],
-                        },
-                    )?;
-
-                    match op {
-                        Operator::Increment | Operator::Decrement => {
-                            let ident = lhs.to_string();
-                            self.assign_variable(ident, res.clone());
-                        }
-                        _ => {}
-                    };
+                match op {
+                    Operator::Increment | Operator::Decrement => {
+                        let ident = lhs.to_string();
+                        self.assign_variable(ident, res.clone());
+                    }
+                    _ => {}
+                };
 
-                    Ok(res)
-                } else {
-                    Err(RuntimeError::OperatorNotImplemented {
-                        expr: expr.clone(),
-                        class: class.name,
-                        operator: op.clone(),
-                    })
-                }
+                Ok(res)
+            }
+            // the right side only gets evaluated when the left side doesn't decide the result, so
+            // that it can rely on the left side (e.g. `x != 0 && 10 / x > 2`)
+            ExpressionKind::BinaryOp(lhs, Operator::And, rhs) => {
+                Ok((self.eval(lhs)?.is_true() && self.eval(rhs)?.is_true()).into())
+            }
+            ExpressionKind::BinaryOp(lhs, Operator::Or, rhs) => {
+                Ok((self.eval(lhs)?.is_true() || self.eval(rhs)?.is_true()).into())
             }
             ExpressionKind::BinaryOp(lhs, op, rhs) => {
                 let (class_name, is_static) = match &lhs.kind {
@@ -357,31 +389,23 @@ impl Interpreter {
                         });
                     }
                 } else {
-                    let class = self.find_class(&lhs.type_name()).unwrap();
-
-                    if class.name == "Null" {
-                        return Err(RuntimeError::OperatorNotImplemented {
-                            expr: expr.clone(),
-                            class: class.name.clone(),
-                            operator: op.clone(),
-                        });
-                    }
-
-                    if let Some(f) = class.get_op_impl(&op).cloned() {
-                        f.invoke(self, lhs, args)
-                    } else {
-                        panic!(
-                            "The class {} doesn't implement the operator {}",
-                            class.name,
-                            op.to_string()
-                        );
-                    }
+                    self.apply_op(expr, lhs, op, args)
                 }
             }
-            ExpressionKind::NumberLiteral(x) => Ok(Dynamic::Number(x.parse().unwrap())),
-            ExpressionKind::HexLiteral(x) => {
-                Ok(Dynamic::Number(i32::from_str_radix(&x[2..], 16).unwrap()))
+            ExpressionKind::NumberLiteral(x) if x.contains('.') => {
+                Ok(Dynamic::Float(x.parse().map_err(|_| format!("Invalid float {}", x))?))
             }
+            ExpressionKind::NumberLiteral(x) => Ok(Dynamic::Number(
+                x.parse().map_err(|_| format!("{} doesn't fit into a number", x))?,
+            )),
+            // the literals are unsigned, so that colors with an alpha channel like `0xFF282828`
+            // don't overflow
+            ExpressionKind::HexLiteral(x) => Ok(Dynamic::Number(
+                u32::from_str_radix(&x[2..], 16).map_err(|e| e.to_string())? as i32,
+            )),
+            ExpressionKind::BinaryLiteral(x) => Ok(Dynamic::Number(
+                u32::from_str_radix(&x[2..], 2).map_err(|e| e.to_string())? as i32,
+            )),
             ExpressionKind::BooleanLiteral(x) => Ok(Dynamic::Boolean(x == "true")),
             ExpressionKind::StringLiteral(x) => Ok(Dynamic::String(x.into())),
             ExpressionKind::Null => Ok(Dynamic::Null),
@@ -405,41 +429,229 @@ impl Interpreter {
 
                 Ok(Dynamic::new_object(evaluated_fields))
             }
+            ExpressionKind::Conditional(condition, then, otherwise) => {
+                if self.eval(condition)?.is_true() {
+                    self.eval(then)
+                } else {
+                    self.eval(otherwise)
+                }
+            }
+            ExpressionKind::NamedArgument(name, _) => {
+                Err(format!("Named argument {} can only be passed to a function", name).into())
+            }
             ExpressionKind::ArrowFunction(arg_names, body) => Ok(Dynamic::Function {
                 name: "<anonymous function>".into(),
-                arg_names: arg_names.into_iter().map(|t| t.into()).collect(),
+                arg_names: arg_names.iter().map(|name| Parameter::new(name)).collect(),
                 body: body.clone(),
                 scope: (&self.scopes).into(),
             }),
         }
     }
+    /// Evaluates the arguments of a call
+    fn eval_args(&mut self, arg: &Expression) -> RuntimeResult<Vec<Dynamic>> {
+        match &arg.kind {
+            ExpressionKind::ArrayLiteral(items) => {
+                items.iter().map(|item| self.eval(item)).collect()
+            }
+            _ => Ok(self.eval(arg)?.as_array().unwrap()),
+        }
+    }
+
+    /// Calls a method like `parts.push(x)`. The methods of the builtin classes get called directly,
+    /// instead of first binding them to the value like evaluating `parts.push` would.
+    fn call_method(
+        &mut self,
+        callee: &Expression,
+        this: Dynamic,
+        name: &str,
+        arg: &Expression,
+    ) -> RuntimeResult {
+        if let Some(method) = self.builtin_method(&this, name) {
+            let args = self.eval_args(arg)?;
+            // like any other call of a rust function, a failing method results in null
+            return Ok(method.invoke(self, this, args).unwrap_or_default());
+        }
+
+        let f = self.apply_op(callee, this, &Operator::Dot, vec![name.into()])?;
+        let args = self.eval_args(arg)?;
+        self.apply_op(callee, f, &Operator::Call, args)
+    }
+
+    /// The method of the builtin class of the value. Classes defined in nogscript can implement
+    /// the dot operator themselves, so their methods always get looked up through it.
+    fn builtin_method(&self, value: &Dynamic, name: &str) -> Option<Method> {
+        match value {
+            Dynamic::ClassInstance(..) | Dynamic::Null => None,
+            _ if self.classes.contains_key(value.class_name()) => None,
+            _ => self
+                .default_classes
+                .get(value.class_name())?
+                .functions
+                .get(name)
+                .cloned(),
+        }
+    }
+
+    /// Runs the implementation of the operator that the class of the value provides. Only the
+    /// implementation gets copied, because this runs for every operator and cloning the class
+    /// would copy all of its methods.
+    fn apply_op(
+        &mut self,
+        expr: &Expression,
+        value: Dynamic,
+        op: &Operator,
+        args: Vec<Dynamic>,
+    ) -> RuntimeResult {
+        let class = self.find_class(value.class_name()).unwrap();
+        let f = match value {
+            // null doesn't implement any operator, but its class gets the default ones
+            Dynamic::Null => None,
+            _ => class.get_op_impl(op).cloned(),
+        };
+
+        match f {
+            Some(f) => f.invoke(self, value, args),
+            None => Err(RuntimeError::OperatorNotImplemented {
+                expr: expr.clone(),
+                class: class.name.clone(),
+                operator: op.clone(),
+            }),
+        }
+    }
     fn consume_return_value(&mut self) -> Dynamic {
         let result = self.return_value.clone();
         self.return_value = None;
         result.unwrap_or_default()
     }
+    /// Evaluates the arguments of a call that uses named arguments. Only functions that are
+    /// defined in nogscript know the names of their parameters.
+    fn call_with_named_args(&mut self, callee: &Expression, arg: &Expression) -> RuntimeResult {
+        let value = self.eval(callee)?;
+        let mut args = Vec::new();
+        let mut named_args = HashMap::new();
+
+        if let ExpressionKind::ArrayLiteral(items) = &arg.kind {
+            for item in items {
+                match &item.kind {
+                    ExpressionKind::NamedArgument(name, expr) => {
+                        let value = self.eval(expr)?;
+                        if named_args.insert(name.clone(), value).is_some() {
+                            return Err(
+                                format!("Argument {} is passed more than once", name).into()
+                            );
+                        }
+                    }
+                    _ if !named_args.is_empty() => {
+                        return Err(
+                            "Positional arguments have to come before named arguments".into()
+                        )
+                    }
+                    _ => args.push(self.eval(item)?),
+                }
+            }
+        }
+
+        match value {
+            Dynamic::Function {
+                arg_names,
+                scope,
+                body,
+                ..
+            } => self.call_fn_with_named_args(
+                None,
+                Some(scope),
+                &arg_names,
+                &args,
+                named_args,
+                &body,
+            ),
+            x => Err(format!("{} doesn't support named arguments", x.type_name()).into()),
+        }
+    }
     pub fn call_fn(
         &mut self,
         this: Option<Dynamic>,
         scope: Option<Scope>,
-        arg_names: &Vec<String>,
+        params: &Vec<Parameter>,
+        args: &Vec<Dynamic>,
+        body: &Vec<AstNode>,
+    ) -> RuntimeResult {
+        self.call_fn_with_named_args(this, scope, params, args, HashMap::new(), body)
+    }
+    /// Binds the positional arguments in order and the named arguments by name. A parameter that
+    /// didn't get an argument falls back to its default value, which gets evaluated inside the
+    /// scope of the function.
+    pub fn call_fn_with_named_args(
+        &mut self,
+        this: Option<Dynamic>,
+        scope: Option<Scope>,
+        params: &Vec<Parameter>,
+        args: &Vec<Dynamic>,
+        named_args: HashMap<String, Dynamic>,
+        body: &Vec<AstNode>,
+    ) -> RuntimeResult {
+        let scope_count = self.scopes.len();
+        let result = match self.usage.enter(&self.limits) {
+            Ok(()) => self.bind_and_execute(this, scope, params, args, named_args, body),
+            Err(limit) => Err(RuntimeError::LimitExceeded { limit }),
+        };
+        self.usage.leave();
+
+        // a call that failed halfway didn't clean up after itself
+        if result.is_err() {
+            self.scopes.truncate(scope_count);
+            self.return_value = None;
+            self.broken = false;
+            self.continued = false;
+        }
+
+        result
+    }
+
+    fn bind_and_execute(
+        &mut self,
+        this: Option<Dynamic>,
+        scope: Option<Scope>,
+        params: &Vec<Parameter>,
         args: &Vec<Dynamic>,
+        mut named_args: HashMap<String, Dynamic>,
         body: &Vec<AstNode>,
     ) -> RuntimeResult {
-        let mut f_scope = scope.unwrap_or_default();
-        for (arg_name, arg) in arg_names.iter().zip(args.iter()) {
-            f_scope.set(
-                arg_name.clone(),
-                match arg {
-                    Dynamic::Lazy(expr) => self.eval(expr)?,
-                    x => x.clone(),
-                },
-            );
+        if let Some(name) = named_args
+            .keys()
+            .find(|name| !params.iter().any(|p| &p.name == *name))
+        {
+            return Err(format!("Function doesn't have a parameter called {}", name).into());
+        }
+
+        // every call gets its own frame, so that the arguments don't leak into the environment
+        let mut f_scope = scope.map(|env| env.child()).unwrap_or_default();
+        let mut missing = Vec::new();
+        for (idx, param) in params.iter().enumerate() {
+            let value = match (args.get(idx), named_args.remove(&param.name)) {
+                (Some(_), Some(_)) => {
+                    return Err(format!("Parameter {} got more than one value", param.name).into())
+                }
+                (Some(Dynamic::Lazy(expr)), None) => self.eval(expr)?,
+                (Some(arg), None) => arg.clone(),
+                (None, Some(arg)) => arg,
+                (None, None) => {
+                    missing.push(param);
+                    continue;
+                }
+            };
+            f_scope.set(param.name.clone(), value);
         }
         if let Some(this) = this {
             f_scope.set("this".to_string(), this);
         }
-        self.scopes.push(f_scope);
+        self.scopes.push(f_scope.clone());
+        for param in missing {
+            if let Some(default) = &param.default {
+                let value = self.eval(default)?;
+                f_scope.set(param.name.clone(), value);
+            }
+        }
         self.execute_stmts(&body)?;
         let result = self.consume_return_value();
         self.scopes.pop();
@@ -447,41 +659,18 @@ impl Interpreter {
     }
 
     fn find(&mut self, key: &str) -> Dynamic {
-        let mut path = key.split(".").peekable();
+        let mut path = key.split(".");
         let root_path = path.next().unwrap();
-        if let Some(scope) = self
+        let root = self
             .scopes
-            .iter_mut()
+            .iter()
             .rev()
-            .find(|s| s.is_defined(root_path))
-        {
-            let mut field_value = scope.get(root_path).clone();
-            if let Some(ident) = path.next() {
-                let mut field_ident = ident;
-                loop {
-                    field_value = field_value.get_field(field_ident);
-                    field_ident = match path.next() {
-                        Some(x) => x,
-                        None => break,
-                    };
-                }
-            }
-            field_value
-        } else {
-            let field_value = self.default_variables.get(root_path);
-            if let Some(mut field_value) = field_value.cloned() {
-                if let Some(ident) = path.next() {
-                    let mut field_ident = ident;
-                    loop {
-                        field_value = field_value.get_field(field_ident);
-                        field_ident = match path.next() {
-                            Some(x) => x,
-                            None => break,
-                        };
-                    }
-                }
-            }
-            field_value.unwrap_or_default().clone()
+            .find_map(|s| s.lookup(root_path))
+            .or_else(|| self.default_variables.get(root_path).cloned());
+
+        match root {
+            Some(value) => path.fold(value, |value, field| value.get_field(field)),
+            None => Dynamic::Null,
         }
     }
     fn import(&mut self, path: &str) -> RuntimeResult<(String, Dynamic)> {
@@ -538,7 +727,158 @@ impl Interpreter {
 
         Ok((name.into(), res))
     }
+    /// Executes every file that matches the pattern in the current scope, sorted by their paths.
+    /// The pattern is relative to the directory of the current file and only its file name can
+    /// contain the wildcards `*` and `?`. A pattern without wildcards has to match a file.
+    fn include(&mut self, pattern: &str) -> RuntimeResult<()> {
+        let pattern = Path::new(pattern);
+        let name_pattern = pattern
+            .file_name()
+            .and_then(|x| x.to_str())
+            .ok_or_else(|| format!("Invalid include pattern {:?}", pattern))?;
+
+        let mut dir = self
+            .file_path
+            .parent()
+            .map(PathBuf::from)
+            .unwrap_or_default();
+        if let Some(parent) = pattern.parent() {
+            dir.push(parent);
+        }
+
+        let paths = std::fs::read_dir(&dir)
+            .map_err(|e| format!("Failed to include {:?}: {}", pattern, e))?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.is_file()
+                    && path
+                        .file_name()
+                        .and_then(|x| x.to_str())
+                        .map_or(false, |name| matches_wildcards(name_pattern, name))
+            })
+            .sorted()
+            .collect::<Vec<_>>();
+
+        if paths.is_empty() && !name_pattern.contains(&['*', '?'][..]) {
+            return Err(format!("Couldn't find {:?} to include", pattern).into());
+        }
+
+        for path in paths {
+            self.include_file(path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the file, which has to be inside of the directory of the current file. Relative paths
+    /// start at that directory.
+    fn read_file(&self, path: &str) -> RuntimeResult<String> {
+        let dir = match self.file_path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let failed = |e: std::io::Error| format!("Failed to read {:?}: {}", path, e);
+
+        // canonical paths resolve `..` and links, so that neither can lead outside
+        let root = dir.canonicalize().map_err(failed)?;
+        let file = dir.join(path).canonicalize().map_err(failed)?;
+
+        if !file.starts_with(&root) {
+            return Err(format!("{:?} is outside of the directory of the script", path).into());
+        }
+
+        Ok(std::fs::read_to_string(file).map_err(failed)?)
+    }
+
+    fn include_file(&mut self, path: PathBuf) -> RuntimeResult<()> {
+        if self.including.contains(&path) {
+            return Err(format!("{:?} includes itself", path).into());
+        }
+
+        let content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+        let mut parser = Parser::new();
+
+        parser.set_source(path.clone(), &content, 0);
+
+        let program = parser.parse()?;
+
+        // the statements run in the current scope, but errors and nested includes have to refer
+        // to the included file
+        let file_path = std::mem::replace(&mut self.file_path, path.clone());
+        let source = std::mem::replace(&mut self.source, content.clone());
+        self.including.push(path.clone());
+
+        let result = self.execute_stmts(&program.stmts);
+
+        self.including.pop();
+        self.source = source;
+        self.file_path = file_path;
+        self.included_files.push(path.clone());
+
+        result.map_err(|e| RuntimeError::Raw {
+            msg: format!("{:?}: {}", path, e.message(&program)),
+        })
+    }
+
+    /// Imports the module or only the given names of it into the current scope. Classes are made
+    /// available like classes defined in the current file.
+    ///
+    /// Returns every name that got defined and whether it is a class.
+    fn import_names(
+        &mut self,
+        path: &str,
+        names: &Option<Vec<String>>,
+    ) -> RuntimeResult<Vec<(String, bool)>> {
+        let (mod_name, module) = self.import(path)?;
+
+        let names = match names {
+            Some(names) => names,
+            None => {
+                self.get_scope_mut().set(mod_name.clone(), module);
+                return Ok(vec![(mod_name, false)]);
+            }
+        };
+
+        let module = match module {
+            Dynamic::Module(module) => *module,
+            x => {
+                return Err(RuntimeError::UnexpectedType {
+                    expected: "Module".into(),
+                    actual: x.type_name(),
+                })
+            }
+        };
+
+        let mut imported = Vec::new();
+
+        for name in names {
+            match module.get_export(name) {
+                Some(Dynamic::Class(class)) => {
+                    self.add_class(*class);
+                    imported.push((name.clone(), true));
+                }
+                Some(value) => {
+                    self.get_scope_mut().set(name.clone(), value);
+                    imported.push((name.clone(), false));
+                }
+                None => return Err(format!("Module {} doesn't export {}", path, name).into()),
+            }
+        }
+
+        Ok(imported)
+    }
+
+    /// Counts an instruction towards the budget of the current callback
+    fn step(&mut self) -> RuntimeResult<()> {
+        self.usage
+            .step(&self.limits)
+            .map_err(|limit| RuntimeError::LimitExceeded { limit })
+    }
+
     fn execute_stmt(&mut self, stmt: &AstNode) -> RuntimeResult<()> {
+        self.step()?;
+
         match &stmt.kind {
             AstKind::VariableDefinition(name, value) => {
                 let value = self.eval(&value)?;
@@ -555,6 +895,7 @@ impl Interpreter {
             }
             AstKind::Documentation(_) => {}
             AstKind::Comment(_) => {}
+            AstKind::BlockComment(_) => {}
             AstKind::VariableAssignment(name, value) => {
                 let value = self.eval(&value)?;
                 self.assign_variable(name.clone(), value)
@@ -587,33 +928,33 @@ impl Interpreter {
                 actual => panic!("Expected {} to be a function, but it is a {}", name, actual),
             },
             AstKind::FunctionDefinition(name, args, body) => {
-                let flat_scope = (&self.scopes).into();
+                // the environment includes the current frame, which is how the function can call
+                // itself
+                let env = (&self.scopes).into();
                 let scope = self.get_scope_mut();
                 scope.set(
                     name.clone(),
                     Dynamic::Function {
                         name: name.clone(),
                         arg_names: args.clone(),
-                        body: body.clone(),
-                        scope: flat_scope,
+                        body: Arc::new(body.clone()),
+                        scope: env,
                     },
                 )
             }
             AstKind::IfStatement(branches) => {
                 for (cond, block) in branches {
                     if self.eval(&cond)?.is_true() {
-                        self.scopes.push(Scope::default());
-                        self.execute_stmts(&block)?;
-                        self.scopes.pop();
+                        self.execute_block(block)?;
                         break;
                     }
                 }
             }
             AstKind::WhileStatement(cond, block) => {
                 while !self.broken && self.eval(&cond)?.is_true() {
-                    self.scopes.push(Scope::default());
-                    self.execute_stmts(&block)?;
-                    self.scopes.pop();
+                    // an empty body doesn't execute any statements
+                    self.step()?;
+                    self.execute_block(block)?;
                     self.continued = false;
                 }
                 self.broken = false;
@@ -664,63 +1005,19 @@ impl Interpreter {
                 }
             }
             AstKind::PlusAssignment(name, expr) => {
-                let new_value = self.eval(&Expression::new(
-                    ExpressionKind::BinaryOp(
-                        Box::new(Expression::new(
-                            ExpressionKind::Identifier(name.clone()),
-                            stmt.location.clone(),
-                        )),
-                        Operator::Add,
-                        Box::new(expr.clone()),
-                    ),
-                    stmt.location.clone(),
-                ))?;
-                self.assign_variable(name.clone(), new_value);
+                self.compound_assign(name, Operator::Add, expr)?
             }
             AstKind::MinusAssignment(name, expr) => {
-                let new_value = self.eval(&Expression::new(
-                    ExpressionKind::BinaryOp(
-                        Box::new(Expression::new(
-                            ExpressionKind::Identifier(name.clone()),
-                            stmt.location.clone(),
-                        )),
-                        Operator::Subtract,
-                        Box::new(expr.clone()),
-                    ),
-                    stmt.location.clone(),
-                ))?;
-                self.assign_variable(name.clone(), new_value);
+                self.compound_assign(name, Operator::Subtract, expr)?
             }
             AstKind::ReturnStatement(expr) => {
                 self.return_value = Some(self.eval(expr)?);
             }
             AstKind::TimesAssignment(name, expr) => {
-                let new_value = self.eval(&Expression::new(
-                    ExpressionKind::BinaryOp(
-                        Box::new(Expression::new(
-                            ExpressionKind::Identifier(name.clone()),
-                            stmt.location.clone(),
-                        )),
-                        Operator::Times,
-                        Box::new(expr.clone()),
-                    ),
-                    stmt.location.clone(),
-                ))?;
-                self.assign_variable(name.clone(), new_value);
+                self.compound_assign(name, Operator::Times, expr)?
             }
             AstKind::DivideAssignment(name, expr) => {
-                let new_value = self.eval(&Expression::new(
-                    ExpressionKind::BinaryOp(
-                        Box::new(Expression::new(
-                            ExpressionKind::Identifier(name.clone()),
-                            stmt.location.clone(),
-                        )),
-                        Operator::Divide,
-                        Box::new(expr.clone()),
-                    ),
-                    stmt.location.clone(),
-                ))?;
-                self.assign_variable(name.clone(), new_value);
+                self.compound_assign(name, Operator::Divide, expr)?
             }
             AstKind::BreakStatement => {
                 self.broken = true;
@@ -732,10 +1029,10 @@ impl Interpreter {
                 self.eval(&expr)?;
             }
             AstKind::StaticFunctionDefinition(_, _, _) => unreachable!(),
-            AstKind::ImportStatement(path) => {
-                let (mod_name, module) = self.import(&path)?;
-                self.get_scope_mut().set(mod_name, module);
+            AstKind::ImportStatement(path, names) => {
+                self.import_names(path, names)?;
             }
+            AstKind::IncludeStatement(pattern) => self.include(pattern)?,
             AstKind::ExportStatement(ast) => {
                 match &ast.kind {
                     AstKind::Expression(expr) => match &expr.kind {
@@ -759,6 +1056,15 @@ impl Interpreter {
                         self.exported_classes.push(name.clone());
                         self.execute_stmt(&ast)?;
                     }
+                    AstKind::ImportStatement(path, names) => {
+                        for (name, is_class) in self.import_names(path, names)? {
+                            if is_class {
+                                self.exported_classes.push(name);
+                            } else {
+                                self.exported_variables.push(name);
+                            }
+                        }
+                    }
                     _ => todo!(),
                 };
             }
@@ -768,6 +1074,36 @@ impl Interpreter {
         Ok(())
     }
 
+    /// Applies the operator of an assignment like `x += 1` to the variable in place, instead of
+    /// building and evaluating the expression `x = x + 1`.
+    fn compound_assign(
+        &mut self,
+        name: &str,
+        op: Operator,
+        expr: &Expression,
+    ) -> RuntimeResult<()> {
+        let value = self.find(name);
+        let rhs = self.eval(expr)?;
+        let new_value = self.apply_op(expr, value, &op, vec![rhs])?;
+        self.assign_variable(name.to_string(), new_value);
+
+        Ok(())
+    }
+
+    /// Executes the statements of a block inside of their own frame. A block that doesn't define
+    /// anything can't tell the difference, so it runs in the current frame, which saves creating a
+    /// frame for every iteration of a loop and looking through it for every variable.
+    fn execute_block(&mut self, stmts: &Vec<AstNode>) -> RuntimeResult<()> {
+        if !stmts.iter().any(AstNode::defines_names) {
+            return self.execute_stmts(stmts);
+        }
+
+        self.scopes.push(Scope::default());
+        let result = self.execute_stmts(stmts);
+        self.scopes.pop();
+        result
+    }
+
     fn execute_stmts(&mut self, stmts: &Vec<AstNode>) -> RuntimeResult<()> {
         for stmt in stmts {
             self.execute_stmt(stmt)?;
@@ -779,7 +1115,8 @@ impl Interpreter {
         Ok(())
     }
 
-    pub fn execute_file(&mut self, path: PathBuf) -> Result<(), String> {
+    /// Executes the file and returns a module that contains everything the file exports
+    pub fn execute_file(&mut self, path: PathBuf) -> Result<Module, String> {
         let mut parser = Parser::new();
 
         let content = std::fs::read_to_string(&path).unwrap();
@@ -792,9 +1129,29 @@ impl Interpreter {
             program.print();
         }
 
-        self.execute(&program)?;
+        self.execute(&program)
+    }
 
-        Ok(())
+    /// Parses and executes the given source in a clean scope. Unlike `execute` this keeps the
+    /// currently loaded program, which makes it possible to run snippets at runtime (e.g. from a
+    /// callback) without breaking the error messages of the main program.
+    pub fn execute_source(&mut self, path: PathBuf, source: &str) -> Result<(), String> {
+        let mut parser = Parser::new();
+
+        parser.set_source(path.clone(), source, 0);
+
+        let program = parser.parse()?;
+
+        let stmts = self.stmts.clone();
+        let prev_source = self.source.clone();
+
+        let result =
+            self.with_clean_state(Scope::default(), Some(path), |i| i.execute(&program));
+
+        self.stmts = stmts;
+        self.source = prev_source;
+
+        result.map(|_| ())
     }
 
     pub fn execute(&mut self, prog: &Program) -> Result<Module, String> {
@@ -862,13 +1219,33 @@ fn create_default_classes() -> HashMap<String, Class> {
             .set_op_impl(Operator::Decrement, |_, this, _| {
                 number!(this).map(|x| x - 1)
             })
+            // floats get truncated
             .add_static_function("from", |_, args| {
                 Ok(match &args[0] {
                     Dynamic::String(x) => x.parse::<Number>().unwrap().into(),
+                    Dynamic::Float(x) => (*x as Number).into(),
                     _ => ().into(),
                 })
             }),
     );
+    classes.push(
+        Class::new("Float")
+            .set_op_impl(Operator::Increment, |_, this, _| {
+                float!(this).map(|x| x + 1.0)
+            })
+            .set_op_impl(Operator::Decrement, |_, this, _| {
+                float!(this).map(|x| x - 1.0)
+            })
+            .add_function("round", |_, this, _| Ok(float!(this)?.round() as Number))
+            .add_function("floor", |_, this, _| Ok(float!(this)?.floor() as Number))
+            .add_function("ceil", |_, this, _| Ok(float!(this)?.ceil() as Number))
+            .add_static_function("from", |_, args| {
+                Ok(match &args[0] {
+                    Dynamic::String(x) => x.parse::<Float>().map(Dynamic::from).unwrap_or_default(),
+                    value => value.to_float().map(Dynamic::from).unwrap_or_default(),
+                })
+            }),
+    );
     classes.push(
         Class::new("String")
             .set_op_impl(Operator::Index, |_, this, args| {
@@ -877,14 +1254,31 @@ fn create_default_classes() -> HashMap<String, Class> {
 
                 Ok(this.chars().skip(idx as usize).next().unwrap_or_default())
             })
+            // strings are indexed by characters instead of bytes, so that multi-byte characters
+            // never get split
             .add_function("len", |_, this, _| {
                 let this = string!(this)?;
-                Ok(this.len() as Number)
+                Ok(this.chars().count() as Number)
+            })
+            .add_function("slice", |_, this, args| {
+                let this = string!(this)?;
+                let len = this.chars().count() as Number;
+                let start = (*number!(&args[0])?).max(0).min(len);
+                let end = match args.get(1) {
+                    Some(end) => (*number!(end)?).max(start).min(len),
+                    None => len,
+                };
+
+                Ok(this
+                    .chars()
+                    .skip(start as usize)
+                    .take((end - start) as usize)
+                    .collect::<String>())
             })
             .add_static_function("from", |_, args| {
                 Ok(match &args[0] {
                     Dynamic::Boolean(x) => x.to_string().into(),
-                    Dynamic::Number(x) => x.to_string().into(),
+                    Dynamic::Number(_) | Dynamic::Float(_) => args[0].to_string().into(),
                     _ => Dynamic::Null,
                 })
             })
@@ -1060,20 +1454,77 @@ fn create_default_classes() -> HashMap<String, Class> {
     classes.into_iter().map(|c| (c.name.clone(), c)).collect()
 }
 
+/// Whether the name matches the pattern, where `*` matches any number of characters and `?`
+/// matches a single one
+fn matches_wildcards(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    // the position in the pattern after the last star and the position in the name it matched
+    let mut star: Option<(usize, usize)> = None;
+    let (mut p, mut n) = (0, 0);
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            // let the last star match one more character and try again
+            _ => match star {
+                Some((after_star, matched)) => {
+                    star = Some((after_star, matched + 1));
+                    p = after_star;
+                    n = matched + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+fn has_named_args(arg: &Expression) -> bool {
+    match &arg.kind {
+        ExpressionKind::ArrayLiteral(items) => items
+            .iter()
+            .any(|item| matches!(item.kind, ExpressionKind::NamedArgument(..))),
+        _ => false,
+    }
+}
+
+/// The value and the name of the method that the callee of a call refers to, like `parts` and
+/// `push` in `parts.push(x)`. Static functions like `Number.from` aren't methods.
+fn method_call(callee: &Expression) -> Option<(&Expression, &str)> {
+    match &callee.kind {
+        ExpressionKind::BinaryOp(this, Operator::Dot, field) => match (&this.kind, &field.kind) {
+            (ExpressionKind::ClassIdentifier(_), _) => None,
+            (_, ExpressionKind::Identifier(name)) | (_, ExpressionKind::ClassIdentifier(name)) => {
+                Some((this, name))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 pub fn create_default_modules() -> HashMap<String, Module> {
     let mut map = HashMap::new();
 
     map.insert(
         "std".into(),
-        Module::new("std").variable(
-            "fs",
-            Module::new("fs").function("read_file", |i, args| {
-                let mut cwd = std::env::current_dir().unwrap();
-                let rel_path = string!(&args[0])?;
-                cwd.push(rel_path);
-                Ok(std::fs::read_to_string(cwd).unwrap())
-            }),
-        ),
+        Module::new("std")
+            .variable(
+                "fs",
+                Module::new("fs")
+                    .function("read_file", |i, args| Ok(i.read_file(string!(&args[0])?)?)),
+            )
+            .variable("json", json::create_module())
+            .variable("time", time::create_module()),
     );
 
     map
@@ -1119,3 +1570,386 @@ pub fn create_default_variables() -> HashMap<String, Dynamic> {
         })
         .build()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{matches_wildcards, Dynamic, Interpreter, Module, Parser};
+    use std::sync::Arc;
+
+    /// Runs the program and returns the value of the variable as a string.
+    fn run(source: &str, name: &str) -> String {
+        let mut parser = Parser::new();
+        parser.set_source("".into(), source, 0);
+        let program = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.execute(&program).unwrap();
+        interpreter.find(name).to_string()
+    }
+
+    /// Like `run`, but with a `nog` module that has a `version` and a nested `fs` module.
+    fn run_with_nog(source: &str, name: &str) -> String {
+        let mut parser = Parser::new();
+        parser.set_source("".into(), source, 0);
+        let program = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.add_module(
+            Module::new("nog")
+                .variable("version", 3)
+                .variable("fs", Module::new("fs").variable("root", "config")),
+        );
+        interpreter.execute(&program).unwrap();
+        interpreter.find(name).to_string()
+    }
+
+    #[test]
+    fn import_module() {
+        let source = "import nog\nvar result = nog.version";
+
+        assert_eq!(run_with_nog(source, "result"), "3");
+    }
+
+    #[test]
+    fn import_sub_module() {
+        let source = "import nog.fs\nvar result = fs.root";
+
+        assert_eq!(run_with_nog(source, "result"), "config");
+    }
+
+    #[test]
+    fn import_names_of_module() {
+        let source = "import { version } from nog\nvar result = version";
+
+        assert_eq!(run_with_nog(source, "result"), "3");
+    }
+
+    #[test]
+    fn recursive_function() {
+        let source = r#"
+fn fact(n) {
+    if n < 2 {
+        return 1
+    }
+    return n * fact(n - 1)
+}
+var result = fact(5)
+"#;
+
+        assert_eq!(run(source, "result"), "120");
+    }
+
+    #[test]
+    fn functions_share_their_body() {
+        let source = r#"
+var render = () => {
+    return [1, 2].map(x => x * 2)
+}
+"#;
+        let mut parser = Parser::new();
+        parser.set_source("".into(), source, 0);
+        let program = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.execute(&program).unwrap();
+
+        let body = |value: Dynamic| match value {
+            Dynamic::Function { body, .. } => body,
+            x => panic!("Expected a function, but got {}", x),
+        };
+
+        let f = interpreter.find("render");
+        let result = f.clone().as_fn().unwrap().invoke(&mut interpreter, vec![]);
+
+        assert_eq!(result.unwrap().as_array().unwrap().len(), 2);
+        assert!(Arc::ptr_eq(&body(f), &body(interpreter.find("render"))));
+    }
+
+    #[test]
+    fn closure_mutates_captured_variable() {
+        let source = r#"
+var count = 0
+var inc = by => {
+    count += by
+}
+inc(1)
+inc(2)
+"#;
+
+        assert_eq!(run(source, "count"), "3");
+    }
+
+    #[test]
+    fn closure_sees_later_changes() {
+        let source = r#"
+var x = 1
+var get = () => {
+    return x
+}
+x = 2
+var result = get()
+"#;
+
+        assert_eq!(run(source, "result"), "2");
+    }
+
+    #[test]
+    fn closure_keeps_its_own_state() {
+        let source = r#"
+fn counter() {
+    var count = 0
+    return () => {
+        count += 1
+        return count
+    }
+}
+var next = counter()
+var other = counter()
+next()
+other()
+var result = next()
+"#;
+
+        assert_eq!(run(source, "result"), "2");
+    }
+
+    #[test]
+    fn blocks_get_their_own_frame() {
+        let source = r#"
+var total = 0
+var fns = []
+var i = 0
+while i < 3 {
+    var j = i
+    if j == 1 {
+        var inner = 10
+        total += inner
+    }
+    fns.push(() => {
+        return j
+    })
+    total += j
+    i += 1
+}
+var captured = fns[0]() + fns[2]()
+"#;
+
+        assert_eq!(run(source, "total"), "13");
+        assert_eq!(run(source, "captured"), "2");
+        assert_eq!(run(source, "inner"), "null");
+        assert_eq!(run(source, "j"), "null");
+    }
+
+    #[test]
+    fn methods_of_builtin_values() {
+        let source = r#"
+var parts = []
+parts.push("a")
+parts.push("bc".len())
+var push = parts.push
+push(3)
+var count = parts.len()
+var object = #{ greet: (name) => {
+    return "hi " + name
+} }
+var greeting = object.greet("nog")
+"#;
+
+        assert_eq!(run(source, "count"), "3");
+        assert_eq!(run(source, "greeting"), "hi nog");
+    }
+
+    #[test]
+    fn comments_inside_of_literals() {
+        let source = r#"
+/* settings that
+   are off for now */
+var settings = #{
+    // gap: 10,
+    width: 2, /* (unbalanced */
+    height: 3 // ]
+}
+var sizes = [
+    1, // first
+    /* 2, */
+    3
+]
+var result = settings.width * settings.height + sizes[1] // 6 + 3
+"#;
+
+        assert_eq!(run(source, "result"), "9");
+    }
+
+    #[test]
+    fn unary_operators() {
+        let source = r#"
+var x = 3
+var negative = -x
+var done = false
+var result = 0
+if !done && negative == -3 {
+    result = -negative * 2
+}
+"#;
+
+        assert_eq!(run(source, "result"), "6");
+    }
+
+    #[test]
+    fn compound_assignment_of_fields() {
+        let source = r#"
+var counter = #{ count: 1 }
+counter.count += 4
+counter.count *= 3
+counter.count -= 5
+var result = counter.count
+"#;
+
+        assert_eq!(run(source, "result"), "10");
+    }
+
+    #[test]
+    fn logical_operators_short_circuit() {
+        let source = r#"
+var calls = 0
+fn check(value) {
+    calls += 1
+    return value
+}
+var x = 0
+var safe = x != 0 && 10 / x > 2
+var either = check(true) || check(false)
+var both = check(false) && check(true)
+var grouped = true || false && false
+"#;
+
+        assert_eq!(run(source, "safe"), "false");
+        assert_eq!(run(source, "either"), "true");
+        assert_eq!(run(source, "both"), "false");
+        assert_eq!(run(source, "grouped"), "true");
+        assert_eq!(run(source, "calls"), "2");
+    }
+
+    #[test]
+    fn conditional_expressions() {
+        let source = r#"
+var count = 3
+var label = count == 1 ? "window" : "windows"
+var size = count < 2 ? "small" : count < 5 ? "medium" : "large"
+var text = () => count > 0 ? "" + count + " " + label : "empty"
+var result = text() + ", " + size
+"#;
+
+        assert_eq!(run(source, "result"), "3 windows, medium");
+    }
+
+    #[test]
+    fn else_if_chain() {
+        let source = r#"
+var x = 2
+var result = ""
+if x == 1 {
+    result = "one"
+} else if x == 2 {
+    result = "two"
+} else {
+    result = "many"
+}
+"#;
+
+        assert_eq!(run(source, "result"), "two");
+    }
+
+    #[test]
+    fn endless_loop_gets_stopped() {
+        let source = r#"
+fn spin() {
+    while true {}
+}
+spin()
+"#;
+        let mut parser = Parser::new();
+        parser.set_source("".into(), source, 0);
+        let program = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.limits.max_instructions = Some(1000);
+
+        let err = interpreter.execute(&program).unwrap_err();
+
+        assert!(err.contains("more than 1000 instructions"));
+        assert_eq!(interpreter.scopes.len(), 1);
+    }
+
+    #[test]
+    fn wildcards_match_file_names() {
+        assert!(matches_wildcards("*.ns", "keybindings.ns"));
+        assert!(matches_wildcards("a*b*c", "abbbc"));
+        assert!(matches_wildcards("??.ns", "10.ns"));
+        assert!(!matches_wildcards("??.ns", "1.ns"));
+        assert!(!matches_wildcards("*.ns", "rules.ns.bak"));
+    }
+
+    #[test]
+    fn include_executes_files_in_order() {
+        let dir = std::env::temp_dir().join("nog_include_test");
+        let conf_dir = dir.join("conf.d");
+        std::fs::create_dir_all(&conf_dir).unwrap();
+        std::fs::write(conf_dir.join("2_bar.ns"), "order = order + \"bar,\"").unwrap();
+        std::fs::write(conf_dir.join("1_keys.ns"), "order = order + \"keys,\"").unwrap();
+        std::fs::write(conf_dir.join("notes.txt"), "not nogscript").unwrap();
+        std::fs::write(
+            dir.join("config.ns"),
+            "var order = \"\"\ninclude \"conf.d/*.ns\"",
+        )
+        .unwrap();
+
+        let mut interpreter = Interpreter::new();
+        interpreter.execute_file(dir.join("config.ns")).unwrap();
+
+        assert_eq!(interpreter.find("order").to_string(), "keys,bar,");
+        assert_eq!(interpreter.included_files.len(), 2);
+    }
+
+    #[test]
+    fn read_file_stays_inside_the_script_directory() {
+        let dir = std::env::temp_dir().join("nog_read_file_test");
+        let script_dir = dir.join("config");
+        std::fs::create_dir_all(&script_dir).unwrap();
+        std::fs::write(script_dir.join("names.txt"), "main").unwrap();
+        std::fs::write(dir.join("secret.txt"), "secret").unwrap();
+        std::fs::write(script_dir.join("config.ns"), "").unwrap();
+
+        let mut interpreter = Interpreter::new();
+        interpreter
+            .execute_file(script_dir.join("config.ns"))
+            .unwrap();
+
+        assert_eq!(interpreter.read_file("names.txt").ok(), Some("main".into()));
+        assert!(interpreter.read_file("../secret.txt").is_err());
+        assert!(interpreter.read_file("missing.txt").is_err());
+    }
+
+    #[test]
+    fn floats_promote_numbers() {
+        assert_eq!(run("var x = 1.5 + 2", "x"), "3.5");
+        assert_eq!(run("var x = 2 * 0.25", "x"), "0.5");
+        assert_eq!(run("var x = 7 / 2", "x"), "3");
+        assert_eq!(run("var x = 7 / 2.0", "x"), "3.5");
+        assert_eq!(run("var x = 4.0 - 1", "x"), "3.0");
+        assert_eq!(run("var x = -1.5", "x"), "-1.5");
+        assert_eq!(run("var x = \"v\" + 1.5", "x"), "v1.5");
+        assert_eq!(run("var x = 1.5 | 1", "x"), "null");
+    }
+
+    #[test]
+    fn floats_compare_with_numbers() {
+        assert_eq!(run("var x = 1 == 1.0", "x"), "true");
+        assert_eq!(run("var x = 1.5 > 1", "x"), "true");
+        assert_eq!(run("var x = 2 <= 1.5", "x"), "false");
+    }
+
+    #[test]
+    fn floats_convert_to_numbers() {
+        assert_eq!(run("var x = 2.5.round()", "x"), "3");
+        assert_eq!(run("var x = 2.5.floor()", "x"), "2");
+        assert_eq!(run("var x = Number.from(-2.7)", "x"), "-2");
+        assert_eq!(run("var x = Float.from(\"0.5\") + Float.from(1)", "x"), "1.5");
+    }
+}
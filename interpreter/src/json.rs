@@ -0,0 +1,138 @@
+//! The `std.json` module. JSON numbers that are whole and fit into 32 bits become numbers, every
+//! other one becomes a float.
+use crate::{
+    dynamic::Dynamic,
+    module::Module,
+    runtime_error::RuntimeError,
+};
+use serde_json::{Map, Number, Value};
+use std::{collections::HashMap, convert::TryFrom, sync::Arc};
+
+fn from_json(value: Value) -> Dynamic {
+    match value {
+        Value::Null => Dynamic::Null,
+        Value::Bool(x) => Dynamic::Boolean(x),
+        Value::Number(x) => match x.as_i64().map(i32::try_from) {
+            Some(Ok(x)) => Dynamic::Number(x),
+            _ => Dynamic::Float(x.as_f64().unwrap_or_default()),
+        },
+        Value::String(x) => Dynamic::String(x),
+        Value::Array(items) => Dynamic::new_array(items.into_iter().map(from_json).collect()),
+        Value::Object(fields) => Dynamic::new_object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key, from_json(value)))
+                .collect::<HashMap<_, _>>(),
+        ),
+    }
+}
+
+/// Converts the value into JSON. `parents` contains the addresses of the arrays and objects that
+/// are currently being converted, which is how values that contain themselves get detected.
+fn to_json(value: &Dynamic, parents: &mut Vec<usize>) -> Result<Value, String> {
+    let enter = |parents: &mut Vec<usize>, addr: usize| {
+        if parents.contains(&addr) {
+            Err("Can't convert a value that contains itself to JSON".to_string())
+        } else {
+            parents.push(addr);
+            Ok(())
+        }
+    };
+
+    let json = match value {
+        Dynamic::Null => Value::Null,
+        Dynamic::Boolean(x) => Value::Bool(*x),
+        Dynamic::Number(x) => Value::Number(Number::from(*x)),
+        // JSON can't represent NaN or infinity
+        Dynamic::Float(x) => Number::from_f64(*x).map_or(Value::Null, Value::Number),
+        Dynamic::String(x) => Value::String(x.clone()),
+        Dynamic::Array(items) => {
+            enter(parents, Arc::as_ptr(items) as usize)?;
+            let items = items
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|item| to_json(item, parents))
+                .collect::<Result<Vec<_>, _>>()?;
+            parents.pop();
+            Value::Array(items)
+        }
+        Dynamic::Object(fields) | Dynamic::ClassInstance(_, fields) => {
+            enter(parents, Arc::as_ptr(fields) as usize)?;
+            let fields = fields
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), to_json(value, parents)?)))
+                .collect::<Result<Map<_, _>, String>>()?;
+            parents.pop();
+            Value::Object(fields)
+        }
+        x => {
+            return Err(format!(
+                "Can't convert a value of type {} to JSON",
+                x.type_name()
+            ))
+        }
+    };
+
+    Ok(json)
+}
+
+fn parse(text: &str) -> Result<Dynamic, String> {
+    serde_json::from_str(text)
+        .map(from_json)
+        .map_err(|e| format!("Failed to parse JSON: {}", e))
+}
+
+fn stringify(value: &Dynamic, pretty: bool) -> Result<String, String> {
+    let json = to_json(value, &mut Vec::new())?;
+
+    if pretty {
+        serde_json::to_string_pretty(&json)
+    } else {
+        serde_json::to_string(&json)
+    }
+    .map_err(|e| e.to_string())
+}
+
+pub fn create_module() -> Module {
+    Module::new("json")
+        .function("parse", |_, args| Ok(parse(string!(&args[0])?)?))
+        .function("stringify", |_, args| {
+            let pretty = args.get(1).map(|x| x.is_true()).unwrap_or(false);
+
+            Ok(stringify(&args[0], pretty)?)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_stringify() {
+        let value = parse(r#"{"name": "nog", "tags": [1, 2.7, true, null]}"#).unwrap();
+
+        assert_eq!(value.get_field("name").as_str().unwrap(), "nog");
+        assert_eq!(
+            stringify(&value.get_field("tags"), false).unwrap(),
+            "[1,2.7,true,null]"
+        );
+        assert!(parse("{").is_err());
+    }
+
+    #[test]
+    fn stringify_rejects_cycles() {
+        let items = Dynamic::new_array(vec![Dynamic::Number(1)]);
+        let value = Dynamic::new_array(vec![items.clone(), items.clone()]);
+
+        assert_eq!(stringify(&value, false).unwrap(), "[[1],[1]]");
+
+        if let Dynamic::Array(inner) = &items {
+            inner.lock().unwrap().push(value.clone());
+        }
+
+        assert!(stringify(&value, false).is_err());
+    }
+}
@@ -9,14 +9,19 @@ mod expr_parser;
 mod expression;
 mod formatter;
 mod function;
+mod gc;
 mod interpreter;
+mod json;
 mod lexer;
+mod limits;
 mod method;
 mod module;
 mod operator;
 mod parser;
 mod runtime_error;
 mod scope;
+pub mod testing;
+mod time;
 mod token;
 
 pub use crate::ast::{AstKind, AstNode};
@@ -25,5 +30,7 @@ pub use crate::parser::Parser;
 pub use class::Class;
 pub use dynamic::Dynamic;
 pub use function::Function;
+pub use gc::{collect_garbage, live_containers};
+pub use limits::{Limit, Limits};
 pub use module::Module;
 pub use runtime_error::{RuntimeError, RuntimeResult};
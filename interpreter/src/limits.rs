@@ -0,0 +1,131 @@
+//! Stops callbacks that run for too long, so that a mistake like an endless loop in a bar
+//! component can't freeze the host.
+//!
+//! The budget of a callback starts when the outermost function gets called and covers every
+//! function it calls in turn. Code that runs outside of a function, like the top level of a file,
+//! is never limited.
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Limits {
+    /// How many statements and loop iterations a callback can execute
+    pub max_instructions: Option<usize>,
+    /// How long a callback can run
+    pub max_time: Option<Duration>,
+    /// How many function calls can be nested inside of each other
+    pub max_depth: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Limit {
+    Instructions(usize),
+    Time(Duration),
+    Depth(usize),
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Limit::Instructions(x) => write!(f, "executed more than {} instructions", x),
+            Limit::Time(x) => write!(f, "ran for more than {:?}", x),
+            Limit::Depth(x) => write!(f, "nested more than {} function calls", x),
+        }
+    }
+}
+
+/// How much of its budget the current callback used up
+#[derive(Debug, Clone, Copy)]
+pub struct Usage {
+    pub depth: usize,
+    pub instructions: usize,
+    pub started: Instant,
+}
+
+impl Default for Usage {
+    fn default() -> Self {
+        Self {
+            depth: 0,
+            instructions: 0,
+            started: Instant::now(),
+        }
+    }
+}
+
+impl Usage {
+    /// Enters a function. The outermost function starts a new budget.
+    pub fn enter(&mut self, limits: &Limits) -> Result<(), Limit> {
+        if self.depth == 0 {
+            *self = Self::default();
+        }
+
+        self.depth += 1;
+
+        match limits.max_depth {
+            Some(max) if self.depth > max => Err(Limit::Depth(max)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn leave(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+    }
+
+    /// Counts an instruction of the current callback
+    pub fn step(&mut self, limits: &Limits) -> Result<(), Limit> {
+        if self.depth == 0 {
+            return Ok(());
+        }
+
+        self.instructions += 1;
+
+        if let Some(max) = limits
+            .max_instructions
+            .filter(|max| self.instructions > *max)
+        {
+            return Err(Limit::Instructions(max));
+        }
+
+        match limits.max_time {
+            Some(max) if self.started.elapsed() > max => Err(Limit::Time(max)),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_covers_the_outermost_call() {
+        let limits = Limits {
+            max_instructions: Some(3),
+            max_depth: Some(2),
+            ..Default::default()
+        };
+        let mut usage = Usage::default();
+
+        // code outside of a function is never limited
+        for _ in 0..10 {
+            assert_eq!(usage.step(&limits), Ok(()));
+        }
+
+        assert_eq!(usage.enter(&limits), Ok(()));
+        assert_eq!(usage.step(&limits), Ok(()));
+        assert_eq!(usage.enter(&limits), Ok(()));
+        assert_eq!(usage.enter(&limits), Err(Limit::Depth(2)));
+        usage.leave();
+        assert_eq!(usage.step(&limits), Ok(()));
+        usage.leave();
+        assert_eq!(usage.step(&limits), Ok(()));
+        assert_eq!(usage.step(&limits), Err(Limit::Instructions(3)));
+        usage.leave();
+
+        // the next callback gets a fresh budget
+        assert_eq!(usage.enter(&limits), Ok(()));
+        assert_eq!(usage.step(&limits), Ok(()));
+    }
+}
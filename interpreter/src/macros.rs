@@ -21,6 +21,14 @@ macro_rules! number {
     };
 }
 
+/// Converts the given value into a float
+#[macro_export]
+macro_rules! float {
+    ($enum: expr) => {
+        cast!($enum, Dynamic::Float, "Float")
+    };
+}
+
 /// Converts the given value into an object
 #[macro_export]
 macro_rules! object {
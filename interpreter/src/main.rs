@@ -12,14 +12,18 @@ mod expr_parser;
 mod expression;
 mod formatter;
 mod function;
+mod gc;
 mod interpreter;
+mod json;
 mod lexer;
+mod limits;
 mod method;
 mod module;
 mod operator;
 mod parser;
 mod runtime_error;
 mod scope;
+mod time;
 mod token;
 
 pub fn main() {
@@ -50,6 +54,11 @@ pub fn main() {
             if let Err(e) = interpreter.execute(&program) {
                 println!("RUNTIME ERROR: {}", e);
             };
+            println!(
+                "{} containers alive, {} freed by collecting cycles",
+                gc::live_containers(),
+                gc::collect_garbage()
+            );
         }
         Err(e) => {
             println!("PARSE ERROR: {}", e);
@@ -6,7 +6,7 @@ use super::{
 
 #[derive(Clone)]
 pub struct Method {
-    name: String,
+    name: Arc<str>,
     inner: Arc<dyn Fn(&mut Interpreter, Dynamic, Vec<Dynamic>) -> RuntimeResult + Send + Sync>,
 }
 
@@ -20,7 +20,7 @@ impl Method {
         T: Fn(&mut Interpreter, Dynamic, Vec<Dynamic>) -> RuntimeResult + 'static + Send + Sync,
     {
         Method {
-            name: name.to_string(),
+            name: name.into(),
             inner: Arc::new(f),
         }
     }
@@ -28,7 +28,7 @@ impl Method {
     pub fn into_dynamic(&self, this: Dynamic) -> Dynamic {
         let f = self.inner.clone();
         Dynamic::RustFunction {
-            name: self.name.clone(),
+            name: self.name.to_string(),
             scope: None,
             callback: Arc::new(move |i, args| (f)(i, this.clone(), args)),
         }
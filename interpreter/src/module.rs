@@ -25,6 +25,15 @@ impl Module {
         }
     }
 
+    /// Returns the exported variable, function or class with the given name
+    pub fn get_export(&self, name: &str) -> Option<Dynamic> {
+        self.variables
+            .get(name)
+            .cloned()
+            .or_else(|| self.functions.get(name).map(|x| x.clone().into()))
+            .or_else(|| self.classes.get(name).map(|x| x.clone().into()))
+    }
+
     pub fn variable<T: Into<Dynamic>>(mut self, name: &str, value: T) -> Self {
         let value = value.into();
         self.variables.insert(name.to_string(), value.clone());
@@ -23,6 +23,11 @@ pub enum Operator {
     Not,
     And,
     Or,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
 }
 
 impl Operator {
@@ -49,6 +54,11 @@ impl Operator {
             "!" => Operator::Not,
             "&&" => Operator::And,
             "||" => Operator::Or,
+            "&" => Operator::BitAnd,
+            "|" => Operator::BitOr,
+            "^" => Operator::BitXor,
+            "<<" => Operator::ShiftLeft,
+            ">>" => Operator::ShiftRight,
             _ => return None,
         })
     }
@@ -76,6 +86,11 @@ impl Operator {
             Operator::Not => "not",
             Operator::And => "and",
             Operator::Or => "or",
+            Operator::BitAnd => "bit_and",
+            Operator::BitOr => "bit_or",
+            Operator::BitXor => "bit_xor",
+            Operator::ShiftLeft => "shift_left",
+            Operator::ShiftRight => "shift_right",
         }
         .into()
     }
@@ -103,6 +118,11 @@ impl Operator {
             Operator::Not => "!",
             Operator::And => "&&",
             Operator::Or => "||",
+            Operator::BitAnd => "&",
+            Operator::BitOr => "|",
+            Operator::BitXor => "^",
+            Operator::ShiftLeft => "<<",
+            Operator::ShiftRight => ">>",
         }
         .into()
     }
@@ -124,6 +144,11 @@ impl From<TokenKind> for Operator {
             TokenKind::EQ => Operator::Equal,
             TokenKind::And => Operator::And,
             TokenKind::Or => Operator::Or,
+            TokenKind::Ampersand => Operator::BitAnd,
+            TokenKind::Bar => Operator::BitOr,
+            TokenKind::Caret => Operator::BitXor,
+            TokenKind::ShiftLeft => Operator::ShiftLeft,
+            TokenKind::ShiftRight => Operator::ShiftRight,
             TokenKind::GT => Operator::GreaterThan,
             TokenKind::LT => Operator::LessThan,
             TokenKind::GTE => Operator::GreaterThanOrEqual,
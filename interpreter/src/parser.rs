@@ -4,7 +4,8 @@ use super::{
     ast::AstKind,
     ast::AstNode,
     ast::ClassMember,
-    expr_parser::ExprParser,
+    ast::Parameter,
+    expr_parser::{unescape, ExprParser},
     expression::{Expression, ExpressionKind},
     interpreter::Program,
     lexer::Lexer,
@@ -90,6 +91,7 @@ impl<'a> Parser<'a> {
         let mut tokens = Vec::new();
         let mut paren_depth = 0;
         let mut curly_depth = 0;
+        let mut bracket_depth = 0;
         let mut previous_token: Option<Token> = prev_token.clone();
 
         self.lexer.reset_peek();
@@ -102,6 +104,7 @@ impl<'a> Parser<'a> {
                 | TokenKind::While
                 | TokenKind::Export
                 | TokenKind::Import
+                | TokenKind::Include
                 | TokenKind::Return
                 | TokenKind::ElseIf
                 | TokenKind::If => {
@@ -110,11 +113,19 @@ impl<'a> Parser<'a> {
                     }
                 }
                 TokenKind::Comment => {
-                    if !tokens.is_empty() && paren_depth == 0 && curly_depth == 0 {
+                    if !tokens.is_empty()
+                        && paren_depth == 0
+                        && curly_depth == 0
+                        && bracket_depth == 0
+                    {
                         break;
                     }
+                    // a comment inside of the expression (e.g. between the fields of an object
+                    // literal) isn't part of it
+                    self.lexer.next();
+                    continue;
                 }
-                TokenKind::NewLine => {
+                TokenKind::NewLine | TokenKind::BlockComment => {
                     self.lexer.next();
                     continue;
                 }
@@ -123,6 +134,8 @@ impl<'a> Parser<'a> {
                         break;
                     }
                 }
+                TokenKind::LBracket => bracket_depth += 1,
+                TokenKind::RBracket => bracket_depth -= 1,
                 TokenKind::LParan => paren_depth += 1,
                 TokenKind::RParan => {
                     paren_depth -= 1;
@@ -224,6 +237,7 @@ impl<'a> Parser<'a> {
                     }
                 }
                 TokenKind::LParan | TokenKind::LBracket | TokenKind::LCurly => depth += 1,
+                TokenKind::Comment | TokenKind::BlockComment | TokenKind::NewLine => continue,
                 _ => {}
             }
             arg_tokens.push(next);
@@ -232,6 +246,42 @@ impl<'a> Parser<'a> {
         Ok(args)
     }
 
+    /// Parses the parameters of a function definition. A parameter can have a default value
+    /// (`width = 8`), which can refer to the parameters before it.
+    fn parse_params(&mut self) -> Result<Vec<Parameter>, ParseError> {
+        let mut params: Vec<Parameter> = Vec::new();
+
+        for arg in self.parse_args()? {
+            let param = match arg.kind {
+                ExpressionKind::Identifier(name) => Parameter::new(&name),
+                ExpressionKind::BinaryOp(lhs, Operator::Assign, default) => match lhs.kind {
+                    ExpressionKind::Identifier(name) => Parameter {
+                        name,
+                        default: Some(*default),
+                    },
+                    _ => return Err(ParseError::Unknown(lhs.location)),
+                },
+                _ => return Err(ParseError::Unknown(arg.location)),
+            };
+
+            if params.iter().any(|p| p.name == param.name) {
+                return Err(format!("Duplicate parameter {}", param.name).into());
+            }
+
+            if param.default.is_none() && params.iter().any(|p| p.default.is_some()) {
+                return Err(format!(
+                    "Parameter {} needs a default value, because a parameter before it has one",
+                    param.name
+                )
+                .into());
+            }
+
+            params.push(param);
+        }
+
+        Ok(params)
+    }
+
     fn advance(&mut self) -> Option<Token> {
         self.lexer.next().map(|t| {
             self.tokens.push(t.clone());
@@ -277,7 +327,7 @@ impl<'a> Parser<'a> {
         self.consume(TokenKind::Fn)?;
         let name = self.consume(TokenKind::Identifier)?;
         self.consume(TokenKind::LParan)?;
-        let args = self.parse_args()?;
+        let params = self.parse_params()?;
         let body = if is_extern {
             Vec::new()
         } else {
@@ -285,11 +335,7 @@ impl<'a> Parser<'a> {
             self.parse_stmts()?
         };
         Ok(AstNode::new(
-            AstKind::FunctionDefinition(
-                self.text(&name).into(),
-                args.iter().map(|a| a.to_string()).collect(),
-                body,
-            ),
+            AstKind::FunctionDefinition(self.text(&name).into(), params, body),
             self.end_group(),
         ))
     }
@@ -300,15 +346,11 @@ impl<'a> Parser<'a> {
         self.consume(TokenKind::Fn)?;
         let name = self.consume(TokenKind::Identifier)?;
         self.consume(TokenKind::LParan)?;
-        let args = self.parse_args()?;
+        let params = self.parse_params()?;
         self.consume(TokenKind::LCurly)?;
         let body = self.parse_stmts()?;
         Ok(AstNode::new(
-            AstKind::StaticFunctionDefinition(
-                self.text(&name).into(),
-                args.iter().map(|a| a.to_string()).collect(),
-                body,
-            ),
+            AstKind::StaticFunctionDefinition(self.text(&name).into(), params, body),
             self.end_group(),
         ))
     }
@@ -352,6 +394,18 @@ impl<'a> Parser<'a> {
                 }
                 TokenKind::Else => {
                     self.consume(TokenKind::Else)?;
+
+                    // `else if` is the same as `elif`
+                    if let Some(TokenKind::If) = self.lexer.peek().map(|x| x.0.clone()) {
+                        let prev_token = self.consume(TokenKind::If)?;
+                        let cond = self.parse_expr(Some(prev_token))?;
+                        self.consume(TokenKind::LCurly)?;
+                        let block = self.parse_stmts()?;
+                        branches.push((cond, block));
+                        continue;
+                    }
+
+                    self.lexer.reset_peek();
                     let cond = Expression::new(ExpressionKind::BooleanLiteral("true".into()), 0..0);
                     self.consume(TokenKind::LCurly)?;
                     let block = self.parse_stmts()?;
@@ -439,16 +493,21 @@ impl<'a> Parser<'a> {
             self.consume(TokenKind::LCurly)?;
             let body = self.parse_stmts()?;
             body.iter()
-                .map(|ast| match &ast.kind {
-                    AstKind::VariableDefinition(a, b) => ClassMember::Field(a.clone(), b.clone()),
+                .filter_map(|ast| match &ast.kind {
+                    AstKind::VariableDefinition(a, b) => {
+                        Some(ClassMember::Field(a.clone(), b.clone()))
+                    }
                     AstKind::FunctionDefinition(a, b, c) => {
-                        ClassMember::Function(a.clone(), b.clone(), c.clone())
+                        Some(ClassMember::Function(a.clone(), b.clone(), c.clone()))
                     }
                     AstKind::StaticFunctionDefinition(a, b, c) => {
-                        ClassMember::StaticFunction(a.clone(), b.clone(), c.clone())
+                        Some(ClassMember::StaticFunction(a.clone(), b.clone(), c.clone()))
                     }
                     AstKind::OperatorImplementation(a, b, c) => {
-                        ClassMember::Operator(a.clone(), b.clone(), c.clone())
+                        Some(ClassMember::Operator(a.clone(), b.clone(), c.clone()))
+                    }
+                    AstKind::Comment(_) | AstKind::BlockComment(_) | AstKind::Documentation(_) => {
+                        None
                     }
                     _ => panic!("not allowed"),
                 })
@@ -484,6 +543,13 @@ impl<'a> Parser<'a> {
     fn parse_import_statement(&mut self) -> Result<AstNode, ParseError> {
         self.start_group();
         self.consume(TokenKind::Import)?;
+
+        let names = match self.lexer.peek() {
+            Some(Token(TokenKind::LCurly, _)) => Some(self.parse_import_names()?),
+            _ => None,
+        };
+        self.lexer.reset_peek();
+
         let mut parts = Vec::new();
 
         while let Some(token) = self.lexer.peek() {
@@ -500,11 +566,69 @@ impl<'a> Parser<'a> {
         let tokens = self.end_group();
 
         Ok(AstNode::new(
-            AstKind::ImportStatement(parts.join("")),
+            AstKind::ImportStatement(parts.join(""), names),
             tokens,
         ))
     }
 
+    fn parse_include_statement(&mut self) -> Result<AstNode, ParseError> {
+        self.start_group();
+        self.consume(TokenKind::Include)?;
+
+        let token = self.consume(TokenKind::StringLiteral)?;
+        let text = self.text(&token);
+        let pattern = unescape(&text[1..text.len() - 1]);
+
+        Ok(AstNode::new(
+            AstKind::IncludeStatement(pattern),
+            self.end_group(),
+        ))
+    }
+
+    /// Parses the `{ a, B } from` part of a selective import
+    fn parse_import_names(&mut self) -> Result<Vec<String>, ParseError> {
+        self.consume(TokenKind::LCurly)?;
+        let mut names: Vec<String> = Vec::new();
+
+        loop {
+            let token = self.consume_either(vec![
+                TokenKind::Identifier,
+                TokenKind::ClassIdentifier,
+                TokenKind::RCurly,
+            ])?;
+
+            if token.0 == TokenKind::RCurly {
+                break;
+            }
+
+            let name = self.text(&token).to_string();
+
+            if names.contains(&name) {
+                return Err(format!("Duplicate import {}", name).into());
+            }
+
+            names.push(name);
+
+            let separator = self.consume_either(vec![TokenKind::Comma, TokenKind::RCurly])?;
+
+            if separator.0 == TokenKind::RCurly {
+                break;
+            }
+        }
+
+        if names.is_empty() {
+            return Err(String::from("Expected at least one name to import").into());
+        }
+
+        let token = self.consume(TokenKind::Identifier)?;
+
+        if self.text(&token) != "from" {
+            return Err(format!("Expected from, but found {}", self.text(&token)).into());
+        }
+
+        Ok(names)
+    }
+
     fn parse_extern_statement(&mut self) -> Result<AstNode, ParseError> {
         self.start_group();
         self.consume(TokenKind::Extern)?;
@@ -542,6 +666,7 @@ impl<'a> Parser<'a> {
                 TokenKind::Var => self.parse_var_definition(false)?,
                 TokenKind::Class => self.parse_class_definition(false)?,
                 TokenKind::Fn => self.parse_fn_definition(false)?,
+                TokenKind::Import => self.parse_import_statement()?,
                 TokenKind::Identifier => AstNode::new(
                     AstKind::Expression(Expression::new(
                         ExpressionKind::Identifier(self.text(&token).to_string()),
@@ -587,39 +712,36 @@ impl<'a> Parser<'a> {
         self.start_group();
         let mut lines = Vec::new();
 
-        self.start_group();
-
-        let token = self.consume(TokenKind::Comment).unwrap();
+        loop {
+            let token = self.consume(TokenKind::Comment)?;
+            lines.push(self.text(&token)[2..].trim_end().to_string());
 
-        let mut line = (token.1.end, 0);
+            // comments on consecutive lines belong together
+            self.lexer.reset_peek();
+            let next = self.lexer.peek().map(|x| x.0.clone());
+            let after_next = self.lexer.peek().map(|x| x.0.clone());
+            self.lexer.reset_peek();
 
-        while let Some(token) = self.lexer.next() {
-            match token.0 {
-                TokenKind::NewLine => {
-                    lines.push(if line.1 < line.0 {
-                        "".into()
-                    } else {
-                        self.text_at(line.0..line.1).to_string()
-                    });
-                    if let Some(TokenKind::Comment) = self.lexer.peek().map(|x| x.0.clone()) {
-                        let token = self.lexer.next().unwrap();
-                        line = (token.1.end, 0);
-                    } else {
-                        self.lexer.reset_peek();
-                        break;
-                    }
-                }
-                _ => {
-                    if token.1.end > line.1 {
-                        line.1 = token.1.end;
-                    }
-                }
+            match (next, after_next) {
+                (Some(TokenKind::NewLine), Some(TokenKind::Comment)) => {}
+                _ => break,
             }
         }
 
         Ok(AstNode::new(AstKind::Comment(lines), self.end_group()))
     }
 
+    fn parse_block_comment(&mut self) -> Result<AstNode, ParseError> {
+        self.start_group();
+        let token = self.consume(TokenKind::BlockComment)?;
+        let text = self.text(&token);
+
+        Ok(AstNode::new(
+            AstKind::BlockComment(text[2..text.len() - 2].to_string()),
+            self.end_group(),
+        ))
+    }
+
     fn parse_documentation(&mut self) -> Result<AstNode, ParseError> {
         self.start_group();
         let mut lines = Vec::new();
@@ -712,6 +834,7 @@ impl<'a> Parser<'a> {
         while let Some(token) = self.lexer.peek() {
             let ast = match token.0 {
                 TokenKind::Comment => self.parse_comment(),
+                TokenKind::BlockComment => self.parse_block_comment(),
                 TokenKind::TripleSlash => self.parse_documentation(),
                 TokenKind::Return => self.parse_return_statement(),
                 TokenKind::Break => Ok(AstNode::new(AstKind::BreakStatement, token.1.clone())),
@@ -725,6 +848,7 @@ impl<'a> Parser<'a> {
                 TokenKind::Fn => self.parse_fn_definition(false),
                 TokenKind::Static => self.parse_static_fn_definition(),
                 TokenKind::Import => self.parse_import_statement(),
+                TokenKind::Include => self.parse_include_statement(),
                 TokenKind::Extern => self.parse_extern_statement(),
                 TokenKind::Export => self.parse_export_statement(),
                 TokenKind::If => self.parse_if(),
@@ -814,6 +938,17 @@ impl<'a> Parser<'a> {
         None
     }
 
+    /// Describes where the token is, including the file if the source came from one
+    fn at(&self, location: &std::ops::Range<usize>) -> String {
+        let (line, col) = self.position(location).unwrap();
+
+        if self.path.as_os_str().is_empty() {
+            format!("at {},{}", line, col)
+        } else {
+            format!("at {},{} in {}", line, col, self.path.display())
+        }
+    }
+
     pub fn set_source(&mut self, path: PathBuf, source: &'a str, offset: usize) {
         self.path = path;
         self.source = source;
@@ -831,29 +966,22 @@ impl<'a> Parser<'a> {
                 stmts,
             }),
             Err(e) => match e {
-                ParseError::UnexpectedOperator(token) => {
-                    let (line, col) = self.position(&token.1).unwrap();
-                    Err(format!(
-                        "Encountered unexpected operator '{}' at {},{}",
-                        self.text(&token),
-                        line,
-                        col
-                    ))
-                }
+                ParseError::UnexpectedOperator(token) => Err(format!(
+                    "Encountered unexpected operator '{}' {}",
+                    self.text(&token),
+                    self.at(&token.1)
+                )),
                 ParseError::Raw(msg) => Err(msg),
                 ParseError::Unknown(loc) => {
-                    let (line, col) = self.position(&loc).unwrap();
-                    Err(format!("Encountered an unknown error at {},{}", line, col))
+                    Err(format!("Encountered an unknown error {}", self.at(&loc)))
                 }
                 ParseError::UnexpectedToken { expected, actual } => {
                     if let Some(actual) = actual {
-                        let (line, col) = self.position(&actual.1).unwrap();
                         Err(format!(
-                            "Expected {:?}, but found {:?} at {},{}",
+                            "Expected {:?}, but found {:?} {}",
                             expected,
                             self.text(&actual),
-                            line,
-                            col
+                            self.at(&actual.1)
                         ))
                     } else {
                         Err(format!("Expected {:?}, but found EOF", expected))
@@ -867,52 +995,143 @@ impl<'a> Parser<'a> {
 #[cfg(test)]
 mod test {
     use super::Parser;
-    use crate::ast::Ast;
-    use crate::ast::Ast::*;
-    use crate::expression::Expression;
+    use crate::ast::{AstKind, AstKind::*, AstNode, Parameter};
+    use crate::expression::{Expression, ExpressionKind};
     use crate::lexer::Lexer;
     use crate::operator::Operator;
 
-    fn expect(code: &str, ast: Ast) {
+    /// Drops the source locations, because the tests only care about the structure
+    fn strip_expr(expr: &Expression) -> Expression {
+        let kind = match &expr.kind {
+            ExpressionKind::BinaryOp(lhs, op, rhs) => ExpressionKind::BinaryOp(
+                Box::new(strip_expr(lhs)),
+                op.clone(),
+                Box::new(strip_expr(rhs)),
+            ),
+            ExpressionKind::PostOp(lhs, op, rhs) => ExpressionKind::PostOp(
+                Box::new(strip_expr(lhs)),
+                op.clone(),
+                rhs.as_ref().map(|rhs| Box::new(strip_expr(rhs))),
+            ),
+            ExpressionKind::PreOp(op, rhs) => {
+                ExpressionKind::PreOp(op.clone(), Box::new(strip_expr(rhs)))
+            }
+            ExpressionKind::ArrayLiteral(items) => {
+                ExpressionKind::ArrayLiteral(items.iter().map(strip_expr).collect())
+            }
+            kind => kind.clone(),
+        };
+        kind.into()
+    }
+
+    fn strip(node: &AstNode) -> AstKind {
+        let block = |stmts: &Vec<AstNode>| {
+            stmts
+                .iter()
+                .map(|n| AstNode::new(strip(n), 0..0))
+                .collect::<Vec<_>>()
+        };
+        match &node.kind {
+            IfStatement(branches) => IfStatement(
+                branches
+                    .iter()
+                    .map(|(cond, body)| (strip_expr(cond), block(body)))
+                    .collect(),
+            ),
+            WhileStatement(cond, body) => WhileStatement(strip_expr(cond), block(body)),
+            AstKind::Expression(e) => AstKind::Expression(strip_expr(e)),
+            PlusAssignment(name, e) => PlusAssignment(name.clone(), strip_expr(e)),
+            MinusAssignment(name, e) => MinusAssignment(name.clone(), strip_expr(e)),
+            TimesAssignment(name, e) => TimesAssignment(name.clone(), strip_expr(e)),
+            DivideAssignment(name, e) => DivideAssignment(name.clone(), strip_expr(e)),
+            kind => kind.clone(),
+        }
+    }
+
+    fn node(kind: AstKind) -> AstNode {
+        AstNode::new(kind, 0..0)
+    }
+
+    fn expr(kind: ExpressionKind) -> Expression {
+        kind.into()
+    }
+
+    fn parse(code: &str) -> Vec<AstKind> {
         let mut parser = Parser::new();
         parser.expr_parser.source = code;
         parser.source = code;
         parser.lexer = itertools::multipeek(Lexer::new(code, 0));
         let prog = parser.parse().unwrap();
-        assert_eq!(prog.stmts, vec![ast]);
+        prog.stmts.iter().map(strip).collect()
+    }
+
+    fn expect(code: &str, ast: AstKind) {
+        assert_eq!(parse(code), vec![ast]);
     }
 
     #[test]
     pub fn if_stmt() {
-        expect(r#"if true {}"#, IfStatement(vec![(true.into(), vec![])]));
+        expect(r#"if true {}"#, IfStatement(vec![(expr(true.into()), vec![])]));
     }
 
     #[test]
     pub fn if_stmt_with_else() {
         expect(
             r#"if true {} else {}"#,
-            IfStatement(vec![(true.into(), vec![]), (true.into(), vec![])]),
+            IfStatement(vec![
+                (expr(true.into()), vec![]),
+                (expr(true.into()), vec![]),
+            ]),
+        );
+    }
+
+    #[test]
+    pub fn import_stmt() {
+        expect(r#"import nog"#, ImportStatement("nog".into(), None));
+        expect(r#"import foo.bar"#, ImportStatement("foo.bar".into(), None));
+    }
+
+    #[test]
+    pub fn import_stmt_with_names() {
+        expect(
+            r#"import { x, Y } from foo"#,
+            ImportStatement("foo".into(), Some(vec!["x".into(), "Y".into()])),
+        );
+    }
+
+    #[test]
+    pub fn import_stmt_followed_by_stmt() {
+        assert_eq!(
+            parse("import nog\nprint()"),
+            vec![
+                ImportStatement("nog".into(), None),
+                AstKind::Expression(expr(ExpressionKind::PostOp(
+                    Box::new(expr(ExpressionKind::Identifier("print".into()))),
+                    Operator::Call,
+                    Some(Box::new(expr(ExpressionKind::ArrayLiteral(vec![])))),
+                ))),
+            ]
         );
     }
 
     #[test]
     pub fn plus_shortcut() {
-        expect(r#"test += 1"#, PlusAssignment("test".into(), 1.into()));
+        expect(r#"test += 1"#, PlusAssignment("test".into(), expr(1.into())));
     }
 
     #[test]
     pub fn minus_shortcut() {
-        expect(r#"test -= 1"#, MinusAssignment("test".into(), 1.into()));
+        expect(r#"test -= 1"#, MinusAssignment("test".into(), expr(1.into())));
     }
 
     #[test]
     pub fn times_shortcut() {
-        expect(r#"test *= 1"#, TimesAssignment("test".into(), 1.into()));
+        expect(r#"test *= 1"#, TimesAssignment("test".into(), expr(1.into())));
     }
 
     #[test]
     pub fn divide_shortcut() {
-        expect(r#"test /= 1"#, DivideAssignment("test".into(), 1.into()));
+        expect(r#"test /= 1"#, DivideAssignment("test".into(), expr(1.into())));
     }
 
     #[test]
@@ -925,14 +1144,14 @@ mod test {
                 }
             "#,
             WhileStatement(
-                true.into(),
+                expr(true.into()),
                 vec![
-                    IfStatement(vec![(true.into(), vec![])]),
-                    Expression(Expression::PostOp(
-                        Box::new(Expression::Identifier("print".into())),
+                    node(IfStatement(vec![(expr(true.into()), vec![])])),
+                    node(AstKind::Expression(expr(ExpressionKind::PostOp(
+                        Box::new(expr(ExpressionKind::Identifier("print".into()))),
                         Operator::Call,
-                        Some(Box::new(Expression::ArrayLiteral(vec![]))),
-                    )),
+                        Some(Box::new(expr(ExpressionKind::ArrayLiteral(vec![])))),
+                    )))),
                 ],
             ),
         );
@@ -1,6 +1,7 @@
 use super::dynamic::Dynamic;
 use super::expression::Expression;
 use super::interpreter::Program;
+use super::limits::Limit;
 use super::operator::Operator;
 
 #[derive(Clone, Debug)]
@@ -27,6 +28,10 @@ pub enum RuntimeError {
         class: String,
         operator: Operator,
     },
+    /// The callback used up its budget and got stopped
+    LimitExceeded {
+        limit: Limit,
+    },
 }
 
 impl RuntimeError {
@@ -56,6 +61,9 @@ impl RuntimeError {
                 &class,
                 &operator.to_string(),
             ),
+            RuntimeError::LimitExceeded { limit } => {
+                format!("Stopped the callback, because it {}", limit)
+            }
         }
     }
 }
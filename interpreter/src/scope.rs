@@ -1,31 +1,105 @@
 use std::{
     collections::HashMap,
+    hash::{BuildHasherDefault, Hasher},
     sync::{Arc, Mutex},
 };
 
 use super::{dynamic::Dynamic, interpreter::Interpreter, runtime_error::RuntimeResult};
 
+/// Hashes the names of variables using FNV-1a. Every access of a variable hashes its name once for
+/// each frame it looks through and names are short, which the default hasher is slow at.
+pub struct NameHasher(u64);
+
+impl Default for NameHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for NameHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+pub type Variables = HashMap<String, Dynamic, BuildHasherDefault<NameHasher>>;
+
+/// A frame of variables. Frames are shared, which means that every clone of a scope sees the
+/// changes made through any other clone.
+///
+/// A scope can be nested inside of a parent scope, whose variables are visible as well. This is how
+/// functions keep the environment they were defined in alive.
 #[derive(Default, Debug, Clone)]
 pub struct Scope {
-    pub variables: Arc<Mutex<HashMap<String, Dynamic>>>,
+    pub variables: Arc<Mutex<Variables>>,
+    pub parent: Option<Arc<Scope>>,
 }
 
 impl Scope {
+    /// Creates an empty scope that is nested inside of this one.
+    pub fn child(&self) -> Scope {
+        Scope {
+            variables: Default::default(),
+            parent: Some(Arc::new(self.clone())),
+        }
+    }
+
+    /// Defines the variable in this frame, shadowing any variable with the same name of a parent.
     pub fn set(&mut self, key: String, value: Dynamic) {
         self.variables.lock().unwrap().insert(key, value);
     }
 
+    /// Changes the value of the variable in the frame that defines it. Returns false if no frame
+    /// defines the variable.
+    pub fn assign(&self, key: &str, value: Dynamic) -> bool {
+        let mut variables = self.variables.lock().unwrap();
+
+        if let Some(slot) = variables.get_mut(key) {
+            *slot = value;
+            return true;
+        }
+
+        drop(variables);
+
+        match &self.parent {
+            Some(parent) => parent.assign(key, value),
+            None => false,
+        }
+    }
+
     pub fn get(&self, key: &str) -> Dynamic {
-        self.variables
-            .lock()
-            .unwrap()
-            .get(key)
-            .cloned()
+        if let Some(value) = self.variables.lock().unwrap().get(key) {
+            return value.clone();
+        }
+
+        self.parent
+            .as_ref()
+            .map(|parent| parent.get(key))
             .unwrap_or_default()
     }
 
+    /// Returns the value of the variable, or `None` if no frame defines it.
+    pub fn lookup(&self, key: &str) -> Option<Dynamic> {
+        if let Some(value) = self.variables.lock().unwrap().get(key) {
+            return Some(value.clone());
+        }
+
+        self.parent.as_ref().and_then(|parent| parent.lookup(key))
+    }
+
     pub fn is_defined(&self, key: &str) -> bool {
         self.variables.lock().unwrap().contains_key(key)
+            || self
+                .parent
+                .as_ref()
+                .map(|parent| parent.is_defined(key))
+                .unwrap_or(false)
     }
 
     pub fn register_rust_function(
@@ -44,16 +118,24 @@ impl Scope {
     }
 }
 
+/// Captures the environment of a function. The frames of the stack get chained together, so that
+/// the function sees every change made to them after it got defined. Frames that are already
+/// nested inside of a parent belong to a function call and only see their own environment, instead
+/// of the stack of the caller.
 impl From<&Vec<Scope>> for Scope {
     fn from(scopes: &Vec<Scope>) -> Scope {
-        let mut flat_scope = Scope::default();
+        let mut env: Option<Scope> = None;
 
         for scope in scopes {
-            for (key, value) in scope.variables.lock().unwrap().iter() {
-                flat_scope.set(key.clone(), value.clone());
-            }
+            env = Some(match scope.parent {
+                Some(_) => scope.clone(),
+                None => Scope {
+                    variables: scope.variables.clone(),
+                    parent: env.map(Arc::new),
+                },
+            });
         }
 
-        flat_scope
+        env.unwrap_or_default()
     }
 }
@@ -0,0 +1,274 @@
+//! Runs the tests of a config. Every file whose name ends with `_test.ns` is a test file and every
+//! function of such a file whose name starts with `test_` is a test.
+//!
+//! Tests check their expectations using `assert` and `assert_eq`. A failed assertion doesn't stop
+//! the test, so that one run reports every expectation that doesn't hold.
+use crate::{dynamic::Dynamic, function::Function, interpreter::Interpreter};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+const FILE_SUFFIX: &str = "_test.ns";
+const TEST_PREFIX: &str = "test_";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestReport {
+    pub file: PathBuf,
+    /// The name of the test function. Errors and failed assertions that happen while the file
+    /// itself runs are reported under the name of the file.
+    pub name: String,
+    pub failures: Vec<String>,
+}
+
+impl TestReport {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl fmt::Display for TestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let file = self
+            .file
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+
+        if self.passed() {
+            return write!(f, "ok      {}::{}", file, self.name);
+        }
+
+        write!(f, "FAILED  {}::{}", file, self.name)?;
+
+        for failure in &self.failures {
+            write!(f, "\n        {}", failure)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Describes the value for a failure message. Only values that can be compared are shown in
+/// full.
+pub fn describe(value: &Dynamic) -> String {
+    match value {
+        Dynamic::String(x) => format!("{:?}", x),
+        Dynamic::Number(_) | Dynamic::Float(_) | Dynamic::Boolean(_) | Dynamic::Null => {
+            value.to_string()
+        }
+        x => x.type_name(),
+    }
+}
+
+pub fn is_test_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name.ends_with(FILE_SUFFIX))
+        .unwrap_or(false)
+}
+
+/// Returns every test file inside of the directory and its subdirectories, sorted by path
+pub fn discover(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+
+            if path.is_dir() {
+                files.extend(discover(&path));
+            } else if is_test_file(&path) {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    files
+}
+
+/// Defines `assert` and `assert_eq`, which add a message to the failures if they fail.
+fn add_assertions(interpreter: &mut Interpreter, failures: &Arc<Mutex<Vec<String>>>) {
+    let f = failures.clone();
+    let assert = Function::new("assert", None, move |_i, args| {
+        if !args.first().map(|x| x.is_true()).unwrap_or(false) {
+            let msg = args
+                .get(1)
+                .map(|x| x.to_string())
+                .unwrap_or("assertion failed".into());
+
+            f.lock().unwrap().push(msg);
+        }
+
+        Ok(Dynamic::Null)
+    });
+
+    let f = failures.clone();
+    let assert_eq = Function::new("assert_eq", None, move |_i, args| {
+        let actual = args.first().cloned().unwrap_or_default();
+        let expected = args.get(1).cloned().unwrap_or_default();
+
+        if actual != expected {
+            f.lock().unwrap().push(format!(
+                "expected {}, but found {}",
+                describe(&expected),
+                describe(&actual)
+            ));
+        }
+
+        Ok(Dynamic::Null)
+    });
+
+    interpreter
+        .default_variables
+        .insert("assert".into(), assert.into());
+    interpreter
+        .default_variables
+        .insert("assert_eq".into(), assert_eq.into());
+}
+
+/// Runs the file and then every test it defines, sorted by name. `before_each` gets called before
+/// every test, e.g. to forget the calls a mock recorded.
+pub fn run_file(
+    interpreter: &mut Interpreter,
+    path: &Path,
+    before_each: impl Fn(),
+) -> Vec<TestReport> {
+    let failures = Arc::new(Mutex::new(Vec::new()));
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    add_assertions(interpreter, &failures);
+
+    let module = match interpreter.execute_file(path.to_path_buf()) {
+        Ok(module) => module,
+        Err(e) => {
+            return vec![TestReport {
+                file: path.to_path_buf(),
+                name: file_name,
+                failures: vec![e],
+            }]
+        }
+    };
+
+    // assertions at the top level of the file don't belong to any test
+    let load_failures = std::mem::take(&mut *failures.lock().unwrap());
+    let load_report = if load_failures.is_empty() {
+        None
+    } else {
+        Some(TestReport {
+            file: path.to_path_buf(),
+            name: file_name,
+            failures: load_failures,
+        })
+    };
+
+    let mut tests = module
+        .scope
+        .variables
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(name, value)| {
+            name.starts_with(TEST_PREFIX) && matches!(value, Dynamic::Function { .. })
+        })
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect::<Vec<_>>();
+
+    tests.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let reports = tests
+        .into_iter()
+        .map(|(name, test)| {
+            before_each();
+
+            if let Dynamic::Function {
+                arg_names,
+                body,
+                scope,
+                ..
+            } = test
+            {
+                let result = interpreter.call_fn(None, Some(scope), &arg_names, &vec![], &body);
+
+                if let Err(e) = result {
+                    let msg = e.message(&interpreter.program());
+                    failures.lock().unwrap().push(msg);
+                }
+            }
+
+            TestReport {
+                file: path.to_path_buf(),
+                name,
+                failures: std::mem::take(&mut *failures.lock().unwrap()),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    load_report.into_iter().chain(reports).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_tests_of_a_file() {
+        let dir = std::env::temp_dir().join("nog_run_tests_of_a_file");
+        let path = dir.join("math_test.ns");
+
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            &path,
+            r#"
+fn double(x) {
+  return x * 2
+}
+
+fn test_double() {
+  assert_eq(double(2), 4)
+}
+
+fn test_broken() {
+  assert_eq(double(2), 5)
+  assert(false, "still running")
+}
+
+fn helper() {
+  assert(false)
+}
+
+assert(false, "at the top level")
+"#,
+        )
+        .unwrap();
+
+        let mut interpreter = Interpreter::new();
+        let reports = run_file(&mut interpreter, &path, || {});
+
+        assert_eq!(discover(&dir), vec![path.clone()]);
+        assert_eq!(
+            reports
+                .iter()
+                .map(|r| (r.name.as_str(), r.failures.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("math_test.ns", vec!["at the top level".to_string()]),
+                (
+                    "test_broken",
+                    vec![
+                        "expected 5, but found 4".to_string(),
+                        "still running".to_string()
+                    ]
+                ),
+                ("test_double", vec![]),
+            ]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
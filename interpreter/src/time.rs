@@ -0,0 +1,256 @@
+//! The `std.time` module. Points in time are unix timestamps in seconds and durations are numbers
+//! of seconds, so that they can be compared and added like any other number. Timestamps are floats,
+//! because they don't fit into a number after 2038.
+use crate::{
+    dynamic::{Dynamic, Float},
+    module::Module,
+    runtime_error::{RuntimeError, RuntimeResult},
+};
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, Datelike, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc,
+};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Zone {
+    Local,
+    Utc,
+    Fixed(FixedOffset),
+}
+
+/// Parses a timezone, which is either `local`, `utc` or an offset like `+02:00`
+fn zone(name: &str) -> Result<Zone, String> {
+    let invalid = || format!("Invalid timezone '{}'", name);
+
+    match name {
+        "local" => Ok(Zone::Local),
+        "utc" => Ok(Zone::Utc),
+        _ => {
+            let sign = match name.chars().next() {
+                Some('+') => 1,
+                Some('-') => -1,
+                _ => return Err(invalid()),
+            };
+            let mut parts = name[1..].splitn(2, ':');
+            let hours: i32 = parts
+                .next()
+                .and_then(|h| h.parse().ok())
+                .ok_or_else(invalid)?;
+            let minutes: i32 = match parts.next() {
+                Some(m) => m.parse().map_err(|_| invalid())?,
+                None => 0,
+            };
+
+            FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+                .map(Zone::Fixed)
+                .ok_or_else(invalid)
+        }
+    }
+}
+
+fn datetime(timestamp: i64, zone: Zone) -> DateTime<FixedOffset> {
+    let utc = Utc.timestamp(timestamp, 0);
+
+    match zone {
+        Zone::Local => {
+            let local = utc.with_timezone(&Local);
+            local.with_timezone(local.offset())
+        }
+        Zone::Utc => utc.with_timezone(&FixedOffset::east(0)),
+        Zone::Fixed(offset) => utc.with_timezone(&offset),
+    }
+}
+
+fn pattern(pattern: &str) -> Result<Vec<Item<'_>>, String> {
+    let items = StrftimeItems::new(pattern).collect::<Vec<_>>();
+
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        Err(format!("Invalid time format '{}'", pattern))
+    } else {
+        Ok(items)
+    }
+}
+
+fn format(timestamp: i64, fmt: &str, zone: Zone) -> Result<String, String> {
+    let items = pattern(fmt)?;
+
+    Ok(datetime(timestamp, zone)
+        .format_with_items(items.into_iter())
+        .to_string())
+}
+
+/// Parses the text using the format. Formats without a time of day result in midnight.
+fn parse(text: &str, fmt: &str, zone: Zone) -> Result<i64, String> {
+    pattern(fmt)?;
+
+    let naive = NaiveDateTime::parse_from_str(text, fmt)
+        .or_else(|_| NaiveDate::parse_from_str(text, fmt).map(|d| d.and_hms(0, 0, 0)))
+        .map_err(|e| format!("Failed to parse '{}' as '{}': {}", text, fmt, e))?;
+
+    let timestamp = match zone {
+        Zone::Local => Local
+            .from_local_datetime(&naive)
+            .earliest()
+            .map(|dt| dt.timestamp()),
+        Zone::Utc => Some(Utc.from_utc_datetime(&naive).timestamp()),
+        Zone::Fixed(offset) => offset
+            .from_local_datetime(&naive)
+            .single()
+            .map(|dt| dt.timestamp()),
+    };
+
+    timestamp.ok_or_else(|| format!("'{}' doesn't exist in the timezone", text))
+}
+
+/// Formats the duration like `1d 2h 3m 4s`, leaving out the units that are zero
+fn format_duration(seconds: i64) -> String {
+    let sign = if seconds < 0 { "-" } else { "" };
+    let seconds = seconds.abs();
+    let parts = [
+        (seconds / 86400, "d"),
+        (seconds % 86400 / 3600, "h"),
+        (seconds % 3600 / 60, "m"),
+        (seconds % 60, "s"),
+    ]
+    .iter()
+    .filter(|(value, _)| *value > 0)
+    .map(|(value, unit)| format!("{}{}", value, unit))
+    .collect::<Vec<_>>();
+
+    if parts.is_empty() {
+        "0s".into()
+    } else {
+        format!("{}{}", sign, parts.join(" "))
+    }
+}
+
+fn now() -> i64 {
+    Utc::now().timestamp()
+}
+
+/// Converts a number or a float of seconds
+fn seconds(value: &Dynamic) -> RuntimeResult<i64> {
+    value
+        .to_float()
+        .map(|x| x as i64)
+        .ok_or_else(|| RuntimeError::UnexpectedType {
+            expected: "Number".into(),
+            actual: value.type_name(),
+        })
+}
+
+fn timestamp_arg(args: &[Dynamic], idx: usize) -> RuntimeResult<i64> {
+    match args.get(idx) {
+        Some(Dynamic::Null) | None => Ok(now()),
+        Some(value) => seconds(value),
+    }
+}
+
+fn zone_arg(args: &[Dynamic], idx: usize) -> RuntimeResult<Zone> {
+    match args.get(idx) {
+        Some(Dynamic::Null) | None => Ok(Zone::Local),
+        Some(value) => Ok(zone(string!(value)?)?),
+    }
+}
+
+pub fn create_module() -> Module {
+    Module::new("time")
+        .function("now", |_, _| Ok(now() as Float))
+        .function("format", |_, args| {
+            let pattern = string!(&args[0])?;
+            let timestamp = timestamp_arg(&args, 1)?;
+            let zone = zone_arg(&args, 2)?;
+
+            Ok(format(timestamp, pattern, zone)?)
+        })
+        .function("parse", |_, args| {
+            let text = string!(&args[0])?;
+            let pattern = string!(&args[1])?;
+            let zone = zone_arg(&args, 2)?;
+
+            Ok(parse(text, pattern, zone)? as Float)
+        })
+        .function("parts", |_, args| {
+            let dt = datetime(timestamp_arg(&args, 0)?, zone_arg(&args, 1)?);
+            let mut parts: HashMap<String, Dynamic> = HashMap::new();
+
+            parts.insert("year".into(), dt.year().into());
+            parts.insert("month".into(), (dt.month() as i32).into());
+            parts.insert("day".into(), (dt.day() as i32).into());
+            parts.insert("hour".into(), (dt.hour() as i32).into());
+            parts.insert("minute".into(), (dt.minute() as i32).into());
+            parts.insert("second".into(), (dt.second() as i32).into());
+            parts.insert(
+                "weekday".into(),
+                (dt.weekday().number_from_monday() as i32).into(),
+            );
+
+            Ok(Dynamic::new_object(parts))
+        })
+        .function("duration", |_, args| {
+            let units_ref = object!(&args[0])?;
+            let units = units_ref.lock().unwrap();
+            let mut seconds = 0;
+
+            for (key, value) in units.iter() {
+                let factor = match key.as_str() {
+                    "days" => 86400,
+                    "hours" => 3600,
+                    "minutes" => 60,
+                    "seconds" => 1,
+                    _ => return Err(format!("Unknown unit '{}'", key).into()),
+                };
+
+                seconds += *number!(value)? * factor;
+            }
+
+            Ok(seconds)
+        })
+        .function("format_duration", |_, args| {
+            Ok(format_duration(seconds(&args[0])?))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_and_parse() {
+        let zone = zone("+02:00").unwrap();
+        let timestamp = parse("2021-03-04 05:06:07", "%Y-%m-%d %H:%M:%S", zone).unwrap();
+
+        assert_eq!(timestamp, 1614827167);
+        assert_eq!(
+            format(timestamp, "%d.%m.%Y %H:%M", Zone::Utc).unwrap(),
+            "04.03.2021 03:06"
+        );
+        assert_eq!(parse("2021-03-04", "%Y-%m-%d", Zone::Utc), Ok(1614816000));
+        assert!(format(timestamp, "%Q", zone).is_err());
+        assert!(parse("yesterday", "%Y-%m-%d", zone).is_err());
+        assert!(super::zone("Europe/Vienna").is_err());
+    }
+
+    #[test]
+    fn timestamps_after_2038() {
+        let timestamp = parse("2040-01-01", "%Y-%m-%d", Zone::Utc).unwrap();
+
+        assert_eq!(timestamp, 2208988800);
+        assert_eq!(
+            seconds(&Dynamic::Float(timestamp as Float)).ok(),
+            Some(timestamp)
+        );
+        assert_eq!(
+            format(timestamp, "%Y-%m-%d", Zone::Utc).unwrap(),
+            "2040-01-01"
+        );
+    }
+
+    #[test]
+    fn format_durations() {
+        assert_eq!(format_duration(0), "0s");
+        assert_eq!(format_duration(90061), "1d 1h 1m 1s");
+        assert_eq!(format_duration(-3600), "-1h");
+    }
+}
@@ -1,20 +1,37 @@
 use logos::Logos;
 use std::ops::Range;
 
+/// Consumes everything up to and including the closing `*/`. An unterminated block comment is an
+/// error.
+fn block_comment(lex: &mut logos::Lexer<TokenKind>) -> bool {
+    match lex.remainder().find("*/") {
+        Some(end) => {
+            lex.bump(end + 2);
+            true
+        }
+        None => false,
+    }
+}
+
 #[derive(Logos, Debug, PartialEq, Clone, Eq, Hash)]
 pub enum TokenKind {
-    #[regex("[a-z$_][a-zA-Z0-9$_]*", priority = 2)]
+    /// Identifiers may contain any unicode letter, but only uppercase letters start a class
+    /// identifier
+    #[regex(r"[\p{Ll}\p{Lo}$_][\p{L}\p{N}$_]*", priority = 2)]
     Identifier,
-    #[regex("[A-Z][a-zA-Z0-9$_]*")]
+    #[regex(r"\p{Lu}[\p{L}\p{N}$_]*")]
     ClassIdentifier,
-    #[regex("[0-9]+")]
+    /// A whole number or a float like `1.5`
+    #[regex(r"[0-9]+(\.[0-9]+)?")]
     NumberLiteral,
-    #[regex(r#""([^"\\]|\\r|\\t|\\u|\\n|\\")*""#)]
+    #[regex(r#""([^"\\]|\\\\|\\r|\\t|\\u|\\n|\\")*""#)]
     StringLiteral,
     #[token("#")]
     Hash,
     #[regex("0x[0-9a-fA-F]+")]
     HexLiteral,
+    #[regex("0b[01]+")]
+    BinaryLiteral,
     #[token("++")]
     PlusPlus,
     #[token("+=")]
@@ -45,6 +62,8 @@ pub enum TokenKind {
     Extern,
     #[token("import")]
     Import,
+    #[token("include")]
+    Include,
     #[token("break")]
     Break,
     #[token("+")]
@@ -61,8 +80,12 @@ pub enum TokenKind {
     Op,
     #[token("null")]
     Null,
-    #[token("//")]
+    /// A comment spans the rest of the line, so that its text can't unbalance the brackets of the
+    /// expression it is in
+    #[regex(r"//([^/\r\n][^\r\n]*)?")]
     Comment,
+    #[token("/*", block_comment)]
+    BlockComment,
     #[token("fn")]
     Fn,
     #[regex("(true|false)")]
@@ -81,6 +104,8 @@ pub enum TokenKind {
     Comma,
     #[token("!")]
     ExclamationMark,
+    #[token("?")]
+    QuestionMark,
     #[token(".")]
     Dot,
     #[token(":")]
@@ -91,6 +116,16 @@ pub enum TokenKind {
     And,
     #[token("||")]
     Or,
+    #[token("&")]
+    Ampersand,
+    #[token("|")]
+    Bar,
+    #[token("^")]
+    Caret,
+    #[token("<<")]
+    ShiftLeft,
+    #[token(">>")]
+    ShiftRight,
     #[token(";")]
     SemiColon,
     #[token(">")]
@@ -232,4 +267,49 @@ mod test {
     fn class_identifier() {
         parse("Identifier", (ClassIdentifier, 0..10))
     }
+
+    #[test]
+    fn unicode_identifiers() {
+        parse("größe", (Identifier, 0..7));
+        parse("Überblick", (ClassIdentifier, 0..10));
+        parse("名前", (Identifier, 0..6));
+    }
+
+    #[test]
+    fn unicode_string_literal() {
+        parse_seq(
+            r#"x = "😀 café""#,
+            vec![(Identifier, 0..1), (Equal, 2..3), (StringLiteral, 4..16)],
+        )
+    }
+
+    #[test]
+    fn escaped_backslash_in_string_literal() {
+        parse(r#""C:\\nog""#, (StringLiteral, 0..9))
+    }
+
+    #[test]
+    fn comments() {
+        parse_seq(
+            "x // a (b\n/// doc\n/* c\n * d) */ y",
+            vec![
+                (Identifier, 0..1),
+                (Comment, 2..9),
+                (NewLine, 9..10),
+                (TripleSlash, 10..13),
+                (Identifier, 14..17),
+                (NewLine, 17..18),
+                (BlockComment, 18..31),
+                (Identifier, 32..33),
+            ],
+        )
+    }
+
+    #[test]
+    fn comment_stops_before_carriage_return() {
+        parse_seq(
+            "//\r\n/**/",
+            vec![(Comment, 0..2), (NewLine, 2..4), (BlockComment, 4..8)],
+        )
+    }
 }
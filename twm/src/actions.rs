@@ -0,0 +1,627 @@
+//! Named actions are the built-in commands of nog. Keybindings, the command palette and scripts
+//! refer to them by name, which means that all of them go through the same dispatch path.
+//!
+//! An invocation consists of the name of the action and its numeric arguments, for example
+//! `focus_left` or `move_to_workspace(3)`.
+//!
+//! Some actions can also be applied to the windows or workspaces that a query produces, see
+//! [`pipeline`](pipeline/index.html).
+use crate::{
+    borderless,
+    direction::Direction,
+    event::Event,
+    hidden, keybindings, pinned,
+    split_direction::SplitDirection,
+    system::{SystemError, SystemResult, WindowId},
+    AppState, COMMAND_PALETTE_MODE, OVERVIEW_MODE, RESIZE_MODE, WINDOW_SEARCH_MODE,
+};
+use lazy_static::lazy_static;
+use std::{fmt, str::FromStr};
+
+pub mod pipeline;
+
+pub struct ActionDef {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// How many numeric arguments the action expects
+    pub arg_count: usize,
+    handler: fn(&mut AppState, &[i32]) -> SystemResult,
+    /// Applies the action to the given window instead of the focused one
+    window_handler: Option<fn(&mut AppState, WindowId, &[i32]) -> SystemResult>,
+    /// Applies the action to the given workspace instead of the focused one
+    workspace_handler: Option<fn(&mut AppState, i32, &[i32]) -> SystemResult>,
+}
+
+impl ActionDef {
+    fn new(
+        name: &'static str,
+        description: &'static str,
+        arg_count: usize,
+        handler: fn(&mut AppState, &[i32]) -> SystemResult,
+    ) -> Self {
+        Self {
+            name,
+            description,
+            arg_count,
+            handler,
+            window_handler: None,
+            workspace_handler: None,
+        }
+    }
+
+    fn for_window(mut self, handler: fn(&mut AppState, WindowId, &[i32]) -> SystemResult) -> Self {
+        self.window_handler = Some(handler);
+        self
+    }
+
+    fn for_workspace(mut self, handler: fn(&mut AppState, i32, &[i32]) -> SystemResult) -> Self {
+        self.workspace_handler = Some(handler);
+        self
+    }
+
+    /// Whether the action can be applied to the items of the collection
+    pub fn accepts(&self, kind: pipeline::CollectionKind) -> bool {
+        match kind {
+            pipeline::CollectionKind::Windows => self.window_handler.is_some(),
+            pipeline::CollectionKind::Workspaces => self.workspace_handler.is_some(),
+        }
+    }
+}
+
+fn create_actions() -> Vec<ActionDef> {
+    vec![
+        ActionDef::new("focus_left", "Focus the tile to the left", 0, |s, _| {
+            s.focus(Direction::Left)
+        }),
+        ActionDef::new("focus_right", "Focus the tile to the right", 0, |s, _| {
+            s.focus(Direction::Right)
+        }),
+        ActionDef::new("focus_up", "Focus the tile above", 0, |s, _| {
+            s.focus(Direction::Up)
+        }),
+        ActionDef::new("focus_down", "Focus the tile below", 0, |s, _| {
+            s.focus(Direction::Down)
+        }),
+        ActionDef::new(
+            "focus_next",
+            "Focus the next tile of the workspace",
+            0,
+            |s, _| s.focus_relative(true),
+        ),
+        ActionDef::new(
+            "focus_prev",
+            "Focus the previous tile of the workspace",
+            0,
+            |s, _| s.focus_relative(false),
+        ),
+        ActionDef::new(
+            "cycle_container",
+            "Focus the container that follows the one of the focused tile",
+            0,
+            |s, _| s.cycle_container(),
+        ),
+        ActionDef::new("swap_left", "Swap with the tile to the left", 0, |s, _| {
+            s.swap(Direction::Left)
+        }),
+        ActionDef::new(
+            "swap_right",
+            "Swap with the tile to the right",
+            0,
+            |s, _| s.swap(Direction::Right),
+        ),
+        ActionDef::new("swap_up", "Swap with the tile above", 0, |s, _| {
+            s.swap(Direction::Up)
+        }),
+        ActionDef::new("swap_down", "Swap with the tile below", 0, |s, _| {
+            s.swap(Direction::Down)
+        }),
+        ActionDef::new(
+            "move_in_left",
+            "Move into the tile to the left",
+            0,
+            |s, _| s.move_in(Direction::Left),
+        ),
+        ActionDef::new(
+            "move_in_right",
+            "Move into the tile to the right",
+            0,
+            |s, _| s.move_in(Direction::Right),
+        ),
+        ActionDef::new("move_in_up", "Move into the tile above", 0, |s, _| {
+            s.move_in(Direction::Up)
+        }),
+        ActionDef::new("move_in_down", "Move into the tile below", 0, |s, _| {
+            s.move_in(Direction::Down)
+        }),
+        ActionDef::new("move_out_left", "Move out to the left", 0, |s, _| {
+            s.move_out(Direction::Left)
+        }),
+        ActionDef::new("move_out_right", "Move out to the right", 0, |s, _| {
+            s.move_out(Direction::Right)
+        }),
+        ActionDef::new("move_out_up", "Move out upwards", 0, |s, _| {
+            s.move_out(Direction::Up)
+        }),
+        ActionDef::new("move_out_down", "Move out downwards", 0, |s, _| {
+            s.move_out(Direction::Down)
+        }),
+        ActionDef::new("resize_left", "Grow the tile to the left", 1, |s, args| {
+            s.resize(Direction::Left, args[0])
+        }),
+        ActionDef::new(
+            "resize_right",
+            "Grow the tile to the right",
+            1,
+            |s, args| s.resize(Direction::Right, args[0]),
+        ),
+        ActionDef::new("resize_up", "Grow the tile upwards", 1, |s, args| {
+            s.resize(Direction::Up, args[0])
+        }),
+        ActionDef::new("resize_down", "Grow the tile downwards", 1, |s, args| {
+            s.resize(Direction::Down, args[0])
+        }),
+        ActionDef::new(
+            "change_workspace",
+            "Switch to the workspace",
+            1,
+            |s, args| {
+                s.change_workspace(s.resolve_workspace_id(args[0]), true);
+                Ok(())
+            },
+        ),
+        ActionDef::new(
+            "focus_workspace_next",
+            "Switch to the next workspace that contains windows",
+            0,
+            |s, _| {
+                s.focus_relative_workspace(true);
+                Ok(())
+            },
+        ),
+        ActionDef::new(
+            "focus_workspace_prev",
+            "Switch to the previous workspace that contains windows",
+            0,
+            |s, _| {
+                s.focus_relative_workspace(false);
+                Ok(())
+            },
+        ),
+        ActionDef::new(
+            "focus_previous_workspace",
+            "Switch to the previously focused workspace of the monitor",
+            0,
+            |s, _| {
+                s.focus_previous_workspace();
+                Ok(())
+            },
+        ),
+        ActionDef::new(
+            "move_to_workspace",
+            "Move the focused window to the workspace",
+            1,
+            |s, args| s.move_window_to_workspace(s.resolve_workspace_id(args[0])),
+        )
+        .for_window(|s, id, args| {
+            s.move_window_by_id_to_workspace(id, s.resolve_workspace_id(args[0]))
+        }),
+        ActionDef::new(
+            "move_to_workspace_next",
+            "Move the window to the next workspace or, given 1, the next empty one",
+            1,
+            |s, args| s.move_window_to_relative_workspace(true, args[0] == 1),
+        ),
+        ActionDef::new(
+            "move_to_workspace_prev",
+            "Move the window to the previous workspace or, given 1, the previous empty one",
+            1,
+            |s, args| s.move_window_to_relative_workspace(false, args[0] == 1),
+        ),
+        ActionDef::new(
+            "move_workspace_to_monitor",
+            "Move the workspace to the monitor",
+            1,
+            |s, args| s.move_workspace_to_monitor(args[0]),
+        )
+        .for_workspace(
+            |s, id, args| match s.get_display_by_idx(args[0]).map(|d| d.id) {
+                Some(display_id) => s.move_workspace_to_display(id, display_id),
+                None => Ok(()),
+            },
+        ),
+        ActionDef::new(
+            "swap_workspaces",
+            "Swap the visible workspaces of two monitors",
+            2,
+            |s, args| s.swap_workspaces(args[0], args[1]),
+        ),
+        ActionDef::new(
+            "move_workspace_to_workspace",
+            "Move the tiles of the workspace to another workspace",
+            1,
+            |s, args| s.move_workspace_to_workspace(s.resolve_workspace_id(args[0])),
+        ),
+        ActionDef::new(
+            "toggle_fullscreen",
+            "Toggle the fullscreen mode of the workspace",
+            0,
+            |s, _| s.toggle_fullscreen(),
+        ),
+        ActionDef::new(
+            "toggle_floating",
+            "Toggle whether the focused window is managed",
+            0,
+            |s, _| s.toggle_floating(),
+        )
+        .for_window(|s, id, _| s.restore_window(id)),
+        ActionDef::new(
+            "toggle_decorations",
+            "Remove the title bar of the focused window or give it back",
+            0,
+            |s, _| s.toggle_decorations(),
+        ),
+        ActionDef::new(
+            "toggle_borderless_fullscreen",
+            "Stretch the focused window over its display without any borders",
+            0,
+            |s, _| borderless::toggle(s),
+        ),
+        ActionDef::new(
+            "toggle_always_on_top",
+            "Keep the focused window above every other window on every workspace",
+            0,
+            |s, _| pinned::toggle(s),
+        ),
+        ActionDef::new(
+            "hide_to_tray",
+            "Hide the focused window until it gets restored",
+            0,
+            |s, _| hidden::hide(s),
+        ),
+        ActionDef::new(
+            "restore_from_tray",
+            "Restore the window that got hidden last",
+            0,
+            |s, _| hidden::restore_last(s),
+        ),
+        ActionDef::new(
+            "minimize_window",
+            "Minimize the focused window",
+            0,
+            |s, _| s.minimize_window(),
+        )
+        .for_window(|s, id, _| s.remove_window(id, |w| w.minimize())),
+        ActionDef::new("close_window", "Close the focused window", 0, |s, _| {
+            s.close_window()
+        })
+        .for_window(|s, id, _| s.remove_window(id, |w| w.close())),
+        ActionDef::new(
+            "kill_window",
+            "Kill the process of the focused window",
+            0,
+            |s, _| s.kill_window(),
+        )
+        .for_window(|s, id, _| s.remove_window(id, |w| w.kill())),
+        ActionDef::new(
+            "center_window",
+            "Center the focused floating window",
+            0,
+            |s, _| s.center_window(),
+        ),
+        ActionDef::new(
+            "ignore_window",
+            "Ignore the focused window from now on",
+            0,
+            |s, _| s.ignore_window(),
+        ),
+        ActionDef::new("reset_row", "Reset the sizes of the row", 0, |s, _| {
+            s.reset_row()
+        }),
+        ActionDef::new(
+            "reset_column",
+            "Reset the sizes of the column",
+            0,
+            |s, _| s.reset_column(),
+        ),
+        ActionDef::new(
+            "equalize",
+            "Give every tile of the workspace the same size as its siblings",
+            0,
+            |s, _| s.equalize(),
+        ),
+        ActionDef::new(
+            "balance_container",
+            "Give the focused tile and its siblings the same size",
+            0,
+            |s, _| s.balance_container(),
+        ),
+        ActionDef::new(
+            "rotate_clockwise",
+            "Rotate the container of the focused tile clockwise",
+            0,
+            |s, _| s.rotate_container(true),
+        ),
+        ActionDef::new(
+            "rotate_counterclockwise",
+            "Rotate the container of the focused tile counterclockwise",
+            0,
+            |s, _| s.rotate_container(false),
+        ),
+        ActionDef::new(
+            "mirror_horizontal",
+            "Swap the left and right of the container of the focused tile",
+            0,
+            |s, _| s.mirror_container(true),
+        ),
+        ActionDef::new(
+            "mirror_vertical",
+            "Swap the top and bottom of the container of the focused tile",
+            0,
+            |s, _| s.mirror_container(false),
+        ),
+        ActionDef::new(
+            "split_horizontal",
+            "Split the next tile horizontally",
+            0,
+            |s, _| s.set_split_direction(SplitDirection::Horizontal),
+        ),
+        ActionDef::new(
+            "split_vertical",
+            "Split the next tile vertically",
+            0,
+            |s, _| s.set_split_direction(SplitDirection::Vertical),
+        ),
+        ActionDef::new(
+            "split_left",
+            "Open the next window to the left of the focused tile",
+            0,
+            |s, _| s.split(Direction::Left),
+        ),
+        ActionDef::new(
+            "split_right",
+            "Open the next window to the right of the focused tile",
+            0,
+            |s, _| s.split(Direction::Right),
+        ),
+        ActionDef::new(
+            "split_up",
+            "Open the next window above the focused tile",
+            0,
+            |s, _| s.split(Direction::Up),
+        ),
+        ActionDef::new(
+            "split_down",
+            "Open the next window below the focused tile",
+            0,
+            |s, _| s.split(Direction::Down),
+        ),
+        ActionDef::new("toggle_bar", "Hide or reveal the bar", 0, |s, _| {
+            s.toggle_appbar()
+        }),
+        ActionDef::new(
+            keybindings::TOGGLE_ACTION,
+            "Suspend or resume all other keybindings",
+            0,
+            |s, _| {
+                s.keybindings_manager.toggle_suspend();
+                Ok(())
+            },
+        ),
+        ActionDef::new("resize_mode", "Toggle the resize mode", 0, |s, _| {
+            s.toggle_mode(RESIZE_MODE.into());
+            Ok(())
+        }),
+        ActionDef::new(
+            "command_palette",
+            "Toggle the command palette",
+            0,
+            |s, _| {
+                s.toggle_mode(COMMAND_PALETTE_MODE.into());
+                Ok(())
+            },
+        ),
+        ActionDef::new(
+            "overview",
+            "Toggle the overview of the workspaces",
+            0,
+            |s, _| {
+                s.toggle_mode(OVERVIEW_MODE.into());
+                Ok(())
+            },
+        ),
+        ActionDef::new(
+            "window_search",
+            "Toggle the search for a window of any workspace",
+            0,
+            |s, _| {
+                s.toggle_mode(WINDOW_SEARCH_MODE.into());
+                Ok(())
+            },
+        ),
+        ActionDef::new("reload_config", "Reload the config", 0, |s, _| {
+            s.event_channel
+                .sender
+                .send(Event::ReloadConfig)
+                .expect("Failed to send ReloadConfig event");
+            Ok(())
+        }),
+        ActionDef::new("quit", "Unmanage all windows and exit", 0, |s, _| {
+            s.event_channel
+                .sender
+                .send(Event::Exit)
+                .expect("Failed to send Exit event");
+            Ok(())
+        }),
+    ]
+}
+
+lazy_static! {
+    static ref ACTIONS: Vec<ActionDef> = create_actions();
+}
+
+pub fn all() -> &'static [ActionDef] {
+    &ACTIONS
+}
+
+pub fn find(name: &str) -> Option<&'static ActionDef> {
+    ACTIONS.iter().find(|a| a.name == name)
+}
+
+/// A call of a named action with its arguments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Invocation {
+    pub name: String,
+    pub args: Vec<i32>,
+}
+
+impl Invocation {
+    pub fn new(name: impl Into<String>, args: Vec<i32>) -> Self {
+        Self {
+            name: name.into(),
+            args,
+        }
+    }
+
+    /// Runs the action. Invocations that got built by hand instead of being parsed may name an
+    /// action that doesn't exist or pass the wrong amount of arguments, which is an error.
+    pub fn dispatch(&self, state: &mut AppState) -> SystemResult {
+        let action = lookup(&self.name, &self.args).map_err(SystemError::InvalidAction)?;
+
+        (action.handler)(state, &self.args)
+    }
+}
+
+/// Finds the action and makes sure that it gets the right amount of arguments.
+fn lookup(name: &str, args: &[i32]) -> Result<&'static ActionDef, String> {
+    let action = find(name).ok_or(format!("Unknown action '{}'", name))?;
+
+    if args.len() != action.arg_count {
+        return Err(format!(
+            "The action '{}' expects {} argument(s), but got {}",
+            action.name,
+            action.arg_count,
+            args.len()
+        ));
+    }
+
+    Ok(action)
+}
+
+/// Parses either a single action or a pipeline and returns the event that runs it.
+pub fn parse_command(command: &str) -> Result<Event, String> {
+    if pipeline::is_pipeline(command) {
+        command.parse().map(Event::RunPipeline)
+    } else {
+        command.parse().map(Event::RunAction)
+    }
+}
+
+/// Splits a call like `move_to_workspace(3)` into its name and its numeric arguments.
+fn parse_call(s: &str) -> Result<(&str, Vec<i32>), String> {
+    let s = s.trim();
+    let (name, args) = match s.find('(') {
+        Some(idx) if s.ends_with(')') => (&s[..idx], &s[idx + 1..s.len() - 1]),
+        Some(_) => return Err(format!("Invalid action '{}'", s)),
+        None => (s, ""),
+    };
+    let name = name.trim();
+
+    let args = args
+        .split(',')
+        .map(|arg| arg.trim())
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| {
+            arg.parse::<i32>()
+                .map_err(|_| format!("Invalid argument '{}' for '{}'", arg, name))
+        })
+        .collect::<Result<Vec<i32>, String>>()?;
+
+    Ok((name, args))
+}
+
+fn write_call(f: &mut fmt::Formatter<'_>, name: &str, args: &[i32]) -> fmt::Result {
+    if args.is_empty() {
+        f.write_str(name)
+    } else {
+        let args = args
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<String>>();
+
+        write!(f, "{}({})", name, args.join(", "))
+    }
+}
+
+impl FromStr for Invocation {
+    type Err = String;
+
+    /// Parses the invocation and makes sure that the action exists and gets the right amount of
+    /// arguments.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, args) = parse_call(s)?;
+        let action = lookup(name, &args)?;
+
+        Ok(Invocation::new(action.name, args))
+    }
+}
+
+impl fmt::Display for Invocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_call(f, &self.name, &self.args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, system::mock::MockSystem};
+    use std::sync::Arc;
+
+    #[test]
+    fn parse_invocations() {
+        assert_eq!(
+            "focus_left".parse::<Invocation>(),
+            Ok(Invocation::new("focus_left", vec![]))
+        );
+        assert_eq!(
+            " move_to_workspace( 3 ) ".parse::<Invocation>(),
+            Ok(Invocation::new("move_to_workspace", vec![3]))
+        );
+        assert!("focus_sideways".parse::<Invocation>().is_err());
+        assert!("move_to_workspace".parse::<Invocation>().is_err());
+        assert!("move_to_workspace(three)".parse::<Invocation>().is_err());
+        assert!("focus_left(1)".parse::<Invocation>().is_err());
+    }
+
+    #[test]
+    fn dispatching_an_invalid_invocation_fails() {
+        let system = Arc::new(MockSystem::default().with_display(0, 0, 1920, 1080));
+        let mut state = AppState::with_system(Config::default(), system);
+
+        for invocation in &[
+            Invocation::new("focus_sideways", vec![]),
+            Invocation::new("change_workspace", vec![]),
+            Invocation::new("focus_left", vec![1]),
+        ] {
+            match invocation.dispatch(&mut state) {
+                Err(SystemError::InvalidAction(_)) => {}
+                res => panic!("Expected {} to be invalid, got {:?}", invocation, res),
+            }
+        }
+    }
+
+    #[test]
+    fn display_roundtrip() {
+        let invocation = Invocation::new("resize_left", vec![-2]);
+
+        assert_eq!(invocation.to_string(), "resize_left(-2)");
+        assert_eq!(invocation.to_string().parse::<Invocation>(), Ok(invocation));
+    }
+
+    #[test]
+    fn action_names_are_unique() {
+        for action in all() {
+            assert_eq!(all().iter().filter(|a| a.name == action.name).count(), 1);
+        }
+    }
+}
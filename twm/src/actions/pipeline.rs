@@ -0,0 +1,307 @@
+//! A pipeline runs a query and applies one or more actions to every item the query produced, for
+//! example `workspace_windows(3) | move_to_workspace(5)` moves every window of workspace 3 to
+//! workspace 5.
+//!
+//! Each query produces either windows or workspaces, which means that only actions that can be
+//! applied to the items of the query can follow it. This gets checked while parsing the pipeline,
+//! so a pipeline that got parsed successfully can always run.
+use super::{find, parse_call, write_call, Invocation};
+use crate::{
+    system::{SystemResult, WindowId},
+    AppState,
+};
+use lazy_static::lazy_static;
+use std::{fmt, str::FromStr};
+
+/// Separates the stages of a pipeline
+pub const SEPARATOR: char = '|';
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CollectionKind {
+    Windows,
+    Workspaces,
+}
+
+impl fmt::Display for CollectionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CollectionKind::Windows => "windows",
+            CollectionKind::Workspaces => "workspaces",
+        })
+    }
+}
+
+/// The items a query produced
+#[derive(Debug, Clone, PartialEq)]
+pub enum Collection {
+    Windows(Vec<WindowId>),
+    /// The ids of the workspaces
+    Workspaces(Vec<i32>),
+}
+
+pub struct QueryDef {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// How many numeric arguments the query expects
+    pub arg_count: usize,
+    pub produces: CollectionKind,
+    handler: fn(&AppState, &[i32]) -> Collection,
+}
+
+impl QueryDef {
+    fn new(
+        name: &'static str,
+        description: &'static str,
+        arg_count: usize,
+        produces: CollectionKind,
+        handler: fn(&AppState, &[i32]) -> Collection,
+    ) -> Self {
+        Self {
+            name,
+            description,
+            arg_count,
+            produces,
+            handler,
+        }
+    }
+}
+
+fn create_queries() -> Vec<QueryDef> {
+    vec![
+        QueryDef::new(
+            "windows",
+            "Every managed window",
+            0,
+            CollectionKind::Windows,
+            |s, _| {
+                Collection::Windows(
+                    s.get_grids()
+                        .iter()
+                        .flat_map(|g| g.get_window_ids())
+                        .collect(),
+                )
+            },
+        ),
+        QueryDef::new(
+            "workspace_windows",
+            "The windows of the workspace",
+            1,
+            CollectionKind::Windows,
+            |s, args| {
+                Collection::Windows(
+                    s.get_grid_by_id(args[0])
+                        .map(|g| g.get_window_ids())
+                        .unwrap_or_default(),
+                )
+            },
+        ),
+        QueryDef::new(
+            "workspaces",
+            "Every workspace that has windows",
+            0,
+            CollectionKind::Workspaces,
+            |s, _| {
+                Collection::Workspaces(
+                    s.get_grids()
+                        .iter()
+                        .filter(|g| !g.is_empty())
+                        .map(|g| g.id)
+                        .collect(),
+                )
+            },
+        ),
+    ]
+}
+
+lazy_static! {
+    static ref QUERIES: Vec<QueryDef> = create_queries();
+}
+
+pub fn all_queries() -> &'static [QueryDef] {
+    &QUERIES
+}
+
+pub fn find_query(name: &str) -> Option<&'static QueryDef> {
+    QUERIES.iter().find(|q| q.name == name)
+}
+
+/// A call of a query with its arguments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub name: String,
+    pub args: Vec<i32>,
+}
+
+impl Query {
+    pub fn run(&self, state: &AppState) -> Option<Collection> {
+        find_query(&self.name).map(|query| (query.handler)(state, &self.args))
+    }
+}
+
+impl FromStr for Query {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, args) = parse_call(s)?;
+        let query = find_query(name).ok_or(format!("Unknown query '{}'", name))?;
+
+        if args.len() != query.arg_count {
+            return Err(format!(
+                "The query '{}' expects {} argument(s), but got {}",
+                query.name,
+                query.arg_count,
+                args.len()
+            ));
+        }
+
+        Ok(Query {
+            name: query.name.into(),
+            args,
+        })
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_call(f, &self.name, &self.args)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pipeline {
+    pub query: Query,
+    /// Every action gets applied to each item of the query before the next action runs
+    pub actions: Vec<Invocation>,
+}
+
+/// Whether the command is a pipeline instead of a single action
+pub fn is_pipeline(command: &str) -> bool {
+    command.contains(SEPARATOR)
+}
+
+impl Pipeline {
+    pub fn run(&self, state: &mut AppState) -> SystemResult {
+        let collection = match self.query.run(state) {
+            Some(collection) => collection,
+            None => return Ok(()),
+        };
+
+        for invocation in &self.actions {
+            let action = match find(&invocation.name) {
+                Some(action) => action,
+                None => continue,
+            };
+
+            match (&collection, action.window_handler, action.workspace_handler) {
+                (Collection::Windows(ids), Some(handler), _) => {
+                    for id in ids {
+                        handler(state, *id, &invocation.args)?;
+                    }
+                }
+                (Collection::Workspaces(ids), _, Some(handler)) => {
+                    for id in ids {
+                        handler(state, *id, &invocation.args)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Pipeline {
+    type Err = String;
+
+    /// Parses the pipeline and makes sure that every action can be applied to the items of the
+    /// query.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut stages = s.split(SEPARATOR);
+        let query = stages.next().unwrap_or_default().parse::<Query>()?;
+        let produces = find_query(&query.name).unwrap().produces;
+
+        let actions = stages
+            .map(|stage| {
+                let invocation = stage.parse::<Invocation>()?;
+                let action = find(&invocation.name).unwrap();
+
+                if action.accepts(produces) {
+                    Ok(invocation)
+                } else {
+                    Err(format!(
+                        "The action '{}' can't be applied to {}",
+                        action.name, produces
+                    ))
+                }
+            })
+            .collect::<Result<Vec<Invocation>, String>>()?;
+
+        if actions.is_empty() {
+            return Err(format!(
+                "The pipeline '{}' doesn't have any actions",
+                s.trim()
+            ));
+        }
+
+        Ok(Pipeline { query, actions })
+    }
+}
+
+impl fmt::Display for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.query)?;
+
+        for action in &self.actions {
+            write!(f, " {} {}", SEPARATOR, action)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_pipelines() {
+        let pipeline = "workspace_windows(3) | move_to_workspace(5) | minimize_window"
+            .parse::<Pipeline>()
+            .unwrap();
+
+        assert_eq!(
+            pipeline.query,
+            Query {
+                name: "workspace_windows".into(),
+                args: vec![3]
+            }
+        );
+        assert_eq!(
+            pipeline.actions,
+            vec![
+                Invocation::new("move_to_workspace", vec![5]),
+                Invocation::new("minimize_window", vec![])
+            ]
+        );
+        assert_eq!(
+            pipeline.to_string(),
+            "workspace_windows(3) | move_to_workspace(5) | minimize_window"
+        );
+    }
+
+    #[test]
+    fn reject_invalid_pipelines() {
+        assert!("windows".parse::<Pipeline>().is_err());
+        assert!("windows |".parse::<Pipeline>().is_err());
+        assert!("focus_left | close_window".parse::<Pipeline>().is_err());
+        assert!("workspace_windows | close_window"
+            .parse::<Pipeline>()
+            .is_err());
+        assert!("windows | focus_left".parse::<Pipeline>().is_err());
+        assert!("workspaces | close_window".parse::<Pipeline>().is_err());
+        assert!("workspaces | move_workspace_to_monitor(1)"
+            .parse::<Pipeline>()
+            .is_ok());
+    }
+}
@@ -5,10 +5,17 @@ use item::Item;
 use item_section::ItemSection;
 use parking_lot::Mutex;
 
+pub mod auto_hide;
 pub mod component;
 pub mod create;
+pub mod drag;
+pub mod font;
+pub mod gauge;
+pub mod idle;
 pub mod item;
 pub mod item_section;
+pub mod layout;
+pub mod tooltip;
 
 #[derive(Clone, Debug)]
 pub struct Bar {
@@ -17,6 +24,14 @@ pub struct Bar {
     pub left: ItemSection,
     pub center: ItemSection,
     pub right: ItemSection,
+    /// Whether the bar got hidden to make room for the workspace
+    pub hidden: bool,
+    /// The height of the bar, which can be overridden per display
+    pub height: i32,
+    /// The color the background of the bar got painted with
+    pub color: i32,
+    /// The font the bar uses, which is the first installed font of its list
+    pub font: String,
 }
 
 impl Default for Bar {
@@ -27,6 +42,10 @@ impl Default for Bar {
             left: ItemSection::default(),
             center: ItemSection::default(),
             right: ItemSection::default(),
+            hidden: false,
+            height: 0,
+            color: 0,
+            font: String::new(),
         }
     }
 }
@@ -57,6 +76,8 @@ pub fn close_all(state_arc: Arc<Mutex<AppState>>) {
         d.appbar = None;
     }
 
+    let _ = tooltip::close();
+
     for w in windows {
         w.close();
     }
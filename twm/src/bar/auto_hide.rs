@@ -0,0 +1,85 @@
+//! Hides the bar once the mouse didn't touch it for a while and reveals it again when the mouse
+//! touches the top edge of the display.
+use crate::{
+    event::Event,
+    state_handle::StateHandle,
+    system::{api, DisplayId},
+    AppState,
+};
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// When the mouse touched the bar of each display for the last time and whether the bar was
+/// hidden at the last check
+#[derive(Debug, Default)]
+struct Tracker {
+    last_touched: HashMap<DisplayId, Instant>,
+    was_hidden: HashMap<DisplayId, bool>,
+}
+
+fn tick(state: &AppState, tracker: &mut Tracker) {
+    if !state.work_mode || !state.config.bar.auto_hide {
+        return;
+    }
+
+    let delay = Duration::from_millis(state.config.bar.auto_hide_delay as u64);
+    let (x, y) = api::get_cursor_pos();
+    let now = Instant::now();
+
+    for display in &state.displays {
+        let bar = match display.appbar.as_ref() {
+            Some(bar) => bar,
+            None => continue,
+        };
+
+        let touches_edge =
+            y == display.rect.top && x >= display.rect.left && x < display.rect.right;
+        let touches_bar = !bar.hidden
+            && bar
+                .window
+                .get_native_window()
+                .get_rect()
+                .map(|rect| rect.contains(x, y))
+                .unwrap_or(false);
+        // the bar could also have been revealed by a keybinding
+        let got_revealed = tracker.was_hidden.get(&display.id) == Some(&true) && !bar.hidden;
+
+        let last_touch = tracker.last_touched.entry(display.id).or_insert(now);
+
+        if touches_edge || touches_bar || got_revealed {
+            *last_touch = now;
+        }
+
+        let hidden = if bar.hidden {
+            !touches_edge
+        } else {
+            now - *last_touch >= delay
+        };
+
+        tracker.was_hidden.insert(display.id, bar.hidden);
+
+        if hidden != bar.hidden {
+            state
+                .event_channel
+                .sender
+                .send(Event::SetAppbarHidden(display.id, hidden))
+                .expect("Failed to send SetAppbarHidden event");
+        }
+    }
+}
+
+pub fn start(handle: StateHandle) {
+    let tracker = Arc::new(Mutex::new(Tracker::default()));
+
+    thread::spawn(move || loop {
+        sleep!(100);
+
+        let tracker = tracker.clone();
+        handle.update(move |state| tick(state, &mut tracker.lock()));
+    });
+}
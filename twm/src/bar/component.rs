@@ -1,25 +1,92 @@
+use super::{
+    gauge::{Gauge, GaugeShape},
+    layout::{Alignment, ComponentLayout},
+};
 use crate::{display::Display, system::DisplayId, AppState};
 use interpreter::{Dynamic, Function, Interpreter, RuntimeError, RuntimeResult};
 use parking_lot::Mutex;
-use std::{any::Any, collections::HashMap, fmt::Debug, sync::Arc};
+use std::{
+    any::Any,
+    collections::HashMap,
+    fmt::{self, Debug},
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 pub mod active_mode;
 pub mod current_window;
 pub mod date;
 pub mod fullscreen_indicator;
+pub mod keyboard_layout;
+pub mod layout_indicator;
 pub mod padding;
 pub mod split_direction;
+pub mod suspended_indicator;
 pub mod time;
+pub mod volume;
+pub mod window_count;
+pub mod workspace_meta;
 pub mod workspaces;
 
 pub const LOCK_TIMEOUT: u64 = 20;
 
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+fn next_id() -> usize {
+    NEXT_ID.fetch_add(1, Ordering::SeqCst)
+}
+
+/// What a component does while the system is idle
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IdlePolicy {
+    /// Renders at the slow cadence of `idle_interval`
+    Throttle,
+    /// Keeps showing the last result until the system is used again, which is meant for
+    /// components that talk to the network
+    Suspend,
+    /// Renders as often as usual
+    Ignore,
+}
+
+impl Default for IdlePolicy {
+    fn default() -> Self {
+        Self::Throttle
+    }
+}
+
+impl FromStr for IdlePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "throttle" => Ok(Self::Throttle),
+            "suspend" => Ok(Self::Suspend),
+            "ignore" => Ok(Self::Ignore),
+            _ => Err(format!("Unknown idle policy '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for IdlePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Throttle => "throttle",
+            Self::Suspend => "suspend",
+            Self::Ignore => "ignore",
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ComponentText {
     pub display_text: String,
     pub value: Arc<Box<dyn Any + Sync + Send>>,
     pub foreground_color: i32,
     pub background_color: i32,
+    /// Gets drawn instead of the display text
+    pub gauge: Option<Gauge>,
 }
 
 impl ComponentText {
@@ -29,6 +96,7 @@ impl ComponentText {
             value: Arc::new(Box::new(())),
             foreground_color: 0,
             background_color: 0,
+            gauge: None,
         }
     }
     pub fn with_display_text(mut self, value: String) -> Self {
@@ -47,12 +115,117 @@ impl ComponentText {
         self.background_color = value;
         self
     }
+    pub fn with_gauge(mut self, value: Gauge) -> Self {
+        self.gauge = Some(value);
+        self
+    }
+}
+
+/// Reads a gauge from the fields of a render result like `#{ gauge: "arc", value: 30 }`. The
+/// optional `text` field becomes the display text, which isn't drawn but labels the gauge while it
+/// gets dragged.
+pub fn gauge_from_fields(fields: &HashMap<String, Dynamic>) -> RuntimeResult<ComponentText> {
+    let mut gauge = Gauge::default();
+    let field = |name: &str| fields.get(name).filter(|x| !x.is_null());
+
+    if let Some(x) = field("gauge") {
+        gauge.shape = string!(x)?.parse::<GaugeShape>()?;
+    }
+
+    gauge.value = *number!(field("value").ok_or("A gauge has to have a value field")?)?;
+
+    if let Some(x) = field("max") {
+        gauge.max = *number!(x)?;
+    }
+    if let Some(x) = field("width") {
+        gauge.width = (*number!(x)?).max(0);
+    }
+    if let Some(x) = field("height") {
+        gauge.height = (*number!(x)?).max(0);
+    }
+    if let Some(x) = field("color") {
+        gauge.color = *number!(x)?;
+    }
+    if let Some(x) = field("background") {
+        gauge.background_color = *number!(x)?;
+    }
+
+    let text = match field("text") {
+        Some(x) => string!(x)?.clone(),
+        None => String::new(),
+    };
+
+    Ok(ComponentText::new()
+        .with_display_text(text)
+        .with_gauge(gauge))
+}
+
+fn gauge_to_dynamic(text: &ComponentText, gauge: &Gauge) -> Dynamic {
+    let mut fields: HashMap<String, Dynamic> = HashMap::new();
+
+    fields.insert("gauge".into(), gauge.shape.to_string().into());
+    fields.insert("value".into(), gauge.value.into());
+    fields.insert("max".into(), gauge.max.into());
+    fields.insert("width".into(), gauge.width.into());
+    fields.insert("height".into(), gauge.height.into());
+    fields.insert("color".into(), gauge.color.into());
+    fields.insert("background".into(), gauge.background_color.into());
+    fields.insert("text".into(), text.display_text.clone().into());
+
+    fields.into()
+}
+
+/// Reads the `padding`, `min_width` and `align` fields of a component. The padding is either a
+/// single number for both sides or an array of the left and the right padding.
+pub fn layout_from_fields(fields: &HashMap<String, Dynamic>) -> RuntimeResult<ComponentLayout> {
+    let mut layout = ComponentLayout::default();
+
+    match fields.get("padding") {
+        Some(Dynamic::Null) | None => {}
+        Some(Dynamic::Number(x)) => {
+            layout.padding_left = *x;
+            layout.padding_right = *x;
+        }
+        Some(x) => {
+            let items = array!(x)?;
+            let items = items.lock().unwrap();
+            let side = |idx: usize| -> RuntimeResult<i32> {
+                let value = items
+                    .get(idx)
+                    .ok_or("The padding array needs a left and a right padding")?;
+
+                Ok(*number!(value)?)
+            };
+
+            layout.padding_left = side(0)?;
+            layout.padding_right = side(1)?;
+        }
+    }
+
+    if let Some(x) = fields.get("min_width").filter(|x| !x.is_null()) {
+        layout.min_width = *number!(x)?;
+    }
+
+    if let Some(x) = fields.get("align").filter(|x| !x.is_null()) {
+        layout.align = string!(x)?.parse::<Alignment>()?;
+    }
+
+    Ok(layout)
 }
 
 #[derive(Clone)]
 pub struct Component {
+    /// Identifies the component, even after it got moved to another position of the bar
+    pub id: usize,
     pub name: String,
     pub is_clickable: bool,
+    /// The minimum amount of milliseconds between two renders. Components without an interval get
+    /// rendered every time the bar gets drawn.
+    pub interval: Option<u64>,
+    pub idle_policy: IdlePolicy,
+    pub layout: ComponentLayout,
+    /// The last result of each display and when it got rendered
+    cache: Arc<Mutex<HashMap<DisplayId, (Instant, Vec<ComponentText>)>>>,
     render_fn: Arc<dyn Fn(DisplayId) -> RuntimeResult<Vec<ComponentText>> + Send + Sync>,
     on_click_fn: Option<
         Arc<
@@ -61,15 +234,47 @@ pub struct Component {
                 + Sync,
         >,
     >,
+    /// Returns the text of a tooltip that gets shown while hovering over the component
+    on_hover_fn: Option<
+        Arc<
+            dyn Fn(
+                    DisplayId,
+                    Arc<Box<dyn Any + Send + Sync>>,
+                    usize,
+                ) -> RuntimeResult<Option<String>>
+                + Send
+                + Sync,
+        >,
+    >,
+    /// Gets called when a text of the component got dragged onto the bar of another display
+    on_drop_fn: Option<
+        Arc<
+            dyn Fn(
+                    DisplayId,
+                    Arc<Box<dyn Any + Send + Sync>>,
+                    usize,
+                    DisplayId,
+                ) -> RuntimeResult<()>
+                + Send
+                + Sync,
+        >,
+    >,
 }
 
 impl Default for Component {
     fn default() -> Self {
         Self {
+            id: next_id(),
             name: "Default".into(),
             is_clickable: false,
+            interval: None,
+            idle_policy: IdlePolicy::default(),
+            layout: ComponentLayout::default(),
+            cache: Arc::new(Mutex::new(HashMap::new())),
             render_fn: Arc::new(|_| Ok(vec![])),
             on_click_fn: None,
+            on_hover_fn: None,
+            on_drop_fn: None,
         }
     }
 }
@@ -80,10 +285,17 @@ impl Component {
         render_fn: impl Fn(DisplayId) -> RuntimeResult<Vec<ComponentText>> + Send + Sync + 'static,
     ) -> Self {
         Self {
+            id: next_id(),
             name: name.into(),
             is_clickable: false,
+            interval: None,
+            idle_policy: IdlePolicy::default(),
+            layout: ComponentLayout::default(),
+            cache: Arc::new(Mutex::new(HashMap::new())),
             render_fn: Arc::new(render_fn),
             on_click_fn: None,
+            on_hover_fn: None,
+            on_drop_fn: None,
         }
     }
 
@@ -102,6 +314,18 @@ impl Component {
 
         let on_click_fn = obj.get("on_click");
 
+        let interval = match obj.get("interval") {
+            Some(Dynamic::Null) | None => None,
+            Some(x) => Some((*number!(x)?).max(0) as u64),
+        };
+
+        let idle_policy = match obj.get("idle") {
+            Some(Dynamic::Null) | None => IdlePolicy::default(),
+            Some(x) => string!(x)?.parse::<IdlePolicy>()?,
+        };
+
+        let layout = layout_from_fields(&obj)?;
+
         let i2 = i.clone();
 
         let mut comp = Component::new(name, move |display_id| {
@@ -122,9 +346,10 @@ impl Component {
                             .with_foreground_color(*number!(&items[1])?)
                             .with_background_color(*number!(&items[2])?)
                     }
+                    Dynamic::Object(x) => gauge_from_fields(&x.lock().unwrap())?,
                     x => {
                         return Err(RuntimeError::UnexpectedType {
-                            expected: "String | Array".into(),
+                            expected: "String | Array | Object".into(),
                             actual: x.type_name(),
                         })
                     }
@@ -134,6 +359,10 @@ impl Component {
             Ok(rendered)
         });
 
+        comp.interval = interval;
+        comp.idle_policy = idle_policy;
+        comp.layout = layout;
+
         if let Some(f) = on_click_fn {
             let f = f.clone().as_fn()?;
             let i2 = i.clone();
@@ -152,7 +381,24 @@ impl Component {
     pub fn into_dynamic(&self, state_arc: Arc<Mutex<AppState>>) -> Dynamic {
         let mut fields: HashMap<String, Dynamic> = HashMap::new();
 
+        fields.insert("id".into(), self.id.into());
         fields.insert("name".into(), self.name.clone().into());
+        fields.insert(
+            "interval".into(),
+            self.interval
+                .map(|x| Dynamic::from(x as i32))
+                .unwrap_or(Dynamic::Null),
+        );
+        fields.insert("idle".into(), self.idle_policy.to_string().into());
+        fields.insert(
+            "padding".into(),
+            Dynamic::new_array(vec![
+                self.layout.padding_left.into(),
+                self.layout.padding_right.into(),
+            ]),
+        );
+        fields.insert("min_width".into(), self.layout.min_width.into());
+        fields.insert("align".into(), self.layout.align.to_string().into());
 
         let render_fn = self.render_fn.clone();
         fields.insert(
@@ -162,7 +408,9 @@ impl Component {
                 Ok((render_fn)(DisplayId(display_id))?
                     .iter()
                     .map(|x| {
-                        if x.foreground_color == 0 && x.background_color == 0 {
+                        if let Some(gauge) = x.gauge.as_ref() {
+                            gauge_to_dynamic(x, gauge)
+                        } else if x.foreground_color == 0 && x.background_color == 0 {
                             x.display_text.clone().into()
                         } else {
                             Dynamic::new_array(vec![
@@ -212,12 +460,87 @@ impl Component {
         Ok(())
     }
 
+    pub fn has_hover(&self) -> bool {
+        self.on_hover_fn.is_some()
+    }
+
+    pub fn on_hover(
+        &self,
+        display_id: DisplayId,
+        value: Arc<Box<dyn Any + Send + Sync>>,
+        idx: usize,
+    ) -> RuntimeResult<Option<String>> {
+        match self.on_hover_fn.clone() {
+            Some(f) => f(display_id, value, idx),
+            None => Ok(None),
+        }
+    }
+
+    pub fn is_draggable(&self) -> bool {
+        self.on_drop_fn.is_some()
+    }
+
+    pub fn on_drop(
+        &self,
+        display_id: DisplayId,
+        value: Arc<Box<dyn Any + Send + Sync>>,
+        idx: usize,
+        target: DisplayId,
+    ) -> RuntimeResult<()> {
+        if let Some(f) = self.on_drop_fn.clone() {
+            f(display_id, value, idx, target)?;
+        }
+
+        Ok(())
+    }
+
     pub fn render(&self, display_id: DisplayId) -> RuntimeResult<Vec<ComponentText>> {
         let f = self.render_fn.clone();
 
         f(display_id)
     }
 
+    /// How long the last result can be shown before the component has to render again. `None`
+    /// means that the result can be shown until the system stops being idle.
+    fn max_age(&self, idle_interval: Option<u64>) -> Option<Duration> {
+        let interval = self.interval.unwrap_or(0);
+
+        match (idle_interval, self.idle_policy) {
+            (Some(_), IdlePolicy::Suspend) => None,
+            (Some(idle_interval), IdlePolicy::Throttle) => {
+                Some(Duration::from_millis(interval.max(idle_interval)))
+            }
+            _ => Some(Duration::from_millis(interval)),
+        }
+    }
+
+    /// Renders the component, unless its last result is recent enough to be reused. The idle
+    /// interval is only `Some` while the system is idle.
+    pub fn render_throttled(
+        &self,
+        display_id: DisplayId,
+        idle_interval: Option<u64>,
+    ) -> RuntimeResult<Vec<ComponentText>> {
+        let max_age = self.max_age(idle_interval);
+
+        if let Some((rendered_at, texts)) = self.cache.lock().get(&display_id) {
+            if max_age
+                .map(|age| rendered_at.elapsed() < age)
+                .unwrap_or(true)
+            {
+                return Ok(texts.clone());
+            }
+        }
+
+        let texts = self.render(display_id)?;
+
+        self.cache
+            .lock()
+            .insert(display_id, (Instant::now(), texts.clone()));
+
+        Ok(texts)
+    }
+
     pub fn with_on_click(
         &mut self,
         f: impl Fn(DisplayId, Arc<Box<dyn Any + Send + Sync>>, usize) -> RuntimeResult<()>
@@ -229,13 +552,61 @@ impl Component {
         self.on_click_fn = Some(Arc::new(f));
         self
     }
+
+    pub fn with_on_hover(
+        &mut self,
+        f: impl Fn(DisplayId, Arc<Box<dyn Any + Send + Sync>>, usize) -> RuntimeResult<Option<String>>
+            + Send
+            + Sync
+            + 'static,
+    ) -> &mut Self {
+        self.on_hover_fn = Some(Arc::new(f));
+        self
+    }
+
+    pub fn with_on_drop(
+        &mut self,
+        f: impl Fn(DisplayId, Arc<Box<dyn Any + Send + Sync>>, usize, DisplayId) -> RuntimeResult<()>
+            + Send
+            + Sync
+            + 'static,
+    ) -> &mut Self {
+        self.on_drop_fn = Some(Arc::new(f));
+        self
+    }
 }
 
 impl Debug for Component {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str(&format!(
-            "Component(name: {}, clickable: {})",
-            self.name, self.is_clickable
+            "Component(id: {}, name: {}, clickable: {})",
+            self.id, self.name, self.is_clickable
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn max_age_depends_on_idle_policy() {
+        let mut component = Component::default();
+        component.interval = Some(1000);
+
+        assert_eq!(component.max_age(None), Some(Duration::from_millis(1000)));
+        assert_eq!(
+            component.max_age(Some(5000)),
+            Some(Duration::from_millis(5000))
+        );
+
+        component.idle_policy = IdlePolicy::Suspend;
+        assert_eq!(component.max_age(Some(5000)), None);
+
+        component.idle_policy = IdlePolicy::Ignore;
+        assert_eq!(
+            component.max_age(Some(5000)),
+            Some(Duration::from_millis(1000))
+        );
+    }
+}
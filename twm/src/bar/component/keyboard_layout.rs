@@ -0,0 +1,14 @@
+use super::{Component, ComponentText};
+use crate::system::api;
+
+/// Renders the language of the keyboard layout that the focused window uses, e.g. `DE` for
+/// `de-DE`.
+pub fn create() -> Component {
+    Component::new("KeyboardLayout", move |_| {
+        let text = api::get_keyboard_layout()
+            .and_then(|locale| locale.split('-').next().map(str::to_uppercase))
+            .unwrap_or_default();
+
+        Ok(vec![ComponentText::new().with_display_text(text)])
+    })
+}
@@ -0,0 +1,59 @@
+use super::{Component, ComponentText};
+use crate::{
+    direction::Direction, snapshot, snapshot::WorkspaceSnapshot, split_direction::SplitDirection,
+};
+
+/// The texts that describe the layout mode of the focused workspace
+#[derive(Debug, Clone)]
+pub struct LayoutLabels {
+    pub tiling: String,
+    pub fullscreen: String,
+}
+
+impl Default for LayoutLabels {
+    fn default() -> Self {
+        Self {
+            tiling: "tiling".into(),
+            fullscreen: "fullscreen".into(),
+        }
+    }
+}
+
+/// Returns the line that separates the focused tile from the next window, followed by an arrow
+/// that points to where the next window appears.
+fn axis(workspace: &WorkspaceSnapshot) -> &'static str {
+    let before = matches!(workspace.next_direction, Direction::Left | Direction::Up);
+
+    match (workspace.next_axis, before) {
+        (SplitDirection::Vertical, false) => "│→",
+        (SplitDirection::Vertical, true) => "←│",
+        (SplitDirection::Horizontal, false) => "─↓",
+        (SplitDirection::Horizontal, true) => "↑─",
+    }
+}
+
+fn text(workspace: &WorkspaceSnapshot, labels: &LayoutLabels) -> String {
+    let mode = if workspace.is_fullscreened {
+        &labels.fullscreen
+    } else {
+        &labels.tiling
+    };
+
+    if mode.is_empty() {
+        axis(workspace).into()
+    } else {
+        format!("{} {}", axis(workspace), mode)
+    }
+}
+
+pub fn create(labels: LayoutLabels) -> Component {
+    Component::new("LayoutIndicator", move |display_id| {
+        Ok(vec![ComponentText::new().with_display_text(
+            snapshot::get()
+                .get_display_by_id(display_id)
+                .and_then(|d| d.get_focused_workspace())
+                .map(|w| text(w, &labels))
+                .unwrap_or("".into()),
+        )])
+    })
+}
@@ -0,0 +1,14 @@
+use super::{Component, ComponentText};
+use crate::snapshot;
+
+pub fn create(indicator: String) -> Component {
+    Component::new("SuspendedIndicator", move |_| {
+        Ok(vec![ComponentText::new().with_display_text(
+            if snapshot::get().is_suspended {
+                indicator.clone()
+            } else {
+                "".into()
+            },
+        )])
+    })
+}
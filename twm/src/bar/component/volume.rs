@@ -0,0 +1,21 @@
+use super::{Component, ComponentText};
+use crate::system::{audio, MediaKey};
+
+/// Renders the volume of the default output device or the muted text while it is muted. Clicking
+/// it toggles the mute.
+pub fn create(muted_text: String) -> Component {
+    Component::new("Volume", move |_| {
+        let text = match audio::get_volume() {
+            Ok((_, true)) => muted_text.clone(),
+            Ok((level, false)) => format!("{}%", level),
+            Err(_) => "".into(),
+        };
+
+        Ok(vec![ComponentText::new().with_display_text(text)])
+    })
+    .with_on_click(|_, _, _| {
+        audio::press(MediaKey::Mute);
+        Ok(())
+    })
+    .to_owned()
+}
@@ -0,0 +1,58 @@
+use super::{Component, ComponentText};
+use crate::snapshot;
+
+/// Formats the number of windows of each workspace, given as `(workspace number, count)`, followed
+/// by the total. The compact format only shows the total and how many workspaces have windows.
+fn format_counts(counts: &[(i32, usize)], compact: bool) -> String {
+    let total: usize = counts.iter().map(|(_, count)| count).sum();
+
+    if compact {
+        return format!("{}/{}", total, counts.len());
+    }
+
+    if counts.is_empty() {
+        return total.to_string();
+    }
+
+    counts
+        .iter()
+        .map(|(number, count)| format!("{}:{}", number, count))
+        .chain(std::iter::once(format!("| {}", total)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn create(compact: bool) -> Component {
+    Component::new("WindowCount", move |display_id| {
+        Ok(vec![ComponentText::new().with_display_text(
+            snapshot::get()
+                .get_display_by_id(display_id)
+                .map(|d| {
+                    let mut counts: Vec<(i32, usize)> = d
+                        .workspaces
+                        .iter()
+                        .filter(|w| w.window_count > 0)
+                        .map(|w| (w.number, w.window_count))
+                        .collect();
+                    counts.sort_by_key(|(number, _)| *number);
+
+                    format_counts(&counts, compact)
+                })
+                .unwrap_or("".into()),
+        )])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_window_counts() {
+        let counts = vec![(1, 3), (2, 1), (5, 2)];
+
+        assert_eq!(format_counts(&counts, false), "1:3 2:1 5:2 | 6");
+        assert_eq!(format_counts(&counts, true), "6/3");
+        assert_eq!(format_counts(&[], false), "0");
+    }
+}
@@ -0,0 +1,14 @@
+use super::{Component, ComponentText};
+use crate::{snapshot, tile_grid::meta};
+
+pub fn create(key: String) -> Component {
+    Component::new("WorkspaceMeta", move |display_id| {
+        Ok(vec![ComponentText::new().with_display_text(
+            snapshot::get()
+                .get_display_by_id(display_id)
+                .and_then(|d| d.focused_workspace_id)
+                .and_then(|id| meta::get(id, &key))
+                .unwrap_or_default(),
+        )])
+    })
+}
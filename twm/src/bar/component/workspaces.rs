@@ -1,63 +1,75 @@
 use super::{Component, ComponentText};
-use crate::{util, AppState, Event};
+use crate::{snapshot, util, AppState, Event};
+use log::warn;
 use parking_lot::Mutex;
 use std::sync::Arc;
 use std::time::Duration;
 
 pub fn create(state_arc: Arc<Mutex<AppState>>) -> Component {
     let state_arc2 = state_arc.clone();
+    let state_arc3 = state_arc.clone();
     Component::new("Workspaces", move |display_id| {
-        if let Some(state) = state_arc.try_lock_for(Duration::from_millis(super::LOCK_TIMEOUT)) {
-            let light_theme = state.config.light_theme;
-            let workspace_settings = state.config.workspace_settings.clone();
-            let bar_color = state.config.bar.color;
+        let snapshot = snapshot::get();
+        let display = match snapshot.get_display_by_id(display_id) {
+            Some(x) => x,
+            None => return Ok(Vec::<ComponentText>::new()),
+        };
+        let focused_id = display.highlighted_workspace_id;
 
-            let mut grids = state
-                .get_display_by_id(display_id)
-                .unwrap()
-                .get_active_grids();
-            grids.sort_by_key(|g| g.id);
-
-            Ok(grids
-                .iter()
-                .map(|grid| {
-                    let factor = if light_theme {
-                        if state.workspace_id == grid.id {
-                            0.75
-                        } else {
-                            0.9
-                        }
+        Ok(display
+            .get_active_workspaces()
+            .iter()
+            .map(|workspace| {
+                let factor = if snapshot.light_theme {
+                    if focused_id == Some(workspace.id) {
+                        0.75
                     } else {
-                        if state.workspace_id == grid.id {
-                            2.0
-                        } else {
-                            1.5
-                        }
-                    };
-                    ComponentText::new()
-                        .with_display_text(
-                            workspace_settings
-                                .iter()
-                                .find(|s| s.id == grid.id)
-                                .map(|s| s.text.clone())
-                                .filter(|t| !t.is_empty())
-                                .unwrap_or(format!(" {} ", grid.id.to_string())),
-                        )
-                        .with_value(grid.id)
-                        .with_background_color(util::scale_color(bar_color, factor))
-                })
-                .collect())
-        } else {
-            Ok(Vec::<ComponentText>::new())
-        }
+                        0.9
+                    }
+                } else {
+                    if focused_id == Some(workspace.id) {
+                        2.0
+                    } else {
+                        1.5
+                    }
+                };
+                ComponentText::new()
+                    .with_display_text(workspace.text.clone())
+                    .with_value(workspace.id)
+                    .with_background_color(util::scale_color(display.bar_color, factor))
+            })
+            .collect())
     })
     .with_on_click(move |_, value, _| {
         let id = *value.downcast_ref::<i32>().unwrap();
-        state_arc2
-            .lock()
-            .event_channel
-            .sender
-            .send(Event::ChangeWorkspace(id, true));
+        let sender = state_arc.lock().event_channel.sender.clone();
+
+        if let Err(e) = sender.send(Event::ChangeWorkspace(id, true)) {
+            warn!("Failed to change to workspace {}: {}", id, e);
+        }
+
+        Ok(())
+    })
+    .with_on_hover(move |_, value, _| {
+        let id = *value.downcast_ref::<i32>().unwrap();
+
+        Ok(state_arc2
+            .try_lock_for(Duration::from_millis(super::LOCK_TIMEOUT))
+            .filter(|state| state.config.workspace_preview)
+            .and_then(|state| {
+                state
+                    .get_grid_by_id(id)
+                    .filter(|grid| !grid.is_empty())
+                    .map(|grid| grid.get_preview(32, 10))
+            }))
+    })
+    .with_on_drop(move |_, value, _, target| {
+        let id = *value.downcast_ref::<i32>().unwrap();
+        let sender = state_arc3.lock().event_channel.sender.clone();
+
+        if let Err(e) = sender.send(Event::MoveWorkspaceToDisplay(id, target)) {
+            warn!("Failed to move workspace {}: {}", id, e);
+        }
 
         Ok(())
     })
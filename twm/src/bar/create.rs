@@ -1,33 +1,87 @@
 use super::{
-    component::Component, component::ComponentText, item::Item, item_section::ItemSection, Bar,
+    component::Component, component::ComponentText, drag, drag::BarDrag, font, gauge::Gauge,
+    gauge::GaugeShape, idle, item::Item, item_section::ItemSection, tooltip, Bar,
 };
 use crate::{
     config::Config, display::Display, event::Event, system::DisplayId, system::Rectangle,
     window::Api, window::WindowEvent, AppState, NOG_BAR_NAME,
 };
 use interpreter::RuntimeResult;
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use parking_lot::Mutex;
 use std::sync::Arc;
 use std::time::Duration;
 
+/// Returns the display and the rectangle of the bar at the given position.
+fn bar_at(state: &AppState, x: i32, y: i32) -> Option<(DisplayId, Rectangle)> {
+    state.displays.iter().find_map(|d| {
+        d.appbar
+            .as_ref()
+            .and_then(|b| b.window.get_native_window().get_rect().ok())
+            .filter(|rect| rect.contains(x, y))
+            .map(|rect| (d.id, rect))
+    })
+}
+
+fn default_text_color(config: &Config) -> i32 {
+    if config.light_theme {
+        0x00333333
+    } else {
+        0x00ffffff
+    }
+}
+
+fn draw_gauge(api: &Api, rect: &Rectangle, config: &Config, gauge: &Gauge) {
+    let color = Some(gauge.color)
+        .filter(|x| *x > 0)
+        .unwrap_or_else(|| default_text_color(config));
+    let background = Some(gauge.background_color)
+        .filter(|x| *x > 0)
+        .unwrap_or(config.bar.color);
+    let (top, height) = gauge.vertical_bounds(rect.height());
+
+    match gauge.shape {
+        GaugeShape::Bar => {
+            api.fill_rect(rect.left, top, gauge.width, height, background);
+            api.fill_rect(rect.left, top, gauge.fill_width(), height, color);
+        }
+        GaugeShape::Arc => {
+            let left = rect.left + (gauge.width - height) / 2;
+
+            api.fill_pie(left, top, height, 1.0, background);
+            api.fill_pie(left, top, height, gauge.fraction(), color);
+        }
+    }
+}
+
+/// Returns how many pixels the text takes up in the bar
+fn component_text_width(api: &Api, component_text: &ComponentText) -> i32 {
+    match component_text.gauge.as_ref() {
+        Some(gauge) => gauge.width,
+        None => api
+            .calculate_text_rect(&component_text.display_text)
+            .width(),
+    }
+}
+
 fn draw_component_text(
     api: &Api,
     rect: &Rectangle,
     config: &Config,
     component_text: &ComponentText,
 ) {
+    if let Some(gauge) = component_text.gauge.as_ref() {
+        draw_gauge(api, rect, config, gauge);
+        return;
+    }
+
     if component_text.display_text.is_empty() {
         return;
     }
 
     let fg = Some(component_text.foreground_color)
         .filter(|x| *x > 0)
-        .unwrap_or(if config.light_theme {
-            0x00333333
-        } else {
-            0x00ffffff
-        });
+        .unwrap_or_else(|| default_text_color(config));
 
     let bg = Some(component_text.background_color)
         .filter(|x| *x > 0)
@@ -44,79 +98,93 @@ fn draw_component_text(
     )
 }
 
-fn draw_components(
-    api: &Api,
-    config: &Config,
-    display_id: DisplayId,
-    mut offset: i32,
-    components: &[Component],
-) -> RuntimeResult<()> {
-    for component in components {
-        let component_texts = component.render(display_id)?;
-
-        for (_i, component_text) in component_texts.iter().enumerate() {
-            let width = api
-                .calculate_text_rect(&component_text.display_text)
-                .width();
+fn draw_section(api: &Api, config: &Config, height: i32, section: &ItemSection) {
+    for item in section.items.iter() {
+        let text_width: i32 = item
+            .cached_result
+            .iter()
+            .map(|((left, right), _)| right - left)
+            .sum();
+
+        // the padding isn't covered by any text, so it would keep what got drawn there before
+        if item.right - item.left > text_width {
+            clear_section(api, config, height, item.left, item.right);
+        }
 
+        for ((left, right), component_text) in item.cached_result.iter() {
             let rect = Rectangle {
-                left: offset,
-                right: offset + width,
-                bottom: config.bar.height,
+                left: *left,
+                right: *right,
+                bottom: height,
                 top: 0,
             };
 
-            offset = rect.right;
-
-            draw_component_text(api, &rect, config, &component_text);
+            draw_component_text(api, &rect, config, component_text);
         }
     }
-
-    Ok(())
 }
 
+/// Renders the components and places them next to each other, starting at zero. The separator
+/// goes between every two components that take up any space.
 fn components_to_section(
     api: &Api,
     display_id: DisplayId,
     components: &[Component],
+    separator: &str,
+    idle_interval: Option<u64>,
 ) -> RuntimeResult<ItemSection> {
     let mut section = ItemSection::default();
-    let mut component_offset = 0;
+    let mut offset = 0;
 
     for component in components {
-        let mut item = Item::default();
-        let mut component_text_offset = 0;
-        let mut component_width = 0;
-
-        for component_text in component.render(display_id)? {
-            let width = api
-                .calculate_text_rect(&component_text.display_text)
-                .width();
-            let left = component_text_offset;
-            let right = component_text_offset + width;
+        let texts = component.render_throttled(display_id, idle_interval)?;
+        let widths = texts
+            .iter()
+            .map(|text| component_text_width(api, text))
+            .collect::<Vec<_>>();
+        let (positions, width) = component.layout.place(&widths);
+
+        if width == 0 {
+            continue;
+        }
 
-            item.cached_result.push(((left, right), component_text));
+        if offset > 0 && !separator.is_empty() {
+            let separator_width = api.calculate_text_rect(separator).width();
 
-            component_width += width;
-            component_text_offset += width;
+            section.items.push(Item {
+                left: offset,
+                right: offset + separator_width,
+                component: Component::new("Separator", |_| Ok(Vec::new())),
+                cached_result: vec![(
+                    (offset, offset + separator_width),
+                    ComponentText::new().with_display_text(separator.into()),
+                )],
+            });
+
+            offset += separator_width;
         }
 
-        item.left = component_offset;
-        item.right = item.left + component_width;
-        item.component = component.clone();
-
-        section.items.push(item);
+        section.items.push(Item {
+            left: offset,
+            right: offset + width,
+            component: component.clone(),
+            cached_result: positions
+                .into_iter()
+                .map(|(left, right)| (offset + left, offset + right))
+                .zip(texts)
+                .collect(),
+        });
 
-        component_offset += component_width;
+        offset += width;
     }
 
-    section.right = component_offset;
+    section.right = offset;
 
     Ok(section)
 }
 
-fn clear_section(api: &Api, config: &Config, left: i32, right: i32) {
-    api.fill_rect(left, 0, right - left, config.bar.height, config.bar.color)
+fn clear_section(api: &Api, config: &Config, height: i32, left: i32, right: i32) {
+    api.fill_rect(left, 0, right - left, height, config.bar.color)
 }
 
 pub fn create(state_arc: Arc<Mutex<AppState>>) {
@@ -133,13 +201,16 @@ pub fn create(state_arc: Arc<Mutex<AppState>>) {
         .unwrap()
         .displays
         .clone();
+    let mut font_warnings: Vec<String> = Vec::new();
 
     for display in displays {
-        let config = state_arc
-            .try_lock_for(Duration::from_millis(100))
-            .unwrap()
-            .config
-            .clone();
+        let (config, display_idx) = {
+            let state = state_arc.try_lock_for(Duration::from_millis(100)).unwrap();
+            (
+                state.config.clone(),
+                state.get_display_idx(display.id).unwrap_or(1),
+            )
+        };
 
         if display.appbar.is_some() {
             error!(
@@ -153,9 +224,27 @@ pub fn create(state_arc: Arc<Mutex<AppState>>) {
         let mut bar = Bar::default();
 
         bar.display_id = display.id;
+        bar.height = config.bar.height_of(display_idx);
+        bar.color = config.bar.color;
+
+        let font = font::choose(config.bar.font_of(display_idx));
+        if !font.missing.is_empty() {
+            let warning = format!(
+                "The font {} isn't installed, so the bar uses {}",
+                font.missing.join(", "),
+                font.font
+            );
+            warn!("{}", warning);
+
+            if !font_warnings.contains(&warning) {
+                font_warnings.push(warning);
+            }
+        }
+        bar.font = font.font;
 
         let left = display.working_area_left();
-        let top = display.working_area_top(&config) - config.bar.height;
+        // the top of the working area without the bar, which is where the bar starts
+        let top = display.working_area_top(&config) - display.appbar_height(&config);
         let width = display.working_area_width(&config);
 
         bar.window = bar
@@ -164,11 +253,11 @@ pub fn create(state_arc: Arc<Mutex<AppState>>) {
             .with_border(false)
             .with_title(NOG_BAR_NAME)
             .with_refresh_rate(100)
-            .with_font(&config.bar.font)
-            .with_font_size(config.bar.font_size)
+            .with_font(&bar.font)
+            .with_font_size(config.bar.font_size_of(display_idx))
             .with_background_color(config.bar.color)
             .with_pos(left, top)
-            .with_size(width, config.bar.height);
+            .with_size(width, bar.height);
 
         let sender = sender.clone();
         let state_arc2 = state_arc.clone();
@@ -182,7 +271,9 @@ pub fn create(state_arc: Arc<Mutex<AppState>>) {
                     #[cfg(target_os = "windows")]
                     {
                         use winapi::um::shellapi::ABN_FULLSCREENAPP;
-                        use winapi::um::winuser::WM_APP;
+                        use winapi::um::winuser::{
+                            PBT_POWERSETTINGCHANGE, WM_APP, WM_POWERBROADCAST,
+                        };
 
                         if msg.code == WM_APP + 1 {
                             if msg.params.0 == ABN_FULLSCREENAPP as usize {
@@ -191,24 +282,32 @@ pub fn create(state_arc: Arc<Mutex<AppState>>) {
                                     .expect("Failed to send ToggleAppbar event");
                             }
                         }
+
+                        if msg.code == WM_POWERBROADCAST
+                            && msg.params.0 == PBT_POWERSETTINGCHANGE as usize
+                        {
+                            // the window callback replaces the setting with the display state
+                            idle::set_display_off(msg.params.1 == 0);
+                        }
                     }
                 }
                 WindowEvent::Click {
                     x,
+                    y,
                     display_id,
                     state_arc,
+                    api,
                     ..
                 } => {
-                    let clickable_items = state_arc
+                    let item = state_arc
                         .lock()
                         .get_display_by_id(*display_id)
                         .unwrap()
                         .appbar
                         .as_ref()
-                        .and_then(|b| b.item_at_pos(*x).cloned())
-                        .filter(|item| item.component.is_clickable);
+                        .and_then(|b| b.item_at_pos(*x).cloned());
 
-                    for item in clickable_items {
+                    if let Some(item) = item.clone().filter(|item| item.component.is_clickable) {
                         for (i, (width, text)) in item.cached_result.iter().enumerate() {
                             if width.0 <= *x && *x <= width.1 {
                                 item.component
@@ -216,14 +315,109 @@ pub fn create(state_arc: Arc<Mutex<AppState>>) {
                             }
                         }
                     }
+
+                    if let Some(item) = item.filter(|item| item.component.is_draggable()) {
+                        for (i, (width, text)) in item.cached_result.iter().enumerate() {
+                            if width.0 <= *x && *x <= width.1 {
+                                // the drag mode only gets entered once the mouse moves, see
+                                // `drag::update`
+                                drag::start(BarDrag {
+                                    component: item.component.clone(),
+                                    value: text.value.clone(),
+                                    idx: i,
+                                    display_id: *display_id,
+                                    text: text.display_text.trim().to_string(),
+                                    origin: (*x, *y),
+                                    moving: false,
+                                    previous_mode: None,
+                                });
+
+                                // the mouse gets captured, so that the bar receives the release of
+                                // the button even if it happens on top of another bar
+                                api.capture_mouse();
+                            }
+                        }
+                    }
+                }
+                WindowEvent::MouseUp {
+                    x,
+                    y,
+                    api,
+                    state_arc,
+                    ..
+                } => {
+                    api.release_mouse();
+
+                    if let Some(drag) = drag::take() {
+                        let _ = tooltip::close();
+
+                        let mut state = state_arc.lock();
+                        drag::end(&mut state, &drag);
+
+                        // releasing the button before the mouse moved is a click
+                        if !drag.moving {
+                            return Ok(());
+                        }
+
+                        let target = api
+                            .window
+                            .get_rect()
+                            .ok()
+                            .and_then(|r| bar_at(&state, r.left + *x, r.top + *y))
+                            .map(|(id, _)| id)
+                            .filter(|id| *id != drag.display_id);
+
+                        drop(state);
+
+                        if let Some(target) = target {
+                            drag.component.on_drop(
+                                drag.display_id,
+                                drag.value,
+                                drag.idx,
+                                target,
+                            )?;
+                        }
+                    }
                 }
                 WindowEvent::MouseMove {
                     x,
+                    y,
                     api,
                     display_id,
                     state_arc,
                     ..
                 } => {
+                    if let Some(drag) = drag::current() {
+                        if !drag::update(&mut state_arc.lock(), *x, *y) {
+                            return Ok(());
+                        }
+
+                        let cursor = api.window.get_rect().map(|r| (r.left + *x, r.top + *y));
+                        let target = cursor.ok().and_then(|(x, y)| {
+                            bar_at(&state_arc.lock(), x, y)
+                                .filter(|(id, _)| *id != drag.display_id)
+                                .map(|(id, rect)| (id, x, rect))
+                        });
+
+                        // the tooltip tells the user where the dragged text would end up
+                        match target {
+                            Some((id, x, rect)) => {
+                                let _ = tooltip::show(
+                                    format!("drag{}", id.0),
+                                    format!("Move {} here", drag.text),
+                                    x,
+                                    rect.bottom,
+                                    state_arc.clone(),
+                                );
+                            }
+                            None => {
+                                let _ = tooltip::close();
+                            }
+                        }
+
+                        return Ok(());
+                    }
+
                     state_arc
                         .lock()
                         .get_display_by_id(*display_id)
@@ -242,6 +436,57 @@ pub fn create(state_arc: Arc<Mutex<AppState>>) {
                             api.set_default_cursor();
                             None
                         });
+
+                    let hovered_item = state_arc
+                        .lock()
+                        .get_display_by_id(*display_id)
+                        .unwrap()
+                        .appbar
+                        .as_ref()
+                        .and_then(|b| b.item_at_pos(*x).cloned())
+                        .filter(|item| item.component.has_hover());
+
+                    let hovered_text = hovered_item.and_then(|item| {
+                        item.cached_result
+                            .iter()
+                            .enumerate()
+                            .find(|(_, (width, _))| width.0 <= *x && *x <= width.1)
+                            .map(|(i, (width, text))| {
+                                (item.component.clone(), i, width.0, text.value.clone())
+                            })
+                    });
+
+                    // tooltips are only cosmetic, so failing to show one shouldn't break the bar
+                    match hovered_text {
+                        Some((component, i, left, value)) => {
+                            let key = format!("{}{}", component.name, i);
+
+                            if !tooltip::is_showing(&key) {
+                                let text = component.on_hover(*display_id, value, i)?;
+
+                                match (text, api.window.get_rect()) {
+                                    (Some(text), Ok(rect)) => {
+                                        let _ = tooltip::show(
+                                            key,
+                                            text,
+                                            rect.left + left,
+                                            rect.bottom,
+                                            state_arc.clone(),
+                                        );
+                                    }
+                                    _ => {
+                                        let _ = tooltip::close();
+                                    }
+                                }
+                            }
+                        }
+                        None => {
+                            let _ = tooltip::close();
+                        }
+                    }
+                }
+                WindowEvent::MouseLeave { .. } => {
+                    let _ = tooltip::close();
                 }
                 WindowEvent::Draw {
                     api,
@@ -250,71 +495,79 @@ pub fn create(state_arc: Arc<Mutex<AppState>>) {
                     ..
                 } => {
                     if let Some(state) = state_arc.try_lock_for(Duration::from_millis(20)) {
-                        let config = state.config.clone();
+                        let mut config = state.config.clone();
+                        // the workspaces can have their own color
+                        config.bar.color = state.bar_color(*display_id);
                         let bar = state.get_display_by_id(*display_id).unwrap().appbar.clone();
+                        let components = config
+                            .bar
+                            .components_of(state.get_display_idx(*display_id).unwrap_or(1));
                         drop(state);
 
                         if let Some(bar) = bar {
+                            let idle_interval = idle::idle_interval(&config.bar, bar.hidden);
                             let working_area_width = display.working_area_width(&config);
+                            let separator = &config.bar.separator;
                             let left = components_to_section(
                                 api,
                                 *display_id,
-                                &config.bar.components.left,
+                                &components.left,
+                                separator,
+                                idle_interval,
                             )?;
 
                             let mut center = components_to_section(
                                 api,
                                 *display_id,
-                                &config.bar.components.center,
+                                &components.center,
+                                separator,
+                                idle_interval,
                             )?;
-
-                            center.left = working_area_width / 2 - center.right / 2;
-                            center.right += center.left;
+                            center.move_to(working_area_width / 2 - center.width() / 2);
 
                             let mut right = components_to_section(
                                 api,
                                 *display_id,
-                                &config.bar.components.right,
+                                &components.right,
+                                separator,
+                                idle_interval,
                             )?;
-                            right.left = working_area_width - right.right;
-                            right.right += right.left;
+                            right.move_to(working_area_width - right.width());
 
-                            draw_components(
-                                api,
-                                &config,
-                                *display_id,
-                                left.left,
-                                &config.bar.components.left,
-                            )?;
-                            draw_components(
-                                api,
-                                &config,
-                                *display_id,
-                                center.left,
-                                &config.bar.components.center,
-                            )?;
-                            draw_components(
-                                api,
-                                &config,
-                                *display_id,
-                                right.left,
-                                &config.bar.components.right,
-                            )?;
+                            if bar.color != config.bar.color {
+                                clear_section(api, &config, bar.height, 0, working_area_width);
+                            }
+
+                            draw_section(api, &config, bar.height, &left);
+                            draw_section(api, &config, bar.height, &center);
+                            draw_section(api, &config, bar.height, &right);
 
                             if bar.left.width() > left.width() {
-                                clear_section(api, &config, left.right, bar.left.right);
+                                clear_section(api, &config, bar.height, left.right, bar.left.right);
                             }
 
                             if bar.center.width() > center.width() {
-                                clear_section(api, &config, bar.center.left, bar.center.right);
+                                clear_section(
+                                    api,
+                                    &config,
+                                    bar.height,
+                                    bar.center.left,
+                                    bar.center.right,
+                                );
                             }
 
                             if bar.right.width() > right.width() {
-                                clear_section(api, &config, bar.right.left, right.left);
+                                clear_section(api, &config, bar.height, bar.right.left, right.left);
                             }
 
                             sender
-                                .send(Event::UpdateBarSections(display.id, left, center, right))
+                                .send(Event::UpdateBarSections(
+                                    display.id,
+                                    left,
+                                    center,
+                                    right,
+                                    config.bar.color,
+                                ))
                                 .expect("Failed to send UpdateBarSections event");
                         }
                     }
@@ -325,6 +578,23 @@ pub fn create(state_arc: Arc<Mutex<AppState>>) {
             Ok(())
         });
 
+        #[cfg(target_os = "windows")]
+        unsafe {
+            use winapi::shared::windef::HWND;
+            use winapi::um::winnt::{GUID_CONSOLE_DISPLAY_STATE, HANDLE};
+            use winapi::um::winuser::{
+                RegisterPowerSettingNotification, DEVICE_NOTIFY_WINDOW_HANDLE,
+            };
+
+            // tells the bar when the display turns off
+            let hwnd: HWND = bar.window.id.into();
+            RegisterPowerSettingNotification(
+                hwnd as HANDLE,
+                &GUID_CONSOLE_DISPLAY_STATE,
+                DEVICE_NOTIFY_WINDOW_HANDLE,
+            );
+        }
+
         state_arc
             .try_lock_for(Duration::from_millis(100))
             .unwrap()
@@ -332,6 +602,10 @@ pub fn create(state_arc: Arc<Mutex<AppState>>) {
             .unwrap()
             .appbar = Some(bar.clone());
     }
+
+    if !font_warnings.is_empty() {
+        state_arc.lock().show_osd(font_warnings.join(". "));
+    }
 }
 
 #[test]
@@ -0,0 +1,81 @@
+use super::component::Component;
+use crate::{system::DisplayId, AppState, BAR_DRAG_MODE};
+use parking_lot::Mutex;
+use std::{any::Any, sync::Arc};
+
+/// How far the mouse has to move while the button is held before the press turns into a drag, so
+/// that a click doesn't enter the bar drag mode.
+const THRESHOLD: i32 = 5;
+
+/// A component text that is being dragged from the bar of one display to the bar of another one.
+#[derive(Clone)]
+pub struct BarDrag {
+    pub component: Component,
+    pub value: Arc<Box<dyn Any + Send + Sync>>,
+    pub idx: usize,
+    /// The display of the bar the drag started on
+    pub display_id: DisplayId,
+    pub text: String,
+    /// Where the button got pressed, relative to the bar
+    pub origin: (i32, i32),
+    /// Whether the mouse moved past the threshold, which entered the bar drag mode
+    pub moving: bool,
+    /// The mode that was active before the bar drag mode
+    pub previous_mode: Option<String>,
+}
+
+static DRAG: Mutex<Option<BarDrag>> = Mutex::new(None);
+
+pub fn start(drag: BarDrag) {
+    *DRAG.lock() = Some(drag);
+}
+
+pub fn current() -> Option<BarDrag> {
+    DRAG.lock().clone()
+}
+
+/// Ends the drag and returns it, if there was one.
+pub fn take() -> Option<BarDrag> {
+    DRAG.lock().take()
+}
+
+/// Enters the bar drag mode once the mouse moved past the threshold. Returns whether the press is a
+/// drag.
+pub fn update(state: &mut AppState, x: i32, y: i32) -> bool {
+    let entered = {
+        let mut drag = DRAG.lock();
+        match drag.as_mut() {
+            Some(drag) if drag.moving => return true,
+            Some(drag)
+                if (x - drag.origin.0).abs() >= THRESHOLD
+                    || (y - drag.origin.1).abs() >= THRESHOLD =>
+            {
+                drag.moving = true;
+                drag.previous_mode = state.keybindings_manager.get_mode();
+                true
+            }
+            _ => false,
+        }
+    };
+
+    if entered {
+        state.toggle_mode(BAR_DRAG_MODE.into());
+    }
+
+    entered
+}
+
+/// Leaves the bar drag mode and restores the mode that was active before the drag.
+pub fn end(state: &mut AppState, drag: &BarDrag) {
+    if !drag.moving || state.keybindings_manager.get_mode().as_deref() != Some(BAR_DRAG_MODE) {
+        return;
+    }
+
+    state.toggle_mode(BAR_DRAG_MODE.into());
+
+    // the mode is restored without toggling it, because that would e.g. start a new resize
+    // session instead of continuing the one the drag interrupted
+    if let Some(mode) = &drag.previous_mode {
+        state.keybindings_manager.enter_mode(mode);
+    }
+}
@@ -0,0 +1,77 @@
+//! Picks the font of the bar from the comma separated list in `bar.font`, so that a config which
+//! names a font that isn't installed still renders readable text.
+use crate::system::api;
+
+/// The font that gets used if none of the listed fonts is installed. Every version of Windows nog
+/// runs on ships with it.
+pub const FALLBACK_FONT: &str = "Segoe UI";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontChoice {
+    pub font: String,
+    /// The fonts that come before the chosen one in the list, but aren't installed
+    pub missing: Vec<String>,
+}
+
+pub fn parse_list(fonts: &str) -> Vec<String> {
+    fonts
+        .split(',')
+        .map(|font| font.trim())
+        .filter(|font| !font.is_empty())
+        .map(|font| font.to_string())
+        .collect()
+}
+
+pub fn choose_with(fonts: &str, is_installed: impl Fn(&str) -> bool) -> FontChoice {
+    let mut missing = Vec::new();
+
+    for font in parse_list(fonts) {
+        if is_installed(&font) {
+            return FontChoice { font, missing };
+        }
+
+        missing.push(font);
+    }
+
+    FontChoice {
+        font: FALLBACK_FONT.into(),
+        missing,
+    }
+}
+
+/// Returns the first installed font of the list
+pub fn choose(fonts: &str) -> FontChoice {
+    choose_with(fonts, api::is_font_installed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_installed_font_wins() {
+        let installed = |font: &str| font == "Consolas" || font == "Cascadia Code";
+
+        assert_eq!(
+            choose_with("Fira Code, Cascadia Code,Consolas", installed),
+            FontChoice {
+                font: "Cascadia Code".into(),
+                missing: vec!["Fira Code".into()],
+            }
+        );
+        assert_eq!(
+            choose_with("Consolas", installed),
+            FontChoice {
+                font: "Consolas".into(),
+                missing: vec![],
+            }
+        );
+        assert_eq!(
+            choose_with("Fira Code, ", installed),
+            FontChoice {
+                font: FALLBACK_FONT.into(),
+                missing: vec!["Fira Code".into()],
+            }
+        );
+    }
+}
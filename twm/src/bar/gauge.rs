@@ -0,0 +1,135 @@
+//! Gauges are component texts that get drawn as a filled shape instead of a string, which suits
+//! values like the volume, the battery level or the cpu usage.
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GaugeShape {
+    /// A horizontal bar that fills up from the left
+    Bar,
+    /// A circle that fills up clockwise, starting at the top
+    Arc,
+}
+
+impl Default for GaugeShape {
+    fn default() -> Self {
+        Self::Bar
+    }
+}
+
+impl FromStr for GaugeShape {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bar" => Ok(Self::Bar),
+            "arc" => Ok(Self::Arc),
+            _ => Err(format!("Unknown gauge shape '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for GaugeShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Bar => "bar",
+            Self::Arc => "arc",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gauge {
+    pub shape: GaugeShape,
+    pub value: i32,
+    pub max: i32,
+    /// The width in pixels the gauge takes up in the bar
+    pub width: i32,
+    /// The height in pixels of a bar gauge. A height of zero uses half of the height of the bar.
+    pub height: i32,
+    /// Zero uses the text color of the bar
+    pub color: i32,
+    /// Zero uses the background color of the bar
+    pub background_color: i32,
+}
+
+impl Default for Gauge {
+    fn default() -> Self {
+        Self {
+            shape: GaugeShape::default(),
+            value: 0,
+            max: 100,
+            width: 40,
+            height: 0,
+            color: 0,
+            background_color: 0,
+        }
+    }
+}
+
+impl Gauge {
+    /// How much of the gauge is filled, between zero and one
+    pub fn fraction(&self) -> f32 {
+        if self.max <= 0 {
+            return 0.0;
+        }
+
+        (self.value as f32 / self.max as f32).max(0.0).min(1.0)
+    }
+
+    /// The width in pixels of the filled part of a bar gauge
+    pub fn fill_width(&self) -> i32 {
+        (self.width as f32 * self.fraction()).round() as i32
+    }
+
+    /// Returns the top and the height of the gauge inside of a bar with the given height. Arcs
+    /// are as high as they are wide, as long as they fit.
+    pub fn vertical_bounds(&self, bar_height: i32) -> (i32, i32) {
+        let height = match self.shape {
+            GaugeShape::Bar if self.height > 0 => self.height.min(bar_height),
+            GaugeShape::Bar => bar_height / 2,
+            GaugeShape::Arc => self.width.min(bar_height - 4).max(0),
+        };
+
+        ((bar_height - height) / 2, height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gauge_fills_up_to_its_width() {
+        let mut gauge = Gauge::default();
+
+        gauge.value = 60;
+        assert_eq!(gauge.fill_width(), 24);
+
+        gauge.value = 150;
+        assert_eq!(gauge.fill_width(), 40);
+
+        gauge.value = -10;
+        assert_eq!(gauge.fill_width(), 0);
+
+        gauge.max = 0;
+        gauge.value = 10;
+        assert_eq!(gauge.fraction(), 0.0);
+    }
+
+    #[test]
+    fn gauge_is_vertically_centered() {
+        let mut gauge = Gauge::default();
+
+        assert_eq!(gauge.vertical_bounds(20), (5, 10));
+
+        gauge.height = 4;
+        assert_eq!(gauge.vertical_bounds(20), (8, 4));
+
+        gauge.shape = GaugeShape::Arc;
+        gauge.width = 12;
+        assert_eq!(gauge.vertical_bounds(20), (4, 12));
+
+        gauge.width = 40;
+        assert_eq!(gauge.vertical_bounds(20), (2, 16));
+    }
+}
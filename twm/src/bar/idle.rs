@@ -0,0 +1,24 @@
+//! Decides how often the components of the bar get rendered. Rendering them at full speed while
+//! nobody looks at the bar only drains the battery.
+use crate::{config::bar_config::BarConfig, system::api};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static DISPLAY_OFF: AtomicBool = AtomicBool::new(false);
+
+pub fn set_display_off(value: bool) {
+    DISPLAY_OFF.store(value, Ordering::SeqCst);
+}
+
+/// Returns how many milliseconds components have to wait between renders while the system is
+/// idle, or `None` if it isn't. A hidden bar counts as idle as well.
+pub fn idle_interval(config: &BarConfig, bar_hidden: bool) -> Option<u64> {
+    let is_idle = bar_hidden
+        || DISPLAY_OFF.load(Ordering::SeqCst)
+        || (config.idle_timeout > 0 && api::get_idle_time() >= config.idle_timeout as u32);
+
+    if is_idle {
+        Some(config.idle_interval.max(0) as u64)
+    } else {
+        None
+    }
+}
@@ -11,6 +11,24 @@ impl ItemSection {
     pub fn width(&self) -> i32 {
         self.right - self.left
     }
+
+    /// Moves the section together with its items, so that it starts at the given position
+    pub fn move_to(&mut self, left: i32) {
+        let distance = left - self.left;
+
+        self.left += distance;
+        self.right += distance;
+
+        for item in self.items.iter_mut() {
+            item.left += distance;
+            item.right += distance;
+
+            for ((left, right), _) in item.cached_result.iter_mut() {
+                *left += distance;
+                *right += distance;
+            }
+        }
+    }
 }
 
 impl Default for ItemSection {
@@ -0,0 +1,104 @@
+//! Places the texts of a component inside of the space the component takes up in its section.
+use std::{fmt, str::FromStr};
+
+/// Where the texts of a component go if the component is wider than them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Alignment {
+    Left,
+    Center,
+    Right,
+}
+
+impl Default for Alignment {
+    fn default() -> Self {
+        Self::Left
+    }
+}
+
+impl FromStr for Alignment {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "left" => Ok(Self::Left),
+            "center" => Ok(Self::Center),
+            "right" => Ok(Self::Right),
+            _ => Err(format!("Unknown alignment '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for Alignment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Left => "left",
+            Self::Center => "center",
+            Self::Right => "right",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ComponentLayout {
+    /// The empty space in pixels before the texts
+    pub padding_left: i32,
+    /// The empty space in pixels after the texts
+    pub padding_right: i32,
+    /// The component is at least this many pixels wide, including its padding
+    pub min_width: i32,
+    pub align: Alignment,
+}
+
+impl ComponentLayout {
+    /// Returns where the texts with the given widths start and end relative to the component,
+    /// together with the width of the whole component.
+    pub fn place(&self, widths: &[i32]) -> (Vec<(i32, i32)>, i32) {
+        let content_width: i32 = widths.iter().sum();
+        let padded_width = self.padding_left + content_width + self.padding_right;
+        let width = padded_width.max(self.min_width);
+        let extra = width - padded_width;
+        let mut offset = self.padding_left
+            + match self.align {
+                Alignment::Left => 0,
+                Alignment::Center => extra / 2,
+                Alignment::Right => extra,
+            };
+        let mut positions = Vec::new();
+
+        for width in widths {
+            positions.push((offset, offset + width));
+            offset += width;
+        }
+
+        (positions, width)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn place_texts_with_padding_and_min_width() {
+        let mut layout = ComponentLayout::default();
+
+        assert_eq!(layout.place(&[10, 20]), (vec![(0, 10), (10, 30)], 30));
+
+        layout.padding_left = 4;
+        layout.padding_right = 2;
+
+        assert_eq!(layout.place(&[10, 20]), (vec![(4, 14), (14, 34)], 36));
+
+        layout.min_width = 50;
+        assert_eq!(layout.place(&[10, 20]).1, 50);
+
+        layout.align = Alignment::Center;
+        assert_eq!(layout.place(&[10, 20]).0, vec![(11, 21), (21, 41)]);
+
+        layout.align = Alignment::Right;
+        assert_eq!(layout.place(&[10, 20]).0, vec![(18, 28), (28, 48)]);
+
+        // the minimum width doesn't cut off texts that are wider
+        assert_eq!(layout.place(&[60]), (vec![(4, 64)], 66));
+    }
+}
@@ -0,0 +1,47 @@
+use crate::{popup, system::SystemResult, window::Window, AppState};
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+/// The currently visible tooltip together with the key of the component text it belongs to.
+static TOOLTIP: Mutex<Option<(String, Window)>> = Mutex::new(None);
+
+/// Shows the text in a small popup at the given position.
+///
+/// Nothing happens if the tooltip with the given key is already visible, otherwise the current
+/// tooltip gets replaced.
+pub fn show(
+    key: String,
+    text: String,
+    x: i32,
+    y: i32,
+    state_arc: Arc<Mutex<AppState>>,
+) -> SystemResult {
+    if is_showing(&key) {
+        return Ok(());
+    }
+
+    close()?;
+
+    let (window, _) = popup::create_text_window(text, 5, Some((x, y)), None, state_arc);
+
+    *TOOLTIP.lock() = Some((key, window));
+
+    Ok(())
+}
+
+pub fn is_showing(key: &str) -> bool {
+    TOOLTIP
+        .lock()
+        .as_ref()
+        .map(|(k, _)| k == key)
+        .unwrap_or(false)
+}
+
+/// Close the current tooltip, if there is one.
+pub fn close() -> SystemResult {
+    if let Some((_, window)) = TOOLTIP.lock().take() {
+        window.close()?;
+    }
+
+    Ok(())
+}
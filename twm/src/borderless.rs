@@ -0,0 +1,86 @@
+//! Stretches a window over its whole display without any borders, which helps with applications
+//! (usually games) that only offer a windowed mode.
+//!
+//! Such windows aren't tiled. The journal remembers their original state, so that toggling it off
+//! again, leaving the work mode or a crash restores them.
+use crate::{
+    journal,
+    system::{NativeWindow, SystemResult, WindowId},
+    AppState,
+};
+use log::{debug, info};
+
+pub fn is_borderless(state: &AppState, id: WindowId) -> bool {
+    state.borderless_windows.contains(&id)
+}
+
+fn enter(state: &mut AppState, mut window: NativeWindow) -> SystemResult {
+    let config = state.config.clone();
+
+    if let Some(grid) = state.find_grid_containing_window(window.id) {
+        let grid_id = grid.id;
+
+        if let Some(mut w) = grid.remove_by_window_id(window.id) {
+            debug!("Unmanaging window '{}' | {}", w.title, w.id);
+            w.cleanup()?;
+        }
+
+        if let Some(d) = state.find_grid_display(grid_id) {
+            d.refresh_grid(&config)?;
+        }
+    }
+
+    let area = match state.get_display_by_id(window.get_display()?.id) {
+        Some(display) => display.rect,
+        None => return Ok(()),
+    };
+
+    info!(
+        "Making '{}' | {} borderless fullscreen",
+        window.get_process_name(),
+        window.id
+    );
+
+    window.init(false, false)?;
+    window.make_borderless(area)?;
+
+    state.borderless_windows.push(window.id);
+
+    Ok(())
+}
+
+fn leave(state: &mut AppState, id: WindowId) -> SystemResult {
+    state.borderless_windows.retain(|x| *x != id);
+
+    if !NativeWindow::from(id).is_window() {
+        return Ok(());
+    }
+
+    info!("Restoring borderless fullscreen window {}", id);
+
+    journal::repair(id)
+}
+
+/// Makes the foreground window borderless fullscreen or restores it if it already is.
+pub fn toggle(state: &mut AppState) -> SystemResult {
+    let window = NativeWindow::get_foreground_window()?;
+
+    state
+        .borderless_windows
+        .retain(|id| NativeWindow::from(*id).is_window());
+
+    if is_borderless(state, window.id) {
+        leave(state, window.id)
+    } else {
+        enter(state, window)
+    }
+}
+
+/// Restores every borderless fullscreen window
+pub fn restore_all(state: &mut AppState) -> SystemResult {
+    for id in state.borderless_windows.clone() {
+        leave(state, id)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,211 @@
+//! The command palette lists every action that nog knows about and executes the one that gets
+//! picked. Actions are either built into nog or functions that get exported by the config.
+use crate::{actions::Invocation, popup::Popup};
+
+/// How many matching actions are visible at once
+const MAX_VISIBLE: usize = 10;
+
+#[derive(Debug, Clone)]
+pub enum ActionTarget {
+    /// A named action of nog
+    Named(Invocation),
+    /// Index of the callback that executes the action
+    Callback(usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct Action {
+    pub name: String,
+    pub target: ActionTarget,
+}
+
+impl Action {
+    pub fn new(name: impl Into<String>, callback_id: usize) -> Self {
+        Self {
+            name: name.into(),
+            target: ActionTarget::Callback(callback_id),
+        }
+    }
+
+    pub fn named(invocation: Invocation) -> Self {
+        Self {
+            name: invocation.to_string(),
+            target: ActionTarget::Named(invocation),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CommandPalette {
+    pub query: String,
+    /// Index of the selected action in the list of matches
+    pub selected: usize,
+}
+
+/// Scores how well the query matches the candidate. Every character of the query has to appear in
+/// the candidate in the same order, ignoring the case. Characters that follow each other or start
+/// a word are worth more.
+///
+/// Returns `None` if the query doesn't match.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i32> {
+    let candidate = candidate.to_lowercase().chars().collect::<Vec<char>>();
+    let mut score = 0;
+    let mut start = 0;
+    let mut prev_idx: Option<usize> = None;
+
+    for c in query.to_lowercase().chars() {
+        let idx = (start..candidate.len()).find(|i| candidate[*i] == c)?;
+
+        score += 1;
+
+        if prev_idx.map(|i| i + 1) == Some(idx) {
+            score += 5;
+        }
+
+        if idx == 0 || !candidate[idx - 1].is_alphanumeric() {
+            score += 3;
+        }
+
+        prev_idx = Some(idx);
+        start = idx + 1;
+    }
+
+    Some(score)
+}
+
+impl CommandPalette {
+    /// Returns the actions that match the query, the best match first.
+    pub fn matches<'a>(&self, actions: &'a [Action]) -> Vec<&'a Action> {
+        let mut matches = actions
+            .iter()
+            .filter_map(|a| fuzzy_score(&self.query, &a.name).map(|score| (score, a)))
+            .collect::<Vec<_>>();
+
+        // the sort is stable, so actions with the same score keep their order
+        matches.sort_by_key(|(score, _)| -score);
+
+        matches
+            .into_iter()
+            .take(MAX_VISIBLE)
+            .map(|(_, a)| a)
+            .collect()
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.query.push(c);
+        self.selected = 0;
+    }
+
+    pub fn pop(&mut self) {
+        self.query.pop();
+        self.selected = 0;
+    }
+
+    pub fn select_next(&mut self, actions: &[Action]) {
+        let count = self.matches(actions).len();
+
+        if count > 0 {
+            self.selected = (self.selected + 1) % count;
+        }
+    }
+
+    pub fn select_prev(&mut self, actions: &[Action]) {
+        let count = self.matches(actions).len();
+
+        if count > 0 {
+            self.selected = (self.selected + count - 1) % count;
+        }
+    }
+
+    pub fn selection<'a>(&self, actions: &'a [Action]) -> Option<&'a Action> {
+        self.matches(actions).into_iter().nth(self.selected)
+    }
+
+    pub fn to_popup(&self, actions: &[Action]) -> Popup {
+        let matches = self.matches(actions);
+        let mut lines = vec![format!("> {}", self.query), "".into()];
+
+        if matches.is_empty() {
+            lines.push("No matching actions".into());
+        }
+
+        for (i, action) in matches.iter().enumerate() {
+            let marker = if i == self.selected { ">" } else { " " };
+            lines.push(format!("{} {}", marker, action.name));
+        }
+
+        Popup::new().with_padding(5).with_text(lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn actions() -> Vec<Action> {
+        vec![
+            Action::new("Focus left", 0),
+            Action::new("Toggle fullscreen", 1),
+            Action::new("Toggle floating", 2),
+            Action::new("Workspace 1", 3),
+        ]
+    }
+
+    #[test]
+    fn fuzzy_score_requires_ordered_characters() {
+        assert!(fuzzy_score("tf", "Toggle floating").is_some());
+        assert!(fuzzy_score("ft", "Toggle floating").is_some());
+        assert!(fuzzy_score("xyz", "Toggle floating").is_none());
+        assert_eq!(fuzzy_score("", "Toggle floating"), Some(0));
+    }
+
+    #[test]
+    fn matches_prefer_word_starts_and_consecutive_characters() {
+        let palette = CommandPalette {
+            query: "tfl".into(),
+            selected: 0,
+        };
+        let names = palette
+            .matches(&actions())
+            .into_iter()
+            .map(|a| a.name.clone())
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, vec!["Toggle floating", "Toggle fullscreen"]);
+    }
+
+    #[test]
+    fn selection_wraps_around() {
+        let actions = actions();
+        let mut palette = CommandPalette::default();
+
+        palette.push('t');
+        palette.select_prev(&actions);
+
+        assert_eq!(palette.selection(&actions).unwrap().name, "Focus left");
+
+        palette.select_next(&actions);
+
+        assert_eq!(
+            palette.selection(&actions).unwrap().name,
+            "Toggle fullscreen"
+        );
+    }
+
+    #[test]
+    fn named_actions_match_on_word_starts() {
+        let actions = vec![
+            Action::named(Invocation::new("focus_left", vec![])),
+            Action::named(Invocation::new("change_workspace", vec![3])),
+        ];
+        let palette = CommandPalette {
+            query: "cw3".into(),
+            selected: 0,
+        };
+
+        assert_eq!(
+            palette.selection(&actions).unwrap().name,
+            "change_workspace(3)"
+        );
+    }
+}
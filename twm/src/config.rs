@@ -1,18 +1,43 @@
-use crate::keybindings::keybinding::Keybinding;
+use crate::{command_palette::Action, keybindings::keybinding::Keybinding, scheduler::Job, util};
 use bar_config::BarConfig;
+use close_focus_policy::CloseFocusPolicy;
+use game_mode_config::GameModeConfig;
+use interpreter::Limits;
+use launch_policy::LaunchPolicy;
 use log::error;
+use profile::{Profile, ProfileSettings};
+use reserved_space::ReservedSpace;
 use rule::Rule;
-use std::{collections::HashMap, path::PathBuf, time::Duration};
+use startup_policy::StartupPolicy;
+use std::{collections::HashMap, path::PathBuf, str::FromStr, time::Duration};
+use template::Template;
 use update_channel::UpdateChannel;
+use workspace_policy::WorkspacePolicy;
 use workspace_setting::WorkspaceSetting;
 
 pub mod bar_config;
+pub mod close_focus_policy;
+pub mod error_popup;
+pub mod fancyzones;
+pub mod game_mode_config;
 pub mod hot_reloading;
+pub mod launch_policy;
+pub mod profile;
+pub mod reserved_space;
 // pub mod rhai;
 pub mod rule;
+pub mod startup_policy;
+pub mod template;
 pub mod update_channel;
+pub mod workspace_policy;
 pub mod workspace_setting;
 
+fn parse<T: FromStr>(field: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value '{}' for {}", value, field))
+}
+
 #[derive(Clone, Debug)]
 pub struct Config {
     pub path: PathBuf,
@@ -23,25 +48,102 @@ pub struct Config {
     pub work_mode: bool,
     pub light_theme: bool,
     pub multi_monitor: bool,
+    /// Every display owns its own workspaces 1 to 10 instead of sharing them with the other
+    /// displays
+    pub display_workspaces: bool,
     pub launch_on_startup: bool,
     pub outer_gap: i32,
     pub inner_gap: i32,
+    /// The amount a tile grows or shrinks by with each keypress in resize mode
+    pub resize_step: i32,
     pub remove_title_bar: bool,
     pub remove_task_bar: bool,
+    /// Sets the taskbars to hide automatically instead of hiding them completely while
+    /// `remove_task_bar` is enabled
+    pub auto_hide_task_bar: bool,
     pub ignore_fullscreen_actions: bool,
     pub display_app_bar: bool,
     pub bar: BarConfig,
+    /// The space that is reserved for other bars and docks. The key is the index of the display.
+    pub reserved_space: HashMap<i32, ReservedSpace>,
+    /// What happens to the empty workspaces of a display. The key is the index of the display.
+    pub workspace_policies: HashMap<i32, WorkspacePolicy>,
     pub workspace_settings: Vec<WorkspaceSetting>,
     pub keybindings: Vec<Keybinding>,
     pub rules: Vec<Rule>,
+    /// The layouts that can be applied to a workspace with `nog.workspace.apply_template`
+    pub templates: Vec<Template>,
     pub update_channels: Vec<UpdateChannel>,
     pub default_update_channel: Option<String>,
     pub update_interval: Duration,
     pub mode_handlers: HashMap<String, usize>,
+    /// The actions that can be executed using the command palette
+    pub actions: Vec<Action>,
+    /// The jobs that get executed by the scheduler
+    pub jobs: Vec<Job>,
     /// contains the metadata for each mode (like an icon)
     /// HashMap<mode, (Option<char>)>
     pub mode_meta: HashMap<String, Option<char>>,
     pub allow_right_alt: bool,
+    /// Whether keybindings that use the windows key override the shortcuts of Windows
+    pub override_win_shortcuts: bool,
+    /// Whether tiles can be swapped and resized by dragging them with the mouse
+    pub mouse_integration: bool,
+    /// Show an osd when the workspace changes
+    pub osd_workspace: bool,
+    /// Switching to the workspace that is already focused switches back to the previous one
+    pub workspace_back_and_forth: bool,
+    /// Show an osd when a mode gets entered or left
+    pub osd_mode: bool,
+    /// How long an osd stays visible before it fades out in milliseconds
+    pub osd_duration: i32,
+    /// How many milliseconds the callback of a keybinding can take before the keybinding gets
+    /// disabled for a while. Zero lets callbacks take as long as they want.
+    pub keybinding_timeout: i32,
+    /// How many milliseconds a callback of the config can run before it gets stopped. Zero lets
+    /// callbacks run as long as they want.
+    pub script_timeout: i32,
+    /// How many instructions a callback of the config can execute. Zero means no limit.
+    pub script_max_instructions: i32,
+    /// How many function calls a callback of the config can nest. Zero means no limit.
+    pub script_max_depth: i32,
+    /// Reloads the config once the config file or a file it imports or includes gets saved
+    pub auto_reload: bool,
+    /// The command that opens a file of the config once its error popup gets clicked. `{file}`
+    /// and `{line}` get replaced with the location of the error.
+    pub editor: String,
+    pub workspace_preview: bool,
+    /// Opens new windows on the workspace where the user last placed a window of the same
+    /// application
+    pub smart_placement: bool,
+    /// Floats windows that can't be resized and dialogs instead of tiling them
+    pub float_fixed_size: bool,
+    /// Makes every managed window except the focused one translucent
+    pub dim_unfocused: bool,
+    /// The opacity of unfocused windows in percent if `dim_unfocused` is enabled
+    pub unfocused_opacity: i32,
+    /// The port the komorebi compatibility server listens on, which is disabled if this is 0
+    pub komorebi_port: u16,
+    /// Allows `nog.fs` to access files outside of the config directory
+    pub unrestricted_fs: bool,
+    pub game_mode: GameModeConfig,
+    pub launch_policy: LaunchPolicy,
+    /// Which layouts the workspaces start with
+    pub startup: StartupPolicy,
+    /// Which window gets focused when the focused window closes
+    pub close_focus: CloseFocusPolicy,
+    /// The ids of the callbacks that get called once after nog started and before it exits
+    pub on_startup: Vec<usize>,
+    pub on_shutdown: Vec<usize>,
+    /// The ids of the callbacks that get called with the id of an empty workspace after switching
+    /// to it
+    pub on_workspace_empty: Vec<usize>,
+    /// The profiles defined with `nog.profile.define`
+    pub profiles: Vec<Profile>,
+    /// The name of the profile that is currently applied
+    pub profile: Option<String>,
+    /// The profile scoped settings as the config sets them without any profile
+    pub profile_base: Option<ProfileSettings>,
 }
 
 impl Default for Config {
@@ -55,23 +157,60 @@ impl Default for Config {
             use_border: true,
             outer_gap: 0,
             inner_gap: 0,
+            resize_step: 2,
             remove_title_bar: true,
             work_mode: true,
             light_theme: false,
             multi_monitor: false,
+            display_workspaces: false,
             remove_task_bar: true,
+            auto_hide_task_bar: false,
             display_app_bar: true,
             ignore_fullscreen_actions: false,
             bar: BarConfig::default(),
+            reserved_space: HashMap::new(),
+            workspace_policies: HashMap::new(),
             mode_handlers: HashMap::new(),
+            actions: Vec::new(),
+            jobs: Vec::new(),
             mode_meta: HashMap::new(),
             workspace_settings: Vec::new(),
             keybindings: vec![],
             rules: Vec::new(),
+            templates: Vec::new(),
             update_channels: Vec::new(),
             default_update_channel: None,
             update_interval: Duration::from_secs(60 * 60),
             allow_right_alt: false,
+            override_win_shortcuts: false,
+            mouse_integration: false,
+            osd_workspace: false,
+            workspace_back_and_forth: false,
+            osd_mode: false,
+            osd_duration: 1000,
+            keybinding_timeout: 1000,
+            script_timeout: 5000,
+            script_max_instructions: 0,
+            script_max_depth: 200,
+            auto_reload: false,
+            editor: "notepad.exe {file}".into(),
+            workspace_preview: true,
+            smart_placement: false,
+            float_fixed_size: false,
+            dim_unfocused: false,
+            unfocused_opacity: 90,
+            komorebi_port: 0,
+            unrestricted_fs: false,
+            game_mode: GameModeConfig::default(),
+            launch_policy: LaunchPolicy::default(),
+            startup: StartupPolicy::default(),
+            close_focus: CloseFocusPolicy::default(),
+            on_startup: Vec::new(),
+            on_shutdown: Vec::new(),
+            on_workspace_empty: Vec::new(),
+            profiles: Vec::new(),
+            profile: None,
+            profile_base: None,
         }
     }
 }
@@ -92,23 +231,123 @@ impl Config {
         self.alter_numerical_field(field, -value);
     }
 
-    pub fn set(&mut self, field: &str, value: &str) {
+    /// Changes the setting with the given name, where `value` is the text representation of the
+    /// new value (e.g. `"true"` or `"10"`).
+    pub fn set(&mut self, field: &str, value: &str) -> Result<(), String> {
         match field {
-            "use_border" => self.use_border = value.parse().unwrap(),
-            "work_mode" => self.work_mode = value.parse().unwrap(),
-            "light_theme" => self.light_theme = value.parse().unwrap(),
-            "multi_monitor" => self.multi_monitor = value.parse().unwrap(),
-            "launch_on_startup" => self.launch_on_startup = value.parse().unwrap(),
-            "remove_title_bar" => self.remove_title_bar = value.parse().unwrap(),
-            "remove_task_bar" => self.remove_task_bar = value.parse().unwrap(),
-            "display_app_bar" => self.display_app_bar = value.parse().unwrap(),
-            "outer_gap" => self.outer_gap = value.parse().unwrap(),
-            "inner_gap" => self.inner_gap = value.parse().unwrap(),
-            "min_width" => self.min_width = value.parse().unwrap(),
-            "min_height" => self.min_height = value.parse().unwrap(),
-            "allow_right_alt" => self.allow_right_alt = value.parse().unwrap(),
-            _ => todo!("{}", field),
+            "use_border" => self.use_border = parse(field, value)?,
+            "work_mode" => self.work_mode = parse(field, value)?,
+            "light_theme" => self.light_theme = parse(field, value)?,
+            "multi_monitor" => self.multi_monitor = parse(field, value)?,
+            "display_workspaces" => self.display_workspaces = parse(field, value)?,
+            "launch_on_startup" => self.launch_on_startup = parse(field, value)?,
+            "remove_title_bar" => self.remove_title_bar = parse(field, value)?,
+            "remove_task_bar" => self.remove_task_bar = parse(field, value)?,
+            "auto_hide_task_bar" => self.auto_hide_task_bar = parse(field, value)?,
+            "display_app_bar" => self.display_app_bar = parse(field, value)?,
+            "outer_gap" => self.outer_gap = parse(field, value)?,
+            "inner_gap" => self.inner_gap = parse(field, value)?,
+            "resize_step" => self.resize_step = parse(field, value)?,
+            "min_width" => self.min_width = parse(field, value)?,
+            "min_height" => self.min_height = parse(field, value)?,
+            "allow_right_alt" => self.allow_right_alt = parse(field, value)?,
+            "override_win_shortcuts" => self.override_win_shortcuts = parse(field, value)?,
+            "mouse_integration" => self.mouse_integration = parse(field, value)?,
+            "osd_workspace" => self.osd_workspace = parse(field, value)?,
+            "workspace_back_and_forth" => self.workspace_back_and_forth = parse(field, value)?,
+            "osd_mode" => self.osd_mode = parse(field, value)?,
+            "osd_duration" => self.osd_duration = parse(field, value)?,
+            "keybinding_timeout" => self.keybinding_timeout = parse(field, value)?,
+            "script_timeout" => self.script_timeout = parse(field, value)?,
+            "script_max_instructions" => self.script_max_instructions = parse(field, value)?,
+            "script_max_depth" => self.script_max_depth = parse(field, value)?,
+            "auto_reload" => self.auto_reload = parse(field, value)?,
+            "editor" => self.editor = value.into(),
+            "workspace_preview" => self.workspace_preview = parse(field, value)?,
+            "smart_placement" => self.smart_placement = parse(field, value)?,
+            "float_fixed_size" => self.float_fixed_size = parse(field, value)?,
+            "dim_unfocused" => self.dim_unfocused = parse(field, value)?,
+            "unfocused_opacity" => self.unfocused_opacity = parse(field, value)?,
+            "komorebi_port" => self.komorebi_port = parse(field, value)?,
+            "unrestricted_fs" => self.unrestricted_fs = parse(field, value)?,
+            "ignore_fullscreen_actions" => self.ignore_fullscreen_actions = parse(field, value)?,
+            "bar.height" => self.bar.height = parse(field, value)?,
+            "bar.color" => {
+                let color = match value.strip_prefix('#') {
+                    Some(hex) => i32::from_str_radix(hex, 16)
+                        .map_err(|_| format!("Invalid color '{}' for {}", value, field))?,
+                    None => parse(field, value)?,
+                };
+                self.bar.color = util::swap_red_blue(color);
+            }
+            "bar.font" => self.bar.font = value.into(),
+            "bar.separator" => self.bar.separator = value.into(),
+            "bar.font_size" => self.bar.font_size = parse(field, value)?,
+            "bar.auto_hide" => self.bar.auto_hide = parse(field, value)?,
+            "bar.auto_hide_delay" => self.bar.auto_hide_delay = parse(field, value)?,
+            "bar.idle_timeout" => self.bar.idle_timeout = parse(field, value)?,
+            "bar.idle_interval" => self.bar.idle_interval = parse(field, value)?,
+            "startup" => self.startup = value.parse()?,
+            "close_focus" => self.close_focus = value.parse()?,
+            _ => return Err(format!("Unknown setting '{}'", field)),
         }
+
+        Ok(())
+    }
+
+    /// Returns the text representation of the setting with the given name, which `set` accepts
+    /// again.
+    pub fn get(&self, field: &str) -> Result<String, String> {
+        Ok(match field {
+            "use_border" => self.use_border.to_string(),
+            "work_mode" => self.work_mode.to_string(),
+            "light_theme" => self.light_theme.to_string(),
+            "multi_monitor" => self.multi_monitor.to_string(),
+            "display_workspaces" => self.display_workspaces.to_string(),
+            "launch_on_startup" => self.launch_on_startup.to_string(),
+            "remove_title_bar" => self.remove_title_bar.to_string(),
+            "remove_task_bar" => self.remove_task_bar.to_string(),
+            "auto_hide_task_bar" => self.auto_hide_task_bar.to_string(),
+            "display_app_bar" => self.display_app_bar.to_string(),
+            "outer_gap" => self.outer_gap.to_string(),
+            "inner_gap" => self.inner_gap.to_string(),
+            "resize_step" => self.resize_step.to_string(),
+            "min_width" => self.min_width.to_string(),
+            "min_height" => self.min_height.to_string(),
+            "allow_right_alt" => self.allow_right_alt.to_string(),
+            "override_win_shortcuts" => self.override_win_shortcuts.to_string(),
+            "mouse_integration" => self.mouse_integration.to_string(),
+            "osd_workspace" => self.osd_workspace.to_string(),
+            "workspace_back_and_forth" => self.workspace_back_and_forth.to_string(),
+            "osd_mode" => self.osd_mode.to_string(),
+            "osd_duration" => self.osd_duration.to_string(),
+            "keybinding_timeout" => self.keybinding_timeout.to_string(),
+            "script_timeout" => self.script_timeout.to_string(),
+            "script_max_instructions" => self.script_max_instructions.to_string(),
+            "script_max_depth" => self.script_max_depth.to_string(),
+            "auto_reload" => self.auto_reload.to_string(),
+            "editor" => self.editor.clone(),
+            "workspace_preview" => self.workspace_preview.to_string(),
+            "smart_placement" => self.smart_placement.to_string(),
+            "float_fixed_size" => self.float_fixed_size.to_string(),
+            "dim_unfocused" => self.dim_unfocused.to_string(),
+            "unfocused_opacity" => self.unfocused_opacity.to_string(),
+            "komorebi_port" => self.komorebi_port.to_string(),
+            "unrestricted_fs" => self.unrestricted_fs.to_string(),
+            "ignore_fullscreen_actions" => self.ignore_fullscreen_actions.to_string(),
+            "bar.height" => self.bar.height.to_string(),
+            "bar.color" => util::swap_red_blue(self.bar.color).to_string(),
+            "bar.font" => self.bar.font.clone(),
+            "bar.separator" => self.bar.separator.clone(),
+            "bar.font_size" => self.bar.font_size.to_string(),
+            "bar.auto_hide" => self.bar.auto_hide.to_string(),
+            "bar.auto_hide_delay" => self.bar.auto_hide_delay.to_string(),
+            "bar.idle_timeout" => self.bar.idle_timeout.to_string(),
+            "bar.idle_interval" => self.bar.idle_interval.to_string(),
+            "startup" => self.startup.to_string(),
+            "close_focus" => self.close_focus.to_string(),
+            _ => return Err(format!("Unknown setting '{}'", field)),
+        })
     }
 
     fn alter_numerical_field(&mut self, field: &str, value: i32) {
@@ -118,6 +357,13 @@ impl Config {
             "bar.font_size" => self.bar.font_size += value,
             "outer_gap" => self.outer_gap += value,
             "inner_gap" => self.inner_gap += value,
+            "resize_step" => self.resize_step += value,
+            "osd_duration" => self.osd_duration += value,
+            "keybinding_timeout" => self.keybinding_timeout += value,
+            "script_timeout" => self.script_timeout += value,
+            "script_max_instructions" => self.script_max_instructions += value,
+            "script_max_depth" => self.script_max_depth += value,
+            "unfocused_opacity" => self.unfocused_opacity += value,
             _ => error!("Attempt to alter unknown field: {} by {}", field, value),
         }
     }
@@ -129,8 +375,21 @@ impl Config {
             "launch_on_startup" => self.launch_on_startup = !self.launch_on_startup,
             "remove_title_bar" => self.remove_title_bar = !self.remove_title_bar,
             "remove_task_bar" => self.remove_task_bar = !self.remove_task_bar,
+            "auto_hide_task_bar" => self.auto_hide_task_bar = !self.auto_hide_task_bar,
             "display_app_bar" => self.display_app_bar = !self.display_app_bar,
             "allow_right_alt" => self.allow_right_alt = !self.allow_right_alt,
+            "override_win_shortcuts" => self.override_win_shortcuts = !self.override_win_shortcuts,
+            "mouse_integration" => self.mouse_integration = !self.mouse_integration,
+            "osd_workspace" => self.osd_workspace = !self.osd_workspace,
+            "workspace_back_and_forth" => {
+                self.workspace_back_and_forth = !self.workspace_back_and_forth
+            }
+            "workspace_preview" => self.workspace_preview = !self.workspace_preview,
+            "smart_placement" => self.smart_placement = !self.smart_placement,
+            "float_fixed_size" => self.float_fixed_size = !self.float_fixed_size,
+            "dim_unfocused" => self.dim_unfocused = !self.dim_unfocused,
+            "osd_mode" => self.osd_mode = !self.osd_mode,
+            "auto_reload" => self.auto_reload = !self.auto_reload,
             "ignore_fullscreen_actions" => {
                 self.ignore_fullscreen_actions = !self.ignore_fullscreen_actions
             }
@@ -143,10 +402,9 @@ impl Config {
             kb.key == keybinding.key
                 && kb.modifier == keybinding.modifier
                 && kb.mode == keybinding.mode
+                && kb.workspaces == keybinding.workspaces
         }) {
-            kb.always_active = kb.always_active;
-            kb.callback_id = kb.callback_id;
-            kb.mode = keybinding.mode;
+            *kb = keybinding;
         } else {
             self.keybindings.push(keybinding);
         }
@@ -160,17 +418,61 @@ impl Config {
             "launch_on_startup" => config.launch_on_startup = value,
             "remove_title_bar" => config.remove_title_bar = value,
             "remove_task_bar" => config.remove_task_bar = value,
+            "auto_hide_task_bar" => config.auto_hide_task_bar = value,
             "ignore_fullscreen_actions" => config.ignore_fullscreen_actions = value,
             "display_app_bar" => config.display_app_bar = value,
             "allow_right_alt" => config.allow_right_alt = value,
+            "override_win_shortcuts" => config.override_win_shortcuts = value,
+            "mouse_integration" => config.mouse_integration = value,
+            "osd_workspace" => config.osd_workspace = value,
+            "workspace_back_and_forth" => config.workspace_back_and_forth = value,
+            "workspace_preview" => config.workspace_preview = value,
+            "smart_placement" => config.smart_placement = value,
+            "float_fixed_size" => config.float_fixed_size = value,
+            "dim_unfocused" => config.dim_unfocused = value,
+            "osd_mode" => config.osd_mode = value,
+            "auto_reload" => config.auto_reload = value,
             _ => error!("Attempt to set unknown field: {}", field),
         }
         config
     }
 
+    /// The limits of the interpreter for the callbacks of the config
+    pub fn script_limits(&self) -> Limits {
+        let limit = |x: i32| Some(x).filter(|x| *x > 0);
+
+        Limits {
+            max_instructions: limit(self.script_max_instructions).map(|x| x as usize),
+            max_time: limit(self.script_timeout).map(|x| Duration::from_millis(x as u64)),
+            max_depth: limit(self.script_max_depth).map(|x| x as usize),
+        }
+    }
+
     pub fn get_update_channel(&self) -> Option<&UpdateChannel> {
         self.default_update_channel
             .clone()
             .and_then(|name| self.update_channels.iter().find(|c| c.name == name))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_and_get_settings() {
+        let mut config = Config::default();
+
+        config.set("inner_gap", "10").unwrap();
+        config.set("bar.color", "#112233").unwrap();
+        config.set("bar.auto_hide", "true").unwrap();
+
+        assert_eq!(config.inner_gap, 10);
+        assert_eq!(config.bar.color, 0x332211);
+        assert_eq!(config.get("bar.color"), Ok(0x112233.to_string()));
+        assert_eq!(config.get("bar.auto_hide"), Ok("true".into()));
+        assert!(config.set("inner_gap", "wide").is_err());
+        assert!(config.set("bar.width", "10").is_err());
+        assert!(config.get("bar.width").is_err());
+    }
+}
@@ -1,4 +1,7 @@
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
 
 use parking_lot::Mutex;
 
@@ -20,6 +23,85 @@ impl BarComponentsConfig {
         self.center = Vec::new();
         self.right = Vec::new();
     }
+
+    pub fn section_mut(&mut self, section: &str) -> Result<&mut Vec<Component>, String> {
+        match section {
+            "left" => Ok(&mut self.left),
+            "center" => Ok(&mut self.center),
+            "right" => Ok(&mut self.right),
+            _ => Err(format!("Unknown bar section '{}'", section)),
+        }
+    }
+
+    /// Inserts the component at the index of the section. The component gets appended if the index
+    /// is out of bounds.
+    pub fn insert(
+        &mut self,
+        section: &str,
+        idx: usize,
+        component: Component,
+    ) -> Result<(), String> {
+        let components = self.section_mut(section)?;
+
+        components.insert(idx.min(components.len()), component);
+
+        Ok(())
+    }
+
+    /// Removes the component with the given id from whichever section it is in.
+    pub fn remove(&mut self, id: usize) -> Option<Component> {
+        for components in vec![&mut self.left, &mut self.center, &mut self.right] {
+            if let Some(pos) = components.iter().position(|c| c.id == id) {
+                return Some(components.remove(pos));
+            }
+        }
+
+        None
+    }
+
+    /// Moves the component with the given id to the index of the section.
+    pub fn move_to(&mut self, id: usize, section: &str, idx: usize) -> Result<(), String> {
+        // makes sure that the component doesn't get lost if the section doesn't exist
+        self.section_mut(section)?;
+
+        let component = self
+            .remove(id)
+            .ok_or(format!("There is no component with the id {}", id))?;
+
+        self.insert(section, idx, component)
+    }
+
+    /// Returns every component together with the name of its section.
+    pub fn list(&self) -> Vec<(&'static str, &Component)> {
+        vec![
+            ("left", &self.left),
+            ("center", &self.center),
+            ("right", &self.right),
+        ]
+        .into_iter()
+        .flat_map(|(section, components)| components.iter().map(move |c| (section, c)))
+        .collect()
+    }
+}
+
+/// The overrides of the bar on a single display. Everything that is `None` uses the value of the
+/// `BarConfig`.
+#[derive(Clone, Debug, Default)]
+pub struct DisplayBarConfig {
+    pub left: Option<Vec<Component>>,
+    pub center: Option<Vec<Component>>,
+    pub right: Option<Vec<Component>>,
+    pub height: Option<i32>,
+    pub font: Option<String>,
+    pub font_size: Option<i32>,
+}
+
+impl DisplayBarConfig {
+    /// Returns the overrides that change how the bar looks, which decide whether the bar has to be
+    /// recreated.
+    fn appearance(&self) -> (Option<i32>, Option<&String>, Option<i32>) {
+        (self.height, self.font.as_ref(), self.font_size)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -28,7 +110,19 @@ pub struct BarConfig {
     pub color: i32,
     pub font: String,
     pub font_size: i32,
+    /// The text that gets drawn between two components of a section
+    pub separator: String,
     pub components: BarComponentsConfig,
+    /// Hides the bar once the mouse didn't touch it for `auto_hide_delay` milliseconds
+    pub auto_hide: bool,
+    pub auto_hide_delay: i32,
+    /// How many milliseconds without any input until the system counts as idle. Zero disables
+    /// the idle detection.
+    pub idle_timeout: i32,
+    /// How many milliseconds components wait between renders while the system is idle
+    pub idle_interval: i32,
+    /// The key is the index of the display, which is the same as the monitor of a workspace
+    pub displays: HashMap<i32, DisplayBarConfig>,
 }
 
 impl BarConfig {
@@ -36,14 +130,61 @@ impl BarConfig {
         self.components.left = vec![component::workspaces::create(state_arc.clone())];
         self.components.center = vec![component::time::create("%T".into())];
         self.components.right = vec![
-            component::active_mode::create(state_arc.clone()),
+            component::active_mode::create(),
             component::padding::create(5),
-            component::split_direction::create(state_arc.clone(), "V".into(), "H".into()),
+            component::split_direction::create("V".into(), "H".into()),
             component::padding::create(5),
             component::date::create("%e %b %Y".into()),
             component::padding::create(1),
         ];
     }
+
+    /// Returns the components of the bar on the display with the given index.
+    pub fn components_of(&self, display_idx: i32) -> BarComponentsConfig {
+        let mut components = self.components.clone();
+
+        if let Some(overrides) = self.displays.get(&display_idx) {
+            if let Some(left) = &overrides.left {
+                components.left = left.clone();
+            }
+            if let Some(center) = &overrides.center {
+                components.center = center.clone();
+            }
+            if let Some(right) = &overrides.right {
+                components.right = right.clone();
+            }
+        }
+
+        components
+    }
+
+    pub fn height_of(&self, display_idx: i32) -> i32 {
+        self.displays
+            .get(&display_idx)
+            .and_then(|d| d.height)
+            .unwrap_or(self.height)
+    }
+
+    pub fn font_of(&self, display_idx: i32) -> &str {
+        self.displays
+            .get(&display_idx)
+            .and_then(|d| d.font.as_ref())
+            .unwrap_or(&self.font)
+    }
+
+    pub fn font_size_of(&self, display_idx: i32) -> i32 {
+        self.displays
+            .get(&display_idx)
+            .and_then(|d| d.font_size)
+            .unwrap_or(self.font_size)
+    }
+
+    fn display_appearances(&self) -> BTreeMap<i32, (Option<i32>, Option<&String>, Option<i32>)> {
+        self.displays
+            .iter()
+            .map(|(idx, d)| (*idx, d.appearance()))
+            .collect()
+    }
 }
 
 impl PartialEq for BarConfig {
@@ -52,6 +193,7 @@ impl PartialEq for BarConfig {
             && self.color == other.color
             && self.font == other.font
             && self.font_size == other.font_size
+            && self.display_appearances() == other.display_appearances()
     }
 }
 
@@ -62,7 +204,69 @@ impl Default for BarConfig {
             color: 0x40342e,
             font: "Consolas".into(),
             font_size: 18,
+            separator: "".into(),
             components: BarComponentsConfig::default(),
+            auto_hide: false,
+            auto_hide_delay: 1000,
+            idle_timeout: 60000,
+            idle_interval: 10000,
+            displays: HashMap::new(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(components: &[Component]) -> Vec<String> {
+        components.iter().map(|c| c.name.clone()).collect()
+    }
+
+    #[test]
+    fn insert_and_remove_components() {
+        let mut config = BarComponentsConfig::default();
+        let recording = Component::new("Recording", |_| Ok(vec![]));
+        let id = recording.id;
+
+        config
+            .insert("left", 0, Component::new("A", |_| Ok(vec![])))
+            .unwrap();
+        config
+            .insert("left", 5, Component::new("B", |_| Ok(vec![])))
+            .unwrap();
+        config.insert("left", 1, recording).unwrap();
+
+        assert_eq!(names(&config.left), vec!["A", "Recording", "B"]);
+        assert!(config.insert("top", 0, Component::default()).is_err());
+
+        assert!(config.move_to(id, "top", 0).is_err());
+        config.move_to(id, "right", 0).unwrap();
+
+        assert_eq!(names(&config.left), vec!["A", "B"]);
+        assert_eq!(config.right[0].id, id);
+        assert!(config.remove(id).is_some());
+        assert!(config.remove(id).is_none());
+    }
+
+    #[test]
+    fn display_overrides() {
+        let mut config = BarConfig::default();
+        let default = config.clone();
+
+        config.displays.insert(
+            2,
+            DisplayBarConfig {
+                height: Some(40),
+                font_size: Some(32),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(config.height_of(1), 20);
+        assert_eq!(config.height_of(2), 40);
+        assert_eq!(config.font_size_of(2), 32);
+        assert_eq!(config.font_of(2), "Consolas");
+        assert!(config != default);
+    }
+}
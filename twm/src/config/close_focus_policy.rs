@@ -0,0 +1,44 @@
+use std::{fmt, str::FromStr};
+
+/// Decides which window gets focused when the focused window of a workspace closes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CloseFocusPolicy {
+    /// Windows decides which window gets focused
+    None,
+    /// The tile next to the closed one, preferring the one before it
+    Neighbor,
+    /// The window of the workspace that was focused before the closed one
+    History,
+}
+
+impl Default for CloseFocusPolicy {
+    fn default() -> Self {
+        CloseFocusPolicy::Neighbor
+    }
+}
+
+impl FromStr for CloseFocusPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "none" => Ok(CloseFocusPolicy::None),
+            "neighbor" => Ok(CloseFocusPolicy::Neighbor),
+            "history" => Ok(CloseFocusPolicy::History),
+            s => Err(format!(
+                "Unknown close focus policy '{}', expected none, neighbor or history",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for CloseFocusPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CloseFocusPolicy::None => write!(f, "none"),
+            CloseFocusPolicy::Neighbor => write!(f, "neighbor"),
+            CloseFocusPolicy::History => write!(f, "history"),
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! Shows the errors of the config in a popup. Clicking the popup opens the file that caused the
+//! error in the editor of the user, at the line of the error if it is known.
+use super::Config;
+use crate::{
+    launcher,
+    popup::{Popup, PopupAction},
+};
+use lazy_static::lazy_static;
+use log::error;
+use parking_lot::Mutex;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+lazy_static! {
+    /// The error that got shown last
+    static ref LAST: Mutex<Option<String>> = Mutex::new(None);
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorLocation {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+}
+
+/// Finds the location in errors like `Expected ... at 3,10 in C:\...\config.ns`, which is how the
+/// parser describes where an error happened.
+pub fn locate(msg: &str) -> Option<ErrorLocation> {
+    // the text of the error can contain " at " as well, so the first one that is followed by a
+    // position wins
+    msg.match_indices(" at ").find_map(|(idx, _)| {
+        let mut parts = msg[idx + 4..].splitn(2, " in ");
+        let mut position = parts.next()?.splitn(2, ',');
+        let line = position.next()?.parse().ok()?;
+        position.next()?.parse::<usize>().ok()?;
+
+        Some(ErrorLocation {
+            path: parts.next()?.trim().into(),
+            line: Some(line),
+        })
+    })
+}
+
+/// Fills in the `{file}` and `{line}` placeholders of the editor command. The file gets quoted,
+/// because its path can contain spaces.
+pub fn editor_command(editor: &str, location: &ErrorLocation) -> String {
+    editor
+        .replace("{file}", &format!("\"{}\"", location.path.display()))
+        .replace("{line}", &location.line.unwrap_or(1).to_string())
+}
+
+/// Whether the error is the same as the one that got shown last. Errors of the bar happen on every
+/// redraw, which would otherwise open a new popup every time.
+pub fn is_repeated(msg: &str) -> bool {
+    let mut last = LAST.lock();
+
+    if last.as_deref() == Some(msg) {
+        return true;
+    }
+
+    *last = Some(msg.to_string());
+    false
+}
+
+/// Shows the next error even if it is the same as the last one, e.g. because the config got
+/// reloaded in the meantime.
+pub fn reset() {
+    *LAST.lock() = None;
+}
+
+/// Creates the popup for the error. Errors that don't mention a file, like the ones that happen
+/// while the config runs, open the file that was running at the time.
+pub fn create(msg: String, file: &Path, config: &Config) -> Popup {
+    let location = locate(&msg).unwrap_or(ErrorLocation {
+        path: file.to_path_buf(),
+        line: None,
+    });
+    let cmd = editor_command(&config.editor, &location);
+    let policy = config.launch_policy.clone();
+
+    let mut popup = Popup::new_error(vec![msg, "(Click to open the file)".into()]);
+
+    popup.actions.push(PopupAction {
+        text: "Open".into(),
+        cb: Some(Arc::new(move || {
+            if let Err(e) = launcher::launch(&policy, &cmd) {
+                error!("Failed to open the config {}", e);
+            }
+        })),
+    });
+
+    popup
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locate_parse_errors() {
+        assert_eq!(
+            locate(r#"Expected "=", but found "let" at 3,10 in C:\nog\config.ns"#),
+            Some(ErrorLocation {
+                path: r"C:\nog\config.ns".into(),
+                line: Some(3),
+            })
+        );
+        assert_eq!(locate("Expected \"=\", but found \"let\" at 3,10"), None);
+        assert_eq!(locate("Module x couldn't be found"), None);
+    }
+
+    #[test]
+    fn fill_in_editor_command() {
+        let location = ErrorLocation {
+            path: r"C:\Users\Jane Doe\nog\config.ns".into(),
+            line: Some(12),
+        };
+
+        assert_eq!(
+            editor_command("code --goto {file}:{line}", &location),
+            r#"code --goto "C:\Users\Jane Doe\nog\config.ns":12"#
+        );
+        assert_eq!(
+            editor_command(
+                "notepad.exe {file}",
+                &ErrorLocation {
+                    line: None,
+                    ..location
+                }
+            ),
+            r#"notepad.exe "C:\Users\Jane Doe\nog\config.ns""#
+        );
+    }
+}
@@ -0,0 +1,248 @@
+//! Turns the custom layouts of PowerToys FancyZones into templates, so that people who come from
+//! FancyZones can keep their layouts.
+//!
+//! FancyZones describes a layout either as a grid, whose cells are assigned to zones, or as a
+//! canvas of freely placed zones. The zones get split into columns and rows recursively, which
+//! only works as long as no zone overlaps another one. Every zone becomes a placeholder that gets
+//! filled by any window of the workspace.
+use super::template::{Placeholder, Template, TemplateNode};
+use crate::system::Rectangle;
+use log::warn;
+use serde_json::Value;
+use std::{collections::BTreeMap, path::PathBuf};
+
+/// Returns the path of the file FancyZones stores its custom layouts in
+pub fn default_path() -> Option<PathBuf> {
+    let mut path = dirs::data_local_dir()?;
+    path.push("Microsoft");
+    path.push("PowerToys");
+    path.push("FancyZones");
+    path.push("custom-layouts.json");
+    Some(path)
+}
+
+fn numbers(value: &Value) -> Vec<i32> {
+    value
+        .as_array()
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|v| v.as_i64().map(|v| v as i32))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Returns the offset at which each cell starts, followed by the end of the last cell
+fn edges(sizes: &[i32]) -> Vec<i32> {
+    let mut edges = vec![0];
+    for size in sizes {
+        edges.push(edges[edges.len() - 1] + size);
+    }
+    edges
+}
+
+fn grid_zones(info: &Value) -> Option<Vec<Rectangle>> {
+    let rows = edges(&numbers(&info["rows-percentage"]));
+    let columns = edges(&numbers(&info["columns-percentage"]));
+    let mut zones: BTreeMap<i32, Rectangle> = BTreeMap::new();
+
+    for (row, cells) in info["cell-child-map"].as_array()?.iter().enumerate() {
+        for (column, zone) in numbers(cells).into_iter().enumerate() {
+            let cell = Rectangle {
+                left: *columns.get(column)?,
+                right: *columns.get(column + 1)?,
+                top: *rows.get(row)?,
+                bottom: *rows.get(row + 1)?,
+            };
+
+            let rect = zones.entry(zone).or_insert(cell);
+            rect.left = rect.left.min(cell.left);
+            rect.right = rect.right.max(cell.right);
+            rect.top = rect.top.min(cell.top);
+            rect.bottom = rect.bottom.max(cell.bottom);
+        }
+    }
+
+    Some(zones.values().copied().collect())
+}
+
+fn canvas_zones(info: &Value) -> Option<Vec<Rectangle>> {
+    info["zones"]
+        .as_array()?
+        .iter()
+        .map(|zone| {
+            let left = zone["X"].as_i64()? as i32;
+            let top = zone["Y"].as_i64()? as i32;
+
+            Some(Rectangle {
+                left,
+                right: left + zone["width"].as_i64()? as i32,
+                top,
+                bottom: top + zone["height"].as_i64()? as i32,
+            })
+        })
+        .collect()
+}
+
+/// Groups the zones into parts that don't overlap along the axis. Returns `None` if the zones
+/// can't be separated.
+fn cut(zones: &[Rectangle], span: fn(&Rectangle) -> (i32, i32)) -> Option<Vec<Vec<Rectangle>>> {
+    let mut sorted = zones.to_vec();
+    sorted.sort_by_key(span);
+
+    let mut groups: Vec<Vec<Rectangle>> = Vec::new();
+    let mut end = i32::MIN;
+
+    for zone in sorted {
+        let (start, zone_end) = span(&zone);
+
+        match groups.last_mut() {
+            Some(group) if start < end => group.push(zone),
+            _ => groups.push(vec![zone]),
+        }
+
+        end = end.max(zone_end);
+    }
+
+    if groups.len() > 1 {
+        Some(groups)
+    } else {
+        None
+    }
+}
+
+fn extent(zones: &[Rectangle], span: fn(&Rectangle) -> (i32, i32)) -> u32 {
+    let start = zones.iter().map(|z| span(z).0).min().unwrap_or(0);
+    let end = zones.iter().map(|z| span(z).1).max().unwrap_or(0);
+    (end - start).max(1) as u32
+}
+
+fn to_node(zones: &[Rectangle], size: u32) -> Option<TemplateNode> {
+    let horizontal = |z: &Rectangle| (z.left, z.right);
+    let vertical = |z: &Rectangle| (z.top, z.bottom);
+
+    if zones.len() == 1 {
+        return Some(TemplateNode::Window(size, Placeholder::any()));
+    }
+
+    if let Some(groups) = cut(zones, horizontal) {
+        return Some(TemplateNode::Columns(
+            size,
+            groups
+                .iter()
+                .map(|group| to_node(group, extent(group, horizontal)))
+                .collect::<Option<_>>()?,
+        ));
+    }
+
+    let groups = cut(zones, vertical)?;
+    Some(TemplateNode::Rows(
+        size,
+        groups
+            .iter()
+            .map(|group| to_node(group, extent(group, vertical)))
+            .collect::<Option<_>>()?,
+    ))
+}
+
+fn to_template(layout: &Value) -> Option<Template> {
+    let info = &layout["info"];
+    let zones = match layout["type"].as_str()? {
+        "grid" => grid_zones(info)?,
+        "canvas" => canvas_zones(info)?,
+        _ => return None,
+    };
+
+    if zones.is_empty() {
+        return None;
+    }
+
+    Some(Template {
+        name: layout["name"].as_str()?.into(),
+        root: to_node(&zones, 1)?,
+    })
+}
+
+/// Parses the content of `custom-layouts.json` or of `zones-settings.json`, which older versions
+/// of FancyZones use. Layouts that can't be turned into a template are skipped.
+pub fn parse(content: &str) -> Result<Vec<Template>, String> {
+    let value: Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    let layouts = value["custom-layouts"]
+        .as_array()
+        .or_else(|| value["custom-zone-sets"].as_array())
+        .ok_or("The file doesn't contain any FancyZones layouts")?;
+
+    Ok(layouts
+        .iter()
+        .filter_map(|layout| {
+            let template = to_template(layout);
+
+            if template.is_none() {
+                warn!(
+                    "Skipping the FancyZones layout '{}' because its zones can't be split into columns and rows",
+                    layout["name"].as_str().unwrap_or_default()
+                );
+            }
+
+            template
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::WindowId;
+
+    #[test]
+    fn layouts_become_templates() {
+        let content = r#"{
+            "custom-layouts": [
+                {
+                    "name": "Grid",
+                    "type": "grid",
+                    "info": {
+                        "rows": 2,
+                        "columns": 3,
+                        "rows-percentage": [5000, 5000],
+                        "columns-percentage": [3333, 3333, 3334],
+                        "cell-child-map": [[0, 1, 1], [0, 2, 3]]
+                    }
+                },
+                {
+                    "name": "Halves",
+                    "type": "canvas",
+                    "info": {
+                        "zones": [
+                            { "X": 960, "Y": 0, "width": 960, "height": 1080 },
+                            { "X": 0, "Y": 0, "width": 960, "height": 1080 }
+                        ]
+                    }
+                },
+                {
+                    "name": "Overlapping",
+                    "type": "canvas",
+                    "info": {
+                        "zones": [
+                            { "X": 0, "Y": 0, "width": 1000, "height": 1080 },
+                            { "X": 900, "Y": 0, "width": 1020, "height": 1080 }
+                        ]
+                    }
+                }
+            ]
+        }"#;
+
+        let templates = parse(content).unwrap();
+        let ids: Vec<Option<WindowId>> = (1..=4).map(|id| Some(WindowId::from(id))).collect();
+
+        assert_eq!(templates.len(), 2);
+        assert_eq!(templates[0].name, "Grid");
+        assert_eq!(
+            templates[0].layout(&ids),
+            "c0|120[t0|39|1,r1|81[t0|60|2,c1|60[t0|59|3,t1|61|4]]]"
+        );
+        assert_eq!(templates[1].layout(&ids), "c0|120[t0|60|1,t1|60|2]");
+        assert!(parse("{}").is_err());
+    }
+}
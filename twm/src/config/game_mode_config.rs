@@ -0,0 +1,33 @@
+/// Decides what happens while a fullscreen application (usually a game) is in the foreground.
+#[derive(Debug, Clone)]
+pub struct GameModeConfig {
+    /// Whether game mode gets entered automatically. This is off by default, because a browser or
+    /// video player in fullscreen looks the same as a game.
+    pub enabled: bool,
+    pub suspend_keybindings: bool,
+    /// Hides the bar on the display of the application
+    pub hide_bar: bool,
+    pub disable_mouse_integration: bool,
+    /// Stops redrawing the workspace on the display of the application until it exits
+    pub suspend_tiling: bool,
+    /// Ignores windows that appear, disappear or get focused on the display of the application
+    pub pause_events: bool,
+    /// The ids of the callbacks that get called when game mode gets entered or left
+    pub on_enter: Option<usize>,
+    pub on_leave: Option<usize>,
+}
+
+impl Default for GameModeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            suspend_keybindings: true,
+            hide_bar: true,
+            disable_mouse_integration: true,
+            suspend_tiling: true,
+            pause_events: true,
+            on_enter: None,
+            on_leave: None,
+        }
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{event::Event, AppState};
+//! Reloads the config once the user saves the config file or one of the files it imports or
+//! includes, as long as `auto_reload` is enabled.
+use crate::{event::Event, state_handle::StateHandle};
+use lazy_static::lazy_static;
 use log::{debug, error};
 use notify::watcher;
 use notify::DebouncedEvent;
@@ -6,17 +9,32 @@ use notify::RecursiveMode;
 use notify::Watcher;
 use parking_lot::Mutex;
 use std::{
-    sync::{mpsc::channel, Arc},
+    collections::HashSet,
+    path::PathBuf,
+    sync::mpsc::channel,
     thread,
+    time::Duration,
 };
 
-pub fn start(state: Arc<Mutex<AppState>>) {
-    let state = state.clone();
+/// Editors often write a file more than once while saving it, so the config only gets reloaded
+/// once the files didn't change for this long.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+lazy_static! {
+    /// The config file and every file it imported or included the last time it got loaded
+    static ref FILES: Mutex<HashSet<PathBuf>> = Mutex::new(HashSet::new());
+}
+
+/// Replaces the files that get watched, which happens every time the config got loaded.
+pub fn set_files(files: impl IntoIterator<Item = PathBuf>) {
+    *FILES.lock() = files.into_iter().collect();
+}
+
+pub fn start(handle: StateHandle) {
     thread::spawn(move || {
         let (tx, rx) = channel();
 
-        let mut watcher = watcher(tx, std::time::Duration::from_millis(10))
-            .expect("Failed to spawn file watcher");
+        let mut watcher = watcher(tx, DEBOUNCE).expect("Failed to spawn file watcher");
 
         let mut path = dirs::config_dir().expect("Failed to get config dir");
 
@@ -24,29 +42,39 @@ pub fn start(state: Arc<Mutex<AppState>>) {
 
         debug!("Watching {:?} recursively for file changes", &path);
 
+        // files that get imported for the first time after a change can be anywhere inside of
+        // the directory, so the whole directory is watched instead of the files themselves
         watcher
             .watch(path, RecursiveMode::Recursive)
             .expect("Failed to watch config directory");
 
         loop {
-            match rx.recv() {
-                Ok(ev) => match ev {
-                    DebouncedEvent::Write(path) => {
-                        if path.extension().unwrap() == "ns" {
-                            debug!("Nogscript file {:?} changed! Reloading config", &path);
-                            state
-                                .lock()
-                                .event_channel
-                                .sender
-                                .clone()
-                                .send(Event::ReloadConfig)
-                                .expect("Failed to send ReloadConfig event");
-                        }
-                    }
-                    _ => {}
-                },
-                Err(e) => error!("watch error: {:?}", e),
+            let path = match rx.recv() {
+                // editors that save by replacing the file create or rename it instead
+                Ok(DebouncedEvent::Write(path))
+                | Ok(DebouncedEvent::Create(path))
+                | Ok(DebouncedEvent::Rename(_, path)) => path,
+                Ok(_) => continue,
+                Err(e) => {
+                    error!("watch error: {:?}", e);
+                    continue;
+                }
+            };
+
+            if !FILES.lock().contains(&path) {
+                continue;
             }
+
+            handle.update(move |state| {
+                if state.config.auto_reload {
+                    debug!("Nogscript file {:?} changed! Reloading config", &path);
+                    state
+                        .event_channel
+                        .sender
+                        .send(Event::AutoReloadConfig)
+                        .expect("Failed to send AutoReloadConfig event");
+                }
+            });
         }
     });
 }
@@ -0,0 +1,252 @@
+use std::path::{Path, PathBuf};
+
+/// Environment variables that are always passed on, because most programs can't start without them
+const REQUIRED_ENV: [&'static str; 2] = ["PATH", "SYSTEMROOT"];
+
+/// Restricts which programs `nog.launch` is allowed to start and how they get started.
+#[derive(Debug, Clone, Default)]
+pub struct LaunchPolicy {
+    /// Only these programs can be launched. Every program is allowed if this is `None`.
+    pub allowed: Option<Vec<String>>,
+    /// These programs can never be launched
+    pub blocked: Vec<String>,
+    pub working_directory: Option<PathBuf>,
+    /// The environment variables a launched program inherits. Every variable gets inherited if this
+    /// is `None`.
+    pub env: Option<Vec<String>>,
+    /// Kills the program if it is still running after this many milliseconds
+    pub timeout: Option<u64>,
+}
+
+/// Whether the entry refers to the program, either by its path, its file name or its file name
+/// without the extension.
+fn matches(entry: &str, program: &str) -> bool {
+    let entry = entry.to_lowercase();
+    let program = program.to_lowercase();
+    let path = Path::new(&program);
+    let file_name = path.file_name().and_then(|s| s.to_str());
+    let file_stem = path.file_stem().and_then(|s| s.to_str());
+
+    entry == program || Some(entry.as_str()) == file_name || Some(entry.as_str()) == file_stem
+}
+
+/// Keeps the entries that both lists allow. The more specific entry is kept if only one of them
+/// is a path.
+fn intersect_programs(current: &[String], other: &[String]) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+
+    for a in current {
+        for b in other {
+            let entry = if matches(a, b) {
+                b
+            } else if matches(b, a) {
+                a
+            } else {
+                continue;
+            };
+
+            if !result.contains(entry) {
+                result.push(entry.clone());
+            }
+        }
+    }
+
+    result
+}
+
+fn intersect_env(current: &[String], other: &[String]) -> Vec<String> {
+    current
+        .iter()
+        .filter(|a| other.iter().any(|b| a.to_uppercase() == b.to_uppercase()))
+        .cloned()
+        .collect()
+}
+
+/// `None` stands for no restriction, which is why it loses against any list
+fn intersect(
+    current: Option<Vec<String>>,
+    other: Option<Vec<String>>,
+    f: impl Fn(&[String], &[String]) -> Vec<String>,
+) -> Option<Vec<String>> {
+    match (current, other) {
+        (Some(current), Some(other)) => Some(f(&current, &other)),
+        (current, other) => current.or(other),
+    }
+}
+
+impl LaunchPolicy {
+    pub fn check(&self, program: &str) -> Result<(), String> {
+        if self.blocked.iter().any(|entry| matches(entry, program)) {
+            return Err(format!("Launching '{}' is blocked", program));
+        }
+
+        if let Some(allowed) = &self.allowed {
+            if !allowed.iter().any(|entry| matches(entry, program)) {
+                return Err(format!("Launching '{}' is not allowed", program));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Combines this policy with one that got configured later on. The result is at least as
+    /// strict as both of them, so that imported modules can't loosen the policy of the config.
+    pub fn restrict(&mut self, other: LaunchPolicy) -> Result<(), String> {
+        if let (Some(current), Some(dir)) = (&self.working_directory, &other.working_directory) {
+            if current != dir {
+                return Err(format!(
+                    "The working directory of the launch policy is already set to '{}'",
+                    current.display()
+                ));
+            }
+        }
+
+        self.allowed = intersect(self.allowed.take(), other.allowed, intersect_programs);
+        self.blocked.extend(other.blocked);
+        self.working_directory = self.working_directory.take().or(other.working_directory);
+        self.env = intersect(self.env.take(), other.env, intersect_env);
+        self.timeout = match (self.timeout, other.timeout) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+
+        Ok(())
+    }
+
+    /// Whether a launched program inherits the environment variable.
+    pub fn keeps_env(&self, name: &str) -> bool {
+        let name = name.to_uppercase();
+
+        match &self.env {
+            Some(env) => {
+                REQUIRED_ENV.contains(&name.as_str())
+                    || env.iter().any(|allowed| allowed.to_uppercase() == name)
+            }
+            None => true,
+        }
+    }
+}
+
+/// Splits the command into the program and its arguments. Double quotes group words that contain
+/// whitespace.
+pub fn split_command(cmd: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_part = false;
+
+    for c in cmd.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_part = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_part {
+                    parts.push(std::mem::take(&mut current));
+                    has_part = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_part = true;
+            }
+        }
+    }
+
+    if has_part {
+        parts.push(current);
+    }
+
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_commands() {
+        assert_eq!(
+            split_command(r#"  "C:\Program Files\app.exe" --flag "" x "#),
+            vec![r"C:\Program Files\app.exe", "--flag", "", "x"]
+        );
+    }
+
+    #[test]
+    fn check_programs() {
+        let policy = LaunchPolicy {
+            allowed: Some(vec!["notepad".into(), "wt.exe".into()]),
+            blocked: vec!["wt".into()],
+            ..LaunchPolicy::default()
+        };
+
+        assert!(policy.check(r"C:\Windows\Notepad.exe").is_ok());
+        assert!(policy.check("wt.exe").is_err());
+        assert!(policy.check("cmd").is_err());
+        assert!(LaunchPolicy::default().check("cmd").is_ok());
+    }
+
+    #[test]
+    fn restrict_only_tightens() {
+        let mut policy = LaunchPolicy {
+            allowed: Some(vec!["notepad".into(), "wt".into()]),
+            env: Some(vec!["USERPROFILE".into(), "APPDATA".into()]),
+            timeout: Some(1000),
+            ..LaunchPolicy::default()
+        };
+
+        // an imported module that tries to allow everything
+        policy
+            .restrict(LaunchPolicy {
+                allowed: Some(vec![r"C:\Windows\notepad.exe".into(), "cmd".into()]),
+                env: Some(vec!["appdata".into(), "GITHUB_TOKEN".into()]),
+                timeout: Some(5000),
+                ..LaunchPolicy::default()
+            })
+            .unwrap();
+        policy
+            .restrict(LaunchPolicy {
+                blocked: vec!["explorer".into()],
+                ..LaunchPolicy::default()
+            })
+            .unwrap();
+
+        assert!(policy.check(r"C:\Windows\notepad.exe").is_ok());
+        assert!(policy.check(r"D:\notepad.exe").is_err());
+        assert!(policy.check("cmd").is_err());
+        assert!(policy.check("wt").is_err());
+        assert!(policy.keeps_env("APPDATA"));
+        assert!(!policy.keeps_env("USERPROFILE"));
+        assert!(!policy.keeps_env("GITHUB_TOKEN"));
+        assert_eq!(policy.blocked, vec!["explorer".to_string()]);
+        assert_eq!(policy.timeout, Some(1000));
+    }
+
+    #[test]
+    fn restrict_keeps_working_directory() {
+        let mut policy = LaunchPolicy::default();
+        let dir = |d: &str| LaunchPolicy {
+            working_directory: Some(d.into()),
+            ..LaunchPolicy::default()
+        };
+
+        policy.restrict(dir(r"C:\Users\me")).unwrap();
+        policy.restrict(dir(r"C:\Users\me")).unwrap();
+
+        assert!(policy.restrict(dir(r"C:\Temp")).is_err());
+        assert_eq!(policy.working_directory, Some(r"C:\Users\me".into()));
+    }
+
+    #[test]
+    fn scrub_env() {
+        let policy = LaunchPolicy {
+            env: Some(vec!["UserProfile".into()]),
+            ..LaunchPolicy::default()
+        };
+
+        assert!(policy.keeps_env("USERPROFILE"));
+        assert!(policy.keeps_env("Path"));
+        assert!(!policy.keeps_env("GITHUB_TOKEN"));
+    }
+}
@@ -0,0 +1,98 @@
+use super::{
+    bar_config::DisplayBarConfig, rule::Rule, workspace_setting::WorkspaceSetting, Config,
+};
+use std::collections::HashMap;
+
+/// A named set of settings that gets applied on top of the config by `nog.profile.switch` or when
+/// the machine matches the profile on startup.
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+    pub name: String,
+    /// The id of the callback that configures the profile
+    pub callback_id: usize,
+    /// The profile gets selected on startup on the machine with this name
+    pub hostname: Option<String>,
+    /// The profile gets selected on startup while this many displays are connected
+    pub displays: Option<usize>,
+}
+
+impl Profile {
+    /// Whether the profile should get selected on startup. A profile without any condition only
+    /// gets selected explicitly.
+    pub fn matches(&self, hostname: Option<&str>, displays: usize) -> bool {
+        if self.hostname.is_none() && self.displays.is_none() {
+            return false;
+        }
+
+        let hostname_matches = match (&self.hostname, hostname) {
+            (Some(expected), Some(actual)) => expected.eq_ignore_ascii_case(actual),
+            (Some(_), None) => false,
+            (None, _) => true,
+        };
+
+        hostname_matches && self.displays.map(|x| x == displays).unwrap_or(true)
+    }
+}
+
+/// Returns the first profile that matches the machine
+pub fn detect<'a>(
+    profiles: &'a [Profile],
+    hostname: Option<&str>,
+    displays: usize,
+) -> Option<&'a Profile> {
+    profiles.iter().find(|p| p.matches(hostname, displays))
+}
+
+/// The settings a profile is allowed to change. Everything else only changes when the config gets
+/// reloaded.
+#[derive(Debug, Clone, Default)]
+pub struct ProfileSettings {
+    pub rules: Vec<Rule>,
+    pub workspace_settings: Vec<WorkspaceSetting>,
+    pub displays: HashMap<i32, DisplayBarConfig>,
+}
+
+impl ProfileSettings {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            rules: config.rules.clone(),
+            workspace_settings: config.workspace_settings.clone(),
+            displays: config.bar.displays.clone(),
+        }
+    }
+
+    pub fn apply(&self, config: &mut Config) {
+        config.rules = self.rules.clone();
+        config.workspace_settings = self.workspace_settings.clone();
+        config.bar.displays = self.displays.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_by_hostname_and_displays() {
+        let profile = |name: &str, hostname: Option<&str>, displays: Option<usize>| Profile {
+            name: name.into(),
+            hostname: hostname.map(String::from),
+            displays,
+            ..Profile::default()
+        };
+        let profiles = vec![
+            profile("manual", None, None),
+            profile("work", Some("WORK-PC"), None),
+            profile("docked", Some("laptop"), Some(3)),
+            profile("laptop", Some("laptop"), None),
+        ];
+        let detected =
+            |hostname, displays| detect(&profiles, hostname, displays).map(|p| p.name.as_str());
+
+        assert_eq!(detected(Some("work-pc"), 1), Some("work"));
+        assert_eq!(detected(Some("LAPTOP"), 3), Some("docked"));
+        assert_eq!(detected(Some("laptop"), 1), Some("laptop"));
+        assert_eq!(detected(Some("home-pc"), 2), None);
+        assert_eq!(detected(None, 3), None);
+    }
+}
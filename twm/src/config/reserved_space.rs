@@ -0,0 +1,9 @@
+/// The space at the edges of a display that tiles and the bar stay away from, so that they don't
+/// overlap bars and docks of other programs.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ReservedSpace {
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+    pub left: i32,
+}
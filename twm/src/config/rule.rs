@@ -1,3 +1,7 @@
+use crate::{
+    keybindings::{key::Key, modifier::Modifier},
+    system::{System, WindowId},
+};
 use regex::Regex;
 
 #[derive(Debug, Clone)]
@@ -8,6 +12,21 @@ pub struct Rule {
     pub chromium: bool,
     pub firefox: bool,
     pub workspace_id: i32,
+    /// Overrides whether the window floats instead of being tiled. If it isn't set, small windows
+    /// and (with `float_fixed_size`) windows that can't be resized float.
+    pub float: Option<bool>,
+    /// Overrides the `remove_title_bar` setting for the windows of the rule
+    pub remove_title_bar: Option<bool>,
+    /// Overrides the `use_border` setting for the windows of the rule
+    pub use_border: Option<bool>,
+    /// Overrides the `unfocused_opacity` setting for the windows of the rule
+    pub unfocused_opacity: Option<i32>,
+    /// The key combos that reach the windows of the rule instead of nog while one of them is
+    /// focused
+    pub pass_through: Vec<(Key, Modifier)>,
+    /// Overrides whether the windows of the rule are games, which enter game mode while they are
+    /// in the foreground even if they aren't fullscreen
+    pub game: Option<bool>,
 }
 
 impl Default for Rule {
@@ -19,6 +38,26 @@ impl Default for Rule {
             chromium: false,
             firefox: false,
             workspace_id: -1,
+            float: None,
+            remove_title_bar: None,
+            use_border: None,
+            unfocused_opacity: None,
+            pass_through: Vec::new(),
+            game: None,
         }
     }
 }
+
+impl Rule {
+    /// Whether the pattern matches the title or the process of the window. Patterns that contain
+    /// a backslash get matched against the path of the process.
+    pub fn matches(&self, system: &dyn System, id: WindowId, title: &str) -> bool {
+        let process_name = if self.pattern.to_string().contains('\\') {
+            system.get_process_path(id)
+        } else {
+            system.get_process_name(id)
+        };
+
+        self.pattern.is_match(&process_name) || self.pattern.is_match(title)
+    }
+}
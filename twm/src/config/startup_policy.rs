@@ -0,0 +1,81 @@
+use std::{fmt, str::FromStr};
+
+/// Decides which layouts the workspaces start with when nog launches.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StartupPolicy {
+    /// Every workspace starts empty
+    Fresh,
+    /// The workspaces get the layouts they had when nog last exited
+    RestoreSession,
+    /// The workspaces get the layouts of the snapshot that has the given name
+    RestoreNamed(String),
+}
+
+impl Default for StartupPolicy {
+    fn default() -> Self {
+        StartupPolicy::RestoreSession
+    }
+}
+
+impl FromStr for StartupPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        match s {
+            "fresh" => return Ok(StartupPolicy::Fresh),
+            "restore_session" => return Ok(StartupPolicy::RestoreSession),
+            _ => {}
+        }
+
+        let name = s
+            .strip_prefix("restore_named(")
+            .and_then(|rest| rest.strip_suffix(')'))
+            .map(|name| name.trim().trim_matches('"'))
+            .ok_or(format!(
+                "Unknown startup policy '{}', expected fresh, restore_session or restore_named(<snapshot>)",
+                s
+            ))?;
+
+        if name.is_empty() {
+            return Err("restore_named requires the name of a snapshot".into());
+        }
+
+        Ok(StartupPolicy::RestoreNamed(name.into()))
+    }
+}
+
+impl fmt::Display for StartupPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StartupPolicy::Fresh => write!(f, "fresh"),
+            StartupPolicy::RestoreSession => write!(f, "restore_session"),
+            StartupPolicy::RestoreNamed(name) => write!(f, "restore_named({})", name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_policies() {
+        assert_eq!("fresh".parse(), Ok(StartupPolicy::Fresh));
+        assert_eq!(
+            " restore_session ".parse(),
+            Ok(StartupPolicy::RestoreSession)
+        );
+        assert_eq!(
+            r#"restore_named("coding")"#.parse(),
+            Ok(StartupPolicy::RestoreNamed("coding".into()))
+        );
+        assert_eq!(
+            StartupPolicy::RestoreNamed("coding".into()).to_string(),
+            "restore_named(coding)"
+        );
+        assert!("restore_named()".parse::<StartupPolicy>().is_err());
+        assert!("restore".parse::<StartupPolicy>().is_err());
+    }
+}
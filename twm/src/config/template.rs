@@ -0,0 +1,220 @@
+//! Layouts that are defined by the config and whose tiles are placeholders for the windows of
+//! certain programs, like an editor next to a terminal and a browser for a project.
+//!
+//! A template gets turned into the format of `TileGrid::to_string`, so that a workspace can load
+//! it with `TileGrid::from_string`.
+use crate::system::WindowId;
+use regex::Regex;
+
+/// The size of the root node, which the sizes of the children of a node add up to
+const FULL_SIZE: u32 = 120;
+
+#[derive(Debug, Clone)]
+pub struct Placeholder {
+    /// The name of the executable, e.g. `code.exe`. A placeholder without an executable gets
+    /// filled by any window of the workspace.
+    pub exe: Option<String>,
+    /// Only windows whose title matches get placed into the placeholder
+    pub title: Option<Regex>,
+    /// Gets launched if no window matches the placeholder. Defaults to the executable.
+    pub cmd: Option<String>,
+}
+
+impl Placeholder {
+    pub fn new(exe: &str) -> Self {
+        Self {
+            exe: Some(exe.into()),
+            title: None,
+            cmd: None,
+        }
+    }
+
+    pub fn any() -> Self {
+        Self {
+            exe: None,
+            title: None,
+            cmd: None,
+        }
+    }
+
+    pub fn matches(&self, process_name: &str, title: &str) -> bool {
+        self.exe
+            .as_ref()
+            .map_or(false, |exe| exe.eq_ignore_ascii_case(process_name))
+            && self.title.as_ref().map_or(true, |t| t.is_match(title))
+    }
+
+    /// Returns the program that gets launched for the placeholder. Placeholders without an
+    /// executable don't launch anything.
+    pub fn command(&self) -> Option<&str> {
+        self.exe.as_ref()?;
+        self.cmd.as_deref().or(self.exe.as_deref())
+    }
+}
+
+/// A node of a template. The number is the size of the node relative to its siblings.
+#[derive(Debug, Clone)]
+pub enum TemplateNode {
+    /// The children are placed next to each other
+    Columns(u32, Vec<TemplateNode>),
+    /// The children are placed below each other
+    Rows(u32, Vec<TemplateNode>),
+    Window(u32, Placeholder),
+}
+
+/// A template node with every placeholder left out that has no window
+enum Filled {
+    Container(char, u32, Vec<Filled>),
+    Tile(u32, WindowId),
+}
+
+impl Filled {
+    fn weight(&self) -> u32 {
+        match self {
+            Filled::Container(_, weight, _) | Filled::Tile(weight, _) => *weight,
+        }
+    }
+
+    fn write(&self, order: usize, size: u32, out: &mut String) {
+        match self {
+            Filled::Tile(_, id) => out.push_str(&format!("t{}|{}|{}", order, size, id)),
+            Filled::Container(kind, _, children) => {
+                out.push_str(&format!("{}{}|{}[", kind, order, size));
+
+                let total: u32 = children.iter().map(Filled::weight).sum();
+                let mut remaining = FULL_SIZE;
+
+                for (i, child) in children.iter().enumerate() {
+                    // the last child gets what is left, so that the sizes add up
+                    let size = if i == children.len() - 1 {
+                        remaining
+                    } else {
+                        FULL_SIZE * child.weight() / total
+                    };
+                    remaining -= size;
+
+                    if i > 0 {
+                        out.push(',');
+                    }
+
+                    child.write(i, size, out);
+                }
+
+                out.push(']');
+            }
+        }
+    }
+}
+
+impl TemplateNode {
+    fn fill(&self, windows: &[Option<WindowId>], next: &mut usize) -> Option<Filled> {
+        let (kind, weight, children) = match self {
+            TemplateNode::Window(weight, _) => {
+                let window = windows.get(*next).copied().flatten();
+                *next += 1;
+                return window.map(|id| Filled::Tile((*weight).max(1), id));
+            }
+            TemplateNode::Columns(weight, children) => ('c', weight, children),
+            TemplateNode::Rows(weight, children) => ('r', weight, children),
+        };
+
+        let mut filled: Vec<Filled> = children
+            .iter()
+            .filter_map(|child| child.fill(windows, next))
+            .collect();
+
+        match filled.len() {
+            0 => None,
+            // a container with a single child is replaced by the child
+            1 => Some(match filled.remove(0) {
+                Filled::Tile(_, id) => Filled::Tile((*weight).max(1), id),
+                Filled::Container(kind, _, children) => {
+                    Filled::Container(kind, (*weight).max(1), children)
+                }
+            }),
+            _ => Some(Filled::Container(kind, (*weight).max(1), filled)),
+        }
+    }
+
+    fn collect_placeholders<'a>(&'a self, placeholders: &mut Vec<&'a Placeholder>) {
+        match self {
+            TemplateNode::Window(_, placeholder) => placeholders.push(placeholder),
+            TemplateNode::Columns(_, children) | TemplateNode::Rows(_, children) => {
+                for child in children {
+                    child.collect_placeholders(placeholders);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Template {
+    pub name: String,
+    pub root: TemplateNode,
+}
+
+impl Template {
+    /// Returns the placeholders from left to right and from top to bottom
+    pub fn placeholders(&self) -> Vec<&Placeholder> {
+        let mut placeholders = Vec::new();
+        self.root.collect_placeholders(&mut placeholders);
+        placeholders
+    }
+
+    /// Returns the layout of the template, where the window of each placeholder is at the same
+    /// index as the placeholder. Placeholders without a window are left out.
+    pub fn layout(&self, windows: &[Option<WindowId>]) -> String {
+        let mut layout = String::new();
+
+        if let Some(filled) = self.root.fill(windows, &mut 0) {
+            filled.write(0, FULL_SIZE, &mut layout);
+        }
+
+        layout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dev_template() -> Template {
+        Template {
+            name: "dev".into(),
+            root: TemplateNode::Columns(
+                1,
+                vec![
+                    TemplateNode::Window(2, Placeholder::new("code.exe")),
+                    TemplateNode::Rows(
+                        1,
+                        vec![
+                            TemplateNode::Window(1, Placeholder::new("wt.exe")),
+                            TemplateNode::Window(1, Placeholder::new("firefox.exe")),
+                        ],
+                    ),
+                ],
+            ),
+        }
+    }
+
+    #[test]
+    fn layout_of_template() {
+        let template = dev_template();
+        let id = |id: i32| Some(WindowId::from(id));
+
+        assert_eq!(template.placeholders().len(), 3);
+        assert!(template.placeholders()[0].matches("Code.exe", "main.rs"));
+        assert!(!Placeholder::any().matches("code.exe", "main.rs"));
+        assert_eq!(
+            template.layout(&[id(1), id(2), id(3)]),
+            "c0|120[t0|80|1,r1|40[t0|60|2,t1|60|3]]"
+        );
+        assert_eq!(
+            template.layout(&[id(1), None, id(3)]),
+            "c0|120[t0|80|1,t1|40|3]"
+        );
+        assert_eq!(template.layout(&[None, None, id(3)]), "t0|120|3");
+        assert_eq!(template.layout(&[None, None, None]), "");
+    }
+}
@@ -0,0 +1,11 @@
+/// What happens to the workspaces of a display once their last window leaves. Workspaces with a
+/// name, which is the text of their workspace setting, are always left alone.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct WorkspacePolicy {
+    /// Forgets the layout of an empty workspace once it isn't focused anymore, as if it got
+    /// deleted
+    pub remove_empty: bool,
+    /// Moves the windows of the workspaces after an empty one down, so that the numbers of the
+    /// workspaces with windows don't have gaps
+    pub compact: bool,
+}
@@ -1,8 +1,10 @@
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct WorkspaceSetting {
     pub id: i32,
     pub monitor: i32,
     pub text: String,
+    /// The color of the bar while the workspace is focused
+    pub color: Option<i32>,
 }
 
 impl Default for WorkspaceSetting {
@@ -11,6 +13,7 @@ impl Default for WorkspaceSetting {
             id: -1,
             monitor: -1,
             text: "".into(),
+            color: None,
         }
     }
 }
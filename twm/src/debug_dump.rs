@@ -0,0 +1,320 @@
+//! Writes a snapshot of the state that helps with reproducing layout bugs from user reports
+//! (`nog.debug.dump`) and replays such a snapshot without touching any window
+//! (`nog.exe --replay <path>`).
+//!
+//! The snapshot contains the layouts of the workspaces together with their recent changes, the
+//! settings and the most recent log entries. It is anonymized, so that it can be attached to an
+//! issue: window ids become sequential numbers, text in single quotes (which is how the log refers
+//! to window titles and process names) gets removed and the name of the user gets replaced.
+use crate::{
+    config::Config,
+    display::{self, Display},
+    logging, renderer,
+    system::{mock::MockSystem, DisplayId},
+    tile_grid::{history, TileGrid},
+    AppState,
+};
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use serde_json::{json, Map, Value};
+use std::{collections::HashMap, fs, io, path::Path};
+
+/// How many log entries end up in the snapshot
+const LOG_SIZE: usize = 200;
+/// How many previous layouts of each workspace end up in the snapshot
+const HISTORY_SIZE: usize = 20;
+/// How many characters wide a replayed layout is
+const REPLAY_WIDTH: i32 = 48;
+
+/// The settings that end up in the snapshot. Paths are left out, because they contain the name of
+/// the user.
+const SETTINGS: &[&str] = &[
+    "use_border",
+    "work_mode",
+    "multi_monitor",
+    "display_workspaces",
+    "remove_title_bar",
+    "remove_task_bar",
+    "auto_hide_task_bar",
+    "display_app_bar",
+    "outer_gap",
+    "inner_gap",
+    "resize_step",
+    "min_width",
+    "min_height",
+    "ignore_fullscreen_actions",
+    "mouse_integration",
+    "smart_placement",
+    "float_fixed_size",
+    "workspace_back_and_forth",
+    "startup",
+    "close_focus",
+    "bar.height",
+    "bar.font_size",
+    "bar.auto_hide",
+];
+
+lazy_static! {
+    static ref TILE_REGEX: Regex = Regex::new(r"t(\d+)\|(\d+)\|(-?\d+)").unwrap();
+    static ref QUOTED_REGEX: Regex = Regex::new(r"'[^']*'").unwrap();
+    static ref NUMBER_REGEX: Regex = Regex::new(r"-?\b\d+\b").unwrap();
+}
+
+#[derive(Debug, Default)]
+struct Anonymizer {
+    /// The number that replaces each window id, in the order the ids first appeared
+    ids: HashMap<String, usize>,
+    user: Option<String>,
+}
+
+impl Anonymizer {
+    fn new(user: Option<String>) -> Self {
+        Self {
+            ids: HashMap::new(),
+            user: user.filter(|user| !user.is_empty()),
+        }
+    }
+
+    fn id(&mut self, id: &str) -> usize {
+        let next = self.ids.len() + 1;
+        *self.ids.entry(id.to_string()).or_insert(next)
+    }
+
+    fn layout(&mut self, layout: &str) -> String {
+        TILE_REGEX
+            .replace_all(layout, |caps: &Captures| {
+                format!("t{}|{}|{}", &caps[1], &caps[2], self.id(&caps[3]))
+            })
+            .into_owned()
+    }
+
+    /// Removes quoted text and the name of the user from the message and replaces the ids of the
+    /// windows that appeared in a layout.
+    fn message(&self, message: &str) -> String {
+        let message = QUOTED_REGEX.replace_all(message, "'...'");
+        let message =
+            NUMBER_REGEX.replace_all(&message, |caps: &Captures| match self.ids.get(&caps[0]) {
+                Some(id) => id.to_string(),
+                None => caps[0].to_string(),
+            });
+
+        match &self.user {
+            Some(user) => message.replace(user.as_str(), "<user>"),
+            None => message.into_owned(),
+        }
+    }
+}
+
+/// Returns the layouts the workspace had, the oldest first and the current one last
+fn layouts_of(grid: &TileGrid) -> Vec<String> {
+    let changes = history::tail(grid.id, HISTORY_SIZE);
+    let mut layouts = changes
+        .first()
+        .map(|change| vec![change.before.clone()])
+        .unwrap_or_default();
+
+    layouts.extend(changes.into_iter().map(|change| change.after));
+
+    let current = grid.to_string();
+    if layouts.last() != Some(&current) {
+        layouts.push(current);
+    }
+
+    layouts.retain(|layout| !layout.is_empty());
+    layouts
+}
+
+pub fn dump(state: &AppState) -> Value {
+    let mut anonymizer = Anonymizer::new(std::env::var("USERNAME").ok());
+    let mut displays = Vec::new();
+
+    for d in &state.displays {
+        let mut workspaces = Vec::new();
+
+        for grid in &d.grids {
+            let layouts = layouts_of(grid)
+                .iter()
+                .map(|layout| anonymizer.layout(layout))
+                .collect::<Vec<_>>();
+
+            workspaces.push(json!({ "id": grid.id, "layouts": layouts }));
+        }
+
+        displays.push(json!({
+            "left": d.rect.left,
+            "top": d.rect.top,
+            "right": d.rect.right,
+            "bottom": d.rect.bottom,
+            "focused_workspace": d.focused_grid_id,
+            "workspaces": workspaces,
+        }));
+    }
+
+    let settings = SETTINGS
+        .iter()
+        .filter_map(|name| {
+            let value = state.config.get(name).ok()?;
+            Some((name.to_string(), Value::from(value)))
+        })
+        .collect::<Map<_, _>>();
+
+    let log = logging::tail(LOG_SIZE)
+        .into_iter()
+        .map(|entry| {
+            format!(
+                "[{}] {} [{}] {}",
+                entry.time,
+                entry.level,
+                entry.module,
+                anonymizer.message(&entry.message)
+            )
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "version": option_env!("NOG_VERSION").unwrap_or("DEV"),
+        "workspace": state.workspace_id,
+        "settings": settings,
+        "displays": displays,
+        "log": log,
+    })
+}
+
+pub fn write(state: &AppState, path: &Path) -> io::Result<()> {
+    fs::write(path, serde_json::to_string_pretty(&dump(state))?)
+}
+
+fn take_grid(displays: &mut [Display], id: i32) -> Option<TileGrid> {
+    for d in displays.iter_mut() {
+        if let Some(pos) = d.grids.iter().position(|g| g.id == id) {
+            return Some(d.grids.remove(pos));
+        }
+    }
+
+    None
+}
+
+fn render(layout: &str, width: u32, height: u32) -> String {
+    let mut grid = TileGrid::new(0, renderer::NativeRenderer);
+    grid.from_string_keep_tiles(layout);
+
+    grid.get_miniature(width, height)
+        .iter()
+        .map(|row| format!("    {}\n", row))
+        .collect()
+}
+
+/// Loads the displays and workspaces of the snapshot into a mock system and renders every layout
+/// of each workspace, the oldest first.
+pub fn replay(content: &str) -> Result<String, String> {
+    let dump: Value =
+        serde_json::from_str(content).map_err(|e| format!("Failed to parse the dump: {}", e))?;
+    let dumped_displays = dump["displays"].as_array().cloned().unwrap_or_default();
+    let mut config = Config::default();
+
+    if let Some(settings) = dump["settings"].as_object() {
+        for (name, value) in settings {
+            config.set(name, value.as_str().unwrap_or_default())?;
+        }
+    }
+
+    // the snapshot only contains the displays that nog used
+    config.multi_monitor = true;
+
+    let mut system = MockSystem::default();
+    for d in &dumped_displays {
+        let edge = |name: &str| d[name].as_i64().unwrap_or_default() as i32;
+
+        system = system.with_display(
+            edge("left"),
+            edge("top"),
+            edge("right") - edge("left"),
+            edge("bottom") - edge("top"),
+        );
+    }
+
+    let mut displays = display::init_with(&system, &config);
+    let mut output = String::new();
+
+    for (idx, d) in dumped_displays.iter().enumerate() {
+        let display_id = DisplayId(idx as i32 + 1);
+        let workspaces = d["workspaces"].as_array().cloned().unwrap_or_default();
+
+        for workspace in &workspaces {
+            let id = workspace["id"].as_i64().unwrap_or_default() as i32;
+            let layout = workspace["layouts"]
+                .as_array()
+                .and_then(|layouts| layouts.last())
+                .and_then(|layout| layout.as_str())
+                .unwrap_or_default();
+            let mut grid = take_grid(&mut displays, id)
+                .unwrap_or_else(|| TileGrid::new(id, renderer::NativeRenderer));
+
+            grid.from_string_keep_tiles(layout);
+
+            if let Some(display) = displays.iter_mut().find(|x| x.id == display_id) {
+                display.grids.push(grid);
+            }
+        }
+
+        let display = match displays.iter().find(|x| x.id == display_id) {
+            Some(display) => display,
+            None => continue,
+        };
+        // characters are about twice as high as they are wide
+        let height = (REPLAY_WIDTH * display.height() / display.width().max(1) / 2).max(1);
+
+        output += &format!(
+            "Display {} ({}x{})\n",
+            idx + 1,
+            display.width(),
+            display.height()
+        );
+
+        for workspace in &workspaces {
+            let id = workspace["id"].as_i64().unwrap_or_default() as i32;
+            let layouts = workspace["layouts"].as_array().cloned().unwrap_or_default();
+
+            if layouts.is_empty() {
+                continue;
+            }
+
+            let focused = d["focused_workspace"].as_i64() == Some(id as i64);
+            output += &format!(
+                "  Workspace {}{}\n",
+                id,
+                if focused { " (focused)" } else { "" }
+            );
+
+            for (i, layout) in layouts.iter().enumerate() {
+                let layout = layout.as_str().unwrap_or_default();
+
+                output += &format!("   {}: {}\n", i + 1, layout);
+                output += &render(layout, REPLAY_WIDTH as u32, height as u32);
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anonymize_ids_and_messages() {
+        let mut anonymizer = Anonymizer::new(Some("alice".into()));
+
+        assert_eq!(
+            anonymizer.layout("c0|120[t0|60|98765,t1|60|-4321]"),
+            "c0|120[t0|60|1,t1|60|2]"
+        );
+        assert_eq!(anonymizer.layout("t0|120|-4321"), "t0|120|2");
+        assert_eq!(
+            anonymizer.message("Managing window 'Secret.docx - Word' | 98765 in C:\\Users\\alice"),
+            "Managing window '...' | 1 in C:\\Users\\<user>"
+        );
+        assert_eq!(anonymizer.message("Resized by 20"), "Resized by 20");
+    }
+}
@@ -1,26 +1,96 @@
 use crate::{
     bar::Bar,
-    config::Config,
+    config::{reserved_space::ReservedSpace, Config},
     renderer,
     system::DisplayId,
     system::SystemResult,
-    system::{api, Rectangle},
+    system::WindowId,
+    system::{api, Rectangle, System},
     task_bar,
-    tile_grid::store::Store,
     tile_grid::TileGrid,
+    tile_grid::{history, store::Store},
+};
+use std::{
+    cmp::Ordering,
+    collections::HashSet,
+    sync::atomic::{self, AtomicBool},
 };
-use std::cmp::Ordering;
 use task_bar::{Taskbar, TaskbarPosition};
 
+/// How many workspaces each display owns if `display_workspaces` is enabled
+pub const WORKSPACES_PER_DISPLAY: i32 = 10;
+
+/// Returns the number the workspace has on its display if `display_workspaces` is enabled. The
+/// workspaces of the n-th display have the ids `(n - 1) * 10 + 1` to `n * 10`.
+pub fn workspace_number(id: i32) -> i32 {
+    (id - 1) % WORKSPACES_PER_DISPLAY + 1
+}
+
+/// Walks from the current workspace through the ids in the given direction, wrapping around at
+/// either end, and returns the first workspace that gets accepted.
+pub fn relative_workspace(
+    ids: &[i32],
+    current: i32,
+    forward: bool,
+    accept: impl Fn(i32) -> bool,
+) -> Option<i32> {
+    let pos = ids.iter().position(|id| *id == current)?;
+    let len = ids.len();
+
+    (1..len)
+        .map(|step| {
+            if forward {
+                ids[(pos + step) % len]
+            } else {
+                ids[(pos + len - step) % len]
+            }
+        })
+        .find(|id| accept(*id))
+}
+
+/// Remembers that a deferred refresh still has to happen. This can be set through a shared
+/// reference, because `refresh_grid` only borrows the display.
+#[derive(Default, Debug)]
+pub struct RefreshPending(AtomicBool);
+
+impl RefreshPending {
+    pub fn set(&self, value: bool) {
+        self.0.store(value, atomic::Ordering::SeqCst);
+    }
+    /// Sets the flag and returns the previous value
+    pub fn replace(&self, value: bool) -> bool {
+        self.0.swap(value, atomic::Ordering::SeqCst)
+    }
+}
+
+impl Clone for RefreshPending {
+    fn clone(&self) -> Self {
+        Self(AtomicBool::new(self.0.load(atomic::Ordering::SeqCst)))
+    }
+}
+
 #[derive(Default, Debug, Clone)]
 pub struct Display {
     pub id: DisplayId,
     pub grids: Vec<TileGrid>,
     pub focused_grid_id: Option<i32>,
+    /// The workspace that was focused on this display before the current one
+    pub previous_grid_id: Option<i32>,
     pub dpi: u32,
     pub rect: Rectangle,
     pub taskbar: Option<Taskbar>,
     pub appbar: Option<Bar>,
+    /// The space at the edges that is reserved for other bars and docks
+    pub reserved: ReservedSpace,
+    /// While this is set, refreshing the grid only remembers that it has to be refreshed
+    pub defer_refresh: bool,
+    pub refresh_pending: RefreshPending,
+    /// Set while a game covers the display. Like `defer_refresh` the grid only gets redrawn once
+    /// this is unset again.
+    pub tiling_suspended: bool,
+    /// The windows that the last workspace switch left visible. Switching workspaces only shows
+    /// and hides the windows whose visibility changes (see `diff_visibility`).
+    pub visible_windows: HashSet<WindowId>,
 }
 
 impl Display {
@@ -36,6 +106,19 @@ impl Display {
     pub fn get_rect(&self) -> Rectangle {
         api::get_display_rect(self.id)
     }
+    /// Returns how much space the bar takes up, which is nothing if the bar is hidden.
+    pub fn appbar_height(&self, config: &Config) -> i32 {
+        let hidden = self.appbar.as_ref().map(|b| b.hidden).unwrap_or(false);
+
+        if config.display_app_bar && !hidden {
+            self.appbar
+                .as_ref()
+                .map(|b| b.height)
+                .unwrap_or(config.bar.height)
+        } else {
+            0
+        }
+    }
     pub fn working_area_height(&self, config: &Config) -> i32 {
         let tb_height = self
             .taskbar
@@ -51,11 +134,9 @@ impl Display {
 
         self.height()
             - if config.remove_task_bar { 0 } else { tb_height }
-            - if config.display_app_bar {
-                config.bar.height
-            } else {
-                0
-            }
+            - self.appbar_height(config)
+            - self.reserved.top
+            - self.reserved.bottom
     }
     pub fn working_area_width(&self, config: &Config) -> i32 {
         let tb_width = self
@@ -70,7 +151,10 @@ impl Display {
             })
             .unwrap_or(0);
 
-        self.width() - if config.remove_task_bar { 0 } else { tb_width }
+        self.width()
+            - if config.remove_task_bar { 0 } else { tb_width }
+            - self.reserved.left
+            - self.reserved.right
     }
     pub fn working_area_top(&self, config: &Config) -> i32 {
         let offset = self
@@ -83,13 +167,7 @@ impl Display {
             })
             .unwrap_or(0);
 
-        self.rect.top
-            + if config.display_app_bar {
-                config.bar.height
-            } else {
-                0
-            }
-            + offset
+        self.rect.top + self.reserved.top + self.appbar_height(config) + offset
     }
     pub fn working_area_left(&self) -> i32 {
         let offset = self
@@ -102,7 +180,7 @@ impl Display {
             })
             .unwrap_or(0);
 
-        self.rect.left + offset
+        self.rect.left + self.reserved.left + offset
     }
     pub fn get_grid_by_id(&self, id: i32) -> Option<&TileGrid> {
         self.grids.iter().find(|g| g.id == id)
@@ -125,10 +203,17 @@ impl Display {
             .and_then(move |id| self.get_grid_by_id_mut(id))
     }
     pub fn refresh_grid(&self, config: &Config) -> SystemResult {
+        if self.defer_refresh || self.tiling_suspended {
+            self.refresh_pending.set(true);
+            return Ok(());
+        }
+
         if let Some(g) = self.get_focused_grid() {
             g.draw_grid(self, config)?;
 
-            Store::save(g.id, g.to_string());
+            let layout = g.to_string();
+            history::record(g.id, &layout);
+            Store::save(g.id, layout);
         }
 
         Ok(())
@@ -144,27 +229,51 @@ impl Display {
     }
 
     /// Returns true if the workspace was found and false if it wasn't
-    pub fn focus_workspace(&mut self, config: &Config, id: i32) -> SystemResult<bool> {
+    pub fn focus_workspace(
+        &mut self,
+        system: &dyn System,
+        config: &Config,
+        id: i32,
+    ) -> SystemResult<bool> {
         if let Some(grid) = self.get_grid_by_id_mut(id) {
             if !grid.focused_id.is_some() {
                 grid.focus_last_tile(); // ensures a tile is focused on the current grid
             }
         }
 
+        let next = match self.get_grid_by_id(id) {
+            Some(grid) => grid.get_window_ids(),
+            None => return Ok(false),
+        };
+        let previous = match self.get_focused_grid() {
+            Some(grid) if grid.id != id => grid.get_window_ids(),
+            _ => Vec::new(),
+        };
+
+        // windows that moved to another display are visible there
+        let grids = &self.grids;
+        self.visible_windows
+            .retain(|window_id| grids.iter().any(|g| g.contains(*window_id)));
+
+        let diff = diff_visibility(&self.visible_windows, &previous, &next);
+
         if let Some(grid) = self.get_grid_by_id(id) {
             grid.draw_grid(self, config)?;
-            grid.show()?;
-        } else {
-            return Ok(false);
+            grid.show(&diff.still_visible)?;
+        }
+
+        // the windows get hidden after the next ones are shown, so that the desktop doesn't
+        // flash in between
+        for window_id in diff.to_hide {
+            system.hide_window(window_id)?;
         }
 
         if self.focused_grid_id != Some(id) {
-            if let Some(grid) = self.get_focused_grid() {
-                grid.hide();
-            }
+            self.previous_grid_id = self.focused_grid_id;
         }
 
         self.focused_grid_id = Some(id);
+        self.visible_windows = next.into_iter().collect();
 
         Ok(true)
     }
@@ -179,18 +288,69 @@ impl Display {
     }
 }
 
-pub fn init(config: &Config) -> Vec<Display> {
-    let mut displays = api::get_displays();
-    let taskbars = api::get_taskbars();
+#[derive(Debug, Default, PartialEq)]
+pub struct VisibilityDiff {
+    /// The windows that are visible, but don't belong to the next workspace
+    pub to_hide: Vec<WindowId>,
+    /// The windows of the next workspace that are visible already
+    pub still_visible: HashSet<WindowId>,
+}
+
+/// Compares the visible windows of the display with the windows of the workspace it switches to.
+/// `previous` are the windows of the workspace that was focused before, which also have to be
+/// hidden if they got added after the last switch.
+pub fn diff_visibility(
+    visible: &HashSet<WindowId>,
+    previous: &[WindowId],
+    next: &[WindowId],
+) -> VisibilityDiff {
+    let mut to_hide = previous
+        .iter()
+        .chain(visible.iter().filter(|id| !previous.contains(id)))
+        .filter(|id| !next.contains(id))
+        .copied()
+        .collect::<Vec<_>>();
 
-    for d in displays.iter_mut() {
-        for tb in &taskbars {
-            let display = tb.window.get_display().unwrap();
-            if display.id == d.id {
-                d.taskbar = Some(tb.clone());
-            }
+    to_hide.sort_by_key(|id| -> i32 { (*id).into() });
+
+    VisibilityDiff {
+        to_hide,
+        still_visible: next
+            .iter()
+            .filter(|id| visible.contains(id))
+            .copied()
+            .collect(),
+    }
+}
+
+/// Sorts the displays from right to left, which is the order the `monitor` of a workspace setting
+/// refers to
+pub fn sort(displays: &mut [Display]) {
+    displays.sort_by(|x, y| {
+        let ordering = y.rect.left.cmp(&x.rect.left);
+
+        if ordering == Ordering::Equal {
+            return y.rect.top.cmp(&x.rect.top);
         }
+
+        ordering
+    });
+}
+
+/// Applies the reserved space of the config to the displays, which have to be sorted.
+pub fn reserve_space(displays: &mut [Display], config: &Config) {
+    let count = displays.len();
+
+    // the index of a display counts from the left, but the displays are sorted from right to left
+    for (pos, d) in displays.iter_mut().enumerate() {
+        let idx = (count - pos) as i32;
+        d.reserved = config.reserved_space.get(&idx).copied().unwrap_or_default();
     }
+}
+
+/// Creates the workspaces of the displays that the system reports
+pub fn init_with(system: &dyn System, config: &Config) -> Vec<Display> {
+    let mut displays = system.get_displays();
 
     if !config.multi_monitor {
         displays = displays
@@ -200,15 +360,24 @@ pub fn init(config: &Config) -> Vec<Display> {
             .collect();
     }
 
-    displays.sort_by(|x, y| {
-        let ordering = y.rect.left.cmp(&x.rect.left);
+    sort(&mut displays);
+    reserve_space(&mut displays, config);
 
-        if ordering == Ordering::Equal {
-            return y.rect.top.cmp(&x.rect.top);
+    if config.display_workspaces {
+        let count = displays.len();
+
+        // the displays are sorted from right to left, but the numbering starts on the left
+        for (pos, d) in displays.iter_mut().enumerate() {
+            let offset = (count - pos - 1) as i32 * WORKSPACES_PER_DISPLAY;
+
+            for i in 1..=WORKSPACES_PER_DISPLAY {
+                d.grids
+                    .push(TileGrid::new(offset + i, renderer::NativeRenderer));
+            }
         }
 
-        ordering
-    });
+        return displays;
+    }
 
     for i in 1..11 {
         let monitor = config
@@ -236,3 +405,121 @@ pub fn init(config: &Config) -> Vec<Display> {
 
     // task_bar::update_task_bars();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::workspace_setting::WorkspaceSetting, system::mock::MockSystem};
+
+    fn grid_ids(display: &Display) -> Vec<i32> {
+        display.grids.iter().map(|g| g.id).collect()
+    }
+
+    fn ids(ids: &[i32]) -> Vec<WindowId> {
+        ids.iter().map(|id| WindowId::from(*id)).collect()
+    }
+
+    #[test]
+    fn switching_workspaces_only_toggles_the_changed_windows() {
+        // the workspace with 1 and 2 is visible and switches to the one with 3 and 4
+        let visible = ids(&[1, 2]).into_iter().collect();
+        let diff = diff_visibility(&visible, &ids(&[1, 2]), &ids(&[3, 4]));
+
+        assert_eq!(diff.to_hide, ids(&[1, 2]));
+        assert!(diff.still_visible.is_empty());
+
+        // focusing the visible workspace again changes nothing
+        let diff = diff_visibility(&visible, &[], &ids(&[1, 2]));
+
+        assert!(diff.to_hide.is_empty());
+        assert_eq!(diff.still_visible, visible);
+    }
+
+    #[test]
+    fn switching_workspaces_hides_windows_that_got_added() {
+        // 5 got added to the visible workspace and 2 got moved to the next one
+        let visible = ids(&[1, 2]).into_iter().collect();
+        let diff = diff_visibility(&visible, &ids(&[1, 5]), &ids(&[2, 3]));
+
+        assert_eq!(diff.to_hide, ids(&[1, 5]));
+        assert_eq!(diff.still_visible, ids(&[2]).into_iter().collect());
+    }
+
+    fn two_displays() -> MockSystem {
+        MockSystem::default()
+            .with_display(0, 0, 1920, 1080)
+            .with_display(1920, 0, 2560, 1440)
+    }
+
+    #[test]
+    fn every_display_owns_its_workspaces() {
+        let mut config = Config::default();
+        config.multi_monitor = true;
+        config.display_workspaces = true;
+
+        let displays = init_with(&two_displays(), &config);
+
+        // sorted from right to left
+        assert_eq!(displays[0].id, DisplayId(2));
+        assert_eq!(grid_ids(&displays[0]), (11..=20).collect::<Vec<_>>());
+        assert_eq!(grid_ids(&displays[1]), (1..=10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn workspaces_start_on_their_monitor() {
+        let mut config = Config::default();
+        config.multi_monitor = true;
+        config.workspace_settings.push(WorkspaceSetting {
+            id: 3,
+            monitor: 1,
+            ..WorkspaceSetting::default()
+        });
+
+        let displays = init_with(&two_displays(), &config);
+
+        assert_eq!(grid_ids(&displays[0]), vec![3]);
+        assert_eq!(grid_ids(&displays[1]), vec![1, 2, 4, 5, 6, 7, 8, 9, 10]);
+
+        config.multi_monitor = false;
+
+        let displays = init_with(&two_displays(), &config);
+
+        assert_eq!(displays.len(), 1);
+        assert_eq!(grid_ids(&displays[0]), (1..=10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn reserved_space_shrinks_the_working_area() {
+        let mut config = Config::default();
+        config.multi_monitor = true;
+        config.display_app_bar = false;
+        config.reserved_space.insert(
+            1,
+            ReservedSpace {
+                top: 32,
+                left: 50,
+                ..ReservedSpace::default()
+            },
+        );
+
+        let displays = init_with(&two_displays(), &config);
+        let left = &displays[1];
+
+        assert_eq!(left.working_area_top(&config), 32);
+        assert_eq!(left.working_area_left(), 50);
+        assert_eq!(left.working_area_height(&config), 1080 - 32);
+        assert_eq!(left.working_area_width(&config), 1920 - 50);
+        assert_eq!(displays[0].reserved, ReservedSpace::default());
+    }
+
+    #[test]
+    fn relative_workspaces_wrap_around() {
+        let ids = [1, 2, 3, 4];
+
+        assert_eq!(relative_workspace(&ids, 2, true, |_| true), Some(3));
+        assert_eq!(relative_workspace(&ids, 1, false, |_| true), Some(4));
+        assert_eq!(relative_workspace(&ids, 4, true, |id| id != 1), Some(2));
+        assert_eq!(relative_workspace(&ids, 3, true, |id| id == 3), None);
+        assert_eq!(relative_workspace(&ids, 7, true, |_| true), None);
+    }
+}
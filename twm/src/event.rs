@@ -1,17 +1,36 @@
 use crate::{
-    bar::item_section::ItemSection, keybindings::keybinding::Keybinding, popup::Popup,
-    system::DisplayId, win_event_handler::win_event::WinEvent,
+    actions::{pipeline::Pipeline, Invocation},
+    bar::item_section::ItemSection,
+    config::Config,
+    keybindings::keybinding::Keybinding,
+    popup::Popup,
+    state_handle::Update,
+    system::DisplayId,
+    win_event_handler::win_event::WinEvent,
 };
 use crossbeam_channel::unbounded;
 use crossbeam_channel::Receiver;
 use crossbeam_channel::Sender;
-use interpreter::RuntimeError;
+use interpreter::{Dynamic, Function, RuntimeError};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy)]
+pub enum MouseEvent {
+    LeftDown(i32, i32),
+    LeftUp(i32, i32),
+}
+
+#[derive(Debug)]
 pub enum Event {
     Keybinding(Keybinding),
     WinEvent(WinEvent),
+    /// Window events that happened in quick succession, which get handled with a single redraw
+    WinEvents(Vec<WinEvent>),
+    Mouse(MouseEvent),
     NewPopup(Popup),
+    /// Shows the text in an osd
+    ShowOsd(String),
+    /// Shows the text in an osd until it gets unpinned
+    PinOsd(String),
     ConfigError(RuntimeError),
     CallCallback {
         idx: usize,
@@ -20,11 +39,41 @@ pub enum Event {
         /// all of the mode specific bindings
         is_mode_callback: bool,
     },
+    /// Calls the callback with the arguments
+    CallCallbackWithArgs {
+        idx: usize,
+        args: Vec<Dynamic>,
+    },
+    /// Calls a function that isn't stored as a callback with the arguments, e.g. the callback of
+    /// a request that finished on a worker thread
+    CallFunction {
+        function: Function,
+        args: Vec<Dynamic>,
+    },
     ToggleAppbar(DisplayId),
-    UpdateBarSections(DisplayId, ItemSection, ItemSection, ItemSection),
+    /// Hides or reveals the bar of the display and resizes its workspace accordingly
+    SetAppbarHidden(DisplayId, bool),
+    /// Stores the sections of the bar after drawing it together with its background color
+    UpdateBarSections(DisplayId, ItemSection, ItemSection, ItemSection, i32),
+    /// Runs the named action
+    RunAction(Invocation),
+    /// Applies the actions of the pipeline to every item of its query
+    RunPipeline(Pipeline),
     ChangeWorkspace(i32, bool),
+    /// Moves the workspace to the display
+    MoveWorkspaceToDisplay(i32, DisplayId),
+    ToggleWorkMode,
+    /// A change of the state that another thread handed to the event loop
+    Update(Update),
     RedrawAppBar,
+    /// A single setting changed without reloading the config. This holds the name of the setting
+    /// and the config from before the change, so that only what depends on the setting gets
+    /// updated.
+    ConfigChanged(String, Box<Config>),
     ReloadConfig,
+    /// Reloads the config, because one of its files changed. Unlike `ReloadConfig` this reports
+    /// errors in an osd, which doesn't get in the way while the user is still editing the config.
+    AutoReloadConfig,
     Exit,
 }
 
@@ -1 +1,2 @@
+pub mod mouse;
 pub mod winevent;
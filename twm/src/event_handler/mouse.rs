@@ -0,0 +1,134 @@
+use crate::{
+    event::MouseEvent,
+    system::{NativeWindow, Rectangle, SystemResult},
+    tile_grid::TileGrid,
+    AppState,
+};
+use log::debug;
+use std::cmp;
+
+#[derive(Debug, Clone)]
+pub enum Drag {
+    /// A managed window is being dragged. `rect` is the position of the window when the drag
+    /// started, which is used to tell a drag apart from a normal click.
+    Tile {
+        grid_id: i32,
+        window: NativeWindow,
+        rect: Rectangle,
+    },
+    /// The gap between two tiles is being dragged. `x` and `y` are where the drag started.
+    Boundary { grid_id: i32, x: i32, y: i32 },
+}
+
+pub fn handle(state: &mut AppState, ev: MouseEvent) -> SystemResult {
+    match ev {
+        MouseEvent::LeftDown(x, y) => {
+            state.drag = start_drag(state, x, y);
+        }
+        MouseEvent::LeftUp(x, y) => {
+            if let Some(drag) = state.drag.take() {
+                finish_drag(state, drag, x, y)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn start_drag(state: &AppState, x: i32, y: i32) -> Option<Drag> {
+    let display = state.displays.iter().find(|d| d.rect.contains(x, y))?;
+    let grid = display.get_focused_grid()?;
+
+    if grid.is_fullscreened() {
+        return None;
+    }
+
+    let area = TileGrid::get_area(display, &state.config);
+    let (width, height) = (area.width() as u32, area.height() as u32);
+    let tile = grid.get_tile_at(x - area.left, y - area.top, width, height);
+    let window_rect = tile.as_ref().and_then(|t| t.window.get_rect().ok());
+
+    match (tile, window_rect) {
+        (Some(tile), Some(rect)) if rect.contains(x, y) => Some(Drag::Tile {
+            grid_id: grid.id,
+            window: tile.window,
+            rect,
+        }),
+        // the cursor is inside of the gap between tiles
+        _ => grid
+            .get_boundary_at(
+                x - area.left,
+                y - area.top,
+                width,
+                height,
+                cmp::max(state.config.inner_gap, 1),
+            )
+            .map(|_| Drag::Boundary {
+                grid_id: grid.id,
+                x,
+                y,
+            }),
+    }
+}
+
+fn finish_drag(state: &mut AppState, drag: Drag, x: i32, y: i32) -> SystemResult {
+    let config = state.config.clone();
+    let grid_id = match &drag {
+        Drag::Tile { grid_id, .. } | Drag::Boundary { grid_id, .. } => *grid_id,
+    };
+
+    let display = match state.find_grid_display_mut(grid_id) {
+        Some(display) => display,
+        None => return Ok(()),
+    };
+
+    let area = TileGrid::get_area(display, &config);
+    let (width, height) = (area.width() as u32, area.height() as u32);
+
+    let grid = match display.get_grid_by_id_mut(grid_id) {
+        Some(grid) => grid,
+        None => return Ok(()),
+    };
+
+    match drag {
+        Drag::Tile { window, rect, .. } => {
+            let moved = window
+                .get_rect()
+                .map(|r| r.left != rect.left || r.top != rect.top)
+                .unwrap_or(false);
+
+            if !moved {
+                return Ok(());
+            }
+
+            let source = grid.get_node_id(window.id);
+            let target = grid.get_tile_at(x - area.left, y - area.top, width, height);
+
+            if let (Some(source), Some(target)) = (source, target) {
+                debug!("Swapping tiles {} and {} by mouse", source, target.debug_id);
+                grid.swap_tiles(source, target.debug_id);
+            }
+        }
+        Drag::Boundary {
+            x: start_x,
+            y: start_y,
+            ..
+        } => {
+            let boundary = grid.get_boundary_at(
+                start_x - area.left,
+                start_y - area.top,
+                width,
+                height,
+                cmp::max(config.inner_gap, 1),
+            );
+
+            if let Some((first, second)) = boundary {
+                debug!("Resizing boundary between {} and {} by mouse", first, second);
+                grid.drag_boundary(first, second, x - start_x, y - start_y, width, height);
+            }
+        }
+    }
+
+    // even if nothing changed, the grid has to be redrawn to move a dragged window back into its tile
+    display.refresh_grid(&config)
+}
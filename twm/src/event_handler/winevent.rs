@@ -3,13 +3,21 @@ use crate::{
     win_event_handler::{win_event::WinEvent, win_event_type::WinEventType},
     AppState,
 };
-use log::debug;
+use log::{debug, error};
 
+mod batch;
 mod destroy;
 mod focus_change;
 mod show;
 
 pub fn handle(state: &mut AppState, ev: WinEvent) -> SystemResult {
+    // destroyed windows still have to be removed from their grid
+    if let Some(game_mode) = state.game_mode.as_ref() {
+        if ev.typ != WinEventType::Destroy && game_mode.ignores(&ev.window) {
+            return Ok(());
+        }
+    }
+
     let grids = state.get_grids_mut();
     let mut title: Option<String> = None;
     let mut grid_id: Option<i32> = None;
@@ -50,3 +58,33 @@ pub fn handle(state: &mut AppState, ev: WinEvent) -> SystemResult {
 
     Ok(())
 }
+
+/// Handles the events one after another, but only refreshes the grid of each display once at the
+/// end, so that a burst of events doesn't cause a redraw per event.
+pub fn handle_batch(state: &mut AppState, events: Vec<WinEvent>) -> SystemResult {
+    let events = batch::coalesce(events);
+
+    debug!("Handling a batch of {} window events", events.len());
+
+    for display in state.displays.iter_mut() {
+        display.defer_refresh = true;
+    }
+
+    for ev in events {
+        if let Err(e) = handle(state, ev) {
+            error!("Failed to handle window event {:?}", e);
+        }
+    }
+
+    for display in state.displays.iter_mut() {
+        display.defer_refresh = false;
+    }
+
+    for display in state.displays.iter() {
+        if display.refresh_pending.replace(false) {
+            display.refresh_grid(&state.config)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use crate::win_event_handler::{win_event::WinEvent, win_event_type::WinEventType};
+
+/// Removes the events of the batch that wouldn't change the outcome of handling it:
+///
+/// * hide events, because they don't get handled
+/// * repeated show and destroy events of the same window
+/// * show events of windows that got destroyed later in the batch
+/// * every focus change except the last one
+///
+/// The remaining events keep their order.
+pub fn coalesce(events: Vec<WinEvent>) -> Vec<WinEvent> {
+    let last_focus_change = events
+        .iter()
+        .rposition(|ev| ev.typ == WinEventType::FocusChange);
+    let mut coalesced: Vec<WinEvent> = Vec::new();
+
+    for (i, ev) in events.iter().enumerate() {
+        let keep = match ev.typ {
+            WinEventType::Hide => false,
+            WinEventType::FocusChange => Some(i) == last_focus_change,
+            WinEventType::Show(_) => !events[i + 1..]
+                .iter()
+                .any(|later| later.typ == WinEventType::Destroy && later.window.id == ev.window.id),
+            WinEventType::Destroy => true,
+        };
+
+        let repeated = coalesced
+            .iter()
+            .any(|earlier| earlier.typ == ev.typ && earlier.window.id == ev.window.id);
+
+        if keep && !repeated {
+            coalesced.push(ev.clone());
+        }
+    }
+
+    coalesced
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::{NativeWindow, WindowId};
+
+    fn event(typ: WinEventType, id: i32) -> WinEvent {
+        WinEvent {
+            typ,
+            window: NativeWindow::from(WindowId::from(id)),
+        }
+    }
+
+    #[test]
+    fn coalesce_bursts() {
+        let events = vec![
+            event(WinEventType::Show(false), 1),
+            event(WinEventType::FocusChange, 1),
+            event(WinEventType::Show(false), 2),
+            event(WinEventType::Show(false), 1),
+            event(WinEventType::Hide, 2),
+            event(WinEventType::Destroy, 2),
+            event(WinEventType::Destroy, 3),
+            event(WinEventType::Destroy, 3),
+            event(WinEventType::FocusChange, 4),
+        ];
+
+        let coalesced: Vec<(WinEventType, i32)> = coalesce(events)
+            .into_iter()
+            .map(|ev| (ev.typ, ev.window.id.into()))
+            .collect();
+
+        assert_eq!(
+            coalesced,
+            vec![
+                (WinEventType::Show(false), 1),
+                (WinEventType::Destroy, 2),
+                (WinEventType::Destroy, 3),
+                (WinEventType::FocusChange, 4),
+            ]
+        );
+    }
+}
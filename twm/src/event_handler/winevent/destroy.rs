@@ -5,10 +5,22 @@ pub fn handle(
     window: NativeWindow,
     _grid_id: Option<i32>, // TODO: maybe remove this? IDK
 ) -> SystemResult {
-    if let Some(_) = state
+    let policy = state.config.close_focus;
+
+    if let Some((grid_id, focused)) = state
         .find_grid_containing_window(window.id)
-        .map(|g| g.remove_by_window_id(window.id))
+        .map(|g| (g.id, g.remove_window(window.id, policy)))
     {
+        // a hidden workspace only remembers which tile is focused
+        if focused.is_some() && state.is_workspace_visible(grid_id) {
+            if let Some(window) = state
+                .get_grid_by_id(grid_id)
+                .and_then(|g| g.get_focused_window())
+            {
+                window.focus()?;
+            }
+        }
+
         state.get_current_display().refresh_grid(&state.config)?;
     }
     Ok(())
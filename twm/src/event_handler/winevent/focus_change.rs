@@ -6,5 +6,5 @@ pub fn handle(state: &mut AppState, window: NativeWindow) -> SystemResult {
         state.workspace_id = g.id;
     }
 
-    Ok(())
+    state.update_opacity()
 }
@@ -1,19 +1,22 @@
-use crate::{system::NativeWindow, system::SystemResult, AppState};
+use crate::{
+    borderless, hidden, pinned, rule_check::float_reason, system::NativeWindow,
+    system::SystemResult, templates, tile_grid::placements, AppState,
+};
 use log::{debug, error};
 
 pub fn handle(state: &mut AppState, mut window: NativeWindow, force: bool) -> SystemResult {
-    let min_width = state.config.min_width;
-    let min_height = state.config.min_height;
+    // the application showed the window on its own, e.g. using its tray icon
+    hidden::forget(state, window.id);
+
+    if borderless::is_borderless(state, window.id) || pinned::is_pinned(state, window.id) {
+        return Ok(());
+    }
 
     let config = state.config.clone();
     let rect = fail!(window
         .get_rect()
         .map_err(|_| "Failed to get rectangle of new window"));
 
-    if !force && (rect.right - rect.left < min_width || rect.bottom - rect.top < min_height) {
-        return Ok(());
-    }
-
     let grid_allows_managing = {
         let display = state.get_current_display();
         if let Some(grid) = display.get_focused_grid() {
@@ -31,25 +34,100 @@ pub fn handle(state: &mut AppState, mut window: NativeWindow, force: bool) -> Sy
 
     window.set_matching_rule(rules);
 
+    if !force {
+        if let Some(reason) = float_reason(
+            &config,
+            &window.rule.clone().unwrap_or_default(),
+            rect.right - rect.left,
+            rect.bottom - rect.top,
+            window.is_resizable(),
+            window.is_dialog(),
+        ) {
+            debug!(
+                "Not managing window '{}' | {}, because {}",
+                window.title, window.id, reason
+            );
+            return Ok(());
+        }
+    }
+
     let parent = window.get_parent_window();
     let rule = window.rule.clone().unwrap_or_default();
     let should_manage =
         force || (rule.manage && parent.is_err() && window.should_manage() && grid_allows_managing);
 
+    if !should_manage {
+        debug!(
+            "Not managing window '{}' | {} (rule allows it: {}, has parent: {}, has caption: {}, grid allows it: {})",
+            window.title,
+            window.id,
+            rule.manage,
+            parent.is_ok(),
+            window.should_manage(),
+            grid_allows_managing
+        );
+    }
+
     if should_manage {
-        debug!("Managing window");
-        if rule.workspace_id != -1 {
+        debug!("Managing window '{}' | {}", window.title, window.id);
+        let process_path = window.get_process_path();
+
+        let template_workspace = templates::claim(state, &window);
+
+        if let Some(id) = template_workspace {
+            state.change_workspace(id, false);
+        } else if rule.workspace_id != -1 {
             state.change_workspace(rule.workspace_id, false);
+        } else if config.smart_placement {
+            if let Some(id) = placements::lookup(&process_path) {
+                debug!("Placing '{}' on workspace {}", window.title, id);
+                state.change_workspace(id, false);
+            }
         }
 
         window.init(config.remove_title_bar, config.use_border)?;
 
+        if let Some(id) = template_workspace {
+            templates::place(state, id, window)?;
+            return state.get_current_display().refresh_grid(&config);
+        }
+
         let display = state.get_current_display_mut();
         if let Some(grid) = display.get_focused_grid_mut() {
             grid.push(window);
+
+            if config.smart_placement {
+                placements::remember(&process_path, grid.id);
+            }
         }
         display.refresh_grid(&config)?;
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{rule::Rule, Config};
+
+    #[test]
+    fn float_small_and_fixed_size_windows() {
+        let mut config = Config::default();
+        let mut rule = Rule::default();
+
+        assert_eq!(float_reason(&config, &rule, 800, 600, false, false), None);
+        assert!(float_reason(&config, &rule, 150, 600, true, false).is_some());
+
+        config.float_fixed_size = true;
+        assert!(float_reason(&config, &rule, 800, 600, false, false).is_some());
+        assert!(float_reason(&config, &rule, 800, 600, true, true).is_some());
+        assert_eq!(float_reason(&config, &rule, 800, 600, true, false), None);
+
+        rule.float = Some(false);
+        assert_eq!(float_reason(&config, &rule, 150, 100, false, true), None);
+
+        rule.float = Some(true);
+        assert!(float_reason(&config, &rule, 800, 600, true, false).is_some());
+    }
+}
@@ -0,0 +1,352 @@
+//! Streams changes of the state to external programs, so that a status bar like polybar can mirror
+//! the workspaces of nog without polling it.
+//!
+//! `nog.exe --subscribe` connects to the running instance and prints every change as a line of
+//! JSON until the instance exits. The events are found by comparing the snapshots that the event
+//! loop publishes, which means that a subscriber never slows down the event loop.
+//!
+//! Every subscriber buffers a limited amount of events. A subscriber that doesn't keep up with
+//! reading them gets disconnected, instead of letting the events pile up in memory.
+use crate::{
+    snapshot::{self, StateSnapshot},
+    system::{DisplayId, WindowId},
+};
+use crossbeam_channel::{bounded, Sender};
+use lazy_static::lazy_static;
+use log::debug;
+use parking_lot::Mutex;
+use serde_json::{json, Value};
+use std::{io::Write, net::TcpStream, thread};
+
+/// How many events a subscriber can fall behind before it gets disconnected
+const MAX_PENDING_EVENTS: usize = 256;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatusEvent {
+    WorkspaceFocused {
+        workspace: i32,
+        display: DisplayId,
+    },
+    WindowOpened {
+        id: WindowId,
+        title: String,
+        workspace: i32,
+    },
+    WindowClosed {
+        id: WindowId,
+        title: String,
+        workspace: i32,
+    },
+    /// A window changed its workspace without getting closed
+    WindowMoved {
+        id: WindowId,
+        title: String,
+        from: i32,
+        to: i32,
+    },
+    ModeChanged {
+        mode: Option<String>,
+    },
+}
+
+impl StatusEvent {
+    pub fn to_json(&self) -> Value {
+        match self {
+            StatusEvent::WorkspaceFocused { workspace, display } => json!({
+                "event": "workspace_focused",
+                "workspace": workspace,
+                "display": display.0,
+            }),
+            StatusEvent::WindowOpened {
+                id,
+                title,
+                workspace,
+            } => json!({
+                "event": "window_opened",
+                "id": Into::<i32>::into(*id),
+                "title": title,
+                "workspace": workspace,
+            }),
+            StatusEvent::WindowClosed {
+                id,
+                title,
+                workspace,
+            } => json!({
+                "event": "window_closed",
+                "id": Into::<i32>::into(*id),
+                "title": title,
+                "workspace": workspace,
+            }),
+            StatusEvent::WindowMoved {
+                id,
+                title,
+                from,
+                to,
+            } => json!({
+                "event": "window_moved",
+                "id": Into::<i32>::into(*id),
+                "title": title,
+                "from": from,
+                "to": to,
+            }),
+            StatusEvent::ModeChanged { mode } => json!({
+                "event": "mode_changed",
+                "mode": mode,
+            }),
+        }
+    }
+}
+
+/// Returns the managed windows of the snapshot together with the workspace they are on
+fn windows(snapshot: &StateSnapshot) -> Vec<(WindowId, &str, i32)> {
+    snapshot
+        .displays
+        .iter()
+        .flat_map(|d| d.workspaces.iter())
+        .flat_map(|w| {
+            w.windows
+                .iter()
+                .map(move |(id, title)| (*id, title.as_str(), w.id))
+        })
+        .collect()
+}
+
+/// Returns what changed between the two snapshots
+pub fn diff(previous: &StateSnapshot, next: &StateSnapshot) -> Vec<StatusEvent> {
+    let mut events = Vec::new();
+
+    if previous.workspace_id != next.workspace_id {
+        let display = next
+            .displays
+            .iter()
+            .find(|d| d.workspaces.iter().any(|w| w.id == next.workspace_id))
+            .map(|d| d.id)
+            .unwrap_or_default();
+
+        events.push(StatusEvent::WorkspaceFocused {
+            workspace: next.workspace_id,
+            display,
+        });
+    }
+
+    let old_windows = windows(previous);
+    let new_windows = windows(next);
+
+    for (id, title, workspace) in &new_windows {
+        match old_windows.iter().find(|(old_id, _, _)| old_id == id) {
+            None => events.push(StatusEvent::WindowOpened {
+                id: *id,
+                title: title.to_string(),
+                workspace: *workspace,
+            }),
+            Some((_, _, from)) if from != workspace => events.push(StatusEvent::WindowMoved {
+                id: *id,
+                title: title.to_string(),
+                from: *from,
+                to: *workspace,
+            }),
+            _ => {}
+        }
+    }
+
+    for (id, title, workspace) in &old_windows {
+        if !new_windows.iter().any(|(new_id, _, _)| new_id == id) {
+            events.push(StatusEvent::WindowClosed {
+                id: *id,
+                title: title.to_string(),
+                workspace: *workspace,
+            });
+        }
+    }
+
+    if previous.mode != next.mode {
+        events.push(StatusEvent::ModeChanged {
+            mode: next.mode.clone(),
+        });
+    }
+
+    events
+}
+
+lazy_static! {
+    /// Every subscriber gets its own thread that writes to its connection
+    static ref SUBSCRIBERS: Mutex<Vec<Sender<String>>> = Mutex::new(Vec::new());
+}
+
+/// Streams the events to the connection until it gets closed. The subscriber first receives the
+/// events that lead from an empty state to the current one.
+pub fn subscribe(mut stream: TcpStream) {
+    // holding the lock while reading the snapshot makes sure that no change gets lost in between
+    let mut subscribers = SUBSCRIBERS.lock();
+    let initial = diff(&StateSnapshot::default(), &snapshot::get());
+    // the events of the current state always fit, no matter how many windows there are
+    let (sender, receiver) = bounded::<String>(initial.len() + MAX_PENDING_EVENTS);
+
+    for event in initial {
+        sender
+            .send(event.to_json().to_string())
+            .expect("Failed to send status event");
+    }
+
+    subscribers.push(sender);
+
+    // once the subscriber got disconnected the events it already received still get written,
+    // after which the connection gets closed
+    thread::spawn(move || {
+        for line in receiver {
+            if stream.write_all(format!("{}\n", line).as_bytes()).is_err() {
+                debug!("Subscriber disconnected");
+                break;
+            }
+        }
+    });
+}
+
+/// Sends the line to every subscriber. Subscribers whose connection got closed or that fell
+/// behind get removed.
+fn broadcast(subscribers: &mut Vec<Sender<String>>, line: &str) {
+    subscribers.retain(|s| match s.try_send(line.to_string()) {
+        Ok(()) => true,
+        Err(e) => {
+            if e.is_full() {
+                debug!("Disconnecting a subscriber that fell behind");
+            }
+            false
+        }
+    });
+}
+
+/// Sends the changes between the snapshots to every subscriber
+pub fn publish(previous: &StateSnapshot, next: &StateSnapshot) {
+    let mut subscribers = SUBSCRIBERS.lock();
+
+    if subscribers.is_empty() {
+        return;
+    }
+
+    for event in diff(previous, next) {
+        broadcast(&mut subscribers, &event.to_json().to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        direction::Direction,
+        snapshot::{DisplaySnapshot, WorkspaceSnapshot},
+        split_direction::SplitDirection,
+    };
+
+    fn workspace(id: i32, windows: &[(i32, &str)]) -> WorkspaceSnapshot {
+        WorkspaceSnapshot {
+            id,
+            number: id,
+            text: id.to_string(),
+            window_count: windows.len(),
+            is_fullscreened: false,
+            next_axis: SplitDirection::Vertical,
+            next_direction: Direction::Right,
+            focused_window: None,
+            windows: windows
+                .iter()
+                .map(|(id, title)| (WindowId::from(*id), title.to_string()))
+                .collect(),
+        }
+    }
+
+    fn state(workspace_id: i32, workspaces: Vec<WorkspaceSnapshot>) -> StateSnapshot {
+        StateSnapshot {
+            workspace_id,
+            displays: vec![DisplaySnapshot {
+                id: DisplayId(7),
+                focused_workspace_id: Some(workspace_id),
+                highlighted_workspace_id: Some(workspace_id),
+                bar_color: 0,
+                workspaces,
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn subscribers_that_fall_behind_get_removed() {
+        let (fast, fast_events) = bounded(1);
+        let (slow, slow_events) = bounded(1);
+        let (closed, closed_events) = bounded(1);
+        let mut subscribers = vec![fast, slow, closed];
+
+        drop(closed_events);
+        broadcast(&mut subscribers, "first");
+        assert_eq!(subscribers.len(), 2);
+
+        assert_eq!(fast_events.try_recv(), Ok("first".to_string()));
+        broadcast(&mut subscribers, "second");
+
+        assert_eq!(subscribers.len(), 1);
+        assert_eq!(fast_events.try_recv(), Ok("second".to_string()));
+        // the events that were received before still get delivered
+        assert_eq!(slow_events.try_iter().collect::<Vec<_>>(), vec!["first"]);
+    }
+
+    #[test]
+    fn diff_snapshots() {
+        let previous = state(
+            1,
+            vec![
+                workspace(1, &[(10, "editor"), (11, "browser")]),
+                workspace(2, &[]),
+            ],
+        );
+        let mut next = state(
+            2,
+            vec![
+                workspace(1, &[(10, "editor")]),
+                workspace(2, &[(12, "terminal")]),
+            ],
+        );
+        next.mode = Some("resize".into());
+
+        assert_eq!(
+            diff(&previous, &next),
+            vec![
+                StatusEvent::WorkspaceFocused {
+                    workspace: 2,
+                    display: DisplayId(7)
+                },
+                StatusEvent::WindowOpened {
+                    id: WindowId::from(12),
+                    title: "terminal".into(),
+                    workspace: 2
+                },
+                StatusEvent::WindowClosed {
+                    id: WindowId::from(11),
+                    title: "browser".into(),
+                    workspace: 1
+                },
+                StatusEvent::ModeChanged {
+                    mode: Some("resize".into())
+                },
+            ]
+        );
+
+        let mut moved = state(
+            2,
+            vec![
+                workspace(1, &[]),
+                workspace(2, &[(12, "terminal"), (10, "editor")]),
+            ],
+        );
+        moved.mode = next.mode.clone();
+
+        assert_eq!(
+            diff(&next, &moved),
+            vec![StatusEvent::WindowMoved {
+                id: WindowId::from(10),
+                title: "editor".into(),
+                from: 1,
+                to: 2
+            }]
+        );
+    }
+}
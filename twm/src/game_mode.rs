@@ -0,0 +1,378 @@
+//! Gets out of the way while a fullscreen application, which is usually a game, is in the
+//! foreground. Depending on the config this suspends the keybindings, hides the bar on the display
+//! of the application, disables the mouse integration, stops tiling and ignores the window events
+//! of that display until the application exits.
+//!
+//! Applications count as fullscreen if they run in exclusive fullscreen or if they cover their
+//! display without a frame. Windows that nog manages never count. Rules can mark windows as games
+//! or exclude them. Game mode ends once the application loses the foreground or stops being
+//! fullscreen.
+use crate::{
+    borderless,
+    event::Event,
+    pinned,
+    state_handle::StateHandle,
+    system::{DisplayId, NativeWindow, SystemResult, WindowId, BIN_NAME},
+    AppState,
+};
+use log::{error, info};
+use std::thread;
+
+/// Remembers what entering game mode changed, so that leaving it only reverts these changes.
+#[derive(Debug, Clone)]
+pub struct GameMode {
+    pub display_id: DisplayId,
+    pub window_id: WindowId,
+    pub suspended_keybindings: bool,
+    pub hid_bar: bool,
+    pub stopped_mouse_listener: bool,
+    pub suspended_tiling: bool,
+    pub pauses_events: bool,
+}
+
+impl GameMode {
+    /// Whether events of the window should be ignored
+    pub fn ignores(&self, window: &NativeWindow) -> bool {
+        self.pauses_events
+            && window
+                .get_display()
+                .map(|d| d.id == self.display_id)
+                .unwrap_or(false)
+    }
+}
+
+fn call_hook(state: &AppState, callback_id: Option<usize>) {
+    if let Some(idx) = callback_id {
+        state
+            .event_channel
+            .sender
+            .send(Event::CallCallback {
+                idx,
+                is_mode_callback: false,
+            })
+            .expect("Failed to send CallCallback event");
+    }
+}
+
+pub fn enter(state: &mut AppState, window_id: WindowId) -> SystemResult {
+    let config = state.config.game_mode.clone();
+    let display_id = state.system.get_window_display(window_id)?;
+
+    info!(
+        "Entering game mode for '{}' on display {:?}",
+        state.system.get_process_name(window_id),
+        display_id
+    );
+
+    let suspended_keybindings =
+        config.suspend_keybindings && !state.keybindings_manager.is_suspended();
+    if suspended_keybindings {
+        state.keybindings_manager.suspend();
+    }
+
+    let hid_bar = config.hide_bar
+        && state
+            .get_display_by_id(display_id)
+            .and_then(|d| d.appbar.as_ref())
+            .map(|bar| !bar.hidden)
+            .unwrap_or(false);
+    if hid_bar {
+        state.set_appbar_hidden(display_id, true)?;
+    }
+
+    let stopped_mouse_listener = config.disable_mouse_integration && state.config.mouse_integration;
+    if stopped_mouse_listener {
+        state.mouse_listener.stop();
+    }
+
+    let suspended_tiling = config.suspend_tiling;
+    if suspended_tiling {
+        if let Some(display) = state.get_display_by_id_mut(display_id) {
+            display.tiling_suspended = true;
+        }
+    }
+
+    state.game_mode = Some(GameMode {
+        display_id,
+        window_id,
+        suspended_keybindings,
+        hid_bar,
+        stopped_mouse_listener,
+        suspended_tiling,
+        pauses_events: config.pause_events,
+    });
+
+    call_hook(state, config.on_enter);
+
+    Ok(())
+}
+
+pub fn leave(state: &mut AppState) -> SystemResult {
+    let game_mode = match state.game_mode.take() {
+        Some(game_mode) => game_mode,
+        None => return Ok(()),
+    };
+
+    info!("Leaving game mode");
+
+    if game_mode.suspended_keybindings {
+        state.keybindings_manager.resume();
+    }
+
+    if game_mode.hid_bar {
+        state.set_appbar_hidden(game_mode.display_id, false)?;
+    }
+
+    if game_mode.stopped_mouse_listener && state.config.mouse_integration {
+        state.mouse_listener.start(&state.event_channel);
+    }
+
+    if game_mode.suspended_tiling {
+        let config = state.config.clone();
+
+        if let Some(display) = state.get_display_by_id_mut(game_mode.display_id) {
+            display.tiling_suspended = false;
+
+            // restores the layout in case the application moved or covered the tiles
+            display.refresh_pending.set(false);
+            display.refresh_grid(&config)?;
+        }
+    }
+
+    call_hook(state, state.config.game_mode.on_leave);
+
+    Ok(())
+}
+
+/// Whether nog tiles, pins or stretched the window, which means that nog removed its frame
+fn is_managed(state: &AppState, id: WindowId) -> bool {
+    borderless::is_borderless(state, id)
+        || pinned::is_pinned(state, id)
+        || state
+            .displays
+            .iter()
+            .flat_map(|d| d.grids.iter())
+            .any(|g| g.contains(id))
+}
+
+/// What is known about the foreground window when deciding whether it is a game
+#[derive(Debug, Default, Clone, Copy)]
+struct Candidate {
+    /// The `game` setting of the rule that matches the window
+    rule: Option<bool>,
+    managed: bool,
+    /// Covers its display without a frame
+    borderless_fullscreen: bool,
+    /// Some application runs in exclusive fullscreen
+    exclusive_fullscreen: bool,
+}
+
+impl Candidate {
+    fn is_game(&self) -> bool {
+        match self.rule {
+            Some(is_game) => is_game,
+            None => !self.managed && (self.borderless_fullscreen || self.exclusive_fullscreen),
+        }
+    }
+}
+
+/// Returns the foreground window if it belongs to a fullscreen application or a rule marks it as
+/// a game.
+fn detect(state: &AppState) -> Option<WindowId> {
+    let system = state.system.as_ref();
+    let id = system.get_foreground_window().ok()?;
+
+    if system.get_process_name(id) == BIN_NAME {
+        return None;
+    }
+
+    let title = system.get_window_title(id).unwrap_or_default();
+    let rule = state
+        .config
+        .rules
+        .iter()
+        .chain(state.additonal_rules.iter())
+        .find(|rule| rule.matches(system, id, &title))
+        .and_then(|rule| rule.game);
+    let managed = rule.is_none() && is_managed(state, id);
+    let candidate = Candidate {
+        rule,
+        managed,
+        borderless_fullscreen: rule.is_none() && !managed && system.is_borderless_fullscreen(id),
+        exclusive_fullscreen: rule.is_none() && !managed && system.is_fullscreen_app_running(),
+    };
+
+    if candidate.is_game() {
+        Some(id)
+    } else {
+        None
+    }
+}
+
+/// Enters or leaves game mode depending on the foreground window. Game mode lasts while the
+/// application is fullscreen and in the foreground.
+pub fn poll(state: &mut AppState) -> SystemResult {
+    let enabled = state.work_mode && state.config.game_mode.enabled;
+    let game = if enabled { detect(state) } else { None };
+
+    match state.game_mode.as_ref() {
+        Some(game_mode) if game != Some(game_mode.window_id) => leave(state),
+        None => match game {
+            Some(window_id) => enter(state, window_id),
+            None => Ok(()),
+        },
+        _ => Ok(()),
+    }
+}
+
+pub fn start(handle: StateHandle) {
+    thread::spawn(move || loop {
+        sleep!(500);
+
+        handle.update(|state| {
+            if let Err(e) = poll(state) {
+                error!("Failed to change game mode {:?}", e);
+            }
+        });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::{
+        config::Config,
+        system::{
+            mock::{MockSystem, MockWindow},
+            Rectangle, System,
+        },
+    };
+
+    #[test]
+    fn fullscreen_windows_are_games() {
+        let borderless = Candidate {
+            borderless_fullscreen: true,
+            ..Candidate::default()
+        };
+        let exclusive = Candidate {
+            exclusive_fullscreen: true,
+            ..Candidate::default()
+        };
+
+        assert!(borderless.is_game());
+        assert!(exclusive.is_game());
+        assert!(!Candidate::default().is_game());
+    }
+
+    #[test]
+    fn managed_windows_are_no_games() {
+        let candidate = Candidate {
+            managed: true,
+            borderless_fullscreen: true,
+            exclusive_fullscreen: true,
+            ..Candidate::default()
+        };
+
+        assert!(!candidate.is_game());
+    }
+
+    #[test]
+    fn rules_decide_regardless_of_size() {
+        let excluded = Candidate {
+            rule: Some(false),
+            borderless_fullscreen: true,
+            ..Candidate::default()
+        };
+        let windowed_game = Candidate {
+            rule: Some(true),
+            ..Candidate::default()
+        };
+
+        assert!(!excluded.is_game());
+        assert!(windowed_game.is_game());
+    }
+
+    #[test]
+    fn covering_a_display() {
+        let display = Rectangle {
+            left: 1920,
+            right: 3840,
+            top: 0,
+            bottom: 1080,
+        };
+        let fullscreen = display;
+        let oversized = Rectangle {
+            left: 1912,
+            right: 3848,
+            top: -8,
+            bottom: 1088,
+        };
+        let maximized = Rectangle {
+            bottom: 1040,
+            ..display
+        };
+
+        assert!(fullscreen.covers(&display));
+        assert!(oversized.covers(&display));
+        assert!(!maximized.covers(&display));
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        assert!(!crate::config::Config::default().game_mode.enabled);
+    }
+
+    #[test]
+    fn follows_the_foreground_window() {
+        let display = Rectangle {
+            left: 0,
+            top: 0,
+            right: 1920,
+            bottom: 1080,
+        };
+        let system = Arc::new(
+            MockSystem::default()
+                .with_display(0, 0, 1920, 1080)
+                .with_window(MockWindow {
+                    framed: false,
+                    ..MockWindow::new(1, "game.exe", display)
+                })
+                .with_window(MockWindow::new(2, "code.exe", display)),
+        );
+        let mut config = Config::default();
+        config.game_mode.enabled = true;
+        let mut state = AppState::with_system(config, system.clone());
+
+        system.focus_window(1.into()).unwrap();
+        poll(&mut state).unwrap();
+        assert_eq!(state.game_mode.as_ref().map(|g| g.window_id), Some(1.into()));
+
+        // switching to another window leaves game mode
+        system.focus_window(2.into()).unwrap();
+        poll(&mut state).unwrap();
+        assert!(state.game_mode.is_none());
+
+        // so does a game that stops covering the display
+        system.focus_window(1.into()).unwrap();
+        poll(&mut state).unwrap();
+        assert!(state.game_mode.is_some());
+        system
+            .set_window_rect(
+                1.into(),
+                Rectangle {
+                    right: 800,
+                    bottom: 600,
+                    ..display
+                },
+            )
+            .unwrap();
+        poll(&mut state).unwrap();
+        assert!(state.game_mode.is_none());
+
+        // exclusive fullscreen doesn't need to cover the display
+        system.set_fullscreen_app_running(true);
+        poll(&mut state).unwrap();
+        assert!(state.game_mode.is_some());
+    }
+}
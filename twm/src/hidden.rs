@@ -0,0 +1,140 @@
+//! Hides windows until the user restores them, similar to minimizing an application to the tray.
+//! A hidden window doesn't belong to any workspace, which is why switching workspaces never shows
+//! it again.
+//!
+//! Restoring a window puts it on the current workspace. Windows that are still hidden when nog
+//! stops managing windows are shown again, so that they can't get lost.
+//!
+//! Only windows of applications can be hidden. The taskbar, the desktop and the windows of nog
+//! itself would otherwise disappear until nog exits.
+use crate::{
+    event::Event,
+    system::{NativeWindow, SystemError, SystemResult, WindowId},
+    win_event_handler::{win_event::WinEvent, win_event_type::WinEventType},
+    AppState,
+};
+use log::info;
+
+#[derive(Debug, Clone)]
+pub struct HiddenWindow {
+    pub window: NativeWindow,
+    /// Whether the window was tiled before it got hidden
+    pub was_managed: bool,
+}
+
+pub fn is_hidden(state: &AppState, id: WindowId) -> bool {
+    state.hidden_windows.iter().any(|h| h.window.id == id)
+}
+
+/// Returns the hidden windows that still exist, the one that got hidden last at the end
+pub fn list(state: &mut AppState) -> &Vec<HiddenWindow> {
+    state.hidden_windows.retain(|h| h.window.is_window());
+    &state.hidden_windows
+}
+
+/// The window classes of the taskbars and the desktop
+const SHELL_CLASSES: [&str; 4] = ["Shell_TrayWnd", "Shell_SecondaryTrayWnd", "Progman", "WorkerW"];
+
+/// Whether the window is a top-level window of an application that nog could manage. Managed
+/// windows always are.
+fn is_app_window(state: &mut AppState, window: &NativeWindow) -> bool {
+    if state.find_grid_containing_window(window.id).is_some() {
+        return true;
+    }
+
+    let is_shell = window
+        .get_class_name()
+        .map(|name| SHELL_CLASSES.contains(&name.as_str()))
+        .unwrap_or(true);
+    let is_own = window.get_process_id() == std::process::id();
+
+    !is_shell && !is_own && window.get_parent_window().is_err() && window.should_manage()
+}
+
+/// Hides the foreground window and takes it out of its workspace. Fails if the foreground window
+/// doesn't belong to an application.
+pub fn hide(state: &mut AppState) -> SystemResult {
+    let window = NativeWindow::get_foreground_window()?;
+
+    if !is_app_window(state, &window) {
+        return Err(SystemError::NotAnAppWindow(window.id));
+    }
+
+    let config = state.config.clone();
+    let mut hidden = HiddenWindow {
+        window,
+        was_managed: false,
+    };
+
+    if let Some(grid) = state.find_grid_containing_window(hidden.window.id) {
+        let grid_id = grid.id;
+
+        if let Some(mut w) = grid.remove_by_window_id(hidden.window.id) {
+            w.cleanup()?;
+            hidden.window = w;
+            hidden.was_managed = true;
+        }
+
+        if let Some(d) = state.find_grid_display(grid_id) {
+            d.refresh_grid(&config)?;
+        }
+    }
+
+    info!("Hiding '{}' | {}", hidden.window.title, hidden.window.id);
+
+    hidden.window.hide();
+    state.hidden_windows.push(hidden);
+
+    Ok(())
+}
+
+/// Shows the hidden window again. A window that was tiled gets managed like a new window, which
+/// tiles it on the current workspace.
+pub fn restore(state: &mut AppState, id: WindowId) -> SystemResult {
+    let idx = match state.hidden_windows.iter().position(|h| h.window.id == id) {
+        Some(idx) => idx,
+        None => return Ok(()),
+    };
+    let hidden = state.hidden_windows.remove(idx);
+
+    info!("Restoring '{}' | {}", hidden.window.title, hidden.window.id);
+
+    hidden.window.show();
+
+    if hidden.was_managed {
+        state
+            .event_channel
+            .sender
+            .send(Event::WinEvent(WinEvent {
+                typ: WinEventType::Show(true),
+                window: hidden.window.clone(),
+            }))
+            .expect("Failed to send WinEvent");
+    }
+
+    hidden.window.focus()
+}
+
+/// Restores the window that got hidden last
+pub fn restore_last(state: &mut AppState) -> SystemResult {
+    match list(state).last().map(|h| h.window.id) {
+        Some(id) => restore(state, id),
+        None => Ok(()),
+    }
+}
+
+/// Forgets the window if it is hidden, because it got shown by someone else, like its application.
+pub fn forget(state: &mut AppState, id: WindowId) {
+    state.hidden_windows.retain(|h| h.window.id != id);
+}
+
+/// Shows every hidden window without managing it again
+pub fn restore_all(state: &mut AppState) -> SystemResult {
+    for hidden in std::mem::take(&mut state.hidden_windows) {
+        if hidden.window.is_window() {
+            hidden.window.show();
+        }
+    }
+
+    Ok(())
+}
@@ -1,7 +1,7 @@
 use parking_lot::Mutex;
 use std::sync::Arc;
 
-use crate::{bar, config::Config, keybindings::KbManager, startup, system::SystemResult, AppState};
+use crate::{bar, config::Config, display, event::Event, startup, system::SystemResult, AppState};
 
 pub fn update_config(state_arc: Arc<Mutex<AppState>>, new_config: Config) -> SystemResult {
     let state = state_arc.lock();
@@ -15,17 +15,56 @@ pub fn update_config(state_arc: Arc<Mutex<AppState>>, new_config: Config) -> Sys
         .unregister_keybindings();
 
     let mut state = state_arc.lock();
+    let old_config = std::mem::replace(&mut state.config, new_config);
+    state
+        .keybindings_manager
+        .set_keybindings(state.config.keybindings.clone(), state.config.mode_handlers.clone());
+    drop(state);
+
+    apply_changes(state_arc.clone(), &old_config)?;
+
+    let mut state = state_arc.lock();
+    state.keybindings_manager.register_keybindings();
+    if let Some(mode) = prev_mode {
+        state.keybindings_manager.enter_mode(&mode);
+    }
+
+    Ok(())
+}
+
+/// Changes a single setting without reloading the config or touching the keybindings. Whatever
+/// depends on the setting gets updated once the `ConfigChanged` event is handled.
+pub fn change_setting(
+    state_arc: Arc<Mutex<AppState>>,
+    field: &str,
+    change: impl FnOnce(&mut Config) -> Result<(), String>,
+) -> Result<(), String> {
+    let mut state = state_arc.lock();
+    let mut config = state.config.clone();
+
+    change(&mut config)?;
+
+    state.keybindings_manager.update_configuration(&config);
+    let old_config = std::mem::replace(&mut state.config, config);
+
+    state
+        .event_channel
+        .sender
+        .send(Event::ConfigChanged(field.into(), Box::new(old_config)))
+        .expect("Failed to send config changed event");
+
+    Ok(())
+}
+
+/// Updates the bars, the windows and the listeners to the differences between the old config and
+/// the current one.
+pub fn apply_changes(state_arc: Arc<Mutex<AppState>>, old_config: &Config) -> SystemResult {
+    let mut state = state_arc.lock();
 
     let work_mode = state.work_mode;
-    let old_config = state.config.clone();
     let mut draw_app_bar = false;
     let mut close_app_bars = false;
 
-    state.config = new_config;
-    state
-        .keybindings_manager
-        .set_keybindings(state.config.keybindings.clone(), state.config.mode_handlers.clone());
-
     if work_mode {
         if old_config.remove_task_bar && !state.config.remove_task_bar {
             state.show_taskbars();
@@ -35,6 +74,13 @@ pub fn update_config(state_arc: Arc<Mutex<AppState>>, new_config: Config) -> Sys
             state.hide_taskbars();
             close_app_bars = true;
             draw_app_bar = state.config.display_app_bar;
+        } else if state.config.remove_task_bar
+            && old_config.auto_hide_task_bar != state.config.auto_hide_task_bar
+        {
+            state.show_taskbars();
+            state.hide_taskbars();
+            close_app_bars = true;
+            draw_app_bar = state.config.display_app_bar;
         }
 
         if old_config.display_app_bar && state.config.display_app_bar {
@@ -51,26 +97,35 @@ pub fn update_config(state_arc: Arc<Mutex<AppState>>, new_config: Config) -> Sys
         }
     }
 
+    if work_mode && old_config.mouse_integration != state.config.mouse_integration {
+        if state.config.mouse_integration {
+            state.mouse_listener.start(&state.event_channel);
+        } else {
+            state.mouse_listener.stop();
+        }
+    }
+
     //TODO: handle multi monitor change
 
+    // windows whose rule decides about the title bar keep it the way it is
     if old_config.remove_title_bar && !state.config.remove_title_bar {
         for grid in state.get_grids_mut().iter_mut() {
             grid.modify_windows(|window| {
-                window.reset_style();
-                window
-                    .update_style()
-                    .expect("Failed to update style of window");
+                let rule = window.rule.clone().unwrap_or_default();
+                if rule.remove_title_bar.is_none() && window.title_bar_removed {
+                    window.restore_title_bar()?;
+                }
                 Ok(())
             })?;
         }
     } else if !old_config.remove_title_bar && state.config.remove_title_bar {
-        let use_border = old_config.use_border;
+        let use_border = state.config.use_border;
         for grid in state.get_grids_mut() {
             grid.modify_windows(|window| {
-                window.remove_title_bar(use_border)?;
-                window
-                    .update_style()
-                    .expect("Failed to update style of window");
+                let rule = window.rule.clone().unwrap_or_default();
+                if rule.remove_title_bar.is_none() && !window.title_bar_removed {
+                    window.remove_title_bar(rule.use_border.unwrap_or(use_border))?;
+                }
                 Ok(())
             })?;
         }
@@ -80,6 +135,16 @@ pub fn update_config(state_arc: Arc<Mutex<AppState>>, new_config: Config) -> Sys
         startup::set_launch_on_startup(state.config.launch_on_startup);
     }
 
+    if old_config.reserved_space != state.config.reserved_space {
+        let config = state.config.clone();
+        display::reserve_space(&mut state.displays, &config);
+
+        if work_mode && state.config.display_app_bar {
+            close_app_bars = true;
+            draw_app_bar = true;
+        }
+    }
+
     if close_app_bars {
         drop(state);
         bar::close_all(state_arc.clone());
@@ -92,9 +157,12 @@ pub fn update_config(state_arc: Arc<Mutex<AppState>>, new_config: Config) -> Sys
         state = state_arc.lock();
     }
 
-    state.keybindings_manager.register_keybindings();
-    if let Some(mode) = prev_mode {
-        state.keybindings_manager.enter_mode(&mode);
+    // the bars show the names of the workspaces
+    if !draw_app_bar
+        && (old_config.workspace_settings != state.config.workspace_settings
+            || old_config.bar.separator != state.config.bar.separator)
+    {
+        state.redraw_app_bars();
     }
 
     for d in state.displays.iter() {
@@ -103,5 +171,5 @@ pub fn update_config(state_arc: Arc<Mutex<AppState>>, new_config: Config) -> Sys
         }
     }
 
-    Ok(())
+    state.update_opacity()
 }
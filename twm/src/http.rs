@@ -0,0 +1,51 @@
+//! Performs the requests of `nog.http` on a worker thread, so that a slow server blocks neither the
+//! config nor the bar. The callback of the script doesn't run on the worker thread. The response
+//! gets sent to the event loop instead, which calls it like every other callback.
+use log::debug;
+use std::{thread, time::Duration};
+
+/// How long a request may take when the script doesn't specify a timeout
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Default)]
+pub struct Request {
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub timeout: Option<Duration>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+fn send(request: &Request) -> reqwest::Result<Response> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(request.timeout.unwrap_or(DEFAULT_TIMEOUT))
+        .user_agent(concat!("nog/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+
+    let mut builder = client.get(&request.url);
+    for (name, value) in &request.headers {
+        builder = builder.header(name.as_str(), value.as_str());
+    }
+
+    let response = builder.send()?;
+    let status = response.status().as_u16();
+
+    Ok(Response {
+        status,
+        body: response.text()?,
+    })
+}
+
+/// Sends the GET request on a new thread and passes the response or the reason why there is none
+/// to `on_done`.
+pub fn get(request: Request, on_done: impl FnOnce(Result<Response, String>) + Send + 'static) {
+    thread::spawn(move || {
+        debug!("Sending a GET request to {}", request.url);
+
+        on_done(send(&request).map_err(|e| e.to_string()));
+    });
+}
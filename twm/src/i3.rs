@@ -0,0 +1,322 @@
+//! Translates a subset of the config of i3 and sway into nogscript (see `nog --import-i3 <path>`),
+//! so that people coming from Linux can keep most of their keybindings.
+//!
+//! Variables, keybindings, workspace assignments, floating rules and gaps get translated. Every
+//! other line ends up as a comment in the generated script, so that nothing gets lost silently.
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref CRITERION: Regex = Regex::new(r#"(\w+)="([^"]*)""#).unwrap();
+}
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Action(String),
+    Launch(String),
+}
+
+impl Command {
+    fn to_script(&self) -> String {
+        match self {
+            Command::Action(action) => format!("nog.actions.run({})", quote(action)),
+            Command::Launch(cmd) => format!("nog.launch({})", quote(cmd)),
+        }
+    }
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn key(name: &str) -> Option<String> {
+    let key = match name {
+        "Mod1" => "Alt",
+        "Mod4" => "Win",
+        "Shift" => "Shift",
+        "Control" | "Ctrl" => "Control",
+        "Return" => "Enter",
+        "space" => "Space",
+        "BackSpace" => "Backspace",
+        "minus" => "Minus",
+        "plus" => "Plus",
+        "comma" => ",",
+        "period" => ".",
+        "Escape" | "Tab" | "Left" | "Right" | "Up" | "Down" => name,
+        _ if name.len() == 1 && name.chars().all(|c| c.is_ascii_alphanumeric()) => {
+            return Some(name.to_uppercase())
+        }
+        _ if name.starts_with('F') && name[1..].parse::<u8>().map_or(false, |n| n <= 12) => name,
+        _ => return None,
+    };
+
+    Some(key.into())
+}
+
+fn key_combo(combo: &str) -> Option<String> {
+    combo
+        .split('+')
+        .map(key)
+        .collect::<Option<Vec<_>>>()
+        .map(|keys| keys.join("+"))
+}
+
+/// Parses the number of a workspace like `number 2` or `2:web`
+fn workspace_number(args: &[&str]) -> Option<i32> {
+    let name = args.iter().find(|arg| **arg != "number")?;
+    name.split(':').next()?.parse().ok()
+}
+
+fn command(cmd: &str) -> Option<Command> {
+    let args: Vec<&str> = cmd.split_whitespace().collect();
+    let action = |name: &str| Some(Command::Action(name.into()));
+
+    match args.as_slice() {
+        ["focus", direction] | ["move", direction]
+            if ["left", "right", "up", "down"].contains(direction) =>
+        {
+            let kind = if args[0] == "focus" { "focus" } else { "swap" };
+            action(&format!("{}_{}", kind, direction))
+        }
+        ["move", "container", "to", "workspace", direction]
+        | ["move", "window", "to", "workspace", direction]
+        | ["move", "to", "workspace", direction]
+            if ["next", "prev"].contains(direction) =>
+        {
+            action(&format!("move_to_workspace_{}(0)", direction))
+        }
+        ["move", "container", "to", "workspace", rest @ ..]
+        | ["move", "window", "to", "workspace", rest @ ..]
+        | ["move", "to", "workspace", rest @ ..] => {
+            action(&format!("move_to_workspace({})", workspace_number(rest)?))
+        }
+        ["workspace", direction] if ["next", "prev"].contains(direction) => {
+            action(&format!("focus_workspace_{}", direction))
+        }
+        ["workspace", "back_and_forth"] => action("focus_previous_workspace"),
+        ["workspace", rest @ ..] => {
+            action(&format!("change_workspace({})", workspace_number(rest)?))
+        }
+        ["kill"] => action("close_window"),
+        ["fullscreen"] | ["fullscreen", "toggle"] => action("toggle_fullscreen"),
+        ["floating", "toggle"] => action("toggle_floating"),
+        ["sticky", "toggle"] => action("toggle_always_on_top"),
+        // i3 names the split after the direction the windows get placed in, nog after the line
+        // that separates them
+        ["split", "h"] | ["split", "horizontal"] | ["splith"] => action("split_vertical"),
+        ["split", "v"] | ["split", "vertical"] | ["splitv"] => action("split_horizontal"),
+        ["mode", "\"resize\""] | ["mode", "resize"] => action("resize_mode"),
+        ["reload"] | ["restart"] => action("reload_config"),
+        ["exit"] => action("quit"),
+        ["exec", ..] => {
+            let cmd = args[1..]
+                .iter()
+                .skip_while(|arg| arg.starts_with("--"))
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            Some(Command::Launch(cmd.trim_matches('"').into()))
+        }
+        _ => None,
+    }
+}
+
+fn bindsym(args: &[&str]) -> Option<String> {
+    let mut args = args.iter().skip_while(|arg| arg.starts_with("--"));
+    let combo = key_combo(args.next()?)?;
+    let commands = args
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .split(&[';', ','][..])
+        .map(command)
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(match commands.as_slice() {
+        [Command::Action(action)] => format!("nog.bind({}, {})", quote(&combo), quote(action)),
+        [command] => format!("nog.bind({}, () => {})", quote(&combo), command.to_script()),
+        _ => format!(
+            "nog.bind({}, () => {{\n{}\n}})",
+            quote(&combo),
+            commands
+                .iter()
+                .map(|command| format!("  {}", command.to_script()))
+                .collect::<Vec<_>>()
+                .join("\n")
+        ),
+    })
+}
+
+/// Returns the pattern of a criteria like `[class="^Firefox$"]`
+fn pattern(line: &str) -> Option<String> {
+    let criteria = &line[line.find('[')?..line.find(']')?];
+    let captures = CRITERION.captures_iter(criteria).collect::<Vec<_>>();
+
+    ["class", "instance", "app_id", "title"]
+        .iter()
+        .find_map(|name| captures.iter().find(|c| &c[1] == *name))
+        .map(|c| c[2].to_string())
+}
+
+fn translate_line(line: &str) -> Option<String> {
+    let args: Vec<&str> = line.split_whitespace().collect();
+
+    match args.as_slice() {
+        ["bindsym", rest @ ..] => bindsym(rest),
+        ["assign", ..] => {
+            let rest = line[line.find(']')? + 1..]
+                .split_whitespace()
+                .filter(|arg| !["→", "workspace"].contains(arg))
+                .collect::<Vec<_>>();
+
+            Some(format!(
+                "nog.rules.match({}, #{{ workspace_id: {} }})",
+                quote(&pattern(line)?),
+                workspace_number(&rest)?
+            ))
+        }
+        ["for_window", ..] => {
+            let float = match line[line.find(']')? + 1..].trim() {
+                "floating enable" => true,
+                "floating disable" => false,
+                _ => return None,
+            };
+
+            Some(format!(
+                "nog.rules.match({}, #{{ float: {} }})",
+                quote(&pattern(line)?),
+                float
+            ))
+        }
+        ["gaps", kind, size] if *kind == "inner" || *kind == "outer" => Some(format!(
+            "nog.config.set(\"{}_gap\", {})",
+            kind,
+            size.parse::<i32>().ok()?
+        )),
+        _ => None,
+    }
+}
+
+/// Joins the lines that end with a backslash with the following line
+fn join_continuations(content: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+
+    for line in content.lines() {
+        match line.trim_end().strip_suffix('\\') {
+            Some(line) => current.push_str(line),
+            None => {
+                current.push_str(line);
+                lines.push(std::mem::take(&mut current));
+            }
+        }
+    }
+
+    if !current.is_empty() {
+        lines.push(current);
+    }
+
+    lines
+}
+
+/// Returns the nogscript that corresponds to the i3 config
+pub fn translate(content: &str) -> String {
+    let mut variables: Vec<(String, String)> = Vec::new();
+    let mut script = vec!["// Translated from an i3 config with `nog --import-i3`".to_string()];
+    // blocks like `bar { .. }` or `mode "resize" { .. }` are kept as comments
+    let mut depth = 0;
+
+    for line in join_continuations(content) {
+        let mut line = line.trim().to_string();
+
+        if depth > 0 || line.ends_with('{') {
+            depth += line.matches('{').count() as i32 - line.matches('}').count() as i32;
+            script.push(format!("// unsupported: {}", line));
+            continue;
+        }
+
+        if line.is_empty() {
+            script.push(line);
+            continue;
+        }
+
+        if let Some(comment) = line.strip_prefix('#') {
+            script.push(format!("//{}", comment));
+            continue;
+        }
+
+        for (name, value) in &variables {
+            line = line.replace(name, value);
+        }
+
+        let args: Vec<&str> = line.split_whitespace().collect();
+        if let ["set", name, value @ ..] = args.as_slice() {
+            if name.starts_with('$') {
+                variables.push((name.to_string(), value.join(" ")));
+                // longer names come first, so that `$mod` doesn't replace a part of `$mod_alt`
+                variables.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+                continue;
+            }
+        }
+
+        match translate_line(&line) {
+            Some(translated) => script.push(translated),
+            None => script.push(format!("// unsupported: {}", line)),
+        }
+    }
+
+    script.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translate_config() {
+        let config = r#"
+# keybindings
+set $mod Mod1
+set $term wt.exe
+bindsym $mod+Return exec --no-startup-id $term
+bindsym $mod+h focus left
+bindsym $mod+Shift+2 move container to workspace number 2; workspace number 2
+bindsym $mod+Shift+q kill
+bindsym $mod+Tab workspace next
+bindsym $mod+Shift+Tab move container to workspace prev
+bindsym $mod+r mode "resize"
+bindsym XF86AudioMute exec pactl set-sink-mute 0 toggle
+assign [class="^Firefox$"] → 2:web
+for_window [title="Picture-in-Picture"] floating enable
+gaps inner 10
+mode "resize" {
+    bindsym h resize shrink width 10 px
+}
+"#;
+
+        let expected = r#"// Translated from an i3 config with `nog --import-i3`
+
+// keybindings
+nog.bind("Alt+Enter", () => nog.launch("wt.exe"))
+nog.bind("Alt+H", "focus_left")
+nog.bind("Alt+Shift+2", () => {
+  nog.actions.run("move_to_workspace(2)")
+  nog.actions.run("change_workspace(2)")
+})
+nog.bind("Alt+Shift+Q", "close_window")
+nog.bind("Alt+Tab", "focus_workspace_next")
+nog.bind("Alt+Shift+Tab", "move_to_workspace_prev(0)")
+nog.bind("Alt+R", "resize_mode")
+// unsupported: bindsym XF86AudioMute exec pactl set-sink-mute 0 toggle
+nog.rules.match("^Firefox$", #{ workspace_id: 2 })
+nog.rules.match("Picture-in-Picture", #{ float: true })
+nog.config.set("inner_gap", 10)
+// unsupported: mode "resize" {
+// unsupported: bindsym h resize shrink width 10 px
+// unsupported: }
+"#;
+
+        assert_eq!(translate(config), expected);
+    }
+}
@@ -0,0 +1,571 @@
+//! Makes sure that only one instance of nog is running. Launching nog while it is already running
+//! forwards the command line arguments to the running instance and exits.
+//!
+//! Every argument that isn't a flag is a named action (e.g. `nog.exe "change_workspace(2)"`) or a
+//! pipeline (e.g. `nog.exe "workspace_windows(3) | move_to_workspace(5)"`), which gets executed by
+//! the running instance. `--trigger <key combo>` dispatches the keybinding of the
+//! key combo as if it got pressed.
+//!
+//! `--logs` asks the running instance for its most recent log entries instead and `--test-rules`
+//! asks it how the rules apply to the focused window. `--subscribe` keeps the connection open and
+//! receives the changes of the state (see `event_stream`).
+//!
+//! The running instance listens on a random local port. The port and a token that is new for
+//! every session get written to the config directory of the user, which only the user can read.
+//! Every request has to start with the token, so other users and websites can't send requests.
+//!
+//! The running instance keeps the session file open without letting other processes write to it.
+//! This makes starting a session atomic, so two launches at the same time can't both start.
+use crate::{
+    actions, event::Event, event_stream, logging, popup::Popup, rule_check::RuleCheck,
+    state_handle::StateHandle, system::NativeWindow, AppState,
+};
+use log::{debug, error, info};
+use std::{
+    collections::hash_map::RandomState,
+    fs::{File, OpenOptions},
+    hash::{BuildHasher, Hasher},
+    io::{BufRead, BufReader, Read, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    os::windows::fs::OpenOptionsExt,
+    path::PathBuf,
+    sync::Arc,
+    thread,
+    time::{Duration, SystemTime},
+};
+use winapi::{shared::winerror::ERROR_SHARING_VIOLATION, um::winnt::FILE_SHARE_READ};
+
+/// Prefix of the request that asks for log entries
+const LOGS_REQUEST: &'static str = "--logs ";
+/// How many log entries `--logs` prints if no amount is given
+const DEFAULT_LOG_COUNT: usize = 100;
+/// The request that asks how the rules apply to the focused window
+const TEST_RULES_REQUEST: &'static str = "--test-rules";
+/// The request that subscribes to the changes of the state
+const SUBSCRIBE_REQUEST: &'static str = "--subscribe";
+/// The request that checks whether the instance behind the session is still running
+const PING_REQUEST: &'static str = "--ping";
+/// The response to `PING_REQUEST`
+const PING_RESPONSE: &'static str = "nog";
+/// How long a client may take to send its request
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long the handshake with a possibly running instance may take
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How later launches reach the running instance
+#[derive(Debug, Clone, PartialEq)]
+struct Session {
+    port: u16,
+    token: String,
+}
+
+impl Session {
+    fn path() -> PathBuf {
+        let mut path = dirs::config_dir().expect("Failed to get config directory");
+        path.push("nog");
+        path.push("ipc_session");
+        path
+    }
+
+    fn parse(content: &str) -> Option<Self> {
+        let mut parts = content.split_whitespace();
+        let port = parts.next()?.parse().ok()?;
+        let token = parts.next()?.to_string();
+
+        Some(Self { port, token })
+    }
+
+    fn load() -> Option<Self> {
+        std::fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|content| Self::parse(&content))
+    }
+
+    /// Opens the session file so that only this instance can write to it until it exits. Returns
+    /// `None` if another instance already has it open.
+    fn lock() -> std::io::Result<Option<File>> {
+        let path = Self::path();
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .share_mode(FILE_SHARE_READ)
+            .open(path);
+
+        match file {
+            Ok(file) => Ok(Some(file)),
+            Err(e) if e.raw_os_error() == Some(ERROR_SHARING_VIOLATION as i32) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn save(&self, mut file: &File) -> std::io::Result<()> {
+        file.set_len(0)?;
+        write!(file, "{} {}", self.port, self.token)?;
+        file.flush()
+    }
+
+    fn address(&self) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], self.port))
+    }
+
+    /// Sends the request to the running instance and returns its response.
+    fn request(&self, content: &str, timeout: Option<Duration>) -> std::io::Result<String> {
+        let mut stream = match timeout {
+            Some(timeout) => TcpStream::connect_timeout(&self.address(), timeout)?,
+            None => TcpStream::connect(self.address())?,
+        };
+        let mut response = String::new();
+
+        stream.set_read_timeout(timeout)?;
+        stream.write_all(format!("{}\n{}", self.token, content).as_bytes())?;
+        stream.shutdown(Shutdown::Write)?;
+        stream.read_to_string(&mut response)?;
+
+        Ok(response)
+    }
+}
+
+/// Returns a token that can't be guessed by other processes. `RandomState` is seeded by the
+/// operating system.
+fn new_token() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+
+    (0..4)
+        .map(|i| {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u128(nanos);
+            hasher.write_u32(i);
+            format!("{:016x}", hasher.finish())
+        })
+        .collect()
+}
+
+/// Splits the request into its token and content. Returns `None` if the token is wrong.
+fn authenticate<'a>(request: &'a str, token: &str) -> Option<&'a str> {
+    let (received, content) = match request.find('\n') {
+        Some(idx) => (&request[..idx], &request[idx + 1..]),
+        None => (request, ""),
+    };
+
+    if received == token {
+        Some(content)
+    } else {
+        None
+    }
+}
+
+/// The listener of the running instance together with the token of its session
+pub struct Server {
+    listener: TcpListener,
+    token: String,
+    /// Kept open so that no other instance can start a session
+    _file: File,
+}
+
+impl Server {
+    /// The token that every request has to start with
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Args {
+    /// Start without entering the work mode, which means that nog only lives in the tray
+    pub hidden: bool,
+    /// Restore the windows that got modified by nog and exit
+    pub cleanup: bool,
+    /// Print the given amount of log entries of the running instance
+    pub logs: Option<usize>,
+    /// Print how the rules apply to the window that is focused after waiting the given amount of
+    /// seconds, which leaves time to focus the window
+    pub test_rules: Option<u64>,
+    /// Print the changes of the state of the running instance as JSON lines until it exits
+    pub subscribe: bool,
+    /// Print the nogscript that corresponds to the i3 config at the path and exit
+    pub import_i3: Option<String>,
+    /// Print the layouts of the dump at the path (see `nog.debug.dump`) and exit
+    pub replay: Option<String>,
+    /// `nog test [dir]` runs the tests of the config, which are inside of the given directory if
+    /// there is one, and exits
+    pub test: Option<Option<String>>,
+    pub actions: Vec<String>,
+    /// Key combos whose keybindings get dispatched
+    pub triggers: Vec<String>,
+}
+
+impl Args {
+    pub fn parse(args: impl Iterator<Item = String>) -> Self {
+        let mut result = Self::default();
+        let mut args = args.peekable();
+
+        // `test` is a subcommand instead of an action only as the first argument
+        if args.peek().map(String::as_str) == Some("test") {
+            args.next();
+            result.test = Some(args.next_if(|dir| !dir.starts_with("--")));
+        }
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--hidden" => result.hidden = true,
+                "--cleanup" => result.cleanup = true,
+                "--logs" => {
+                    let count = args.peek().and_then(|count| count.parse::<usize>().ok());
+
+                    if count.is_some() {
+                        args.next();
+                    }
+
+                    result.logs = Some(count.unwrap_or(DEFAULT_LOG_COUNT));
+                }
+                "--test-rules" => {
+                    let delay = args.peek().and_then(|delay| delay.parse::<u64>().ok());
+
+                    if delay.is_some() {
+                        args.next();
+                    }
+
+                    result.test_rules = Some(delay.unwrap_or(0));
+                }
+                "--subscribe" => result.subscribe = true,
+                "--trigger" => result.triggers.extend(args.next()),
+                "--import-i3" => result.import_i3 = args.next(),
+                "--replay" => result.replay = args.next(),
+                _ => result.actions.push(arg),
+            }
+        }
+
+        result
+    }
+
+    /// Whether nog only prints something and exits, which needs the console of the terminal it
+    /// got started from
+    pub fn prints_output(&self) -> bool {
+        self.logs.is_some()
+            || self.test_rules.is_some()
+            || self.subscribe
+            || self.import_i3.is_some()
+            || self.replay.is_some()
+            || self.test.is_some()
+    }
+}
+
+/// Whether another instance of nog is running. This is the case if the instance of the last
+/// session answers the handshake.
+pub fn is_running() -> bool {
+    Session::load()
+        .and_then(|session| session.request(PING_REQUEST, Some(HANDSHAKE_TIMEOUT)).ok())
+        .map(|response| response == PING_RESPONSE)
+        .unwrap_or(false)
+}
+
+/// Starts a new session, which later launches use to reach this instance. Returns `None` if
+/// another instance already started one.
+pub fn listen() -> std::io::Result<Option<Server>> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let file = match Session::lock()? {
+        Some(file) => file,
+        None => return Ok(None),
+    };
+    let session = Session {
+        port: listener.local_addr()?.port(),
+        token: new_token(),
+    };
+
+    session.save(&file)?;
+
+    Ok(Some(Server {
+        listener,
+        token: session.token,
+        _file: file,
+    }))
+}
+
+fn running_session() -> std::io::Result<Session> {
+    Session::load()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "Nog is not running"))
+}
+
+/// Sends the arguments to the running instance.
+pub fn forward(args: &[String]) -> std::io::Result<()> {
+    running_session()?
+        .request(&args.join("\n"), Some(READ_TIMEOUT))
+        .map(|_| ())
+}
+
+/// Sends the request to the running instance and returns its response.
+fn request(content: &str) -> std::io::Result<String> {
+    running_session()?.request(content, None)
+}
+
+/// Returns the last `count` log entries of the running instance.
+pub fn request_logs(count: usize) -> std::io::Result<String> {
+    request(&format!("{}{}", LOGS_REQUEST, count))
+}
+
+/// Returns how the rules of the running instance apply to the window that is focused once the
+/// delay is over.
+pub fn request_rule_check(delay: Duration) -> std::io::Result<String> {
+    thread::sleep(delay);
+    request(TEST_RULES_REQUEST)
+}
+
+/// Calls `f` with every line the running instance sends after subscribing, until it exits.
+pub fn subscribe(mut f: impl FnMut(String)) -> std::io::Result<()> {
+    let session = running_session()?;
+    let mut stream = TcpStream::connect(session.address())?;
+
+    stream.write_all(format!("{}\n{}", session.token, SUBSCRIBE_REQUEST).as_bytes())?;
+    stream.shutdown(Shutdown::Write)?;
+
+    for line in BufReader::new(stream).lines() {
+        f(line?);
+    }
+
+    Ok(())
+}
+
+fn send_rule_check(mut stream: &TcpStream, handle: &StateHandle) -> std::io::Result<()> {
+    let report = match NativeWindow::get_foreground_window() {
+        Ok(window) => handle
+            .query(move |state| RuleCheck::new(state, window).to_string())
+            .unwrap_or_else(|| "Nog stopped before checking the rules".into()),
+        Err(e) => format!("Failed to get the focused window {:?}", e),
+    };
+
+    stream.write_all(report.as_bytes())
+}
+
+fn send_logs(mut stream: &TcpStream, count: usize) -> std::io::Result<()> {
+    let logs = logging::tail(count)
+        .iter()
+        .map(|entry| entry.to_string())
+        .collect::<Vec<String>>();
+
+    stream.write_all(logs.join("\n").as_bytes())
+}
+
+/// Runs the actions and triggers of the arguments and shows an error popup for the ones that are
+/// invalid.
+pub fn run(args: &Args, state: &AppState) {
+    let sender = &state.event_channel.sender;
+    let kb_manager = &state.keybindings_manager;
+    let mut errors = Vec::new();
+
+    for action in &args.actions {
+        match actions::parse_command(action) {
+            Ok(event) => sender.send(event).expect("Failed to send action event"),
+            Err(e) => errors.push(e),
+        }
+    }
+
+    for key_combo in &args.triggers {
+        if let Err(e) = kb_manager.trigger(key_combo) {
+            errors.push(e);
+        }
+    }
+
+    if !errors.is_empty() {
+        sender
+            .send(Event::NewPopup(Popup::new_error(errors)))
+            .expect("Failed to send NewPopup event");
+    }
+}
+
+fn handle(mut stream: TcpStream, token: &str, state: &StateHandle) {
+    let mut request = String::new();
+
+    // a client that never finishes its request only blocks its own thread
+    if let Err(e) = stream
+        .set_read_timeout(Some(READ_TIMEOUT))
+        .and_then(|_| stream.read_to_string(&mut request))
+    {
+        error!("Failed to receive forwarded arguments {:?}", e);
+        return;
+    }
+
+    let content = match authenticate(&request, token) {
+        Some(content) => content,
+        None => {
+            debug!("Rejected a request without a valid token");
+            return;
+        }
+    };
+
+    if content == PING_REQUEST {
+        if let Err(e) = stream.write_all(PING_RESPONSE.as_bytes()) {
+            error!("Failed to answer the handshake {:?}", e);
+        }
+
+        return;
+    }
+
+    if let Some(count) = content.strip_prefix(LOGS_REQUEST) {
+        let count = count.trim().parse().unwrap_or(DEFAULT_LOG_COUNT);
+
+        if let Err(e) = send_logs(&stream, count) {
+            error!("Failed to send the logs {:?}", e);
+        }
+
+        return;
+    }
+
+    if content == SUBSCRIBE_REQUEST {
+        event_stream::subscribe(stream);
+        return;
+    }
+
+    if content == TEST_RULES_REQUEST {
+        if let Err(e) = send_rule_check(&stream, state) {
+            error!("Failed to send the rule check {:?}", e);
+        }
+
+        return;
+    }
+
+    let args = Args::parse(content.lines().map(String::from));
+
+    info!("Received arguments of another instance {:?}", args);
+
+    if args.actions.is_empty() && args.triggers.is_empty() {
+        state.send(Event::ShowOsd("Nog is already running".into()));
+    } else {
+        state.update(move |state| run(&args, state));
+    }
+}
+
+/// Listens for the arguments that later launches forward. Every client gets its own thread.
+pub fn start(server: Server, state: StateHandle) {
+    thread::spawn(move || {
+        let token = Arc::new(server.token);
+
+        for stream in server.listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let token = token.clone();
+                    let state = state.clone();
+
+                    thread::spawn(move || handle(stream, &token, &state));
+                }
+                Err(e) => error!("Failed to accept a connection {:?}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_args() {
+        let args = vec!["--hidden", "change_workspace(2)", "toggle_fullscreen"]
+            .into_iter()
+            .map(String::from);
+
+        assert_eq!(
+            Args::parse(args),
+            Args {
+                hidden: true,
+                cleanup: false,
+                logs: None,
+                test_rules: None,
+                subscribe: false,
+                import_i3: None,
+                replay: None,
+                test: None,
+                actions: vec!["change_workspace(2)".into(), "toggle_fullscreen".into()],
+                triggers: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn parse_logs_arg() {
+        let parse = |args: &[&str]| Args::parse(args.iter().map(|arg| arg.to_string())).logs;
+
+        assert_eq!(parse(&["--logs", "20"]), Some(20));
+        assert_eq!(parse(&["--logs"]), Some(DEFAULT_LOG_COUNT));
+        assert_eq!(parse(&["--logs", "--hidden"]), Some(DEFAULT_LOG_COUNT));
+        assert_eq!(parse(&["--hidden"]), None);
+    }
+
+    #[test]
+    fn parse_test_rules_arg() {
+        let parse = |args: &[&str]| Args::parse(args.iter().map(|arg| arg.to_string())).test_rules;
+
+        assert_eq!(parse(&["--test-rules", "3"]), Some(3));
+        assert_eq!(parse(&["--test-rules"]), Some(0));
+        assert_eq!(parse(&["focus_left"]), None);
+    }
+
+    #[test]
+    fn parse_test_arg() {
+        let parse = |args: &[&str]| Args::parse(args.iter().map(|arg| arg.to_string())).test;
+
+        assert_eq!(parse(&["test", "tests"]), Some(Some("tests".into())));
+        assert_eq!(parse(&["test", "--hidden"]), Some(None));
+        assert_eq!(parse(&["test"]), Some(None));
+        assert_eq!(parse(&["--hidden", "test"]), None);
+        assert_eq!(parse(&["--test"]), None);
+    }
+
+    #[test]
+    fn printing_args() {
+        let parse = |args: &[&str]| Args::parse(args.iter().map(|arg| arg.to_string()));
+
+        assert!(parse(&["--logs"]).prints_output());
+        assert!(parse(&["test"]).prints_output());
+        assert!(parse(&["--import-i3", "config"]).prints_output());
+        assert!(!parse(&["--hidden", "focus_left"]).prints_output());
+    }
+
+    #[test]
+    fn authenticate_requests() {
+        assert_eq!(authenticate("abc\nfocus_left", "abc"), Some("focus_left"));
+        assert_eq!(authenticate("abc", "abc"), Some(""));
+        assert_eq!(authenticate("abd\nquit", "abc"), None);
+        assert_eq!(authenticate("quit", "abc"), None);
+        assert_eq!(authenticate("\nquit", "abc"), None);
+    }
+
+    #[test]
+    fn parse_session() {
+        assert_eq!(
+            Session::parse("52427 0123abcd"),
+            Some(Session {
+                port: 52427,
+                token: "0123abcd".into()
+            })
+        );
+        assert_eq!(Session::parse("52427"), None);
+        assert_eq!(Session::parse("nog 0123abcd"), None);
+    }
+
+    #[test]
+    fn tokens_differ() {
+        let token = new_token();
+
+        assert_eq!(token.len(), 64);
+        assert_ne!(token, new_token());
+    }
+
+    #[test]
+    fn parse_trigger_args() {
+        let args = vec!["--trigger", "Alt+H", "focus_left", "--trigger"]
+            .into_iter()
+            .map(String::from);
+        let args = Args::parse(args);
+
+        assert_eq!(args.triggers, vec!["Alt+H".to_string()]);
+        assert_eq!(args.actions, vec!["focus_left".to_string()]);
+    }
+}
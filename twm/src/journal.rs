@@ -0,0 +1,365 @@
+//! Records every window nog modifies, so that they can be restored if nog doesn't exit cleanly.
+//!
+//! Each entry is a ledger of the attributes nog changed about a window, holding the value each
+//! attribute had before nog touched it for the first time. Unmanaging a window restores exactly
+//! these attributes and nothing else.
+//!
+//! The journal gets written to `journal.json` whenever it changes. A clean exit empties it, which
+//! means that any entry found on startup belongs to a run that crashed or got killed.
+use crate::{
+    system::{NativeWindow, Rectangle, SystemResult, WindowId},
+    window::{gwl_ex_style::GwlExStyle, gwl_style::GwlStyle},
+};
+use lazy_static::lazy_static;
+use log::{error, info};
+use parking_lot::Mutex;
+use serde_json::{json, Value};
+use std::{collections::HashMap, fs, path::PathBuf};
+
+#[derive(Debug, Clone)]
+pub struct JournalEntry {
+    pub window_id: i32,
+    /// Used to make sure that the id didn't get reused by another window in the meantime
+    pub process_name: String,
+    pub original_rect: Rectangle,
+    pub maximized: bool,
+    pub hidden: bool,
+    pub original_style: Option<i32>,
+    pub original_ex_style: Option<i32>,
+    /// Only gets recorded if the window was already translucent
+    pub original_opacity: Option<u8>,
+    pub was_topmost: Option<bool>,
+}
+
+/// The value of an attribute before nog changed it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Attribute {
+    Style(i32),
+    ExStyle(i32),
+    Opacity(u8),
+    Topmost(bool),
+}
+
+impl JournalEntry {
+    fn to_json(&self) -> Value {
+        let rect = self.original_rect;
+
+        json!({
+            "window_id": self.window_id,
+            "process_name": self.process_name,
+            "original_rect": [rect.left, rect.top, rect.right, rect.bottom],
+            "maximized": self.maximized,
+            "hidden": self.hidden,
+            "original_style": self.original_style,
+            "original_ex_style": self.original_ex_style,
+            "original_opacity": self.original_opacity,
+            "was_topmost": self.was_topmost,
+        })
+    }
+
+    fn from_json(value: &Value) -> Option<Self> {
+        let rect = value["original_rect"]
+            .as_array()?
+            .iter()
+            .map(|x| x.as_i64().map(|x| x as i32))
+            .collect::<Option<Vec<i32>>>()?;
+
+        if rect.len() != 4 {
+            return None;
+        }
+
+        Some(Self {
+            window_id: value["window_id"].as_i64()? as i32,
+            process_name: value["process_name"].as_str()?.into(),
+            original_rect: Rectangle {
+                left: rect[0],
+                top: rect[1],
+                right: rect[2],
+                bottom: rect[3],
+            },
+            maximized: value["maximized"].as_bool()?,
+            hidden: value["hidden"].as_bool()?,
+            original_style: value["original_style"].as_i64().map(|x| x as i32),
+            original_ex_style: value["original_ex_style"].as_i64().map(|x| x as i32),
+            original_opacity: value["original_opacity"].as_u64().map(|x| x as u8),
+            was_topmost: value["was_topmost"].as_bool(),
+        })
+    }
+
+    /// Reverts every change that nog made to the window.
+    pub fn restore(&self, window: &mut NativeWindow) -> SystemResult {
+        if let Some(opacity) = self.original_opacity {
+            window.set_opacity(opacity)?;
+        }
+
+        if let Some(ex_style) = self.original_ex_style {
+            window.set_ex_style(unsafe { GwlExStyle::from_bits_unchecked(ex_style) })?;
+        }
+
+        if let Some(style) = self.original_style {
+            window.original_style = unsafe { GwlStyle::from_bits_unchecked(style) };
+            window.reset_style();
+            window.update_style()?;
+        }
+
+        window.set_window_pos(self.original_rect, None, None)?;
+
+        if let Some(topmost) = self.was_topmost {
+            window.set_topmost(topmost)?;
+        }
+
+        if self.maximized {
+            window.maximize()?;
+        }
+
+        if self.hidden {
+            window.show();
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Journal {
+    pub entries: HashMap<i32, JournalEntry>,
+}
+
+impl Journal {
+    fn get_path() -> PathBuf {
+        #[allow(unused_mut)]
+        let mut path: PathBuf = ["./log"].iter().collect();
+        #[cfg(not(debug_assertions))]
+        {
+            path = dirs::config_dir().expect("Failed to get config directory");
+
+            path.push("nog");
+        }
+
+        path.push("journal.json");
+        path
+    }
+
+    pub fn to_json(&self) -> Value {
+        Value::Array(self.entries.values().map(|e| e.to_json()).collect())
+    }
+
+    pub fn from_json(value: &Value) -> Self {
+        let entries = value
+            .as_array()
+            .map(|entries| {
+                entries
+                    .iter()
+                    .filter_map(JournalEntry::from_json)
+                    .map(|e| (e.window_id, e))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self { entries }
+    }
+
+    pub fn load() -> Self {
+        fs::read_to_string(Journal::get_path())
+            .ok()
+            .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+            .map(|value| Journal::from_json(&value))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = fs::write(Journal::get_path(), self.to_json().to_string()) {
+            error!("Error storing the window journal {:?}", e);
+        }
+    }
+}
+
+lazy_static! {
+    static ref JOURNAL: Mutex<Journal> = Mutex::new(Journal::load());
+}
+
+/// Remembers the original state of the window. Has to be called after the window got initialized.
+pub fn record(window: &NativeWindow) {
+    let mut journal = JOURNAL.lock();
+    let window_id: i32 = window.id.into();
+
+    // windows that got destroyed in the meantime don't need to be restored anymore
+    journal
+        .entries
+        .retain(|id, _| NativeWindow::from(WindowId::from(*id)).is_window());
+
+    journal.entries.insert(
+        window_id,
+        JournalEntry {
+            window_id,
+            process_name: window.get_process_name(),
+            original_rect: window.original_rect,
+            maximized: window.maximized,
+            hidden: window.is_hidden(),
+            original_style: None,
+            original_ex_style: None,
+            original_opacity: None,
+            was_topmost: None,
+        },
+    );
+
+    journal.save();
+}
+
+/// Remembers the value of the attribute, unless nog already changed it before. Windows that nog
+/// didn't initialize are ignored.
+pub fn remember(window_id: WindowId, attribute: Attribute) {
+    let mut journal = JOURNAL.lock();
+    let window_id: i32 = window_id.into();
+
+    let entry = match journal.entries.get_mut(&window_id) {
+        Some(entry) => entry,
+        None => return,
+    };
+
+    let changed = match attribute {
+        Attribute::Style(style) => set_once(&mut entry.original_style, style),
+        Attribute::ExStyle(ex_style) => set_once(&mut entry.original_ex_style, ex_style),
+        Attribute::Opacity(opacity) => set_once(&mut entry.original_opacity, opacity),
+        Attribute::Topmost(topmost) => set_once(&mut entry.was_topmost, topmost),
+    };
+
+    if changed {
+        journal.save();
+    }
+}
+
+fn set_once<T>(slot: &mut Option<T>, value: T) -> bool {
+    if slot.is_none() {
+        *slot = Some(value);
+        true
+    } else {
+        false
+    }
+}
+
+/// Removes the entry of the window and returns it.
+pub fn take(window_id: WindowId) -> Option<JournalEntry> {
+    let mut journal = JOURNAL.lock();
+    let window_id: i32 = window_id.into();
+    let entry = journal.entries.remove(&window_id);
+
+    if entry.is_some() {
+        journal.save();
+    }
+
+    entry
+}
+
+pub fn set_hidden(window_id: WindowId, hidden: bool) {
+    let mut journal = JOURNAL.lock();
+    let window_id: i32 = window_id.into();
+
+    if let Some(entry) = journal.entries.get_mut(&window_id) {
+        if entry.hidden != hidden {
+            entry.hidden = hidden;
+            journal.save();
+        }
+    }
+}
+
+/// Empties the journal. This gets called once nog restored every window on exit.
+pub fn clear() {
+    let mut journal = JOURNAL.lock();
+
+    journal.entries.clear();
+    journal.save();
+}
+
+/// Restores the windows that a previous run didn't restore, because it didn't exit cleanly.
+pub fn restore() {
+    let entries = std::mem::take(&mut JOURNAL.lock().entries);
+
+    if entries.is_empty() {
+        return;
+    }
+
+    info!("Restoring {} windows of a previous run", entries.len());
+
+    for entry in entries.values() {
+        let mut window = NativeWindow::from(WindowId::from(entry.window_id));
+
+        if !window.is_window() || window.get_process_name() != entry.process_name {
+            continue;
+        }
+
+        if let Err(e) = entry.restore(&mut window) {
+            error!("Failed to restore window {} {:?}", entry.window_id, e);
+        }
+    }
+
+    clear();
+}
+
+/// Restores the window using its entry. A window without an entry gets its title bar back and
+/// becomes visible and opaque again, which repairs windows that were left behind by an older run.
+pub fn repair(window_id: WindowId) -> SystemResult {
+    let mut window = NativeWindow::from(window_id);
+
+    if let Some(entry) = take(window_id) {
+        return entry.restore(&mut window);
+    }
+
+    window.style = window.get_style()?;
+    window
+        .style
+        .insert(GwlStyle::CAPTION | GwlStyle::THICKFRAME);
+    window.update_style()?;
+
+    if window.get_ex_style()?.contains(GwlExStyle::LAYERED) {
+        window.set_opacity(255)?;
+    }
+
+    window.show();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_roundtrip() {
+        let mut journal = Journal::default();
+
+        journal.entries.insert(
+            42,
+            JournalEntry {
+                window_id: 42,
+                process_name: "notepad.exe".into(),
+                original_rect: Rectangle {
+                    left: 1,
+                    top: 2,
+                    right: 3,
+                    bottom: 4,
+                },
+                maximized: true,
+                hidden: false,
+                original_style: Some(-1),
+                original_ex_style: None,
+                original_opacity: Some(200),
+                was_topmost: Some(false),
+            },
+        );
+
+        let restored = Journal::from_json(&journal.to_json());
+        let entry = &restored.entries[&42];
+
+        assert_eq!(entry.process_name, "notepad.exe");
+        assert_eq!(entry.original_style, Some(-1));
+        assert_eq!(entry.original_ex_style, None);
+        assert_eq!(entry.original_opacity, Some(200));
+        assert_eq!(entry.was_topmost, Some(false));
+        assert_eq!(
+            (entry.original_rect.left, entry.original_rect.bottom),
+            (1, 4)
+        );
+        assert!(entry.maximized && !entry.hidden);
+    }
+}
@@ -1,14 +1,22 @@
-use crate::{config::Config, event::Event, popup::Popup, system, system::api, AppState};
+use crate::{
+    config::{rule::Rule, Config},
+    event::{Event, EventSender},
+    popup::Popup,
+    system,
+    system::{api, keyboard_hook, KeyboardHook},
+    AppState,
+};
 use key::Key;
 use keybinding::Keybinding;
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use modifier::Modifier;
 use num_traits::FromPrimitive;
 use parking_lot::Mutex;
 use std::collections::HashMap;
 use std::{
     fmt::Debug,
-    sync::atomic::{AtomicBool, Ordering},
+    str::FromStr,
+    sync::atomic::{AtomicBool, AtomicI32, Ordering},
     sync::mpsc::channel,
     sync::mpsc::Receiver,
     sync::mpsc::Sender,
@@ -20,6 +28,8 @@ use std::{
 pub mod key;
 pub mod keybinding;
 pub mod modifier;
+pub mod table;
+pub mod watchdog;
 
 pub type Mode = Option<String>;
 
@@ -32,6 +42,68 @@ pub enum ChanMessage {
     UnregisterKeybindings,
     ChangeMode(Mode),
     ModeCbExecuted,
+    /// Handles the key combo as if it got pressed
+    Trigger(Key, Modifier),
+    /// Unregisters every keybinding except the ones that resume them again
+    Suspend,
+    Resume,
+    OverrideWinShortcuts(bool),
+    PassThrough(Vec<(Key, Modifier)>),
+    /// Another workspace got focused, which changes the workspace specific keybindings
+    ChangeWorkspace,
+}
+
+/// The action that suspends and resumes the keybindings. Its keybindings stay registered while
+/// the other ones are suspended.
+pub const TOGGLE_ACTION: &'static str = "toggle_keybindings";
+
+/// Returns the key combos that at least one of the rules lets through to its windows
+fn pass_through_combos(rules: &[Rule]) -> Vec<(Key, Modifier)> {
+    let mut combos: Vec<(Key, Modifier)> = Vec::new();
+
+    for combo in rules.iter().flat_map(|rule| rule.pass_through.iter()) {
+        if !combos.contains(combo) {
+            combos.push(*combo);
+        }
+    }
+
+    combos
+}
+
+/// Adds the keybinding to the list, replacing the one with the same key combo
+fn push_keybinding(kbs: &mut Vec<Keybinding>, kb: Keybinding) {
+    match kbs
+        .iter_mut()
+        .find(|x| x.key == kb.key && x.modifier == kb.modifier)
+    {
+        Some(existing) => *existing = kb,
+        None => kbs.push(kb),
+    }
+}
+
+/// Returns the keybindings of the list that are active on the workspace, where a keybinding that
+/// is restricted to the workspace replaces the one with the same key combo that isn't.
+fn scoped_keybindings<'a>(
+    kbs: impl Iterator<Item = &'a Keybinding>,
+    workspace_id: i32,
+) -> Vec<Keybinding> {
+    let (scoped, unscoped): (Vec<&Keybinding>, Vec<&Keybinding>) = kbs
+        .filter(|kb| kb.is_active_on(workspace_id))
+        .partition(|kb| kb.is_scoped());
+    let mut result = Vec::new();
+
+    for kb in unscoped.into_iter().chain(scoped) {
+        push_keybinding(&mut result, kb.clone());
+    }
+
+    result
+}
+
+fn is_toggle(kb: &Keybinding) -> bool {
+    kb.invocation
+        .as_ref()
+        .map(|invocation| invocation.name == TOGGLE_ACTION)
+        .unwrap_or(false)
 }
 
 struct KbManagerInner {
@@ -42,8 +114,22 @@ struct KbManagerInner {
     pub mode_handlers: HashMap<String, usize>,
     pub keybindings: Vec<Keybinding>,
     allow_right_alt: bool,
+    /// Whether keybindings with the windows key get intercepted by the keyboard hook instead of
+    /// being registered as hotkeys
+    override_win_shortcuts: bool,
+    /// The key combos that get intercepted by the keyboard hook, so that it can let them through
+    /// when a window that matches one of the pass-through rules is focused
+    pass_through: Vec<(Key, Modifier)>,
+    suspended: Arc<AtomicBool>,
+    keyboard_hook: KeyboardHook,
     mode_keybindings: Mutex<HashMap<String, Vec<Keybinding>>>,
     mode: Mutex<Mode>,
+    /// The workspace that is focused according to the app state
+    focused_workspace: Arc<AtomicI32>,
+    /// The workspace the registered keybindings belong to
+    workspace: i32,
+    /// Whether the keybindings of the work mode are registered
+    work_mode: bool,
 }
 
 impl KbManagerInner {
@@ -51,6 +137,10 @@ impl KbManagerInner {
         kbs: Vec<Keybinding>,
         handlers: HashMap<String, usize>,
         allow_right_alt: bool,
+        override_win_shortcuts: bool,
+        pass_through: Vec<(Key, Modifier)>,
+        suspended: Arc<AtomicBool>,
+        focused_workspace: Arc<AtomicI32>,
     ) -> Self {
         Self {
             running: AtomicBool::new(false),
@@ -60,11 +150,39 @@ impl KbManagerInner {
             keybindings: kbs,
             mode_keybindings: Mutex::new(HashMap::new()),
             allow_right_alt: allow_right_alt,
+            override_win_shortcuts,
+            pass_through,
+            suspended,
+            keyboard_hook: KeyboardHook::default(),
+            workspace: focused_workspace.load(Ordering::SeqCst),
+            focused_workspace,
+            work_mode: false,
+        }
+    }
+
+    fn is_intercepted(&self, kb: &Keybinding) -> bool {
+        (self.override_win_shortcuts && kb.modifier.contains(Modifier::WIN))
+            || self.pass_through.contains(&(kb.key, kb.modifier))
+    }
+
+    fn needs_keyboard_hook(&self) -> bool {
+        self.override_win_shortcuts || !self.pass_through.is_empty()
+    }
+
+    fn update_keyboard_hook(&self, sender: &Sender<ChanMessage>) {
+        if self.needs_keyboard_hook() {
+            self.keyboard_hook.start(sender.clone());
+        } else {
+            self.keyboard_hook.stop();
         }
     }
 
     pub fn unregister_kb(&self, kb: &Keybinding) {
         info!("Unregistering {:?}", kb);
+        if self.is_intercepted(kb) {
+            keyboard_hook::release(kb.key, kb.modifier);
+            return;
+        }
         api::unregister_keybinding(kb).map_err(|err| {
             error!("WINAPI {:?}", err);
         });
@@ -77,7 +195,18 @@ impl KbManagerInner {
     }
 
     pub fn register_kb(&self, kb: &Keybinding) -> Result<(), String> {
+        if self.suspended.load(Ordering::SeqCst) && !is_toggle(kb) {
+            debug!(
+                "Not registering {:?}, because keybindings are suspended",
+                kb
+            );
+            return Ok(());
+        }
         info!("Registering {:?}", kb);
+        if self.is_intercepted(kb) {
+            keyboard_hook::intercept(kb.key, kb.modifier);
+            return Ok(());
+        }
         api::register_keybinding(kb).map_err(|err| {
             let msg = KbManager::make_keybinding_error(&kb);
             error!("{}", &msg);
@@ -99,23 +228,69 @@ impl KbManagerInner {
         }
     }
 
+    /// Returns the keybindings that are currently registered, ignoring whether they are suspended.
+    /// Every key combo appears at most once, the keybindings of the mode replacing the global
+    /// ones.
+    pub fn active_keybindings(&self, work_mode: bool) -> Vec<Keybinding> {
+        let mode = self.mode.lock();
+        let mut kbs = scoped_keybindings(
+            self.keybindings
+                .iter()
+                .filter(|kb| kb.always_active || (work_mode && mode.is_none())),
+            self.workspace,
+        );
+
+        if let Some(mode_kbs) = mode.as_ref().and_then(|mode| {
+            self.mode_keybindings
+                .lock()
+                .get(mode)
+                .map(|kbs| scoped_keybindings(kbs.iter(), self.workspace))
+        }) {
+            for kb in mode_kbs {
+                push_keybinding(&mut kbs, kb);
+            }
+        }
+
+        kbs
+    }
+
+    /// Unregisters the keybindings that aren't active anymore and registers the new ones. A key
+    /// combo that stays active keeps its registration, even if another keybinding handles it now.
+    fn switch_keybindings(
+        &self,
+        previous: &[Keybinding],
+        next: &[Keybinding],
+        state_arc: Arc<Mutex<AppState>>,
+    ) {
+        let contains = |kbs: &[Keybinding], kb: &Keybinding| {
+            kbs.iter()
+                .any(|x| x.key == kb.key && x.modifier == kb.modifier)
+        };
+
+        for kb in previous.iter().filter(|kb| !contains(next, kb)) {
+            self.unregister_kb(kb);
+        }
+
+        self.register_all(
+            &next.iter().filter(|kb| !contains(previous, kb)).collect(),
+            state_arc,
+        );
+    }
+
     pub fn get_keybinding(&self, key: Key, modifier: Modifier) -> Option<Keybinding> {
         let mode = self.mode.lock();
-        match mode.as_ref() {
+        let kbs = match mode.as_ref() {
             Some(mode) => self
                 .mode_keybindings
                 .lock()
                 .get(mode)
-                .unwrap()
-                .iter()
-                .find(|kb| kb.key == key && kb.modifier == modifier)
-                .map(|kb| kb.clone()),
-            None => self
-                .keybindings
-                .iter()
-                .find(|kb| kb.key == key && kb.modifier == modifier)
-                .map(|kb| kb.clone()),
-        }
+                .map(|kbs| scoped_keybindings(kbs.iter(), self.workspace))
+                .unwrap_or_default(),
+            None => scoped_keybindings(self.keybindings.iter(), self.workspace),
+        };
+
+        kbs.into_iter()
+            .find(|kb| kb.key == key && kb.modifier == modifier)
     }
 }
 
@@ -124,6 +299,8 @@ pub struct KbManager {
     inner: Arc<Mutex<KbManagerInner>>,
     pub sender: Sender<ChanMessage>,
     receiver: Arc<Mutex<Receiver<ChanMessage>>>,
+    suspended: Arc<AtomicBool>,
+    focused_workspace: Arc<AtomicI32>,
 }
 
 impl Debug for KbManager {
@@ -137,16 +314,27 @@ impl KbManager {
         kbs: Vec<Keybinding>,
         handlers: HashMap<String, usize>,
         allow_right_alt: bool,
+        override_win_shortcuts: bool,
+        rules: &[Rule],
     ) -> Self {
         let (sender, receiver) = channel();
+        let suspended = Arc::new(AtomicBool::new(false));
+        let focused_workspace = Arc::new(AtomicI32::new(1));
+        keyboard_hook::set_pass_through(rules.to_vec());
         Self {
             inner: Arc::new(Mutex::new(KbManagerInner::new(
                 kbs,
                 handlers,
                 allow_right_alt,
+                override_win_shortcuts,
+                pass_through_combos(rules),
+                suspended.clone(),
+                focused_workspace.clone(),
             ))),
             sender,
             receiver: Arc::new(Mutex::new(receiver)),
+            suspended,
+            focused_workspace,
         }
     }
     fn change_mode(&mut self, mode: Mode) {
@@ -155,7 +343,22 @@ impl KbManager {
             .expect("Failed to change mode of kb manager");
     }
     pub fn update_configuration(&self, config: &Config) {
-        self.inner.lock().allow_right_alt = config.allow_right_alt;
+        let mut inner = self.inner.lock();
+        inner.allow_right_alt = config.allow_right_alt;
+        if inner.override_win_shortcuts != config.override_win_shortcuts {
+            self.sender
+                .send(ChanMessage::OverrideWinShortcuts(
+                    config.override_win_shortcuts,
+                ))
+                .expect("Failed to send OverrideWinShortcuts");
+        }
+        keyboard_hook::set_pass_through(config.rules.clone());
+        let combos = pass_through_combos(&config.rules);
+        if inner.pass_through != combos {
+            self.sender
+                .send(ChanMessage::PassThrough(combos))
+                .expect("Failed to send PassThrough");
+        }
     }
     pub fn leave_work_mode(&self) {
         self.sender
@@ -188,6 +391,47 @@ impl KbManager {
     pub fn leave_mode(&mut self) {
         self.change_mode(None);
     }
+    /// Activates the keybindings that are restricted to the workspace. Nothing happens if it
+    /// already is the focused one.
+    pub fn set_workspace(&self, id: i32) {
+        if self.focused_workspace.swap(id, Ordering::SeqCst) != id {
+            self.sender
+                .send(ChanMessage::ChangeWorkspace)
+                .expect("Failed to send ChangeWorkspace");
+        }
+    }
+    /// Dispatches the keybinding of the key combo (e.g. `Alt+H`) in the active mode without
+    /// pressing any keys. This happens after every previously requested mode change.
+    pub fn trigger(&self, key_combo: &str) -> Result<(), String> {
+        let kb = Keybinding::from_str(key_combo)
+            .map_err(|e| format!("Invalid key combo '{}': {}", key_combo, e))?;
+
+        self.sender
+            .send(ChanMessage::Trigger(kb.key, kb.modifier))
+            .map_err(|e| e.to_string())
+    }
+    /// Unregisters every keybinding, except the ones that run the `toggle_keybindings` action, so
+    /// that games and virtual machines receive all of the key presses.
+    pub fn suspend(&self) {
+        self.sender
+            .send(ChanMessage::Suspend)
+            .expect("Failed to send Suspend");
+    }
+    pub fn resume(&self) {
+        self.sender
+            .send(ChanMessage::Resume)
+            .expect("Failed to send Resume");
+    }
+    pub fn toggle_suspend(&self) {
+        if self.is_suspended() {
+            self.resume();
+        } else {
+            self.suspend();
+        }
+    }
+    pub fn is_suspended(&self) -> bool {
+        self.suspended.load(Ordering::SeqCst)
+    }
     pub fn add_mode_keybinding(&mut self, kb: Keybinding) {
         if let Some(mode) = self.get_mode() {
             self.inner
@@ -199,6 +443,19 @@ impl KbManager {
                 .push(kb);
         }
     }
+    /// Returns the global keybindings followed by the keybindings of every mode that has been
+    /// entered at least once. Mode keybindings only exist after the mode handler was called.
+    pub fn get_keybindings(&self) -> Vec<Keybinding> {
+        let inner = self.inner.lock();
+        let mode_kbs = inner.mode_keybindings.lock();
+
+        inner
+            .keybindings
+            .iter()
+            .chain(mode_kbs.values().flatten())
+            .cloned()
+            .collect()
+    }
     pub fn is_running(&self) -> bool {
         self.inner.lock().running.load(Ordering::SeqCst)
     }
@@ -218,12 +475,17 @@ impl KbManager {
     pub fn start(&self, state_arc: Arc<Mutex<AppState>>) {
         let inner = self.inner.clone();
         let receiver = self.receiver.clone();
+        let sender = self.sender.clone();
         let state = state_arc.clone();
 
         thread::spawn(move || {
             let receiver = receiver.lock();
+            let events = state.lock().event_channel.sender.clone();
             {
                 let inner = inner.lock();
+                if inner.needs_keyboard_hook() {
+                    inner.keyboard_hook.start(sender.clone());
+                }
                 inner.register_all(
                     &inner
                         .keybindings
@@ -243,56 +505,119 @@ impl KbManager {
                             debug!("Stopping KbManager");
                             let inner = inner.lock();
                             inner.unregister_all();
+                            inner.keyboard_hook.stop();
                             inner.running.store(false, Ordering::SeqCst);
                             break;
                         }
                         ChanMessage::LeaveWorkMode => {
+                            let mut inner = inner.lock();
+                            inner.work_mode = false;
+                            let previous = inner.active_keybindings(true);
+                            let next = inner.active_keybindings(false);
+                            inner.switch_keybindings(&previous, &next, state.clone());
+                        }
+                        ChanMessage::EnterWorkMode => {
+                            let mut inner = inner.lock();
+                            inner.work_mode = true;
+                            let previous = inner.active_keybindings(false);
+                            let next = inner.active_keybindings(true);
+                            inner.switch_keybindings(&previous, &next, state.clone());
+                        }
+                        ChanMessage::UnregisterKeybindings => {
                             let inner = inner.lock();
-                            for kb in inner.keybindings.iter().filter(|kb| !kb.always_active) {
-                                inner.unregister_kb(kb);
+                            let work_mode = inner.work_mode;
+                            for kb in inner.active_keybindings(work_mode) {
+                                inner.unregister_kb(&kb);
                             }
                         }
-                        ChanMessage::EnterWorkMode => {
+                        ChanMessage::RegisterKeybindings => {
                             let inner = inner.lock();
-                            for kb in inner.keybindings.iter().filter(|kb| !kb.always_active) {
-                                inner.register_kb(kb);
+                            let work_mode = inner.work_mode;
+                            let kbs = inner.active_keybindings(work_mode);
+                            inner.register_all(&kbs.iter().collect(), state.clone());
+                        }
+                        ChanMessage::ChangeWorkspace => {
+                            let mut inner = inner.lock();
+                            let work_mode = inner.work_mode;
+                            let previous = inner.active_keybindings(work_mode);
+                            inner.workspace = inner.focused_workspace.load(Ordering::SeqCst);
+                            let next = inner.active_keybindings(work_mode);
+                            inner.switch_keybindings(&previous, &next, state.clone());
+                        }
+                        ChanMessage::Trigger(key, modifier) => {
+                            let inner = inner.lock();
+                            let kb = inner.get_keybinding(key, modifier);
+                            let work_mode = inner.work_mode;
+                            drop(inner);
+
+                            match kb {
+                                Some(kb) => dispatch(kb, work_mode, &events),
+                                None => {
+                                    info!("There is no keybinding for {:?} {:?}", modifier, key)
+                                }
                             }
                         }
-                        ChanMessage::UnregisterKeybindings => {
+                        ChanMessage::Suspend => {
                             let inner = inner.lock();
-                            if state.lock().work_mode {
-                                inner.unregister_all();
-                            } else {
-                                for kb in inner.keybindings.iter().filter(|kb| kb.always_active) {
-                                    inner.unregister_kb(kb);
+                            if !inner.suspended.swap(true, Ordering::SeqCst) {
+                                info!("Suspending keybindings");
+                                for kb in inner.active_keybindings(inner.work_mode) {
+                                    inner.unregister_kb(&kb);
                                 }
+                                inner.register_all(
+                                    &inner
+                                        .keybindings
+                                        .iter()
+                                        .filter(|kb| is_toggle(kb))
+                                        .collect(),
+                                    state.clone(),
+                                );
+                                notify_bar(&state);
                             }
-                            drop(inner);
                         }
-                        ChanMessage::RegisterKeybindings => {
+                        ChanMessage::Resume => {
                             let inner = inner.lock();
-                            let work_mode = state.lock().work_mode;
-                            inner.register_all(
-                                &inner
-                                    .keybindings
-                                    .iter()
-                                    .filter(|kb| kb.always_active || work_mode)
-                                    .collect(),
-                                state.clone(),
-                            );
+                            if inner.suspended.swap(false, Ordering::SeqCst) {
+                                info!("Resuming keybindings");
+                                for kb in inner.keybindings.iter().filter(|kb| is_toggle(kb)) {
+                                    inner.unregister_kb(kb);
+                                }
+                                let kbs = inner.active_keybindings(inner.work_mode);
+                                inner.register_all(&kbs.iter().collect(), state.clone());
+                                notify_bar(&state);
+                            }
+                        }
+                        ChanMessage::OverrideWinShortcuts(enabled) => {
+                            let mut inner = inner.lock();
+                            let kbs = inner.active_keybindings(inner.work_mode);
+                            for kb in kbs.iter() {
+                                inner.unregister_kb(kb);
+                            }
+                            inner.override_win_shortcuts = enabled;
+                            inner.update_keyboard_hook(&sender);
+                            inner.register_all(&kbs.iter().collect(), state.clone());
+                        }
+                        ChanMessage::PassThrough(combos) => {
+                            let mut inner = inner.lock();
+                            let kbs = inner.active_keybindings(inner.work_mode);
+                            for kb in kbs.iter() {
+                                inner.unregister_kb(kb);
+                            }
+                            inner.pass_through = combos;
+                            inner.update_keyboard_hook(&sender);
+                            inner.register_all(&kbs.iter().collect(), state.clone());
                         }
                         ChanMessage::ChangeMode(new_mode) => {
                             let mut inner_g = inner.lock();
-                            // Unregister all none global keybindings to ensure a clean state
-                            for kb in inner_g.keybindings.iter().filter(|kb| !kb.always_active) {
-                                inner_g.unregister_kb(kb);
-                            }
+                            let work_mode = inner_g.work_mode;
+                            let previous = inner_g.active_keybindings(work_mode);
+
+                            *inner_g.mode.lock() = new_mode.clone();
 
                             if let Some(mode) = new_mode.as_ref() {
-                                *inner_g.mode.lock() = new_mode.clone();
                                 if !inner_g.mode_keybindings.lock().contains_key(mode) {
                                     if let Some(id) = inner_g.mode_handlers.get(mode).map(|x| *x) {
-                                        let sender = state.lock().event_channel.sender.clone();
+                                        let sender = events.clone();
                                         inner_g
                                             .mode_keybindings
                                             .lock()
@@ -310,50 +635,23 @@ impl KbManager {
                                         inner_g = inner.lock();
                                     }
                                 }
-
-                                let kbs_lock = inner_g.mode_keybindings.lock();
-                                let kbs = kbs_lock.get(mode).unwrap();
-
-                                inner_g.register_all(&kbs.iter().collect(), state_arc.clone());
-                            } else {
-                                let mut mode_lock = inner_g.mode.lock();
-                                let kbs_lock = inner_g.mode_keybindings.lock();
-                                let kbs = kbs_lock.get(mode_lock.as_ref().unwrap()).unwrap();
-
-                                for kb in kbs.iter() {
-                                    api::unregister_keybinding(kb);
-                                }
-
-                                *mode_lock = new_mode.clone();
-
-                                inner_g.register_all(
-                                    &inner_g
-                                        .keybindings
-                                        .iter()
-                                        .filter(|kb| !kb.always_active)
-                                        .collect(),
-                                    state_arc.clone(),
-                                );
                             }
+
+                            // the workspace may have changed while the mode handler was running,
+                            // so both changes get applied at once
+                            inner_g.workspace = inner_g.focused_workspace.load(Ordering::SeqCst);
+                            let next = inner_g.active_keybindings(work_mode);
+                            inner_g.switch_keybindings(&previous, &next, state_arc.clone());
                         }
                     };
                 }
 
                 let inner_lock = inner.lock();
                 let kb = do_loop(&inner_lock);
+                let work_mode = inner_lock.work_mode;
                 drop(inner_lock);
                 if let Some(kb) = kb {
-                    // if we fail to grab state here, the key event will just need to be ignored
-                    // to avoid blocking other threads that might be trying to change state.
-                    if let Some(state) = state.try_lock_for(Duration::from_millis(100)) {
-                        let work_mode = state.work_mode;
-                        if work_mode || kb.always_active {
-                            let sender = state.event_channel.sender.clone();
-                            sender
-                                .send(Event::Keybinding(kb))
-                                .expect("Failed to send key event");
-                        }
-                    }
+                    dispatch(kb, work_mode, &events);
                 }
 
                 thread::sleep(Duration::from_millis(10));
@@ -371,6 +669,43 @@ impl KbManager {
     }
 }
 
+fn notify_bar(state_arc: &Arc<Mutex<AppState>>) {
+    state_arc
+        .lock()
+        .event_channel
+        .sender
+        .send(Event::RedrawAppBar)
+        .expect("Failed to send RedrawAppBar event");
+}
+
+/// Queues the keybinding for the event loop, which runs its callback. This never waits for the
+/// state or the event loop, so that the next key press gets handled right away.
+fn dispatch(kb: Keybinding, work_mode: bool, events: &EventSender) {
+    if !work_mode && !kb.always_active {
+        return;
+    }
+
+    if kb.invocation.is_none() && watchdog::is_disabled(kb.callback_id) {
+        warn!(
+            "Ignoring {}, because its callback took too long",
+            kb.key_combo()
+        );
+        return;
+    }
+
+    if !watchdog::enqueue() {
+        warn!(
+            "Ignoring {}, because too many keybindings are waiting",
+            kb.key_combo()
+        );
+        return;
+    }
+
+    events
+        .send(Event::Keybinding(kb))
+        .expect("Failed to send key event");
+}
+
 #[cfg(not(any(target_os = "windows", target_os = "linux")))]
 fn do_loop(inner: &Arc<KbManagerInner>) -> Option<Keybinding> {
     todo!();
@@ -404,3 +739,35 @@ fn do_loop(inner: &KbManagerInner) -> Option<Keybinding> {
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kb(combo: &str, callback_id: usize, workspaces: Vec<i32>) -> Keybinding {
+        let mut kb = Keybinding::from_str(combo).unwrap();
+        kb.callback_id = callback_id;
+        kb.workspaces = workspaces;
+        kb
+    }
+
+    #[test]
+    fn workspace_keybindings_replace_global_ones() {
+        let kbs = vec![
+            kb("F5", 1, vec![]),
+            kb("F5", 2, vec![3]),
+            kb("F6", 3, vec![3, 4]),
+            kb("Alt+H", 4, vec![]),
+        ];
+        let active = |workspace_id| {
+            scoped_keybindings(kbs.iter(), workspace_id)
+                .iter()
+                .map(|kb| kb.callback_id)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(active(1), vec![1, 4]);
+        assert_eq!(active(3), vec![2, 4, 3]);
+        assert_eq!(active(4), vec![1, 4, 3]);
+    }
+}
@@ -1,4 +1,5 @@
 use super::{key::Key, modifier::Modifier};
+use crate::actions::Invocation;
 use std::{fmt::Debug, str::FromStr};
 
 #[derive(Clone)]
@@ -6,17 +7,58 @@ pub struct Keybinding {
     /// This variable defines whether the keybinding should be active when outside of the work mode
     /// or not
     pub always_active: bool,
-    /// This is the id of the callback in the global callbacks store
+    /// This is the id of the callback in the global callbacks store. It is only used if the
+    /// keybinding doesn't run a named action.
     pub callback_id: usize,
+    /// The named action that runs when the keybinding gets pressed
+    pub invocation: Option<Invocation>,
     pub mode: Option<String>,
+    /// The ids of the workspaces the keybinding is restricted to. It is active on every workspace
+    /// if this is empty.
+    pub workspaces: Vec<i32>,
     pub key: Key,
     pub modifier: Modifier,
+    /// A human readable description of what the keybinding does
+    pub description: Option<String>,
+    /// The nogscript source that gets executed by the callback. This is only known for
+    /// keybindings that got imported from a keybinding table.
+    pub action: Option<String>,
 }
 
 impl Keybinding {
     pub fn get_id(&self) -> i32 {
         (self.key as u32 + self.modifier.bits() * 1000) as i32
     }
+
+    pub fn is_scoped(&self) -> bool {
+        !self.workspaces.is_empty()
+    }
+
+    /// Whether the keybinding can be used while the workspace is focused
+    pub fn is_active_on(&self, workspace_id: i32) -> bool {
+        !self.is_scoped() || self.workspaces.contains(&workspace_id)
+    }
+
+    /// Returns the key combination in the same format that `from_str` accepts (e.g. `Alt+Shift+H`).
+    pub fn key_combo(&self) -> String {
+        let mut parts = Vec::new();
+
+        if self.modifier.contains(Modifier::ALT) {
+            parts.push("Alt".to_string());
+        }
+        if self.modifier.contains(Modifier::CONTROL) {
+            parts.push("Control".to_string());
+        }
+        if self.modifier.contains(Modifier::SHIFT) {
+            parts.push("Shift".to_string());
+        }
+        if self.modifier.contains(Modifier::WIN) {
+            parts.push("Win".to_string());
+        }
+
+        parts.push(self.key.to_string());
+        parts.join("+")
+    }
 }
 
 impl FromStr for Keybinding {
@@ -32,6 +74,7 @@ impl FromStr for Keybinding {
                 "Alt" => Modifier::ALT,
                 "Control" => Modifier::CONTROL,
                 "Shift" => Modifier::SHIFT,
+                "Win" => Modifier::WIN,
                 _ => Modifier::default(),
             })
             .fold(Modifier::default(), |mut sum, crr| {
@@ -45,12 +88,21 @@ impl FromStr for Keybinding {
             .ok()
             .ok_or(format!("Invalid key {}", raw_key))?;
 
+        // Windows handles Win+L before any application gets to see it
+        if modifier.contains(Modifier::WIN) && key == Key::L {
+            return Err(format!("{} can't be bound, because Windows reserves Win+L", s).into());
+        }
+
         Ok(Self {
             always_active: false,
             callback_id: 0,
+            invocation: None,
             mode: None,
+            workspaces: Vec::new(),
             modifier,
             key,
+            description: None,
+            action: None,
         })
     }
 }
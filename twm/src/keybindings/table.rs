@@ -0,0 +1,177 @@
+//! A keybinding table is a declarative description of keybindings stored as json.
+//!
+//! ```json
+//! {
+//!   "keybindings": [
+//!     {
+//!       "key": "Alt+H",
+//!       "mode": null,
+//!       "always_active": false,
+//!       "description": "Focus the tile to the left",
+//!       "action": "nog.workspace.focus(\"Left\")"
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! The action is either a named action (e.g. `"focus_left"` or `"change_workspace(2)"`) or
+//! nogscript source that gets executed when the keybinding is pressed. Keybindings that are bound
+//! to a function in the config have no action that could be written down, so they can't be
+//! exported. `to_json` leaves them out and returns their key combos instead.
+use super::keybinding::Keybinding;
+use serde_json::{json, Value};
+use std::{path::Path, str::FromStr};
+
+fn action(kb: &Keybinding) -> Option<String> {
+    kb.invocation
+        .as_ref()
+        .map(|i| i.to_string())
+        .or_else(|| kb.action.clone())
+}
+
+/// Returns the table and the key combos of the keybindings that got left out, because they are
+/// bound to a function.
+pub fn to_json(kbs: &[Keybinding]) -> (Value, Vec<String>) {
+    let mut entries = Vec::new();
+    let mut skipped = Vec::new();
+
+    for kb in kbs {
+        match action(kb) {
+            Some(action) => entries.push(json!({
+                "key": kb.key_combo(),
+                "mode": kb.mode,
+                "always_active": kb.always_active,
+                "description": kb.description,
+                "action": action,
+            })),
+            None => skipped.push(kb.key_combo()),
+        }
+    }
+
+    (json!({ "keybindings": entries }), skipped)
+}
+
+/// Parses a keybinding table. The returned keybindings don't have a callback yet.
+pub fn from_json(value: &Value) -> Result<Vec<Keybinding>, String> {
+    let entries = value["keybindings"]
+        .as_array()
+        .ok_or("Expected the keybinding table to contain a 'keybindings' array")?;
+
+    let mut kbs = Vec::new();
+
+    for entry in entries {
+        let key = entry["key"]
+            .as_str()
+            .ok_or("Expected every keybinding to have a 'key'")?;
+
+        let action = entry["action"]
+            .as_str()
+            .ok_or(format!("Expected the keybinding {} to have an 'action'", key))?
+            .to_string();
+
+        let mut kb = Keybinding::from_str(key).map_err(|e| e.to_string())?;
+
+        kb.mode = entry["mode"].as_str().map(String::from);
+        kb.always_active = entry["always_active"].as_bool().unwrap_or(false);
+        kb.description = entry["description"].as_str().map(String::from);
+        kb.invocation = action.parse().ok();
+        kb.action = Some(action);
+
+        kbs.push(kb);
+    }
+
+    Ok(kbs)
+}
+
+/// Writes the table to the file and returns the key combos of the keybindings that got left out
+/// (see `to_json`).
+pub fn export(kbs: &[Keybinding], path: &Path) -> Result<Vec<String>, String> {
+    let (table, skipped) = to_json(kbs);
+    let content = serde_json::to_string_pretty(&table).map_err(|e| e.to_string())?;
+
+    std::fs::write(path, content).map_err(|e| e.to_string())?;
+
+    Ok(skipped)
+}
+
+pub fn import(path: &Path) -> Result<Vec<Keybinding>, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let value: Value = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+
+    from_json(&value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::Invocation;
+
+    fn roundtrip(kbs: &[Keybinding]) -> Vec<Keybinding> {
+        from_json(&to_json(kbs).0).unwrap()
+    }
+
+    #[test]
+    fn scripted_actions_roundtrip() {
+        let mut kb = Keybinding::from_str("Alt+Shift+H").unwrap();
+        kb.mode = Some("resize".into());
+        kb.description = Some("Grow left".into());
+        kb.action = Some("nog.workspace.resize(\"Left\", 2)".into());
+
+        let kbs = roundtrip(&[kb.clone()]);
+
+        assert_eq!(kbs.len(), 1);
+        assert_eq!(kbs[0].key_combo(), "Alt+Shift+H");
+        assert_eq!(kbs[0].mode, kb.mode);
+        assert_eq!(kbs[0].description, kb.description);
+        assert_eq!(kbs[0].action, kb.action);
+    }
+
+    #[test]
+    fn named_actions_get_recognized() {
+        let mut kb = Keybinding::from_str("Alt+2").unwrap();
+        kb.invocation = Some(Invocation::new("change_workspace", vec![2]));
+
+        let kbs = roundtrip(&[kb.clone()]);
+
+        assert_eq!(kbs[0].invocation, kb.invocation);
+        assert_eq!(kbs[0].action, Some("change_workspace(2)".into()));
+    }
+
+    #[test]
+    fn reports_keybindings_bound_to_a_function() {
+        let mut named = Keybinding::from_str("Alt+2").unwrap();
+        named.invocation = Some(Invocation::new("change_workspace", vec![2]));
+        let mut function = Keybinding::from_str("Alt+H").unwrap();
+        function.callback_id = 3;
+
+        let (table, skipped) = to_json(&[named, function]);
+
+        assert_eq!(skipped, vec!["Alt+H".to_string()]);
+        assert_eq!(from_json(&table).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn rejects_entries_without_action() {
+        let table = json!({ "keybindings": [{ "key": "Alt+H" }] });
+
+        assert!(from_json(&table).is_err());
+    }
+
+    #[test]
+    fn export_and_import_roundtrip() {
+        let path = std::env::temp_dir().join("nog_keybinding_table_roundtrip.json");
+        let mut kb = Keybinding::from_str("Control+Alt+F").unwrap();
+        kb.always_active = true;
+        kb.action = Some("nog.win.toggle_floating()".into());
+
+        assert!(export(&[kb.clone()], &path).unwrap().is_empty());
+
+        let kbs = import(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(kbs.len(), 1);
+        assert_eq!(kbs[0].key_combo(), kb.key_combo());
+        assert_eq!(kbs[0].always_active, kb.always_active);
+        assert_eq!(kbs[0].action, kb.action);
+    }
+}
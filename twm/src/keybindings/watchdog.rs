@@ -0,0 +1,128 @@
+//! Keeps slow callbacks from piling up key presses.
+//!
+//! Keybindings only get queued for the event loop, which runs their callbacks. At most
+//! `MAX_PENDING` of them wait at the same time and every further key press gets dropped until the
+//! event loop catches up. A keybinding whose callback takes longer than `keybinding_timeout`
+//! milliseconds gets disabled for `COOLDOWN`, which includes the time the callback is still
+//! running.
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+/// How many keybindings can wait for the event loop
+pub const MAX_PENDING: usize = 16;
+/// How long a keybinding stays disabled after its callback took too long
+pub const COOLDOWN: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Default)]
+pub struct Watchdog {
+    /// The callback that runs right now, when it started and how long it may take
+    running: Option<(usize, Instant, Option<Duration>)>,
+    /// The callbacks that are disabled and until when
+    disabled: HashMap<usize, Instant>,
+}
+
+impl Watchdog {
+    pub fn start(&mut self, idx: usize, budget: Option<Duration>, now: Instant) {
+        self.running = Some((idx, now, budget));
+    }
+
+    /// Returns how long the callback took if it exceeded its budget, in which case it is disabled
+    /// now.
+    pub fn finish(&mut self, now: Instant) -> Option<(usize, Duration)> {
+        let (idx, started, budget) = self.running.take()?;
+        let elapsed = now.duration_since(started);
+
+        if budget.map_or(false, |budget| elapsed > budget) {
+            self.disabled.insert(idx, now + COOLDOWN);
+            Some((idx, elapsed))
+        } else {
+            None
+        }
+    }
+
+    pub fn is_disabled(&mut self, idx: usize, now: Instant) -> bool {
+        self.disabled.retain(|_, until| *until > now);
+
+        let exceeds_budget = match self.running {
+            Some((running_idx, started, Some(budget))) => {
+                running_idx == idx && now.duration_since(started) > budget
+            }
+            _ => false,
+        };
+
+        exceeds_budget || self.disabled.contains_key(&idx)
+    }
+}
+
+lazy_static! {
+    static ref WATCHDOG: Mutex<Watchdog> = Mutex::new(Watchdog::default());
+}
+
+static PENDING: AtomicUsize = AtomicUsize::new(0);
+
+/// Starts watching the callback. A timeout of zero lets it take as long as it wants.
+pub fn start(idx: usize, timeout: i32) {
+    let budget = Some(timeout)
+        .filter(|x| *x > 0)
+        .map(|x| Duration::from_millis(x as u64));
+
+    WATCHDOG.lock().start(idx, budget, Instant::now());
+}
+
+pub fn finish() -> Option<(usize, Duration)> {
+    WATCHDOG.lock().finish(Instant::now())
+}
+
+pub fn is_disabled(idx: usize) -> bool {
+    WATCHDOG.lock().is_disabled(idx, Instant::now())
+}
+
+/// Reserves a spot in the queue of the event loop. Returns false if the queue is full.
+pub fn enqueue() -> bool {
+    PENDING
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |x| {
+            Some(x + 1).filter(|x| *x <= MAX_PENDING)
+        })
+        .is_ok()
+}
+
+/// Frees the spot of a keybinding that the event loop handled
+pub fn dequeue() {
+    let _ = PENDING.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |x| x.checked_sub(1));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slow_callbacks_get_disabled() {
+        let mut watchdog = Watchdog::default();
+        let now = Instant::now();
+        let budget = Some(Duration::from_millis(100));
+
+        watchdog.start(1, budget, now);
+        assert_eq!(watchdog.finish(now + Duration::from_millis(50)), None);
+        assert!(!watchdog.is_disabled(1, now));
+
+        watchdog.start(1, budget, now);
+        // the callback is disabled while it is still running
+        assert!(!watchdog.is_disabled(1, now + Duration::from_millis(50)));
+        assert!(watchdog.is_disabled(1, now + Duration::from_millis(150)));
+        assert!(!watchdog.is_disabled(2, now + Duration::from_millis(150)));
+
+        let done = now + Duration::from_millis(200);
+        assert_eq!(watchdog.finish(done), Some((1, Duration::from_millis(200))));
+        assert!(watchdog.is_disabled(1, done + Duration::from_secs(1)));
+        assert!(!watchdog.is_disabled(1, done + COOLDOWN));
+
+        // callbacks without a budget can take as long as they want
+        watchdog.start(2, None, now);
+        assert_eq!(watchdog.finish(now + Duration::from_secs(60)), None);
+    }
+}
@@ -0,0 +1,337 @@
+//! A compatibility server that understands the socket messages of komorebi, so that status bars
+//! and scripts written for komorebi can control nog and read its state. It listens on
+//! `127.0.0.1:<komorebi_port>` once the setting is greater than 0.
+//!
+//! The first line of a connection has to be the token of the ipc session (see `ipc`), so that other
+//! users and websites can't control nog. Every following line is a message like
+//! `{"type": "FocusWindow", "content": "Left"}`.
+//! Commands get executed as named actions. `State` and `Query` get answered with a single line of
+//! JSON on the same connection, where the state uses the shape of komorebi's state: monitors
+//! containing workspaces, which contain a container for each window.
+use crate::{
+    actions, display::Display, state_handle::StateHandle, system::NativeWindow,
+    tile_grid::TileGrid, AppState,
+};
+use log::{debug, error, info};
+use serde_json::{json, Value};
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+/// How long a client may take to send the token
+const TOKEN_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, PartialEq)]
+enum Request {
+    Action(String),
+    State,
+    Query(String),
+}
+
+fn direction(content: &Value) -> Result<String, String> {
+    match content.as_str() {
+        Some(dir @ "Left") | Some(dir @ "Right") | Some(dir @ "Up") | Some(dir @ "Down") => {
+            Ok(dir.to_lowercase())
+        }
+        _ => Err(format!("Invalid direction {}", content)),
+    }
+}
+
+/// komorebi counts the workspaces from 0
+fn workspace(content: &Value) -> Result<i64, String> {
+    content
+        .as_i64()
+        .map(|idx| idx + 1)
+        .ok_or(format!("Invalid workspace {}", content))
+}
+
+fn parse(message: &Value) -> Result<Request, String> {
+    let content = &message["content"];
+    let action = |name: String| Ok(Request::Action(name));
+
+    match message["type"].as_str().unwrap_or_default() {
+        "FocusWindow" => action(format!("focus_{}", direction(content)?)),
+        "MoveWindow" => action(format!("swap_{}", direction(content)?)),
+        "FocusWorkspaceNumber" => action(format!("change_workspace({})", workspace(content)?)),
+        "MoveContainerToWorkspaceNumber" | "SendContainerToWorkspaceNumber" => {
+            action(format!("move_to_workspace({})", workspace(content)?))
+        }
+        "FlipLayout" => match content.as_str() {
+            Some("Horizontal") => action("mirror_horizontal".into()),
+            Some("Vertical") => action("mirror_vertical".into()),
+            _ => Err(format!("Invalid axis {}", content)),
+        },
+        "ToggleFloat" => action("toggle_floating".into()),
+        "ToggleMonocle" | "ToggleMaximize" => action("toggle_fullscreen".into()),
+        "Close" => action("close_window".into()),
+        "Minimize" => action("minimize_window".into()),
+        "ReloadConfiguration" => action("reload_config".into()),
+        "Stop" => action("quit".into()),
+        "State" => Ok(Request::State),
+        "Query" => match content.as_str() {
+            Some(query) => Ok(Request::Query(query.into())),
+            None => Err(format!("Invalid query {}", content)),
+        },
+        kind => Err(format!("Unsupported message '{}'", kind)),
+    }
+}
+
+fn ring(elements: Vec<Value>, focused: usize) -> Value {
+    json!({ "elements": elements, "focused": focused })
+}
+
+fn window(window: &NativeWindow) -> Value {
+    let hwnd: i32 = window.id.into();
+
+    json!({
+        "hwnd": hwnd,
+        "title": window.get_title().unwrap_or_default(),
+        "exe": window.get_process_name(),
+        "class": window.get_class_name().unwrap_or_default(),
+    })
+}
+
+fn sorted_grids(display: &Display) -> Vec<&TileGrid> {
+    let mut grids = display.grids.iter().collect::<Vec<_>>();
+    grids.sort_by_key(|g| g.id);
+    grids
+}
+
+fn focused_workspace(display: &Display) -> Option<usize> {
+    sorted_grids(display)
+        .iter()
+        .position(|g| display.focused_grid_id == Some(g.id))
+}
+
+fn focused_container(grid: &TileGrid) -> Option<usize> {
+    let focused = grid.get_focused_window()?;
+    grid.get_window_ids()
+        .iter()
+        .position(|id| *id == focused.id)
+}
+
+fn workspaces(state: &AppState, display: &Display) -> Value {
+    let elements = sorted_grids(display)
+        .iter()
+        .map(|grid| {
+            let containers = grid
+                .get_window_ids()
+                .iter()
+                .filter_map(|id| grid.get_window(*id))
+                .map(|w| json!({ "id": w.id.to_string(), "windows": ring(vec![window(w)], 0) }))
+                .collect();
+            let number = state.workspace_number(grid.id);
+            let name = state
+                .get_workspace_settings(grid.id)
+                .map(|s| s.text.clone())
+                .filter(|t| !t.is_empty())
+                .unwrap_or_else(|| number.to_string());
+
+            json!({
+                "name": name,
+                "containers": ring(containers, focused_container(grid).unwrap_or(0)),
+                "floating_windows": [],
+                "monocle_container": null,
+            })
+        })
+        .collect();
+
+    ring(elements, focused_workspace(display).unwrap_or(0))
+}
+
+fn state_json(state: &AppState) -> Value {
+    let current = state.get_current_display().id;
+    let monitors = state
+        .displays
+        .iter()
+        .enumerate()
+        .map(|(i, display)| {
+            let rect = display.rect;
+
+            json!({
+                "id": display.id.0,
+                "name": format!("DISPLAY{}", i + 1),
+                "size": {
+                    "left": rect.left,
+                    "top": rect.top,
+                    "right": rect.right - rect.left,
+                    "bottom": rect.bottom - rect.top,
+                },
+                "workspaces": workspaces(state, display),
+            })
+        })
+        .collect();
+    let focused = state
+        .displays
+        .iter()
+        .position(|d| d.id == current)
+        .unwrap_or(0);
+
+    json!({
+        "monitors": ring(monitors, focused),
+        "is_paused": !state.work_mode,
+    })
+}
+
+/// Answers the queries for the indices of the focused elements, which komorebi returns as strings
+fn query(state: &AppState, query: &str) -> Result<Value, String> {
+    let display = state.get_current_display();
+    let idx = match query {
+        "FocusedMonitorIndex" => state.displays.iter().position(|d| d.id == display.id),
+        "FocusedWorkspaceIndex" => focused_workspace(display),
+        "FocusedContainerIndex" | "FocusedWindowIndex" => {
+            state.get_current_grid().and_then(focused_container)
+        }
+        _ => return Err(format!("Unsupported query '{}'", query)),
+    };
+
+    Ok(Value::from(idx.unwrap_or(0).to_string()))
+}
+
+fn handle(line: &str, state: &StateHandle) -> Result<Option<Value>, String> {
+    let message: Value = serde_json::from_str(line).map_err(|e| e.to_string())?;
+    let stopped = || "Nog stopped before answering".to_string();
+
+    match parse(&message)? {
+        Request::Action(action) => {
+            state.send(actions::parse_command(&action)?);
+
+            Ok(None)
+        }
+        Request::State => state.query(state_json).map(Some).ok_or_else(stopped),
+        Request::Query(name) => state
+            .query(move |state| query(state, &name))
+            .ok_or_else(stopped)?
+            .map(Some),
+    }
+}
+
+fn serve(stream: TcpStream, token: &str, state: StateHandle) -> std::io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut lines = BufReader::new(stream).lines();
+
+    writer.set_read_timeout(Some(TOKEN_TIMEOUT))?;
+
+    match lines.next().transpose()? {
+        Some(line) if line.trim() == token => {}
+        _ => {
+            debug!("Rejected a komorebi client without a valid token");
+            return Ok(());
+        }
+    }
+
+    writer.set_read_timeout(None)?;
+
+    for line in lines {
+        let line = line?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        debug!("Received komorebi message {}", line);
+
+        match handle(&line, &state) {
+            Ok(Some(response)) => writeln!(writer, "{}", response)?,
+            Ok(None) => {}
+            Err(e) => error!("Failed to handle the komorebi message {}: {}", line, e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Starts listening for komorebi messages on the port. Clients have to send the token of the ipc
+/// session first.
+pub fn start(port: u16, token: String, state: StateHandle) {
+    let listener = match TcpListener::bind(("127.0.0.1", port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!(
+                "Failed to listen for komorebi messages on port {}: {:?}",
+                port, e
+            );
+            return;
+        }
+    };
+
+    info!("Listening for komorebi messages on port {}", port);
+
+    thread::spawn(move || {
+        let token = Arc::new(token);
+
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let token = token.clone();
+                    let state = state.clone();
+                    thread::spawn(move || {
+                        if let Err(e) = serve(stream, &token, state) {
+                            debug!("A komorebi client disconnected {:?}", e);
+                        }
+                    });
+                }
+                Err(e) => error!("Failed to accept a komorebi client {:?}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{Event, EventChannel};
+    use std::net::Shutdown;
+
+    fn parse_str(message: &str) -> Result<Request, String> {
+        parse(&serde_json::from_str(message).unwrap())
+    }
+
+    #[test]
+    fn parse_messages() {
+        assert_eq!(
+            parse_str(r#"{"type": "FocusWindow", "content": "Left"}"#),
+            Ok(Request::Action("focus_left".into()))
+        );
+        assert_eq!(
+            parse_str(r#"{"type": "MoveContainerToWorkspaceNumber", "content": 2}"#),
+            Ok(Request::Action("move_to_workspace(3)".into()))
+        );
+        assert_eq!(
+            parse_str(r#"{"type": "Query", "content": "FocusedWorkspaceIndex"}"#),
+            Ok(Request::Query("FocusedWorkspaceIndex".into()))
+        );
+        assert_eq!(parse_str(r#"{"type": "State"}"#), Ok(Request::State));
+        assert!(parse_str(r#"{"type": "FocusWindow", "content": "Sideways"}"#).is_err());
+        assert!(parse_str(r#"{"type": "Retile"}"#).is_err());
+    }
+
+    #[test]
+    fn clients_need_the_token() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let channel = EventChannel::default();
+        let send = |content: &str| {
+            let mut client = TcpStream::connect(address).unwrap();
+            client.write_all(content.as_bytes()).unwrap();
+            client.shutdown(Shutdown::Write).unwrap();
+
+            let (stream, _) = listener.accept().unwrap();
+            serve(stream, "abc", StateHandle::new(channel.sender.clone())).unwrap();
+        };
+
+        send("{\"type\": \"Close\"}\n");
+        send("abd\n{\"type\": \"Close\"}\n");
+        assert!(channel.receiver.try_recv().is_err());
+
+        send("abc\n{\"type\": \"Close\"}\n");
+        assert!(matches!(
+            channel.receiver.try_recv(),
+            Ok(Event::RunAction(_))
+        ));
+    }
+}
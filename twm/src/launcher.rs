@@ -0,0 +1,66 @@
+//! Starts programs on behalf of the config while respecting its launch policy.
+use crate::config::launch_policy::{split_command, LaunchPolicy};
+use log::{debug, error, warn};
+use std::{
+    process::Command,
+    thread,
+    time::{Duration, Instant},
+};
+
+pub fn launch(policy: &LaunchPolicy, cmd: &str) -> Result<(), String> {
+    let parts = split_command(cmd);
+    let (program, args) = parts.split_first().ok_or("The command is empty")?;
+
+    policy.check(program)?;
+
+    let mut command = Command::new(program);
+
+    command.args(args);
+
+    if let Some(dir) = &policy.working_directory {
+        command.current_dir(dir);
+    }
+
+    if policy.env.is_some() {
+        command
+            .env_clear()
+            .envs(std::env::vars().filter(|(name, _)| policy.keeps_env(name)));
+    }
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to launch '{}': {}", program, e))?;
+
+    debug!("Launched '{}' ({})", cmd, child.id());
+
+    if let Some(timeout) = policy.timeout {
+        let timeout = Duration::from_millis(timeout);
+        let program = program.clone();
+
+        thread::spawn(move || {
+            let start = Instant::now();
+
+            loop {
+                match child.try_wait() {
+                    Ok(None) if start.elapsed() >= timeout => {
+                        warn!(
+                            "Killing '{}', because it ran longer than {}ms",
+                            program,
+                            timeout.as_millis()
+                        );
+
+                        if let Err(e) = child.kill() {
+                            error!("Failed to kill '{}' {:?}", program, e);
+                        }
+
+                        break;
+                    }
+                    Ok(None) => sleep!(100),
+                    _ => break,
+                }
+            }
+        });
+    }
+
+    Ok(())
+}
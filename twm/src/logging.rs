@@ -1,5 +1,21 @@
-use flexi_logger::{opt_format, Age, Cleanup, Criterion, Duplicate, Logger, Naming};
-use std::path::PathBuf;
+//! Besides writing to the log files, the most recent records are kept in memory, where scripts
+//! (`nog.log.tail`) and other instances (`nog.exe --logs`) can look at them.
+//!
+//! The level of each module can be changed at runtime, for example to only get the debug output of
+//! `twm::keybindings`.
+use chrono::Local;
+use flexi_logger::{
+    opt_format, Age, Cleanup, Criterion, Duplicate, LogSpecification, Logger, Naming,
+    ReconfigurationHandle,
+};
+use lazy_static::lazy_static;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use parking_lot::Mutex;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt,
+    path::PathBuf,
+};
 
 #[cfg(debug_assertions)]
 const DEBUG: &'static str = "twm=debug";
@@ -7,6 +23,146 @@ const DEBUG: &'static str = "twm=debug";
 #[cfg(not(debug_assertions))]
 const DEBUG: &'static str = "twm=debug";
 
+/// How many records are kept in memory
+const BUFFER_SIZE: usize = 1000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry {
+    pub time: String,
+    pub level: Level,
+    pub module: String,
+    pub message: String,
+}
+
+impl fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}] {} [{}] {}",
+            self.time, self.level, self.module, self.message
+        )
+    }
+}
+
+impl From<&Record<'_>> for LogEntry {
+    fn from(record: &Record<'_>) -> Self {
+        Self {
+            time: Local::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+            level: record.level(),
+            module: record.module_path().unwrap_or("<unnamed>").into(),
+            message: record.args().to_string(),
+        }
+    }
+}
+
+/// Keeps the last `capacity` items and drops the oldest ones once it is full.
+#[derive(Debug, Clone)]
+pub struct RingBuffer<T> {
+    capacity: usize,
+    items: VecDeque<T>,
+}
+
+impl<T: Clone> RingBuffer<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            items: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn push(&mut self, item: T) {
+        if self.items.len() == self.capacity {
+            self.items.pop_front();
+        }
+
+        self.items.push_back(item);
+    }
+
+    /// Returns the last `count` items, the oldest first.
+    pub fn tail(&self, count: usize) -> Vec<T> {
+        let skip = self.items.len().saturating_sub(count);
+
+        self.items.iter().skip(skip).cloned().collect()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.items.iter_mut()
+    }
+}
+
+lazy_static! {
+    static ref BUFFER: Mutex<RingBuffer<LogEntry>> = Mutex::new(RingBuffer::new(BUFFER_SIZE));
+    static ref LEVELS: Mutex<BTreeMap<String, LevelFilter>> = {
+        let mut levels = BTreeMap::new();
+        levels.insert("twm".into(), LevelFilter::Debug);
+        Mutex::new(levels)
+    };
+}
+
+static HANDLE: Mutex<Option<ReconfigurationHandle>> = Mutex::new(None);
+
+/// Forwards every record to the file logger and stores the ones that pass its filter.
+struct BufferedLogger {
+    inner: Box<dyn Log>,
+}
+
+impl Log for BufferedLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.inner.enabled(record.metadata()) {
+            BUFFER.lock().push(LogEntry::from(record));
+        }
+
+        self.inner.log(record)
+    }
+
+    fn flush(&self) {
+        self.inner.flush()
+    }
+}
+
+/// Returns the last `count` log entries, the oldest first.
+pub fn tail(count: usize) -> Vec<LogEntry> {
+    BUFFER.lock().tail(count)
+}
+
+/// Returns the configured level of every module.
+pub fn levels() -> Vec<(String, LevelFilter)> {
+    LEVELS
+        .lock()
+        .iter()
+        .map(|(module, level)| (module.clone(), *level))
+        .collect()
+}
+
+/// Changes the level of the module (e.g. `twm::bar`) and all of its submodules.
+pub fn set_level(module: &str, level: &str) -> Result<(), String> {
+    let level = level
+        .parse::<LevelFilter>()
+        .map_err(|_| format!("Unknown log level '{}'", level))?;
+
+    let mut levels = LEVELS.lock();
+
+    levels.insert(module.into(), level);
+
+    let spec = levels
+        .iter()
+        .map(|(module, level)| format!("{}={}", module, level.to_string().to_lowercase()))
+        .collect::<Vec<String>>()
+        .join(",");
+
+    let spec = LogSpecification::parse(&spec).map_err(|e| e.to_string())?;
+
+    if let Some(handle) = HANDLE.lock().as_mut() {
+        handle.set_new_spec(spec);
+    }
+
+    Ok(())
+}
+
 pub fn setup() -> Result<(), Box<dyn std::error::Error>> {
     #[allow(unused_mut)]
     let mut path: PathBuf = ["./log"].iter().collect();
@@ -19,7 +175,7 @@ pub fn setup() -> Result<(), Box<dyn std::error::Error>> {
         path.push("log");
     }
 
-    Logger::with_env_or_str(DEBUG)
+    let (logger, handle) = Logger::with_env_or_str(DEBUG)
         .log_to_file()
         .duplicate_to_stderr(Duplicate::All)
         .directory(path)
@@ -29,8 +185,30 @@ pub fn setup() -> Result<(), Box<dyn std::error::Error>> {
             Naming::Timestamps,
             Cleanup::KeepLogFiles(6),
         )
-        .start()
-        .expect("Failed to initialize logger");
+        .build()?;
+
+    log::set_boxed_logger(Box::new(BufferedLogger { inner: logger }))?;
+    // the specification of the file logger decides which records get through
+    log::set_max_level(LevelFilter::Trace);
+
+    *HANDLE.lock() = Some(handle);
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ring_buffer_drops_the_oldest_items() {
+        let mut buffer = RingBuffer::new(3);
+
+        for i in 0..5 {
+            buffer.push(i);
+        }
+
+        assert_eq!(buffer.tail(2), vec![3, 4]);
+        assert_eq!(buffer.tail(10), vec![2, 3, 4]);
+    }
+}
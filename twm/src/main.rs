@@ -8,37 +8,64 @@ extern crate strum_macros;
 extern crate interpreter;
 
 use bar::component::{self, Component, ComponentText};
-use config::{rule::Rule, workspace_setting::WorkspaceSetting, Config};
+use command_palette::{Action, ActionTarget, CommandPalette};
+use config::{
+    profile::{self, ProfileSettings},
+    rule::Rule,
+    workspace_setting::WorkspaceSetting,
+    Config,
+};
 use crossbeam_channel::select;
 use direction::Direction;
-use display::Display;
+use display::{Display, WORKSPACES_PER_DISPLAY};
 use event::Event;
 use event::EventChannel;
+use game_mode::GameMode;
 use hot_reload::update_config;
 use interpreter::{Dynamic, Function, Interpreter, Module, RuntimeError};
 use itertools::Itertools;
 use keybindings::{keybinding::Keybinding, KbManager};
 use log::debug;
-use log::{error, info};
+use log::{error, info, warn};
+use overview::{Overview, OverviewItem};
 use parking_lot::{deadlock, Mutex};
 use popup::Popup;
 use regex::Regex;
+use scheduler::Scheduler;
 use split_direction::SplitDirection;
+use state_handle::StateHandle;
+use std::collections::HashMap;
 use std::fs::ReadDir;
 use std::path::PathBuf;
 use std::process::Command;
 use std::str::FromStr;
-use std::{mem, thread, time::Duration};
-use std::{process, sync::atomic::AtomicBool, sync::Arc};
+use std::{
+    mem, thread,
+    time::{Duration, Instant},
+};
+use std::{
+    panic::{self, AssertUnwindSafe},
+    process,
+    sync::atomic::{self, AtomicBool},
+    sync::Arc,
+};
 use system::NativeWindow;
-use system::{DisplayId, SystemResult, WinEventListener, WindowId};
+use system::{
+    DisplayId, MouseListener, NativeSystem, System, SystemError, SystemResult, WindowId,
+};
 use task_bar::Taskbar;
-use tile_grid::{store::Store, TileGrid};
+use tile_grid::{placements, store::Store, TileGrid};
 use win_event_handler::{win_event::WinEvent, win_event_type::WinEventType};
 use window::Window;
+use window_search::{WindowEntry, WindowSearch};
 
 pub const NOG_BAR_NAME: &'static str = "nog_bar";
 pub const NOG_POPUP_NAME: &'static str = "nog_popup";
+pub const RESIZE_MODE: &'static str = "resize";
+pub const COMMAND_PALETTE_MODE: &'static str = "command_palette";
+pub const BAR_DRAG_MODE: &'static str = "bar_drag";
+pub const OVERVIEW_MODE: &'static str = "overview";
+pub const WINDOW_SEARCH_MODE: &'static str = "window_search";
 
 #[macro_use]
 #[allow(unused_macros)]
@@ -98,7 +125,7 @@ mod macros {
                     error!("{}", m);
                     return $value;
                 }
-            };
+            }
         };
     }
     /// This macro either gets the Ok(..) value of the passed expression or returns an Ok(()).
@@ -111,28 +138,49 @@ mod macros {
                     error!("{}", m);
                     return Ok(());
                 }
-            };
+            }
         };
     }
 }
 
+mod actions;
 mod bar;
+mod borderless;
+mod command_palette;
 mod config;
+mod debug_dump;
 mod direction;
 mod display;
 mod event;
 mod event_handler;
+mod event_stream;
+mod game_mode;
+mod hidden;
 mod hot_reload;
+mod http;
+mod i3;
+mod ipc;
+mod journal;
 mod keybindings;
+mod komorebi;
+mod launcher;
 mod logging;
 mod message_loop;
 mod nogscript;
+mod osd;
+mod overview;
+mod pinned;
 mod popup;
 mod renderer;
+mod rule_check;
+mod scheduler;
+mod snapshot;
 mod split_direction;
 mod startup;
+mod state_handle;
 mod system;
 mod task_bar;
+mod templates;
 mod tile;
 mod tile_grid;
 mod tray;
@@ -140,6 +188,8 @@ mod update;
 mod util;
 mod win_event_handler;
 mod window;
+mod window_search;
+mod workspace_policy;
 
 #[derive(Debug, Clone)]
 pub struct AppState {
@@ -149,25 +199,67 @@ pub struct AppState {
     pub event_channel: EventChannel,
     pub keybindings_manager: KbManager,
     pub additonal_rules: Vec<Rule>,
-    pub window_event_listener: WinEventListener,
+    /// The desktop, which is the real one unless the state got created for a test
+    pub system: Arc<dyn System>,
+    pub mouse_listener: MouseListener,
+    /// The mouse drag that is currently in progress
+    pub drag: Option<event_handler::mouse::Drag>,
     pub workspace_id: i32,
+    /// The sizes of the grid that is being resized in resize mode, which get restored when the
+    /// resize gets cancelled.
+    pub resize_snapshot: Option<(i32, HashMap<usize, u32>)>,
+    /// The command palette, if it is currently open
+    pub command_palette: Option<CommandPalette>,
+    /// The overview of the workspaces, if it is currently open
+    pub overview: Option<Overview>,
+    /// The search for a window of any workspace, if it is currently open
+    pub window_search: Option<WindowSearch>,
+    pub scheduler: Scheduler,
+    /// Set while a fullscreen application is in the foreground
+    pub game_mode: Option<GameMode>,
+    /// The windows that got made borderless fullscreen
+    pub borderless_windows: Vec<WindowId>,
+    /// The templates that still wait for some of their windows
+    pub pending_templates: Vec<templates::PendingTemplate>,
+    /// The managed windows that got made translucent by `dim_unfocused`
+    pub dimmed_windows: Vec<WindowId>,
+    /// The windows that are kept above every other window
+    pub pinned_windows: Vec<pinned::PinnedWindow>,
+    /// The windows that got hidden until the user restores them
+    pub hidden_windows: Vec<hidden::HiddenWindow>,
 }
 
 impl Default for AppState {
     fn default() -> Self {
         let config = Config::default();
+        let system: Arc<dyn System> = Arc::new(NativeSystem::default());
         Self {
             work_mode: true,
-            displays: time!("initializing displays", display::init(&config)),
+            displays: time!("initializing displays", display::init_with(&*system, &config)),
             keybindings_manager: KbManager::new(
                 config.keybindings.clone(),
                 config.mode_handlers.clone(),
                 config.allow_right_alt,
+                config.override_win_shortcuts,
+                &config.rules,
             ),
             event_channel: EventChannel::default(),
             additonal_rules: Vec::new(),
-            window_event_listener: WinEventListener::default(),
+            system,
+            mouse_listener: MouseListener::default(),
+            drag: None,
             workspace_id: 1,
+            resize_snapshot: None,
+            command_palette: None,
+            overview: None,
+            window_search: None,
+            scheduler: Scheduler::default(),
+            game_mode: None,
+            borderless_windows: Vec::new(),
+            pending_templates: Vec::new(),
+            dimmed_windows: Vec::new(),
+            pinned_windows: Vec::new(),
+            hidden_windows: Vec::new(),
             config,
         }
     }
@@ -175,29 +267,50 @@ impl Default for AppState {
 
 impl AppState {
     pub fn new(config: Config) -> Self {
+        Self::with_system(config, Arc::new(NativeSystem::default()))
+    }
+    /// Creates the state on top of the given desktop, e.g. a `system::mock::MockSystem` in tests
+    pub fn with_system(config: Config, system: Arc<dyn System>) -> Self {
         Self {
             work_mode: config.work_mode,
-            displays: display::init(&config),
+            displays: display::init_with(&*system, &config),
             keybindings_manager: KbManager::new(
                 config.keybindings.clone(),
                 config.mode_handlers.clone(),
                 config.allow_right_alt,
+                config.override_win_shortcuts,
+                &config.rules,
             ),
             event_channel: EventChannel::default(),
             additonal_rules: Vec::new(),
-            window_event_listener: WinEventListener::default(),
+            system,
+            mouse_listener: MouseListener::default(),
+            drag: None,
             workspace_id: 1,
+            resize_snapshot: None,
+            command_palette: None,
+            overview: None,
+            window_search: None,
+            scheduler: Scheduler::default(),
+            game_mode: None,
+            borderless_windows: Vec::new(),
+            pending_templates: Vec::new(),
+            dimmed_windows: Vec::new(),
+            pinned_windows: Vec::new(),
+            hidden_windows: Vec::new(),
             config,
         }
     }
     pub fn init(&mut self, config: Config) {
         self.config = config;
         self.work_mode = self.config.work_mode;
-        self.displays = display::init(&self.config);
+        self.displays = display::init_with(&*self.system, &self.config);
         self.keybindings_manager = KbManager::new(
             self.config.keybindings.clone(),
             self.config.mode_handlers.clone(),
             self.config.allow_right_alt,
+            self.config.override_win_shortcuts,
+            &self.config.rules,
         );
     }
 
@@ -209,10 +322,36 @@ impl AppState {
             }
         }
 
+        borderless::restore_all(self)?;
+        pinned::restore_all(self)?;
+        hidden::restore_all(self)?;
+        // cleaning up the windows restored their opacity
+        self.dimmed_windows.clear();
+
+        Ok(())
+    }
+
+    /// Updates the opacity of the windows of the visible workspaces after the focus changed.
+    pub fn update_opacity(&mut self) -> SystemResult {
+        let focused = NativeWindow::get_foreground_window().ok().map(|w| w.id);
+        let mut dimmed = std::mem::take(&mut self.dimmed_windows);
+
+        for display in &self.displays {
+            if let Some(grid) = display.get_focused_grid() {
+                grid.update_opacity(&self.config, focused, &mut dimmed)?;
+            }
+        }
+
+        self.dimmed_windows = dimmed;
+
         Ok(())
     }
 
     pub fn move_workspace_to_monitor(&mut self, monitor: i32) -> SystemResult {
+        if self.config.display_workspaces {
+            error!("Workspaces can't be moved while every display owns its own workspaces");
+            return Ok(());
+        }
         if self.get_display_by_idx_mut(monitor).is_none() {
             error!("Monitor with id {} doesn't exist", monitor);
             return Ok(());
@@ -224,17 +363,119 @@ impl AppState {
             .and_then(|id| display.remove_grid_by_id(id))
         {
             let config = self.config.clone();
+            let system = self.system.clone();
             let new_display = self.get_display_by_idx_mut(monitor).unwrap();
             let id = grid.id;
 
             new_display.grids.push(grid);
-            new_display.focus_workspace(&config, id)?;
+            new_display.focus_workspace(&*system, &config, id)?;
             self.workspace_id = id;
         }
 
         Ok(())
     }
 
+    /// Moves the workspace to the display and focuses it there. If the workspace was visible on
+    /// its previous display, another workspace of that display takes its place.
+    pub fn move_workspace_to_display(&mut self, id: i32, display_id: DisplayId) -> SystemResult {
+        let config = self.config.clone();
+        let system = self.system.clone();
+
+        if config.display_workspaces {
+            error!("Workspaces can't be moved while every display owns its own workspaces");
+            return Ok(());
+        }
+
+        if self.get_display_by_id(display_id).is_none() {
+            error!("Display with id {:?} doesn't exist", display_id);
+            return Ok(());
+        }
+
+        let source = match self.find_grid_display_mut(id) {
+            Some(d) if d.id != display_id => d,
+            _ => return Ok(()),
+        };
+
+        let grid = source.remove_grid_by_id(id).unwrap();
+
+        if source.focused_grid_id == Some(id) {
+            source.focused_grid_id = None;
+
+            let next_id = source
+                .grids
+                .iter()
+                .find(|g| !g.is_empty())
+                .or_else(|| source.grids.first())
+                .map(|g| g.id);
+
+            if let Some(next_id) = next_id {
+                source.focus_workspace(&*system, &config, next_id)?;
+            }
+        }
+
+        info!("Moving workspace {} to display {:?}", id, display_id);
+
+        let target = self.get_display_by_id_mut(display_id).unwrap();
+
+        target.grids.push(grid);
+        target.focus_workspace(&*system, &config, id)?;
+        self.workspace_id = id;
+
+        Ok(())
+    }
+
+    /// Swaps the visible workspaces of the two displays, which are given by their index. Each
+    /// workspace gets drawn to fit the display it ends up on and keeps its windows and focus.
+    pub fn swap_workspaces(&mut self, a: i32, b: i32) -> SystemResult {
+        let config = self.config.clone();
+        let system = self.system.clone();
+
+        if config.display_workspaces {
+            error!("Workspaces can't be swapped while every display owns its own workspaces");
+            return Ok(());
+        }
+
+        let (a, b) = match (self.get_display_by_idx(a), self.get_display_by_idx(b)) {
+            (Some(x), Some(y)) if x.id != y.id => (x.id, y.id),
+            _ => {
+                error!("Can't swap the workspaces of the displays {} and {}", a, b);
+                return Ok(());
+            }
+        };
+
+        // both workspaces are taken first, so that neither display hides the workspace it is
+        // about to receive
+        let mut take_visible_grid = |display_id| {
+            let display = self.get_display_by_id_mut(display_id).unwrap();
+            let grid = display
+                .focused_grid_id
+                .and_then(|id| display.remove_grid_by_id(id));
+            display.focused_grid_id = None;
+            grid
+        };
+        let grid_a = take_visible_grid(a);
+        let grid_b = take_visible_grid(b);
+
+        info!(
+            "Swapping the workspaces of the displays {:?} and {:?}",
+            a, b
+        );
+
+        for (grid, display_id) in vec![(grid_a, b), (grid_b, a)] {
+            if let Some(grid) = grid {
+                let id = grid.id;
+                let display = self.get_display_by_id_mut(display_id).unwrap();
+
+                display.grids.push(grid);
+                display.focus_workspace(&*system, &config, id)?;
+            }
+        }
+
+        self.redraw_app_bars();
+
+        Ok(())
+    }
+
     pub fn move_workspace_to_workspace(&mut self, workspace_id: i32) -> SystemResult {
         let is_empty = self
             .get_grid_by_id(workspace_id)
@@ -251,8 +492,9 @@ impl AppState {
             mem::swap(target, &mut empty_grid);
 
             let config = self.config.clone();
+            let system = self.system.clone();
             if let Some(display) = self.find_grid_display_mut(workspace_id) {
-                display.focus_workspace(&config, workspace_id)?;
+                display.focus_workspace(&*system, &config, workspace_id)?;
                 self.workspace_id = workspace_id;
             }
         }
@@ -262,7 +504,10 @@ impl AppState {
 
     pub fn minimize_window(&mut self) -> SystemResult {
         let config = self.config.clone();
-        let grid = self.get_current_grid_mut().unwrap();
+        let grid = match self.get_current_grid_mut() {
+            Some(grid) => grid,
+            None => return Ok(()),
+        };
 
         grid.modify_focused_window(|window| {
             window.minimize()?;
@@ -283,7 +528,10 @@ impl AppState {
         }
 
         let config = self.config.clone();
-        let grid = self.get_current_grid_mut().unwrap();
+        let grid = match self.get_current_grid_mut() {
+            Some(grid) => grid,
+            None => return Ok(()),
+        };
 
         grid.modify_focused_window(|window| {
             window.cleanup()?;
@@ -323,6 +571,9 @@ impl AppState {
         let window = grid.pop();
 
         window.map(|window| {
+            if self.config.smart_placement {
+                placements::remember(&window.get_process_path(), id);
+            }
             self.get_grid_by_id_mut(id).unwrap().push(window);
             self.change_workspace(id, false);
         });
@@ -330,6 +581,66 @@ impl AppState {
         Ok(())
     }
 
+    /// Moves the focused window to the next or previous workspace. If `empty` is set, the
+    /// workspaces that already contain windows get skipped.
+    pub fn move_window_to_relative_workspace(
+        &mut self,
+        forward: bool,
+        empty: bool,
+    ) -> SystemResult {
+        let ids = self.relative_workspace_ids();
+        let target = display::relative_workspace(&ids, self.workspace_id, forward, |id| {
+            !empty || self.get_grid_by_id(id).map_or(false, |g| g.is_empty())
+        });
+
+        match target {
+            Some(id) => self.move_window_to_workspace(id),
+            None => Ok(()),
+        }
+    }
+
+    /// Moves the window to the workspace without switching to it.
+    pub fn move_window_by_id_to_workspace(
+        &mut self,
+        window_id: WindowId,
+        workspace_id: i32,
+    ) -> SystemResult {
+        let config = self.config.clone();
+        let source_id = match self.find_grid_containing_window(window_id) {
+            Some(grid) if grid.id != workspace_id => grid.id,
+            _ => return Ok(()),
+        };
+
+        if self.get_grid_by_id(workspace_id).is_none() {
+            error!("Workspace with id {} doesn't exist", workspace_id);
+            return Ok(());
+        }
+
+        let window = self
+            .get_grid_by_id_mut(source_id)
+            .and_then(|grid| grid.remove_by_window_id(window_id));
+
+        if let Some(window) = window {
+            if config.smart_placement {
+                placements::remember(&window.get_process_path(), workspace_id);
+            }
+
+            if !self.is_workspace_visible(workspace_id) {
+                window.hide();
+            }
+
+            self.get_grid_by_id_mut(workspace_id).unwrap().push(window);
+        }
+
+        for id in &[source_id, workspace_id] {
+            if let Some(d) = self.find_grid_display(*id) {
+                d.refresh_grid(&config)?;
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn toggle_fullscreen(&mut self) -> SystemResult {
         let config = self.config.clone();
         let display = self.get_current_display_mut();
@@ -339,6 +650,25 @@ impl AppState {
         Ok(())
     }
 
+    /// Removes the title bar of the focused window or gives it back.
+    pub fn toggle_decorations(&mut self) -> SystemResult {
+        let config = self.config.clone();
+        let display = self.get_current_display_mut();
+
+        if let Some(grid) = display.get_focused_grid_mut() {
+            grid.modify_focused_window(|window| {
+                if window.title_bar_removed {
+                    window.restore_title_bar()
+                } else {
+                    let rule = window.rule.clone().unwrap_or_default();
+                    window.remove_title_bar(rule.use_border.unwrap_or(config.use_border))
+                }
+            })?;
+        }
+
+        display.refresh_grid(&config)
+    }
+
     pub fn enter_work_mode(state_arc: Arc<Mutex<AppState>>) -> SystemResult {
         let mut this = state_arc.lock();
         if this.config.remove_task_bar {
@@ -355,13 +685,26 @@ impl AppState {
         let mut focused_workspaces = Vec::<i32>::new();
         let remove_title_bar = this.config.remove_title_bar;
         let use_border = this.config.use_border;
-        let stored_grids: Vec<String> = Store::load();
+        let layouts = startup::initial_layouts(&this.config.startup);
         let rules = this.config.rules.clone();
         let additional_rules = this.additonal_rules.clone();
+        let mut restored_windows = Vec::<WindowId>::new();
         for display in this.displays.iter_mut() {
             for grid in display.grids.iter_mut() {
-                if let Some(stored_grid) = stored_grids.get((grid.id - 1) as usize) {
-                    grid.from_string(stored_grid);
+                if let Some(layout) = layouts.get(&grid.id) {
+                    // windows that no longer exist get removed while loading the layout
+                    grid.from_string(layout);
+
+                    // a window can only be on one workspace, so it stays on the first one
+                    for id in grid.get_window_ids() {
+                        if restored_windows.contains(&id) {
+                            info!("Window {:?} is already on another workspace", id);
+                            grid.remove_by_window_id(id);
+                        } else {
+                            restored_windows.push(id);
+                        }
+                    }
+
                     Store::save(grid.id, grid.to_string());
 
                     if let Err(e) = grid.modify_windows(|window| {
@@ -388,13 +731,29 @@ impl AppState {
             for id in focused_workspaces.iter().rev() {
                 this.change_workspace(*id, false);
             }
+        } else if this.config.display_workspaces {
+            // otherwise focus the first workspace of each display, ending with the first display
+            let mut ids: Vec<i32> = this
+                .displays
+                .iter()
+                .filter_map(|d| d.grids.first().map(|g| g.id))
+                .collect();
+            ids.sort();
+
+            for id in ids.iter().rev() {
+                this.change_workspace(*id, false);
+            }
         } else {
             // otherwise just focus first workspace
             this.change_workspace(1, false);
         }
 
         info!("Registering windows event handler");
-        this.window_event_listener.start(&this.event_channel);
+        this.system.start_hooks(&this.event_channel);
+
+        if this.config.mouse_integration {
+            this.mouse_listener.start(&this.event_channel);
+        }
 
         let kb = this.keybindings_manager.clone();
 
@@ -407,10 +766,12 @@ impl AppState {
 
     pub fn leave_work_mode(state_arc: Arc<Mutex<AppState>>) -> SystemResult {
         let mut this = state_arc.lock();
-        this.window_event_listener.stop();
+        this.system.stop_hooks();
+        this.mouse_listener.stop();
         this.keybindings_manager.leave_work_mode();
 
         popup::cleanup()?;
+        osd::close()?;
 
         if this.config.display_app_bar {
             drop(this);
@@ -497,93 +858,583 @@ impl AppState {
         Ok(())
     }
 
-    pub fn resize(&mut self, direction: Direction, amount: i32) -> SystemResult {
+    /// Focuses the next or previous tile of the workspace, see `TileGrid::focus_relative`
+    pub fn focus_relative(&mut self, forward: bool) -> SystemResult {
+        let config = self.config.clone();
+        let display = self.get_current_display_mut();
+
+        if let Some(grid) = display.get_focused_grid_mut() {
+            if !config.ignore_fullscreen_actions || !grid.is_fullscreened() {
+                grid.focus_relative(forward)?;
+                display.refresh_grid(&config)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn cycle_container(&mut self) -> SystemResult {
+        let config = self.config.clone();
+        let display = self.get_current_display_mut();
+
+        if let Some(grid) = display.get_focused_grid_mut() {
+            if !config.ignore_fullscreen_actions || !grid.is_fullscreened() {
+                grid.cycle_container()?;
+                display.refresh_grid(&config)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn resize(&mut self, direction: Direction, amount: i32) -> SystemResult {
+        let config = self.config.clone();
+        let display = self.get_current_display_mut();
+
+        if let Some(grid) = display.get_focused_grid_mut() {
+            if !config.ignore_fullscreen_actions || !grid.is_fullscreened() {
+                grid.trade_size_with_neighbor(grid.focused_id, direction, amount);
+                info!("Resizing in the direction {:?} by {}", direction, amount);
+
+                display.refresh_grid(&config)?;
+            }
+        }
+
+        if self.resize_snapshot.is_some() {
+            self.show_resize_overlay();
+        }
+
+        Ok(())
+    }
+
+    pub fn resize_step(&mut self, direction: Direction, grow: bool) -> SystemResult {
+        let step = self.config.resize_step;
+        self.resize(direction, if grow { step } else { -step })
+    }
+
+    pub fn begin_resize(&mut self) {
+        let workspace_id = self.workspace_id;
+        self.resize_snapshot = self
+            .get_grid_by_id(workspace_id)
+            .map(|g| (workspace_id, g.get_sizes()));
+        self.show_resize_overlay();
+    }
+
+    /// Leaves the resize session. When `revert` is true, every size change made since entering
+    /// resize mode gets undone. This only gets called when the resize mode is left.
+    fn end_resize(&mut self, revert: bool) -> SystemResult {
+        popup::close()?;
+
+        if let Some((grid_id, sizes)) = self.resize_snapshot.take() {
+            if revert {
+                let config = self.config.clone();
+                let restored = self
+                    .get_grid_by_id_mut(grid_id)
+                    .map(|g| g.restore_sizes(&sizes))
+                    .unwrap_or(false);
+
+                if restored {
+                    if let Some(d) = self.find_grid_display(grid_id) {
+                        d.refresh_grid(&config)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn show_resize_overlay(&self) {
+        let size = self.get_current_grid().and_then(|g| {
+            g.get_render_info(100, 100)
+                .into_iter()
+                .find(|info| Some(info.debug_id) == g.focused_id)
+                .map(|info| (info.width, info.height))
+        });
+
+        let size_line = match size {
+            Some((width, height)) => format!("Width: {}%  Height: {}%", width, height),
+            None => "No window focused".into(),
+        };
+
+        self.event_channel
+            .sender
+            .send(Event::NewPopup(Popup::new().with_padding(5).with_text(
+                vec![
+                    "Resize".into(),
+                    size_line,
+                    "".into(),
+                    "H/J/K/L to resize, Enter to apply, Escape to cancel".into(),
+                ],
+            )))
+            .expect("Failed to send NewPopup event");
+    }
+
+    pub fn open_command_palette(&mut self) {
+        self.command_palette = Some(CommandPalette::default());
+        self.show_command_palette();
+    }
+
+    pub fn close_command_palette(&mut self) -> SystemResult {
+        popup::close()?;
+        self.command_palette = None;
+        Ok(())
+    }
+
+    /// Changes the command palette using the given function and redraws it afterwards.
+    pub fn update_command_palette(&mut self, f: impl FnOnce(&mut CommandPalette, &[Action])) {
+        if let Some(palette) = self.command_palette.as_mut() {
+            f(palette, &self.config.actions);
+            self.show_command_palette();
+        }
+    }
+
+    /// Executes the action that is currently selected in the command palette.
+    pub fn execute_command_palette_selection(&self) {
+        let selection = self
+            .command_palette
+            .as_ref()
+            .and_then(|p| p.selection(&self.config.actions));
+
+        if let Some(action) = selection {
+            info!("Executing action '{}'", action.name);
+
+            let event = match &action.target {
+                ActionTarget::Named(invocation) => Event::RunAction(invocation.clone()),
+                ActionTarget::Callback(idx) => Event::CallCallback {
+                    idx: *idx,
+                    is_mode_callback: false,
+                },
+            };
+
+            self.event_channel
+                .sender
+                .send(event)
+                .expect("Failed to send action event");
+        }
+    }
+
+    fn show_command_palette(&self) {
+        if let Some(palette) = self.command_palette.as_ref() {
+            self.event_channel
+                .sender
+                .send(Event::NewPopup(palette.to_popup(&self.config.actions)))
+                .expect("Failed to send NewPopup event");
+        }
+    }
+
+    pub fn open_overview(&mut self) {
+        let display = self.get_current_display();
+        let items = display
+            .grids
+            .iter()
+            .filter(|g| !g.is_empty() || display.focused_grid_id == Some(g.id))
+            .sorted_by_key(|g| g.id)
+            .map(|g| OverviewItem {
+                workspace_id: g.id,
+                number: self.workspace_number(g.id),
+                miniature: g.get_miniature(overview::MINIATURE_WIDTH, overview::MINIATURE_HEIGHT),
+            })
+            .collect();
+
+        self.overview = Some(Overview::new(items, display.focused_grid_id));
+        self.show_overview();
+    }
+
+    pub fn close_overview(&mut self) -> SystemResult {
+        popup::close()?;
+        self.overview = None;
+        Ok(())
+    }
+
+    /// Changes the overview using the given function and redraws it afterwards.
+    pub fn update_overview(&mut self, f: impl FnOnce(&mut Overview)) {
+        if let Some(overview) = self.overview.as_mut() {
+            f(overview);
+            self.show_overview();
+        }
+    }
+
+    /// Switches to the workspace that is currently selected in the overview.
+    pub fn change_to_overview_selection(&mut self) {
+        if let Some(id) = self.overview.as_ref().and_then(|o| o.selection()) {
+            self.change_workspace(id, false);
+        }
+    }
+
+    fn show_overview(&self) {
+        if let Some(overview) = self.overview.as_ref() {
+            self.event_channel
+                .sender
+                .send(Event::NewPopup(overview.to_popup()))
+                .expect("Failed to send NewPopup event");
+        }
+    }
+
+    /// Returns every managed window, ordered by the id of its workspace, followed by the hidden
+    /// windows
+    pub fn window_index(&self) -> Vec<WindowEntry> {
+        let mut entries = Vec::new();
+
+        for grid in self.get_grids().into_iter().sorted_by_key(|g| g.id) {
+            for id in grid.get_window_ids() {
+                if let Some(window) = grid.get_window(id) {
+                    entries.push(WindowEntry {
+                        id,
+                        title: window.title.clone(),
+                        exe: window.get_process_name(),
+                        workspace_id: grid.id,
+                        workspace_number: self.workspace_number(grid.id),
+                        hidden: false,
+                    });
+                }
+            }
+        }
+
+        for h in self.hidden_windows.iter().filter(|h| h.window.is_window()) {
+            entries.push(WindowEntry {
+                id: h.window.id,
+                title: h.window.title.clone(),
+                exe: h.window.get_process_name(),
+                workspace_id: self.workspace_id,
+                workspace_number: self.workspace_number(self.workspace_id),
+                hidden: true,
+            });
+        }
+
+        entries
+    }
+
+    /// Focuses the managed window and switches to its workspace first if necessary. A hidden
+    /// window gets restored instead.
+    pub fn focus_window(&mut self, id: WindowId) -> SystemResult {
+        if hidden::is_hidden(self, id) {
+            return hidden::restore(self, id);
+        }
+
+        let workspace_id = match self.find_grid_containing_window(id) {
+            Some(grid) => {
+                grid.focus_tile_by_window_id(id);
+                grid.id
+            }
+            None => return Ok(()),
+        };
+
+        self.change_workspace(workspace_id, false);
+
+        if let Some(window) = self
+            .get_grid_by_id(workspace_id)
+            .and_then(|g| g.get_window(id))
+        {
+            window.focus()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn open_window_search(&mut self) {
+        self.window_search = Some(WindowSearch::new(self.window_index()));
+        self.show_window_search();
+    }
+
+    pub fn close_window_search(&mut self) -> SystemResult {
+        popup::close()?;
+        self.window_search = None;
+        Ok(())
+    }
+
+    /// Changes the window search using the given function and redraws it afterwards.
+    pub fn update_window_search(&mut self, f: impl FnOnce(&mut WindowSearch)) {
+        if let Some(search) = self.window_search.as_mut() {
+            f(search);
+            self.show_window_search();
+        }
+    }
+
+    /// Returns the window that is currently selected in the window search.
+    pub fn window_search_selection(&self) -> Option<WindowId> {
+        self.window_search
+            .as_ref()
+            .and_then(|s| s.selection())
+            .map(|w| w.id)
+    }
+
+    fn show_window_search(&self) {
+        if let Some(search) = self.window_search.as_ref() {
+            self.event_channel
+                .sender
+                .send(Event::NewPopup(search.to_popup()))
+                .expect("Failed to send NewPopup event");
+        }
+    }
+
+    pub fn set_split_direction(&mut self, direction: SplitDirection) -> SystemResult {
+        let display = self.get_current_display_mut();
+        if let Some(grid) = display.get_focused_grid_mut() {
+            grid.next_axis = direction;
+        }
+        Ok(())
+    }
+
+    /// Makes the next window of the workspace open next to the focused tile in the given
+    /// direction. An osd shows the direction until the window opens.
+    pub fn split(&mut self, direction: Direction) -> SystemResult {
+        let display = self.get_current_display_mut();
+        let grid = match display.get_focused_grid_mut() {
+            Some(grid) => grid,
+            None => return Ok(()),
+        };
+
+        if let Some(id) = grid.get_focused_window().map(|w| w.id) {
+            let text = format!("Split {:?}", direction).to_lowercase();
+
+            grid.pending_split = Some((id, direction));
+            self.event_channel
+                .sender
+                .send(Event::PinOsd(text))
+                .expect("Failed to send PinOsd event");
+        }
+
+        Ok(())
+    }
+
+    pub fn toggle_floating(&mut self) -> SystemResult {
+        let config = self.config.clone();
+
+        let window =
+            NativeWindow::get_foreground_window().expect("Failed to get foreground window");
+        let current_workspace_id = self.workspace_id;
+        let grid = self.find_grid_containing_window(window.id);
+
+        if let Some(grid) = grid {
+            // don't do anything if focused window isn't on current grid
+            if grid.id == current_workspace_id {
+                if let Some(mut w) = grid.remove_by_window_id(window.id) {
+                    debug!("Unmanaging window '{}' | {}", w.title, w.id);
+                    w.cleanup();
+                    if let Some(d) = self.find_grid_display(current_workspace_id) {
+                        d.refresh_grid(&config);
+                    }
+                }
+            }
+        } else {
+            self.event_channel
+                .sender
+                .clone()
+                .send(Event::WinEvent(WinEvent {
+                    typ: WinEventType::Show(true),
+                    window,
+                }))
+                .expect("Failed to send WinEvent");
+        }
+
+        Ok(())
+    }
+
+    /// Unmanages the window if necessary and reverts everything nog changed about it.
+    pub fn restore_window(&mut self, id: WindowId) -> SystemResult {
+        let config = self.config.clone();
+
+        if let Some(grid) = self.find_grid_containing_window(id) {
+            let grid_id = grid.id;
+
+            if let Some(mut w) = grid.remove_by_window_id(id) {
+                debug!("Restoring window '{}' | {}", w.title, w.id);
+                w.cleanup()?;
+            }
+
+            if let Some(d) = self.find_grid_display(grid_id) {
+                d.refresh_grid(&config)?;
+            }
+
+            Ok(())
+        } else {
+            journal::repair(id)
+        }
+    }
+
+    /// Unmanages the window and passes it to `f`, for example to close or minimize it.
+    pub fn remove_window(
+        &mut self,
+        id: WindowId,
+        f: fn(&NativeWindow) -> SystemResult,
+    ) -> SystemResult {
+        let config = self.config.clone();
+
+        if let Some(grid) = self.find_grid_containing_window(id) {
+            let grid_id = grid.id;
+
+            if let Some(mut w) = grid.remove_by_window_id(id) {
+                w.cleanup()?;
+                f(&w)?;
+            }
+
+            if let Some(d) = self.find_grid_display(grid_id) {
+                d.refresh_grid(&config)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Kills the process of the foreground window and removes the window from its grid.
+    pub fn kill_window(&mut self) -> SystemResult {
+        let window = NativeWindow::get_foreground_window()?;
+
+        if self.find_grid_containing_window(window.id).is_some() {
+            self.remove_window(window.id, |w| w.kill())
+        } else {
+            window.kill()
+        }
+    }
+
+    /// Returns the foreground window, unless it is tiled.
+    fn get_floating_window(&self) -> SystemResult<NativeWindow> {
+        let window = NativeWindow::get_foreground_window()?;
+
+        if self.get_grids().iter().any(|g| g.contains(window.id)) {
+            return Err(SystemError::WindowIsTiled);
+        }
+
+        Ok(window)
+    }
+
+    /// Centers the floating foreground window on its display.
+    pub fn center_window(&self) -> SystemResult {
+        let window = self.get_floating_window()?;
+        let rect = window.get_rect()?;
+        let area = match self.get_display_by_id(window.get_display()?.id) {
+            Some(display) => display.rect,
+            None => return Ok(()),
+        };
+
+        window.set_position(
+            area.left + (area.width() - rect.width()) / 2,
+            area.top + (area.height() - rect.height()) / 2,
+        )
+    }
+
+    /// Resizes the floating foreground window without moving its top left corner.
+    pub fn resize_window(&self, width: i32, height: i32) -> SystemResult {
+        self.get_floating_window()?.set_size(width, height)
+    }
+
+    pub fn reset_column(&mut self) -> SystemResult {
+        let config = self.config.clone();
+        let display = self.get_current_display_mut();
+
+        if let Some(g) = display.get_focused_grid_mut() {
+            g.reset_column();
+        }
+        display.refresh_grid(&config)?;
+
+        Ok(())
+    }
+
+    pub fn reset_row(&mut self) -> SystemResult {
+        let config = self.config.clone();
+        let display = self.get_current_display_mut();
+
+        if let Some(g) = display.get_focused_grid_mut() {
+            g.reset_row();
+        }
+        display.refresh_grid(&config)?;
+
+        Ok(())
+    }
+
+    pub fn equalize(&mut self) -> SystemResult {
         let config = self.config.clone();
         let display = self.get_current_display_mut();
 
-        if let Some(grid) = display.get_focused_grid_mut() {
-            if !config.ignore_fullscreen_actions || !grid.is_fullscreened() {
-                grid.trade_size_with_neighbor(grid.focused_id, direction, amount);
-                info!("Resizing in the direction {:?} by {}", direction, amount);
-
-                display.refresh_grid(&config)?;
-            }
+        if let Some(g) = display.get_focused_grid_mut() {
+            g.equalize();
         }
-        Ok(())
-    }
+        display.refresh_grid(&config)?;
 
-    pub fn set_split_direction(&mut self, direction: SplitDirection) -> SystemResult {
-        let display = self.get_current_display_mut();
-        if let Some(grid) = display.get_focused_grid_mut() {
-            grid.next_axis = direction;
-        }
         Ok(())
     }
 
-    pub fn toggle_floating(&mut self) -> SystemResult {
+    pub fn balance_container(&mut self) -> SystemResult {
         let config = self.config.clone();
+        let display = self.get_current_display_mut();
 
-        let window =
-            NativeWindow::get_foreground_window().expect("Failed to get foreground window");
-        let current_workspace_id = self.workspace_id;
-        let grid = self.find_grid_containing_window(window.id);
-
-        if let Some(grid) = grid {
-            // don't do anything if focused window isn't on current grid
-            if grid.id == current_workspace_id {
-                if let Some(mut w) = grid.remove_by_window_id(window.id) {
-                    debug!("Unmanaging window '{}' | {}", w.title, w.id);
-                    w.cleanup();
-                    if let Some(d) = self.find_grid_display(current_workspace_id) {
-                        d.refresh_grid(&config);
-                    }
-                }
-            }
-        } else {
-            self.event_channel
-                .sender
-                .clone()
-                .send(Event::WinEvent(WinEvent {
-                    typ: WinEventType::Show(true),
-                    window,
-                }))
-                .expect("Failed to send WinEvent");
+        if let Some(g) = display.get_focused_grid_mut() {
+            g.balance_focused_container();
         }
+        display.refresh_grid(&config)?;
 
         Ok(())
     }
 
-    pub fn reset_column(&mut self) -> SystemResult {
+    pub fn rotate_container(&mut self, clockwise: bool) -> SystemResult {
         let config = self.config.clone();
         let display = self.get_current_display_mut();
 
         if let Some(g) = display.get_focused_grid_mut() {
-            g.reset_column();
+            g.rotate_focused_container(clockwise);
         }
         display.refresh_grid(&config)?;
 
         Ok(())
     }
 
-    pub fn reset_row(&mut self) -> SystemResult {
+    pub fn mirror_container(&mut self, horizontally: bool) -> SystemResult {
         let config = self.config.clone();
         let display = self.get_current_display_mut();
 
         if let Some(g) = display.get_focused_grid_mut() {
-            g.reset_row();
+            g.mirror_focused_container(horizontally);
         }
         display.refresh_grid(&config)?;
 
         Ok(())
     }
 
+    /// Leaves the mode if it is active. Unlike toggling it off this undoes what the mode changed,
+    /// which only the resize mode supports.
+    pub fn cancel_mode(&mut self, mode: &str) {
+        if self.keybindings_manager.get_mode().as_deref() == Some(mode) {
+            self.leave_mode(mode, true);
+        }
+    }
+
+    fn leave_mode(&mut self, mode: &str, cancel: bool) {
+        info!("Disabling {} mode", mode);
+        self.keybindings_manager.leave_mode();
+        if mode == RESIZE_MODE {
+            let _ = self.end_resize(cancel);
+        } else if mode == COMMAND_PALETTE_MODE {
+            let _ = self.close_command_palette();
+        } else if mode == OVERVIEW_MODE {
+            let _ = self.close_overview();
+        } else if mode == WINDOW_SEARCH_MODE {
+            let _ = self.close_window_search();
+        } else if mode != BAR_DRAG_MODE && self.config.osd_mode {
+            self.show_osd(format!("Left {} mode", mode));
+        }
+    }
+
     pub fn toggle_mode(&mut self, mode: String) {
         if self.keybindings_manager.get_mode() == Some(mode.clone()) {
-            info!("Disabling {} mode", mode);
-            self.keybindings_manager.leave_mode();
+            self.leave_mode(&mode, false);
         } else {
             info!("Enabling {} mode", mode);
             self.keybindings_manager.enter_mode(&mode);
+            // the resize mode, the command palette, the overview and the window search have their
+            // own popup that would overlap with the osd and the bar drag mode is only active while
+            // the mouse button is held
+            if mode == RESIZE_MODE {
+                self.begin_resize();
+            } else if mode == COMMAND_PALETTE_MODE {
+                self.open_command_palette();
+            } else if mode == OVERVIEW_MODE {
+                self.open_overview();
+            } else if mode == WINDOW_SEARCH_MODE {
+                self.open_window_search();
+            } else if mode != BAR_DRAG_MODE && self.config.osd_mode {
+                self.show_osd(format!("Mode: {}", mode));
+            }
         }
     }
 
@@ -591,6 +1442,52 @@ impl AppState {
         self.config.workspace_settings.iter().find(|s| s.id == id)
     }
 
+    /// Returns the id of the workspace with the number. If every display owns its own workspaces,
+    /// the number refers to a workspace of the current display.
+    pub fn resolve_workspace_id(&self, number: i32) -> i32 {
+        if !self.config.display_workspaces || number < 1 || number > WORKSPACES_PER_DISPLAY {
+            return number;
+        }
+
+        let idx = self
+            .get_display_idx(self.get_current_display().id)
+            .unwrap_or(1);
+
+        (idx - 1) * WORKSPACES_PER_DISPLAY + number
+    }
+
+    /// Returns the number that is shown for the workspace, which is its number on the display if
+    /// every display owns its own workspaces.
+    pub fn workspace_number(&self, id: i32) -> i32 {
+        if self.config.display_workspaces {
+            display::workspace_number(id)
+        } else {
+            id
+        }
+    }
+
+    /// Returns the color of the bar on the display, which is the color of its focused workspace if
+    /// that one has a color.
+    pub fn bar_color(&self, display_id: DisplayId) -> i32 {
+        let focused_id = if self.config.display_workspaces {
+            self.get_display_by_id(display_id)
+                .and_then(|d| d.focused_grid_id)
+        } else {
+            Some(self.workspace_id)
+        };
+
+        focused_id
+            .map(|id| self.workspace_number(id))
+            .and_then(|number| {
+                self.config
+                    .workspace_settings
+                    .iter()
+                    .find(|s| s.id == number)
+                    .and_then(|s| s.color)
+            })
+            .unwrap_or(self.config.bar.color)
+    }
+
     pub fn is_workspace_visible(&self, id: i32) -> bool {
         self.displays
             .iter()
@@ -598,21 +1495,97 @@ impl AppState {
             .is_some()
     }
 
-    pub fn change_workspace(&mut self, id: i32, _force: bool) {
+    /// `force` is set when the user explicitly asked for the workspace, in which case
+    /// `workspace_back_and_forth` may switch to the previous workspace instead.
+    pub fn change_workspace(&mut self, id: i32, force: bool) {
         let config = self.config.clone();
+        let system = self.system.clone();
         let current = self.get_current_display().id;
+        let prev_workspace_id = self.workspace_id;
+        let back_and_forth = force && config.workspace_back_and_forth && id == prev_workspace_id;
+        let id = match self.get_current_display().previous_grid_id {
+            Some(previous) if back_and_forth => previous,
+            _ => id,
+        };
         if let Some(d) = self.find_grid_display_mut(id) {
             let new = d.id;
-            d.focus_workspace(&config, id);
+            d.focus_workspace(&*system, &config, id);
             self.workspace_id = id;
+            if config.osd_workspace && prev_workspace_id != id {
+                self.show_osd(format!("Workspace {}", self.workspace_number(id)));
+            }
             self.redraw_app_bars();
             if current != new {
                 self.get_display_by_id(current)
                     .map(|d| d.refresh_grid(&config));
             }
+            if prev_workspace_id != id {
+                self.call_workspace_empty_hooks(id);
+            }
+        }
+    }
+
+    /// Calls the `on_workspace_empty` callbacks if the workspace doesn't contain any windows
+    fn call_workspace_empty_hooks(&self, id: i32) {
+        if !self.get_grid_by_id(id).map_or(false, |g| g.is_empty()) {
+            return;
+        }
+
+        for idx in self.config.on_workspace_empty.iter() {
+            self.event_channel
+                .sender
+                .send(Event::CallCallbackWithArgs {
+                    idx: *idx,
+                    args: vec![id.into()],
+                })
+                .expect("Failed to send CallCallbackWithArgs event");
+        }
+    }
+
+    /// Returns the workspaces that relative workspace switches cycle through, which are only the
+    /// ones of the current display if `display_workspaces` is enabled.
+    fn relative_workspace_ids(&self) -> Vec<i32> {
+        let grids = if self.config.display_workspaces {
+            self.get_current_display().grids.iter().collect()
+        } else {
+            self.get_grids()
+        };
+        let mut ids = grids.iter().map(|g| g.id).collect::<Vec<_>>();
+
+        ids.sort();
+        ids
+    }
+
+    /// Switches to the next or previous workspace that contains windows
+    pub fn focus_relative_workspace(&mut self, forward: bool) {
+        let ids = self.relative_workspace_ids();
+        let target = display::relative_workspace(&ids, self.workspace_id, forward, |id| {
+            self.get_grid_by_id(id).map_or(false, |g| !g.is_empty())
+        });
+
+        if let Some(id) = target {
+            self.change_workspace(id, false);
+        }
+    }
+
+    /// Switches to the workspace that was focused on the current display before the current one
+    pub fn focus_previous_workspace(&mut self) {
+        if let Some(id) = self.get_current_display().previous_grid_id {
+            self.change_workspace(id, false);
         }
     }
 
+    pub fn show_osd(&self, text: String) {
+        self.event_channel
+            .sender
+            .send(Event::ShowOsd(text))
+            .expect("Failed to send ShowOsd event");
+    }
+
+    /// Returns a handle for threads that shouldn't lock the state (see `state_handle`)
+    pub fn handle(&self) -> StateHandle {
+        StateHandle::new(self.event_channel.sender.clone())
+    }
     pub fn redraw_app_bars(&self) {
         debug!("Sending redraw-app-bar event");
         self.event_channel
@@ -629,6 +1602,50 @@ impl AppState {
         self.displays.iter_mut().find(|d| d.id == id)
     }
 
+    /// Hides or reveals the bar of the display. Only the visible workspace of the display gets
+    /// redrawn, the other ones use the new size once they get focused.
+    pub fn set_appbar_hidden(&mut self, display_id: DisplayId, hidden: bool) -> SystemResult {
+        let config = self.config.clone();
+
+        if let Some(display) = self.get_display_by_id_mut(display_id) {
+            if let Some(bar) = display.appbar.as_mut() {
+                if bar.hidden == hidden {
+                    return Ok(());
+                }
+
+                bar.hidden = hidden;
+
+                let window = bar.window.get_native_window();
+
+                if hidden {
+                    window.hide();
+                } else {
+                    window.show();
+                }
+
+                display.refresh_grid(&config)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn toggle_appbar(&mut self) -> SystemResult {
+        let display = self.get_current_display();
+        let display_id = display.id;
+        let hidden = display.appbar.as_ref().map(|b| b.hidden).unwrap_or(false);
+
+        self.set_appbar_hidden(display_id, !hidden)
+    }
+
+    /// Returns the index of the display, which is the inverse of `get_display_by_idx`.
+    pub fn get_display_idx(&self, id: DisplayId) -> Option<i32> {
+        self.displays
+            .iter()
+            .position(|d| d.id == id)
+            .map(|pos| (self.displays.len() - pos) as i32)
+    }
+
     pub fn get_display_by_idx(&self, idx: i32) -> Option<&Display> {
         let x: usize = if idx == -1 {
             0
@@ -703,13 +1720,21 @@ impl AppState {
             .collect()
     }
 
-    pub fn show_taskbars(&self) {
-        for tb in self.get_taskbars() {
+    pub fn show_taskbars(&mut self) {
+        for tb in self.get_taskbars_mut() {
+            tb.restore_auto_hide();
             tb.window.show();
         }
     }
 
-    pub fn hide_taskbars(&self) {
+    pub fn hide_taskbars(&mut self) {
+        if self.config.auto_hide_task_bar {
+            for tb in self.get_taskbars_mut() {
+                tb.enable_auto_hide();
+            }
+            return;
+        }
+
         // have to hide the taskbars in a specific order for it to work (I know like wtf)
 
         // first hide primary display
@@ -791,7 +1816,10 @@ fn on_quit(state: &mut AppState) -> SystemResult {
         state.show_taskbars();
     }
 
-    state.window_event_listener.stop();
+    journal::clear();
+
+    state.system.stop_hooks();
+    state.mouse_listener.stop();
 
     process::exit(0);
 }
@@ -827,6 +1855,7 @@ fn parse_config(
     let is_init = move || is_init_inner2.load(std::sync::atomic::Ordering::SeqCst);
 
     interpreter.debug = true;
+    interpreter.limits = config.lock().script_limits();
     interpreter.source_locations = interpreter_arc.lock().source_locations.clone();
     let root = nogscript::lib::create_root_module(
         is_init,
@@ -866,10 +1895,63 @@ fn parse_config(
 
     debug!("Running config file");
 
-    interpreter.execute_file(config_path)?;
+    let exports = interpreter.execute_file(config_path.clone())?;
+
+    // every function that the config exports can be executed using the command palette
+    for (name, f) in exports
+        .functions
+        .into_iter()
+        .sorted_by(|a, b| a.0.cmp(&b.0))
+    {
+        let mut cbs = callbacks_arc.lock();
+        config.lock().actions.push(Action::new(name, cbs.len()));
+        cbs.push(f);
+    }
+
+    // the profile that the config switched to or the first one that matches this machine gets
+    // applied on top of the rest of the config
+    let selected = {
+        let cfg = config.lock();
+        match &cfg.profile {
+            Some(name) => Some(
+                cfg.profiles
+                    .iter()
+                    .find(|p| &p.name == name)
+                    .cloned()
+                    .ok_or_else(|| format!("Unknown profile '{}'", name))?,
+            ),
+            None => profile::detect(
+                &cfg.profiles,
+                system::api::get_hostname().as_deref(),
+                system::api::get_displays().len(),
+            )
+            .cloned(),
+        }
+    };
+
+    if let Some(selected) = selected {
+        info!("Applying profile {}", selected.name);
+
+        let base = ProfileSettings::from_config(&config.lock());
+        let callback = callbacks_arc.lock()[selected.callback_id].clone();
+
+        callback
+            .invoke(&mut interpreter, vec![])
+            .map_err(|e| e.message(&interpreter.program()))?;
+
+        let mut cfg = config.lock();
+        cfg.profile = Some(selected.name);
+        cfg.profile_base = Some(base);
+    }
 
     is_init_inner.store(false, std::sync::atomic::Ordering::SeqCst);
 
+    interpreter.limits = config.lock().script_limits();
+    config::hot_reloading::set_files(
+        std::iter::once(config_path)
+            .chain(interpreter.module_cache.keys().cloned())
+            .chain(interpreter.included_files.iter().cloned()),
+    );
     *interpreter_arc.lock() = interpreter;
 
     let cfg = config.lock();
@@ -877,6 +1959,114 @@ fn parse_config(
     Ok(cfg.clone())
 }
 
+/// Set once the shutdown hooks ran, so that quitting again while they run (e.g. by pressing ctrl-c)
+/// doesn't call them twice
+static SHUTDOWN_HOOKS_RAN: AtomicBool = AtomicBool::new(false);
+
+/// Runs the shutdown hooks and exits. Every way of quitting goes through here, except for the
+/// deadlock detection, because the hooks would wait for the deadlocked state as well.
+fn quit(
+    state_arc: &Arc<Mutex<AppState>>,
+    callbacks_arc: &Arc<Mutex<Vec<Function>>>,
+    interpreter_arc: &Arc<Mutex<Interpreter>>,
+) -> SystemResult {
+    if !SHUTDOWN_HOOKS_RAN.swap(true, atomic::Ordering::SeqCst) {
+        run_shutdown_hooks(state_arc, callbacks_arc, interpreter_arc);
+    }
+
+    on_quit(&mut state_arc.lock())
+}
+
+/// Calls the callbacks of `nog.on_shutdown` in the order they got registered. The windows are
+/// still managed while they run.
+fn run_shutdown_hooks(
+    state_arc: &Arc<Mutex<AppState>>,
+    callbacks_arc: &Arc<Mutex<Vec<Function>>>,
+    interpreter_arc: &Arc<Mutex<Interpreter>>,
+) {
+    let ids = state_arc.lock().config.on_shutdown.clone();
+
+    for idx in ids {
+        let cb = match callbacks_arc.lock().get(idx) {
+            Some(cb) => cb.clone(),
+            None => continue,
+        };
+        let result = cb.invoke(&mut interpreter_arc.lock(), vec![]);
+
+        if let Err(e) = result {
+            error!("{}", e.message(&interpreter_arc.lock().program()));
+        }
+    }
+}
+
+/// Shows the error in a popup that opens the file at fault once it gets clicked. `file` is the file
+/// that was running when the error happened, which defaults to `config.ns`.
+fn show_config_error(state_arc: &Arc<Mutex<AppState>>, msg: String, file: Option<PathBuf>) {
+    if config::error_popup::is_repeated(&msg) {
+        return;
+    }
+
+    let file = file.unwrap_or_else(|| {
+        let mut path = dirs::config_dir().unwrap_or_default();
+        path.push("nog");
+        path.push("config.ns");
+        path
+    });
+    let state = state_arc.lock();
+    let popup = config::error_popup::create(msg, &file, &state.config);
+
+    state
+        .event_channel
+        .sender
+        .send(Event::NewPopup(popup))
+        .expect("Failed to send NewPopup event");
+}
+
+/// Replaces the config with a freshly loaded one. The previous config stays active if the new one
+/// fails to load, including the callbacks its keybindings refer to. Errors of an automatic reload
+/// are shown in an osd, because the user is probably still editing the config.
+fn reload_config(
+    state_arc: &Arc<Mutex<AppState>>,
+    callbacks_arc: &Arc<Mutex<Vec<Function>>>,
+    interpreter_arc: &Arc<Mutex<Interpreter>>,
+    is_auto_reload: bool,
+) -> SystemResult {
+    info!("Reloading Config");
+    config::error_popup::reset();
+
+    let callbacks = callbacks_arc.lock().clone();
+
+    match parse_config(
+        state_arc.clone(),
+        callbacks_arc.clone(),
+        interpreter_arc.clone(),
+    ) {
+        Ok(new_config) => {
+            let result = update_config(state_arc.clone(), new_config);
+
+            // the values of the previous config that reference each other would only get freed by
+            // the next automatic collection, which might not run for a long time
+            drop(callbacks);
+            debug!("Freed {} containers", interpreter::collect_garbage());
+
+            result
+        }
+        Err(e) => {
+            *callbacks_arc.lock() = callbacks;
+
+            if is_auto_reload {
+                let text = format!("Failed to reload the config: {}", e);
+                let sender = state_arc.lock().event_channel.sender.clone();
+                let _ = sender.send(Event::ShowOsd(text));
+            } else {
+                show_config_error(state_arc, e, None);
+            }
+
+            Ok(())
+        }
+    }
+}
+
 fn run(
     state_arc: Arc<Mutex<AppState>>,
     callbacks_arc: Arc<Mutex<Vec<Function>>>,
@@ -884,9 +2074,19 @@ fn run(
 ) -> Result<(), Box<dyn std::error::Error>> {
     let receiver = state_arc.lock().event_channel.receiver.clone();
     let sender = state_arc.lock().event_channel.sender.clone();
+    let handle = state_arc.lock().handle();
 
     info!("Starting hot reloading of config");
-    config::hot_reloading::start(state_arc.clone());
+    config::hot_reloading::start(handle.clone());
+
+    info!("Starting scheduler");
+    scheduler::start(handle.clone());
+
+    info!("Starting auto hiding of the bar");
+    bar::auto_hide::start(handle.clone());
+
+    info!("Starting game mode detection");
+    game_mode::start(handle);
 
     startup::set_launch_on_startup(state_arc.lock().config.launch_on_startup);
 
@@ -898,10 +2098,20 @@ fn run(
         .keybindings_manager
         .start(state_arc.clone());
 
-    if state_arc.lock().config.work_mode {
+    if state_arc.lock().work_mode {
         AppState::enter_work_mode(state_arc.clone())?;
     }
 
+    // the bars exist and the workspaces got restored at this point
+    for idx in state_arc.lock().config.on_startup.clone() {
+        sender
+            .send(Event::CallCallback {
+                idx,
+                is_mode_callback: false,
+            })
+            .expect("Failed to send CallCallback event");
+    }
+
     loop {
         select! {
             recv(receiver) -> maybe_msg => {
@@ -911,6 +2121,8 @@ fn run(
                         p.create(state_arc.clone())?;
                         Ok(())
                     },
+                    Event::ShowOsd(text) => osd::show(text, state_arc.clone()),
+                    Event::PinOsd(text) => osd::pin(text, state_arc.clone()),
                     Event::ToggleAppbar(display_id) => {
                         let window = state_arc
                             .clone()
@@ -932,17 +2144,45 @@ fn run(
                     },
                     Event::Keybinding(kb) => {
                         debug!("Received keybinding {:?}", kb);
-                        sender.send(Event::CallCallback { idx: kb.callback_id, is_mode_callback: false } ).unwrap();
+                        keybindings::watchdog::dequeue();
+                        match kb.invocation {
+                            Some(invocation) => sender.send(Event::RunAction(invocation)).unwrap(),
+                            None => sender.send(Event::CallCallback { idx: kb.callback_id, is_mode_callback: false } ).unwrap(),
+                        }
                         Ok(())
                     },
+                    Event::RunAction(invocation) => {
+                        info!("Running action {}", invocation);
+                        invocation.dispatch(&mut state_arc.lock())
+                    },
+                    Event::RunPipeline(pipeline) => {
+                        info!("Running pipeline {}", pipeline);
+                        pipeline.run(&mut state_arc.lock())
+                    },
                     Event::ConfigError(err) => {
-                        error!("{}", err.message(&interpreter_arc.lock().program()));
+                        let interpreter = interpreter_arc.lock();
+                        let msg = err.message(&interpreter.program());
+                        let file = Some(interpreter.file_path.clone()).filter(|f| f.is_file());
+                        drop(interpreter);
+
+                        error!("{}", msg);
+                        show_config_error(&state_arc, msg, file);
 
                         Ok(())
                     }
                     Event::CallCallback { idx, is_mode_callback } => {
                         let cb = callbacks_arc.lock().get(idx).unwrap().clone();
-                        if let Err(e) = cb.invoke(&mut interpreter_arc.lock(), vec![]) {
+                        let timeout = state_arc.lock().config.keybinding_timeout;
+                        let mut interpreter = interpreter_arc.lock();
+                        let started = Instant::now();
+                        keybindings::watchdog::start(idx, timeout);
+                        let result = cb.invoke(&mut interpreter, vec![]);
+                        nogscript::stats::record(idx, started.elapsed());
+                        drop(interpreter);
+                        if let Some((_, elapsed)) = keybindings::watchdog::finish() {
+                            warn!("Callback {} took {:?}, so its keybindings are disabled for {:?}", idx, elapsed, keybindings::watchdog::COOLDOWN);
+                        }
+                        if let Err(e) = result {
                             state_arc.lock().event_channel.sender.send(Event::ConfigError(e)).unwrap();
                         }
                         if is_mode_callback {
@@ -950,8 +2190,36 @@ fn run(
                         }
                         Ok(())
                     },
+                    Event::CallCallbackWithArgs { idx, args } => {
+                        let cb = callbacks_arc.lock().get(idx).unwrap().clone();
+                        let mut interpreter = interpreter_arc.lock();
+                        let started = Instant::now();
+                        let result = cb.invoke(&mut interpreter, args);
+                        nogscript::stats::record(idx, started.elapsed());
+                        drop(interpreter);
+                        if let Err(e) = result {
+                            state_arc.lock().event_channel.sender.send(Event::ConfigError(e)).unwrap();
+                        }
+                        Ok(())
+                    },
+                    Event::Update(update) => {
+                        update.run(&mut state_arc.lock());
+                        Ok(())
+                    },
+                    Event::CallFunction { function, args } => {
+                        let result = function.invoke(&mut interpreter_arc.lock(), args);
+                        if let Err(e) = result {
+                            state_arc.lock().event_channel.sender.send(Event::ConfigError(e)).unwrap();
+                        }
+                        Ok(())
+                    },
                     Event::RedrawAppBar => {
-                        let windows = state_arc.lock().displays.iter().map(|d| d.appbar.as_ref()).flatten().map(|b| b.window.clone()).collect::<Vec<Window>>();
+                        let state = state_arc.lock();
+                        // the mode could have changed without going through the event loop, so the
+                        // bars get a fresh snapshot before they redraw
+                        snapshot::publish(&state);
+                        let windows = state.displays.iter().map(|d| d.appbar.as_ref()).flatten().map(|b| b.window.clone()).collect::<Vec<Window>>();
+                        drop(state);
 
                         for window in windows {
                             window.redraw();
@@ -960,22 +2228,19 @@ fn run(
                         Ok(())
                     },
                     Event::WinEvent(ev) => event_handler::winevent::handle(&mut state_arc.lock(), ev),
+                    Event::WinEvents(evs) => event_handler::winevent::handle_batch(&mut state_arc.lock(), evs),
+                    Event::Mouse(ev) => event_handler::mouse::handle(&mut state_arc.lock(), ev),
                     Event::Exit => {
-                        on_quit(&mut state_arc.lock())?;
+                        quit(&state_arc, &callbacks_arc, &interpreter_arc)?;
                         break;
                     },
-                    Event::ReloadConfig => {
-                        info!("Reloading Config");
-                        match parse_config(state_arc.clone(), callbacks_arc.clone(), interpreter_arc.clone()) {
-                            Ok(new_config) => update_config(state_arc.clone(), new_config),
-                            Err(e) => {
-                                sender.send(Event::NewPopup(Popup::new_error(vec![e])));
-                                Ok(())
-                            }
-
-                        }
+                    Event::ConfigChanged(field, old_config) => {
+                        debug!("Setting {} changed", field);
+                        hot_reload::apply_changes(state_arc.clone(), &old_config)
                     },
-                    Event::UpdateBarSections(display_id, left, center, right) => {
+                    Event::ReloadConfig => reload_config(&state_arc, &callbacks_arc, &interpreter_arc, false),
+                    Event::AutoReloadConfig => reload_config(&state_arc, &callbacks_arc, &interpreter_arc, true),
+                    Event::UpdateBarSections(display_id, left, center, right, color) => {
                         let mut state = state_arc.lock();
                         for d in state.displays.iter_mut() {
                             if d.id == display_id {
@@ -983,20 +2248,43 @@ fn run(
                                     bar.left = left;
                                     bar.center = center;
                                     bar.right = right;
+                                    bar.color = color;
                                     break;
                                 }
                             }
                         }
                         Ok(())
                     },
+                    Event::SetAppbarHidden(display_id, hidden) => {
+                        state_arc.lock().set_appbar_hidden(display_id, hidden)
+                    }
+                    Event::ToggleWorkMode => AppState::toggle_work_mode(state_arc.clone()),
                     Event::ChangeWorkspace(id, force) => {
                         state_arc.lock().change_workspace(id, force);
                         Ok(())
                     }
+                    Event::MoveWorkspaceToDisplay(id, display_id) => {
+                        state_arc.lock().move_workspace_to_display(id, display_id)
+                    }
                 }.map_err(|e| {
                     error!("{:?}", e);
                     crate::system::win::api::print_last_error();
                 });
+
+                // the workspace can change in a lot of ways, so the keybindings that are
+                // restricted to a workspace get updated after every event
+                let mut state = state_arc.lock();
+                if workspace_policy::apply(&mut state) {
+                    state.redraw_app_bars();
+                }
+                // the indicator of a split stays until a window got pushed into it
+                if !state.get_grids().iter().any(|g| g.pending_split.is_some()) {
+                    if let Err(e) = osd::unpin() {
+                        error!("Failed to close the split indicator {:?}", e);
+                    }
+                }
+                state.keybindings_manager.set_workspace(state.workspace_id);
+                snapshot::publish(&state);
             }
         }
     }
@@ -1038,7 +2326,98 @@ fn main() {
     std::env::set_var("RUST_BACKTRACE", "1");
     logging::setup().expect("Failed to setup logging");
 
+    let raw_args = std::env::args().skip(1).collect::<Vec<String>>();
+    let args = ipc::Args::parse(raw_args.clone().into_iter());
+
+    if args.prints_output() {
+        system::api::attach_console();
+    }
+
+    if let Some(count) = args.logs {
+        match ipc::request_logs(count) {
+            Ok(logs) => println!("{}", logs),
+            Err(e) => eprintln!("Failed to get the logs of the running instance {:?}", e),
+        }
+        return;
+    }
+
+    if let Some(delay) = args.test_rules {
+        match ipc::request_rule_check(std::time::Duration::from_secs(delay)) {
+            Ok(report) => println!("{}", report),
+            Err(e) => eprintln!("Failed to reach the running instance {:?}", e),
+        }
+        return;
+    }
+
+    if let Some(dir) = &args.test {
+        let code = nogscript::test_runner::run(dir.as_ref().map(PathBuf::from));
+        std::process::exit(code);
+    }
+
+    if args.subscribe {
+        if let Err(e) = ipc::subscribe(|line| println!("{}", line)) {
+            eprintln!("Failed to reach the running instance {:?}", e);
+        }
+        return;
+    }
+
+    if let Some(path) = &args.replay {
+        match std::fs::read_to_string(path).map_err(|e| e.to_string()) {
+            Ok(content) => match debug_dump::replay(&content) {
+                Ok(output) => print!("{}", output),
+                Err(e) => eprintln!("Failed to replay {}: {}", path, e),
+            },
+            Err(e) => eprintln!("Failed to read {}: {}", path, e),
+        }
+        return;
+    }
+
+    if let Some(path) = &args.import_i3 {
+        match std::fs::read_to_string(path) {
+            Ok(content) => print!("{}", i3::translate(&content)),
+            Err(e) => eprintln!("Failed to read {}: {}", path, e),
+        }
+        return;
+    }
+
+    if args.cleanup {
+        if ipc::is_running() {
+            // quitting makes the running instance restore its windows
+            if let Err(e) = ipc::forward(&["quit".into()]) {
+                error!("Failed to forward the arguments {:?}", e);
+            }
+        } else {
+            journal::restore();
+        }
+        return;
+    }
+
+    let server = match ipc::listen() {
+        Ok(Some(server)) => Some(server),
+        Ok(None) => {
+            info!("Nog is already running. Forwarding the arguments");
+            if let Err(e) = ipc::forward(&raw_args) {
+                error!("Failed to forward the arguments {:?}", e);
+            }
+            return;
+        }
+        Err(e) => {
+            error!("Failed to start listening for other instances {:?}", e);
+            None
+        }
+    };
+
+    journal::restore();
+
     let state_arc = Arc::new(Mutex::new(AppState::default()));
+    let token = server.as_ref().map(|server| server.token().to_string());
+
+    // later launches get answered while the config loads, the events they cause wait for the
+    // event loop
+    if let Some(server) = server {
+        ipc::start(server, state_arc.lock().handle());
+    }
+
     let callbacks_arc: Arc<Mutex<Vec<Function>>> = Arc::new(Mutex::new(Vec::new()));
     let mut interpreter = Interpreter::new();
 
@@ -1052,10 +2431,7 @@ fn main() {
             callbacks_arc.clone(),
             interpreter_arc.clone(),
         )
-        .map_err(|e| {
-            let state_arc = state_arc.clone();
-            Popup::error(vec![e], state_arc);
-        })
+        .map_err(|e| show_config_error(&state_arc, e, None))
         .unwrap_or_else(|_| {
             let mut config = Config::default();
             config.bar.use_default_components(state_arc.clone());
@@ -1065,6 +2441,20 @@ fn main() {
         state_arc.lock().init(config)
     }
 
+    if args.hidden {
+        state_arc.lock().work_mode = false;
+    }
+
+    ipc::run(&args, &state_arc.lock());
+
+    let komorebi_port = state_arc.lock().config.komorebi_port;
+    if komorebi_port > 0 {
+        match token {
+            Some(token) => komorebi::start(komorebi_port, token, state_arc.lock().handle()),
+            None => error!("The komorebi messages need the ipc session, which failed to start"),
+        }
+    }
+
     let arc = state_arc.clone();
 
     thread::spawn(move || loop {
@@ -1088,23 +2478,34 @@ fn main() {
 
     info!("");
 
-    let arc = state_arc.clone();
+    let (state, callbacks, interpreter) = (
+        state_arc.clone(),
+        callbacks_arc.clone(),
+        interpreter_arc.clone(),
+    );
     ctrlc::set_handler(move || {
-        if let Err(e) = on_quit(&mut arc.lock()) {
+        if let Err(e) = quit(&state, &callbacks, &interpreter) {
             error!("Something happend when cleaning up. {}", e);
         }
     })
     .unwrap();
 
-    let arc = state_arc.clone();
-    if let Err(e) = run(
-        state_arc.clone(),
-        callbacks_arc.clone(),
-        interpreter_arc.clone(),
-    ) {
-        error!("An error occured {:?}", e);
-        if let Err(e) = on_quit(&mut arc.lock()) {
-            error!("Something happend when cleaning up. {}", e);
-        }
+    // a panic of the event loop cleans up as well, so that no window stays hidden
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        run(
+            state_arc.clone(),
+            callbacks_arc.clone(),
+            interpreter_arc.clone(),
+        )
+    }));
+
+    match result {
+        Ok(Ok(())) => return,
+        Ok(Err(e)) => error!("An error occured {:?}", e),
+        Err(_) => error!("The event loop panicked"),
+    }
+
+    if let Err(e) = quit(&state_arc, &callbacks_arc, &interpreter_arc) {
+        error!("Something happend when cleaning up. {}", e);
     }
 }
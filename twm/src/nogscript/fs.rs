@@ -0,0 +1,157 @@
+//! Resolves the paths that get passed to `nog.fs`. Relative paths start at the config directory and
+//! unless `unrestricted_fs` is enabled every path has to stay inside of it, so that a plugin can't
+//! read or overwrite arbitrary files. Scripts can't be written either, because a plugin could
+//! otherwise enable `unrestricted_fs` in `config.ns` and get it applied by the next reload.
+use std::path::{Component, Path, PathBuf};
+
+/// Removes the `.` and `..` components of the path without touching the file system, because the
+/// file might not exist yet.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            x => normalized.push(x.as_os_str()),
+        }
+    }
+
+    normalized
+}
+
+/// Resolves the symbolic links and junctions of the longest part of the path that exists, so that
+/// a link inside of the root can't lead outside of it. The rest of the path doesn't exist yet and
+/// gets appended as is.
+fn canonicalize_existing(path: &Path) -> PathBuf {
+    let mut existing = path.to_path_buf();
+    let mut rest = Vec::new();
+
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            return rest
+                .iter()
+                .rev()
+                .fold(canonical, |path, name| path.join(name));
+        }
+
+        match (existing.file_name().map(|n| n.to_os_string()), existing.parent()) {
+            (Some(name), Some(parent)) => {
+                rest.push(name);
+                existing = parent.to_path_buf();
+            }
+            _ => return path.to_path_buf(),
+        }
+    }
+}
+
+pub fn resolve(root: &Path, path: &str, unrestricted: bool) -> Result<PathBuf, String> {
+    let resolved = normalize(&root.join(path));
+
+    if unrestricted {
+        return Ok(resolved);
+    }
+
+    let canonical = canonicalize_existing(&resolved);
+
+    if canonical.starts_with(canonicalize_existing(&normalize(root))) {
+        Ok(canonical)
+    } else {
+        Err(format!(
+            "'{}' is outside of the config directory. Enable unrestricted_fs to allow this",
+            path
+        ))
+    }
+}
+
+/// Whether the file is a nogscript file. Windows ignores trailing dots and spaces of a file name and
+/// `:` starts an alternate data stream, so `config.ns.` and `config.ns:x` are scripts too.
+fn is_script(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.split(':').next())
+        .map(|name| {
+            name.trim_end_matches(['.', ' '])
+                .to_lowercase()
+                .ends_with(".ns")
+        })
+        .unwrap_or(false)
+}
+
+/// Like `resolve`, but also refuses scripts unless `unrestricted_fs` is enabled
+pub fn resolve_writable(root: &Path, path: &str, unrestricted: bool) -> Result<PathBuf, String> {
+    let resolved = resolve(root, path, unrestricted)?;
+
+    if !unrestricted && is_script(&resolved) {
+        Err(format!(
+            "'{}' is a script and can't be written. Enable unrestricted_fs to allow this",
+            path
+        ))
+    } else {
+        Ok(resolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_stay_inside_the_root() {
+        let root = Path::new("/config/nog");
+
+        assert_eq!(
+            resolve(root, "state/names.json", false),
+            Ok(PathBuf::from("/config/nog/state/names.json"))
+        );
+        assert_eq!(
+            resolve(root, "./a/../b.txt", false),
+            Ok(PathBuf::from("/config/nog/b.txt"))
+        );
+        assert!(resolve(root, "../other/file.txt", false).is_err());
+        assert!(resolve(root, "/etc/hosts", false).is_err());
+        assert_eq!(
+            resolve(root, "../other/file.txt", true),
+            Ok(PathBuf::from("/config/other/file.txt"))
+        );
+    }
+
+    #[test]
+    fn scripts_arent_writable() {
+        let root = Path::new("/config/nog");
+
+        assert!(resolve_writable(root, "state/names.json", false).is_ok());
+        assert!(resolve_writable(root, "config.ns", false).is_err());
+        assert!(resolve_writable(root, "plugins/bar/BAR.NS", false).is_err());
+        assert!(resolve_writable(root, "config.ns. ", false).is_err());
+        assert!(resolve_writable(root, "config.ns:stream", false).is_err());
+        assert!(resolve_writable(root, "config.ns", true).is_ok());
+    }
+
+    #[test]
+    fn links_cant_leave_the_root() {
+        let dir = std::env::temp_dir().join(format!("nog_fs_test_{}", std::process::id()));
+        let root = dir.join("config");
+        let outside = dir.join("outside");
+
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+
+        #[cfg(unix)]
+        let linked = std::os::unix::fs::symlink(&outside, root.join("link"));
+        #[cfg(windows)]
+        let linked = std::os::windows::fs::symlink_dir(&outside, root.join("link"));
+
+        // creating links on windows requires the developer mode
+        if linked.is_ok() {
+            assert!(resolve(&root, "link/secrets.txt", false).is_err());
+            assert!(resolve(&root, "link/new/file.txt", false).is_err());
+        }
+
+        assert!(resolve(&root, "state/new.json", false).is_ok());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
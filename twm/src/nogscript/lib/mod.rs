@@ -1,27 +1,153 @@
-use crate::update_config;
+use crate::hot_reload::change_setting;
 use crate::{
+    actions,
+    actions::Invocation,
+    bar,
     bar::component,
-    bar::component::{Component, ComponentText},
-    config::{workspace_setting::WorkspaceSetting, Config},
+    bar::component::{layout_indicator::LayoutLabels, Component, ComponentText},
+    borderless,
+    command_palette::Action,
+    config::{
+        bar_config::DisplayBarConfig,
+        fancyzones,
+        launch_policy::LaunchPolicy,
+        profile::{Profile, ProfileSettings},
+        reserved_space::ReservedSpace,
+        template::{Placeholder, Template, TemplateNode},
+        workspace_policy::WorkspacePolicy,
+        workspace_setting::WorkspaceSetting,
+        Config,
+    },
+    debug_dump,
     direction::Direction,
-    keybindings::keybinding::Keybinding,
+    display, hidden, http,
+    keybindings::{keybinding::Keybinding, table},
+    launcher, logging,
+    nogscript::{fs, stats},
+    pinned,
+    rule_check::RuleCheck,
+    scheduler::{Job, Schedule},
+    snapshot,
     split_direction::SplitDirection,
-    system, window, AppState, Event, Rule,
+    system,
+    system::{MediaKey, SystemResult},
+    templates,
+    tile_grid::{history, meta, placements, snapshots},
+    window, AppState, Event, Rule, BAR_DRAG_MODE, COMMAND_PALETTE_MODE, OVERVIEW_MODE, RESIZE_MODE,
+    WINDOW_SEARCH_MODE,
 };
 use crate::{get_plugins_path_iter, popup::Popup};
-use interpreter::{Dynamic, Function, Interpreter, Module, RuntimeError};
+use chrono::Local;
+use interpreter::{Dynamic, Function, Interpreter, Module, RuntimeError, RuntimeResult};
 use itertools::Itertools;
-use log::debug;
+use log::{debug, warn};
 use parking_lot::Mutex;
 use regex::Regex;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
 use std::time::Duration;
 
-fn kb_from_args(callbacks_arc: Arc<Mutex<Vec<Function>>>, args: Vec<Dynamic>) -> Keybinding {
+/// Parses a font, which is either the name of a font or an array of fonts in the order they should
+/// be tried in.
+fn font_list_from_dynamic(value: &Dynamic) -> RuntimeResult<String> {
+    match value {
+        Dynamic::Array(_) => Ok(value
+            .clone()
+            .as_array()?
+            .iter()
+            .map(|font| Ok(string!(font)?.clone()))
+            .collect::<RuntimeResult<Vec<_>>>()?
+            .join(", ")),
+        value => Ok(string!(value)?.clone()),
+    }
+}
+
+/// Parses a node of a template, which is either `#{ columns: [..] }`, `#{ rows: [..] }` or a
+/// placeholder like `#{ exe: "code.exe" }`. Every node can have a `size`. A placeholder without an
+/// exe is filled by any window of the workspace.
+fn template_node_from_dynamic(value: &Dynamic) -> RuntimeResult<TemplateNode> {
+    let fields_ref = object!(value)?;
+    let fields = fields_ref.lock().unwrap();
+
+    let size = match fields.get("size") {
+        Some(size) => (*number!(size)?).max(1) as u32,
+        None => 1,
+    };
+
+    let children = |val: &Dynamic| -> RuntimeResult<Vec<TemplateNode>> {
+        val.clone()
+            .as_array()?
+            .iter()
+            .map(template_node_from_dynamic)
+            .collect()
+    };
+
+    if let Some(columns) = fields.get("columns") {
+        return Ok(TemplateNode::Columns(size, children(columns)?));
+    }
+
+    if let Some(rows) = fields.get("rows") {
+        return Ok(TemplateNode::Rows(size, children(rows)?));
+    }
+
+    let mut placeholder = match fields.get("exe") {
+        Some(exe) => Placeholder::new(string!(exe)?),
+        None => Placeholder::any(),
+    };
+
+    if let Some(title) = fields.get("title") {
+        placeholder.title = Some(Regex::new(string!(title)?).map_err(|e| e.to_string())?);
+    }
+
+    if let Some(cmd) = fields.get("cmd") {
+        placeholder.cmd = Some(string!(cmd)?.clone());
+    }
+
+    Ok(TemplateNode::Window(size, placeholder))
+}
+
+/// Runs the named action the same way a keybinding or the command palette would.
+fn dispatch(state: &Mutex<AppState>, name: &str, args: Vec<i32>) -> RuntimeResult {
+    Invocation::new(name, args)
+        .dispatch(&mut state.lock())
+        .map_err(|e| e.to_string())?;
+
+    Ok(Dynamic::Null)
+}
+
+/// Returns the name of the action that goes into the direction, e.g. `focus_left` for `"Left"`.
+fn directed_action(prefix: &str, direction: &Dynamic) -> RuntimeResult<String> {
+    let text = string!(direction)?;
+    let direction =
+        Direction::from_str(text).map_err(|_| format!("Unknown direction {}", text))?;
+
+    Ok(format!("{}_{}", prefix, format!("{:?}", direction).to_lowercase()))
+}
+
+/// Returns `<function name>` or `<anonymous function>` for functions without a name
+fn describe_function(f: &Function) -> String {
+    if f.name.starts_with('<') {
+        f.name.clone()
+    } else {
+        format!("<function {}>", f.name)
+    }
+}
+
+fn kb_from_args(
+    callbacks_arc: Arc<Mutex<Vec<Function>>>,
+    args: Vec<Dynamic>,
+) -> RuntimeResult<Keybinding> {
     let mut kb = Keybinding::from_str(&args[0].clone().as_str().unwrap()).unwrap();
     match &args[1] {
+        Dynamic::String(action) => {
+            kb.invocation = Some(action.parse::<Invocation>()?);
+        }
         Dynamic::Function {
             body,
             scope,
@@ -67,7 +193,386 @@ fn kb_from_args(callbacks_arc: Arc<Mutex<Vec<Function>>>, args: Vec<Dynamic>) ->
         }
     }
 
-    kb
+    if let Some(Dynamic::String(description)) = args.get(3) {
+        kb.description = Some(description.clone());
+    }
+
+    Ok(kb)
+}
+
+/// Adds a keybinding to the mode that executes the given rust function.
+fn add_mode_keybinding(
+    state_arc: Arc<Mutex<AppState>>,
+    callbacks_arc: Arc<Mutex<Vec<Function>>>,
+    mode: &str,
+    key: &str,
+    action: impl Fn(&mut AppState) -> SystemResult + Send + Sync + 'static,
+) {
+    let state = state_arc.clone();
+    let args = vec![
+        key.into(),
+        Dynamic::RustFunction {
+            name: format!("{}_mode_{}", mode, key),
+            callback: Arc::new(move |_, _| {
+                action(&mut state.lock()).map_err(|e| e.to_string())?;
+                Ok(Dynamic::Null)
+            }),
+            scope: None,
+        },
+    ];
+
+    let mut kb = kb_from_args(callbacks_arc, args).expect("Failed to create mode keybinding");
+    kb.mode = Some(mode.into());
+    state_arc.lock().keybindings_manager.add_mode_keybinding(kb);
+}
+
+/// Registers the handler of the built-in resize mode. This has to happen before the config gets
+/// executed, so that a user defined `nog.mode("resize", ...)` replaces it.
+fn register_resize_mode(
+    state_arc: Arc<Mutex<AppState>>,
+    callbacks_arc: Arc<Mutex<Vec<Function>>>,
+    config: Arc<Mutex<Config>>,
+) {
+    let cbs = callbacks_arc.clone();
+    let state = state_arc.clone();
+
+    let handler = Function::new("resize_mode_handler", None, move |_, _| {
+        let bindings: Vec<(&str, fn(&mut AppState) -> SystemResult)> = vec![
+            ("H", |s| s.resize_step(Direction::Left, true)),
+            ("J", |s| s.resize_step(Direction::Down, true)),
+            ("K", |s| s.resize_step(Direction::Up, true)),
+            ("L", |s| s.resize_step(Direction::Right, true)),
+            ("Shift+H", |s| s.resize_step(Direction::Left, false)),
+            ("Shift+J", |s| s.resize_step(Direction::Down, false)),
+            ("Shift+K", |s| s.resize_step(Direction::Up, false)),
+            ("Shift+L", |s| s.resize_step(Direction::Right, false)),
+            ("Enter", |s| {
+                s.toggle_mode(RESIZE_MODE.into());
+                Ok(())
+            }),
+            ("Escape", |s| {
+                s.cancel_mode(RESIZE_MODE);
+                Ok(())
+            }),
+        ];
+
+        for (key, action) in bindings {
+            add_mode_keybinding(state.clone(), cbs.clone(), RESIZE_MODE, key, action);
+        }
+
+        Ok(Dynamic::Null)
+    });
+
+    let idx = callbacks_arc.lock().len();
+    callbacks_arc.lock().push(handler);
+    config.lock().mode_handlers.insert(RESIZE_MODE.into(), idx);
+}
+
+/// Adds a keybinding to the mode for every letter, digit and the space key that passes the typed
+/// character to `push`.
+fn add_typing_keybindings(
+    state_arc: Arc<Mutex<AppState>>,
+    callbacks_arc: Arc<Mutex<Vec<Function>>>,
+    mode: &str,
+    push: fn(&mut AppState, char),
+) {
+    let letters = ('A'..='Z').map(|c| (c.to_string(), c.to_ascii_lowercase()));
+    let digits = [
+        "Zero", "One", "Two", "Three", "Four", "Five", "Six", "Seven", "Eight", "Nine",
+    ]
+    .iter()
+    .zip('0'..='9')
+    .map(|(key, c)| (key.to_string(), c));
+
+    for (key, c) in letters
+        .chain(digits)
+        .chain(std::iter::once(("Space".into(), ' ')))
+    {
+        add_mode_keybinding(
+            state_arc.clone(),
+            callbacks_arc.clone(),
+            mode,
+            &key,
+            move |s| {
+                push(s, c);
+                Ok(())
+            },
+        );
+    }
+}
+
+/// Registers the handler of the mode that is active while the command palette is open. Every
+/// letter, digit and the space key get added to the query.
+fn register_command_palette_mode(
+    state_arc: Arc<Mutex<AppState>>,
+    callbacks_arc: Arc<Mutex<Vec<Function>>>,
+    config: Arc<Mutex<Config>>,
+) {
+    let cbs = callbacks_arc.clone();
+    let state = state_arc.clone();
+
+    let handler = Function::new("command_palette_mode_handler", None, move |_, _| {
+        add_typing_keybindings(state.clone(), cbs.clone(), COMMAND_PALETTE_MODE, |s, c| {
+            s.update_command_palette(|p, _| p.push(c))
+        });
+
+        let bindings: Vec<(&str, fn(&mut AppState) -> SystemResult)> = vec![
+            ("Backspace", |s| {
+                s.update_command_palette(|p, _| p.pop());
+                Ok(())
+            }),
+            ("Down", |s| {
+                s.update_command_palette(|p, actions| p.select_next(actions));
+                Ok(())
+            }),
+            ("Tab", |s| {
+                s.update_command_palette(|p, actions| p.select_next(actions));
+                Ok(())
+            }),
+            ("Up", |s| {
+                s.update_command_palette(|p, actions| p.select_prev(actions));
+                Ok(())
+            }),
+            ("Enter", |s| {
+                s.execute_command_palette_selection();
+                s.toggle_mode(COMMAND_PALETTE_MODE.into());
+                Ok(())
+            }),
+            ("Escape", |s| {
+                s.toggle_mode(COMMAND_PALETTE_MODE.into());
+                Ok(())
+            }),
+        ];
+
+        for (key, action) in bindings {
+            add_mode_keybinding(
+                state.clone(),
+                cbs.clone(),
+                COMMAND_PALETTE_MODE,
+                key,
+                action,
+            );
+        }
+
+        Ok(Dynamic::Null)
+    });
+
+    let idx = callbacks_arc.lock().len();
+    callbacks_arc.lock().push(handler);
+    config
+        .lock()
+        .mode_handlers
+        .insert(COMMAND_PALETTE_MODE.into(), idx);
+}
+
+/// Registers the handler of the mode that is active while the window search is open. Typing works
+/// like in the command palette.
+fn register_window_search_mode(
+    state_arc: Arc<Mutex<AppState>>,
+    callbacks_arc: Arc<Mutex<Vec<Function>>>,
+    config: Arc<Mutex<Config>>,
+) {
+    let cbs = callbacks_arc.clone();
+    let state = state_arc.clone();
+
+    let handler = Function::new("window_search_mode_handler", None, move |_, _| {
+        add_typing_keybindings(state.clone(), cbs.clone(), WINDOW_SEARCH_MODE, |s, c| {
+            s.update_window_search(|w| w.push(c))
+        });
+
+        let bindings: Vec<(&str, fn(&mut AppState) -> SystemResult)> = vec![
+            ("Backspace", |s| {
+                s.update_window_search(|w| w.pop());
+                Ok(())
+            }),
+            ("Down", |s| {
+                s.update_window_search(|w| w.select_next());
+                Ok(())
+            }),
+            ("Tab", |s| {
+                s.update_window_search(|w| w.select_next());
+                Ok(())
+            }),
+            ("Up", |s| {
+                s.update_window_search(|w| w.select_prev());
+                Ok(())
+            }),
+            ("Enter", |s| {
+                let selection = s.window_search_selection();
+                s.toggle_mode(WINDOW_SEARCH_MODE.into());
+
+                match selection {
+                    Some(id) => s.focus_window(id),
+                    None => Ok(()),
+                }
+            }),
+            ("Escape", |s| {
+                s.toggle_mode(WINDOW_SEARCH_MODE.into());
+                Ok(())
+            }),
+        ];
+
+        for (key, action) in bindings {
+            add_mode_keybinding(state.clone(), cbs.clone(), WINDOW_SEARCH_MODE, key, action);
+        }
+
+        Ok(Dynamic::Null)
+    });
+
+    let idx = callbacks_arc.lock().len();
+    callbacks_arc.lock().push(handler);
+    config
+        .lock()
+        .mode_handlers
+        .insert(WINDOW_SEARCH_MODE.into(), idx);
+}
+
+/// Registers the handler of the mode that is active while the overview is open. The digits
+/// switch to the workspace with that number right away.
+fn register_overview_mode(
+    state_arc: Arc<Mutex<AppState>>,
+    callbacks_arc: Arc<Mutex<Vec<Function>>>,
+    config: Arc<Mutex<Config>>,
+) {
+    let cbs = callbacks_arc.clone();
+    let state = state_arc.clone();
+
+    let handler = Function::new("overview_mode_handler", None, move |_, _| {
+        let digits = [
+            "One", "Two", "Three", "Four", "Five", "Six", "Seven", "Eight", "Nine", "Zero",
+        ];
+
+        for (key, number) in digits.iter().zip(1..) {
+            add_mode_keybinding(state.clone(), cbs.clone(), OVERVIEW_MODE, key, move |s| {
+                let mut found = false;
+                s.update_overview(|o| found = o.select_number(number));
+
+                if found {
+                    s.change_to_overview_selection();
+                    s.toggle_mode(OVERVIEW_MODE.into());
+                }
+
+                Ok(())
+            });
+        }
+
+        let directions = [
+            ("H", Direction::Left),
+            ("J", Direction::Down),
+            ("K", Direction::Up),
+            ("L", Direction::Right),
+            ("Left", Direction::Left),
+            ("Down", Direction::Down),
+            ("Up", Direction::Up),
+            ("Right", Direction::Right),
+        ];
+
+        for (key, direction) in directions.iter().copied() {
+            add_mode_keybinding(state.clone(), cbs.clone(), OVERVIEW_MODE, key, move |s| {
+                s.update_overview(|o| o.move_selection(direction));
+                Ok(())
+            });
+        }
+
+        let bindings: Vec<(&str, fn(&mut AppState) -> SystemResult)> = vec![
+            ("Enter", |s| {
+                s.change_to_overview_selection();
+                s.toggle_mode(OVERVIEW_MODE.into());
+                Ok(())
+            }),
+            ("Escape", |s| {
+                s.toggle_mode(OVERVIEW_MODE.into());
+                Ok(())
+            }),
+        ];
+
+        for (key, action) in bindings {
+            add_mode_keybinding(state.clone(), cbs.clone(), OVERVIEW_MODE, key, action);
+        }
+
+        Ok(Dynamic::Null)
+    });
+
+    let idx = callbacks_arc.lock().len();
+    callbacks_arc.lock().push(handler);
+    config
+        .lock()
+        .mode_handlers
+        .insert(OVERVIEW_MODE.into(), idx);
+}
+
+/// Registers the handler of the mode that is active while a text of the bar is being dragged,
+/// which makes it possible to cancel the drag by pressing escape.
+fn register_bar_drag_mode(
+    state_arc: Arc<Mutex<AppState>>,
+    callbacks_arc: Arc<Mutex<Vec<Function>>>,
+    config: Arc<Mutex<Config>>,
+) {
+    let cbs = callbacks_arc.clone();
+    let state = state_arc.clone();
+
+    let handler = Function::new("bar_drag_mode_handler", None, move |_, _| {
+        add_mode_keybinding(state.clone(), cbs.clone(), BAR_DRAG_MODE, "Escape", |s| {
+            match bar::drag::take() {
+                Some(drag) => bar::drag::end(s, &drag),
+                None => s.toggle_mode(BAR_DRAG_MODE.into()),
+            }
+            bar::tooltip::close()?;
+            Ok(())
+        });
+
+        Ok(Dynamic::Null)
+    });
+
+    let idx = callbacks_arc.lock().len();
+    callbacks_arc.lock().push(handler);
+    config
+        .lock()
+        .mode_handlers
+        .insert(BAR_DRAG_MODE.into(), idx);
+}
+
+/// Lists the named actions of nog in the command palette. Actions that expect arguments are only
+/// listed for the workspaces, because those are the only arguments that can be guessed.
+fn register_builtin_actions(config: Arc<Mutex<Config>>) {
+    let mut config = config.lock();
+
+    for action in actions::all() {
+        if action.arg_count == 0 && action.name != "command_palette" {
+            config
+                .actions
+                .push(Action::named(Invocation::new(action.name, vec![])));
+        }
+    }
+
+    for id in 1..=10 {
+        config
+            .actions
+            .push(Action::named(Invocation::new("change_workspace", vec![id])));
+    }
+}
+
+/// Settings that loosen the sandbox of the scripts. Only the config itself can change them while
+/// it loads, so that imported modules, plugins and callbacks can't turn the sandbox off.
+const PROTECTED_SETTINGS: [&'static str; 1] = ["unrestricted_fs"];
+
+fn check_protected_setting(
+    i: &Interpreter,
+    field: &str,
+    loading_config: bool,
+    config_dir: &Path,
+) -> RuntimeResult<()> {
+    if PROTECTED_SETTINGS.contains(&field)
+        && (!loading_config || i.file_path != config_dir.join("config.ns"))
+    {
+        return Err(format!(
+            "{} can only be changed by config.ns while the config loads",
+            field
+        )
+        .into());
+    }
+
+    Ok(())
 }
 
 pub fn create_root_module(
@@ -77,46 +582,120 @@ pub fn create_root_module(
     interpreter_arc: Arc<Mutex<Interpreter>>,
     config: Arc<Mutex<Config>>,
 ) -> Module {
+    register_resize_mode(state_arc.clone(), callbacks_arc.clone(), config.clone());
+    register_command_palette_mode(state_arc.clone(), callbacks_arc.clone(), config.clone());
+    register_bar_drag_mode(state_arc.clone(), callbacks_arc.clone(), config.clone());
+    register_overview_mode(state_arc.clone(), callbacks_arc.clone(), config.clone());
+    register_window_search_mode(state_arc.clone(), callbacks_arc.clone(), config.clone());
+    register_builtin_actions(config.clone());
+
+    // a profile that gets switched to at runtime writes its settings into `config` as well, so
+    // that only the profile scoped ones can be taken from it afterwards
+    let applying_profile = Arc::new(AtomicBool::new(false));
+    let loading_config = is_init.clone();
+    let is_init = {
+        let applying_profile = applying_profile.clone();
+        move || is_init() || applying_profile.load(Ordering::SeqCst)
+    };
+
     let mut workspace = Module::new("workspace");
 
     let state = state_arc.clone();
     workspace = workspace.function("change", move |_, args| {
-        let idx = number!(args[0])?;
-        let mut state = state.lock();
+        dispatch(&state, "change_workspace", vec![number!(args[0])?])
+    });
+
+    let cfg = config.clone();
+    let cbs = callbacks_arc.clone();
+    workspace = workspace.function("bind", move |_, args| {
+        let workspaces = match &args[0] {
+            Dynamic::Number(id) => vec![*id],
+            value => {
+                let arr_ref = array!(value)?;
+                let arr = arr_ref.lock().unwrap();
+                let mut ids = Vec::new();
+
+                for id in arr.iter() {
+                    ids.push(*number!(id)?);
+                }
 
-        state.change_workspace(idx, true);
+                ids
+            }
+        };
+        let kb_args = vec![
+            args[1].clone(),
+            args[2].clone(),
+            false.into(),
+            args.get(3).cloned().unwrap_or_default(),
+        ];
+        let mut kb = kb_from_args(cbs.clone(), kb_args)?;
+
+        kb.workspaces = workspaces;
+        cfg.lock().add_keybinding(kb);
 
         Ok(Dynamic::Null)
     });
 
+    let state = state_arc.clone();
+    workspace = workspace.function("focus_previous", move |_, _| {
+        dispatch(&state, "focus_previous_workspace", vec![])
+    });
+
     let state = state_arc.clone();
     workspace = workspace.function("move_to_monitor", move |_, args| {
-        state.lock().move_workspace_to_monitor(number!(args[0])?);
-        Ok(Dynamic::Null)
+        dispatch(&state, "move_workspace_to_monitor", vec![number!(args[0])?])
     });
 
     let state = state_arc.clone();
     workspace = workspace.function("workspace_to_workspace", move |_, args| {
-        state.lock().move_workspace_to_workspace(number!(args[0])?);
-        Ok(Dynamic::Null)
+        dispatch(&state, "move_workspace_to_workspace", vec![number!(args[0])?])
     });
 
     let state = state_arc.clone();
-    workspace = workspace.function("toggle_fullscreen", move |_, args| {
-        state.lock().toggle_fullscreen();
-        Ok(Dynamic::Null)
+    workspace = workspace.function("toggle_fullscreen", move |_, _| {
+        dispatch(&state, "toggle_fullscreen", vec![])
     });
 
     let state = state_arc.clone();
-    workspace = workspace.function("reset_row", move |_, args| {
-        state.lock().reset_row();
-        Ok(Dynamic::Null)
+    workspace = workspace.function("reset_row", move |_, _| {
+        dispatch(&state, "reset_row", vec![])
     });
 
     let state = state_arc.clone();
-    workspace = workspace.function("reset_col", move |_, args| {
-        state.lock().reset_column();
-        Ok(Dynamic::Null)
+    workspace = workspace.function("reset_col", move |_, _| {
+        dispatch(&state, "reset_column", vec![])
+    });
+
+    let state = state_arc.clone();
+    workspace = workspace.function("equalize", move |_, _| {
+        dispatch(&state, "equalize", vec![])
+    });
+
+    let state = state_arc.clone();
+    workspace = workspace.function("balance", move |_, _| {
+        dispatch(&state, "balance_container", vec![])
+    });
+
+    let state = state_arc.clone();
+    workspace = workspace.function("rotate", move |_, args| {
+        let action = match string!(&args[0])?.as_str() {
+            "clockwise" => "rotate_clockwise",
+            "counterclockwise" => "rotate_counterclockwise",
+            x => return Err(format!("Unknown rotation '{}'", x).into()),
+        };
+
+        dispatch(&state, action, vec![])
+    });
+
+    let state = state_arc.clone();
+    workspace = workspace.function("mirror", move |_, args| {
+        let action = match string!(&args[0])?.as_str() {
+            "horizontal" => "mirror_horizontal",
+            "vertical" => "mirror_vertical",
+            x => return Err(format!("Unknown axis '{}'", x).into()),
+        };
+
+        dispatch(&state, action, vec![])
     });
 
     let state = state_arc.clone();
@@ -134,6 +713,14 @@ pub fn create_root_module(
             match key.as_str() {
                 "text" => settings.text = string!(val)?.clone(),
                 "monitor" => settings.monitor = *number!(val)?,
+                "color" => {
+                    let mut color = *number!(val)?;
+                    #[cfg(target_os = "windows")]
+                    {
+                        color = window::convert_color_to_winapi(color as u32) as i32;
+                    }
+                    settings.color = Some(color);
+                }
                 _ => {}
             }
         }
@@ -147,107 +734,383 @@ pub fn create_root_module(
         Ok(Dynamic::Null)
     });
 
-    let state = state_arc.clone();
-    workspace = workspace.function("move_in", move |_, args| {
-        state
-            .lock()
-            .move_in(Direction::from_str(string!(&args[0])?).unwrap());
-
+    workspace = workspace.function("clear_placements", move |_, _| {
+        placements::clear();
         Ok(Dynamic::Null)
     });
 
     let state = state_arc.clone();
-    workspace = workspace.function("move_out", move |_, args| {
-        state
-            .lock()
-            .move_out(Direction::from_str(string!(&args[0])?).unwrap());
+    let workspace_id_arg = move |arg: Option<&Dynamic>| -> RuntimeResult<i32> {
+        Ok(match arg {
+            Some(id) => *number!(id)?,
+            None => state.lock().workspace_id,
+        })
+    };
+
+    let get_workspace_id = workspace_id_arg.clone();
+    workspace = workspace.function("set_meta", move |_, args| {
+        let key = string!(&args[0])?;
+        let value = match &args[1] {
+            Dynamic::Null => None,
+            value => Some(string!(value)?.clone()),
+        };
+
+        meta::set(get_workspace_id(args.get(2))?, key, value);
 
         Ok(Dynamic::Null)
     });
 
+    let get_workspace_id = workspace_id_arg.clone();
+    workspace = workspace.function("get_meta", move |_, args| {
+        let key = string!(&args[0])?;
+
+        Ok(meta::get(get_workspace_id(args.get(1))?, key)
+            .map(Dynamic::from)
+            .unwrap_or_default())
+    });
+
+    let get_workspace_id = workspace_id_arg.clone();
+    workspace = workspace.function("meta", move |_, args| {
+        let fields = meta::get_all(get_workspace_id(args.get(0))?)
+            .into_iter()
+            .map(|(key, value)| (key, value.into()))
+            .collect();
+
+        Ok(Dynamic::new_object(fields))
+    });
+
     let state = state_arc.clone();
-    workspace = workspace.function("focus", move |_, args| {
-        state
+    workspace = workspace.function("save_snapshot", move |_, args| {
+        let layouts = state
             .lock()
-            .focus(Direction::from_str(string!(&args[0])?).unwrap());
+            .get_grids()
+            .iter()
+            .map(|g| (g.id, g.to_string()))
+            .collect();
+
+        snapshots::save(string!(&args[0])?, layouts);
 
         Ok(Dynamic::Null)
     });
 
-    let state = state_arc.clone();
-    workspace = workspace.function("resize", move |_, args| {
-        state.lock().resize(
-            Direction::from_str(string!(&args[0])?).unwrap(),
-            number!(args[1])?,
-        );
+    workspace = workspace.function("remove_snapshot", move |_, args| {
+        Ok(Dynamic::from(snapshots::remove(string!(&args[0])?)))
+    });
 
-        Ok(Dynamic::Null)
+    workspace = workspace.function("snapshots", move |_, _| {
+        Ok(Dynamic::new_array(
+            snapshots::names().into_iter().map(Dynamic::from).collect(),
+        ))
     });
 
     let state = state_arc.clone();
-    workspace = workspace.function("swap", move |_, args| {
-        state
-            .lock()
-            .swap(Direction::from_str(string!(&args[0])?).unwrap());
+    let cfg = config.clone();
+    let is_init2 = is_init.clone();
+    workspace = workspace.function("define_template", move |_, args| {
+        let template = Template {
+            name: string!(&args[0])?.clone(),
+            root: template_node_from_dynamic(&args[1])?,
+        };
+
+        let define = |templates: &mut Vec<Template>| {
+            templates.retain(|t| t.name != template.name);
+            templates.push(template.clone());
+        };
+
+        if is_init2() {
+            define(&mut cfg.lock().templates);
+        } else {
+            define(&mut state.lock().config.templates);
+        }
 
         Ok(Dynamic::Null)
     });
 
     let state = state_arc.clone();
-    workspace = workspace.function("set_split_direction", move |_i, args| {
-        state
-            .lock()
-            .set_split_direction(SplitDirection::from_str(string!(&args[0])?).unwrap());
+    let get_workspace_id = workspace_id_arg.clone();
+    workspace = workspace.function("apply_template", move |_, args| {
+        let name = string!(&args[0])?;
+        let workspace_id = get_workspace_id(args.get(1))?;
+        let mut state = state.lock();
+
+        let template = state
+            .config
+            .templates
+            .iter()
+            .find(|t| &t.name == name)
+            .cloned()
+            .ok_or(format!("There is no template called '{}'", name))?;
+
+        templates::apply(&mut state, template, workspace_id).map_err(|e| e.to_string())?;
+
         Ok(Dynamic::Null)
     });
 
-    let mut window = Module::new("window");
-
     let state = state_arc.clone();
-    window = window.function("get_title", move |_i, _args| {
-        let state = state.lock();
+    let cfg = config.clone();
+    let is_init2 = is_init.clone();
+    workspace = workspace.function("import_fancyzones", move |_, args| {
+        let path = match args.get(0) {
+            Some(path) => PathBuf::from(string!(path)?),
+            None => fancyzones::default_path().ok_or("Failed to find the layouts of FancyZones")?,
+        };
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let imported = fancyzones::parse(&content)?;
+        let names = imported
+            .iter()
+            .map(|t| Dynamic::from(t.name.clone()))
+            .collect();
+
+        let define = |templates: &mut Vec<Template>| {
+            for template in imported {
+                templates.retain(|t| t.name != template.name);
+                templates.push(template);
+            }
+        };
 
-        Ok(state
-            .get_current_grid()
-            .and_then(|g| g.get_focused_window())
-            .and_then(|w| w.get_title().ok())
-            .unwrap_or_default())
+        if is_init2() {
+            define(&mut cfg.lock().templates);
+        } else {
+            define(&mut state.lock().config.templates);
+        }
+
+        Ok(Dynamic::new_array(names))
     });
 
     let state = state_arc.clone();
-    window = window.function("minimize", move |_i, _args| {
-        state.lock().minimize_window();
-        Ok(Dynamic::Null)
+    workspace = workspace.function("move_in", move |_, args| {
+        dispatch(&state, &directed_action("move_in", &args[0])?, vec![])
     });
 
     let state = state_arc.clone();
-    window = window.function("toggle_floating", move |_i, _args| {
-        state.lock().toggle_floating();
-        Ok(Dynamic::Null)
+    workspace = workspace.function("move_out", move |_, args| {
+        dispatch(&state, &directed_action("move_out", &args[0])?, vec![])
     });
 
     let state = state_arc.clone();
-    window = window.function("ignore", move |_i, _args| {
-        state.lock().ignore_window();
-        Ok(Dynamic::Null)
+    workspace = workspace.function("focus", move |_, args| {
+        dispatch(&state, &directed_action("focus", &args[0])?, vec![])
     });
 
     let state = state_arc.clone();
-    window = window.function("close", move |_i, _args| {
-        state.lock().close_window();
-        Ok(Dynamic::Null)
+    workspace = workspace.function("resize", move |_, args| {
+        let action = directed_action("resize", &args[0])?;
+
+        dispatch(&state, &action, vec![number!(args[1])?])
     });
 
     let state = state_arc.clone();
-    window = window.function("move_to_workspace", move |_i, args| {
-        state.lock().move_window_to_workspace(number!(args[0])?);
-        Ok(Dynamic::Null)
+    workspace = workspace.function("swap", move |_, args| {
+        dispatch(&state, &directed_action("swap", &args[0])?, vec![])
     });
 
-    let mut bar = Module::new("bar");
+    let state = state_arc.clone();
+    workspace = workspace.function("set_split_direction", move |_i, args| {
+        let text = string!(&args[0])?;
+        let action = match SplitDirection::from_str(text) {
+            Ok(SplitDirection::Horizontal) => "split_horizontal",
+            Ok(SplitDirection::Vertical) => "split_vertical",
+            Err(_) => return Err(format!("Unknown split direction {}", text).into()),
+        };
 
-    bar = bar.variable("components", {
-        let state = state_arc.clone();
+        dispatch(&state, action, vec![])
+    });
+
+    let mut window = Module::new("window");
+
+    // bar components call this a lot, which is why it reads the snapshot
+    window = window.function("get_title", move |_i, _args| {
+        let snapshot = snapshot::get();
+
+        Ok(snapshot
+            .displays
+            .iter()
+            .flat_map(|d| d.workspaces.iter())
+            .find(|w| w.id == snapshot.workspace_id)
+            .and_then(|w| w.focused_window.as_ref())
+            .and_then(|w| w.get_title().ok())
+            .unwrap_or_default())
+    });
+
+    let state = state_arc.clone();
+    window = window.function("get", move |_i, _args| {
+        let window = match system::NativeWindow::get_foreground_window() {
+            Ok(window) => window,
+            Err(_) => return Ok(Dynamic::Null),
+        };
+        let state = state.lock();
+        let grid = state
+            .get_grids()
+            .into_iter()
+            .find(|g| g.contains(window.id));
+        let rect = window.get_rect().unwrap_or_default();
+
+        let mut rect_fields = HashMap::new();
+        rect_fields.insert("left".into(), rect.left.into());
+        rect_fields.insert("top".into(), rect.top.into());
+        rect_fields.insert("right".into(), rect.right.into());
+        rect_fields.insert("bottom".into(), rect.bottom.into());
+
+        let id: i32 = window.id.into();
+
+        let mut fields = HashMap::new();
+        fields.insert("id".into(), id.into());
+        fields.insert(
+            "title".into(),
+            window.get_title().unwrap_or_default().into(),
+        );
+        fields.insert(
+            "class".into(),
+            window.get_class_name().unwrap_or_default().into(),
+        );
+        fields.insert("exe".into(), window.get_process_path().into());
+        fields.insert("pid".into(), (window.get_process_id() as i32).into());
+        fields.insert("rect".into(), Dynamic::new_object(rect_fields));
+        fields.insert(
+            "workspace".into(),
+            grid.map(|g| Dynamic::from(g.id)).unwrap_or_default(),
+        );
+        fields.insert("floating".into(), grid.is_none().into());
+        fields.insert(
+            "fullscreen".into(),
+            grid.map_or(false, |g| {
+                g.is_fullscreened() && g.get_focused_window().map(|w| w.id) == Some(window.id)
+            })
+            .into(),
+        );
+        fields.insert(
+            "borderless".into(),
+            borderless::is_borderless(&state, window.id).into(),
+        );
+        fields.insert(
+            "always_on_top".into(),
+            pinned::is_pinned(&state, window.id).into(),
+        );
+
+        Ok(Dynamic::new_object(fields))
+    });
+
+    let state = state_arc.clone();
+    window = window.function("minimize", move |_i, _args| {
+        dispatch(&state, "minimize_window", vec![])
+    });
+
+    let state = state_arc.clone();
+    window = window.function("toggle_floating", move |_i, _args| {
+        dispatch(&state, "toggle_floating", vec![])
+    });
+
+    let state = state_arc.clone();
+    window = window.function("split", move |_i, args| {
+        dispatch(&state, &directed_action("split", &args[0])?, vec![])
+    });
+
+    let state = state_arc.clone();
+    window = window.function("ignore", move |_i, _args| {
+        dispatch(&state, "ignore_window", vec![])
+    });
+
+    let state = state_arc.clone();
+    window = window.function("close", move |_i, _args| {
+        dispatch(&state, "close_window", vec![])
+    });
+
+    let state = state_arc.clone();
+    window = window.function("kill", move |_i, _args| {
+        dispatch(&state, "kill_window", vec![])
+    });
+
+    let state = state_arc.clone();
+    window = window.function("center", move |_i, _args| {
+        dispatch(&state, "center_window", vec![])
+    });
+
+    let state = state_arc.clone();
+    window = window.function("resize", move |_i, args| {
+        let width = number!(args[0])?;
+        let height = number!(args[1])?;
+
+        state
+            .lock()
+            .resize_window(width, height)
+            .map_err(|e| e.to_string())?;
+        Ok(Dynamic::Null)
+    });
+
+    let state = state_arc.clone();
+    window = window.function("move_to_workspace", move |_i, args| {
+        dispatch(&state, "move_to_workspace", vec![number!(args[0])?])
+    });
+
+    let state = state_arc.clone();
+    window = window.function("toggle_decorations", move |_i, _args| {
+        dispatch(&state, "toggle_decorations", vec![])
+    });
+
+    let state = state_arc.clone();
+    window = window.function("toggle_always_on_top", move |_i, _args| {
+        dispatch(&state, "toggle_always_on_top", vec![])
+    });
+
+    let state = state_arc.clone();
+    window = window.function("hide_to_tray", move |_i, _args| {
+        dispatch(&state, "hide_to_tray", vec![])
+    });
+
+    let state = state_arc.clone();
+    window = window.function("restore_from_tray", move |_i, args| match args.get(0) {
+        Some(id) => {
+            hidden::restore(&mut state.lock(), (*number!(id)?).into())
+                .map_err(|e| e.to_string())?;
+
+            Ok(Dynamic::Null)
+        }
+        None => dispatch(&state, "restore_from_tray", vec![]),
+    });
+
+    let state = state_arc.clone();
+    window = window.function("get_hidden", move |_i, _args| {
+        let mut state = state.lock();
+        let windows = hidden::list(&mut state)
+            .iter()
+            .map(|h| {
+                let id: i32 = h.window.id.into();
+                let mut fields: HashMap<String, Dynamic> = HashMap::new();
+                fields.insert("id".into(), id.into());
+                fields.insert("title".into(), h.window.title.clone().into());
+                Dynamic::new_object(fields)
+            })
+            .collect();
+
+        Ok(Dynamic::new_array(windows))
+    });
+
+    let state = state_arc.clone();
+    window = window.function("force_borderless_fullscreen", move |_i, _args| {
+        dispatch(&state, "toggle_borderless_fullscreen", vec![])
+    });
+
+    let state = state_arc.clone();
+    window = window.function("restore", move |_i, args| {
+        let id = match args.get(0) {
+            Some(id) => (*number!(id)?).into(),
+            None => {
+                system::NativeWindow::get_foreground_window()
+                    .map_err(|e| e.to_string())?
+                    .id
+            }
+        };
+
+        state.lock().restore_window(id).map_err(|e| e.to_string())?;
+
+        Ok(Dynamic::Null)
+    });
+
+    let mut bar = Module::new("bar");
+
+    bar = bar.variable("components", {
+        let state = state_arc.clone();
         let mut m = Module::new("components").function("workspaces", move |_, _| {
             Ok(component::workspaces::create(state.clone()).into_dynamic(state.clone()))
         });
@@ -272,31 +1135,82 @@ pub fn create_root_module(
 
         let state = state_arc.clone();
         m = m.function("current_window", move |_, _| {
-            Ok(component::current_window::create(state.clone()).into_dynamic(state.clone()))
+            Ok(component::current_window::create().into_dynamic(state.clone()))
         });
 
         let state = state_arc.clone();
         m = m.function("fullscreen_indicator", move |_, args| {
             let indicator = string!(&args[0])?.clone();
-            Ok(
-                component::fullscreen_indicator::create(state.clone(), indicator)
-                    .into_dynamic(state.clone()),
-            )
+            Ok(component::fullscreen_indicator::create(indicator).into_dynamic(state.clone()))
+        });
+
+        let state = state_arc.clone();
+        m = m.function("layout_indicator", move |_, args| {
+            let mut labels = LayoutLabels::default();
+
+            if let Some(arg) = args.get(0) {
+                let options_ref = object!(arg)?;
+                let options = options_ref.lock().unwrap();
+
+                for (key, value) in options.iter() {
+                    match key.as_str() {
+                        "tiling" => labels.tiling = string!(value)?.clone(),
+                        "fullscreen" => labels.fullscreen = string!(value)?.clone(),
+                        _ => return Err(format!("Unknown option '{}'", key).into()),
+                    }
+                }
+            }
+
+            Ok(component::layout_indicator::create(labels).into_dynamic(state.clone()))
+        });
+
+        let state = state_arc.clone();
+        m = m.function("window_count", move |_, args| {
+            let compact = match args.get(0) {
+                Some(arg) => *boolean!(arg)?,
+                None => false,
+            };
+
+            Ok(component::window_count::create(compact).into_dynamic(state.clone()))
+        });
+
+        let state = state_arc.clone();
+        m = m.function("suspended_indicator", move |_, args| {
+            let indicator = string!(&args[0])?.clone();
+            Ok(component::suspended_indicator::create(indicator).into_dynamic(state.clone()))
+        });
+
+        let state = state_arc.clone();
+        m = m.function("keyboard_layout", move |_, _| {
+            Ok(component::keyboard_layout::create().into_dynamic(state.clone()))
+        });
+
+        let state = state_arc.clone();
+        m = m.function("volume", move |_, args| {
+            let muted_text = match args.get(0) {
+                Some(text) => string!(text)?.clone(),
+                None => "muted".into(),
+            };
+            Ok(component::volume::create(muted_text).into_dynamic(state.clone()))
         });
 
         let state = state_arc.clone();
         m = m.function("active_mode", move |_, _| {
-            Ok(component::active_mode::create(state.clone()).into_dynamic(state.clone()))
+            Ok(component::active_mode::create().into_dynamic(state.clone()))
+        });
+
+        let state = state_arc.clone();
+        m = m.function("workspace_meta", move |_, args| {
+            let key = string!(&args[0])?.clone();
+            Ok(component::workspace_meta::create(key).into_dynamic(state.clone()))
         });
 
         let state = state_arc.clone();
         m = m.function("split_direction", move |_, args| {
             let vertical = string!(&args[0])?.clone();
             let horizontal = string!(&args[1])?.clone();
-            Ok(
-                component::split_direction::create(state.clone(), vertical, horizontal)
-                    .into_dynamic(state.clone()),
-            )
+            let component = component::split_direction::create(vertical, horizontal);
+            Ok(component.into_dynamic(state.clone()))
         });
 
         let state = state_arc.clone();
@@ -308,6 +1222,24 @@ pub fn create_root_module(
             })
             .into_dynamic(state.clone()))
         });
+
+        m = m.function("with_layout", move |_, args| {
+            let component_ref = object!(&args[0])?;
+            let layout_ref = object!(&args[1])?;
+            let mut fields = component_ref.lock().unwrap().clone();
+            let layout = layout_ref.lock().unwrap();
+
+            // invalid values fail here instead of once the component gets added to the bar
+            component::layout_from_fields(&layout)?;
+
+            for key in &["padding", "min_width", "align"] {
+                if let Some(value) = layout.get(*key) {
+                    fields.insert(key.to_string(), value.clone());
+                }
+            }
+
+            Ok(Dynamic::new_object(fields))
+        });
         m
     });
 
@@ -323,24 +1255,75 @@ pub fn create_root_module(
         for (key, val) in config.iter() {
             match key.as_str() {
                 "height" => {
+                    let value = *number!(val)?;
                     if is_init2() {
-                        cfg.lock().bar.height = *number!(val)?;
+                        cfg.lock().bar.height = value;
                     } else {
-                        state.lock().config.bar.height = *number!(val)?;
+                        change_setting(state.clone(), "bar.height", |cfg| {
+                            cfg.bar.height = value;
+                            Ok(())
+                        })?;
                     }
                 }
                 "font_size" => {
+                    let value = *number!(val)?;
                     if is_init2() {
-                        cfg.lock().bar.font_size = *number!(val)?;
+                        cfg.lock().bar.font_size = value;
                     } else {
-                        state.lock().config.bar.font_size = *number!(val)?;
+                        change_setting(state.clone(), "bar.font_size", |cfg| {
+                            cfg.bar.font_size = value;
+                            Ok(())
+                        })?;
                     }
                 }
                 "font" => {
+                    let value = font_list_from_dynamic(val)?;
                     if is_init2() {
-                        cfg.lock().bar.font = string!(val)?.clone();
+                        cfg.lock().bar.font = value;
                     } else {
-                        state.lock().config.bar.font = string!(val)?.clone();
+                        change_setting(state.clone(), "bar.font", |cfg| {
+                            cfg.bar.font = value;
+                            Ok(())
+                        })?;
+                    }
+                }
+                "separator" => {
+                    let value = string!(val)?.clone();
+                    if is_init2() {
+                        cfg.lock().bar.separator = value;
+                    } else {
+                        change_setting(state.clone(), "bar.separator", |cfg| {
+                            cfg.bar.separator = value;
+                            Ok(())
+                        })?;
+                    }
+                }
+                "auto_hide" => {
+                    if is_init2() {
+                        cfg.lock().bar.auto_hide = *boolean!(val)?;
+                    } else {
+                        state.lock().config.bar.auto_hide = *boolean!(val)?;
+                    }
+                }
+                "auto_hide_delay" => {
+                    if is_init2() {
+                        cfg.lock().bar.auto_hide_delay = *number!(val)?;
+                    } else {
+                        state.lock().config.bar.auto_hide_delay = *number!(val)?;
+                    }
+                }
+                "idle_timeout" => {
+                    if is_init2() {
+                        cfg.lock().bar.idle_timeout = *number!(val)?;
+                    } else {
+                        state.lock().config.bar.idle_timeout = *number!(val)?;
+                    }
+                }
+                "idle_interval" => {
+                    if is_init2() {
+                        cfg.lock().bar.idle_interval = *number!(val)?;
+                    } else {
+                        state.lock().config.bar.idle_interval = *number!(val)?;
                     }
                 }
                 "color" => {
@@ -352,7 +1335,10 @@ pub fn create_root_module(
                     if is_init2() {
                         cfg.lock().bar.color = color;
                     } else {
-                        state.lock().config.bar.color = color;
+                        change_setting(state.clone(), "bar.color", |cfg| {
+                            cfg.bar.color = color;
+                            Ok(())
+                        })?;
                     }
                 }
                 "components" => {
@@ -395,6 +1381,166 @@ pub fn create_root_module(
         Ok(Dynamic::Null)
     });
 
+    let i_arc = interpreter_arc.clone();
+    let state = state_arc.clone();
+    let cfg = config.clone();
+    let is_init2 = is_init.clone();
+
+    bar = bar.function("configure_display", move |_, args| {
+        let display_idx = *number!(&args[0])?;
+        let obj_ref = object!(&args[1])?;
+        let obj = obj_ref.lock().unwrap();
+        let mut settings = DisplayBarConfig::default();
+
+        for (key, val) in obj.iter() {
+            let components = || -> RuntimeResult<Vec<Component>> {
+                let mut comps = Vec::new();
+
+                for raw_comp in val.clone().as_array()? {
+                    comps.push(Component::from_dynamic(i_arc.clone(), raw_comp)?);
+                }
+
+                Ok(comps)
+            };
+
+            match key.as_ref() {
+                "left" => settings.left = Some(components()?),
+                "center" => settings.center = Some(components()?),
+                "right" => settings.right = Some(components()?),
+                "height" => settings.height = Some(*number!(val)?),
+                "font" => settings.font = Some(font_list_from_dynamic(val)?),
+                "font_size" => settings.font_size = Some(*number!(val)?),
+                _ => {}
+            }
+        }
+
+        if is_init2() {
+            cfg.lock().bar.displays.insert(display_idx, settings);
+        } else {
+            change_setting(state.clone(), "bar.displays", |cfg| {
+                cfg.bar.displays.insert(display_idx, settings);
+                Ok(())
+            })?;
+        }
+
+        Ok(Dynamic::Null)
+    });
+
+    let i_arc = interpreter_arc.clone();
+    let state = state_arc.clone();
+    let cfg = config.clone();
+    let is_init2 = is_init.clone();
+
+    bar = bar.function("insert", move |_, args| {
+        let section = string!(&args[0])?.clone();
+        let idx = (*number!(&args[1])?).max(0) as usize;
+        let component = Component::from_dynamic(i_arc.clone(), args[2].clone())?;
+        let id = component.id;
+
+        if is_init2() {
+            cfg.lock().bar.components.insert(&section, idx, component)?;
+        } else {
+            let mut state = state.lock();
+            state
+                .config
+                .bar
+                .components
+                .insert(&section, idx, component)?;
+            state
+                .event_channel
+                .sender
+                .send(Event::RedrawAppBar)
+                .expect("Failed to send RedrawAppBar event");
+        }
+
+        Ok(Dynamic::from(id))
+    });
+
+    let state = state_arc.clone();
+    let cfg = config.clone();
+    let is_init2 = is_init.clone();
+
+    bar = bar.function("remove", move |_, args| {
+        let id = *number!(&args[0])? as usize;
+
+        let removed = if is_init2() {
+            cfg.lock().bar.components.remove(id).is_some()
+        } else {
+            let mut state = state.lock();
+            let removed = state.config.bar.components.remove(id).is_some();
+            state
+                .event_channel
+                .sender
+                .send(Event::RedrawAppBar)
+                .expect("Failed to send RedrawAppBar event");
+            removed
+        };
+
+        Ok(Dynamic::from(removed))
+    });
+
+    let state = state_arc.clone();
+    let cfg = config.clone();
+    let is_init2 = is_init.clone();
+
+    bar = bar.function("move", move |_, args| {
+        let id = *number!(&args[0])? as usize;
+        let section = string!(&args[1])?.clone();
+        let idx = (*number!(&args[2])?).max(0) as usize;
+
+        if is_init2() {
+            cfg.lock().bar.components.move_to(id, &section, idx)?;
+        } else {
+            let mut state = state.lock();
+            state.config.bar.components.move_to(id, &section, idx)?;
+            state
+                .event_channel
+                .sender
+                .send(Event::RedrawAppBar)
+                .expect("Failed to send RedrawAppBar event");
+        }
+
+        Ok(Dynamic::Null)
+    });
+
+    let state = state_arc.clone();
+    bar = bar.function("get_font", move |_, _| {
+        let state = state.lock();
+
+        Ok(state
+            .get_current_display()
+            .appbar
+            .as_ref()
+            .map(|bar| Dynamic::from(bar.font.clone()))
+            .unwrap_or_default())
+    });
+
+    let state = state_arc.clone();
+    let cfg = config.clone();
+    let is_init2 = is_init.clone();
+
+    bar = bar.function("list", move |_, _| {
+        let components = if is_init2() {
+            cfg.lock().bar.components.clone()
+        } else {
+            state.lock().config.bar.components.clone()
+        };
+
+        Ok(Dynamic::new_array(
+            components
+                .list()
+                .into_iter()
+                .map(|(section, component)| {
+                    let mut fields = HashMap::new();
+                    fields.insert("id".into(), component.id.into());
+                    fields.insert("name".into(), component.name.clone().into());
+                    fields.insert("section".into(), section.into());
+                    Dynamic::new_object(fields)
+                })
+                .collect(),
+        ))
+    });
+
     let mut plugin = Module::new("plugin");
     let cfg = config.clone();
 
@@ -558,42 +1704,69 @@ pub fn create_root_module(
         Ok(Dynamic::Null)
     });
 
-    let mut config_mod = Module::new("config");
-
+    let mut osd = Module::new("osd");
     let state = state_arc.clone();
-    let cfg = config.clone();
-    let is_init2 = is_init.clone();
-    config_mod = config_mod.function("increment", move |_i, args| {
-        let (field, amount) = match args.len() {
-            1 => (string!(&args[0])?, 1),
-            _ => (string!(&args[0])?, *number!(&args[1])?),
-        };
-
-        if is_init2() {
-            cfg.lock().set(string!(&args[0])?, string!(&args[1])?);
-        } else {
-            let mut cfg = state.lock().config.clone();
-            cfg.increment_field(field, amount);
-            update_config(state.clone(), cfg);
-        }
+    osd = osd.function("show", move |_i, args| {
+        state.lock().show_osd(string!(&args[0])?.clone());
         Ok(Dynamic::Null)
     });
 
+    let mut media = Module::new("media");
+
+    for (name, key) in vec![
+        ("volume_up", MediaKey::VolumeUp),
+        ("volume_down", MediaKey::VolumeDown),
+        ("mute", MediaKey::Mute),
+        ("play_pause", MediaKey::PlayPause),
+        ("next", MediaKey::Next),
+        ("prev", MediaKey::Prev),
+    ] {
+        media = media.function(name, move |_i, _args| {
+            system::audio::press(key);
+            Ok(Dynamic::Null)
+        });
+    }
+
+    media = media.function("volume", move |_i, _args| {
+        let (level, _) = system::audio::get_volume().map_err(|e| e.to_string())?;
+        Ok(Dynamic::from(level as i32))
+    });
+
+    media = media.function("is_muted", move |_i, _args| {
+        let (_, muted) = system::audio::get_volume().map_err(|e| e.to_string())?;
+        Ok(Dynamic::from(muted))
+    });
+
+    let mut display_mod = Module::new("display");
+
     let state = state_arc.clone();
     let cfg = config.clone();
     let is_init2 = is_init.clone();
-    config_mod = config_mod.function("decrement", move |_i, args| {
-        let (field, amount) = match args.len() {
-            1 => (string!(&args[0])?, -1),
-            _ => (string!(&args[0])?, *number!(&args[1])?),
-        };
+    display_mod = display_mod.function("reserve", move |_, args| {
+        let display_idx = *number!(&args[0])?;
+        let space_ref = object!(&args[1])?;
+        let space = space_ref.lock().unwrap();
+        let mut reserved = ReservedSpace::default();
+
+        for (key, val) in space.iter() {
+            let value = (*number!(val)?).max(0);
+
+            match key.as_str() {
+                "top" => reserved.top = value,
+                "right" => reserved.right = value,
+                "bottom" => reserved.bottom = value,
+                "left" => reserved.left = value,
+                x => return Err(format!("Unknown edge '{}'", x).into()),
+            }
+        }
 
         if is_init2() {
-            cfg.lock().set(string!(&args[0])?, string!(&args[1])?);
+            cfg.lock().reserved_space.insert(display_idx, reserved);
         } else {
-            let mut cfg = state.lock().config.clone();
-            cfg.decrement_field(field, amount);
-            update_config(state.clone(), cfg);
+            change_setting(state.clone(), "reserved_space", |cfg| {
+                cfg.reserved_space.insert(display_idx, reserved);
+                Ok(())
+            })?;
         }
 
         Ok(Dynamic::Null)
@@ -602,28 +1775,417 @@ pub fn create_root_module(
     let state = state_arc.clone();
     let cfg = config.clone();
     let is_init2 = is_init.clone();
-    config_mod = config_mod.function("toggle", move |_i, args| {
+    display_mod = display_mod.function("workspace_policy", move |_, args| {
+        let display_idx = *number!(&args[0])?;
+        let options_ref = object!(&args[1])?;
+        let options = options_ref.lock().unwrap();
+        let mut policy = WorkspacePolicy::default();
+
+        for (key, val) in options.iter() {
+            let value = *boolean!(val)?;
+
+            match key.as_str() {
+                "remove_empty" => policy.remove_empty = value,
+                "compact" => policy.compact = value,
+                x => return Err(format!("Unknown option '{}'", x).into()),
+            }
+        }
+
         if is_init2() {
-            cfg.lock().set(string!(&args[0])?, string!(&args[1])?);
+            cfg.lock().workspace_policies.insert(display_idx, policy);
         } else {
-            let mut cfg = state.lock().config.clone();
-            cfg.toggle_field(string!(&args[0])?);
-            update_config(state.clone(), cfg);
+            change_setting(state.clone(), "workspace_policies", |cfg| {
+                cfg.workspace_policies.insert(display_idx, policy);
+                Ok(())
+            })?;
         }
 
         Ok(Dynamic::Null)
     });
 
+    let state = state_arc.clone();
+    display_mod = display_mod.function("swap_workspaces", move |_, args| {
+        let a = *number!(&args[0])?;
+        let b = *number!(&args[1])?;
+
+        state
+            .lock()
+            .swap_workspaces(a, b)
+            .map_err(|e| e.to_string())?;
+
+        Ok(Dynamic::Null)
+    });
+
+    let mut system_mod = Module::new("system");
+
+    system_mod = system_mod.function("keyboard_layout", move |_i, _args| {
+        Ok(system::api::get_keyboard_layout()
+            .map(Dynamic::from)
+            .unwrap_or_default())
+    });
+
+    system_mod = system_mod.function("set_keyboard_layout", move |_i, args| {
+        system::api::set_keyboard_layout(string!(&args[0])?).map_err(|e| e.to_string())?;
+        Ok(Dynamic::Null)
+    });
+
+    system_mod = system_mod.function("hostname", move |_i, _args| {
+        Ok(system::api::get_hostname()
+            .map(Dynamic::from)
+            .unwrap_or_default())
+    });
+
+    system_mod = system_mod.function("username", move |_i, _args| {
+        Ok(system::api::get_username()
+            .map(Dynamic::from)
+            .unwrap_or_default())
+    });
+
+    system_mod = system_mod.function("displays", move |_i, _args| {
+        let mut displays = system::api::get_displays();
+        display::sort(&mut displays);
+
+        Ok(Dynamic::new_array(
+            displays
+                .iter()
+                .enumerate()
+                .map(|(idx, d)| {
+                    let (name, description) = system::api::get_display_names(d.id);
+                    let mut fields: HashMap<String, Dynamic> = HashMap::new();
+
+                    fields.insert("number".into(), (idx as i32 + 1).into());
+                    fields.insert("name".into(), name.into());
+                    fields.insert("description".into(), description.into());
+                    fields.insert("primary".into(), d.is_primary().into());
+                    fields.insert("width".into(), d.width().into());
+                    fields.insert("height".into(), d.height().into());
+                    fields.insert("dpi".into(), (d.dpi as i32).into());
+
+                    Dynamic::new_object(fields)
+                })
+                .collect(),
+        ))
+    });
+
+    let mut env = Module::new("env");
+
+    env = env.function("get", move |_i, args| {
+        Ok(std::env::var(string!(&args[0])?)
+            .map(Dynamic::from)
+            .unwrap_or_default())
+    });
+
+    let mut clipboard = Module::new("clipboard");
+
+    clipboard = clipboard.function("get", move |_i, _args| {
+        Ok(Dynamic::from(
+            system::api::get_clipboard_text().map_err(|e| e.to_string())?,
+        ))
+    });
+
+    clipboard = clipboard.function("set", move |_i, args| {
+        system::api::set_clipboard_text(string!(&args[0])?).map_err(|e| e.to_string())?;
+        Ok(Dynamic::Null)
+    });
+
+    let mut http_mod = Module::new("http");
+
+    let state = state_arc.clone();
+    http_mod = http_mod.function("get", move |_i, args| {
+        let mut request = http::Request {
+            url: string!(&args[0])?.clone(),
+            ..Default::default()
+        };
+        let callback = args[1].clone().as_fn()?;
+
+        if let Some(options) = args.get(2).filter(|x| !x.is_null()) {
+            let options_ref = object!(options)?;
+            let options = options_ref.lock().unwrap();
+
+            for (key, val) in options.iter() {
+                match key.as_str() {
+                    "timeout" => {
+                        request.timeout =
+                            Some(Duration::from_millis((*number!(val)?).max(0) as u64))
+                    }
+                    "headers" => {
+                        let headers_ref = object!(val)?;
+                        for (name, value) in headers_ref.lock().unwrap().iter() {
+                            request
+                                .headers
+                                .push((name.clone(), string!(value)?.clone()));
+                        }
+                    }
+                    _ => return Err(format!("Unknown option '{}'", key).into()),
+                }
+            }
+        }
+
+        let sender = state.lock().event_channel.sender.clone();
+        http::get(request, move |result| {
+            let mut fields: HashMap<String, Dynamic> = HashMap::new();
+
+            match result {
+                Ok(response) => {
+                    fields.insert("status".into(), (response.status as i32).into());
+                    fields.insert("body".into(), response.body.into());
+                    fields.insert("error".into(), Dynamic::Null);
+                }
+                Err(e) => {
+                    fields.insert("status".into(), 0.into());
+                    fields.insert("body".into(), "".into());
+                    fields.insert("error".into(), e.into());
+                }
+            }
+
+            // the callback runs on the main thread like every other callback of the config. The
+            // channel is gone if nog quit in the meantime, which leaves nobody to call it.
+            let _ = sender.send(Event::CallFunction {
+                function: callback,
+                args: vec![Dynamic::new_object(fields)],
+            });
+        });
+
+        Ok(Dynamic::Null)
+    });
+
+    let mut fs_mod = Module::new("fs");
+
+    let state = state_arc.clone();
     let cfg = config.clone();
+    let is_init2 = is_init.clone();
+    let resolve = move |path: &str, write: bool| -> RuntimeResult<PathBuf> {
+        let (root, unrestricted) = if is_init2() {
+            let cfg = cfg.lock();
+            (cfg.path.clone(), cfg.unrestricted_fs)
+        } else {
+            let state = state.lock();
+            (state.config.path.clone(), state.config.unrestricted_fs)
+        };
+
+        if write {
+            Ok(fs::resolve_writable(&root, path, unrestricted)?)
+        } else {
+            Ok(fs::resolve(&root, path, unrestricted)?)
+        }
+    };
+
+    let resolve2 = resolve.clone();
+    fs_mod = fs_mod.function("read_text", move |_i, args| {
+        let path = resolve2(string!(&args[0])?, false)?;
+        Ok(std::fs::read_to_string(path).map_err(|e| e.to_string())?)
+    });
+
+    let resolve2 = resolve.clone();
+    fs_mod = fs_mod.function("write_text", move |_i, args| {
+        let path = resolve2(string!(&args[0])?, true)?;
+        let text = string!(&args[1])?;
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+
+        std::fs::write(path, text).map_err(|e| e.to_string())?;
+        Ok(Dynamic::Null)
+    });
+
+    let resolve2 = resolve.clone();
+    fs_mod = fs_mod.function("exists", move |_i, args| {
+        Ok(resolve2(string!(&args[0])?, false)?.exists())
+    });
+
+    let resolve2 = resolve.clone();
+    fs_mod = fs_mod.function("list_dir", move |_i, args| {
+        let path = match args.get(0) {
+            Some(Dynamic::Null) | None => resolve2(".", false)?,
+            Some(path) => resolve2(string!(path)?, false)?,
+        };
+
+        let mut names = std::fs::read_dir(path)
+            .map_err(|e| e.to_string())?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        names.sort();
+
+        Ok(Dynamic::new_array(
+            names.into_iter().map(Dynamic::from).collect(),
+        ))
+    });
+
+    let mut scheduler = Module::new("scheduler");
+
+    let cfg = config.clone();
+    let cbs = callbacks_arc.clone();
+    scheduler = scheduler.function("add", move |_i, args| {
+        let name = string!(&args[0])?.clone();
+        let rule = string!(&args[1])?.clone();
+        let schedule = rule.parse::<Schedule>()?;
+        let callback = args[2].clone().as_fn()?;
+
+        let callback_id = cbs.lock().len();
+        cbs.lock().push(callback);
+
+        let mut cfg = cfg.lock();
+        cfg.jobs.retain(|job| job.name != name);
+        cfg.jobs.push(Job {
+            name,
+            rule,
+            schedule,
+            callback_id,
+        });
+
+        Ok(Dynamic::Null)
+    });
+
     let state = state_arc.clone();
+    scheduler = scheduler.function("jobs", move |_i, _args| {
+        let state = state.lock();
+        let now = Local::now().naive_local();
+
+        Ok(Dynamic::new_array(
+            state
+                .scheduler
+                .pending(&state.config.jobs, now)
+                .into_iter()
+                .map(|(job, next_run)| {
+                    let mut fields = HashMap::new();
+                    fields.insert("name".into(), job.name.clone().into());
+                    fields.insert("rule".into(), job.rule.clone().into());
+                    fields.insert(
+                        "next_run".into(),
+                        next_run.format("%Y-%m-%d %H:%M").to_string().into(),
+                    );
+                    Dynamic::new_object(fields)
+                })
+                .collect(),
+        ))
+    });
+
+    let mut game_mode_mod = Module::new("game_mode");
+
+    let state = state_arc.clone();
+    let cfg = config.clone();
     let is_init2 = is_init.clone();
-    config_mod = config_mod.function("set", move |_i, args| {
+    game_mode_mod = game_mode_mod.function("configure", move |_i, args| {
+        let settings_ref = object!(&args[0])?;
+        let settings = settings_ref.lock().unwrap();
+        let mut game_mode = if is_init2() {
+            cfg.lock().game_mode.clone()
+        } else {
+            state.lock().config.game_mode.clone()
+        };
+
+        for (key, val) in settings.iter() {
+            let value = *boolean!(val)?;
+            match key.as_str() {
+                "enabled" => game_mode.enabled = value,
+                "suspend_keybindings" => game_mode.suspend_keybindings = value,
+                "hide_bar" => game_mode.hide_bar = value,
+                "disable_mouse_integration" => game_mode.disable_mouse_integration = value,
+                "suspend_tiling" => game_mode.suspend_tiling = value,
+                "pause_events" => game_mode.pause_events = value,
+                _ => return Err(format!("Unknown game mode setting '{}'", key).into()),
+            }
+        }
+
+        if is_init2() {
+            cfg.lock().game_mode = game_mode;
+        } else {
+            state.lock().config.game_mode = game_mode;
+        }
+
+        Ok(Dynamic::Null)
+    });
+
+    let cfg = config.clone();
+    let cbs = callbacks_arc.clone();
+    game_mode_mod = game_mode_mod.function("on_enter", move |_i, args| {
+        let callback = args[0].clone().as_fn()?;
+        let mut cbs = cbs.lock();
+
+        cfg.lock().game_mode.on_enter = Some(cbs.len());
+        cbs.push(callback);
+
+        Ok(Dynamic::Null)
+    });
+
+    let cfg = config.clone();
+    let cbs = callbacks_arc.clone();
+    game_mode_mod = game_mode_mod.function("on_leave", move |_i, args| {
+        let callback = args[0].clone().as_fn()?;
+        let mut cbs = cbs.lock();
+
+        cfg.lock().game_mode.on_leave = Some(cbs.len());
+        cbs.push(callback);
+
+        Ok(Dynamic::Null)
+    });
+
+    let state = state_arc.clone();
+    game_mode_mod = game_mode_mod.function("is_active", move |_i, _args| {
+        Ok(Dynamic::from(state.lock().game_mode.is_some()))
+    });
+
+    let mut config_mod = Module::new("config");
+
+    let state = state_arc.clone();
+    let cfg = config.clone();
+    let is_init2 = is_init.clone();
+    config_mod = config_mod.function("increment", move |_i, args| {
+        let (field, amount) = match args.len() {
+            1 => (string!(&args[0])?, 1),
+            _ => (string!(&args[0])?, *number!(&args[1])?),
+        };
+
+        if is_init2() {
+            cfg.lock().increment_field(field, amount);
+        } else {
+            change_setting(state.clone(), field, |cfg| {
+                cfg.increment_field(field, amount);
+                Ok(())
+            })?;
+        }
+        Ok(Dynamic::Null)
+    });
+
+    let state = state_arc.clone();
+    let cfg = config.clone();
+    let is_init2 = is_init.clone();
+    config_mod = config_mod.function("decrement", move |_i, args| {
+        let (field, amount) = match args.len() {
+            1 => (string!(&args[0])?, 1),
+            _ => (string!(&args[0])?, *number!(&args[1])?),
+        };
+
         if is_init2() {
-            cfg.lock().set(string!(&args[0])?, string!(&args[1])?);
+            cfg.lock().decrement_field(field, amount);
         } else {
-            let mut cfg = state.lock().config.clone();
-            cfg.set(string!(&args[0])?, string!(&args[1])?);
-            update_config(state.clone(), cfg);
+            change_setting(state.clone(), field, |cfg| {
+                cfg.decrement_field(field, amount);
+                Ok(())
+            })?;
+        }
+
+        Ok(Dynamic::Null)
+    });
+
+    let state = state_arc.clone();
+    let cfg = config.clone();
+    let is_init2 = is_init.clone();
+    let loading_config2 = loading_config.clone();
+    config_mod = config_mod.function("toggle", move |i, args| {
+        let field = string!(&args[0])?;
+
+        check_protected_setting(i, field, loading_config2(), &cfg.lock().path)?;
+
+        if is_init2() {
+            cfg.lock().toggle_field(field);
+        } else {
+            change_setting(state.clone(), field, |cfg| {
+                cfg.toggle_field(field);
+                Ok(())
+            })?;
         }
 
         Ok(Dynamic::Null)
@@ -632,13 +2194,25 @@ pub fn create_root_module(
     let cfg = config.clone();
     let state = state_arc.clone();
     let is_init2 = is_init.clone();
-    config_mod = config_mod.function("enable", move |_i, args| {
+    let loading_config2 = loading_config.clone();
+    config_mod = config_mod.function("set", move |i, args| {
+        let field = string!(&args[0])?;
+        let value = args[1].to_string();
+
+        check_protected_setting(i, field, loading_config2(), &cfg.lock().path)?;
+
         if is_init2() {
-            cfg.lock().set(string!(&args[0])?, "true");
+            cfg.lock().set(field, &value)?;
         } else {
-            let mut cfg = state.lock().config.clone();
-            cfg.set(string!(&args[0])?, "true");
-            update_config(state.clone(), cfg);
+            change_setting(state.clone(), field, |cfg| cfg.set(field, &value))?;
+        }
+
+        if field.starts_with("script_") {
+            i.limits = if is_init2() {
+                cfg.lock().script_limits()
+            } else {
+                state.lock().config.script_limits()
+            };
         }
 
         Ok(Dynamic::Null)
@@ -647,13 +2221,54 @@ pub fn create_root_module(
     let cfg = config.clone();
     let state = state_arc.clone();
     let is_init2 = is_init.clone();
-    config_mod = config_mod.function("disable", move |_i, args| {
+    config_mod = config_mod.function("get", move |_i, args| {
+        let field = string!(&args[0])?;
+        let value = if is_init2() {
+            cfg.lock().get(field)?
+        } else {
+            state.lock().config.get(field)?
+        };
+
+        Ok(if let Ok(boolean) = value.parse::<bool>() {
+            Dynamic::from(boolean)
+        } else if let Ok(number) = value.parse::<i32>() {
+            Dynamic::from(number)
+        } else {
+            Dynamic::from(value)
+        })
+    });
+
+    let cfg = config.clone();
+    let state = state_arc.clone();
+    let is_init2 = is_init.clone();
+    let loading_config2 = loading_config.clone();
+    config_mod = config_mod.function("enable", move |i, args| {
+        let field = string!(&args[0])?;
+
+        check_protected_setting(i, field, loading_config2(), &cfg.lock().path)?;
+
+        if is_init2() {
+            cfg.lock().set(field, "true")?;
+        } else {
+            change_setting(state.clone(), field, |cfg| cfg.set(field, "true"))?;
+        }
+
+        Ok(Dynamic::Null)
+    });
+
+    let cfg = config.clone();
+    let state = state_arc.clone();
+    let is_init2 = is_init.clone();
+    let loading_config2 = loading_config.clone();
+    config_mod = config_mod.function("disable", move |i, args| {
+        let field = string!(&args[0])?;
+
+        check_protected_setting(i, field, loading_config2(), &cfg.lock().path)?;
+
         if is_init2() {
-            cfg.lock().set(string!(&args[0])?, "false");
+            cfg.lock().set(field, "false")?;
         } else {
-            let mut cfg = state.lock().config.clone();
-            cfg.set(string!(&args[0])?, "false");
-            update_config(state.clone(), cfg);
+            change_setting(state.clone(), field, |cfg| cfg.set(field, "false"))?;
         }
 
         Ok(Dynamic::Null)
@@ -697,6 +2312,31 @@ pub fn create_root_module(
                 "workspace_id" => {
                     rule.workspace_id = *number!(value)?;
                 }
+                "float" => {
+                    rule.float = Some(*boolean!(value)?);
+                }
+                "remove_title_bar" => {
+                    rule.remove_title_bar = Some(*boolean!(value)?);
+                }
+                "use_border" => {
+                    rule.use_border = Some(*boolean!(value)?);
+                }
+                "unfocused_opacity" => {
+                    rule.unfocused_opacity = Some(*number!(value)?);
+                }
+                "game" => {
+                    rule.game = Some(*boolean!(value)?);
+                }
+                "pass_through" => {
+                    let combos_ref = array!(value)?;
+                    let combos = combos_ref.lock().unwrap();
+
+                    for combo in combos.iter() {
+                        let kb =
+                            Keybinding::from_str(string!(combo)?).map_err(|e| e.to_string())?;
+                        rule.pass_through.push((kb.key, kb.modifier));
+                    }
+                }
                 _ => todo!("{}", key),
             }
         }
@@ -706,6 +2346,448 @@ pub fn create_root_module(
         Ok(Dynamic::Null)
     });
 
+    let state = state_arc.clone();
+    rules = rules.function("test", move |_, _| {
+        let window = system::NativeWindow::get_foreground_window().map_err(|e| e.to_string())?;
+        let check = RuleCheck::new(&state.lock(), window);
+        let strings = |items: &[String]| {
+            Dynamic::new_array(items.iter().cloned().map(Dynamic::from).collect())
+        };
+
+        let mut fields = HashMap::new();
+        fields.insert("title".into(), check.title.clone().into());
+        fields.insert("process_name".into(), check.process_name.clone().into());
+        fields.insert("rules".into(), strings(&check.matching_rules));
+        fields.insert("settings".into(), strings(&check.actions));
+        fields.insert("managed".into(), check.managed.into());
+        fields.insert("reasons".into(), strings(&check.reasons));
+        fields.insert("text".into(), check.to_string().into());
+
+        Ok(Dynamic::new_object(fields))
+    });
+
+    let mut keybindings = Module::new("keybindings");
+
+    let cfg = config.clone();
+    let state = state_arc.clone();
+    keybindings = keybindings.function("export_table", move |_, args| {
+        let path = cfg.lock().path.join(string!(&args[0])?);
+        let kbs = state.lock().keybindings_manager.get_keybindings();
+
+        let skipped = table::export(&kbs, &path)?;
+
+        if !skipped.is_empty() {
+            warn!(
+                "The keybindings {} are bound to a function and didn't get exported",
+                skipped.join(", ")
+            );
+        }
+
+        Ok(Dynamic::new_array(
+            skipped.into_iter().map(Dynamic::from).collect(),
+        ))
+    });
+
+    let cfg = config.clone();
+    let cbs = callbacks_arc.clone();
+    let state = state_arc.clone();
+    keybindings = keybindings.function("import_table", move |_, args| {
+        let path = cfg.lock().path.join(string!(&args[0])?);
+        let mut mode_kbs: HashMap<String, Vec<Keybinding>> = HashMap::new();
+
+        for mut kb in table::import(&path)? {
+            // named actions get dispatched by nog itself and don't need a callback
+            if kb.invocation.is_none() {
+                let source = format!("import nog\n{}", kb.action.clone().unwrap_or_default());
+                let source_path = path.clone();
+                let callback = Function::new("imported_keybinding", None, move |i, _| {
+                    i.execute_source(source_path.clone(), &source)?;
+                    Ok(Dynamic::Null)
+                });
+
+                let idx = cbs.lock().len();
+                cbs.lock().push(callback);
+                kb.callback_id = idx;
+            }
+
+            match kb.mode.clone() {
+                Some(mode) => mode_kbs.entry(mode).or_default().push(kb),
+                None => cfg.lock().add_keybinding(kb),
+            }
+        }
+
+        // Mode keybindings only get registered once the mode handler runs, so the imported ones
+        // get added by a handler that wraps the existing one (if there is one).
+        for (mode, kbs) in mode_kbs {
+            let prev_handler = cfg
+                .lock()
+                .mode_handlers
+                .get(&mode)
+                .and_then(|idx| cbs.lock().get(*idx).cloned());
+            let state = state.clone();
+
+            let handler = Function::new("imported_mode_handler", None, move |i, args| {
+                if let Some(prev_handler) = &prev_handler {
+                    prev_handler.invoke(i, args)?;
+                }
+
+                for kb in &kbs {
+                    state.lock().keybindings_manager.add_mode_keybinding(kb.clone());
+                }
+
+                Ok(Dynamic::Null)
+            });
+
+            let idx = cbs.lock().len();
+            cbs.lock().push(handler);
+            cfg.lock().mode_handlers.insert(mode, idx);
+        }
+
+        Ok(Dynamic::Null)
+    });
+
+    let state = state_arc.clone();
+    keybindings = keybindings.function("trigger", move |_, args| {
+        let key_combo = string!(&args[0])?;
+        let kb_manager = state.lock().keybindings_manager.clone();
+
+        kb_manager.trigger(key_combo)?;
+
+        Ok(Dynamic::Null)
+    });
+
+    let state = state_arc.clone();
+    keybindings = keybindings.function("suspend", move |_, _| {
+        state.lock().keybindings_manager.suspend();
+        Ok(Dynamic::Null)
+    });
+
+    let state = state_arc.clone();
+    keybindings = keybindings.function("resume", move |_, _| {
+        state.lock().keybindings_manager.resume();
+        Ok(Dynamic::Null)
+    });
+
+    let state = state_arc.clone();
+    keybindings = keybindings.function("toggle_suspend", move |_, _| {
+        state.lock().keybindings_manager.toggle_suspend();
+        Ok(Dynamic::Null)
+    });
+
+    let state = state_arc.clone();
+    keybindings = keybindings.function("is_suspended", move |_, _| {
+        Ok(Dynamic::from(
+            state.lock().keybindings_manager.is_suspended(),
+        ))
+    });
+
+    let state = state_arc.clone();
+    let cbs = callbacks_arc.clone();
+    keybindings = keybindings.function("list", move |_, _| {
+        let kbs = state.lock().keybindings_manager.get_keybindings();
+        let cbs = cbs.lock();
+
+        Ok(Dynamic::new_array(
+            kbs.iter()
+                .map(|kb| {
+                    // keybindings that call a function get described by the function
+                    let action = kb
+                        .invocation
+                        .as_ref()
+                        .map(|i| i.to_string())
+                        .or_else(|| kb.action.clone())
+                        .or_else(|| cbs.get(kb.callback_id).map(describe_function));
+                    let mut fields = HashMap::new();
+                    fields.insert("key".into(), kb.key_combo().into());
+                    fields.insert(
+                        "mode".into(),
+                        kb.mode.clone().map(Dynamic::from).unwrap_or_default(),
+                    );
+                    fields.insert(
+                        "workspaces".into(),
+                        Dynamic::new_array(
+                            kb.workspaces.iter().map(|id| Dynamic::from(*id)).collect(),
+                        ),
+                    );
+                    fields.insert(
+                        "action".into(),
+                        action.map(Dynamic::from).unwrap_or_default(),
+                    );
+                    fields.insert(
+                        "description".into(),
+                        kb.description
+                            .clone()
+                            .map(Dynamic::from)
+                            .unwrap_or_default(),
+                    );
+                    Dynamic::new_object(fields)
+                })
+                .collect(),
+        ))
+    });
+
+    let mut actions_mod = Module::new("actions");
+
+    let state = state_arc.clone();
+    actions_mod = actions_mod.function("run", move |_, args| {
+        let event = actions::parse_command(string!(&args[0])?)?;
+
+        state
+            .lock()
+            .event_channel
+            .sender
+            .send(event)
+            .expect("Failed to send action event");
+
+        Ok(Dynamic::Null)
+    });
+
+    actions_mod = actions_mod.function("list", move |_, _| {
+        Ok(Dynamic::new_array(
+            actions::all()
+                .iter()
+                .map(|action| {
+                    let mut fields = HashMap::new();
+                    fields.insert("name".into(), action.name.into());
+                    fields.insert("description".into(), action.description.into());
+                    fields.insert("arg_count".into(), action.arg_count.into());
+                    Dynamic::new_object(fields)
+                })
+                .collect(),
+        ))
+    });
+
+    actions_mod = actions_mod.function("queries", move |_, _| {
+        Ok(Dynamic::new_array(
+            actions::pipeline::all_queries()
+                .iter()
+                .map(|query| {
+                    let mut fields = HashMap::new();
+                    fields.insert("name".into(), query.name.into());
+                    fields.insert("description".into(), query.description.into());
+                    fields.insert("arg_count".into(), query.arg_count.into());
+                    fields.insert("produces".into(), query.produces.to_string().into());
+                    Dynamic::new_object(fields)
+                })
+                .collect(),
+        ))
+    });
+
+    let mut log_mod = Module::new("log");
+
+    log_mod = log_mod.function("tail", move |_, args| {
+        let count = match args.get(0) {
+            Some(count) => (*number!(count)?).max(0) as usize,
+            None => 100,
+        };
+
+        Ok(Dynamic::new_array(
+            logging::tail(count)
+                .into_iter()
+                .map(|entry| {
+                    let mut fields = HashMap::new();
+                    fields.insert("time".into(), entry.time.into());
+                    fields.insert("level".into(), entry.level.to_string().into());
+                    fields.insert("module".into(), entry.module.into());
+                    fields.insert("message".into(), entry.message.into());
+                    Dynamic::new_object(fields)
+                })
+                .collect(),
+        ))
+    });
+
+    log_mod = log_mod.function("set_level", move |_, args| {
+        let module = string!(&args[0])?;
+        let level = string!(&args[1])?;
+
+        logging::set_level(module, level)?;
+
+        Ok(Dynamic::Null)
+    });
+
+    log_mod = log_mod.function("levels", move |_, _| {
+        let mut levels = HashMap::new();
+
+        for (module, level) in logging::levels() {
+            levels.insert(module, level.to_string().to_lowercase().into());
+        }
+
+        Ok(Dynamic::new_object(levels))
+    });
+
+    let mut debug_mod = Module::new("debug");
+
+    let state = state_arc.clone();
+    debug_mod = debug_mod.function("layout_history", move |_, args| {
+        let count = match args.get(0) {
+            Some(count) => (*number!(count)?).max(0) as usize,
+            None => 10,
+        };
+        let workspace_id = match args.get(1) {
+            Some(id) => *number!(id)?,
+            None => state.lock().workspace_id,
+        };
+
+        Ok(Dynamic::new_array(
+            history::tail(workspace_id, count)
+                .into_iter()
+                .map(|change| {
+                    let mut fields = HashMap::new();
+                    fields.insert("time".into(), change.time.clone().into());
+                    fields.insert("workspace".into(), change.workspace_id.into());
+                    fields.insert("diff".into(), change.diff().join("\n").into());
+                    fields.insert("text".into(), change.to_string().into());
+                    fields.insert("before".into(), change.before.into());
+                    fields.insert("after".into(), change.after.into());
+                    Dynamic::new_object(fields)
+                })
+                .collect(),
+        ))
+    });
+
+    let state = state_arc.clone();
+    debug_mod = debug_mod.function("dump", move |_, args| {
+        let state = state.lock();
+        let name = match args.get(0) {
+            Some(name) => string!(name)?.clone(),
+            None => "dump.json".into(),
+        };
+        let path = fs::resolve_writable(&state.config.path, &name, state.config.unrestricted_fs)?;
+
+        debug_dump::write(&state, &path).map_err(|e| e.to_string())?;
+
+        Ok(path.to_string_lossy().to_string())
+    });
+
+    let cbs = callbacks_arc.clone();
+    debug_mod = debug_mod.function("interpreter_stats", move |i, _| {
+        let callbacks = cbs.lock();
+        let callback_time = stats::callbacks()
+            .into_iter()
+            .sorted_by_key(|(_, stats)| std::cmp::Reverse(stats.total_time))
+            .map(|(idx, stats)| {
+                let name = callbacks
+                    .get(idx)
+                    .map(|cb| cb.name.clone())
+                    .unwrap_or_default();
+                let mut fields = HashMap::new();
+                fields.insert("index".into(), idx.into());
+                fields.insert("name".into(), name.into());
+                fields.insert("calls".into(), stats.calls.into());
+                fields.insert(
+                    "total_ms".into(),
+                    (stats.total_time.as_millis() as i32).into(),
+                );
+                Dynamic::new_object(fields)
+            })
+            .collect();
+
+        let mut fields = HashMap::new();
+        fields.insert("scopes".into(), i.scopes.len().into());
+        fields.insert("modules".into(), i.modules.len().into());
+        fields.insert("cached_modules".into(), i.module_cache.len().into());
+        fields.insert("callbacks".into(), callbacks.len().into());
+        fields.insert("objects".into(), interpreter::live_containers().into());
+        fields.insert("callback_time".into(), Dynamic::new_array(callback_time));
+
+        Ok(Dynamic::new_object(fields))
+    });
+
+    let mut profile_mod = Module::new("profile");
+
+    let state = state_arc.clone();
+    let cfg = config.clone();
+    let cbs = callbacks_arc.clone();
+    let is_init2 = is_init.clone();
+    profile_mod = profile_mod.function("define", move |_i, args| {
+        let mut profile = Profile::default();
+        profile.name = string!(&args[0])?.clone();
+        let callback = args[1].clone().as_fn()?;
+
+        if let Some(conditions) = args.get(2) {
+            let conditions_ref = object!(conditions)?;
+            let conditions = conditions_ref.lock().unwrap();
+
+            for (key, val) in conditions.iter() {
+                match key.as_str() {
+                    "hostname" => profile.hostname = Some(string!(val)?.clone()),
+                    "displays" => profile.displays = Some((*number!(val)?).max(0) as usize),
+                    x => return Err(format!("Unknown profile condition '{}'", x).into()),
+                }
+            }
+        }
+
+        let mut cbs = cbs.lock();
+        profile.callback_id = cbs.len();
+        cbs.push(callback);
+
+        if is_init2() {
+            cfg.lock().profiles.push(profile);
+        } else {
+            state.lock().config.profiles.push(profile);
+        }
+
+        Ok(Dynamic::Null)
+    });
+
+    let state = state_arc.clone();
+    let cfg = config.clone();
+    let cbs = callbacks_arc.clone();
+    let is_init2 = is_init.clone();
+    profile_mod = profile_mod.function("switch", move |i, args| {
+        let name = string!(&args[0])?.clone();
+
+        // the profile gets applied once the config finished running
+        if is_init2() {
+            cfg.lock().profile = Some(name);
+            return Ok(Dynamic::Null);
+        }
+
+        let mut config = state.lock().config.clone();
+        let profile = config
+            .profiles
+            .iter()
+            .find(|p| p.name == name)
+            .cloned()
+            .ok_or_else(|| format!("Unknown profile '{}'", name))?;
+        let callback = cbs.lock()[profile.callback_id].clone();
+        let base = config
+            .profile_base
+            .clone()
+            .unwrap_or_else(|| ProfileSettings::from_config(&config));
+
+        base.apply(&mut config);
+        *cfg.lock() = config;
+
+        applying_profile.store(true, Ordering::SeqCst);
+        let result = callback.invoke(i, vec![]);
+        applying_profile.store(false, Ordering::SeqCst);
+        result?;
+
+        let settings = ProfileSettings::from_config(&cfg.lock());
+        change_setting(state.clone(), "profile", |config| {
+            settings.apply(config);
+            config.profile = Some(name);
+            config.profile_base = Some(base);
+            Ok(())
+        })?;
+
+        Ok(Dynamic::Null)
+    });
+
+    let state = state_arc.clone();
+    let cfg = config.clone();
+    let is_init2 = is_init.clone();
+    profile_mod = profile_mod.function("current", move |_i, _args| {
+        let profile = if is_init2() {
+            cfg.lock().profile.clone()
+        } else {
+            state.lock().config.profile.clone()
+        };
+
+        Ok(profile.map(Dynamic::from).unwrap_or_default())
+    });
+
     let mut root = Module::new("nog")
         .variable("version", option_env!("NOG_VERSION").unwrap_or("DEV"))
         .variable("workspace", workspace)
@@ -713,8 +2795,23 @@ pub fn create_root_module(
         .variable("rules", rules)
         .variable("window", window)
         .variable("popup", popup)
+        .variable("osd", osd)
+        .variable("clipboard", clipboard)
+        .variable("http", http_mod)
+        .variable("fs", fs_mod)
+        .variable("media", media)
+        .variable("system", system_mod)
+        .variable("display", display_mod)
+        .variable("env", env)
+        .variable("scheduler", scheduler)
         .variable("bar", bar)
-        .variable("config", config_mod);
+        .variable("config", config_mod)
+        .variable("keybindings", keybindings)
+        .variable("actions", actions_mod)
+        .variable("log", log_mod)
+        .variable("debug", debug_mod)
+        .variable("profile", profile_mod)
+        .variable("game_mode", game_mode_mod);
 
     let state = state_arc.clone();
     root = root.function("quit", move |_i, _args| {
@@ -723,6 +2820,62 @@ pub fn create_root_module(
         Ok(Dynamic::Null)
     });
 
+    let state = state_arc.clone();
+    let cfg = config.clone();
+    let cbs = callbacks_arc.clone();
+    let is_init2 = is_init.clone();
+    root = root.function("on_startup", move |_i, args| {
+        let callback = args[0].clone().as_fn()?;
+        let mut cbs = cbs.lock();
+
+        if is_init2() {
+            cfg.lock().on_startup.push(cbs.len());
+        } else {
+            state.lock().config.on_startup.push(cbs.len());
+        }
+        cbs.push(callback);
+
+        Ok(Dynamic::Null)
+    });
+
+    let state = state_arc.clone();
+    let cfg = config.clone();
+    let cbs = callbacks_arc.clone();
+    let is_init2 = is_init.clone();
+    root = root.function("on_shutdown", move |_i, args| {
+        let callback = args[0].clone().as_fn()?;
+        let mut cbs = cbs.lock();
+
+        if is_init2() {
+            cfg.lock().on_shutdown.push(cbs.len());
+        } else {
+            state.lock().config.on_shutdown.push(cbs.len());
+        }
+        cbs.push(callback);
+
+        Ok(Dynamic::Null)
+    });
+
+    let state = state_arc.clone();
+    let cfg = config.clone();
+    let cbs = callbacks_arc.clone();
+    let is_init2 = is_init.clone();
+    root = root.function("on_workspace_empty", move |_i, args| {
+        let callback = args[0].clone().as_fn()?;
+        let mut cbs = cbs.lock();
+
+        if is_init2() {
+            cfg.lock().on_workspace_empty.push(cbs.len());
+        } else {
+            state.lock().config.on_workspace_empty.push(cbs.len());
+        }
+        cbs.push(callback);
+
+        Ok(Dynamic::Null)
+    });
+
+    root = root.function("state", move |_i, _args| Ok(snapshot::get().into_dynamic()));
+
     let state = state_arc.clone();
     root = root.function("toggle_work_mode", move |_i, _args| {
         AppState::toggle_work_mode(state.clone());
@@ -735,8 +2888,58 @@ pub fn create_root_module(
         Ok(Dynamic::Null)
     });
 
+    let state = state_arc.clone();
+    let cfg = config.clone();
+    let is_init2 = is_init.clone();
+
     root = root.function("launch", move |_i, args| {
-        system::api::launch_program(string!(&args[0])?.clone());
+        let cmd = string!(&args[0])?;
+        let policy = if is_init2() {
+            cfg.lock().launch_policy.clone()
+        } else {
+            state.lock().config.launch_policy.clone()
+        };
+
+        launcher::launch(&policy, cmd)?;
+
+        Ok(Dynamic::Null)
+    });
+
+    let state = state_arc.clone();
+    let cfg = config.clone();
+    let is_init2 = is_init.clone();
+
+    root = root.function("configure_launch", move |_i, args| {
+        let settings_ref = object!(&args[0])?;
+        let settings = settings_ref.lock().unwrap();
+        let mut policy = LaunchPolicy::default();
+
+        let to_strings = |val: &Dynamic| -> RuntimeResult<Vec<String>> {
+            val.clone()
+                .as_array()?
+                .iter()
+                .map(|x| string!(x).map(|x| x.clone()))
+                .collect()
+        };
+
+        for (key, val) in settings.iter() {
+            match key.as_str() {
+                "allowed" => policy.allowed = Some(to_strings(val)?),
+                "blocked" => policy.blocked = to_strings(val)?,
+                "working_directory" => policy.working_directory = Some(string!(val)?.into()),
+                "env" => policy.env = Some(to_strings(val)?),
+                "timeout" => policy.timeout = Some((*number!(val)?).max(0) as u64),
+                _ => return Err(format!("Unknown launch policy setting '{}'", key).into()),
+            }
+        }
+
+        // every call can only restrict the policy further, so imported modules can't loosen it
+        if is_init2() {
+            cfg.lock().launch_policy.restrict(policy)?;
+        } else {
+            state.lock().config.launch_policy.restrict(policy)?;
+        }
+
         Ok(Dynamic::Null)
     });
 
@@ -754,7 +2957,7 @@ pub fn create_root_module(
 
         let bind_fn = Function::new("bind", None, move |_, args| {
             // THIS FUNCTION
-            let mut kb = kb_from_args(cbs_arc.clone(), args);
+            let mut kb = kb_from_args(cbs_arc.clone(), args)?;
             kb.mode = Some(mode.clone());
             state2.lock().keybindings_manager.add_mode_keybinding(kb);
             Ok(Dynamic::Null)
@@ -774,7 +2977,7 @@ pub fn create_root_module(
     let cfg = config.clone();
     let cbs = callbacks_arc.clone();
     root = root.function("bind", move |_i, args| {
-        let kb = kb_from_args(cbs.clone(), args);
+        let kb = kb_from_args(cbs.clone(), args)?;
         cfg.lock().add_keybinding(kb);
 
         Ok(())
@@ -807,7 +3010,7 @@ pub fn create_root_module(
                 always_active.into(),
             ];
 
-            let kb = kb_from_args(cbs.clone(), args);
+            let kb = kb_from_args(cbs.clone(), args)?;
             cfg.lock().add_keybinding(kb);
         }
 
@@ -841,7 +3044,7 @@ pub fn create_root_module(
                 always_active.into(),
             ];
 
-            let kb = kb_from_args(cbs.clone(), args);
+            let kb = kb_from_args(cbs.clone(), args)?;
             cfg.lock().add_keybinding(kb);
         }
 
@@ -1 +1,4 @@
+pub mod fs;
 pub mod lib;
+pub mod stats;
+pub mod test_runner;
@@ -0,0 +1,29 @@
+//! Measures how long the callbacks of the config take, so that slow or leaking callbacks can be
+//! found with `nog.debug.interpreter_stats`.
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use std::{collections::HashMap, time::Duration};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CallbackStats {
+    pub calls: usize,
+    pub total_time: Duration,
+}
+
+lazy_static! {
+    static ref CALLBACKS: Mutex<HashMap<usize, CallbackStats>> = Mutex::new(HashMap::new());
+}
+
+/// Adds a call of the callback that took the given time.
+pub fn record(idx: usize, time: Duration) {
+    let mut callbacks = CALLBACKS.lock();
+    let stats = callbacks.entry(idx).or_default();
+
+    stats.calls += 1;
+    stats.total_time += time;
+}
+
+/// Returns the stats of every callback that got called at least once, keyed by its index.
+pub fn callbacks() -> HashMap<usize, CallbackStats> {
+    CALLBACKS.lock().clone()
+}
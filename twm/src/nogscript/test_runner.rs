@@ -0,0 +1,119 @@
+//! `nog.exe test` runs the tests of the config (see `interpreter::testing`).
+//!
+//! Tests get a copy of the nog module whose functions only record that they got called, which
+//! keeps a test from touching the windows of the user. The recorded calls can be checked using the
+//! `mock` module, e.g. `assert(mock.called("nog.config.set(\"outer_gap\", 10)"))`.
+use crate::{config::Config, nogscript::lib::create_root_module, AppState};
+use interpreter::{testing, Dynamic, Interpreter, Module, RuntimeError};
+use parking_lot::Mutex;
+use std::{path::PathBuf, sync::Arc};
+
+type Calls = Arc<Mutex<Vec<String>>>;
+
+/// Replaces every function of the module and its submodules with one that records its calls
+fn mock(module: Module, path: &str, calls: &Calls) -> Module {
+    let mut mocked = Module::new(&module.name);
+
+    for (name, value) in module.variables {
+        let path = format!("{}.{}", path, name);
+
+        mocked = match value {
+            Dynamic::Module(inner) => mocked.variable(&name, mock(*inner, &path, calls)),
+            Dynamic::RustFunction { .. } => {
+                let calls = calls.clone();
+                mocked.function(&name, move |_i, args| {
+                    let args = args.iter().map(testing::describe).collect::<Vec<_>>();
+                    calls.lock().push(format!("{}({})", path, args.join(", ")));
+                    Ok(Dynamic::Null)
+                })
+            }
+            value => mocked.variable(&name, value),
+        };
+    }
+
+    mocked
+}
+
+/// The `mock` module, which lets tests inspect the calls of the nog module
+fn create_mock_module(calls: &Calls) -> Module {
+    let mut module = Module::new("mock");
+
+    let c = calls.clone();
+    module = module.function("calls", move |_i, _args| {
+        Ok(Dynamic::new_array(
+            c.lock().iter().cloned().map(Dynamic::from).collect(),
+        ))
+    });
+
+    // either the name of a function or a whole call, including its arguments
+    let c = calls.clone();
+    module = module.function("called", move |_i, args| {
+        let text = string!(&args[0])?.clone();
+        let prefix = format!("{}(", text);
+
+        Ok(c.lock()
+            .iter()
+            .any(|call| call == &text || call.starts_with(&prefix)))
+    });
+
+    let c = calls.clone();
+    module = module.function("reset", move |_i, _args| {
+        c.lock().clear();
+        Ok(())
+    });
+
+    module
+}
+
+/// Runs every test inside of the directory, which defaults to the config directory, and prints
+/// the results. Returns the exit code, which is 1 if a test failed.
+pub fn run(dir: Option<PathBuf>) -> i32 {
+    let mut config_path = dirs::config_dir().unwrap_or_default();
+    config_path.push("nog");
+
+    let dir = dir.unwrap_or(config_path.clone());
+    let calls: Calls = Default::default();
+    let config = Config::default();
+
+    let nog = create_root_module(
+        || true,
+        Arc::new(Mutex::new(AppState::default())),
+        Default::default(),
+        Arc::new(Mutex::new(Interpreter::new())),
+        Arc::new(Mutex::new(config.clone())),
+    );
+    let nog = mock(nog, "nog", &calls);
+    let mock_module = create_mock_module(&calls);
+
+    let mut passed = 0;
+    let mut failed = 0;
+
+    for file in testing::discover(&dir) {
+        let mut interpreter = Interpreter::new();
+
+        interpreter.limits = config.script_limits();
+        interpreter.source_locations = vec![config_path.clone(), dir.clone()];
+        interpreter.add_module(nog.clone());
+        interpreter.add_module(mock_module.clone());
+
+        let reports = testing::run_file(&mut interpreter, &file, || calls.lock().clear());
+
+        for report in reports {
+            println!("{}", report);
+
+            if report.passed() {
+                passed += 1;
+            } else {
+                failed += 1;
+            }
+        }
+    }
+
+    println!("\n{} passed, {} failed", passed, failed);
+
+    if failed > 0 {
+        1
+    } else {
+        0
+    }
+}
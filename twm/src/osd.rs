@@ -0,0 +1,92 @@
+use crate::{popup, system::SystemResult, window::Window, AppState};
+use log::debug;
+use parking_lot::Mutex;
+use std::{
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+static OSD: Mutex<Option<Window>> = Mutex::new(None);
+/// Gets incremented every time an osd is shown, so the fade out of a replaced osd knows that it
+/// has to stop.
+static GENERATION: AtomicUsize = AtomicUsize::new(0);
+/// Whether the current osd stays visible until it gets unpinned
+static PINNED: AtomicBool = AtomicBool::new(false);
+
+const FADE_STEPS: u64 = 10;
+const FADE_DURATION: u64 = 200;
+
+/// Shows the text in a small popup that fades out after the configured duration.
+///
+/// An osd that is currently visible gets replaced.
+pub fn show(text: String, state_arc: Arc<Mutex<AppState>>) -> SystemResult {
+    let duration = state_arc.lock().config.osd_duration.max(0) as u64;
+    let (window, generation) = open(text, false, state_arc)?;
+
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(duration));
+
+        for step in (0..FADE_STEPS).rev() {
+            if GENERATION.load(Ordering::SeqCst) != generation {
+                return;
+            }
+
+            let opacity = (255 * step / FADE_STEPS) as u8;
+            let _ = window.get_native_window().set_opacity(opacity);
+
+            thread::sleep(Duration::from_millis(FADE_DURATION / FADE_STEPS));
+        }
+
+        if GENERATION.load(Ordering::SeqCst) == generation {
+            let _ = close();
+        }
+    });
+
+    Ok(())
+}
+
+/// Shows the text in an osd that stays visible until `unpin` gets called or another osd replaces
+/// it.
+pub fn pin(text: String, state_arc: Arc<Mutex<AppState>>) -> SystemResult {
+    open(text, true, state_arc).map(|_| ())
+}
+
+/// Closes the current osd if it is pinned.
+pub fn unpin() -> SystemResult {
+    if PINNED.swap(false, Ordering::SeqCst) {
+        close()?;
+    }
+
+    Ok(())
+}
+
+/// Replaces the current osd and returns the new one together with its generation
+fn open(
+    text: String,
+    pinned: bool,
+    state_arc: Arc<Mutex<AppState>>,
+) -> SystemResult<(Window, usize)> {
+    close()?;
+
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    PINNED.store(pinned, Ordering::SeqCst);
+
+    debug!("Showing osd '{}'", text);
+
+    let (window, _) = popup::create_text_window(text, 10, None, None, state_arc);
+
+    *OSD.lock() = Some(window.clone());
+
+    Ok((window, generation))
+}
+
+/// Close the current osd, if there is one.
+pub fn close() -> SystemResult {
+    if let Some(window) = OSD.lock().take() {
+        window.close()?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,179 @@
+//! The overview shows the workspaces of a display next to each other as miniatures of their
+//! layouts, so that the workspace to switch to can be picked by what is on it instead of by its
+//! number.
+use crate::{direction::Direction, popup::Popup};
+
+/// How many miniatures are placed next to each other
+const COLUMNS: usize = 3;
+/// The size of a miniature in characters
+pub const MINIATURE_WIDTH: u32 = 24;
+pub const MINIATURE_HEIGHT: u32 = 6;
+
+#[derive(Debug, Clone)]
+pub struct OverviewItem {
+    pub workspace_id: i32,
+    /// The number that is shown for the workspace
+    pub number: i32,
+    /// The rows of the miniature, which are empty if the workspace has no windows
+    pub miniature: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Overview {
+    pub items: Vec<OverviewItem>,
+    /// Index of the selected workspace
+    pub selected: usize,
+}
+
+impl Overview {
+    pub fn new(items: Vec<OverviewItem>, focused_workspace_id: Option<i32>) -> Self {
+        let selected = items
+            .iter()
+            .position(|item| Some(item.workspace_id) == focused_workspace_id)
+            .unwrap_or(0);
+
+        Self { items, selected }
+    }
+
+    /// Moves the selection to the neighboring miniature in the direction. The selection stops at
+    /// the edges.
+    pub fn move_selection(&mut self, direction: Direction) {
+        let column = self.selected % COLUMNS;
+        let target = match direction {
+            Direction::Left if column > 0 => self.selected - 1,
+            Direction::Right if column + 1 < COLUMNS => self.selected + 1,
+            Direction::Up if self.selected >= COLUMNS => self.selected - COLUMNS,
+            Direction::Down => self.selected + COLUMNS,
+            _ => self.selected,
+        };
+
+        if target < self.items.len() {
+            self.selected = target;
+        }
+    }
+
+    /// Selects the workspace with the number and returns whether the display has it.
+    pub fn select_number(&mut self, number: i32) -> bool {
+        match self.items.iter().position(|item| item.number == number) {
+            Some(idx) => {
+                self.selected = idx;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the id of the selected workspace
+    pub fn selection(&self) -> Option<i32> {
+        self.items.get(self.selected).map(|item| item.workspace_id)
+    }
+
+    fn cell(&self, idx: usize) -> Vec<String> {
+        let item = &self.items[idx];
+        let marker = if idx == self.selected { ">" } else { " " };
+        let width = MINIATURE_WIDTH as usize;
+        let pad = |line: &str| format!("{:<width$}", line, width = width);
+
+        let mut lines = vec![pad(&format!("{} {}", marker, item.number))];
+
+        if item.miniature.is_empty() {
+            lines.extend((0..MINIATURE_HEIGHT).map(|i| {
+                if i == MINIATURE_HEIGHT / 2 {
+                    pad("  (empty)")
+                } else {
+                    pad("")
+                }
+            }));
+        } else {
+            lines.extend(item.miniature.iter().map(|line| pad(line)));
+        }
+
+        lines
+    }
+
+    pub fn to_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        for start in (0..self.items.len()).step_by(COLUMNS) {
+            let cells = (start..self.items.len().min(start + COLUMNS))
+                .map(|idx| self.cell(idx))
+                .collect::<Vec<_>>();
+
+            if start > 0 {
+                lines.push("".into());
+            }
+
+            for row in 0..cells[0].len() {
+                let line = cells
+                    .iter()
+                    .map(|cell| cell[row].as_str())
+                    .collect::<Vec<_>>()
+                    .join("   ");
+
+                lines.push(line.trim_end().to_string());
+            }
+        }
+
+        lines
+    }
+
+    pub fn to_popup(&self) -> Popup {
+        Popup::new().with_padding(5).with_text(self.to_lines())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overview(count: i32) -> Overview {
+        let items = (1..=count)
+            .map(|number| OverviewItem {
+                workspace_id: number,
+                number,
+                miniature: Vec::new(),
+            })
+            .collect();
+
+        Overview::new(items, Some(2))
+    }
+
+    #[test]
+    fn selection_moves_between_miniatures() {
+        let mut overview = overview(5);
+        assert_eq!(overview.selection(), Some(2));
+
+        overview.move_selection(Direction::Right);
+        assert_eq!(overview.selection(), Some(3));
+
+        overview.move_selection(Direction::Right);
+        assert_eq!(overview.selection(), Some(3));
+
+        overview.move_selection(Direction::Down);
+        assert_eq!(overview.selection(), Some(3));
+
+        overview.move_selection(Direction::Left);
+        overview.move_selection(Direction::Down);
+        assert_eq!(overview.selection(), Some(5));
+
+        overview.move_selection(Direction::Up);
+        assert_eq!(overview.selection(), Some(2));
+
+        assert!(overview.select_number(4));
+        assert_eq!(overview.selection(), Some(4));
+        assert!(!overview.select_number(9));
+    }
+
+    #[test]
+    fn miniatures_are_placed_in_rows() {
+        let lines = overview(4).to_lines();
+
+        // two rows of a header and the miniature, separated by an empty line
+        assert_eq!(lines.len(), 2 * (1 + MINIATURE_HEIGHT as usize) + 1);
+        assert_eq!(
+            lines[0],
+            format!("  1{}> 2{}  3", " ".repeat(24), " ".repeat(24))
+        );
+        assert_eq!(lines[1 + MINIATURE_HEIGHT as usize + 1], "  4");
+    }
+}
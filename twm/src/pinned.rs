@@ -0,0 +1,158 @@
+//! Keeps windows above every other window. A pinned window floats and stays visible when the
+//! workspace changes, because only the windows of a workspace get hidden.
+//!
+//! A window that was tiled before it got pinned goes back into its tile once it gets unpinned,
+//! as long as the windows of its workspace didn't change in the meantime. Otherwise it gets
+//! appended to the workspace like a new window.
+use crate::{
+    system::{NativeWindow, SystemResult, WindowId},
+    tile_grid::history,
+    AppState,
+};
+use log::{debug, info};
+
+#[derive(Debug, Clone)]
+pub struct PinnedWindow {
+    pub window: NativeWindow,
+    /// The workspace the window was tiled in
+    pub workspace_id: Option<i32>,
+    /// The layout of the workspace before the window got pinned
+    pub layout: String,
+}
+
+pub fn is_pinned(state: &AppState, id: WindowId) -> bool {
+    state.pinned_windows.iter().any(|p| p.window.id == id)
+}
+
+fn pin(state: &mut AppState, window: NativeWindow) -> SystemResult {
+    let config = state.config.clone();
+    let mut pinned = PinnedWindow {
+        window,
+        workspace_id: None,
+        layout: String::new(),
+    };
+
+    if let Some(grid) = state.find_grid_containing_window(pinned.window.id) {
+        let grid_id = grid.id;
+        pinned.workspace_id = Some(grid_id);
+        pinned.layout = grid.to_string();
+
+        if let Some(mut w) = grid.remove_by_window_id(pinned.window.id) {
+            debug!("Unmanaging window '{}' | {}", w.title, w.id);
+            w.cleanup()?;
+            // the managed window knows its rule, which it needs once it gets tiled again
+            pinned.window = w;
+        }
+
+        if let Some(d) = state.find_grid_display(grid_id) {
+            d.refresh_grid(&config)?;
+        }
+    }
+
+    info!("Pinning '{}' | {}", pinned.window.title, pinned.window.id);
+
+    pinned.window.set_topmost(true)?;
+    state.pinned_windows.push(pinned);
+
+    Ok(())
+}
+
+/// Puts the window back into the layout its workspace had before the window got pinned.
+fn reinsert(state: &mut AppState, pinned: PinnedWindow, workspace_id: i32) -> SystemResult {
+    let grid = match state.get_grid_by_id_mut(workspace_id) {
+        Some(grid) => grid,
+        None => return Ok(()),
+    };
+
+    let id = pinned.window.id;
+    let mut expected: Vec<i32> = history::parse_layout(&pinned.layout)
+        .iter()
+        .map(|p| p.window_id)
+        .collect();
+    let mut current: Vec<i32> = grid.get_window_ids().into_iter().map(Into::into).collect();
+    current.push(id.into());
+    expected.sort();
+    current.sort();
+
+    if expected != current {
+        debug!(
+            "The windows of workspace {} changed, appending {}",
+            workspace_id, id
+        );
+        grid.push(pinned.window);
+        return Ok(());
+    }
+
+    let mut windows = vec![pinned.window];
+    for id in grid.get_window_ids() {
+        if let Some(window) = grid.remove_by_window_id(id) {
+            windows.push(window);
+        }
+    }
+
+    grid.from_string(&pinned.layout);
+
+    // the layout only contains the ids, so the windows lose what nog knows about them otherwise
+    grid.modify_windows(|window| {
+        if let Some(known) = windows.iter().find(|w| w.id == window.id) {
+            *window = known.clone();
+        }
+
+        Ok(())
+    })?;
+
+    grid.focus_tile_by_window_id(id);
+
+    Ok(())
+}
+
+fn unpin(state: &mut AppState, id: WindowId) -> SystemResult {
+    let idx = match state.pinned_windows.iter().position(|p| p.window.id == id) {
+        Some(idx) => idx,
+        None => return Ok(()),
+    };
+    let mut pinned = state.pinned_windows.remove(idx);
+
+    info!("Unpinning '{}' | {}", pinned.window.title, pinned.window.id);
+
+    pinned.window.remove_topmost()?;
+
+    if let Some(workspace_id) = pinned.workspace_id {
+        let config = state.config.clone();
+
+        pinned
+            .window
+            .init(config.remove_title_bar, config.use_border)?;
+        reinsert(state, pinned, workspace_id)?;
+
+        if let Some(d) = state.find_grid_display(workspace_id) {
+            d.refresh_grid(&config)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Pins the foreground window or unpins it if it already is pinned.
+pub fn toggle(state: &mut AppState) -> SystemResult {
+    let window = NativeWindow::get_foreground_window()?;
+
+    state.pinned_windows.retain(|p| p.window.is_window());
+
+    if is_pinned(state, window.id) {
+        unpin(state, window.id)
+    } else {
+        pin(state, window)
+    }
+}
+
+/// Unpins every window without tiling it again
+pub fn restore_all(state: &mut AppState) -> SystemResult {
+    for pinned in std::mem::take(&mut state.pinned_windows) {
+        if pinned.window.is_window() {
+            pinned.window.remove_topmost()?;
+        }
+    }
+
+    Ok(())
+}
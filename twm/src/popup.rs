@@ -1,6 +1,6 @@
 use crate::{
-    system::Rectangle, system::SystemResult, window::Window, window::WindowEvent, AppState,
-    NOG_POPUP_NAME,
+    bar::font, system::Rectangle, system::SystemResult, window::Window, window::WindowEvent,
+    AppState, NOG_POPUP_NAME,
 };
 use parking_lot::Mutex;
 use std::{fmt::Debug, sync::Arc, thread, thread::JoinHandle};
@@ -69,63 +69,15 @@ impl Popup {
             close()?;
         }
 
-        let state = state_arc.lock();
-
-        let text = self.text.join("\n");
-        let padding = self.padding;
-
-        let mut window = Window::new()
-            .with_title(NOG_POPUP_NAME)
-            .with_font(&state.config.bar.font)
-            .with_size(10, 10)
-            .with_font_size(state.config.bar.font_size)
-            .with_is_popup(true)
-            .with_background_color(state.config.bar.color);
-
-        drop(state);
-
-        let t = window.create(state_arc, true, move |event| {
-            match event {
-                WindowEvent::Draw {
-                    api,
-                    display_id,
-                    state_arc,
-                    ..
-                } => {
-                    let (display_width, display_height) = {
-                        let state = state_arc.lock();
-                        let display = state.get_display_by_id(*display_id).unwrap();
-
-                        (display.width(), display.height())
-                    };
-                    let rect = api.calculate_text_rect(&text);
-
-                    let height = rect.height();
-                    let width = rect.width();
-
-                    let x = display_width / 2 - width / 2 - padding;
-                    let y = display_height / 2 - height / 2 - padding;
-
-                    api.window
-                        .set_window_pos(
-                            Rectangle {
-                                left: x,
-                                right: x + width + padding * 2,
-                                top: y,
-                                bottom: y + height + padding * 2,
-                            },
-                            None,
-                            None,
-                        )
-                        .expect("Failed to move popup to its location");
-
-                    api.set_text_color(0xffffff);
-                    api.write_text(&text, padding, padding, false, false);
-                }
-                _ => {}
-            }
-            Ok(())
-        });
+        // clicking the popup runs its first action
+        let on_click = self.actions.first().and_then(|a| a.cb.clone());
+        let (window, t) = create_text_window(
+            self.text.join("\n"),
+            self.padding,
+            None,
+            on_click,
+            state_arc,
+        );
 
         self.window = Some(window);
         *POPUP.lock() = Some(self.clone());
@@ -134,6 +86,82 @@ impl Popup {
     }
 }
 
+/// Creates a window that displays the given text. The window gets placed at the given position or
+/// in the center of the display if there is none. Clicking the window calls `on_click` and closes
+/// the popup.
+pub fn create_text_window(
+    text: String,
+    padding: i32,
+    position: Option<(i32, i32)>,
+    on_click: Option<PopupActionCallback>,
+    state_arc: Arc<Mutex<AppState>>,
+) -> (Window, JoinHandle<()>) {
+    let state = state_arc.lock();
+
+    let mut window = Window::new()
+        .with_title(NOG_POPUP_NAME)
+        .with_font(&font::choose(&state.config.bar.font).font)
+        .with_size(10, 10)
+        .with_font_size(state.config.bar.font_size)
+        .with_is_popup(true)
+        .with_background_color(state.config.bar.color);
+
+    drop(state);
+
+    let t = window.create(state_arc, true, move |event| {
+        match event {
+            WindowEvent::Draw {
+                api,
+                display_id,
+                state_arc,
+                ..
+            } => {
+                let (display_width, display_height) = {
+                    let state = state_arc.lock();
+                    let display = state.get_display_by_id(*display_id).unwrap();
+
+                    (display.width(), display.height())
+                };
+                let rect = api.calculate_text_rect(&text);
+
+                let height = rect.height();
+                let width = rect.width();
+
+                let (x, y) = position.unwrap_or((
+                    display_width / 2 - width / 2 - padding,
+                    display_height / 2 - height / 2 - padding,
+                ));
+
+                api.window
+                    .set_window_pos(
+                        Rectangle {
+                            left: x,
+                            right: x + width + padding * 2,
+                            top: y,
+                            bottom: y + height + padding * 2,
+                        },
+                        None,
+                        None,
+                    )
+                    .expect("Failed to move popup to its location");
+
+                api.set_text_color(0xffffff);
+                api.write_text(&text, padding, padding, false, false);
+            }
+            WindowEvent::Click { .. } => {
+                if let Some(cb) = on_click.as_ref() {
+                    cb();
+                    close().map_err(|e| e.to_string())?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    });
+
+    (window, t)
+}
+
 pub fn cleanup() -> SystemResult {
     close()
 }
@@ -7,6 +7,16 @@ pub use win::WinRenderer as NativeRenderer;
 
 pub mod win;
 
+/// The area a window gets drawn in during a layout pass
+#[derive(Debug, Clone, Copy)]
+pub struct TileRect<'a> {
+    pub window: &'a NativeWindow,
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
 pub trait Renderer {
     fn render<TRenderer: Renderer>(
         &self,
@@ -19,6 +29,35 @@ pub trait Renderer {
         width: i32,
         height: i32,
     ) -> SystemResult;
+    /// Renders every tile of a layout pass. Renderers that are able to move several windows at
+    /// once should override this, so that the tiles don't get moved one after another.
+    fn render_all<TRenderer: Renderer>(
+        &self,
+        grid: &TileGrid<TRenderer>,
+        tiles: &[TileRect],
+        config: &Config,
+        display: &Display,
+    ) -> SystemResult {
+        for tile in tiles {
+            self.render(
+                grid,
+                tile.window,
+                config,
+                display,
+                tile.x,
+                tile.y,
+                tile.width,
+                tile.height,
+            )?;
+        }
+
+        Ok(())
+    }
+    /// Changes the opacity of a window, where 255 is fully opaque. Renderers that can't change
+    /// the opacity ignore this.
+    fn set_opacity(&self, _window: &NativeWindow, _opacity: u8) -> SystemResult {
+        Ok(())
+    }
     /// Converts the percentage to the real pixel value of the current display
     fn percentage_to_real(&self, p: i32, display: &Display, config: &Config) -> i32 {
         display.working_area_height(config) / 100 * p
@@ -1,25 +1,27 @@
-use super::Renderer;
+use super::{Renderer, TileRect};
 use crate::{
     config::Config, display::Display, system::NativeWindow, system::SystemError,
     system::SystemResult, tile_grid::TileGrid,
 };
+use log::warn;
+use std::ptr;
 use winapi::{shared::windef::*, um::winuser::*};
 
 #[derive(Default, Clone, Copy, Debug)]
 pub struct WinRenderer;
 
-impl Renderer for WinRenderer {
-    fn render<TRenderer: Renderer>(
+impl WinRenderer {
+    /// Returns the rectangle the window has to be moved to, so that its visible part fills the
+    /// tile.
+    fn window_rect(
         &self,
-        grid: &TileGrid<TRenderer>,
         window: &NativeWindow,
-        config: &Config,
         display: &Display,
         x: i32,
         y: i32,
         width: i32,
         height: i32,
-    ) -> SystemResult {
+    ) -> RECT {
         let rule = window.rule.clone().unwrap_or_default();
 
         let mut left = x;
@@ -31,13 +33,13 @@ impl Renderer for WinRenderer {
             let border_width = GetSystemMetricsForDpi(SM_CXFRAME, display.dpi);
             let border_height = GetSystemMetricsForDpi(SM_CYFRAME, display.dpi);
 
-            if rule.chromium || rule.firefox || !config.remove_title_bar {
+            if rule.chromium || rule.firefox || !window.title_bar_removed {
                 let caption_height = GetSystemMetricsForDpi(SM_CYCAPTION, display.dpi);
                 top += caption_height;
             } else {
                 top -= border_height * 2;
 
-                if config.use_border {
+                if window.has_border {
                     left += 1;
                     right -= 1;
                     top += 1;
@@ -47,7 +49,7 @@ impl Renderer for WinRenderer {
 
             if rule.firefox
                 || rule.chromium
-                || (!config.remove_title_bar && rule.has_custom_titlebar)
+                || (!window.title_bar_removed && rule.has_custom_titlebar)
             {
                 if rule.firefox {
                     left -= (border_width as f32 * 1.5) as i32;
@@ -88,8 +90,93 @@ impl Renderer for WinRenderer {
 
         // println!("after {}", rect_to_string(rect));
 
+        rect
+    }
+}
+
+impl Renderer for WinRenderer {
+    fn render<TRenderer: Renderer>(
+        &self,
+        _grid: &TileGrid<TRenderer>,
+        window: &NativeWindow,
+        _config: &Config,
+        display: &Display,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    ) -> SystemResult {
+        let rect = self.window_rect(window, display, x, y, width, height);
+
         window
             .set_window_pos(rect.into(), None, Some(SWP_NOSENDCHANGING))
             .map_err(SystemError::DrawTile)
     }
+
+    fn set_opacity(&self, window: &NativeWindow, opacity: u8) -> SystemResult {
+        window.set_opacity(opacity).map_err(SystemError::SetOpacity)
+    }
+
+    /// Moves every window at once using `DeferWindowPos`, which avoids the flicker of windows
+    /// being moved one after another. Falls back to moving the windows one by one if Windows
+    /// refuses to defer one of them.
+    fn render_all<TRenderer: Renderer>(
+        &self,
+        grid: &TileGrid<TRenderer>,
+        tiles: &[TileRect],
+        config: &Config,
+        display: &Display,
+    ) -> SystemResult {
+        unsafe {
+            let mut hdwp = BeginDeferWindowPos(tiles.len() as i32);
+
+            for tile in tiles {
+                if hdwp.is_null() {
+                    break;
+                }
+
+                let rect = self.window_rect(
+                    tile.window,
+                    display,
+                    tile.x,
+                    tile.y,
+                    tile.width,
+                    tile.height,
+                );
+
+                // a failed call frees the handle
+                hdwp = DeferWindowPos(
+                    hdwp,
+                    tile.window.id.into(),
+                    ptr::null_mut(),
+                    rect.left,
+                    rect.top,
+                    rect.right - rect.left,
+                    rect.bottom - rect.top,
+                    SWP_NOSENDCHANGING,
+                );
+            }
+
+            if !hdwp.is_null() && EndDeferWindowPos(hdwp) != 0 {
+                return Ok(());
+            }
+        }
+
+        warn!("Failed to move the windows at once, moving them one by one instead");
+
+        for tile in tiles {
+            self.render(
+                grid,
+                tile.window,
+                config,
+                display,
+                tile.x,
+                tile.y,
+                tile.width,
+                tile.height,
+            )?;
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,216 @@
+//! Explains how the rules apply to a window and why nog doesn't tile it, which is what
+//! `nog.rules.test` and `nog.exe --test-rules` report.
+use crate::{
+    borderless,
+    config::{rule::Rule, Config},
+    pinned,
+    system::NativeWindow,
+    AppState,
+};
+use std::fmt;
+
+/// Returns why the window should float instead of being tiled or `None` if it should be tiled.
+pub fn float_reason(
+    config: &Config,
+    rule: &Rule,
+    width: i32,
+    height: i32,
+    is_resizable: bool,
+    is_dialog: bool,
+) -> Option<&'static str> {
+    match rule.float {
+        Some(true) => Some("its rule floats it"),
+        Some(false) => None,
+        None if width < config.min_width || height < config.min_height => {
+            Some("it is smaller than the minimum size")
+        }
+        None if config.float_fixed_size && is_dialog => Some("it is a dialog"),
+        None if config.float_fixed_size && !is_resizable => Some("it can't be resized"),
+        None => None,
+    }
+}
+
+/// Lists the settings the rule changes about its windows.
+pub fn describe(rule: &Rule) -> Vec<String> {
+    let mut actions = Vec::new();
+    let mut push = |name: &str, value: String| actions.push(format!("{}: {}", name, value));
+
+    if !rule.manage {
+        push("manage", "false".into());
+    }
+    if rule.workspace_id != -1 {
+        push("workspace_id", rule.workspace_id.to_string());
+    }
+    if let Some(float) = rule.float {
+        push("float", float.to_string());
+    }
+    if rule.has_custom_titlebar {
+        push("has_custom_titlebar", "true".into());
+    }
+    if rule.chromium {
+        push("chromium", "true".into());
+    }
+    if rule.firefox {
+        push("firefox", "true".into());
+    }
+    if let Some(remove_title_bar) = rule.remove_title_bar {
+        push("remove_title_bar", remove_title_bar.to_string());
+    }
+    if let Some(use_border) = rule.use_border {
+        push("use_border", use_border.to_string());
+    }
+    if let Some(opacity) = rule.unfocused_opacity {
+        push("unfocused_opacity", opacity.to_string());
+    }
+    if let Some(game) = rule.game {
+        push("game", game.to_string());
+    }
+    if !rule.pass_through.is_empty() {
+        push(
+            "pass_through",
+            format!("{} key combos", rule.pass_through.len()),
+        );
+    }
+
+    actions
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RuleCheck {
+    pub title: String,
+    pub process_name: String,
+    /// The patterns of the rules that match the window. Only the first one applies.
+    pub matching_rules: Vec<String>,
+    /// What the applied rule changes about the window
+    pub actions: Vec<String>,
+    pub managed: bool,
+    /// Why nog doesn't tile the window
+    pub reasons: Vec<String>,
+}
+
+impl RuleCheck {
+    pub fn new(state: &AppState, mut window: NativeWindow) -> Self {
+        let config = &state.config;
+
+        window.title = window.get_title().unwrap_or_default();
+
+        let matching = config
+            .rules
+            .iter()
+            .chain(state.additonal_rules.iter())
+            .filter(|rule| window.matches_rule(rule))
+            .collect::<Vec<_>>();
+        let rule = matching.first().cloned().cloned().unwrap_or_default();
+        let managed = state.get_grids().iter().any(|g| g.contains(window.id));
+        let mut reasons = Vec::new();
+
+        if !managed {
+            if borderless::is_borderless(state, window.id) {
+                reasons.push("it is borderless fullscreen".into());
+            }
+            if pinned::is_pinned(state, window.id) {
+                reasons.push("it is pinned".into());
+            }
+            if !rule.manage {
+                reasons.push(format!("the rule '{}' ignores it", rule.pattern));
+            }
+            if let Ok(rect) = window.get_rect() {
+                let reason = float_reason(
+                    config,
+                    &rule,
+                    rect.width(),
+                    rect.height(),
+                    window.is_resizable(),
+                    window.is_dialog(),
+                );
+                reasons.extend(reason.map(String::from));
+            }
+            if window.get_parent_window().is_ok() {
+                reasons.push("it has a parent window".into());
+            }
+            if !window.should_manage() {
+                reasons.push("it has no title bar or is a modal dialog".into());
+            }
+        }
+
+        Self {
+            process_name: window.get_process_name(),
+            title: window.title,
+            matching_rules: matching.iter().map(|r| r.pattern.to_string()).collect(),
+            actions: if matching.is_empty() {
+                Vec::new()
+            } else {
+                describe(&rule)
+            },
+            managed,
+            reasons,
+        }
+    }
+}
+
+impl fmt::Display for RuleCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |items: &[String]| {
+            if items.is_empty() {
+                "none".to_string()
+            } else {
+                items.join(", ")
+            }
+        };
+
+        writeln!(f, "Window: '{}' ({})", self.title, self.process_name)?;
+        writeln!(f, "Matching rules: {}", list(&self.matching_rules))?;
+        writeln!(f, "Applied settings: {}", list(&self.actions))?;
+        write!(f, "Managed: {}", if self.managed { "yes" } else { "no" })?;
+
+        if !self.managed {
+            write!(f, "\nNot tiled, because: {}", list(&self.reasons))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+
+    #[test]
+    fn describe_lists_changed_settings() {
+        let mut rule = Rule::default();
+
+        assert!(describe(&rule).is_empty());
+
+        rule.pattern = Regex::new("^Spotify").unwrap();
+        rule.workspace_id = 4;
+        rule.float = Some(false);
+        rule.use_border = Some(true);
+
+        assert_eq!(
+            describe(&rule),
+            vec!["workspace_id: 4", "float: false", "use_border: true"]
+        );
+    }
+
+    #[test]
+    fn report_explains_unmanaged_windows() {
+        let check = RuleCheck {
+            title: "Picture-in-Picture".into(),
+            process_name: "firefox.exe".into(),
+            matching_rules: vec!["Picture-in-Picture".into()],
+            actions: vec!["float: true".into()],
+            managed: false,
+            reasons: vec!["its rule floats it".into()],
+        };
+
+        assert_eq!(
+            check.to_string(),
+            "Window: 'Picture-in-Picture' (firefox.exe)\n\
+             Matching rules: Picture-in-Picture\n\
+             Applied settings: float: true\n\
+             Managed: no\n\
+             Not tiled, because: its rule floats it"
+        );
+    }
+}
@@ -0,0 +1,322 @@
+//! Runs callbacks of the config at certain times of the day or in a fixed interval.
+//!
+//! A schedule is described by a rule:
+//!
+//! * `"every 15m"` runs every 15 minutes (`s`, `m` and `h` are supported)
+//! * `"18:00"` or `"daily 18:00"` runs every day at 18:00
+//! * `"weekdays 9:00"` and `"weekends 10:30"` only run on the given days
+//! * `"mon,wed,fri 12:00"` runs on every listed day
+//!
+//! The time at which a job last ran gets stored in `scheduler.json`, which means that interval jobs
+//! keep their rhythm across restarts and a job that was missed while nog wasn't running gets
+//! executed once on startup.
+use crate::{event::Event, state_handle::StateHandle, AppState};
+use chrono::{Datelike, Duration, Local, NaiveDateTime, NaiveTime, Weekday};
+use log::{debug, error, info};
+use serde_json::{Map, Value};
+use std::{collections::HashMap, fs, path::PathBuf, str::FromStr, thread};
+
+const DATE_FORMAT: &'static str = "%Y-%m-%dT%H:%M:%S";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Schedule {
+    /// Runs at the given time on every listed day
+    At { days: Vec<Weekday>, time: NaiveTime },
+    /// Runs after every interval
+    Every(Duration),
+}
+
+impl Schedule {
+    /// Returns the first point in time after `after` at which the job has to run.
+    pub fn next_after(&self, after: NaiveDateTime) -> NaiveDateTime {
+        match self {
+            Schedule::At { days, time } => (0..=7)
+                .map(|offset| (after.date() + Duration::days(offset)).and_time(*time))
+                .find(|candidate| *candidate > after && days.contains(&candidate.weekday()))
+                .expect("A schedule has to contain at least one day"),
+            Schedule::Every(interval) => after + *interval,
+        }
+    }
+}
+
+fn parse_days(days: &str) -> Result<Vec<Weekday>, String> {
+    Ok(match days {
+        "daily" => vec![
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+            Weekday::Sat,
+            Weekday::Sun,
+        ],
+        "weekdays" => vec![
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+        ],
+        "weekends" => vec![Weekday::Sat, Weekday::Sun],
+        list => list
+            .split(",")
+            .map(|day| Weekday::from_str(day.trim()).map_err(|_| format!("Unknown day '{}'", day)))
+            .collect::<Result<Vec<Weekday>, String>>()?,
+    })
+}
+
+fn parse_interval(interval: &str) -> Result<Duration, String> {
+    let invalid = || format!("Invalid interval '{}'", interval);
+    let unit_idx = interval.char_indices().last().map(|(i, _)| i).unwrap_or(0);
+    let (amount, unit) = interval.split_at(unit_idx);
+    let amount = amount.parse::<i64>().map_err(|_| invalid())?;
+
+    if amount <= 0 {
+        return Err(invalid());
+    }
+
+    match unit {
+        "s" => Ok(Duration::seconds(amount)),
+        "m" => Ok(Duration::minutes(amount)),
+        "h" => Ok(Duration::hours(amount)),
+        _ => Err(invalid()),
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let rule = rule.trim().to_lowercase();
+        let parts = rule.split_whitespace().collect::<Vec<&str>>();
+
+        let (days, time) = match parts.as_slice() {
+            ["every", interval] => return parse_interval(interval).map(Schedule::Every),
+            [time] => ("daily", *time),
+            [days, time] => (*days, *time),
+            _ => return Err(format!("Invalid schedule '{}'", rule)),
+        };
+
+        Ok(Schedule::At {
+            days: parse_days(days)?,
+            time: NaiveTime::parse_from_str(time, "%H:%M")
+                .map_err(|_| format!("Invalid time '{}'", time))?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub name: String,
+    /// The rule the schedule got parsed from
+    pub rule: String,
+    pub schedule: Schedule,
+    pub callback_id: usize,
+}
+
+/// Keeps track of when each job ran for the last time.
+#[derive(Debug, Clone, Default)]
+pub struct Scheduler {
+    /// The point in time from which the next run of a job gets calculated. This is either the last
+    /// time the job ran or when it was seen for the first time.
+    last_runs: HashMap<String, NaiveDateTime>,
+}
+
+impl Scheduler {
+    fn get_path() -> PathBuf {
+        #[allow(unused_mut)]
+        let mut path: PathBuf = ["./log"].iter().collect();
+        #[cfg(not(debug_assertions))]
+        {
+            path = dirs::config_dir().expect("Failed to get config directory");
+
+            path.push("nog");
+        }
+
+        path.push("scheduler.json");
+        path
+    }
+
+    pub fn load() -> Self {
+        let value = fs::read_to_string(Scheduler::get_path())
+            .ok()
+            .and_then(|content| serde_json::from_str::<Value>(&content).ok());
+
+        let last_runs = value
+            .as_ref()
+            .and_then(|v| v.as_object())
+            .map(|entries| {
+                entries
+                    .iter()
+                    .filter_map(|(name, date)| {
+                        date.as_str()
+                            .and_then(|d| NaiveDateTime::parse_from_str(d, DATE_FORMAT).ok())
+                            .map(|d| (name.clone(), d))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self { last_runs }
+    }
+
+    pub fn save(&self) {
+        let entries = self
+            .last_runs
+            .iter()
+            .map(|(name, date)| {
+                (
+                    name.clone(),
+                    Value::String(date.format(DATE_FORMAT).to_string()),
+                )
+            })
+            .collect::<Map<String, Value>>();
+
+        if let Err(e) = fs::write(Scheduler::get_path(), Value::Object(entries).to_string()) {
+            error!("Error storing scheduler state {:?}", e);
+        }
+    }
+
+    /// Returns when the job runs next.
+    pub fn next_run(&self, job: &Job, now: NaiveDateTime) -> NaiveDateTime {
+        job.schedule
+            .next_after(self.last_runs.get(&job.name).cloned().unwrap_or(now))
+    }
+
+    /// Returns every job together with the time it runs next, the earliest first.
+    pub fn pending<'a>(
+        &self,
+        jobs: &'a [Job],
+        now: NaiveDateTime,
+    ) -> Vec<(&'a Job, NaiveDateTime)> {
+        let mut pending = jobs
+            .iter()
+            .map(|job| (job, self.next_run(job, now)))
+            .collect::<Vec<_>>();
+
+        pending.sort_by_key(|(_, next_run)| *next_run);
+
+        pending
+    }
+
+    /// Returns the jobs that have to run now and marks them as executed.
+    pub fn take_due<'a>(&mut self, jobs: &'a [Job], now: NaiveDateTime) -> Vec<&'a Job> {
+        let mut due = Vec::new();
+
+        for job in jobs {
+            match self.last_runs.get(&job.name) {
+                Some(last_run) if job.schedule.next_after(*last_run) <= now => {
+                    due.push(job);
+                }
+                Some(_) => continue,
+                None => {}
+            }
+
+            self.last_runs.insert(job.name.clone(), now);
+        }
+
+        due
+    }
+}
+
+/// Runs the jobs that are due
+fn tick(state: &mut AppState) {
+    let jobs = state.config.jobs.clone();
+    let now = Local::now().naive_local();
+    let prev_last_runs = state.scheduler.last_runs.clone();
+    let due = state.scheduler.take_due(&jobs, now);
+
+    for job in &due {
+        info!("Running scheduled job '{}'", job.name);
+
+        state
+            .event_channel
+            .sender
+            .send(Event::CallCallback {
+                idx: job.callback_id,
+                is_mode_callback: false,
+            })
+            .expect("Failed to send CallCallback event");
+    }
+
+    if state.scheduler.last_runs.len() != prev_last_runs.len() || !due.is_empty() {
+        debug!("Storing scheduler state");
+        state.scheduler.save();
+    }
+}
+
+/// Checks every second whether a job has to run.
+pub fn start(handle: StateHandle) {
+    handle.update(|state| state.scheduler = Scheduler::load());
+
+    thread::spawn(move || loop {
+        sleep!(1000);
+
+        handle.update(tick);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn job(rule: &str) -> Job {
+        Job {
+            name: rule.into(),
+            rule: rule.into(),
+            schedule: rule.parse().unwrap(),
+            callback_id: 0,
+        }
+    }
+
+    #[test]
+    fn parse_rules() {
+        assert_eq!(
+            "every 15m".parse::<Schedule>(),
+            Ok(Schedule::Every(Duration::minutes(15)))
+        );
+        assert_eq!(
+            "Mon,Fri 9:30".parse::<Schedule>(),
+            Ok(Schedule::At {
+                days: vec![Weekday::Mon, Weekday::Fri],
+                time: NaiveTime::from_hms(9, 30, 0),
+            })
+        );
+        assert!("every 0m".parse::<Schedule>().is_err());
+        assert!("someday 9:00".parse::<Schedule>().is_err());
+        assert!("weekdays 25:00".parse::<Schedule>().is_err());
+    }
+
+    #[test]
+    fn next_run_skips_excluded_days() {
+        // 2020-11-06 is a friday
+        let friday_evening = NaiveDate::from_ymd(2020, 11, 6).and_hms(18, 0, 0);
+        let schedule = "weekdays 9:00".parse::<Schedule>().unwrap();
+
+        assert_eq!(
+            schedule.next_after(friday_evening),
+            NaiveDate::from_ymd(2020, 11, 9).and_hms(9, 0, 0)
+        );
+    }
+
+    #[test]
+    fn jobs_only_run_once_they_are_due() {
+        let jobs = vec![job("every 1h")];
+        let mut scheduler = Scheduler::default();
+        let start = NaiveDate::from_ymd(2020, 11, 6).and_hms(12, 0, 0);
+
+        assert!(scheduler.take_due(&jobs, start).is_empty());
+        assert!(scheduler
+            .take_due(&jobs, start + Duration::minutes(30))
+            .is_empty());
+        assert_eq!(
+            scheduler.take_due(&jobs, start + Duration::hours(1)).len(),
+            1
+        );
+        assert_eq!(
+            scheduler.next_run(&jobs[0], start),
+            start + Duration::hours(2)
+        );
+    }
+}
@@ -0,0 +1,210 @@
+//! A read-only copy of the parts of the state that the bar and scripts show.
+//!
+//! The event loop publishes a new snapshot after every event. Bar components render on the thread
+//! of the bar, so reading the snapshot means that they never wait for the event loop, which might
+//! itself be waiting for the bar or the interpreter.
+use crate::{
+    direction::Direction, event_stream, split_direction::SplitDirection, system::DisplayId,
+    system::NativeWindow, system::WindowId, tile_grid::TileGrid, AppState,
+};
+use interpreter::Dynamic;
+use lazy_static::lazy_static;
+use parking_lot::RwLock;
+use std::{collections::HashMap, sync::Arc};
+
+#[derive(Debug, Clone)]
+pub struct WorkspaceSnapshot {
+    pub id: i32,
+    /// The number the user knows the workspace by
+    pub number: i32,
+    /// The text of the workspace in the bar
+    pub text: String,
+    pub window_count: usize,
+    pub is_fullscreened: bool,
+    pub next_axis: SplitDirection,
+    pub next_direction: Direction,
+    pub focused_window: Option<NativeWindow>,
+    /// The id and title of every window of the workspace
+    pub windows: Vec<(WindowId, String)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DisplaySnapshot {
+    pub id: DisplayId,
+    pub focused_workspace_id: Option<i32>,
+    /// The workspace that the bar of the display highlights. Every display highlights its own
+    /// workspace if the displays don't share workspaces.
+    pub highlighted_workspace_id: Option<i32>,
+    pub bar_color: i32,
+    /// Every workspace of the display, sorted by id
+    pub workspaces: Vec<WorkspaceSnapshot>,
+}
+
+impl DisplaySnapshot {
+    pub fn get_focused_workspace(&self) -> Option<&WorkspaceSnapshot> {
+        self.focused_workspace_id
+            .and_then(|id| self.workspaces.iter().find(|w| w.id == id))
+    }
+
+    /// Returns the workspaces that have windows or are focused
+    pub fn get_active_workspaces(&self) -> Vec<&WorkspaceSnapshot> {
+        self.workspaces
+            .iter()
+            .filter(|w| self.focused_workspace_id == Some(w.id) || w.window_count > 0)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct StateSnapshot {
+    pub workspace_id: i32,
+    pub work_mode: bool,
+    pub mode: Option<String>,
+    pub is_suspended: bool,
+    pub light_theme: bool,
+    pub displays: Vec<DisplaySnapshot>,
+}
+
+impl StateSnapshot {
+    pub fn get_display_by_id(&self, id: DisplayId) -> Option<&DisplaySnapshot> {
+        self.displays.iter().find(|d| d.id == id)
+    }
+
+    fn workspace(state: &AppState, grid: &TileGrid) -> WorkspaceSnapshot {
+        let number = state.workspace_number(grid.id);
+
+        WorkspaceSnapshot {
+            id: grid.id,
+            number,
+            text: state
+                .config
+                .workspace_settings
+                .iter()
+                .find(|s| s.id == number)
+                .map(|s| s.text.clone())
+                .filter(|t| !t.is_empty())
+                .unwrap_or(format!(" {} ", number)),
+            window_count: grid.window_count(),
+            is_fullscreened: grid.is_fullscreened(),
+            next_axis: grid.next_axis,
+            next_direction: grid.next_direction,
+            focused_window: grid.get_focused_window().cloned(),
+            windows: grid
+                .get_window_ids()
+                .into_iter()
+                .filter_map(|id| grid.get_window(id).map(|w| (id, w.title.clone())))
+                .collect(),
+        }
+    }
+
+    pub fn into_dynamic(&self) -> Dynamic {
+        let displays = self
+            .displays
+            .iter()
+            .map(|d| {
+                let workspaces = d
+                    .workspaces
+                    .iter()
+                    .map(|w| {
+                        let mut fields: HashMap<String, Dynamic> = HashMap::new();
+                        fields.insert("id".into(), w.id.into());
+                        fields.insert("number".into(), w.number.into());
+                        fields.insert("text".into(), w.text.clone().into());
+                        fields.insert("windows".into(), (w.window_count as i32).into());
+                        fields.insert("fullscreen".into(), w.is_fullscreened.into());
+                        fields.insert(
+                            "title".into(),
+                            w.focused_window
+                                .as_ref()
+                                .and_then(|w| w.get_title().ok())
+                                .map(Dynamic::from)
+                                .unwrap_or_default(),
+                        );
+                        Dynamic::new_object(fields)
+                    })
+                    .collect::<Vec<_>>();
+
+                let mut fields: HashMap<String, Dynamic> = HashMap::new();
+                fields.insert("id".into(), d.id.0.into());
+                fields.insert(
+                    "workspace".into(),
+                    d.focused_workspace_id
+                        .map(Dynamic::from)
+                        .unwrap_or_default(),
+                );
+                fields.insert("workspaces".into(), Dynamic::new_array(workspaces));
+                Dynamic::new_object(fields)
+            })
+            .collect::<Vec<_>>();
+
+        let mut fields: HashMap<String, Dynamic> = HashMap::new();
+        fields.insert("workspace".into(), self.workspace_id.into());
+        fields.insert("work_mode".into(), self.work_mode.into());
+        fields.insert(
+            "mode".into(),
+            self.mode.clone().map(Dynamic::from).unwrap_or_default(),
+        );
+        fields.insert("suspended".into(), self.is_suspended.into());
+        fields.insert("displays".into(), Dynamic::new_array(displays));
+
+        Dynamic::new_object(fields)
+    }
+
+    /// The mode is owned by the keybinding manager. If it is busy, the mode of the previous
+    /// snapshot gets kept instead of waiting for it.
+    pub fn new(state: &AppState, previous: &StateSnapshot) -> Self {
+        Self {
+            workspace_id: state.workspace_id,
+            work_mode: state.work_mode,
+            mode: state
+                .keybindings_manager
+                .try_get_mode()
+                .unwrap_or_else(|| previous.mode.clone()),
+            is_suspended: state.keybindings_manager.is_suspended(),
+            light_theme: state.config.light_theme,
+            displays: state
+                .displays
+                .iter()
+                .map(|d| {
+                    let mut workspaces = d
+                        .grids
+                        .iter()
+                        .map(|g| Self::workspace(state, g))
+                        .collect::<Vec<_>>();
+                    workspaces.sort_by_key(|w| w.id);
+
+                    DisplaySnapshot {
+                        id: d.id,
+                        focused_workspace_id: d.focused_grid_id,
+                        highlighted_workspace_id: if state.config.display_workspaces {
+                            d.focused_grid_id
+                        } else {
+                            Some(state.workspace_id)
+                        },
+                        bar_color: state.bar_color(d.id),
+                        workspaces,
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+lazy_static! {
+    static ref SNAPSHOT: RwLock<Arc<StateSnapshot>> = RwLock::new(Default::default());
+}
+
+/// Replaces the snapshot with the current state. This has to be called by whoever holds the
+/// lock of the state.
+pub fn publish(state: &AppState) {
+    let previous = get();
+    let snapshot = Arc::new(StateSnapshot::new(state, &previous));
+
+    *SNAPSHOT.write() = snapshot.clone();
+    event_stream::publish(&previous, &snapshot);
+}
+
+/// Returns the latest snapshot without waiting for the state
+pub fn get() -> Arc<StateSnapshot> {
+    SNAPSHOT.read().clone()
+}
@@ -1,5 +1,10 @@
-use crate::system::api;
-use log::info;
+use crate::{
+    config::startup_policy::StartupPolicy,
+    system::api,
+    tile_grid::{snapshots, store::Store},
+};
+use log::{info, warn};
+use std::collections::HashMap;
 
 #[allow(unreachable_code, unused_variables)]
 pub fn set_launch_on_startup(enabled: bool) {
@@ -14,3 +19,27 @@ pub fn set_launch_on_startup(enabled: bool) {
         info!("Disabled launch on startup in registry");
     }
 }
+
+/// Returns the layouts the workspaces start with according to the policy, keyed by the id of the
+/// workspace. Falls back to the last session if the named snapshot doesn't exist.
+pub fn initial_layouts(policy: &StartupPolicy) -> HashMap<i32, String> {
+    let session = || {
+        Store::load()
+            .into_iter()
+            .enumerate()
+            .map(|(i, layout)| (i as i32 + 1, layout))
+            .collect()
+    };
+
+    match policy {
+        StartupPolicy::Fresh => HashMap::new(),
+        StartupPolicy::RestoreSession => session(),
+        StartupPolicy::RestoreNamed(name) => snapshots::get(name).unwrap_or_else(|| {
+            warn!(
+                "The snapshot '{}' doesn't exist, restoring the last session instead",
+                name
+            );
+            session()
+        }),
+    }
+}
@@ -0,0 +1,93 @@
+//! Gives the threads other than the event loop access to the state without locking it.
+//!
+//! The event loop owns the state. Other threads send it the changes they want to make, which run
+//! in between the other events, and read the snapshot that the event loop publishes after every
+//! event (see `snapshot`). A thread that only uses a handle never waits for a lock that the event
+//! loop, the bar or a script holds, so it can't take part in a deadlock.
+//!
+//! Handles are used by the background threads: the ipc server, komorebi, the scheduler, the hot
+//! reloading, the game mode and the auto hiding of the bar. The event loop, the script callbacks it
+//! runs and the mouse handlers of the bar still lock the state, because they act on it directly.
+//! Bar components only read the snapshot while they render.
+use crate::{
+    event::{Event, EventSender},
+    snapshot::{self, StateSnapshot},
+    AppState,
+};
+use crossbeam_channel::bounded;
+use std::{fmt::Debug, sync::Arc};
+
+/// A change that the event loop applies to the state
+pub struct Update(Box<dyn FnOnce(&mut AppState) + Send>);
+
+impl Update {
+    pub fn run(self, state: &mut AppState) {
+        (self.0)(state)
+    }
+}
+
+impl Debug for Update {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Update { .. }")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StateHandle {
+    sender: EventSender,
+}
+
+impl StateHandle {
+    pub fn new(sender: EventSender) -> Self {
+        Self { sender }
+    }
+
+    pub fn send(&self, event: Event) {
+        self.sender.send(event).expect("Failed to send event");
+    }
+
+    /// Lets the event loop apply the change once it handled the events that came before it.
+    pub fn update(&self, f: impl FnOnce(&mut AppState) + Send + 'static) {
+        self.send(Event::Update(Update(Box::new(f))));
+    }
+
+    /// Lets the event loop compute the value from the state and waits for it. This must not be
+    /// called on the event loop itself, because it would wait for itself.
+    pub fn query<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&AppState) -> T + Send + 'static,
+    ) -> Option<T> {
+        let (sender, receiver) = bounded(1);
+
+        self.update(move |state| {
+            // the caller may have given up waiting
+            let _ = sender.send(f(state));
+        });
+
+        receiver.recv().ok()
+    }
+
+    /// Returns the state as of the last event
+    pub fn snapshot(&self) -> Arc<StateSnapshot> {
+        snapshot::get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::EventChannel;
+
+    #[test]
+    fn changes_get_sent_to_the_event_loop() {
+        let channel = EventChannel::default();
+        let handle = StateHandle::new(channel.sender.clone());
+
+        handle.update(|state| state.workspace_id = 3);
+
+        match channel.receiver.try_recv() {
+            Ok(Event::Update(_)) => {}
+            ev => panic!("Expected an update, got {:?}", ev),
+        }
+    }
+}
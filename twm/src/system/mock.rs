@@ -0,0 +1,300 @@
+//! An in-memory desktop for tests and for replaying dumps (see `debug_dump`). Nothing in here talks
+//! to the operating system, so the logic that only depends on the `System` trait runs headless.
+//!
+//! Like the hooks of Win32 the mock reports the windows that get shown, hidden, focused or
+//! destroyed to the event channel once `start_hooks` got called, which is how a test drives the
+//! event handling.
+use super::{DisplayId, NativeWindow, Rectangle, System, SystemError, SystemResult, WindowId};
+use crate::{
+    display::Display,
+    event::{Event, EventChannel, EventSender},
+    win_event_handler::{win_event::WinEvent, win_event_type::WinEventType},
+};
+use parking_lot::Mutex;
+
+#[derive(Debug, Default, Clone)]
+pub struct MockWindow {
+    pub id: WindowId,
+    pub title: String,
+    /// The file name of the executable
+    pub process_name: String,
+    pub rect: Rectangle,
+    pub visible: bool,
+    /// Whether the window has a title bar or a frame
+    pub framed: bool,
+}
+
+impl MockWindow {
+    #[cfg(test)]
+    pub fn new(id: i32, process_name: &str, rect: Rectangle) -> Self {
+        Self {
+            id: id.into(),
+            title: process_name.into(),
+            process_name: process_name.into(),
+            rect,
+            visible: true,
+            framed: true,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MockSystem {
+    pub displays: Vec<Display>,
+    windows: Mutex<Vec<MockWindow>>,
+    foreground: Mutex<Option<WindowId>>,
+    fullscreen_app_running: Mutex<bool>,
+    hooks: Mutex<Option<EventSender>>,
+}
+
+impl MockSystem {
+    /// Adds a display that covers the given area. Displays get numbered starting at 1.
+    pub fn with_display(mut self, left: i32, top: i32, width: i32, height: i32) -> Self {
+        let mut display = Display::default();
+
+        display.id = DisplayId(self.displays.len() as i32 + 1);
+        display.dpi = 96;
+        display.rect = Rectangle {
+            left,
+            top,
+            right: left + width,
+            bottom: top + height,
+        };
+
+        self.displays.push(display);
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_window(self, window: MockWindow) -> Self {
+        self.windows.lock().push(window);
+        self
+    }
+
+    pub fn window(&self, id: WindowId) -> Option<MockWindow> {
+        self.windows.lock().iter().find(|w| w.id == id).cloned()
+    }
+
+    /// Changes the window, which doesn't report anything to the hooks
+    pub fn update_window(&self, id: WindowId, f: impl FnOnce(&mut MockWindow)) -> SystemResult {
+        let mut windows = self.windows.lock();
+        let window = windows
+            .iter_mut()
+            .find(|w| w.id == id)
+            .ok_or(SystemError::WindowNotFound(id))?;
+
+        f(window);
+
+        Ok(())
+    }
+
+    #[cfg(test)]
+    /// Lets the application of the window run in exclusive fullscreen or stop doing so
+    pub fn set_fullscreen_app_running(&self, running: bool) {
+        *self.fullscreen_app_running.lock() = running;
+    }
+
+    #[cfg(test)]
+    /// Adds a window that got opened and reports it like Win32 would
+    pub fn open_window(&self, window: MockWindow) {
+        let id = window.id;
+
+        self.windows.lock().push(window);
+        self.emit(WinEventType::Show(false), id);
+    }
+
+    #[cfg(test)]
+    pub fn destroy_window(&self, id: WindowId) {
+        self.windows.lock().retain(|w| w.id != id);
+
+        let mut foreground = self.foreground.lock();
+        if *foreground == Some(id) {
+            *foreground = None;
+        }
+        drop(foreground);
+
+        self.emit(WinEventType::Destroy, id);
+    }
+
+    /// Sends the event to the channel of the hooks, if they are started
+    pub fn emit(&self, typ: WinEventType, id: WindowId) {
+        if let Some(sender) = self.hooks.lock().as_ref() {
+            sender
+                .send(Event::WinEvent(WinEvent {
+                    typ,
+                    window: NativeWindow::from(id),
+                }))
+                .expect("Failed to send WinEvent");
+        }
+    }
+
+    fn get_window(&self, id: WindowId) -> SystemResult<MockWindow> {
+        self.window(id).ok_or(SystemError::WindowNotFound(id))
+    }
+}
+
+impl System for MockSystem {
+    fn get_displays(&self) -> Vec<Display> {
+        self.displays.clone()
+    }
+
+    fn get_foreground_window(&self) -> SystemResult<WindowId> {
+        self.foreground
+            .lock()
+            .ok_or(SystemError::WindowNotFound(WindowId::default()))
+    }
+
+    fn get_window_rect(&self, id: WindowId) -> SystemResult<Rectangle> {
+        self.get_window(id).map(|w| w.rect)
+    }
+
+    fn get_window_display(&self, id: WindowId) -> SystemResult<DisplayId> {
+        let rect = self.get_window(id)?.rect;
+        let (x, y) = (rect.left + rect.width() / 2, rect.top + rect.height() / 2);
+
+        self.displays
+            .iter()
+            .find(|d| d.rect.contains(x, y))
+            .map(|d| d.id)
+            .ok_or(SystemError::WindowNotFound(id))
+    }
+
+    fn get_window_title(&self, id: WindowId) -> SystemResult<String> {
+        self.get_window(id).map(|w| w.title)
+    }
+
+    fn get_process_name(&self, id: WindowId) -> String {
+        self.window(id).map(|w| w.process_name).unwrap_or_default()
+    }
+
+    fn get_process_path(&self, id: WindowId) -> String {
+        self.window(id)
+            .map(|w| format!(r"C:\Program Files\{}", w.process_name))
+            .unwrap_or_default()
+    }
+
+    fn is_borderless_fullscreen(&self, id: WindowId) -> bool {
+        match self.window(id) {
+            Some(window) if !window.framed => self
+                .displays
+                .iter()
+                .any(|d| window.rect.covers(&d.rect)),
+            _ => false,
+        }
+    }
+
+    fn is_fullscreen_app_running(&self) -> bool {
+        *self.fullscreen_app_running.lock()
+    }
+
+    fn show_window(&self, id: WindowId) -> SystemResult {
+        self.update_window(id, |w| w.visible = true)?;
+        self.emit(WinEventType::Show(false), id);
+
+        Ok(())
+    }
+
+    fn hide_window(&self, id: WindowId) -> SystemResult {
+        self.update_window(id, |w| w.visible = false)?;
+        self.emit(WinEventType::Hide, id);
+
+        Ok(())
+    }
+
+    fn focus_window(&self, id: WindowId) -> SystemResult {
+        self.get_window(id)?;
+        *self.foreground.lock() = Some(id);
+        self.emit(WinEventType::FocusChange, id);
+
+        Ok(())
+    }
+
+    fn set_window_rect(&self, id: WindowId, rect: Rectangle) -> SystemResult {
+        self.update_window(id, |w| w.rect = rect)
+    }
+
+    fn start_hooks(&self, channel: &EventChannel) {
+        *self.hooks.lock() = Some(channel.sender.clone());
+    }
+
+    fn stop_hooks(&self) {
+        *self.hooks.lock() = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window_events(channel: &EventChannel) -> Vec<(WinEventType, WindowId)> {
+        channel
+            .receiver
+            .try_iter()
+            .filter_map(|ev| match ev {
+                Event::WinEvent(ev) => Some((ev.typ, ev.window.id)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn hooks_report_what_happens_to_the_windows() {
+        let rect = Rectangle {
+            left: 0,
+            top: 0,
+            right: 800,
+            bottom: 600,
+        };
+        let system = MockSystem::default()
+            .with_display(0, 0, 1920, 1080)
+            .with_window(MockWindow::new(1, "code.exe", rect));
+        let channel = EventChannel::default();
+
+        // nothing gets reported before the hooks are started
+        system.hide_window(1.into()).unwrap();
+        assert!(window_events(&channel).is_empty());
+
+        system.start_hooks(&channel);
+        system.show_window(1.into()).unwrap();
+        system.focus_window(1.into()).unwrap();
+        system.open_window(MockWindow::new(2, "firefox.exe", rect));
+        system.destroy_window(1.into());
+
+        assert_eq!(
+            window_events(&channel),
+            vec![
+                (WinEventType::Show(false), 1.into()),
+                (WinEventType::FocusChange, 1.into()),
+                (WinEventType::Show(false), 2.into()),
+                (WinEventType::Destroy, 1.into()),
+            ]
+        );
+        assert!(system.get_foreground_window().is_err());
+        assert!(system.hide_window(1.into()).is_err());
+
+        system.stop_hooks();
+        system.hide_window(2.into()).unwrap();
+        assert!(window_events(&channel).is_empty());
+        assert_eq!(system.window(2.into()).map(|w| w.visible), Some(false));
+    }
+
+    #[test]
+    fn borderless_fullscreen_windows() {
+        let display = Rectangle {
+            left: 0,
+            top: 0,
+            right: 1920,
+            bottom: 1080,
+        };
+        let system = MockSystem::default()
+            .with_display(0, 0, 1920, 1080)
+            .with_window(MockWindow {
+                framed: false,
+                ..MockWindow::new(1, "game.exe", display)
+            })
+            .with_window(MockWindow::new(2, "firefox.exe", display));
+
+        assert!(system.is_borderless_fullscreen(1.into()));
+        assert!(!system.is_borderless_fullscreen(2.into()));
+    }
+}
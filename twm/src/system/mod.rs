@@ -1,15 +1,21 @@
+use crate::{display::Display, event::EventChannel};
+use std::fmt::Debug;
 use thiserror::Error;
 
+pub mod mock;
 pub mod win;
 
 pub use win::api;
-pub use win::win_event_listener::WinEventListener;
+pub use win::audio::{self, MediaKey};
+pub use win::keyboard_hook::{self, KeyboardHook};
+pub use win::mouse_listener::MouseListener;
+pub use win::WinSystem as NativeSystem;
 pub use win::Window as NativeWindow;
 pub use win::BIN_NAME;
 
 pub type SpecificError = win::WinError;
 
-#[derive(Debug, Default, PartialEq, Clone, Copy)]
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy)]
 pub struct WindowId(i32);
 
 impl std::fmt::Display for WindowId {
@@ -54,6 +60,16 @@ pub struct Rectangle {
 }
 
 impl Rectangle {
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.left && x < self.right && y >= self.top && y < self.bottom
+    }
+    /// Whether the other rectangle lies completely inside of this one
+    pub fn covers(&self, other: &Rectangle) -> bool {
+        self.left <= other.left
+            && self.top <= other.top
+            && self.right >= other.right
+            && self.bottom >= other.bottom
+    }
     pub fn width(&self) -> i32 {
         self.right - self.left
     }
@@ -82,12 +98,32 @@ pub enum SystemError {
     MinimizeWindow(SpecificError),
     #[error("Failed to maximize window")]
     MaximizeWindow(SpecificError),
+    #[error("Failed to move window")]
+    MoveWindow(SpecificError),
+    #[error("The window {0} doesn't exist")]
+    WindowNotFound(WindowId),
+    #[error("Only floating windows can be moved or resized")]
+    WindowIsTiled,
+    #[error("{0}")]
+    InvalidAction(String),
+    #[error("The window {0} doesn't belong to an application and can't be hidden")]
+    NotAnAppWindow(WindowId),
+    #[error("Failed to kill the process of the window")]
+    KillProcess(SpecificError),
     #[error("Failed to draw tile")]
     DrawTile(SpecificError),
+    #[error("Failed to change the opacity of window")]
+    SetOpacity(SpecificError),
     #[error("Failed to get foreground window")]
     GetForegroundWindow(SpecificError),
     #[error("Failed to launch a program")]
     LaunchProgram(String),
+    #[error("Failed to access the clipboard: {0}")]
+    Clipboard(String),
+    #[error("Failed to access the audio device: {0}")]
+    Audio(String),
+    #[error("Failed to change the keyboard layout: {0}")]
+    KeyboardLayout(String),
     #[error("Failed to register keybinding")]
     RegisterKeybinding(String),
     #[error("Failed to unregister keybinding")]
@@ -99,3 +135,32 @@ pub enum SystemError {
 }
 
 pub type SystemResult<T = ()> = Result<T, SystemError>;
+
+/// The desktop as nog sees it: the displays, the windows on them and the hooks that report what
+/// happens to the windows. `NativeSystem` talks to Win32, while `mock::MockSystem` keeps the
+/// desktop in memory, so that the logic built on top of it can be tested without a desktop.
+pub trait System: Debug + Send + Sync {
+    /// Returns the connected displays together with their taskbars, but without any workspaces
+    fn get_displays(&self) -> Vec<Display>;
+    /// Returns the window that currently receives the input
+    fn get_foreground_window(&self) -> SystemResult<WindowId>;
+    fn get_window_rect(&self, id: WindowId) -> SystemResult<Rectangle>;
+    /// Returns the display that shows most of the window
+    fn get_window_display(&self, id: WindowId) -> SystemResult<DisplayId>;
+    fn get_window_title(&self, id: WindowId) -> SystemResult<String>;
+    /// Returns the file name of the executable that owns the window
+    fn get_process_name(&self, id: WindowId) -> String;
+    /// Returns the path of the executable that owns the window
+    fn get_process_path(&self, id: WindowId) -> String;
+    /// Whether the window has no frame and covers its whole display
+    fn is_borderless_fullscreen(&self, id: WindowId) -> bool;
+    /// Whether some application runs in exclusive fullscreen
+    fn is_fullscreen_app_running(&self) -> bool;
+    fn show_window(&self, id: WindowId) -> SystemResult;
+    fn hide_window(&self, id: WindowId) -> SystemResult;
+    fn focus_window(&self, id: WindowId) -> SystemResult;
+    fn set_window_rect(&self, id: WindowId, rect: Rectangle) -> SystemResult;
+    /// Starts sending the events of the windows to the channel
+    fn start_hooks(&self, channel: &EventChannel);
+    fn stop_hooks(&self);
+}
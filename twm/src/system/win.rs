@@ -1,6 +1,12 @@
-use super::{DisplayId, Rectangle, SystemError, SystemResult, WindowId};
+use super::{DisplayId, Rectangle, System, SystemError, SystemResult, WindowId};
 use crate::{
-    display::Display, util, window::gwl_ex_style::GwlExStyle, window::gwl_style::GwlStyle, Rule,
+    display::Display,
+    event::EventChannel,
+    journal::{self, Attribute},
+    util,
+    window::gwl_ex_style::GwlExStyle,
+    window::gwl_style::GwlStyle,
+    Rule,
 };
 use log::{debug, error};
 use thiserror::Error;
@@ -10,11 +16,97 @@ use winapi::{
 };
 
 pub mod api;
+pub mod audio;
+pub mod keyboard_hook;
 pub mod menu;
+pub mod mouse_listener;
 pub mod win_event_listener;
 
 pub const BIN_NAME: &'static str = "nog.exe";
 
+#[derive(Debug, Default, Clone)]
+pub struct WinSystem {
+    window_event_listener: win_event_listener::WinEventListener,
+}
+
+impl System for WinSystem {
+    fn get_displays(&self) -> Vec<Display> {
+        let mut displays = api::get_displays();
+        let taskbars = api::get_taskbars();
+
+        for d in displays.iter_mut() {
+            for tb in &taskbars {
+                let display = tb.window.get_display().unwrap();
+                if display.id == d.id {
+                    d.taskbar = Some(tb.clone());
+                }
+            }
+        }
+
+        displays
+    }
+
+    fn get_foreground_window(&self) -> SystemResult<WindowId> {
+        Window::get_foreground_window().map(|w| w.id)
+    }
+
+    fn get_window_rect(&self, id: WindowId) -> SystemResult<Rectangle> {
+        Ok(Window::from(id).get_rect()?)
+    }
+
+    fn get_window_display(&self, id: WindowId) -> SystemResult<DisplayId> {
+        Ok(Window::from(id).get_display()?.id)
+    }
+
+    fn get_window_title(&self, id: WindowId) -> SystemResult<String> {
+        Ok(Window::from(id).get_title()?)
+    }
+
+    fn get_process_name(&self, id: WindowId) -> String {
+        Window::from(id).get_process_name()
+    }
+
+    fn get_process_path(&self, id: WindowId) -> String {
+        Window::from(id).get_process_path()
+    }
+
+    fn is_borderless_fullscreen(&self, id: WindowId) -> bool {
+        Window::from(id).is_borderless_fullscreen()
+    }
+
+    fn is_fullscreen_app_running(&self) -> bool {
+        api::is_fullscreen_app_running()
+    }
+
+    fn show_window(&self, id: WindowId) -> SystemResult {
+        Window::from(id).show();
+        Ok(())
+    }
+
+    fn hide_window(&self, id: WindowId) -> SystemResult {
+        Window::from(id).hide();
+        Ok(())
+    }
+
+    fn focus_window(&self, id: WindowId) -> SystemResult {
+        Window::from(id).focus()
+    }
+
+    fn set_window_rect(&self, id: WindowId, rect: Rectangle) -> SystemResult {
+        Window::from(id)
+            .set_window_pos(rect, None, None)
+            .map_err(SystemError::MoveWindow)
+    }
+
+    fn start_hooks(&self, channel: &EventChannel) {
+        self.window_event_listener.start(channel);
+    }
+
+    fn stop_hooks(&self) {
+        self.window_event_listener.stop();
+    }
+}
+
 impl From<HWND> for WindowId {
     fn from(val: HWND) -> Self {
         Self(val as i32)
@@ -119,6 +211,10 @@ pub struct Window {
     pub exstyle: GwlExStyle,
     pub original_style: GwlStyle,
     pub original_rect: Rectangle,
+    /// Whether nog removed the title bar of the window
+    pub title_bar_removed: bool,
+    /// Whether nog added a thin border after removing the title bar
+    pub has_border: bool,
 }
 
 impl PartialEq<i32> for Window {
@@ -167,10 +263,33 @@ impl Window {
         if use_border {
             self.style.insert(GwlStyle::BORDER);
         }
+        self.title_bar_removed = true;
+        self.has_border = use_border;
+        self.update_style()
+            .map(|_| {})
+            .map_err(SystemError::Unknown)
+    }
+    /// Gives the window back the title bar, frame and border it had before nog removed them.
+    pub fn restore_title_bar(&mut self) -> SystemResult {
+        let decorations = GwlStyle::CAPTION | GwlStyle::THICKFRAME | GwlStyle::BORDER;
+
+        self.style.remove(decorations);
+        self.style.insert(self.original_style & decorations);
+        self.title_bar_removed = false;
+        self.has_border = false;
         self.update_style()
             .map(|_| {})
             .map_err(SystemError::Unknown)
     }
+    /// Removes the frame of the window and makes it cover the area on top of every other window.
+    pub fn make_borderless(&mut self, area: Rectangle) -> SystemResult {
+        self.style
+            .remove(GwlStyle::CAPTION | GwlStyle::THICKFRAME | GwlStyle::BORDER);
+        self.update_style().map_err(SystemError::Unknown)?;
+        self.remember_ex_style();
+        self.set_window_pos(area, Some(HWND_TOPMOST), Some(SWP_FRAMECHANGED))
+            .map_err(SystemError::Unknown)
+    }
     pub fn get_display(&self) -> WinResult<Display> {
         unsafe {
             nullable_to_result(MonitorFromWindow(self.id.into(), MONITOR_DEFAULTTONULL).into())
@@ -194,6 +313,35 @@ impl Window {
             .map(|_| util::bytes_to_string(&buffer))
         }
     }
+    /// Whether the window has a frame that can be dragged to resize it
+    pub fn is_resizable(&self) -> bool {
+        self.get_style()
+            .map(|style| style.contains(GwlStyle::THICKFRAME))
+            .unwrap_or(true)
+    }
+    /// Whether the window has no frame and covers its whole display, which is how most games
+    /// implement borderless fullscreen. The desktop looks the same, so it gets excluded.
+    pub fn is_borderless_fullscreen(&self) -> bool {
+        let frameless = self
+            .get_style()
+            .map(|style| !style.intersects(GwlStyle::CAPTION | GwlStyle::THICKFRAME))
+            .unwrap_or(false);
+        let is_desktop = self
+            .get_class_name()
+            .map(|name| name == "Progman" || name == "WorkerW")
+            .unwrap_or(true);
+
+        match (self.get_rect(), self.get_display()) {
+            (Ok(rect), Ok(display)) if frameless && !is_desktop => rect.covers(&display.rect),
+            _ => false,
+        }
+    }
+    /// Whether the window uses the class of the standard dialog box
+    pub fn is_dialog(&self) -> bool {
+        self.get_class_name()
+            .map(|name| name == "#32770")
+            .unwrap_or(false)
+    }
     pub fn get_parent_window(&self) -> WinResult<WindowId> {
         unsafe { nullable_to_result(GetParent(self.id.into()).into()) }
     }
@@ -227,6 +375,51 @@ impl Window {
             nullable_to_result(GetWindowRect(self.id.into(), &mut temp)).map(|_| temp.into())
         }
     }
+    /// Returns the opacity of a translucent window, where an opacity of 255 is fully opaque.
+    pub fn get_opacity(&self) -> WinResult<u8> {
+        let mut opacity = 0;
+
+        unsafe {
+            bool_to_result(GetLayeredWindowAttributes(
+                self.id.into(),
+                std::ptr::null_mut(),
+                &mut opacity,
+                std::ptr::null_mut(),
+            ))
+            .map(|_| opacity)
+        }
+    }
+    /// Makes the window translucent, where an opacity of 255 is fully opaque.
+    pub fn set_opacity(&self, opacity: u8) -> WinResult {
+        self.remember_ex_style();
+
+        if let Ok(ex_style) = self.get_ex_style() {
+            if ex_style.contains(GwlExStyle::LAYERED) {
+                if let Ok(opacity) = self.get_opacity() {
+                    journal::remember(self.id, Attribute::Opacity(opacity));
+                }
+            }
+        }
+
+        unsafe {
+            let ex_style = GetWindowLongA(self.id.into(), GWL_EXSTYLE);
+
+            if ex_style & WS_EX_LAYERED as i32 == 0 {
+                SetWindowLongA(
+                    self.id.into(),
+                    GWL_EXSTYLE,
+                    ex_style | WS_EX_LAYERED as i32,
+                );
+            }
+
+            bool_to_result(SetLayeredWindowAttributes(
+                self.id.into(),
+                0,
+                opacity,
+                LWA_ALPHA,
+            ))
+        }
+    }
     pub fn is_window(&self) -> bool {
         unsafe { IsWindow(self.id.into()) != 0 }
     }
@@ -234,12 +427,43 @@ impl Window {
         self.style = self.original_style;
     }
     pub fn update_style(&self) -> WinResult<i32> {
+        if let Ok(style) = self.get_style() {
+            journal::remember(self.id, Attribute::Style(style.bits()));
+        }
+
         unsafe {
             nullable_to_result::<i32>(SetWindowLongA(self.id.into(), GWL_STYLE, self.style.bits()))
         }
     }
+    pub fn set_ex_style(&self, ex_style: GwlExStyle) -> WinResult<i32> {
+        self.remember_ex_style();
+
+        unsafe {
+            nullable_to_result::<i32>(SetWindowLongA(self.id.into(), GWL_EXSTYLE, ex_style.bits()))
+        }
+    }
+    fn remember_ex_style(&self) {
+        if let Ok(ex_style) = self.get_ex_style() {
+            journal::remember(self.id, Attribute::ExStyle(ex_style.bits()));
+            journal::remember(
+                self.id,
+                Attribute::Topmost(ex_style.contains(GwlExStyle::TOPMOST)),
+            );
+        }
+    }
+    pub fn set_topmost(&self, topmost: bool) -> WinResult {
+        if topmost {
+            self.to_foreground(true)
+        } else {
+            self.remove_topmost()
+        }
+    }
     /// This could error if the window is already in the foreground
     pub fn to_foreground(&self, topmost: bool) -> WinResult {
+        if topmost {
+            self.remember_ex_style();
+        }
+
         self.set_window_pos(
             Rectangle::default(),
             Some(if topmost { HWND_TOPMOST } else { HWND_TOP }),
@@ -247,6 +471,8 @@ impl Window {
         )
     }
     pub fn remove_topmost(&self) -> WinResult {
+        self.remember_ex_style();
+
         self.set_window_pos(
             Rectangle::default(),
             Some(HWND_NOTOPMOST),
@@ -274,6 +500,15 @@ impl Window {
     fn reset_pos(&self) -> WinResult {
         self.set_window_pos(self.original_rect, None, None)
     }
+    pub fn get_process_id(&self) -> u32 {
+        let mut process_id = 0;
+
+        unsafe {
+            GetWindowThreadProcessId(self.id.into(), &mut process_id);
+        }
+
+        process_id
+    }
     pub fn get_process_name(&self) -> String {
         self.get_process_path()
             .split('\\')
@@ -320,9 +555,15 @@ impl Window {
             exstyle: GwlExStyle::default(),
             original_style: GwlStyle::default(),
             original_rect: Rectangle::default(),
+            title_bar_removed: false,
+            has_border: false,
         }
     }
     pub fn cleanup(&mut self) -> SystemResult {
+        if let Some(entry) = journal::take(self.id) {
+            return entry.restore(self);
+        }
+
         self.reset_style();
         self.update_style().map_err(SystemError::CleanupWindow)?;
         self.reset_pos().map_err(SystemError::CleanupWindow)?;
@@ -337,11 +578,13 @@ impl Window {
         unsafe {
             ShowWindow(self.id.into(), SW_SHOW);
         }
+        journal::set_hidden(self.id, false);
     }
     pub fn hide(&self) {
         unsafe {
             ShowWindow(self.id.into(), SW_HIDE);
         }
+        journal::set_hidden(self.id, true);
     }
     pub fn close(&self) -> SystemResult {
         unsafe {
@@ -375,28 +618,30 @@ impl Window {
         self.exstyle = self.get_ex_style().map_err(SystemError::Init)?;
         self.original_rect = self.get_rect().map_err(SystemError::Init)?;
 
-        if remove_title_bar {
-            self.remove_title_bar(use_border)?;
+        journal::record(self);
+
+        let rule = self.rule.clone().unwrap_or_default();
+        if rule.remove_title_bar.unwrap_or(remove_title_bar) {
+            self.remove_title_bar(rule.use_border.unwrap_or(use_border))?;
         }
 
         Ok(())
     }
-    pub fn set_matching_rule(&mut self, rules: Vec<&Rule>) {
-        for rule in rules {
-            // checks for path
-            let process_name = if rule.pattern.to_string().contains('\\') {
-                self.get_process_path()
-            } else {
-                self.get_process_name()
-            };
+    /// Whether the pattern of the rule matches the title or the process of the window. Patterns
+    /// that contain a backslash get matched against the path of the process.
+    pub fn matches_rule(&self, rule: &Rule) -> bool {
+        let process_name = if rule.pattern.to_string().contains('\\') {
+            self.get_process_path()
+        } else {
+            self.get_process_name()
+        };
 
-            let window_name = self.title.clone();
-
-            if rule.pattern.is_match(&process_name) || rule.pattern.is_match(&window_name) {
-                debug!("Rule({:?}) matched!", rule.pattern);
-                self.rule = Some(rule.clone());
-                break;
-            }
+        rule.pattern.is_match(&process_name) || rule.pattern.is_match(&self.title)
+    }
+    pub fn set_matching_rule(&mut self, rules: Vec<&Rule>) {
+        if let Some(rule) = rules.into_iter().find(|rule| self.matches_rule(rule)) {
+            debug!("Rule({:?}) matched!", rule.pattern);
+            self.rule = Some(rule.clone());
         }
     }
     fn restore(&self) -> WinResult {
@@ -412,6 +657,51 @@ impl Window {
                 .map_err(SystemError::MinimizeWindow)
         }
     }
+    pub fn set_position(&self, x: i32, y: i32) -> SystemResult {
+        unsafe {
+            bool_to_result(SetWindowPos(
+                self.id.into(),
+                std::ptr::null_mut(),
+                x,
+                y,
+                0,
+                0,
+                SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE,
+            ))
+            .map_err(SystemError::MoveWindow)
+        }
+    }
+    pub fn set_size(&self, width: i32, height: i32) -> SystemResult {
+        unsafe {
+            bool_to_result(SetWindowPos(
+                self.id.into(),
+                std::ptr::null_mut(),
+                0,
+                0,
+                width,
+                height,
+                SWP_NOMOVE | SWP_NOZORDER | SWP_NOACTIVATE,
+            ))
+            .map_err(SystemError::MoveWindow)
+        }
+    }
+    /// Terminates the process of the window, which unlike closing the window can't be refused by
+    /// the application.
+    pub fn kill(&self) -> SystemResult {
+        unsafe {
+            let process =
+                processthreadsapi::OpenProcess(PROCESS_TERMINATE, 0, self.get_process_id());
+
+            if process.is_null() {
+                return Err(SystemError::KillProcess(WinError::Null));
+            }
+
+            let result = bool_to_result(processthreadsapi::TerminateProcess(process, 1));
+            handleapi::CloseHandle(process);
+
+            result.map_err(SystemError::KillProcess)
+        }
+    }
     pub fn maximize(&self) -> SystemResult {
         unsafe {
             lresult_to_result(SendMessageA(self.id.into(), WM_SYSCOMMAND, SC_MAXIMIZE, 0))
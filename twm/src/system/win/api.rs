@@ -14,10 +14,18 @@ use crate::{
 use log::{debug, error};
 use regex::Regex;
 use winapi::{
-    shared::{minwindef::*, windef::*},
+    ctypes::c_int,
+    um::shellapi::{
+        SHAppBarMessage, ABM_GETSTATE, ABM_SETSTATE, ABS_ALWAYSONTOP, ABS_AUTOHIDE, APPBARDATA,
+    },
+    um::wincon::{AttachConsole, ATTACH_PARENT_PROCESS},
+    um::wingdi::{EnumFontFamiliesExA, DEFAULT_CHARSET, LF_FACESIZE, LOGFONTA, TEXTMETRICA},
+};
+use winapi::{
+    shared::{lmcons::UNLEN, minwindef::*, windef::*},
     um::{
-        errhandlingapi::*, processthreadsapi::*, shellscalingapi::*, winbase::*, winnt::*,
-        winreg::*, winuser::*,
+        errhandlingapi::*, processthreadsapi::*, shellscalingapi::*, sysinfoapi::*, winbase::*,
+        wingdi::DISPLAY_DEVICEW, winnls::*, winnt::*, winreg::*, winuser::*,
     },
 };
 
@@ -43,6 +51,15 @@ unsafe extern "system" fn monitor_cb(
     1
 }
 
+/// Attaches nog to the console of the process that started it, so that whatever it prints shows up
+/// in the terminal. Release builds don't get a console of their own, because they use the windows
+/// subsystem. Nothing happens if nog wasn't started from a terminal.
+pub fn attach_console() {
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
 pub fn get_last_error() -> String {
     let mut buffer = [0 as i8; 512];
     unsafe {
@@ -114,6 +131,286 @@ pub fn get_display_rect(id: DisplayId) -> Rectangle {
     monitor_info.rcMonitor.into()
 }
 
+/// Returns the position of the cursor in screen coordinates.
+pub fn get_cursor_pos() -> (i32, i32) {
+    let mut point = POINT::default();
+
+    unsafe {
+        GetCursorPos(&mut point);
+    }
+
+    (point.x, point.y)
+}
+
+/// Whether an application is running in fullscreen, which is usually a game.
+pub fn is_fullscreen_app_running() -> bool {
+    use winapi::um::shellapi::{
+        SHQueryUserNotificationState, QUERY_USER_NOTIFICATION_STATE, QUNS_BUSY,
+        QUNS_RUNNING_D3D_FULL_SCREEN,
+    };
+
+    let mut notification_state: QUERY_USER_NOTIFICATION_STATE = 0;
+
+    unsafe {
+        if SHQueryUserNotificationState(&mut notification_state) != 0 {
+            return false;
+        }
+    }
+
+    notification_state == QUNS_BUSY || notification_state == QUNS_RUNNING_D3D_FULL_SCREEN
+}
+
+/// Returns how many milliseconds passed since the last keyboard or mouse input.
+pub fn get_idle_time() -> u32 {
+    let mut info = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        dwTime: 0,
+    };
+
+    unsafe {
+        if GetLastInputInfo(&mut info) == 0 {
+            return 0;
+        }
+
+        // the tick count wraps around after ~50 days
+        GetTickCount().wrapping_sub(info.dwTime)
+    }
+}
+
+/// Keeps the clipboard open until it gets dropped.
+struct ClipboardGuard;
+
+impl ClipboardGuard {
+    fn open() -> SystemResult<Self> {
+        // another application might have the clipboard open at the moment
+        for _ in 0..10 {
+            if unsafe { OpenClipboard(ptr::null_mut()) } != 0 {
+                return Ok(ClipboardGuard);
+            }
+
+            sleep!(10);
+        }
+
+        Err(SystemError::Clipboard(get_last_error()))
+    }
+}
+
+impl Drop for ClipboardGuard {
+    fn drop(&mut self) {
+        unsafe {
+            CloseClipboard();
+        }
+    }
+}
+
+/// Returns the text of the clipboard, which is empty if the clipboard doesn't contain any text.
+pub fn get_clipboard_text() -> SystemResult<String> {
+    let _clipboard = ClipboardGuard::open()?;
+
+    unsafe {
+        let handle = GetClipboardData(CF_UNICODETEXT);
+
+        if handle.is_null() {
+            return Ok(String::new());
+        }
+
+        let data = GlobalLock(handle) as *const u16;
+
+        if data.is_null() {
+            return Err(SystemError::Clipboard(get_last_error()));
+        }
+
+        let mut len = 0;
+        while *data.add(len) != 0 {
+            len += 1;
+        }
+
+        let text = String::from_utf16_lossy(std::slice::from_raw_parts(data, len));
+
+        GlobalUnlock(handle);
+
+        Ok(text)
+    }
+}
+
+pub fn set_clipboard_text(text: &str) -> SystemResult {
+    let text = util::to_widestring(text);
+    let _clipboard = ClipboardGuard::open()?;
+
+    unsafe {
+        EmptyClipboard();
+
+        let handle = GlobalAlloc(GMEM_MOVEABLE, text.len() * 2);
+
+        if handle.is_null() {
+            return Err(SystemError::Clipboard(get_last_error()));
+        }
+
+        let data = GlobalLock(handle) as *mut u16;
+        ptr::copy_nonoverlapping(text.as_ptr(), data, text.len());
+        GlobalUnlock(handle);
+
+        // the clipboard owns the memory once it accepted it
+        if SetClipboardData(CF_UNICODETEXT, handle).is_null() {
+            GlobalFree(handle);
+            return Err(SystemError::Clipboard(get_last_error()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the locale of the keyboard layout that the foreground window uses, e.g. `de-DE`.
+pub fn get_keyboard_layout() -> Option<String> {
+    unsafe {
+        let thread_id = GetWindowThreadProcessId(GetForegroundWindow(), ptr::null_mut());
+        let layout = GetKeyboardLayout(thread_id) as usize;
+        // the low word of the layout is the language
+        let language = (layout & 0xffff) as LCID;
+        let mut buffer = [0u16; LOCALE_NAME_MAX_LENGTH];
+
+        let len = LCIDToLocaleName(
+            language,
+            buffer.as_mut_ptr(),
+            LOCALE_NAME_MAX_LENGTH as i32,
+            0,
+        );
+
+        if len <= 1 {
+            return None;
+        }
+
+        Some(String::from_utf16_lossy(&buffer[..len as usize - 1]))
+    }
+}
+
+/// Switches the keyboard layout of the foreground window to the default layout of the locale,
+/// e.g. `de-DE`. The layout has to be installed.
+pub fn set_keyboard_layout(locale: &str) -> SystemResult {
+    unsafe {
+        let language = LocaleNameToLCID(util::to_widestring(locale).as_ptr(), 0);
+
+        if language == 0 {
+            return Err(SystemError::KeyboardLayout(format!(
+                "Unknown locale '{}'",
+                locale
+            )));
+        }
+
+        let name = util::to_widestring(&format!("{:08X}", language & 0xffff));
+        let layout = LoadKeyboardLayoutW(name.as_ptr(), KLF_ACTIVATE);
+
+        if layout.is_null() {
+            return Err(SystemError::KeyboardLayout(get_last_error()));
+        }
+
+        PostMessageW(
+            GetForegroundWindow(),
+            WM_INPUTLANGCHANGEREQUEST,
+            0,
+            layout as LPARAM,
+        );
+    }
+
+    Ok(())
+}
+
+/// Returns the name of the computer
+pub fn get_hostname() -> Option<String> {
+    let mut buffer = [0u16; 256];
+    let mut len = buffer.len() as DWORD;
+
+    unsafe {
+        bool_to_result(GetComputerNameW(buffer.as_mut_ptr(), &mut len)).ok()?;
+    }
+
+    Some(util::from_widestring(&buffer))
+}
+
+unsafe extern "system" fn font_cb(
+    _: *const LOGFONTA,
+    _: *const TEXTMETRICA,
+    _: DWORD,
+    l_param: LPARAM,
+) -> c_int {
+    *(l_param as *mut bool) = true;
+    // one match is enough
+    0
+}
+
+/// Returns whether a font family with the name is installed
+pub fn is_font_installed(name: &str) -> bool {
+    let bytes = name.as_bytes();
+
+    // the name has to fit into the face name of a LOGFONTA including its terminating zero
+    if bytes.is_empty() || bytes.len() >= LF_FACESIZE || bytes.contains(&0) {
+        return false;
+    }
+
+    let mut logfont = LOGFONTA {
+        lfCharSet: DEFAULT_CHARSET as u8,
+        ..LOGFONTA::default()
+    };
+
+    for (i, byte) in bytes.iter().enumerate() {
+        logfont.lfFaceName[i] = *byte as i8;
+    }
+
+    let mut found = false;
+
+    unsafe {
+        let hdc = GetDC(ptr::null_mut());
+        EnumFontFamiliesExA(
+            hdc,
+            &mut logfont,
+            Some(font_cb),
+            &mut found as *mut bool as LPARAM,
+            0,
+        );
+        ReleaseDC(ptr::null_mut(), hdc);
+    }
+
+    found
+}
+
+/// Returns the name of the user that nog runs as
+pub fn get_username() -> Option<String> {
+    let mut buffer = [0u16; UNLEN as usize + 1];
+    let mut len = buffer.len() as DWORD;
+
+    unsafe {
+        bool_to_result(GetUserNameW(buffer.as_mut_ptr(), &mut len)).ok()?;
+    }
+
+    Some(util::from_widestring(&buffer))
+}
+
+/// Returns the device name of the display (e.g. `\\.\DISPLAY1`) and the description of the
+/// monitor that is connected to it, which usually is its model.
+pub fn get_display_names(id: DisplayId) -> (String, String) {
+    let mut monitor_info = MONITORINFOEXW {
+        cbSize: core::mem::size_of::<MONITORINFOEXW>() as u32,
+        ..MONITORINFOEXW::default()
+    };
+    let mut device = DISPLAY_DEVICEW {
+        cb: core::mem::size_of::<DISPLAY_DEVICEW>() as u32,
+        ..DISPLAY_DEVICEW::default()
+    };
+
+    unsafe {
+        GetMonitorInfoW(
+            id.into(),
+            &mut monitor_info as *mut MONITORINFOEXW as *mut MONITORINFO,
+        );
+        EnumDisplayDevicesW(monitor_info.szDevice.as_ptr(), 0, &mut device, 0);
+    }
+
+    (
+        util::from_widestring(&monitor_info.szDevice),
+        util::from_widestring(&device.DeviceString),
+    )
+}
+
 pub fn get_taskbars() -> Vec<Taskbar> {
     let mut taskbars: Vec<Taskbar> = Vec::new();
     unsafe {
@@ -125,6 +422,34 @@ pub fn get_taskbars() -> Vec<Taskbar> {
     taskbars
 }
 
+fn appbar_data(id: WindowId) -> APPBARDATA {
+    APPBARDATA {
+        cbSize: std::mem::size_of::<APPBARDATA>() as DWORD,
+        hWnd: id.into(),
+        ..APPBARDATA::default()
+    }
+}
+
+/// Whether the taskbar hides automatically instead of reserving space on its display
+pub fn is_taskbar_auto_hide(id: WindowId) -> bool {
+    let mut data = appbar_data(id);
+
+    unsafe { SHAppBarMessage(ABM_GETSTATE, &mut data) as UINT & ABS_AUTOHIDE != 0 }
+}
+
+pub fn set_taskbar_auto_hide(id: WindowId, auto_hide: bool) {
+    let mut data = appbar_data(id);
+    data.lParam = if auto_hide {
+        ABS_AUTOHIDE
+    } else {
+        ABS_ALWAYSONTOP
+    } as LPARAM;
+
+    unsafe {
+        SHAppBarMessage(ABM_SETSTATE, &mut data);
+    }
+}
+
 pub fn add_launch_on_startup() {
     unsafe {
         let mut target_path = dirs::config_dir().unwrap();
@@ -0,0 +1,101 @@
+//! Controls the volume and the media playback by pressing the media keys on behalf of the user,
+//! which makes Windows show its usual volume overlay. The current volume gets read from the
+//! default audio endpoint.
+use crate::system::{SystemError, SystemResult};
+use std::ptr;
+use winapi::{
+    shared::{minwindef::*, winerror::*},
+    um::{combaseapi::*, endpointvolume::*, mmdeviceapi::*, objbase::*, winuser::*},
+    Class, Interface,
+};
+
+#[derive(Debug, Clone, Copy)]
+pub enum MediaKey {
+    VolumeUp,
+    VolumeDown,
+    Mute,
+    PlayPause,
+    Next,
+    Prev,
+}
+
+impl MediaKey {
+    fn vk(self) -> i32 {
+        match self {
+            MediaKey::VolumeUp => VK_VOLUME_UP,
+            MediaKey::VolumeDown => VK_VOLUME_DOWN,
+            MediaKey::Mute => VK_VOLUME_MUTE,
+            MediaKey::PlayPause => VK_MEDIA_PLAY_PAUSE,
+            MediaKey::Next => VK_MEDIA_NEXT_TRACK,
+            MediaKey::Prev => VK_MEDIA_PREV_TRACK,
+        }
+    }
+}
+
+pub fn press(key: MediaKey) {
+    let vk = key.vk() as u8;
+
+    unsafe {
+        keybd_event(vk, 0, KEYEVENTF_EXTENDEDKEY, 0);
+        keybd_event(vk, 0, KEYEVENTF_EXTENDEDKEY | KEYEVENTF_KEYUP, 0);
+    }
+}
+
+fn check(hr: HRESULT) -> SystemResult {
+    if SUCCEEDED(hr) {
+        Ok(())
+    } else {
+        Err(SystemError::Audio(format!("HRESULT {:#x}", hr)))
+    }
+}
+
+unsafe fn read_volume() -> SystemResult<(u32, bool)> {
+    let mut enumerator: *mut IMMDeviceEnumerator = ptr::null_mut();
+    check(CoCreateInstance(
+        &MMDeviceEnumerator::uuidof(),
+        ptr::null_mut(),
+        CLSCTX_ALL,
+        &IMMDeviceEnumerator::uuidof(),
+        &mut enumerator as *mut _ as *mut LPVOID,
+    ))?;
+
+    let mut device: *mut IMMDevice = ptr::null_mut();
+    let hr = (*enumerator).GetDefaultAudioEndpoint(eRender, eConsole, &mut device);
+    (*enumerator).Release();
+    check(hr)?;
+
+    let mut endpoint: *mut IAudioEndpointVolume = ptr::null_mut();
+    let hr = (*device).Activate(
+        &IAudioEndpointVolume::uuidof(),
+        CLSCTX_ALL,
+        ptr::null_mut(),
+        &mut endpoint as *mut _ as *mut LPVOID,
+    );
+    (*device).Release();
+    check(hr)?;
+
+    let mut level = 0.0;
+    let mut muted: BOOL = 0;
+    let level_hr = (*endpoint).GetMasterVolumeLevelScalar(&mut level);
+    let mute_hr = (*endpoint).GetMute(&mut muted);
+    (*endpoint).Release();
+    check(level_hr)?;
+    check(mute_hr)?;
+
+    Ok(((level * 100.0).round() as u32, muted != 0))
+}
+
+/// Returns the volume of the default output device in percent and whether it is muted.
+pub fn get_volume() -> SystemResult<(u32, bool)> {
+    unsafe {
+        let init = CoInitializeEx(ptr::null_mut(), COINIT_MULTITHREADED);
+        let volume = read_volume();
+
+        // every successful initialization has to be undone, even if COM was initialized before
+        if SUCCEEDED(init) {
+            CoUninitialize();
+        }
+
+        volume
+    }
+}
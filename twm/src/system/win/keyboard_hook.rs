@@ -0,0 +1,200 @@
+use crate::{
+    config::rule::Rule,
+    keybindings::{key::Key, modifier::Modifier, ChanMessage},
+    message_loop,
+    system::NativeWindow,
+};
+use lazy_static::lazy_static;
+use log::{debug, error, warn};
+use num_traits::FromPrimitive;
+use parking_lot::Mutex;
+use std::{
+    ptr, sync::atomic::AtomicBool, sync::atomic::Ordering, sync::mpsc::sync_channel,
+    sync::mpsc::Receiver, sync::mpsc::Sender, sync::mpsc::SyncSender, sync::Arc, thread,
+};
+use winapi::{
+    shared::{minwindef::*, windef::*},
+    um::libloaderapi::GetModuleHandleW,
+    um::winuser::*,
+};
+
+/// A virtual key code that isn't assigned to any key
+const MASK_KEY: u8 = 0xE8;
+/// How many intercepted key combos can wait to be forwarded
+const QUEUE_SIZE: usize = 32;
+
+lazy_static! {
+    static ref CHAN: Arc<Mutex<(SyncSender<(Key, Modifier)>, Receiver<(Key, Modifier)>)>> =
+        Arc::new(Mutex::new(sync_channel(QUEUE_SIZE)));
+    /// The key combos that get swallowed by the hook instead of reaching Windows
+    static ref COMBOS: Mutex<Vec<(Key, Modifier)>> = Mutex::new(Vec::new());
+    /// The rules that let key combos through to their windows
+    static ref PASS_THROUGH: Mutex<Vec<Rule>> = Mutex::new(Vec::new());
+}
+
+pub fn intercept(key: Key, modifier: Modifier) {
+    let mut combos = COMBOS.lock();
+
+    if !combos.contains(&(key, modifier)) {
+        combos.push((key, modifier));
+    }
+}
+
+pub fn release(key: Key, modifier: Modifier) {
+    COMBOS.lock().retain(|combo| *combo != (key, modifier));
+}
+
+pub fn set_pass_through(rules: Vec<Rule>) {
+    *PASS_THROUGH.lock() = rules;
+}
+
+/// Whether the foreground window matches a rule that lets the key combo through
+fn passes_through(key: Key, modifier: Modifier) -> bool {
+    let rules = PASS_THROUGH.lock();
+    let rules: Vec<&Rule> = rules
+        .iter()
+        .filter(|rule| rule.pass_through.contains(&(key, modifier)))
+        .collect();
+
+    if rules.is_empty() {
+        return false;
+    }
+
+    match NativeWindow::get_foreground_window() {
+        Ok(mut window) => {
+            window.title = window.get_title().unwrap_or_default();
+            window.set_matching_rule(rules);
+            window.rule.is_some()
+        }
+        Err(_) => false,
+    }
+}
+
+unsafe fn is_down(vk: i32) -> bool {
+    GetAsyncKeyState(vk) as u16 & 0x8000 != 0
+}
+
+unsafe fn current_modifier() -> Modifier {
+    let mut modifier = Modifier::default();
+
+    modifier.set(Modifier::ALT, is_down(VK_MENU));
+    modifier.set(Modifier::CONTROL, is_down(VK_CONTROL));
+    modifier.set(Modifier::SHIFT, is_down(VK_SHIFT));
+    modifier.set(Modifier::WIN, is_down(VK_LWIN) || is_down(VK_RWIN));
+
+    modifier
+}
+
+unsafe extern "system" fn handler(code: i32, w_param: WPARAM, l_param: LPARAM) -> LRESULT {
+    if code >= 0 && (w_param as u32 == WM_KEYDOWN || w_param as u32 == WM_SYSKEYDOWN) {
+        let info = &*(l_param as *const KBDLLHOOKSTRUCT);
+
+        if let Some(key) = Key::from_u32(info.vkCode) {
+            let modifier = current_modifier();
+
+            let intercepted = COMBOS.lock().contains(&(key, modifier));
+
+            if intercepted && passes_through(key, modifier) {
+                debug!(
+                    "Passing {:?} {:?} through to the foreground window",
+                    modifier, key
+                );
+            } else if intercepted {
+                if modifier.contains(Modifier::WIN) {
+                    // Windows opens the start menu when the windows key gets released without any
+                    // other key being pressed in between, so we press a key that does nothing.
+                    keybd_event(MASK_KEY, 0, 0, 0);
+                    keybd_event(MASK_KEY, 0, KEYEVENTF_KEYUP, 0);
+                }
+
+                // the hook has to return quickly, otherwise the input of the whole system lags, so
+                // the key combo gets dropped instead of waiting for space in the queue
+                if CHAN.lock().0.try_send((key, modifier)).is_err() {
+                    warn!(
+                        "Dropping {:?} {:?}, because the queue is full",
+                        modifier, key
+                    );
+                }
+
+                return 1;
+            }
+        }
+    }
+
+    CallNextHookEx(ptr::null_mut(), code, w_param, l_param)
+}
+
+/// Intercepts key combos that include the windows key using a low-level keyboard hook. Most of
+/// these are reserved by Windows, which means that they can't be registered as hotkeys. Key combos
+/// that rules let through to some windows get intercepted as well, because a registered hotkey
+/// never reaches the focused window.
+#[derive(Debug, Clone)]
+pub struct KeyboardHook {
+    stopped: Arc<AtomicBool>,
+    running: Arc<AtomicBool>,
+}
+
+impl Default for KeyboardHook {
+    fn default() -> Self {
+        Self {
+            stopped: Arc::new(AtomicBool::new(false)),
+            running: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl KeyboardHook {
+    /// Starts the hook, which sends a `Trigger` message to the keybinding manager whenever an
+    /// intercepted key combo gets pressed.
+    pub fn start(&self, sender: Sender<ChanMessage>) {
+        if self.running.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let stopped = self.stopped.clone();
+        let running = self.running.clone();
+
+        thread::spawn(move || unsafe {
+            debug!("Registering keyboard hook");
+
+            let hook: HHOOK = SetWindowsHookExW(
+                WH_KEYBOARD_LL,
+                Some(handler),
+                GetModuleHandleW(ptr::null()),
+                0,
+            );
+
+            if hook.is_null() {
+                error!("Failed to register keyboard hook");
+                running.store(false, Ordering::SeqCst);
+                return;
+            }
+
+            message_loop::start_with_sleep(1, |_| {
+                if stopped.load(Ordering::SeqCst) {
+                    UnhookWindowsHookEx(hook);
+                    debug!("Keyboard hook unregistered");
+                    stopped.store(false, Ordering::SeqCst);
+                    running.store(false, Ordering::SeqCst);
+                    return false;
+                }
+
+                while let Ok((key, modifier)) = CHAN.lock().1.try_recv() {
+                    sender
+                        .send(ChanMessage::Trigger(key, modifier))
+                        .expect("Failed to send key combo");
+                }
+
+                true
+            });
+        });
+    }
+
+    pub fn stop(&self) {
+        if self.running.load(Ordering::SeqCst) {
+            debug!("Unregistering keyboard hook");
+
+            self.stopped.store(true, Ordering::SeqCst);
+        }
+    }
+}
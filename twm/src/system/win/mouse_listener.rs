@@ -0,0 +1,114 @@
+use crate::{event::Event, event::EventChannel, event::MouseEvent, message_loop};
+use lazy_static::lazy_static;
+use log::{debug, error};
+use parking_lot::Mutex;
+use std::{
+    ptr, sync::atomic::AtomicBool, sync::atomic::Ordering, sync::mpsc::channel,
+    sync::mpsc::Receiver, sync::mpsc::Sender, sync::Arc, thread,
+};
+use winapi::{
+    shared::{minwindef::*, windef::*},
+    um::libloaderapi::GetModuleHandleW,
+    um::winuser::*,
+};
+
+lazy_static! {
+    static ref CHAN: Arc<Mutex<(Sender<MouseEvent>, Receiver<MouseEvent>)>> =
+        Arc::new(Mutex::new(channel()));
+}
+
+unsafe extern "system" fn handler(code: i32, w_param: WPARAM, l_param: LPARAM) -> LRESULT {
+    if code >= 0 {
+        let info = &*(l_param as *const MSLLHOOKSTRUCT);
+
+        let event = match w_param as u32 {
+            WM_LBUTTONDOWN => Some(MouseEvent::LeftDown(info.pt.x, info.pt.y)),
+            WM_LBUTTONUP => Some(MouseEvent::LeftUp(info.pt.x, info.pt.y)),
+            _ => None,
+        };
+
+        if let Some(event) = event {
+            CHAN.lock()
+                .0
+                .send(event)
+                .expect("Failed to forward MouseEvent");
+        }
+    }
+
+    // the event always gets passed on, because we only observe the mouse
+    CallNextHookEx(ptr::null_mut(), code, w_param, l_param)
+}
+
+/// Listens for left mouse button presses and releases anywhere on the screen using a low-level
+/// mouse hook.
+#[derive(Debug, Clone)]
+pub struct MouseListener {
+    stopped: Arc<AtomicBool>,
+    running: Arc<AtomicBool>,
+}
+
+impl Default for MouseListener {
+    fn default() -> Self {
+        Self {
+            stopped: Arc::new(AtomicBool::new(false)),
+            running: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl MouseListener {
+    pub fn start(&self, channel: &EventChannel) {
+        if self.running.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let stopped = self.stopped.clone();
+        let running = self.running.clone();
+        let sender = channel.sender.clone();
+
+        thread::spawn(move || unsafe {
+            debug!("Registering mouse hook");
+
+            let hook: HHOOK = SetWindowsHookExW(
+                WH_MOUSE_LL,
+                Some(handler),
+                GetModuleHandleW(ptr::null()),
+                0,
+            );
+
+            if hook.is_null() {
+                error!("Failed to register mouse hook");
+                running.store(false, Ordering::SeqCst);
+                return;
+            }
+
+            // Low-level hooks get called while the thread is waiting for messages, so the sleep
+            // has to be short to not make the mouse feel sluggish.
+            message_loop::start_with_sleep(1, |_| {
+                if stopped.load(Ordering::SeqCst) {
+                    UnhookWindowsHookEx(hook);
+                    debug!("Mouse hook unregistered");
+                    stopped.store(false, Ordering::SeqCst);
+                    running.store(false, Ordering::SeqCst);
+                    return false;
+                }
+
+                while let Ok(event) = CHAN.lock().1.try_recv() {
+                    sender
+                        .send(Event::Mouse(event))
+                        .expect("Failed to send MouseEvent");
+                }
+
+                true
+            });
+        });
+    }
+
+    pub fn stop(&self) {
+        if self.running.load(Ordering::SeqCst) {
+            debug!("Unregistering mouse hook");
+
+            self.stopped.store(true, Ordering::SeqCst);
+        }
+    }
+}
@@ -8,17 +8,31 @@ use lazy_static::lazy_static;
 use log::debug;
 use parking_lot::Mutex;
 use std::{
-    ptr, sync::atomic::AtomicBool, sync::atomic::AtomicPtr, sync::atomic::Ordering,
-    sync::mpsc::channel, sync::mpsc::Receiver, sync::mpsc::Sender, sync::Arc, thread,
-    time::Duration,
+    cell::{Cell, RefCell},
+    mem, ptr,
+    sync::atomic::AtomicBool,
+    sync::atomic::AtomicPtr,
+    sync::atomic::Ordering,
+    sync::mpsc::channel,
+    sync::mpsc::Receiver,
+    sync::mpsc::Sender,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
 };
 use winapi::{
     shared::{minwindef::*, ntdef::*, windef::*},
     um::winuser::*,
 };
 
+/// A batch of events gets handled once no new event arrived for this long
+const BATCH_QUIET_TIME: Duration = Duration::from_millis(20);
+/// The longest time an event waits in a batch
+const BATCH_MAX_DELAY: Duration = Duration::from_millis(100);
+
 lazy_static! {
-    static ref CHAN: Arc<Mutex<(Sender<Event>, Receiver<Event>)>> = Arc::new(Mutex::new(channel()));
+    static ref CHAN: Arc<Mutex<(Sender<WinEvent>, Receiver<WinEvent>)>> =
+        Arc::new(Mutex::new(channel()));
 }
 
 unsafe extern "system" fn handler(
@@ -47,10 +61,10 @@ unsafe extern "system" fn handler(
         None => return,
     };
 
-    let event = Event::WinEvent(WinEvent {
+    let event = WinEvent {
         typ: win_event_type,
         window,
-    });
+    };
 
     CHAN.lock()
         .0
@@ -95,6 +109,10 @@ impl WinEventListener {
 
             hook.store(hook_ptr as HWINEVENTHOOK, Ordering::SeqCst);
 
+            let batch = RefCell::new(Vec::<WinEvent>::new());
+            let first_event = Cell::new(Instant::now());
+            let last_event = Cell::new(Instant::now());
+
             message_loop::start(|_| {
                 if stopped.load(Ordering::SeqCst) {
                     debug!("Win event hook unregistered");
@@ -102,8 +120,25 @@ impl WinEventListener {
                     return false;
                 }
 
-                if let Ok(event) = CHAN.lock().1.try_recv() {
-                    sender.send(event).expect("Failed to send WinEvent");
+                let mut batch = batch.borrow_mut();
+
+                for event in CHAN.lock().1.try_iter() {
+                    if batch.is_empty() {
+                        first_event.set(Instant::now());
+                    }
+                    last_event.set(Instant::now());
+                    batch.push(event);
+                }
+
+                // a burst of events gets handled at once, but a steady stream of events can't
+                // hold back the batch forever
+                if !batch.is_empty()
+                    && (last_event.get().elapsed() >= BATCH_QUIET_TIME
+                        || first_event.get().elapsed() >= BATCH_MAX_DELAY)
+                {
+                    sender
+                        .send(Event::WinEvents(mem::take(&mut *batch)))
+                        .expect("Failed to send WinEvents");
                 }
 
                 thread::sleep(Duration::from_millis(5));
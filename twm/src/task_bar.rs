@@ -1,4 +1,4 @@
-use crate::system::NativeWindow;
+use crate::system::{api, NativeWindow};
 
 #[derive(Debug, Clone, Copy)]
 pub enum TaskbarPosition {
@@ -19,6 +19,8 @@ impl Default for TaskbarPosition {
 pub struct Taskbar {
     pub window: NativeWindow,
     position: TaskbarPosition,
+    /// Whether the taskbar hid automatically before nog set it to auto-hide
+    original_auto_hide: Option<bool>,
 }
 
 impl Taskbar {
@@ -26,6 +28,27 @@ impl Taskbar {
         Taskbar {
             window,
             position: TaskbarPosition::default(),
+            original_auto_hide: None,
+        }
+    }
+
+    pub fn is_auto_hide(&self) -> bool {
+        api::is_taskbar_auto_hide(self.window.id)
+    }
+
+    /// Sets the taskbar to hide automatically and remembers whether it did so before.
+    pub fn enable_auto_hide(&mut self) {
+        if self.original_auto_hide.is_none() {
+            self.original_auto_hide = Some(self.is_auto_hide());
+        }
+
+        api::set_taskbar_auto_hide(self.window.id, true);
+    }
+
+    /// Restores the auto-hide setting the taskbar had before `enable_auto_hide` got called.
+    pub fn restore_auto_hide(&mut self) {
+        if let Some(auto_hide) = self.original_auto_hide.take() {
+            api::set_taskbar_auto_hide(self.window.id, auto_hide);
         }
     }
 
@@ -41,7 +64,8 @@ impl Taskbar {
             .expect("Failed to get display of taskbar")
             .rect;
 
-        if self.window.is_hidden() {
+        // a taskbar that hides automatically doesn't take up any space
+        if self.window.is_hidden() || self.is_auto_hide() {
             TaskbarPosition::Hidden
         } else if tb_rect.left == display_rect.left
             && tb_rect.top == display_rect.top
@@ -0,0 +1,227 @@
+//! Applies the templates of the config (see `config::template`) to workspaces.
+//!
+//! Managed windows that match a placeholder get moved into it right away. The programs of the
+//! remaining placeholders get launched and their windows are placed as soon as they show up.
+//! Placeholders without a program are filled with the windows the workspace already has.
+use crate::{
+    config::template::Template,
+    launcher,
+    system::{NativeWindow, SystemResult, WindowId},
+    AppState,
+};
+use log::{debug, error, info};
+
+/// A template whose placeholders aren't all filled yet
+#[derive(Debug, Clone)]
+pub struct PendingTemplate {
+    pub workspace_id: i32,
+    pub template: Template,
+    /// The window of each placeholder
+    pub windows: Vec<Option<WindowId>>,
+}
+
+impl PendingTemplate {
+    /// Assigns the window to the first empty placeholder it matches and returns whether it found
+    /// one.
+    fn claim(&mut self, id: WindowId, process_name: &str, title: &str) -> bool {
+        let placeholders = self.template.placeholders();
+
+        for (i, placeholder) in placeholders.iter().enumerate() {
+            if self.windows[i].is_none() && placeholder.matches(process_name, title) {
+                self.windows[i] = Some(id);
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Assigns the windows in order to the empty placeholders that don't need a certain program.
+    fn fill_any(&mut self, ids: Vec<WindowId>) {
+        let placeholders = self.template.placeholders();
+        let mut ids = ids
+            .into_iter()
+            .filter(|id| !self.windows.contains(&Some(*id)))
+            .collect::<Vec<_>>()
+            .into_iter();
+
+        for (i, placeholder) in placeholders.iter().enumerate() {
+            if self.windows[i].is_none() && placeholder.exe.is_none() {
+                self.windows[i] = ids.next();
+            }
+        }
+    }
+
+    /// Whether every placeholder that waits for a program has its window
+    fn is_complete(&self) -> bool {
+        self.template
+            .placeholders()
+            .iter()
+            .zip(self.windows.iter())
+            .all(|(placeholder, window)| window.is_some() || placeholder.exe.is_none())
+    }
+}
+
+/// Loads the layout of the template into the workspace. The windows of the workspace that don't
+/// belong to the template get added afterwards.
+fn arrange(
+    state: &mut AppState,
+    pending: &PendingTemplate,
+    mut windows: Vec<NativeWindow>,
+) -> SystemResult {
+    let grid = match state.get_grid_by_id_mut(pending.workspace_id) {
+        Some(grid) => grid,
+        None => return Ok(()),
+    };
+
+    for id in grid.get_window_ids() {
+        if let Some(window) = grid.remove_by_window_id(id) {
+            windows.push(window);
+        }
+    }
+
+    // placeholders whose window got closed in the meantime are left out
+    let ids: Vec<Option<WindowId>> = pending
+        .windows
+        .iter()
+        .map(|id| id.filter(|id| windows.iter().any(|w| w.id == *id)))
+        .collect();
+
+    grid.from_string(&pending.template.layout(&ids));
+
+    // the layout only contains the ids, so the windows lose what nog knows about them otherwise
+    let known = &windows;
+    grid.modify_windows(|window| {
+        if let Some(known) = known.iter().find(|w| w.id == window.id) {
+            *window = known.clone();
+        }
+
+        Ok(())
+    })?;
+
+    for window in windows {
+        if !ids.contains(&Some(window.id)) {
+            grid.push(window);
+        }
+    }
+
+    Ok(())
+}
+
+/// Arranges the workspace with the template and launches the programs whose windows are missing.
+pub fn apply(state: &mut AppState, template: Template, workspace_id: i32) -> SystemResult {
+    if state.get_grid_by_id(workspace_id).is_none() {
+        error!("Workspace {} doesn't exist", workspace_id);
+        return Ok(());
+    }
+
+    info!(
+        "Applying template '{}' to workspace {}",
+        template.name, workspace_id
+    );
+
+    let mut pending = PendingTemplate {
+        workspace_id,
+        windows: vec![None; template.placeholders().len()],
+        template,
+    };
+
+    let mut candidates = Vec::new();
+    for grid in state.get_grids() {
+        for id in grid.get_window_ids() {
+            if let Some(window) = grid.get_window(id) {
+                candidates.push((grid.id, window.clone()));
+            }
+        }
+    }
+
+    let mut windows = Vec::new();
+    for (grid_id, window) in candidates {
+        let title = window.get_title().unwrap_or_default();
+
+        if pending.claim(window.id, &window.get_process_name(), &title) {
+            if grid_id != workspace_id {
+                if let Some(window) = state
+                    .get_grid_by_id_mut(grid_id)
+                    .and_then(|grid| grid.remove_by_window_id(window.id))
+                {
+                    windows.push(window);
+                }
+            }
+        }
+    }
+
+    if let Some(grid) = state.get_grid_by_id(workspace_id) {
+        pending.fill_any(grid.get_window_ids());
+    }
+
+    arrange(state, &pending, windows)?;
+
+    for (i, placeholder) in pending.template.placeholders().iter().enumerate() {
+        match placeholder.command() {
+            Some(command) if pending.windows[i].is_none() => {
+                if let Err(e) = launcher::launch(&state.config.launch_policy, command) {
+                    error!("{}", e);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // a workspace only waits for the windows of the template that got applied last
+    state
+        .pending_templates
+        .retain(|p| p.workspace_id != workspace_id);
+
+    if !pending.is_complete() {
+        state.pending_templates.push(pending);
+    }
+
+    let config = state.config.clone();
+    for display in &state.displays {
+        display.refresh_grid(&config)?;
+    }
+
+    state.change_workspace(workspace_id, false);
+
+    Ok(())
+}
+
+/// Returns the workspace of the template that has a placeholder for the window. The placeholder
+/// counts as filled afterwards.
+pub fn claim(state: &mut AppState, window: &NativeWindow) -> Option<i32> {
+    let process_name = window.get_process_name();
+
+    let idx = state
+        .pending_templates
+        .iter_mut()
+        .position(|p| p.claim(window.id, &process_name, &window.title))?;
+    let pending = &state.pending_templates[idx];
+
+    debug!(
+        "Placing '{}' into template '{}'",
+        window.title, pending.template.name
+    );
+
+    Some(pending.workspace_id)
+}
+
+/// Places the window that got claimed by a template of the workspace.
+pub fn place(state: &mut AppState, workspace_id: i32, window: NativeWindow) -> SystemResult {
+    let pending = match state
+        .pending_templates
+        .iter()
+        .find(|p| p.workspace_id == workspace_id)
+    {
+        Some(pending) => pending.clone(),
+        None => return Ok(()),
+    };
+
+    if pending.is_complete() {
+        state
+            .pending_templates
+            .retain(|p| p.workspace_id != workspace_id);
+    }
+
+    arrange(state, &pending, vec![window])
+}
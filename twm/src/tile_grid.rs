@@ -1,10 +1,11 @@
 use crate::{
-    config::Config,
+    config::{close_focus_policy::CloseFocusPolicy, Config},
     direction::Direction,
     display::Display,
-    renderer::{NativeRenderer, Renderer},
+    renderer::{NativeRenderer, Renderer, TileRect},
     split_direction::SplitDirection,
     system::NativeWindow,
+    system::Rectangle,
     system::SystemError,
     system::SystemResult,
     system::WindowId,
@@ -14,16 +15,25 @@ use crate::{
     },
 };
 use log::{debug, error, info};
-use std::cmp;
+use std::{
+    cmp,
+    collections::{HashMap, HashSet},
+};
 
 pub mod graph_wrapper;
+pub mod history;
+pub mod meta;
 pub mod node;
+pub mod placements;
+pub mod snapshots;
 pub mod store;
 pub mod text_renderer;
 pub mod tile_render_info;
 
 static FULL_SIZE: u32 = 120;
 static HALF_SIZE: u32 = FULL_SIZE / 2;
+/// How many of the previously focused windows a grid remembers
+static FOCUS_HISTORY_SIZE: usize = 20;
 
 #[derive(Clone, Debug)]
 pub struct TileGrid<TRenderer: Renderer = NativeRenderer> {
@@ -37,10 +47,34 @@ pub struct TileGrid<TRenderer: Renderer = NativeRenderer> {
     //       pushing a tile "above" a focused tile in a column or "before" a focused tile in a row
     //       as opposed to the current way where it always adds below/after
     pub next_direction: Direction,
+    /// The windows that got focused on this grid, the most recent one last
+    pub focus_history: Vec<WindowId>,
+    /// Where the next window goes relative to the given window, which takes precedence over
+    /// `next_axis` and `next_direction` for a single push. It only applies while the window is
+    /// still focused.
+    pub pending_split: Option<(WindowId, Direction)>,
     graph: GraphWrapper,
 }
 
 impl TileGrid {
+    /// Returns the area of the display that the tiles get rendered in.
+    pub fn get_area(display: &Display, config: &Config) -> Rectangle {
+        let margin = if config.outer_gap > 0 {
+            config.outer_gap
+        } else {
+            0
+        };
+
+        let left = display.working_area_left() + (margin / 2);
+        let top = display.working_area_top(config) + (margin / 2);
+
+        Rectangle {
+            left,
+            top,
+            right: left + display.working_area_width(config) - margin,
+            bottom: top + display.working_area_height(config) - margin,
+        }
+    }
     pub fn draw_grid(&self, display: &Display, config: &Config) -> SystemResult {
         // for Debug purposes. Adds information to the log
         // TODO: make this configurable?
@@ -52,28 +86,23 @@ impl TileGrid {
         let render_infos = self.get_render_info(64, 20);
         debug!("{}", TextRenderer::render(64, 20, render_infos));
 
-        let (padding, margin) = (
-            if config.inner_gap > 0 {
-                config.inner_gap / 2
-            } else {
-                0
-            },
-            if config.outer_gap > 0 {
-                config.outer_gap
-            } else {
-                0
-            },
-        );
+        let padding = if config.inner_gap > 0 {
+            config.inner_gap / 2
+        } else {
+            0
+        };
 
-        let display_width = display.working_area_width(config) - margin;
-        let display_height = display.working_area_height(config) - margin;
-        let display_left = display.working_area_left() + (margin / 2);
-        let display_top = display.working_area_top(config) + (margin / 2);
+        let area = Self::get_area(display, config);
+        let display_width = area.width();
+        let display_height = area.height();
+        let display_left = area.left;
+        let display_top = area.top;
 
         let render_infos = self.get_render_info(display_width as u32, display_height as u32);
 
         info!("Beginning Rendering");
-        for render_info in render_infos {
+        let mut tiles = Vec::new();
+        for render_info in render_infos.iter() {
             let left_padding = if render_info.x != 0 { padding } else { 0 };
             let top_padding = if render_info.y != 0 { padding } else { 0 };
             let right_padding = if (render_info.x + render_info.width) as i32 != display_width {
@@ -92,21 +121,34 @@ impl TileGrid {
             let width = render_info.width as i32 - left_padding - right_padding;
             let height = render_info.height as i32 - top_padding - bottom_padding;
 
-            self.renderer.render(
-                self,
-                &render_info.window,
-                config,
-                display,
-                left,
-                top,
+            tiles.push(TileRect {
+                window: &render_info.window,
+                x: left,
+                y: top,
                 width,
                 height,
-            )?;
+            });
         }
+        self.renderer.render_all(self, &tiles, config, display)?;
         info!("Rendering completed");
 
         Ok(())
     }
+    /// Renders the layout of the grid as text, followed by the title of each window.
+    pub fn get_preview(&self, width: u32, height: u32) -> String {
+        TextRenderer::render_preview(width, height, self.get_render_info(width, height))
+            .trim()
+            .to_string()
+    }
+    /// Renders the layout of the grid as text without any titles. Returns no rows if the grid is
+    /// empty.
+    pub fn get_miniature(&self, width: u32, height: u32) -> Vec<String> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+
+        TextRenderer::render_miniature(width, height, self.get_render_info(width, height))
+    }
     /// Returns a list of render information for each tile in the graph
     /// inner/outer padding should be handled outside of the tile grid by reducing the
     /// width/height by the outer padding and trimming off between tiles with the inner padding.
@@ -135,6 +177,90 @@ impl TileGrid {
 
         render_infos
     }
+    /// Returns the render information of the tile at the given position. The position is relative
+    /// to the area of the grid.
+    pub fn get_tile_at(&self, x: i32, y: i32, width: u32, height: u32) -> Option<TileRenderInfo> {
+        self.get_render_info(width, height).into_iter().find(|info| {
+            x >= info.x as i32
+                && x < (info.x + info.width) as i32
+                && y >= info.y as i32
+                && y < (info.y + info.height) as i32
+        })
+    }
+    /// Returns the two tiles that share a boundary at the given position, where the first tile is
+    /// left of/above the second one. `tolerance` is the distance in pixels the position may have
+    /// from the boundary.
+    pub fn get_boundary_at(
+        &self,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        tolerance: i32,
+    ) -> Option<(usize, usize)> {
+        let candidates = vec![
+            (
+                self.get_tile_at(x - tolerance, y, width, height),
+                self.get_tile_at(x + tolerance, y, width, height),
+            ),
+            (
+                self.get_tile_at(x, y - tolerance, width, height),
+                self.get_tile_at(x, y + tolerance, width, height),
+            ),
+        ];
+
+        candidates
+            .into_iter()
+            .filter_map(|tiles| match tiles {
+                (Some(first), Some(second)) if first.debug_id != second.debug_id => {
+                    Some((first.debug_id, second.debug_id))
+                }
+                _ => None,
+            })
+            .find(|(first, second)| self.get_boundary(*first, *second).is_some())
+    }
+    /// Moves the boundary between the two tiles by the given distance in pixels. Only the distance
+    /// along the axis of the boundary is used.
+    pub fn drag_boundary(
+        &mut self,
+        first: usize,
+        second: usize,
+        dx: i32,
+        dy: i32,
+        width: u32,
+        height: u32,
+    ) {
+        let parent_id = match self.get_boundary(first, second) {
+            Some((parent_id, _, _)) => parent_id,
+            None => return,
+        };
+        let is_column = self.graph.node(parent_id).is_column();
+
+        // the size of a node is relative to its parent, so the distance has to be converted
+        // using the extent of the parent instead of the whole grid
+        let (min, max) = self
+            .get_render_info(width, height)
+            .into_iter()
+            .filter(|info| self.get_ancestors(info.debug_id).contains(&parent_id))
+            .fold((u32::MAX, 0), |(min, max), info| {
+                if is_column {
+                    (min.min(info.x), max.max(info.x + info.width))
+                } else {
+                    (min.min(info.y), max.max(info.y + info.height))
+                }
+            });
+
+        if max <= min {
+            return;
+        }
+
+        let pixels = if is_column { dx } else { dy };
+        let amount = pixels * FULL_SIZE as i32 / (max - min) as i32;
+
+        if amount != 0 {
+            self.resize_at_boundary(first, second, amount);
+        }
+    }
     /// A recursive function that walks the graph and populates the supplied vec with rendering information
     /// for each node based on the given resolution.
     fn populate_render_info(
@@ -263,12 +389,18 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
             focused_id: None,
             next_axis: SplitDirection::Vertical,
             next_direction: Direction::Right,
+            focus_history: Vec::new(),
+            pending_split: None,
         }
     }
     /// Returns whether the tile grid is populated or not
     pub fn is_empty(&self) -> bool {
         self.graph.is_empty()
     }
+    /// Returns how many windows the tile grid contains
+    pub fn window_count(&self) -> usize {
+        self.graph.tile_count()
+    }
     /// Returns whether the tile grid is fullscreened or not
     pub fn is_fullscreened(&self) -> bool {
         self.fullscreen_id.is_some()
@@ -322,6 +454,19 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
     pub fn reset_column(&mut self) {
         self.reset_size(self.graph.to_closest_column(self.focused_id));
     }
+    /// Gives every node the same size as its siblings, across the whole grid.
+    pub fn equalize(&mut self) {
+        for node_id in self.graph.nodes().collect::<Vec<usize>>() {
+            if !self.graph.node(node_id).is_tile() {
+                self.reset_size(Some(node_id));
+            }
+        }
+    }
+    /// Gives the focused tile and its siblings the same size. Containers inside of the focused
+    /// tile's container keep the sizes of their children.
+    pub fn balance_focused_container(&mut self) {
+        self.reset_size(self.graph.map_to_parent(self.focused_id));
+    }
     /// Gets all the child nodes of a node and re-distrbutes the size among them.
     /// This applies only one level down, regardless of what type of nodes they are; any
     /// child Row/Column nodes' children will retain their respective size.
@@ -350,8 +495,10 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
                 .set_size(size_per_child + get_remainder_slice());
         }
     }
-    /// Iterates and shows every window managed by the current tile_grid
-    pub fn show(&self) -> SystemResult {
+    /// Iterates and shows every window managed by the current tile_grid. Windows in `visible` that
+    /// are still visible get skipped and keep their position in the z-order, unless they are
+    /// fullscreened.
+    pub fn show(&self, visible: &HashSet<WindowId>) -> SystemResult {
         let mut nodes = self.graph.nodes().collect::<Vec<usize>>();
         if self.fullscreen_id.is_some() {
             nodes.sort_by_key(|n| {
@@ -365,6 +512,14 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
         for node_id in nodes {
             if self.graph.node(node_id).is_tile() {
                 let window = self.graph.node(node_id).get_window();
+
+                if visible.contains(&window.id)
+                    && self.fullscreen_id != Some(node_id)
+                    && window.is_visible()
+                {
+                    continue;
+                }
+
                 window.show();
                 window
                     .to_foreground(true)
@@ -397,7 +552,57 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
             })
             .map(|n| self.graph.node(n).get_window())
     }
+    /// Returns the IDs of every window in the tile grid
+    pub fn get_window_ids(&self) -> Vec<WindowId> {
+        self.graph
+            .nodes()
+            .filter(|n| self.graph.node(*n).is_tile())
+            .map(|n| self.graph.node(n).get_window().id)
+            .collect()
+    }
     /// Runs the passed in function on the currently focused tile's window in the current tile grid.
+    /// Makes every window except the focused one translucent if `dim_unfocused` is enabled. The
+    /// translucent windows are kept track of in `dimmed`, so that they become opaque again once
+    /// they get focused or the setting gets disabled.
+    pub fn update_opacity(
+        &self,
+        config: &Config,
+        focused: Option<WindowId>,
+        dimmed: &mut Vec<WindowId>,
+    ) -> SystemResult {
+        for node_id in self.graph.nodes() {
+            let node = self.graph.node(node_id);
+            if !node.is_tile() {
+                continue;
+            }
+
+            let window = node.get_window();
+            let opacity = if config.dim_unfocused && focused != Some(window.id) {
+                window
+                    .rule
+                    .as_ref()
+                    .and_then(|rule| rule.unfocused_opacity)
+                    .unwrap_or(config.unfocused_opacity)
+                    .max(0)
+            } else {
+                100
+            };
+
+            if opacity < 100 {
+                self.renderer
+                    .set_opacity(window, (opacity * 255 / 100) as u8)?;
+
+                if !dimmed.contains(&window.id) {
+                    dimmed.push(window.id);
+                }
+            } else if dimmed.contains(&window.id) {
+                self.renderer.set_opacity(window, 255)?;
+                dimmed.retain(|id| *id != window.id);
+            }
+        }
+
+        Ok(())
+    }
     pub fn modify_focused_window<TFunction>(self: &mut Self, f: TFunction) -> SystemResult
     where
         TFunction: FnMut(&mut NativeWindow) -> SystemResult + Copy,
@@ -455,6 +660,78 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
             }
         }
     }
+    /// Returns the id of the tile that contains the given window.
+    pub fn get_node_id(&self, window_id: WindowId) -> Option<usize> {
+        self.graph
+            .find(|node| node.is_tile() && node.get_window().id == window_id)
+    }
+    /// Swaps the windows of two arbitrary tiles, which don't have to be siblings. The focus follows
+    /// the focused window. No-op if one of the nodes isn't a tile.
+    pub fn swap_tiles(&mut self, first: usize, second: usize) {
+        if first == second || !self.graph.node(first).is_tile() || !self.graph.node(second).is_tile()
+        {
+            return;
+        }
+
+        let first_window = self.graph.node(first).get_window().clone();
+        let second_window =
+            std::mem::replace(self.graph.node_mut(second).get_window_mut(), first_window);
+        *self.graph.node_mut(first).get_window_mut() = second_window;
+
+        if self.focused_id == Some(first) {
+            self.focused_id = Some(second);
+        } else if self.focused_id == Some(second) {
+            self.focused_id = Some(first);
+        }
+    }
+    /// Returns the node itself followed by all of its ancestors up to the root.
+    fn get_ancestors(&self, node_id: usize) -> Vec<usize> {
+        let mut ancestors = vec![node_id];
+
+        while let Some(parent_id) = self.graph.map_to_parent(ancestors.last().copied()) {
+            ancestors.push(parent_id);
+        }
+
+        ancestors
+    }
+    /// Finds the boundary between two tiles, where the first tile is left of/above the second one.
+    /// The boundary is represented by the closest common ancestor and its two adjacent children
+    /// that contain the tiles: (parent, first child, second child).
+    pub fn get_boundary(&self, first: usize, second: usize) -> Option<(usize, usize, usize)> {
+        let first_ancestors = self.get_ancestors(first);
+        let second_ancestors = self.get_ancestors(second);
+
+        let (first_idx, parent_id) = first_ancestors
+            .iter()
+            .enumerate()
+            .skip(1)
+            .find(|(_, id)| second_ancestors.contains(id))
+            .map(|(idx, id)| (idx, *id))?;
+        let second_idx = second_ancestors.iter().position(|id| *id == parent_id)?;
+
+        let first_child = first_ancestors[first_idx - 1];
+        let second_child = second_ancestors[second_idx.checked_sub(1)?];
+
+        if self.graph.node(first_child).get_order() + 1 == self.graph.node(second_child).get_order()
+        {
+            Some((parent_id, first_child, second_child))
+        } else {
+            None
+        }
+    }
+    /// Moves the boundary between two tiles (see get_boundary) by the given amount. A positive amount
+    /// moves the boundary to the right/down.
+    pub fn resize_at_boundary(&mut self, first: usize, second: usize, amount: i32) {
+        if let Some((parent_id, first_child, _)) = self.get_boundary(first, second) {
+            let direction = if self.graph.node(parent_id).is_column() {
+                Direction::Right
+            } else {
+                Direction::Down
+            };
+
+            self.trade_size_with_neighbor(Some(first_child), direction, amount);
+        }
+    }
     /// Trades order (the index/position in a column or row) between two nodes. This function assumes the given nodes are siblings.
     fn swap_order(&mut self, first: usize, second: usize) {
         let first_order = self.graph.node(first).get_order();
@@ -518,6 +795,72 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
 
         Ok(())
     }
+    /// Focuses the next or previous tile in depth-first order, wrapping around at either end. Unlike
+    /// `focus` this reaches every tile, no matter how deeply it is nested.
+    pub fn focus_relative(&mut self, forward: bool) -> SystemResult {
+        let tiles = self.get_tiles_in_order();
+        let pos = match self
+            .focused_id
+            .and_then(|id| tiles.iter().position(|tile| *tile == id))
+        {
+            Some(pos) => pos,
+            None => return Ok(()),
+        };
+        let len = tiles.len();
+        let target = if forward {
+            tiles[(pos + 1) % len]
+        } else {
+            tiles[(pos + len - 1) % len]
+        };
+
+        self.focus_node(target)
+    }
+    /// Focuses the first tile of the next sibling of the focused tile's container, wrapping around
+    /// at the end. No-op if the container is the root node.
+    pub fn cycle_container(&mut self) -> SystemResult {
+        let container_id = self.graph.map_to_parent(self.focused_id);
+
+        if let (Some(container_id), Some(parent_id)) =
+            (container_id, self.graph.map_to_parent(container_id))
+        {
+            let siblings = self.graph.get_sorted_children(parent_id);
+            let pos = siblings
+                .iter()
+                .position(|id| *id == container_id)
+                .unwrap_or(0);
+            let next = siblings[(pos + 1) % siblings.len()];
+
+            if let Some(tile_id) = self.graph.to_closest_tile(Some(next), None) {
+                return self.focus_node(tile_id);
+            }
+        }
+
+        Ok(())
+    }
+    /// Returns the tiles in depth-first order, visiting the children of a node in their order
+    fn get_tiles_in_order(&self) -> Vec<usize> {
+        let mut tiles = Vec::new();
+        let mut stack: Vec<usize> = self.graph.get_root().into_iter().collect();
+
+        while let Some(id) = stack.pop() {
+            if self.graph.node(id).is_tile() {
+                tiles.push(id);
+            } else {
+                stack.extend(self.graph.get_sorted_children(id).into_iter().rev());
+            }
+        }
+
+        tiles
+    }
+    fn focus_node(&mut self, node_id: usize) -> SystemResult {
+        self.focused_id = Some(node_id);
+
+        if self.is_fullscreened() {
+            self.fullscreen_id = self.focused_id;
+        }
+
+        self.graph.node(node_id).get_window().focus()
+    }
     /// Resets the order of all child nodes by sorting them and then "re-indexing" their order starting at 0
     fn reset_order(&mut self, parent_id: usize) {
         let nodes = self.graph.get_sorted_children(parent_id);
@@ -644,6 +987,56 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
 
         window
     }
+    /// Removes the tile of the window. If it was the focused tile, another tile gets focused
+    /// according to the policy. Returns the node that received the focus.
+    pub fn remove_window(&mut self, id: WindowId, policy: CloseFocusPolicy) -> Option<usize> {
+        let node_id = self.get_node_id(id)?;
+        let was_focused = self.focused_id == Some(node_id);
+        // the neighbor has to be found before the removal merges the containers around the tile
+        let neighbor = self.get_closest_neighbor(node_id);
+
+        self.focus_history.retain(|x| *x != id);
+        self.remove_by_window_id(id);
+
+        if !was_focused {
+            return None;
+        }
+
+        let target = match policy {
+            CloseFocusPolicy::None => None,
+            CloseFocusPolicy::Neighbor => neighbor,
+            CloseFocusPolicy::History => self
+                .focus_history
+                .iter()
+                .rev()
+                .find_map(|id| self.get_node_id(*id))
+                .or(neighbor),
+        };
+
+        if target.is_some() {
+            self.focused_id = target;
+            if self.is_fullscreened() {
+                self.fullscreen_id = target;
+            }
+        }
+
+        target
+    }
+    /// Returns the tile next to the node that is closest to it, preferring the sibling before it.
+    fn get_closest_neighbor(&self, node_id: usize) -> Option<usize> {
+        let parent_id = self.graph.map_to_parent(Some(node_id))?;
+        let (before, after) = match self.graph.node(parent_id) {
+            Node::Column(_) => (Direction::Left, Direction::Right),
+            _ => (Direction::Up, Direction::Down),
+        };
+        let (sibling_id, direction) = match self.graph.get_neighbor(node_id, before) {
+            Some(sibling_id) => (sibling_id, before),
+            None => (self.graph.get_neighbor(node_id, after)?, after),
+        };
+
+        self.graph
+            .to_closest_tile(Some(sibling_id), Some(direction))
+    }
     /// Returns whether a given window ID exists in the tile grid
     pub fn contains(&self, window_id: WindowId) -> bool {
         self.graph
@@ -677,6 +1070,11 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
         });
         if maybe_window_tile.is_some() {
             self.focused_id = maybe_window_tile;
+            self.focus_history.retain(|id| *id != window_id);
+            self.focus_history.push(window_id);
+            if self.focus_history.len() > FOCUS_HISTORY_SIZE {
+                self.focus_history.remove(0);
+            }
         }
     }
     /// Creates a node from the given window and adds it to the graph if the grid doesn't already contain the window.
@@ -686,6 +1084,23 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
     /// tile doesn't have a sibling then the function introduces a new parent node opposite of the current parent's type
     /// and nests the focused node and the new window node within. This is how pushing into a tile creates rows or columns.
     pub fn push(&mut self, window: NativeWindow) {
+        if let Some((target, direction)) = self.pending_split.take() {
+            if self.get_focused_window().map(|w| w.id) == Some(target) {
+                let (axis, next_direction) = (self.next_axis, self.next_direction);
+
+                self.next_axis = match direction {
+                    Direction::Left | Direction::Right => SplitDirection::Vertical,
+                    Direction::Up | Direction::Down => SplitDirection::Horizontal,
+                };
+                self.next_direction = direction;
+                self.push(window);
+
+                self.next_axis = axis;
+                self.next_direction = next_direction;
+                return;
+            }
+        }
+
         if self.graph.len() == 0 {
             let new_root_node = Node::Tile((
                 NodeInfo {
@@ -897,6 +1312,30 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
             }
         }
     }
+    /// Returns the size of every node in the grid keyed by its node id. Together with
+    /// restore_sizes this makes it possible to undo any resizing done in between.
+    pub fn get_sizes(&self) -> HashMap<usize, u32> {
+        self.graph
+            .nodes()
+            .map(|node_id| (node_id, self.graph.node(node_id).get_size()))
+            .collect()
+    }
+    /// Applies sizes previously returned by get_sizes. The sizes only get applied if the grid
+    /// still consists of exactly the same nodes, because otherwise the sizes of a row/column
+    /// wouldn't add up anymore. Returns whether the sizes got applied.
+    pub fn restore_sizes(&mut self, sizes: &HashMap<usize, u32>) -> bool {
+        let node_ids = self.graph.nodes().collect::<Vec<usize>>();
+
+        if node_ids.len() != sizes.len() || node_ids.iter().any(|id| !sizes.contains_key(id)) {
+            return false;
+        }
+
+        for node_id in node_ids {
+            self.graph.node_mut(node_id).set_size(sizes[&node_id]);
+        }
+
+        true
+    }
     /// Moves the focused tile out of a row/column in the given direction and automatically handles redistributing size/resetting the order
     /// of any previous siblings. The behavior of this movement is essentially moving the tile so that it is a sibling of its parent and introducing a
     /// new parent node that is the opposite type of the previous parent if necessary.
@@ -1154,6 +1593,65 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
             }
         }
     }
+    /// Rotates the container of the focused tile, including everything inside it, by 90 degrees.
+    /// A column becomes a row and vice versa, where the children that end up in reverse order get
+    /// reordered. No-op if the focused tile isn't inside a container.
+    pub fn rotate_focused_container(&mut self, clockwise: bool) {
+        if let Some(parent_id) = self.graph.map_to_parent(self.focused_id) {
+            self.rotate_subtree(parent_id, clockwise);
+        }
+    }
+    fn rotate_subtree(&mut self, node_id: usize, clockwise: bool) {
+        // clockwise the left child of a column ends up on top, whereas the top child of a row
+        // ends up on the right
+        let (node, reverse) = match self.graph.node(node_id) {
+            Node::Column(info) => (Node::row(info.order, info.size), !clockwise),
+            Node::Row(info) => (Node::column(info.order, info.size), clockwise),
+            Node::Tile(_) => return,
+        };
+
+        self.graph.swap_node(node_id, node);
+
+        if reverse {
+            self.reverse_order(node_id);
+        }
+
+        for child in self.graph.get_children(node_id) {
+            self.rotate_subtree(child, clockwise);
+        }
+    }
+    /// Mirrors the container of the focused tile, including everything inside it. Mirroring
+    /// horizontally swaps left and right, otherwise top and bottom get swapped. No-op if the
+    /// focused tile isn't inside a container.
+    pub fn mirror_focused_container(&mut self, horizontally: bool) {
+        if let Some(parent_id) = self.graph.map_to_parent(self.focused_id) {
+            self.mirror_subtree(parent_id, horizontally);
+        }
+    }
+    fn mirror_subtree(&mut self, node_id: usize, horizontally: bool) {
+        let reverse = match self.graph.node(node_id) {
+            Node::Column(_) => horizontally,
+            Node::Row(_) => !horizontally,
+            Node::Tile(_) => return,
+        };
+
+        if reverse {
+            self.reverse_order(node_id);
+        }
+
+        for child in self.graph.get_children(node_id) {
+            self.mirror_subtree(child, horizontally);
+        }
+    }
+    /// Reverses the order of the children of a node. The children keep their sizes.
+    fn reverse_order(&mut self, parent_id: usize) {
+        let children = self.graph.get_sorted_children(parent_id);
+        let last = children.len() as u32 - 1;
+
+        for (order, child) in children.into_iter().enumerate() {
+            self.graph.node_mut(child).set_order(last - order as u32);
+        }
+    }
     /// Iterates nodes in tile grid and removes any that are no longer valid windows
     pub fn remove_empty_tiles(&mut self) {
         for node_id in self.graph.nodes() {
@@ -1215,6 +1713,13 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
             self.remove_empty_tiles();
         }
     }
+    /// Like `from_string`, but keeps the tiles of windows that don't exist, which is what loading
+    /// the layout of another machine needs.
+    pub fn from_string_keep_tiles(&mut self, target: &str) {
+        if !target.is_empty() {
+            self.inner_from_string(target, None);
+        }
+    }
     fn inner_from_string(&mut self, target: &str, parent_id: Option<usize>) -> usize {
         // intended to get the matching brace when nested children occur [ [ [ ] ] ]
         //                                                               ^         ^
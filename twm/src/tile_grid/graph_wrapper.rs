@@ -9,12 +9,16 @@ static EDGE: u32 = 0;
 
 pub struct GraphWrapper {
     graph: StableGraph<Node, u32>,
+    /// The number of tiles, which gets kept up to date so that the bar doesn't have to count them
+    /// on every redraw
+    tile_count: usize,
 }
 
 impl Clone for GraphWrapper {
     fn clone(&self) -> Self {
         Self {
             graph: self.graph.clone(),
+            tile_count: self.tile_count,
         }
     }
 }
@@ -31,23 +35,38 @@ impl GraphWrapper {
     pub fn new() -> Self {
         Self {
             graph: StableGraph::<Node, u32>::new(),
+            tile_count: 0,
         }
     }
 
     pub fn add_node(&mut self, node: Node) -> usize {
+        if node.is_tile() {
+            self.tile_count += 1;
+        }
         self.graph.add_node(node).index()
     }
 
     pub fn remove_node(&mut self, node_id: usize) -> Option<Node> {
-        self.graph.remove_node(NodeIndex::new(node_id))
+        let node = self.graph.remove_node(NodeIndex::new(node_id));
+        if node.as_ref().map(|n| n.is_tile()).unwrap_or(false) {
+            self.tile_count -= 1;
+        }
+        node
     }
 
     pub fn clear(&mut self) {
         self.graph.clear();
+        self.tile_count = 0;
     }
 
     pub fn swap_node(&mut self, node_id: usize, mut node: Node) -> Node {
+        if node.is_tile() {
+            self.tile_count += 1;
+        }
         mem::swap(self.node_mut(node_id), &mut node);
+        if node.is_tile() {
+            self.tile_count -= 1;
+        }
         node
     }
 
@@ -57,8 +76,8 @@ impl GraphWrapper {
         child_id
     }
 
-    pub fn swap_and_nest(&mut self, node_id: usize, mut swap_item: Node) -> (usize, usize) {
-        mem::swap(self.node_mut(node_id), &mut swap_item);
+    pub fn swap_and_nest(&mut self, node_id: usize, swap_item: Node) -> (usize, usize) {
+        let swap_item = self.swap_node(node_id, swap_item);
         (node_id, self.add_child(node_id, swap_item))
     }
 
@@ -80,6 +99,11 @@ impl GraphWrapper {
         self.graph.node_count()
     }
 
+    /// Returns how many of the nodes are tiles without iterating over them
+    pub fn tile_count(&self) -> usize {
+        self.tile_count
+    }
+
     pub fn node(&self, id: usize) -> &Node {
         &self.graph[NodeIndex::new(id)]
     }
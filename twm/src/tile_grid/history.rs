@@ -0,0 +1,275 @@
+//! Keeps a changelog of the layout of every workspace, which helps with finding out why a layout
+//! ended up the way it did.
+//!
+//! A change gets recorded whenever the grid of a workspace gets refreshed with a layout that
+//! differs from the previous one. The layouts use the format of `TileGrid::to_string`.
+use crate::logging::RingBuffer;
+use chrono::Local;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use std::{collections::HashMap, fmt};
+
+/// How many changes are kept per workspace
+const HISTORY_SIZE: usize = 100;
+
+/// Where a window is placed in the layout
+#[derive(Debug, Clone, PartialEq)]
+pub struct TilePlacement {
+    pub window_id: i32,
+    /// The nodes from the root to the tile, e.g. `c0/r1/t0` is the first tile of the row that is
+    /// the second child of the root column.
+    pub path: String,
+    /// The size of the tile relative to its siblings
+    pub size: u32,
+}
+
+/// Splits the layout at the commas that aren't nested inside of brackets.
+fn split_nodes(layout: &str) -> Vec<&str> {
+    let mut nodes = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (i, c) in layout.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            ',' if depth == 0 => {
+                nodes.push(&layout[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    if start < layout.len() {
+        nodes.push(&layout[start..]);
+    }
+
+    nodes
+}
+
+fn parse_nodes(layout: &str, parent_path: &str, placements: &mut Vec<TilePlacement>) -> Option<()> {
+    for node in split_nodes(layout) {
+        let (header, children) = match (node.find('['), node.rfind(']')) {
+            (Some(start), Some(end)) => (&node[..start], Some(&node[start + 1..end])),
+            _ => (node, None),
+        };
+
+        let mut info = header.split('|');
+        let segment = info.next()?;
+        let size = info.next()?.parse().ok()?;
+        let path = if parent_path.is_empty() {
+            segment.to_string()
+        } else {
+            format!("{}/{}", parent_path, segment)
+        };
+
+        match children {
+            Some(children) => parse_nodes(children, &path, placements)?,
+            None => placements.push(TilePlacement {
+                window_id: info.next()?.parse().ok()?,
+                path,
+                size,
+            }),
+        }
+    }
+
+    Some(())
+}
+
+/// Returns where each window of the layout is placed. A malformed layout results in no placements.
+pub fn parse_layout(layout: &str) -> Vec<TilePlacement> {
+    let mut placements = Vec::new();
+
+    match parse_nodes(layout, "", &mut placements) {
+        Some(_) => placements,
+        None => Vec::new(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutChange {
+    pub time: String,
+    pub workspace_id: i32,
+    pub before: String,
+    pub after: String,
+}
+
+impl LayoutChange {
+    /// Describes what happened to each window, one line per window that got added, removed,
+    /// moved or resized.
+    pub fn diff(&self) -> Vec<String> {
+        let before = parse_layout(&self.before);
+        let after = parse_layout(&self.after);
+        let mut lines = Vec::new();
+
+        for old in &before {
+            match after.iter().find(|p| p.window_id == old.window_id) {
+                Some(new) if new.path != old.path && new.size != old.size => lines.push(format!(
+                    "~ window {} moved from {} to {} and resized from {} to {}",
+                    old.window_id, old.path, new.path, old.size, new.size
+                )),
+                Some(new) if new.path != old.path => lines.push(format!(
+                    "~ window {} moved from {} to {}",
+                    old.window_id, old.path, new.path
+                )),
+                Some(new) if new.size != old.size => lines.push(format!(
+                    "~ window {} at {} resized from {} to {}",
+                    old.window_id, old.path, old.size, new.size
+                )),
+                Some(_) => {}
+                None => lines.push(format!("- window {} at {}", old.window_id, old.path)),
+            }
+        }
+
+        for new in &after {
+            if !before.iter().any(|p| p.window_id == new.window_id) {
+                lines.push(format!(
+                    "+ window {} at {} with size {}",
+                    new.window_id, new.path, new.size
+                ));
+            }
+        }
+
+        lines
+    }
+}
+
+impl fmt::Display for LayoutChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] workspace {}", self.time, self.workspace_id)?;
+
+        for line in self.diff() {
+            write!(f, "\n{}", line)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct History {
+    layouts: HashMap<i32, String>,
+    changes: HashMap<i32, RingBuffer<LayoutChange>>,
+}
+
+impl History {
+    fn renumber(&mut self, moves: &HashMap<i32, i32>) {
+        let new_id = |id: i32| moves.get(&id).copied().unwrap_or(id);
+
+        self.layouts = self
+            .layouts
+            .drain()
+            .map(|(id, layout)| (new_id(id), layout))
+            .collect();
+
+        self.changes = self
+            .changes
+            .drain()
+            .map(|(id, mut changes)| {
+                for change in changes.iter_mut() {
+                    change.workspace_id = new_id(change.workspace_id);
+                }
+
+                (new_id(id), changes)
+            })
+            .collect();
+    }
+}
+
+lazy_static! {
+    static ref HISTORY: Mutex<History> = Mutex::new(History::default());
+}
+
+/// Records the layout of the workspace if it differs from the previously recorded one.
+pub fn record(workspace_id: i32, layout: &str) {
+    let mut history = HISTORY.lock();
+
+    let before = history
+        .layouts
+        .insert(workspace_id, layout.to_string())
+        .unwrap_or_default();
+
+    if before == layout {
+        return;
+    }
+
+    history
+        .changes
+        .entry(workspace_id)
+        .or_insert_with(|| RingBuffer::new(HISTORY_SIZE))
+        .push(LayoutChange {
+            time: Local::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+            workspace_id,
+            before,
+            after: layout.to_string(),
+        });
+}
+
+/// Returns the last `count` changes of the workspace, the oldest first.
+pub fn tail(workspace_id: i32, count: usize) -> Vec<LayoutChange> {
+    HISTORY
+        .lock()
+        .changes
+        .get(&workspace_id)
+        .map(|changes| changes.tail(count))
+        .unwrap_or_default()
+}
+
+/// Moves the history of every workspace to its new id, e.g. after the workspaces got compacted.
+pub fn renumber(moves: &HashMap<i32, i32>) {
+    HISTORY.lock().renumber(moves);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_nested_layout() {
+        let placements = parse_layout("c0|120[t0|60|1,r1|60[t0|40|2,t1|80|3]]");
+
+        assert_eq!(
+            placements,
+            vec![
+                TilePlacement {
+                    window_id: 1,
+                    path: "c0/t0".into(),
+                    size: 60
+                },
+                TilePlacement {
+                    window_id: 2,
+                    path: "c0/r1/t0".into(),
+                    size: 40
+                },
+                TilePlacement {
+                    window_id: 3,
+                    path: "c0/r1/t1".into(),
+                    size: 80
+                },
+            ]
+        );
+        assert!(parse_layout("").is_empty());
+        assert!(parse_layout("c0|abc[t0|60|1]").is_empty());
+    }
+
+    #[test]
+    fn diff_layouts() {
+        let change = LayoutChange {
+            time: "".into(),
+            workspace_id: 1,
+            before: "c0|120[t0|60|1,t1|60|2,t2|60|3]".into(),
+            after: "c0|120[t0|90|1,r1|60[t0|60|3,t1|60|4]]".into(),
+        };
+
+        assert_eq!(
+            change.diff(),
+            vec![
+                "~ window 1 at c0/t0 resized from 60 to 90",
+                "- window 2 at c0/t1",
+                "~ window 3 moved from c0/t2 to c0/r1/t0",
+                "+ window 4 at c0/r1/t1 with size 60",
+            ]
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! Arbitrary metadata that scripts attach to a workspace, like the path of the project that is
+//! worked on in it or the current git branch.
+//!
+//! The metadata is stored next to the workspaces in `workspace_meta.json`, so that it survives a
+//! restart just like the layout of the workspaces.
+use lazy_static::lazy_static;
+use log::error;
+use parking_lot::Mutex;
+use serde_json::{Map, Value};
+use std::{collections::HashMap, fs, path::PathBuf};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WorkspaceMeta {
+    pub workspaces: HashMap<i32, HashMap<String, String>>,
+}
+
+impl WorkspaceMeta {
+    fn get_path() -> PathBuf {
+        #[allow(unused_mut)]
+        let mut path: PathBuf = ["./log"].iter().collect();
+        #[cfg(not(debug_assertions))]
+        {
+            path = dirs::config_dir().expect("Failed to get config directory");
+
+            path.push("nog");
+        }
+
+        path.push("workspace_meta.json");
+        path
+    }
+
+    pub fn get(&self, workspace_id: i32, key: &str) -> Option<&String> {
+        self.workspaces.get(&workspace_id)?.get(key)
+    }
+
+    /// Removes the entry if the value is `None`. Returns whether the metadata changed.
+    pub fn set(&mut self, workspace_id: i32, key: &str, value: Option<String>) -> bool {
+        let meta = self.workspaces.entry(workspace_id).or_default();
+
+        let changed = match value {
+            Some(value) => meta.insert(key.into(), value.clone()) != Some(value),
+            None => meta.remove(key).is_some(),
+        };
+
+        if meta.is_empty() {
+            self.workspaces.remove(&workspace_id);
+        }
+
+        changed
+    }
+
+    /// Moves the metadata of every workspace to its new id
+    pub fn renumber(&mut self, moves: &HashMap<i32, i32>) {
+        self.workspaces = self
+            .workspaces
+            .drain()
+            .map(|(id, meta)| (moves.get(&id).copied().unwrap_or(id), meta))
+            .collect();
+    }
+
+    pub fn to_json(&self) -> Value {
+        Value::Object(
+            self.workspaces
+                .iter()
+                .map(|(id, meta)| {
+                    let meta = meta
+                        .iter()
+                        .map(|(key, value)| (key.clone(), Value::from(value.clone())))
+                        .collect::<Map<String, Value>>();
+
+                    (id.to_string(), Value::Object(meta))
+                })
+                .collect::<Map<String, Value>>(),
+        )
+    }
+
+    pub fn from_json(value: &Value) -> Self {
+        let workspaces = value
+            .as_object()
+            .map(|obj| {
+                obj.iter()
+                    .filter_map(|(id, meta)| {
+                        let meta = meta
+                            .as_object()?
+                            .iter()
+                            .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.into())))
+                            .collect();
+
+                        Some((id.parse::<i32>().ok()?, meta))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self { workspaces }
+    }
+
+    pub fn load() -> Self {
+        fs::read_to_string(WorkspaceMeta::get_path())
+            .ok()
+            .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+            .map(|value| WorkspaceMeta::from_json(&value))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = fs::write(WorkspaceMeta::get_path(), self.to_json().to_string()) {
+            error!("Error storing the workspace metadata {:?}", e);
+        }
+    }
+}
+
+lazy_static! {
+    static ref META: Mutex<WorkspaceMeta> = Mutex::new(WorkspaceMeta::load());
+}
+
+pub fn get(workspace_id: i32, key: &str) -> Option<String> {
+    META.lock().get(workspace_id, key).cloned()
+}
+
+/// Returns every entry of the workspace
+pub fn get_all(workspace_id: i32) -> HashMap<String, String> {
+    META.lock()
+        .workspaces
+        .get(&workspace_id)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Sets the entry of the workspace or removes it if the value is `None`.
+pub fn set(workspace_id: i32, key: &str, value: Option<String>) {
+    let mut meta = META.lock();
+
+    if meta.set(workspace_id, key, value) {
+        meta.save();
+    }
+}
+
+/// Moves the metadata of every workspace to its new id, e.g. after the workspaces got compacted.
+pub fn renumber(moves: &HashMap<i32, i32>) {
+    let mut meta = META.lock();
+
+    meta.renumber(moves);
+    meta.save();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_and_remove_entries() {
+        let mut meta = WorkspaceMeta::default();
+
+        assert!(meta.set(2, "project", Some(r"C:\dev\nog".into())));
+        assert!(!meta.set(2, "project", Some(r"C:\dev\nog".into())));
+        assert!(meta.set(2, "branch", Some("master".into())));
+        assert_eq!(meta.get(2, "project"), Some(&r"C:\dev\nog".to_string()));
+        assert_eq!(meta.get(3, "project"), None);
+
+        let restored = WorkspaceMeta::from_json(&meta.to_json());
+        assert_eq!(restored, meta);
+
+        assert!(meta.set(2, "project", None));
+        assert!(!meta.set(2, "project", None));
+        assert!(meta.set(2, "branch", None));
+        assert!(meta.workspaces.is_empty());
+    }
+}
@@ -0,0 +1,145 @@
+//! Remembers on which workspace the user last placed a window of each application, so that smart
+//! placement can open new windows of the application on the same workspace.
+//!
+//! The placements are stored next to the workspaces in `placements.json` and are keyed by the
+//! path of the executable.
+use lazy_static::lazy_static;
+use log::error;
+use parking_lot::Mutex;
+use serde_json::{Map, Value};
+use std::{collections::HashMap, fs, path::PathBuf};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Placements {
+    pub workspaces: HashMap<String, i32>,
+}
+
+impl Placements {
+    fn get_path() -> PathBuf {
+        #[allow(unused_mut)]
+        let mut path: PathBuf = ["./log"].iter().collect();
+        #[cfg(not(debug_assertions))]
+        {
+            path = dirs::config_dir().expect("Failed to get config directory");
+
+            path.push("nog");
+        }
+
+        path.push("placements.json");
+        path
+    }
+
+    /// Paths on Windows are case insensitive
+    fn key(exe_path: &str) -> String {
+        exe_path.to_lowercase()
+    }
+
+    pub fn get(&self, exe_path: &str) -> Option<i32> {
+        self.workspaces.get(&Placements::key(exe_path)).copied()
+    }
+
+    /// Returns whether the workspace of the application changed.
+    pub fn set(&mut self, exe_path: &str, workspace_id: i32) -> bool {
+        self.workspaces
+            .insert(Placements::key(exe_path), workspace_id)
+            != Some(workspace_id)
+    }
+
+    /// Moves the applications of every workspace to its new id
+    pub fn renumber(&mut self, moves: &HashMap<i32, i32>) {
+        for id in self.workspaces.values_mut() {
+            *id = moves.get(id).copied().unwrap_or(*id);
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        Value::Object(
+            self.workspaces
+                .iter()
+                .map(|(path, id)| (path.clone(), Value::from(*id)))
+                .collect::<Map<String, Value>>(),
+        )
+    }
+
+    pub fn from_json(value: &Value) -> Self {
+        let workspaces = value
+            .as_object()
+            .map(|obj| {
+                obj.iter()
+                    .filter_map(|(path, id)| Some((path.clone(), id.as_i64()? as i32)))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self { workspaces }
+    }
+
+    pub fn load() -> Self {
+        fs::read_to_string(Placements::get_path())
+            .ok()
+            .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+            .map(|value| Placements::from_json(&value))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = fs::write(Placements::get_path(), self.to_json().to_string()) {
+            error!("Error storing the workspace placements {:?}", e);
+        }
+    }
+}
+
+lazy_static! {
+    static ref PLACEMENTS: Mutex<Placements> = Mutex::new(Placements::load());
+}
+
+/// Returns the workspace on which a window of the application was placed last.
+pub fn lookup(exe_path: &str) -> Option<i32> {
+    PLACEMENTS.lock().get(exe_path)
+}
+
+pub fn remember(exe_path: &str, workspace_id: i32) {
+    if exe_path.is_empty() {
+        return;
+    }
+
+    let mut placements = PLACEMENTS.lock();
+
+    if placements.set(exe_path, workspace_id) {
+        placements.save();
+    }
+}
+
+/// Moves the applications of every workspace to its new id, e.g. after the workspaces got
+/// compacted.
+pub fn renumber(moves: &HashMap<i32, i32>) {
+    let mut placements = PLACEMENTS.lock();
+
+    placements.renumber(moves);
+    placements.save();
+}
+
+/// Forgets every placement
+pub fn clear() {
+    let mut placements = PLACEMENTS.lock();
+
+    placements.workspaces.clear();
+    placements.save();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placements_ignore_case() {
+        let mut placements = Placements::default();
+
+        assert!(placements.set(r"C:\Program Files\App\App.exe", 3));
+        assert!(!placements.set(r"c:\program files\app\app.exe", 3));
+        assert_eq!(placements.get(r"C:\PROGRAM FILES\APP\APP.EXE"), Some(3));
+
+        let restored = Placements::from_json(&placements.to_json());
+        assert_eq!(restored, placements);
+    }
+}
@@ -0,0 +1,147 @@
+//! Named snapshots of the layouts of every workspace, which can be restored when nog starts (see
+//! the `startup` setting).
+//!
+//! The snapshots are stored next to the workspaces in `snapshots.json`. The layouts use the format
+//! of `TileGrid::to_string`.
+use lazy_static::lazy_static;
+use log::error;
+use parking_lot::Mutex;
+use serde_json::{Map, Value};
+use std::{collections::HashMap, fs, path::PathBuf};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Snapshots {
+    /// The layouts of each snapshot keyed by the id of the workspace
+    pub snapshots: HashMap<String, HashMap<i32, String>>,
+}
+
+impl Snapshots {
+    fn get_path() -> PathBuf {
+        #[allow(unused_mut)]
+        let mut path: PathBuf = ["./log"].iter().collect();
+        #[cfg(not(debug_assertions))]
+        {
+            path = dirs::config_dir().expect("Failed to get config directory");
+
+            path.push("nog");
+        }
+
+        path.push("snapshots.json");
+        path
+    }
+
+    pub fn to_json(&self) -> Value {
+        Value::Object(
+            self.snapshots
+                .iter()
+                .map(|(name, layouts)| {
+                    let layouts = layouts
+                        .iter()
+                        .map(|(id, layout)| (id.to_string(), Value::from(layout.clone())))
+                        .collect::<Map<String, Value>>();
+
+                    (name.clone(), Value::Object(layouts))
+                })
+                .collect::<Map<String, Value>>(),
+        )
+    }
+
+    pub fn from_json(value: &Value) -> Self {
+        let snapshots = value
+            .as_object()
+            .map(|obj| {
+                obj.iter()
+                    .filter_map(|(name, layouts)| {
+                        let layouts = layouts
+                            .as_object()?
+                            .iter()
+                            .filter_map(|(id, layout)| {
+                                Some((id.parse::<i32>().ok()?, layout.as_str()?.into()))
+                            })
+                            .collect();
+
+                        Some((name.clone(), layouts))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self { snapshots }
+    }
+
+    pub fn load() -> Self {
+        fs::read_to_string(Snapshots::get_path())
+            .ok()
+            .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+            .map(|value| Snapshots::from_json(&value))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = fs::write(Snapshots::get_path(), self.to_json().to_string()) {
+            error!("Error storing the snapshots {:?}", e);
+        }
+    }
+}
+
+lazy_static! {
+    static ref SNAPSHOTS: Mutex<Snapshots> = Mutex::new(Snapshots::load());
+}
+
+/// Returns the layouts of the snapshot keyed by the id of the workspace.
+pub fn get(name: &str) -> Option<HashMap<i32, String>> {
+    SNAPSHOTS.lock().snapshots.get(name).cloned()
+}
+
+/// Returns the names of every snapshot in alphabetical order
+pub fn names() -> Vec<String> {
+    let mut names: Vec<String> = SNAPSHOTS.lock().snapshots.keys().cloned().collect();
+    names.sort();
+    names
+}
+
+/// Stores the layouts under the name, replacing the snapshot that had the same name. Empty
+/// layouts are left out.
+pub fn save(name: &str, layouts: HashMap<i32, String>) {
+    let mut snapshots = SNAPSHOTS.lock();
+
+    snapshots.snapshots.insert(
+        name.into(),
+        layouts
+            .into_iter()
+            .filter(|(_, layout)| !layout.is_empty())
+            .collect(),
+    );
+    snapshots.save();
+}
+
+/// Returns whether a snapshot with the name existed
+pub fn remove(name: &str) -> bool {
+    let mut snapshots = SNAPSHOTS.lock();
+    let removed = snapshots.snapshots.remove(name).is_some();
+
+    if removed {
+        snapshots.save();
+    }
+
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshots_roundtrip() {
+        let mut layouts = HashMap::new();
+        layouts.insert(1, "c0|120[t0|60|1,t1|60|2]".to_string());
+        layouts.insert(3, "t0|120|3".to_string());
+
+        let mut snapshots = Snapshots::default();
+        snapshots.snapshots.insert("coding".into(), layouts);
+
+        let restored = Snapshots::from_json(&snapshots.to_json());
+        assert_eq!(restored, snapshots);
+        assert_eq!(Snapshots::from_json(&Value::Null), Snapshots::default());
+    }
+}
@@ -4,7 +4,10 @@ use super::TileGrid;
 use crate::display::Display;
 use crate::window::Window;
 use crate::{
-    config::Config, renderer::Renderer, system::NativeWindow, system::SystemResult,
+    config::{close_focus_policy::CloseFocusPolicy, Config},
+    renderer::Renderer,
+    system::NativeWindow,
+    system::SystemResult,
     system::WindowId,
 };
 use crate::{direction::Direction, split_direction::SplitDirection};
@@ -25,6 +28,13 @@ fn get_window_id(tile_grid: &TileGrid<TestRenderer>, node_id: usize) -> i32 {
     }
 }
 
+fn find_tile(tile_grid: &TileGrid<TestRenderer>, window_id: i32) -> usize {
+    tile_grid
+        .graph
+        .find(|node| node.is_tile() && node.get_window().id == window_id)
+        .expect("Expected tile with window")
+}
+
 fn is_column(tile_grid: &TileGrid<TestRenderer>, node_id: usize) -> bool {
     match tile_grid.graph.node(node_id) {
         Node::Column(_) => true,
@@ -1569,6 +1579,328 @@ fn remove_merges_rows() {
     assert_eq!(3, node_3);
 }
 
+#[test]
+fn restore_sizes_reverts_resizing() {
+    let mut tile_grid = TileGrid::new(0, TestRenderer {});
+    perform_actions(&mut tile_grid, "p,p,p");
+    let sizes = tile_grid.get_sizes();
+
+    tile_grid.trade_size_with_neighbor(tile_grid.focused_id, Direction::Left, 10);
+    assert_eq!("c0|120[t0|40|1,t1|30|2,t2|50|3]", tile_grid.to_string());
+
+    assert!(tile_grid.restore_sizes(&sizes));
+    assert_eq!("c0|120[t0|40|1,t1|40|2,t2|40|3]", tile_grid.to_string());
+}
+
+#[test]
+fn restore_sizes_ignores_changed_layout() {
+    let mut tile_grid = TileGrid::new(0, TestRenderer {});
+    perform_actions(&mut tile_grid, "p,p,p");
+    let sizes = tile_grid.get_sizes();
+
+    perform_actions(&mut tile_grid, "o");
+    let layout = tile_grid.to_string();
+
+    assert!(!tile_grid.restore_sizes(&sizes));
+    assert_eq!(layout, tile_grid.to_string());
+}
+
+#[test]
+fn swap_tiles_across_parents() {
+    let mut tile_grid = TileGrid::new(0, TestRenderer {});
+    perform_actions(&mut tile_grid, "p,p,axh,p");
+    let first = find_tile(&tile_grid, 1);
+    let third = find_tile(&tile_grid, 3);
+    let layout = tile_grid.to_string();
+
+    tile_grid.swap_tiles(first, third);
+
+    assert_eq!(3, get_window_id(&tile_grid, first));
+    assert_eq!(1, get_window_id(&tile_grid, third));
+    assert_eq!(Some(first), tile_grid.focused_id);
+
+    tile_grid.swap_tiles(first, third);
+    assert_eq!(layout, tile_grid.to_string());
+}
+
+#[test]
+fn resize_at_boundary_of_adjacent_tiles() {
+    let mut tile_grid = TileGrid::new(0, TestRenderer {});
+    perform_actions(&mut tile_grid, "p,p,p");
+    let first = find_tile(&tile_grid, 1);
+    let second = find_tile(&tile_grid, 2);
+
+    tile_grid.resize_at_boundary(first, second, 10);
+    assert_eq!("c0|120[t0|50|1,t1|30|2,t2|40|3]", tile_grid.to_string());
+
+    tile_grid.resize_at_boundary(first, second, -20);
+    assert_eq!("c0|120[t0|30|1,t1|50|2,t2|40|3]", tile_grid.to_string());
+}
+
+#[test]
+fn resize_at_boundary_ignores_tiles_without_shared_boundary() {
+    let mut tile_grid = TileGrid::new(0, TestRenderer {});
+    perform_actions(&mut tile_grid, "p,p,p");
+    let first = find_tile(&tile_grid, 1);
+    let third = find_tile(&tile_grid, 3);
+
+    tile_grid.resize_at_boundary(first, third, 10);
+    assert_eq!("c0|120[t0|40|1,t1|40|2,t2|40|3]", tile_grid.to_string());
+}
+
+#[test]
+fn update_opacity() {
+    let mut tile_grid = TileGrid::new(0, TestRenderer {});
+    perform_actions(&mut tile_grid, "p,p,p");
+
+    let mut config = Config::default();
+    let mut dimmed = Vec::new();
+    let id = |id: i32| WindowId::from(id);
+
+    tile_grid
+        .update_opacity(&config, Some(id(1)), &mut dimmed)
+        .unwrap();
+    assert!(dimmed.is_empty());
+
+    config.dim_unfocused = true;
+    tile_grid
+        .update_opacity(&config, Some(id(1)), &mut dimmed)
+        .unwrap();
+    assert_eq!(dimmed.len(), 2);
+    assert!(!dimmed.contains(&id(1)));
+
+    tile_grid
+        .update_opacity(&config, Some(id(2)), &mut dimmed)
+        .unwrap();
+    assert_eq!(dimmed.len(), 2);
+    assert!(!dimmed.contains(&id(2)));
+
+    config.dim_unfocused = false;
+    tile_grid
+        .update_opacity(&config, None, &mut dimmed)
+        .unwrap();
+    assert!(dimmed.is_empty());
+}
+
+#[test]
+fn equalize_and_balance() {
+    let layout = "c0|120[t0|90|1,r1|30[t0|20|2,t1|100|3]]";
+
+    let mut tile_grid = TileGrid::new(0, TestRenderer {});
+    tile_grid.from_string(&layout.into());
+    tile_grid.focused_id = Some(find_tile(&tile_grid, 2));
+    tile_grid.balance_focused_container();
+    assert_eq!(
+        "c0|120[t0|90|1,r1|30[t0|60|2,t1|60|3]]",
+        tile_grid.to_string()
+    );
+
+    let mut tile_grid = TileGrid::new(0, TestRenderer {});
+    tile_grid.from_string(&layout.into());
+    tile_grid.equalize();
+    assert_eq!(
+        "c0|120[t0|60|1,r1|60[t0|60|2,t1|60|3]]",
+        tile_grid.to_string()
+    );
+}
+
+#[test]
+fn rotate_focused_container() {
+    /*
+        testing [1][2]    [1][4][3][2]
+                [1][3]  >
+                [1][4]
+    */
+    let mut tile_grid = TileGrid::new(0, TestRenderer {});
+    perform_actions(&mut tile_grid, "p,p,axh,p,p");
+    tile_grid.rotate_focused_container(true);
+    assert_eq!(
+        "c0|120[t0|60|1,c1|60[t0|40|4,t1|40|3,t2|40|2]]",
+        tile_grid.to_string()
+    );
+
+    tile_grid.rotate_focused_container(false);
+    assert_eq!(
+        "c0|120[t0|60|1,r1|60[t0|40|2,t1|40|3,t2|40|4]]",
+        tile_grid.to_string()
+    );
+
+    // rotating the whole tree also rotates the nested containers
+    let mut tile_grid = TileGrid::new(0, TestRenderer {});
+    tile_grid.from_string(&"c0|120[t0|80|1,r1|40[t0|60|2,t1|60|3]]".into());
+    tile_grid.focused_id = Some(find_tile(&tile_grid, 1));
+    tile_grid.rotate_focused_container(true);
+    assert_eq!(
+        "r0|120[t0|80|1,c1|40[t0|60|3,t1|60|2]]",
+        tile_grid.to_string()
+    );
+
+    let rotated = tile_grid.to_string();
+    let mut tile_grid = TileGrid::new(0, TestRenderer {});
+    tile_grid.from_string(&rotated);
+    assert_eq!(rotated, tile_grid.to_string());
+}
+
+#[test]
+fn mirror_focused_container() {
+    let mut tile_grid = TileGrid::new(0, TestRenderer {});
+    tile_grid.from_string(&"c0|120[t0|80|1,r1|40[t0|60|2,t1|60|3]]".into());
+    tile_grid.focused_id = Some(find_tile(&tile_grid, 1));
+
+    tile_grid.mirror_focused_container(true);
+    assert_eq!(
+        "c0|120[r0|40[t0|60|2,t1|60|3],t1|80|1]",
+        tile_grid.to_string()
+    );
+
+    tile_grid.mirror_focused_container(false);
+    assert_eq!(
+        "c0|120[r0|40[t0|60|3,t1|60|2],t1|80|1]",
+        tile_grid.to_string()
+    );
+
+    let mirrored = tile_grid.to_string();
+    let mut tile_grid = TileGrid::new(0, TestRenderer {});
+    tile_grid.from_string(&mirrored);
+    assert_eq!(mirrored, tile_grid.to_string());
+}
+
+#[test]
+fn window_count() {
+    let mut tile_grid = TileGrid::new(0, TestRenderer {});
+    assert_eq!(tile_grid.window_count(), 0);
+
+    perform_actions(&mut tile_grid, "p,p,axh,p,p,r");
+    assert_eq!(tile_grid.window_count(), 4);
+
+    perform_actions(&mut tile_grid, "o");
+    tile_grid.remove_by_window_id(WindowId::from(1));
+    assert_eq!(tile_grid.window_count(), 2);
+
+    let mut tile_grid = TileGrid::new(0, TestRenderer {});
+    tile_grid.from_string(&"c0|120[t0|80|1,r1|40[t0|60|2,t1|60|3]]".into());
+    assert_eq!(tile_grid.window_count(), 3);
+}
+
+#[test]
+fn focus_relative_visits_every_tile() {
+    let mut tile_grid = TileGrid::new(0, TestRenderer {});
+    perform_actions(&mut tile_grid, LARGE_LAYOUT);
+
+    let order = tile_grid
+        .get_tiles_in_order()
+        .iter()
+        .map(|id| get_window_id(&tile_grid, *id))
+        .collect::<Vec<_>>();
+    assert_eq!(order, vec![1, 2, 3, 6, 7, 10, 12, 11, 9, 8, 5, 4]);
+
+    tile_grid.focused_id = Some(find_tile(&tile_grid, 12));
+    let _ = tile_grid.focus_relative(true);
+    assert_eq!(get_window_id(&tile_grid, tile_grid.focused_id.unwrap()), 11);
+
+    tile_grid.focused_id = Some(find_tile(&tile_grid, 1));
+    let _ = tile_grid.focus_relative(false);
+    assert_eq!(get_window_id(&tile_grid, tile_grid.focused_id.unwrap()), 4);
+}
+
+#[test]
+fn cycle_container_focuses_next_sibling() {
+    let mut tile_grid = TileGrid::new(0, TestRenderer {});
+    perform_actions(&mut tile_grid, LARGE_LAYOUT);
+
+    tile_grid.focused_id = Some(find_tile(&tile_grid, 10));
+    let _ = tile_grid.cycle_container();
+    assert_eq!(get_window_id(&tile_grid, tile_grid.focused_id.unwrap()), 9);
+
+    tile_grid.focused_id = Some(find_tile(&tile_grid, 4));
+    let _ = tile_grid.cycle_container();
+    assert_eq!(get_window_id(&tile_grid, tile_grid.focused_id.unwrap()), 1);
+
+    let _ = tile_grid.cycle_container();
+    assert_eq!(get_window_id(&tile_grid, tile_grid.focused_id.unwrap()), 1);
+}
+
+#[test]
+fn remove_window_focuses_neighbor() {
+    let mut tile_grid = TileGrid::new(0, TestRenderer {});
+    tile_grid.from_string(&"c0|120[t0|40|1,r1|40[t0|60|2,t1|60|3],t2|40|4]".into());
+    let focused_window = |tile_grid: &TileGrid<TestRenderer>, node_id: Option<usize>| {
+        node_id.map(|id| get_window_id(tile_grid, id))
+    };
+
+    tile_grid.focused_id = Some(find_tile(&tile_grid, 4));
+    let focused = tile_grid.remove_window(WindowId::from(4), CloseFocusPolicy::Neighbor);
+    assert_eq!(focused_window(&tile_grid, focused), Some(2));
+
+    let focused = tile_grid.remove_window(WindowId::from(2), CloseFocusPolicy::Neighbor);
+    assert_eq!(focused_window(&tile_grid, focused), Some(3));
+
+    // the focus only moves if the focused window got removed
+    let focused = tile_grid.remove_window(WindowId::from(1), CloseFocusPolicy::Neighbor);
+    assert_eq!(focused, None);
+    assert_eq!(focused_window(&tile_grid, tile_grid.focused_id), Some(3));
+}
+
+#[test]
+fn remove_window_focuses_previous_window() {
+    let mut tile_grid = TileGrid::new(0, TestRenderer {});
+    tile_grid.from_string(&"c0|120[t0|30|1,t1|30|2,t2|30|3,t3|30|4]".into());
+    let focused_window = |tile_grid: &TileGrid<TestRenderer>, node_id: Option<usize>| {
+        node_id.map(|id| get_window_id(tile_grid, id))
+    };
+
+    for id in &[1, 3, 2] {
+        tile_grid.focus_tile_by_window_id(WindowId::from(*id));
+    }
+
+    let focused = tile_grid.remove_window(WindowId::from(2), CloseFocusPolicy::History);
+    assert_eq!(focused_window(&tile_grid, focused), Some(3));
+
+    let focused = tile_grid.remove_window(WindowId::from(3), CloseFocusPolicy::History);
+    assert_eq!(focused_window(&tile_grid, focused), Some(1));
+
+    let focused = tile_grid.remove_window(WindowId::from(1), CloseFocusPolicy::None);
+    assert_eq!(focused, None);
+    assert_eq!(tile_grid.focused_id, None);
+}
+
+#[test]
+fn push_node_into_pending_split() {
+    let mut tile_grid = TileGrid::new(0, TestRenderer {});
+    perform_actions(&mut tile_grid, "p,p");
+
+    // [1][2] -> [1][2]
+    //           [3][2]
+    tile_grid.focus_tile_by_window_id(WindowId::from(1));
+    tile_grid.pending_split = Some((WindowId::from(1), Direction::Down));
+    tile_grid.push(create_window(3));
+
+    let row_id = tile_grid.graph.get_sorted_children(0)[0];
+    assert!(is_row(&tile_grid, row_id));
+    assert_eq!(
+        vec![1, 3],
+        tile_grid
+            .graph
+            .get_sorted_children(row_id)
+            .iter()
+            .map(|id| get_window_id(&tile_grid, *id))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(tile_grid.pending_split, None);
+    assert_eq!(tile_grid.next_axis, SplitDirection::Vertical);
+    assert_eq!(tile_grid.next_direction, Direction::Right);
+
+    // the split gets dropped once its window isn't focused anymore
+    tile_grid.pending_split = Some((WindowId::from(2), Direction::Left));
+    tile_grid.push(create_window(4));
+
+    assert_eq!(tile_grid.pending_split, None);
+    assert_eq!(
+        2,
+        get_window_id(&tile_grid, tile_grid.graph.get_sorted_children(0)[1])
+    );
+}
+
 fn print(tile_grid: &TileGrid) {
     let render_infos = tile_grid.get_render_info(127, 90);
     println!("{}", TextRenderer::render(127, 90, render_infos));
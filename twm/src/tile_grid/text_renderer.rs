@@ -4,26 +4,61 @@ pub struct TextRenderer {}
 
 impl TextRenderer {
     pub fn render(width: u32, height: u32, render_infos: Vec<TileRenderInfo>) -> String {
+        TextRenderer::render_with_legend(width, height, render_infos, |letter, render_info| {
+            format!(
+                "{} ~ NodeID:{} WinID:{} - {} Size: {} Order: {}\n",
+                letter,
+                render_info.debug_id,
+                render_info.window.id,
+                render_info
+                    .window
+                    .get_title()
+                    .unwrap_or("".to_string())
+                    .clone(),
+                render_info.debug_size,
+                render_info.debug_order
+            )
+        })
+    }
+
+    /// Renders a compact version of the layout that only lists the title of each window in the
+    /// legend.
+    pub fn render_preview(width: u32, height: u32, render_infos: Vec<TileRenderInfo>) -> String {
+        TextRenderer::render_with_legend(width, height, render_infos, |letter, render_info| {
+            format!(
+                "{}: {}\n",
+                letter,
+                render_info.window.get_title().unwrap_or_default()
+            )
+        })
+    }
+
+    /// Renders only the layout, one string per row.
+    pub fn render_miniature(
+        width: u32,
+        height: u32,
+        render_infos: Vec<TileRenderInfo>,
+    ) -> Vec<String> {
+        TextRenderer::render_with_legend(width, height, render_infos, |_, _| String::new())
+            .lines()
+            .skip(1)
+            .map(String::from)
+            .collect()
+    }
+
+    fn render_with_legend(
+        width: u32,
+        height: u32,
+        render_infos: Vec<TileRenderInfo>,
+        legend_line: impl Fn(&str, &TileRenderInfo) -> String,
+    ) -> String {
         let mut buffer = vec![vec![" ".to_string(); height as usize]; width as usize];
 
         let mut letter_counter = 65;
         let mut tile_legend = String::new();
         for render_info in render_infos {
             let letter = std::char::from_u32(letter_counter).unwrap().to_string();
-            tile_legend = tile_legend
-                + &format!(
-                    "{} ~ NodeID:{} WinID:{} - {} Size: {} Order: {}\n",
-                    letter,
-                    render_info.debug_id,
-                    render_info.window.id,
-                    render_info
-                        .window
-                        .get_title()
-                        .unwrap_or("".to_string())
-                        .clone(),
-                    render_info.debug_size,
-                    render_info.debug_order
-                );
+            tile_legend = tile_legend + &legend_line(&letter, &render_info);
             buffer = TextRenderer::add_to_buffer(buffer, render_info, letter);
             letter_counter += 1;
             if letter_counter > 90 {
@@ -1,4 +1,5 @@
-use crate::{event::Event, util, window::Window, window::WindowEvent, AppState};
+use crate::{event::Event, system, util, window::Window, window::WindowEvent, AppState};
+use log::error;
 use num_traits::FromPrimitive;
 use parking_lot::Mutex;
 use std::sync::Arc;
@@ -41,6 +42,8 @@ pub static WINDOW: Mutex<Option<Window>> = Mutex::new(None);
 enum PopupId {
     Exit = 1000,
     Reload = 1001,
+    ToggleWorkMode = 1002,
+    Settings = 1003,
 }
 
 pub fn create(state: Arc<Mutex<AppState>>) {
@@ -55,6 +58,8 @@ pub fn create(state: Arc<Mutex<AppState>>) {
 
     drop(state);
 
+    let state = state_arc.clone();
+
     window.create(state_arc, false, move |event| {
         match event {
             WindowEvent::Create { window_id, .. } => {
@@ -76,12 +81,27 @@ pub fn create(state: Arc<Mutex<AppState>>) {
                                     .send(Event::ReloadConfig)
                                     .expect("Failed to send event");
                             }
+                            PopupId::ToggleWorkMode => {
+                                sender
+                                    .send(Event::ToggleWorkMode)
+                                    .expect("Failed to send event");
+                            }
+                            PopupId::Settings => {
+                                let path = state.lock().config.path.clone();
+                                let cmd = format!("explorer.exe \"{}\"", path.display());
+
+                                if let Err(e) = system::api::launch_program(cmd) {
+                                    error!("Failed to open the config folder {:?}", e);
+                                }
+                            }
                         }
                     }
                 } else if msg.code == WM_APP && msg.params.1 as u32 == WM_RBUTTONUP {
+                    let work_mode = state.lock().work_mode;
+
                     unsafe {
                         SetForegroundWindow(msg.hwnd);
-                        show_popup_menu(msg.hwnd);
+                        show_popup_menu(msg.hwnd, work_mode);
                         PostMessageW(msg.hwnd, WM_APP + 1, 0, 0);
                     }
                 }
@@ -140,11 +160,18 @@ pub fn remove_icon(hwnd: HWND) {
     }
 }
 
-unsafe fn show_popup_menu(hwnd: HWND) {
+/// Every item gets inserted at the top, so the items appear in the reversed order.
+unsafe fn show_popup_menu(hwnd: HWND, work_mode: bool) {
     let menu = CreatePopupMenu();
 
     let mut exit = util::to_widestring("Exit");
+    let mut settings = util::to_widestring("Settings");
     let mut reload = util::to_widestring("Reload");
+    let mut toggle_work_mode = util::to_widestring(if work_mode {
+        "Pause tiling"
+    } else {
+        "Resume tiling"
+    });
 
     InsertMenuW(
         menu,
@@ -154,6 +181,14 @@ unsafe fn show_popup_menu(hwnd: HWND) {
         exit.as_mut_ptr(),
     );
 
+    InsertMenuW(
+        menu,
+        0,
+        MF_BYPOSITION | MF_STRING,
+        PopupId::Settings as usize,
+        settings.as_mut_ptr(),
+    );
+
     InsertMenuW(
         menu,
         0,
@@ -162,6 +197,14 @@ unsafe fn show_popup_menu(hwnd: HWND) {
         reload.as_mut_ptr(),
     );
 
+    InsertMenuW(
+        menu,
+        0,
+        MF_BYPOSITION | MF_STRING,
+        PopupId::ToggleWorkMode as usize,
+        toggle_work_mode.as_mut_ptr(),
+    );
+
     SetMenuItemBitmaps(
         menu,
         1,
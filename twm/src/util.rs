@@ -10,6 +10,12 @@ pub fn to_widestring(string: &str) -> Vec<u16> {
     string.encode_utf16().chain(std::iter::once(0)).collect()
 }
 
+/// Converts a null-terminated utf16 buffer into a string
+pub fn from_widestring(buffer: &[u16]) -> String {
+    let len = buffer.iter().position(|c| *c == 0).unwrap_or(buffer.len());
+    String::from_utf16_lossy(&buffer[..len])
+}
+
 pub type RGB = (i32, i32, i32);
 
 pub fn rgb_to_hex(rgb: RGB) -> i32 {
@@ -20,6 +26,13 @@ pub fn hex_to_rgb(hex: i32) -> RGB {
     ((hex >> 16) & 0xFF, (hex >> 8) & 0xFF, hex & 0xFF)
 }
 
+/// Converts a color between 0xRRGGBB and the 0xBBGGRR order that winapi uses
+pub fn swap_red_blue(color: i32) -> i32 {
+    let (red, green, blue) = hex_to_rgb(color);
+
+    rgb_to_hex((blue, green, red))
+}
+
 pub fn scale_color(color: i32, factor: f64) -> i32 {
     let (mut red, mut green, mut blue) = hex_to_rgb(color);
 
@@ -8,10 +8,15 @@ use std::{
 };
 use thread::JoinHandle;
 use winapi::um::wingdi::LOGFONTA;
+use winapi::um::wingdi::{Ellipse, GetStockObject, Pie, NULL_PEN};
 use winapi::um::wingdi::{GetBValue, GetGValue, GetRValue, RGB};
+use winapi::um::winuser::WM_MOUSELEAVE;
+use winapi::um::winuser::{PBT_POWERSETTINGCHANGE, POWERBROADCAST_SETTING, WM_POWERBROADCAST};
+use winapi::um::{winuser::ReleaseCapture, winuser::SetCapture, winuser::WM_LBUTTONUP};
 use winapi::um::{wingdi::CreateFontIndirectA, winuser::IDC_HAND, winuser::WM_MOUSEMOVE};
 use winapi::um::{wingdi::DeleteObject, winuser::DT_SINGLELINE, winuser::DT_VCENTER};
 use winapi::um::{wingdi::SelectObject, winuser::SW_HIDE, winuser::SW_SHOW};
+use winapi::um::{winuser::TrackMouseEvent, winuser::TME_LEAVE, winuser::TRACKMOUSEEVENT};
 use winapi::{
     shared::minwindef::LPARAM, shared::minwindef::LRESULT, shared::minwindef::UINT,
     shared::minwindef::WPARAM, shared::windef::HDC, shared::windef::HWND, shared::windef::POINT,
@@ -60,10 +65,18 @@ unsafe extern "system" fn window_cb(
     if msg == WM_SETCURSOR {
         return 1;
     } else if msg != WM_IDENT {
+        let mut params = (w_param, l_param);
+
+        // the setting only lives until this callback returns, so its value gets sent instead
+        if msg == WM_POWERBROADCAST && w_param == PBT_POWERSETTINGCHANGE as usize {
+            let setting = &*(l_param as *const POWERBROADCAST_SETTING);
+            params.1 = setting.Data[0] as LPARAM;
+        }
+
         let payload = WindowMsg {
             code: msg,
             hwnd,
-            params: (w_param, l_param),
+            params,
         };
 
         let ptr = Box::into_raw(Box::new(payload));
@@ -97,6 +110,17 @@ impl Api {
             SetCursor(LoadCursorA(std::ptr::null_mut(), IDC_ARROW as *const i8));
         }
     }
+    /// Sends every mouse event to this window, even if the cursor is outside of it.
+    pub fn capture_mouse(&self) {
+        unsafe {
+            SetCapture(self.window.id.into());
+        }
+    }
+    pub fn release_mouse(&self) {
+        unsafe {
+            ReleaseCapture();
+        }
+    }
     pub fn set_text_color(&self, color: i32) {
         unsafe {
             SetTextColor(self.hdc as HDC, convert_color_to_winapi(color as u32));
@@ -125,6 +149,47 @@ impl Api {
             DeleteObject(brush as *mut c_void);
         }
     }
+    /// Fills the part of the circle inside of the given square that starts at the top and goes
+    /// clockwise for the given fraction of the circle.
+    pub fn fill_pie(&self, x: i32, y: i32, size: i32, fraction: f32, color: i32) {
+        if fraction <= 0.0 || size <= 0 {
+            return;
+        }
+
+        unsafe {
+            let brush = CreateSolidBrush(convert_color_to_winapi(color as u32));
+            let prev_brush = SelectObject(self.hdc as HDC, brush as *mut c_void);
+            let prev_pen = SelectObject(self.hdc as HDC, GetStockObject(NULL_PEN as i32));
+            // the null pen doesn't draw the outline, which makes the shape one pixel smaller
+            let (right, bottom) = (x + size + 1, y + size + 1);
+
+            if fraction >= 1.0 {
+                Ellipse(self.hdc as HDC, x, y, right, bottom);
+            } else {
+                let radius = size as f32 / 2.0;
+                let (cx, cy) = (x as f32 + radius, y as f32 + radius);
+                let end = std::f32::consts::FRAC_PI_2;
+                let start = end - fraction * 2.0 * std::f32::consts::PI;
+
+                // the pie goes counterclockwise from the start to the end
+                Pie(
+                    self.hdc as HDC,
+                    x,
+                    y,
+                    right,
+                    bottom,
+                    (cx + radius * start.cos()).round() as i32,
+                    (cy - radius * start.sin()).round() as i32,
+                    (cx + radius * end.cos()).round() as i32,
+                    (cy - radius * end.sin()).round() as i32,
+                );
+            }
+
+            SelectObject(self.hdc as HDC, prev_pen);
+            SelectObject(self.hdc as HDC, prev_brush);
+            DeleteObject(brush as *mut c_void);
+        }
+    }
     pub fn calculate_text_rect(&self, text: &str) -> Rectangle {
         let c_text = util::to_widestring(&text);
         let mut rect = RECT::default();
@@ -163,6 +228,15 @@ pub enum WindowEvent {
         x: i32,
         y: i32,
         state_arc: Arc<Mutex<AppState>>,
+        api: Api,
+    },
+    MouseUp {
+        display_id: DisplayId,
+        window_id: WindowId,
+        x: i32,
+        y: i32,
+        state_arc: Arc<Mutex<AppState>>,
+        api: Api,
     },
     Create {
         display_id: DisplayId,
@@ -192,6 +266,11 @@ pub enum WindowEvent {
         x: i32,
         y: i32,
     },
+    MouseLeave {
+        display_id: DisplayId,
+        window_id: WindowId,
+        state_arc: Arc<Mutex<AppState>>,
+    },
     Native {
         display_id: DisplayId,
         window_id: WindowId,
@@ -460,10 +539,36 @@ impl Window {
                             GetCursorPos(&mut point);
                             let win_rect = window.get_rect().unwrap();
 
+                            let api = Api {
+                                hdc: hdc as i32,
+                                window: window.clone(),
+                                background_color,
+                            };
+
                             call_handler(&WindowEvent::Click {
                                 display_id,
                                 window_id: window.id,
                                 state_arc: state_arc.clone(),
+                                api,
+                                x: point.x - win_rect.left,
+                                y: point.y - win_rect.top,
+                            });
+                        } else if msg.code == WM_LBUTTONUP {
+                            let mut point = POINT::default();
+                            GetCursorPos(&mut point);
+                            let win_rect = window.get_rect().unwrap();
+
+                            let api = Api {
+                                hdc: hdc as i32,
+                                window: window.clone(),
+                                background_color,
+                            };
+
+                            call_handler(&WindowEvent::MouseUp {
+                                display_id,
+                                window_id: window.id,
+                                state_arc: state_arc.clone(),
+                                api,
                                 x: point.x - win_rect.left,
                                 y: point.y - win_rect.top,
                             });
@@ -488,6 +593,16 @@ impl Window {
                             GetCursorPos(&mut point);
                             let win_rect = window.get_rect().unwrap();
 
+                            // windows only sends WM_MOUSELEAVE if it got requested since the last
+                            // time the mouse entered the window
+                            let mut track_event = TRACKMOUSEEVENT {
+                                cbSize: std::mem::size_of::<TRACKMOUSEEVENT>() as u32,
+                                dwFlags: TME_LEAVE,
+                                hwndTrack: hwnd,
+                                dwHoverTime: 0,
+                            };
+                            TrackMouseEvent(&mut track_event);
+
                             let api = Api {
                                 hdc: hdc as i32,
                                 window: window.clone(),
@@ -502,6 +617,12 @@ impl Window {
                                 x: point.x - win_rect.left,
                                 y: point.y - win_rect.top,
                             });
+                        } else if msg.code == WM_MOUSELEAVE {
+                            call_handler(&WindowEvent::MouseLeave {
+                                display_id,
+                                window_id: window.id,
+                                state_arc: state_arc.clone(),
+                            });
                         } else {
                             call_handler(&WindowEvent::Native {
                                 display_id,
@@ -0,0 +1,186 @@
+//! The window search lists the managed windows of every workspace and focuses the one that gets
+//! picked, switching to its workspace and display if necessary.
+use crate::{command_palette::fuzzy_score, popup::Popup, system::WindowId};
+
+/// How many matching windows are visible at once
+const MAX_VISIBLE: usize = 10;
+/// How many characters of a title are visible
+const MAX_TITLE_LEN: usize = 50;
+
+#[derive(Debug, Clone)]
+pub struct WindowEntry {
+    pub id: WindowId,
+    pub title: String,
+    /// The file name of the executable
+    pub exe: String,
+    pub workspace_id: i32,
+    /// The number that is shown for the workspace
+    pub workspace_number: i32,
+    /// Whether the window got hidden, in which case picking it restores it
+    pub hidden: bool,
+}
+
+impl WindowEntry {
+    /// The text the query gets matched against
+    fn search_text(&self) -> String {
+        format!("{} {} {}", self.title, self.location(), self.exe)
+    }
+
+    /// Where the window is, which is either its workspace or hidden
+    fn location(&self) -> String {
+        if self.hidden {
+            "hidden".into()
+        } else {
+            self.workspace_number.to_string()
+        }
+    }
+
+    fn label(&self) -> String {
+        let title = if self.title.chars().count() > MAX_TITLE_LEN {
+            format!(
+                "{}...",
+                self.title.chars().take(MAX_TITLE_LEN).collect::<String>()
+            )
+        } else {
+            self.title.clone()
+        };
+
+        format!("{}  [{}] {}", title, self.location(), self.exe)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct WindowSearch {
+    pub windows: Vec<WindowEntry>,
+    pub query: String,
+    /// Index of the selected window in the list of matches
+    pub selected: usize,
+}
+
+impl WindowSearch {
+    pub fn new(windows: Vec<WindowEntry>) -> Self {
+        Self {
+            windows,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the windows that match the query, the best match first.
+    pub fn matches(&self) -> Vec<&WindowEntry> {
+        let mut matches = self
+            .windows
+            .iter()
+            .filter_map(|w| fuzzy_score(&self.query, &w.search_text()).map(|score| (score, w)))
+            .collect::<Vec<_>>();
+
+        // the sort is stable, so windows with the same score keep the order of their workspaces
+        matches.sort_by_key(|(score, _)| -score);
+
+        matches
+            .into_iter()
+            .take(MAX_VISIBLE)
+            .map(|(_, w)| w)
+            .collect()
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.query.push(c);
+        self.selected = 0;
+    }
+
+    pub fn pop(&mut self) {
+        self.query.pop();
+        self.selected = 0;
+    }
+
+    pub fn select_next(&mut self) {
+        let count = self.matches().len();
+
+        if count > 0 {
+            self.selected = (self.selected + 1) % count;
+        }
+    }
+
+    pub fn select_prev(&mut self) {
+        let count = self.matches().len();
+
+        if count > 0 {
+            self.selected = (self.selected + count - 1) % count;
+        }
+    }
+
+    pub fn selection(&self) -> Option<&WindowEntry> {
+        self.matches().into_iter().nth(self.selected)
+    }
+
+    pub fn to_popup(&self) -> Popup {
+        let matches = self.matches();
+        let mut lines = vec![format!("> {}", self.query), "".into()];
+
+        if matches.is_empty() {
+            lines.push("No matching windows".into());
+        }
+
+        for (i, window) in matches.iter().enumerate() {
+            let marker = if i == self.selected { ">" } else { " " };
+            lines.push(format!("{} {}", marker, window.label()));
+        }
+
+        Popup::new().with_padding(5).with_text(lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: i32, title: &str, exe: &str, workspace_id: i32) -> WindowEntry {
+        WindowEntry {
+            id: WindowId::from(id),
+            title: title.into(),
+            exe: exe.into(),
+            workspace_id,
+            workspace_number: workspace_id,
+            hidden: false,
+        }
+    }
+
+    #[test]
+    fn matches_title_workspace_and_exe() {
+        let mut search = WindowSearch::new(vec![
+            entry(1, "Inbox - Outlook", "OUTLOOK.EXE", 1),
+            entry(2, "main.rs - nog - Visual Studio Code", "Code.exe", 2),
+            entry(3, "Windows Terminal", "WindowsTerminal.exe", 2),
+        ]);
+
+        search.query = "code".into();
+
+        assert_eq!(search.selection().unwrap().id, WindowId::from(2));
+
+        search.query = "wt".into();
+
+        assert_eq!(search.selection().unwrap().id, WindowId::from(3));
+
+        search.select_next();
+
+        assert_eq!(search.matches().len(), 1);
+        assert_eq!(search.selection().unwrap().id, WindowId::from(3));
+    }
+
+    #[test]
+    fn hidden_windows_are_marked() {
+        let mut hidden = entry(4, "Spotify", "Spotify.exe", 1);
+        hidden.hidden = true;
+
+        let mut search =
+            WindowSearch::new(vec![entry(1, "Inbox - Outlook", "OUTLOOK.EXE", 1), hidden]);
+
+        search.query = "hidden".into();
+
+        assert_eq!(search.selection().unwrap().id, WindowId::from(4));
+        assert_eq!(
+            search.selection().unwrap().label(),
+            "Spotify  [hidden] Spotify.exe"
+        );
+    }
+}
@@ -0,0 +1,173 @@
+//! Applies the workspace policies of the displays (see `config::workspace_policy`) after every
+//! event, because there are a lot of ways for the last window of a workspace to leave.
+use crate::{
+    direction::Direction,
+    event_handler::mouse::Drag,
+    renderer::NativeRenderer,
+    split_direction::SplitDirection,
+    tile_grid::{history, meta, placements, store::Store, TileGrid},
+    AppState,
+};
+use log::debug;
+use std::collections::HashMap;
+
+/// Returns the new id of every workspace that has to move, so that the occupied ones come first.
+/// The workspaces are given as their id and whether they are occupied, sorted by id.
+pub fn compact(workspaces: &[(i32, bool)]) -> Vec<(i32, i32)> {
+    let occupied = workspaces.iter().filter(|(_, occupied)| *occupied);
+    let empty = workspaces.iter().filter(|(_, occupied)| !*occupied);
+
+    occupied
+        .chain(empty)
+        .zip(workspaces.iter())
+        .map(|((old, _), (new, _))| (*old, *new))
+        .filter(|(old, new)| old != new)
+        .collect()
+}
+
+fn is_named(state: &AppState, id: i32) -> bool {
+    let number = state.workspace_number(id);
+
+    state
+        .config
+        .workspace_settings
+        .iter()
+        .any(|s| s.id == number && !s.text.is_empty())
+}
+
+/// Whether the workspace looks like it just got created (see `TileGrid::new`)
+fn is_pristine(grid: &TileGrid) -> bool {
+    grid.is_empty()
+        && grid.fullscreen_id.is_none()
+        && grid.focus_history.is_empty()
+        && grid.next_axis == SplitDirection::Vertical
+        && grid.next_direction == Direction::Right
+}
+
+/// Moves everything that belongs to a workspace to its new id. The caller holds the lock of the
+/// app state, so nothing can observe a workspace that only moved halfway.
+fn migrate(state: &mut AppState, moves: &HashMap<i32, i32>) {
+    let new_id = |id: i32| moves.get(&id).copied().unwrap_or(id);
+
+    for display in state.displays.iter_mut() {
+        for grid in display.grids.iter_mut() {
+            if moves.contains_key(&grid.id) {
+                grid.id = new_id(grid.id);
+                Store::save(grid.id, grid.to_string());
+            }
+        }
+
+        display.focused_grid_id = display.focused_grid_id.map(new_id);
+        display.previous_grid_id = display.previous_grid_id.map(new_id);
+    }
+
+    for pending in state.pending_templates.iter_mut() {
+        pending.workspace_id = new_id(pending.workspace_id);
+    }
+
+    match &mut state.drag {
+        Some(Drag::Tile { grid_id, .. }) | Some(Drag::Boundary { grid_id, .. }) => {
+            *grid_id = new_id(*grid_id)
+        }
+        None => {}
+    }
+
+    meta::renumber(moves);
+    history::renumber(moves);
+    placements::renumber(moves);
+
+    state.workspace_id = new_id(state.workspace_id);
+    state.keybindings_manager.set_workspace(state.workspace_id);
+}
+
+/// Returns whether a workspace changed, in which case the bars have to be redrawn.
+pub fn apply(state: &mut AppState) -> bool {
+    let count = state.displays.len();
+    let mut changed = false;
+
+    // the index of a display counts from the left, but the displays are sorted from right to left
+    for pos in 0..count {
+        let idx = (count - pos) as i32;
+        let policy = match state.config.workspace_policies.get(&idx) {
+            Some(policy) => *policy,
+            None => continue,
+        };
+
+        let display = &state.displays[pos];
+        let mut workspaces = display
+            .grids
+            .iter()
+            .filter(|g| !is_named(state, g.id))
+            .map(|g| (g.id, display.focused_grid_id == Some(g.id) || !g.is_empty()))
+            .collect::<Vec<_>>();
+
+        workspaces.sort();
+
+        let display = &mut state.displays[pos];
+
+        if policy.remove_empty {
+            for (id, _) in workspaces.iter().filter(|(_, occupied)| !occupied) {
+                if display.previous_grid_id == Some(*id) {
+                    display.previous_grid_id = None;
+                }
+
+                if let Some(grid) = display.get_grid_by_id_mut(*id) {
+                    if !is_pristine(grid) {
+                        debug!("Removing the empty workspace {}", id);
+                        *grid = TileGrid::new(*id, NativeRenderer);
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        if policy.compact {
+            let moves = compact(&workspaces).into_iter().collect::<HashMap<_, _>>();
+
+            if moves.is_empty() {
+                continue;
+            }
+
+            debug!("Compacting the workspaces {:?}", moves);
+
+            migrate(state, &moves);
+
+            changed = true;
+        }
+    }
+
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tile_grid::meta::WorkspaceMeta;
+
+    #[test]
+    fn compact_moves_occupied_workspaces_down() {
+        assert_eq!(
+            compact(&[(1, true), (2, false), (3, true), (5, true)]),
+            vec![(3, 2), (5, 3), (2, 5)]
+        );
+        assert_eq!(compact(&[(1, true), (2, true), (3, false)]), vec![]);
+    }
+
+    #[test]
+    fn compact_moves_the_metadata_along() {
+        let mut meta = WorkspaceMeta::default();
+        meta.set(3, "project", Some("nog".into()));
+        meta.set(5, "project", Some("komorebi".into()));
+        meta.set(7, "project", Some("named".into()));
+
+        let moves = compact(&[(1, true), (2, false), (3, true), (5, true)])
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+        meta.renumber(&moves);
+
+        assert_eq!(meta.get(2, "project"), Some(&"nog".to_string()));
+        assert_eq!(meta.get(3, "project"), Some(&"komorebi".to_string()));
+        assert_eq!(meta.get(5, "project"), None);
+        assert_eq!(meta.get(7, "project"), Some(&"named".to_string()));
+    }
+}